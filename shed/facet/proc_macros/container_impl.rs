@@ -7,13 +7,20 @@
  * of this source tree.
  */
 
-use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
+use std::collections::BTreeMap;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use syn::parse::{Parse, ParseStream, Parser};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::{parse_macro_input, Attribute, Error, Expr, Fields, Ident, ItemStruct, Token, Type};
+use syn::{
+    parse_macro_input, Attribute, Error, Expr, Field, Fields, GenericParam, Generics, Ident,
+    ItemStruct, Meta, NestedMeta, Path, Token, Type, TypeTraitObject, WherePredicate,
+};
 
 use crate::facet_crate_name;
+use crate::util::{access_attr, parse_facet_key, parse_facet_name, static_facet_key};
 
 #[derive(Debug)]
 struct ContainerMembers {
@@ -21,20 +28,93 @@ struct ContainerMembers {
     field_inits: Vec<Expr>,
     facet_idents: Vec<Ident>,
     facet_types: Vec<Type>,
+    facet_ref_types: Vec<Type>,
+    lazy_facet_idents: Vec<Ident>,
+    lazy_facet_types: Vec<Type>,
+    weak_facet_idents: Vec<Ident>,
+    weak_facet_types: Vec<Type>,
+    swappable_facet_idents: Vec<Ident>,
+    swappable_facet_types: Vec<Type>,
+    boxed_facet_idents: Vec<Ident>,
+    boxed_facet_types: Vec<Type>,
+    mutable_facet_idents: Vec<Ident>,
+    mutable_facet_types: Vec<Type>,
+    keyed_facet_idents: Vec<Ident>,
+    keyed_facet_types: Vec<Type>,
+    keyed_facet_ref_types: Vec<Type>,
+    keyed_facet_keys: Vec<Type>,
+    shutdown_facet_idents: Vec<Ident>,
+    health_facet_idents: Vec<Ident>,
+    downcast_facet_idents: Vec<Ident>,
+    supertrait_facet_idents: Vec<Ident>,
+    supertrait_types: Vec<Type>,
     delegate_idents: Vec<Ident>,
     delegate_types: Vec<Type>,
     delegate_facets: Vec<Vec<Type>>,
+    facet_names: BTreeMap<Ident, Ident>,
 }
 
 impl ContainerMembers {
-    fn extract(container: &mut ItemStruct) -> Result<Self, Error> {
+    /// Returns true if any of the fields have async initializers, in which
+    /// case the container can only be built by async factories.
+    fn has_async_inits(&self) -> bool {
+        self.field_inits
+            .iter()
+            .any(|init| matches!(init, Expr::Async(_)))
+    }
+
+    /// Returns true if the container owns any of its facets, which are built
+    /// separately for the container from boxed facets.
+    fn has_owned_facets(&self) -> bool {
+        !self.boxed_facet_idents.is_empty() || !self.mutable_facet_idents.is_empty()
+    }
+
+    /// The facet fields whose accesses are tracked by containers that track
+    /// usage: those that are accessed through the container's facet traits.
+    fn tracked_facet_idents(&self) -> Vec<&Ident> {
+        self.facet_idents
+            .iter()
+            .chain(&self.keyed_facet_idents)
+            .chain(&self.boxed_facet_idents)
+            .collect()
+    }
+
+    /// The name of the facet stored in a facet field, which is the name of
+    /// the field unless it has been renamed.
+    fn facet_name<'a>(&'a self, field_ident: &'a Ident) -> &'a Ident {
+        self.facet_names.get(field_ident).unwrap_or(field_ident)
+    }
+
+    fn extract(container: &mut ItemStruct, options: &ContainerOptions) -> Result<Self, Error> {
         let mut field_idents = Vec::new();
         let mut field_inits = Vec::new();
         let mut facet_idents = Vec::new();
         let mut facet_types = Vec::new();
+        let mut facet_ref_types = Vec::new();
+        let mut static_predicates: Vec<WherePredicate> = Vec::new();
+        let mut lazy_facet_idents = Vec::new();
+        let mut lazy_facet_types = Vec::new();
+        let mut weak_facet_idents = Vec::new();
+        let mut weak_facet_types = Vec::new();
+        let mut swappable_facet_idents = Vec::new();
+        let mut swappable_facet_types = Vec::new();
+        let mut boxed_facet_idents = Vec::new();
+        let mut boxed_facet_types = Vec::new();
+        let mut mutable_facet_idents = Vec::new();
+        let mut mutable_facet_types = Vec::new();
+        let mut keyed_facet_idents = Vec::new();
+        let mut keyed_facet_types = Vec::new();
+        let mut keyed_facet_ref_types = Vec::new();
+        let mut keyed_facet_keys = Vec::new();
+        let mut shutdown_facet_idents = Vec::new();
+        let mut health_facet_idents = Vec::new();
+        let mut downcast_facet_idents = Vec::new();
+        let mut supertrait_facet_idents = Vec::new();
+        let mut supertrait_types = Vec::new();
         let mut delegate_idents = Vec::new();
         let mut delegate_types = Vec::new();
         let mut delegate_facets = Vec::new();
+        let mut facet_names = BTreeMap::new();
         match &mut container.fields {
             Fields::Named(named_fields) => {
                 for field in named_fields.named.iter_mut() {
@@ -53,6 +133,16 @@ impl ContainerMembers {
                             }
                             attr_found = true;
                             let expr: Expr = attr.parse_args()?;
+                            if options.local && matches!(expr, Expr::Async(_)) {
+                                return Err(Error::new(
+                                    expr.span(),
+                                    concat!(
+                                        "facet::container(local) fields cannot have ",
+                                        "async initializers, as local containers are ",
+                                        "built synchronously"
+                                    ),
+                                ));
+                            }
                             field_idents
                                 .push(field.ident.clone().expect("named field must have a name"));
                             field_inits.push(expr);
@@ -67,16 +157,126 @@ impl ContainerMembers {
                                 ));
                             }
                             attr_found = true;
+                            let static_dispatch = options.static_dispatch;
+                            let local = options.local;
+                            let ptr = facet_ptr(local);
+                            let options = FacetOptions::parse(&attr)?;
+                            if local
+                                && (options.lazy
+                                    || options.weak
+                                    || options.swappable
+                                    || options.mutable
+                                    || options.shutdown
+                                    || options.health
+                                    || options.key.is_some())
+                            {
+                                return Err(Error::new(
+                                    attr.span(),
+                                    concat!(
+                                        "facet::container(local) fields cannot be 'lazy', ",
+                                        "'weak', 'swappable', 'mutable', 'shutdown', 'health' ",
+                                        "or 'key' fields"
+                                    ),
+                                ));
+                            }
                             let mut facet_type = field.ty.clone();
                             if let Type::TraitObject(obj) = &mut facet_type {
-                                obj.bounds.push(syn::parse2(quote!(::std::marker::Send))?);
-                                obj.bounds.push(syn::parse2(quote!(::std::marker::Sync))?);
-                                obj.bounds.push(syn::parse2(quote!('static))?);
+                                add_trait_object_bounds(obj, local)?;
+                            }
+                            let facet_ident =
+                                field.ident.clone().expect("named field must have a name");
+                            if let Some(name) = &options.name {
+                                facet_names.insert(facet_ident.clone(), name.clone());
+                            }
+                            let facet_crate = format_ident!("{}", facet_crate_name());
+                            if options.lazy {
+                                field.ty = syn::parse2(quote! {
+                                    ::#facet_crate::LazyFacet<::std::sync::Arc<#facet_type>>
+                                })?;
+                                lazy_facet_idents.push(facet_ident);
+                                lazy_facet_types.push(facet_type);
+                            } else if options.weak {
+                                field.ty =
+                                    syn::parse2(quote!(::#facet_crate::WeakFacet<#facet_type>))?;
+                                weak_facet_idents.push(facet_ident);
+                                weak_facet_types.push(facet_type);
+                            } else if options.swappable {
+                                field.ty = syn::parse2(
+                                    quote!(::#facet_crate::SwappableFacet<#facet_type>),
+                                )?;
+                                swappable_facet_idents.push(facet_ident);
+                                swappable_facet_types.push(facet_type);
+                            } else if options.boxed {
+                                if options.health {
+                                    health_facet_idents.push(facet_ident.clone());
+                                }
+                                if options.downcast {
+                                    downcast_facet_idents.push(facet_ident.clone());
+                                }
+                                field.ty = syn::parse2(quote!(::std::boxed::Box<#facet_type>))?;
+                                boxed_facet_idents.push(facet_ident);
+                                boxed_facet_types.push(facet_type);
+                            } else if options.mutable {
+                                field.ty = syn::parse2(quote! {
+                                    ::std::sync::Arc<
+                                        ::std::sync::RwLock<::std::boxed::Box<#facet_type>>
+                                    >
+                                })?;
+                                mutable_facet_idents.push(facet_ident);
+                                mutable_facet_types.push(facet_type);
+                            } else {
+                                if options.shutdown {
+                                    shutdown_facet_idents.push(facet_ident.clone());
+                                }
+                                if options.health {
+                                    health_facet_idents.push(facet_ident.clone());
+                                }
+                                if options.downcast {
+                                    downcast_facet_idents.push(facet_ident.clone());
+                                }
+                                for supertrait in &options.supertraits {
+                                    supertrait_facet_idents.push(facet_ident.clone());
+                                    let mut supertrait_type = syn::parse2(quote!(dyn #supertrait))?;
+                                    if let Type::TraitObject(obj) = &mut supertrait_type {
+                                        add_trait_object_bounds(obj, local)?;
+                                    }
+                                    supertrait_types.push(supertrait_type);
+                                }
+                                let facet_ref_type = facet_type.clone();
+                                if let (true, Type::TraitObject(obj)) = (static_dispatch, &field.ty)
+                                {
+                                    // Store the concrete type that the
+                                    // factory's method of the same name
+                                    // builds.
+                                    let key = static_facet_key(&facet_ident);
+                                    let static_facet = quote! {
+                                        ::#facet_crate::StaticFacet<#key>
+                                    };
+                                    let mut bounds = obj.clone();
+                                    add_trait_object_bounds(&mut bounds, local)?;
+                                    let bounds = &bounds.bounds;
+                                    static_predicates.push(syn::parse2(quote! {
+                                        Factory: #static_facet
+                                    })?);
+                                    static_predicates.push(syn::parse2(quote! {
+                                        <Factory as #static_facet>::Impl: #bounds
+                                    })?);
+                                    facet_type = syn::parse2(quote! {
+                                        <Factory as #static_facet>::Impl
+                                    })?;
+                                }
+                                field.ty = syn::parse2(quote!(#ptr<#facet_type>))?;
+                                if let Some(key) = options.key {
+                                    keyed_facet_idents.push(facet_ident);
+                                    keyed_facet_types.push(facet_type);
+                                    keyed_facet_ref_types.push(facet_ref_type);
+                                    keyed_facet_keys.push(key);
+                                } else {
+                                    facet_idents.push(facet_ident);
+                                    facet_types.push(facet_type);
+                                    facet_ref_types.push(facet_ref_type);
+                                }
                             }
-                            field.ty = syn::parse2(quote!(::std::sync::Arc<#facet_type>))?;
-                            facet_idents
-                                .push(field.ident.clone().expect("named field must have a name"));
-                            facet_types.push(facet_type);
                         } else if attr.path.is_ident("delegate") {
                             if attr_found {
                                 return Err(Error::new(
@@ -89,7 +289,7 @@ impl ContainerMembers {
                             }
                             attr_found = true;
                             let delegate_type = field.ty.clone();
-                            let facets = extract_delegate_facets(&attr)?;
+                            let facets = extract_delegate_facets(&attr, options.local)?;
                             delegate_idents
                                 .push(field.ident.clone().expect("named field must have a name"));
                             delegate_types.push(delegate_type);
@@ -118,69 +318,1399 @@ impl ContainerMembers {
             }
         }
 
+        if !static_predicates.is_empty() {
+            // Static-dispatch containers are generic over the factory that
+            // builds them.
+            container
+                .generics
+                .params
+                .push(syn::parse2(quote!(Factory: ?::std::marker::Sized))?);
+            container
+                .generics
+                .make_where_clause()
+                .predicates
+                .extend(static_predicates);
+        }
+
         Ok(ContainerMembers {
             field_idents,
             field_inits,
             facet_idents,
             facet_types,
+            facet_ref_types,
+            lazy_facet_idents,
+            lazy_facet_types,
+            weak_facet_idents,
+            weak_facet_types,
+            swappable_facet_idents,
+            swappable_facet_types,
+            boxed_facet_idents,
+            boxed_facet_types,
+            mutable_facet_idents,
+            mutable_facet_types,
+            keyed_facet_idents,
+            keyed_facet_types,
+            keyed_facet_ref_types,
+            keyed_facet_keys,
+            shutdown_facet_idents,
+            health_facet_idents,
+            downcast_facet_idents,
+            supertrait_facet_idents,
+            supertrait_types,
             delegate_idents,
             delegate_types,
             delegate_facets,
+            facet_names,
         })
     }
 }
 
+/// Options for a facet field, given as `#[facet(option, ...)]`.
+#[derive(Debug, Default)]
+struct FacetOptions {
+    /// The facet is built on first access rather than when the container is
+    /// built.
+    lazy: bool,
+
+    /// The container only holds a weak reference to the facet.
+    weak: bool,
+
+    /// The facet can be replaced after the container has been built.
+    swappable: bool,
+
+    /// The facet is owned by the container, which holds it in a `Box` and
+    /// so can only provide access to it by reference.
+    boxed: bool,
+
+    /// The facet is owned by the container, which holds it behind a lock so
+    /// that it can be mutated or replaced through accessor methods.
+    mutable: bool,
+
+    /// The facet implements `FacetShutdown` and should be shut down when the
+    /// container is shut down.
+    shutdown: bool,
+
+    /// The facet implements `FacetHealth` and should be checked when the
+    /// container's health is checked.
+    health: bool,
+
+    /// The facet can be downcast to its concrete type with the container's
+    /// `downcast_facet` method.
+    downcast: bool,
+
+    /// Supertraits of the facet that are also facets, which the container
+    /// provides access to by upcasting the facet.
+    supertraits: Vec<Path>,
+
+    /// The name of the facet, if it is different from the name of the field.
+    name: Option<Ident>,
+
+    /// The key that distinguishes this instance of the facet from other
+    /// instances of the same facet in the container.
+    key: Option<Type>,
+}
+
+impl FacetOptions {
+    fn parse(attr: &Attribute) -> Result<Self, Error> {
+        let mut options = FacetOptions::default();
+        if attr.tokens.is_empty() {
+            return Ok(options);
+        }
+        let args: Punctuated<Meta, Token![,]> =
+            attr.parse_args_with(Punctuated::parse_terminated)?;
+        for arg in args {
+            match &arg {
+                Meta::Path(path) if path.is_ident("lazy") => options.lazy = true,
+                Meta::Path(path) if path.is_ident("weak") => options.weak = true,
+                Meta::Path(path) if path.is_ident("swappable") => options.swappable = true,
+                Meta::Path(path) if path.is_ident("boxed") => options.boxed = true,
+                Meta::Path(path) if path.is_ident("mutable") => options.mutable = true,
+                Meta::Path(path) if path.is_ident("shutdown") => options.shutdown = true,
+                Meta::Path(path) if path.is_ident("health") => options.health = true,
+                Meta::Path(path) if path.is_ident("downcast") => options.downcast = true,
+                Meta::NameValue(name_value) if name_value.path.is_ident("name") => {
+                    options.name = Some(parse_facet_name(&name_value.lit)?);
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("key") => {
+                    options.key = Some(parse_facet_key(&name_value.lit)?);
+                }
+                Meta::List(list) if list.path.is_ident("supertraits") => {
+                    for nested in &list.nested {
+                        match nested {
+                            NestedMeta::Meta(Meta::Path(path)) => {
+                                options.supertraits.push(path.clone())
+                            }
+                            _ => {
+                                return Err(Error::new(
+                                    nested.span(),
+                                    "expected the name of a facet trait",
+                                ));
+                            }
+                        }
+                    }
+                }
+                _ => return Err(Error::new(arg.span(), "unrecognised facet option")),
+            }
+        }
+        if options.lazy && options.weak {
+            return Err(Error::new(
+                attr.span(),
+                "facet::container field cannot be both 'lazy' and 'weak'",
+            ));
+        }
+        if options.shutdown && (options.lazy || options.weak) {
+            return Err(Error::new(
+                attr.span(),
+                "facet::container 'shutdown' fields cannot be 'lazy' or 'weak'",
+            ));
+        }
+        if options.health && (options.lazy || options.weak || options.swappable) {
+            return Err(Error::new(
+                attr.span(),
+                "facet::container 'health' fields cannot be 'lazy', 'weak' or 'swappable'",
+            ));
+        }
+        if options.downcast && (options.lazy || options.weak || options.swappable) {
+            return Err(Error::new(
+                attr.span(),
+                "facet::container 'downcast' fields cannot be 'lazy', 'weak' or 'swappable'",
+            ));
+        }
+        if !options.supertraits.is_empty() && (options.lazy || options.weak) {
+            return Err(Error::new(
+                attr.span(),
+                "facet::container 'supertraits' fields cannot be 'lazy' or 'weak'",
+            ));
+        }
+        if options.key.is_some() && (options.lazy || options.weak) {
+            return Err(Error::new(
+                attr.span(),
+                "facet::container 'key' fields cannot be 'lazy' or 'weak'",
+            ));
+        }
+        if options.key.is_some() && !options.supertraits.is_empty() {
+            return Err(Error::new(
+                attr.span(),
+                "facet::container 'key' fields cannot have 'supertraits'",
+            ));
+        }
+        if options.swappable
+            && (options.lazy
+                || options.weak
+                || options.shutdown
+                || options.key.is_some()
+                || !options.supertraits.is_empty())
+        {
+            return Err(Error::new(
+                attr.span(),
+                concat!(
+                    "facet::container 'swappable' fields cannot be 'lazy', 'weak', ",
+                    "'shutdown' or 'key' fields or have 'supertraits'"
+                ),
+            ));
+        }
+        if options.boxed
+            && (options.lazy
+                || options.weak
+                || options.swappable
+                || options.shutdown
+                || options.key.is_some()
+                || !options.supertraits.is_empty())
+        {
+            return Err(Error::new(
+                attr.span(),
+                concat!(
+                    "facet::container 'boxed' fields cannot be 'lazy', 'weak', ",
+                    "'swappable', 'shutdown' or 'key' fields or have 'supertraits'"
+                ),
+            ));
+        }
+        if options.mutable
+            && (options.lazy
+                || options.weak
+                || options.swappable
+                || options.boxed
+                || options.shutdown
+                || options.health
+                || options.downcast
+                || options.key.is_some()
+                || !options.supertraits.is_empty())
+        {
+            return Err(Error::new(
+                attr.span(),
+                concat!(
+                    "facet::container 'mutable' fields cannot be 'lazy', 'weak', ",
+                    "'swappable', 'boxed', 'shutdown', 'health', 'downcast' or 'key' ",
+                    "fields or have 'supertraits'"
+                ),
+            ));
+        }
+        Ok(options)
+    }
+}
+
+/// Options for a container, given as `#[facet::container(option, ...)]`.
+#[derive(Debug, Default)]
+struct ContainerOptions {
+    /// Dynamic facets are stored as the concrete types built by the factory,
+    /// which becomes a parameter of the container.
+    static_dispatch: bool,
+
+    /// Generate a `Debug` implementation that shows the names of the facets
+    /// rather than the facets themselves.
+    debug: bool,
+
+    /// Facets are held in an `Rc` and don't need to be `Send` or `Sync`.
+    /// Local containers can only be built by synchronous factories.
+    local: bool,
+
+    /// Record which facets are accessed through the container's facet
+    /// traits, so that unused facets can be reported.
+    track_usage: bool,
+
+    /// Factory parameters that the initializers of normal fields can
+    /// access through `params`, given as `params(name: Type, ...)`.
+    params: Vec<(Ident, Type)>,
+
+    /// Record the values of the factory parameters in `params` that the
+    /// container was built with, so they can be inspected later.
+    record_params: bool,
+
+    /// Other containers that this container can be converted from, by
+    /// cloning the facets they share, given as `from(Type, ...)`.
+    from: Vec<Type>,
+
+    /// Generate a `downgrade` method that returns a weak handle to the
+    /// container, which holds its facets as weak references.
+    downgrade: bool,
+}
+
+impl Parse for ContainerOptions {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let mut options = ContainerOptions::default();
+        while !input.is_empty() {
+            let arg = input.parse::<Ident>()?;
+            if arg == "static_dispatch" {
+                options.static_dispatch = true;
+            } else if arg == "debug" {
+                options.debug = true;
+            } else if arg == "local" {
+                options.local = true;
+            } else if arg == "track_usage" {
+                options.track_usage = true;
+            } else if arg == "record_params" {
+                options.record_params = true;
+            } else if arg == "downgrade" {
+                options.downgrade = true;
+            } else if arg == "from" {
+                let content;
+                syn::parenthesized!(content in input);
+                let types = Punctuated::<Type, Token![,]>::parse_terminated(&content)?;
+                options.from.extend(types);
+            } else if arg == "params" {
+                let content;
+                syn::parenthesized!(content in input);
+                while !content.is_empty() {
+                    let ident = content.parse::<Ident>()?;
+                    content.parse::<Token![:]>()?;
+                    let ty = content.parse::<Type>()?;
+                    options.params.push((ident, ty));
+                    if !content.is_empty() {
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+            } else {
+                return Err(Error::new(arg.span(), "unrecognised container option"));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(options)
+    }
+}
+
 pub fn container(
     attr: proc_macro::TokenStream,
     item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    let _ = parse_macro_input!(attr as syn::parse::Nothing);
+    let options = parse_macro_input!(attr as ContainerOptions);
     let container = parse_macro_input!(item as ItemStruct);
 
-    match gen_container(container) {
-        Ok(output) => output,
-        Err(e) => e.to_compile_error(),
+    match gen_container(container, options) {
+        Ok(output) => output,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
+
+fn gen_container(
+    mut container: ItemStruct,
+    options: ContainerOptions,
+) -> Result<TokenStream, Error> {
+    let facet_crate = format_ident!("{}", facet_crate_name());
+    let members = ContainerMembers::extract(&mut container, &options)?;
+
+    if options.record_params && options.params.is_empty() {
+        return Err(Error::new(
+            Span::call_site(),
+            "facet::container(record_params) requires the recorded parameters to be listed in params(...)",
+        ));
+    }
+
+    // Initializers access factory parameters through `params`, so no field
+    // can have that name.
+    if !options.params.is_empty() {
+        if let Some(field) = container
+            .fields
+            .iter()
+            .find(|field| field.ident.as_ref().is_some_and(|ident| ident == "params"))
+        {
+            return Err(Error::new(
+                field.span(),
+                "facet::container fields cannot be named 'params' when the container has params",
+            ));
+        }
+    }
+
+    // Containers with facets that need shutting down record the order
+    // facets were built in, so that they can be shut down in reverse order.
+    if !members.shutdown_facet_idents.is_empty() {
+        if let Fields::Named(named_fields) = &mut container.fields {
+            named_fields.named.push(Field::parse_named.parse2(quote! {
+                __facet_build_order: ::std::vec::Vec<&'static str>
+            })?);
+        }
+    }
+
+    // Containers that track usage hold a flag for each facet that records
+    // whether it has been accessed.
+    if options.track_usage {
+        if let Fields::Named(named_fields) = &mut container.fields {
+            named_fields.named.push(Field::parse_named.parse2(quote! {
+                __facet_usage: ::#facet_crate::FacetUsage
+            })?);
+        }
+    }
+
+    // Containers that record their build parameters hold a copy of them.
+    if options.record_params {
+        let build_params_name = build_params_name(&container);
+        let (_, ty_generics, _) = container.generics.split_for_impl();
+        let build_params_ty = quote!(#build_params_name #ty_generics);
+        if let Fields::Named(named_fields) = &mut container.fields {
+            named_fields.named.push(Field::parse_named.parse2(quote! {
+                __facet_build_params: #build_params_ty
+            })?);
+        }
+    }
+
+    let attr_impls = gen_attr_impls(&facet_crate, &container, &members, &options);
+    // Containers with async initializers can't be built synchronously.
+    let buildable_impl = if members.has_async_inits() {
+        quote!()
+    } else {
+        gen_buildable_impl(&facet_crate, &container, &members, &options)
+    };
+    // Local containers hold facets that can't be sent between threads, so
+    // they can't be built in parallel either.
+    let parallel_buildable_impl = if members.has_async_inits() || options.local {
+        quote!()
+    } else {
+        gen_parallel_buildable_impl(&facet_crate, &container, &members)
+    };
+    // Local containers can't be built asynchronously, as async builds
+    // require facets to be `Send`, and neither can containers with boxed or
+    // mutable facets, as async builders share the facets they build.
+    let async_buildable_impl = if options.local || members.has_owned_facets() {
+        quote!()
+    } else {
+        gen_async_buildable_impl(&facet_crate, &container, &members, &options)
+    };
+    let container_facets_impl = if cfg!(feature = "graph") {
+        gen_container_facets_impl(&facet_crate, &container, &members)
+    } else {
+        quote!()
+    };
+    let container_shutdown_impl = gen_container_shutdown_impl(&facet_crate, &container, &members);
+    let container_health_impl = gen_container_health_impl(&facet_crate, &container, &members);
+    let like_trait = gen_like_trait(&facet_crate, &container, &members);
+    let container_usage_impl = if options.track_usage {
+        gen_container_usage_impl(&facet_crate, &container)
+    } else {
+        quote!()
+    };
+    let accessors = gen_accessors(&facet_crate, &container, &members);
+    let downcast = gen_downcast(&facet_crate, &container, &members);
+    let partial = if options.local || members.has_owned_facets() {
+        quote!()
+    } else {
+        gen_partial(&facet_crate, &container, &members)?
+    };
+    let build_params = if options.record_params {
+        gen_build_params(&container, &options)
+    } else {
+        quote!()
+    };
+    let from_impls = gen_from_impls(&facet_crate, &container, &members, &options)?;
+    let downgrade = if options.downgrade {
+        gen_downgrade(&facet_crate, &container, &members, &options)?
+    } else {
+        quote!()
+    };
+    let debug_impl = if options.debug {
+        gen_debug_impl(&container, &members)
+    } else {
+        quote!()
+    };
+
+    Ok(quote! {
+        #container
+
+        #accessors
+
+        #downcast
+
+        #( #attr_impls )*
+
+        #buildable_impl
+
+        #parallel_buildable_impl
+
+        #async_buildable_impl
+
+        #container_facets_impl
+
+        #container_shutdown_impl
+
+        #container_health_impl
+
+        #like_trait
+
+        #container_usage_impl
+
+        #partial
+
+        #build_params
+
+        #from_impls
+
+        #downgrade
+
+        #debug_impl
+    })
+}
+
+/// Generates the weak handle to the container that is returned by its
+/// `downgrade` method.  The handle holds weak references to the container's
+/// facets, and can be upgraded back to the container while all of them are
+/// still alive.
+fn gen_downgrade(
+    facet_crate: &Ident,
+    container: &ItemStruct,
+    members: &ContainerMembers,
+    options: &ContainerOptions,
+) -> Result<TokenStream, Error> {
+    if options.record_params {
+        return Err(Error::new(
+            Span::call_site(),
+            "facet::container(downgrade) cannot be used with record_params",
+        ));
+    }
+    let undowngradable_field = members
+        .field_idents
+        .iter()
+        .chain(&members.delegate_idents)
+        .chain(&members.lazy_facet_idents)
+        .chain(&members.swappable_facet_idents)
+        .chain(&members.boxed_facet_idents)
+        .chain(&members.mutable_facet_idents)
+        .next();
+    if let Some(field) = undowngradable_field {
+        return Err(Error::new(
+            field.span(),
+            concat!(
+                "facet::container(downgrade) requires all fields to be plain, keyed or ",
+                "weak facets, not 'init', 'delegate', 'lazy', 'swappable', 'boxed' or ",
+                "'mutable' fields"
+            ),
+        ));
+    }
+    let ptr = facet_ptr(options.local);
+    let weak_ptr = if options.local {
+        quote!(::std::rc::Weak)
+    } else {
+        quote!(::std::sync::Weak)
+    };
+    let container_name = &container.ident;
+    let weak_name = format_ident!("Weak{}", container_name);
+    let vis = &container.vis;
+    let generics = &container.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let facet_idents = members
+        .facet_idents
+        .iter()
+        .chain(&members.keyed_facet_idents)
+        .collect::<Vec<_>>();
+    let facet_types = members
+        .facet_types
+        .iter()
+        .chain(&members.keyed_facet_types)
+        .collect::<Vec<_>>();
+    let weak_facet_idents = &members.weak_facet_idents;
+    let weak_facet_types = &members.weak_facet_types;
+    let (build_order_field, build_order_clone) = if members.shutdown_facet_idents.is_empty() {
+        (quote!(), quote!())
+    } else {
+        (
+            quote!(__facet_build_order: ::std::vec::Vec<&'static str>,),
+            quote!(__facet_build_order: self.__facet_build_order.clone(),),
+        )
+    };
+    let usage_field = gen_usage_field(facet_crate, members, options);
+    let doc = format!(
+        concat!(
+            "A weak handle to a [`{}`], which doesn't keep its facets alive.\n\n",
+            "Returned by [`{}::downgrade`]."
+        ),
+        container_name, container_name
+    );
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #weak_name #generics #where_clause {
+            #( #facet_idents: #weak_ptr<#facet_types>, )*
+            #( #weak_facet_idents: ::#facet_crate::WeakFacet<#weak_facet_types>, )*
+            #build_order_field
+        }
+
+        impl #impl_generics #container_name #ty_generics #where_clause {
+            /// Returns a weak handle to this container, which doesn't keep
+            /// its facets alive.
+            pub fn downgrade(&self) -> #weak_name #ty_generics {
+                #weak_name {
+                    #( #facet_idents: #ptr::downgrade(&self.#facet_idents), )*
+                    #( #weak_facet_idents: ::std::clone::Clone::clone(&self.#weak_facet_idents), )*
+                    #build_order_clone
+                }
+            }
+        }
+
+        impl #impl_generics #weak_name #ty_generics #where_clause {
+            /// Upgrades the handle back to the container, or returns `None`
+            /// if any of the container's facets have been dropped.
+            pub fn upgrade(&self) -> ::std::option::Option<#container_name #ty_generics> {
+                ::std::option::Option::Some(#container_name {
+                    #( #facet_idents: self.#facet_idents.upgrade()?, )*
+                    #( #weak_facet_idents: ::std::clone::Clone::clone(&self.#weak_facet_idents), )*
+                    #build_order_clone
+                    #usage_field
+                })
+            }
+        }
+
+        impl #impl_generics ::std::clone::Clone for #weak_name #ty_generics #where_clause {
+            fn clone(&self) -> Self {
+                #weak_name {
+                    #( #facet_idents: ::std::clone::Clone::clone(&self.#facet_idents), )*
+                    #( #weak_facet_idents: ::std::clone::Clone::clone(&self.#weak_facet_idents), )*
+                    #build_order_clone
+                }
+            }
+        }
+    })
+}
+
+/// Generates the `From` implementations that convert references to the
+/// containers listed in `from(...)` into this container.  Facets are matched
+/// by type, so the other container must provide every facet this container
+/// holds, but the fields may have different names.
+fn gen_from_impls(
+    facet_crate: &Ident,
+    container: &ItemStruct,
+    members: &ContainerMembers,
+    options: &ContainerOptions,
+) -> Result<TokenStream, Error> {
+    if options.from.is_empty() {
+        return Ok(quote!());
+    }
+    if options.static_dispatch || options.record_params {
+        return Err(Error::new(
+            Span::call_site(),
+            "facet::container(from(...)) cannot be used with static_dispatch or record_params",
+        ));
+    }
+    let unconvertible_field = members
+        .field_idents
+        .iter()
+        .chain(&members.delegate_idents)
+        .chain(&members.lazy_facet_idents)
+        .chain(&members.weak_facet_idents)
+        .chain(&members.swappable_facet_idents)
+        .chain(&members.boxed_facet_idents)
+        .chain(&members.mutable_facet_idents)
+        .chain(&members.shutdown_facet_idents)
+        .next();
+    if let Some(field) = unconvertible_field {
+        return Err(Error::new(
+            field.span(),
+            concat!(
+                "facet::container(from(...)) requires all fields to be plain or keyed ",
+                "facets, not 'init', 'delegate', 'lazy', 'weak', 'swappable', 'boxed', ",
+                "'mutable' or 'shutdown' fields"
+            ),
+        ));
+    }
+
+    let (facet_ptr_trait, facet_ptr_method) = if options.local {
+        (quote!(FacetRc), quote!(facet_rc))
+    } else {
+        (quote!(FacetArc), quote!(facet_arc))
+    };
+    let container_name = &container.ident;
+    let (impl_generics, ty_generics, where_clause) = container.generics.split_for_impl();
+    let usage_field = gen_usage_field(facet_crate, members, options);
+    let impls = options.from.iter().map(|source| {
+        let facets = members
+            .facet_idents
+            .iter()
+            .zip(&members.facet_ref_types)
+            .map(|(facet_ident, facet_type)| {
+                quote_spanned! { facet_ident.span()=>
+                    #facet_ident: <#source as ::#facet_crate::#facet_ptr_trait<#facet_type>>
+                        ::#facet_ptr_method(source),
+                }
+            });
+        let keyed_facets = members
+            .keyed_facet_idents
+            .iter()
+            .zip(&members.keyed_facet_ref_types)
+            .zip(&members.keyed_facet_keys)
+            .map(|((facet_ident, facet_type), key)| {
+                quote_spanned! { facet_ident.span()=>
+                    #facet_ident:
+                        <#source as ::#facet_crate::KeyedFacetArc<#key, #facet_type>>
+                            ::keyed_facet_arc(source),
+                }
+            });
+        quote! {
+            impl #impl_generics ::std::convert::From<&#source>
+                for #container_name #ty_generics #where_clause
+            {
+                fn from(source: &#source) -> Self {
+                    #container_name {
+                        #( #facets )*
+                        #( #keyed_facets )*
+                        #usage_field
+                    }
+                }
+            }
+        }
+    });
+    Ok(quote!(#( #impls )*))
+}
+
+/// Generates a `Debug` implementation for the container.  Facets are shown
+/// by name, as they are not required to implement `Debug`, but normal fields
+/// and nested containers are shown using their own `Debug` implementations.
+fn gen_debug_impl(container: &ItemStruct, members: &ContainerMembers) -> TokenStream {
+    let container_name = &container.ident;
+    let (impl_generics, ty_generics, _) = container.generics.split_for_impl();
+    let where_predicates = where_predicates(&container.generics);
+    let mut debug_bounds = Vec::new();
+    let mut debug_fields = Vec::new();
+    for field in &container.fields {
+        let field_ident = match &field.ident {
+            Some(ident)
+                if ident != "__facet_build_order"
+                    && ident != "__facet_usage"
+                    && ident != "__facet_build_params" =>
+            {
+                ident
+            }
+            _ => continue,
+        };
+        let field_ty = &field.ty;
+        if members.field_idents.contains(field_ident)
+            || members.delegate_idents.contains(field_ident)
+        {
+            debug_bounds.push(quote!(#field_ty: ::std::fmt::Debug));
+            debug_fields.push(quote! {
+                .field(stringify!(#field_ident), &self.#field_ident)
+            });
+        } else {
+            let facet_name = members.facet_name(field_ident);
+            debug_fields.push(quote! {
+                .field(
+                    stringify!(#field_ident),
+                    &format_args!("<{}>", stringify!(#facet_name)),
+                )
+            });
+        }
+    }
+
+    quote! {
+        impl #impl_generics ::std::fmt::Debug for #container_name #ty_generics
+        where
+            #( #debug_bounds, )*
+            #( #where_predicates, )*
+        {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_struct(stringify!(#container_name))
+                    #( #debug_fields )*
+                    .finish()
+            }
+        }
+    }
+}
+
+/// Generates the partial version of the container, which holds the facets
+/// built by a partial build, and the impls that allow it to be built.
+fn gen_partial(
+    facet_crate: &Ident,
+    container: &ItemStruct,
+    members: &ContainerMembers,
+) -> Result<TokenStream, Error> {
+    let facet_idents = &members.facet_idents;
+    let facet_types = &members.facet_types;
+    let facet_ref_types = &members.facet_ref_types;
+    let vis = &container.vis;
+    let container_name = &container.ident;
+    let partial_name = format_ident!("Partial{}", container_name);
+    let generics = &container.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let where_predicates = where_predicates(generics);
+    let sync_generics = extend_generics(generics, quote!(B));
+    let (sync_impl_generics, _, _) = sync_generics.split_for_impl();
+    let async_generics = extend_generics(generics, quote!('builder, B));
+    let (async_impl_generics, _, _) = async_generics.split_for_impl();
+    let partial_doc = format!(
+        "A partial build of [`{}`], holding only the facets that were built.",
+        container_name
+    );
+    let not_built = facet_idents
+        .iter()
+        .map(|ident| {
+            format!(
+                "facet '{}' was not built by this partial build of '{}'",
+                ident, container_name
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // The parameters of the container may not all be used by the facets in
+    // the partial container.
+    let lifetimes = generics.lifetimes().map(|def| &def.lifetime);
+    let type_params = generics.type_params().map(|param| &param.ident);
+    let phantom_type = quote! {
+        ::std::marker::PhantomData<(#( &#lifetimes (), )* fn() -> (#( #type_params, )*))>
+    };
+
+    Ok(quote! {
+        #[doc = #partial_doc]
+        #vis struct #partial_name #generics #where_clause {
+            #(
+                #[allow(missing_docs)]
+                #vis #facet_idents: ::std::option::Option<::std::sync::Arc<#facet_types>>,
+            )*
+            __facet_params: #phantom_type,
+        }
+
+        #(
+            impl #impl_generics ::#facet_crate::FacetRef<#facet_ref_types>
+                for #partial_name #ty_generics #where_clause
+            {
+                #[inline]
+                fn facet_ref(&self) -> &(#facet_ref_types) {
+                    self.#facet_idents.as_deref().expect(#not_built)
+                }
+            }
+
+            impl #impl_generics ::#facet_crate::FacetRef<#facet_ref_types>
+                for &#partial_name #ty_generics #where_clause
+            {
+                #[inline]
+                fn facet_ref(&self) -> &(#facet_ref_types) {
+                    (*self).#facet_idents.as_deref().expect(#not_built)
+                }
+            }
+
+            impl #impl_generics ::#facet_crate::FacetArc<#facet_ref_types>
+                for #partial_name #ty_generics #where_clause
+            {
+                #[inline]
+                fn facet_arc(&self) -> ::std::sync::Arc<#facet_ref_types> {
+                    self.#facet_idents.clone().expect(#not_built)
+                }
+            }
+
+            impl #impl_generics ::#facet_crate::FacetArc<#facet_ref_types>
+                for &#partial_name #ty_generics #where_clause
+            {
+                #[inline]
+                fn facet_arc(&self) -> ::std::sync::Arc<#facet_ref_types> {
+                    (*self).#facet_idents.clone().expect(#not_built)
+                }
+            }
+        )*
+
+        impl #sync_impl_generics ::#facet_crate::PartialBuildable<B>
+            for #container_name #ty_generics
+        where B: ::std::marker::Send + ::std::marker::Sync
+            #( + ::#facet_crate::Builder<::std::sync::Arc<#facet_types>> )*,
+            #( #where_predicates, )*
+        {
+            type Partial = #partial_name #ty_generics;
+
+            fn build_partial(
+                builder: &mut B,
+                facets: &::#facet_crate::FacetSet,
+            ) -> ::std::result::Result<Self::Partial, ::#facet_crate::FactoryError> {
+                facets.check_fields(
+                    stringify!(#container_name),
+                    &[ #( stringify!(#facet_idents), )* ],
+                )?;
+                #(
+                    let #facet_idents = if facets.contains(stringify!(#facet_idents)) {
+                        Some(
+                            <B as ::#facet_crate::Builder<
+                                ::std::sync::Arc<#facet_types>
+                            >>::build(builder)?
+                        )
+                    } else {
+                        None
+                    };
+                )*
+                Ok(#partial_name {
+                    #( #facet_idents, )*
+                    __facet_params: ::std::marker::PhantomData,
+                })
+            }
+        }
+
+        impl #async_impl_generics ::#facet_crate::AsyncPartialBuildable<'builder, B>
+            for #container_name #ty_generics
+        where B: ::std::marker::Send + ::std::marker::Sync + ::#facet_crate::AsyncBuilder
+            #( + ::#facet_crate::AsyncBuilderFor<::std::sync::Arc<#facet_types>> )*
+            + 'builder,
+            #( #where_predicates, )*
+        {
+            type Partial = #partial_name #ty_generics;
+
+            fn build_partial_async(
+                mut builder: B,
+                facets: ::#facet_crate::FacetSet,
+            ) -> ::std::pin::Pin<::std::boxed::Box<
+                dyn std::future::Future<
+                    Output = ::std::result::Result<Self::Partial, ::#facet_crate::FactoryError>
+                > + ::std::marker::Send + 'builder
+            >>
+            {
+                ::std::boxed::Box::pin(async move {
+                    facets.check_fields(
+                        stringify!(#container_name),
+                        &[ #( stringify!(#facet_idents), )* ],
+                    )?;
+                    #(
+                        if facets.contains(stringify!(#facet_idents)) {
+                            <B as ::#facet_crate::AsyncBuilderFor<
+                                ::std::sync::Arc<#facet_types>
+                            >>::need(&mut builder);
+                        }
+                    )*
+                    <B as ::#facet_crate::AsyncBuilder>::build_needed(&mut builder).await?;
+                    #(
+                        let #facet_idents = if facets.contains(stringify!(#facet_idents)) {
+                            Some(
+                                <B as ::#facet_crate::AsyncBuilderFor<
+                                    ::std::sync::Arc<#facet_types>
+                                >>::get(&builder)
+                            )
+                        } else {
+                            None
+                        };
+                    )*
+                    Ok(#partial_name {
+                        #( #facet_idents, )*
+                        __facet_params: ::std::marker::PhantomData,
+                    })
+                })
+            }
+        }
+    })
+}
+
+fn gen_container_shutdown_impl(
+    facet_crate: &Ident,
+    container: &ItemStruct,
+    members: &ContainerMembers,
+) -> TokenStream {
+    let shutdown_facet_idents = &members.shutdown_facet_idents;
+    let shutdown_facet_names = shutdown_facet_idents
+        .iter()
+        .map(|ident| members.facet_name(ident));
+    let delegate_idents = &members.delegate_idents;
+    let delegate_types = &members.delegate_types;
+    let container_name = &container.ident;
+    let (impl_generics, ty_generics, _) = container.generics.split_for_impl();
+    let where_predicates = where_predicates(&container.generics);
+
+    quote! {
+        impl #impl_generics ::#facet_crate::ContainerShutdown
+            for #container_name #ty_generics
+        where
+            #( #delegate_types: ::#facet_crate::ContainerShutdown, )*
+            #( #where_predicates, )*
+        {
+            fn shutdown_facets(&self) -> ::std::vec::Vec<(
+                usize,
+                &'static str,
+                ::#facet_crate::futures::future::BoxFuture<'_, ()>,
+            )> {
+                ::std::vec![
+                    #(
+                        (
+                            self.__facet_build_order
+                                .iter()
+                                .position(|name| *name == stringify!(#shutdown_facet_names))
+                                .unwrap_or_default(),
+                            stringify!(#shutdown_facet_names),
+                            ::#facet_crate::FacetShutdown::shutdown(
+                                &*self.#shutdown_facet_idents
+                            ),
+                        ),
+                    )*
+                ]
+                .into_iter()
+                #(
+                    .chain(
+                        ::#facet_crate::ContainerShutdown::shutdown_facets(
+                            &self.#delegate_idents
+                        )
+                    )
+                )*
+                .collect()
+            }
+        }
+    }
+}
+
+/// Generates a trait for types that provide access by reference to the same
+/// facets as the container, so that generic code can use a single bound
+/// rather than a bound for each facet.
+fn gen_like_trait(
+    facet_crate: &Ident,
+    container: &ItemStruct,
+    members: &ContainerMembers,
+) -> TokenStream {
+    let container_name = &container.ident;
+    let vis = &container.vis;
+    let like_trait_name = format_ident!("{}Like", container_name);
+    let facet_ref_types = members
+        .facet_ref_types
+        .iter()
+        .chain(&members.boxed_facet_types)
+        .chain(&members.supertrait_types)
+        .chain(members.delegate_facets.iter().flatten());
+    let keyed_facet_ref_types = &members.keyed_facet_ref_types;
+    let keyed_facet_keys = &members.keyed_facet_keys;
+    let bounds = quote! {
+        #( ::#facet_crate::FacetRef<#facet_ref_types> + )*
+        #( ::#facet_crate::KeyedFacetRef<#keyed_facet_keys, #keyed_facet_ref_types> + )*
+    };
+    let (trait_generics, ty_generics, where_clause) = container.generics.split_for_impl();
+    let generics = extend_generics(&container.generics, quote!(__C: ?::std::marker::Sized));
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let where_predicates = where_predicates(&container.generics);
+
+    quote! {
+        /// Trait for types that provide access by reference to all of the
+        /// facets of
+        #[doc = concat!("[`", stringify!(#container_name), "`],")]
+        /// such as the container itself or containers that delegate to it.
+        #vis trait #like_trait_name #trait_generics: #bounds #where_clause {}
+
+        impl #impl_generics #like_trait_name #ty_generics for __C
+        where
+            __C: #bounds,
+            #( #where_predicates, )*
+        {
+        }
+    }
+}
+
+fn gen_container_health_impl(
+    facet_crate: &Ident,
+    container: &ItemStruct,
+    members: &ContainerMembers,
+) -> TokenStream {
+    let health_facet_idents = &members.health_facet_idents;
+    let health_facet_names = health_facet_idents
+        .iter()
+        .map(|ident| members.facet_name(ident));
+    let delegate_idents = &members.delegate_idents;
+    let delegate_types = &members.delegate_types;
+    let container_name = &container.ident;
+    let (impl_generics, ty_generics, _) = container.generics.split_for_impl();
+    let where_predicates = where_predicates(&container.generics);
+
+    quote! {
+        impl #impl_generics ::#facet_crate::ContainerHealth
+            for #container_name #ty_generics
+        where
+            #( #delegate_types: ::#facet_crate::ContainerHealth, )*
+            #( #where_predicates, )*
+        {
+            fn health_checks(&self) -> ::std::vec::Vec<(
+                &'static str,
+                ::#facet_crate::futures::future::BoxFuture<'_, ::#facet_crate::HealthStatus>,
+            )> {
+                ::std::vec![
+                    #(
+                        (
+                            stringify!(#health_facet_names),
+                            ::#facet_crate::FacetHealth::check_health(
+                                &*self.#health_facet_idents
+                            ),
+                        ),
+                    )*
+                ]
+                .into_iter()
+                #(
+                    .chain(
+                        ::#facet_crate::ContainerHealth::health_checks(
+                            &self.#delegate_idents
+                        )
+                    )
+                )*
+                .collect()
+            }
+        }
+    }
+}
+
+fn gen_container_facets_impl(
+    facet_crate: &Ident,
+    container: &ItemStruct,
+    members: &ContainerMembers,
+) -> TokenStream {
+    let facet_idents = members
+        .facet_idents
+        .iter()
+        .chain(members.keyed_facet_idents.iter())
+        .chain(members.lazy_facet_idents.iter())
+        .chain(members.weak_facet_idents.iter())
+        .chain(members.swappable_facet_idents.iter())
+        .chain(members.boxed_facet_idents.iter())
+        .chain(members.mutable_facet_idents.iter())
+        .collect::<Vec<_>>();
+    let facet_names = facet_idents.iter().map(|ident| members.facet_name(ident));
+    let delegate_idents = &members.delegate_idents;
+    let delegate_types = &members.delegate_types;
+
+    let container_name = &container.ident;
+    let (impl_generics, ty_generics, where_clause) = container.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::#facet_crate::ContainerFacets
+            for #container_name #ty_generics #where_clause
+        {
+            fn container_name() -> &'static str {
+                stringify!(#container_name)
+            }
+
+            fn facet_fields() -> ::std::vec::Vec<::#facet_crate::ContainerField> {
+                ::std::vec![
+                    #(
+                        ::#facet_crate::ContainerField {
+                            path: ::std::string::String::from(stringify!(#facet_idents)),
+                            facet: stringify!(#facet_names),
+                        },
+                    )*
+                ]
+                .into_iter()
+                #(
+                    .chain(
+                        <#delegate_types as ::#facet_crate::ContainerFacets>::facet_fields()
+                            .into_iter()
+                            .map(|field| ::#facet_crate::ContainerField {
+                                path: format!("{}.{}", stringify!(#delegate_idents), field.path),
+                                facet: field.facet,
+                            })
+                    )
+                )*
+                .collect()
+            }
+        }
+    }
+}
+
+/// Returns the builder bound and field initializer for the build order of
+/// containers that have facets that need shutting down.
+fn gen_build_order(facet_crate: &Ident, members: &ContainerMembers) -> (TokenStream, TokenStream) {
+    if members.shutdown_facet_idents.is_empty() {
+        return (quote!(), quote!());
+    }
+    (
+        quote!(+ ::#facet_crate::BuildOrder),
+        quote! {
+            __facet_build_order: <B as ::#facet_crate::BuildOrder>::build_order(builder),
+        },
+    )
+}
+
+/// Returns the builder bounds and the `params` binding for containers whose
+/// field initializers access factory parameters.  Synchronous builds report
+/// missing parameters when the binding is made, while async builds check for
+/// them with `check_params` before building any facets.
+fn gen_params(
+    facet_crate: &Ident,
+    options: &ContainerOptions,
+    checked: bool,
+) -> (TokenStream, TokenStream) {
+    if options.params.is_empty() {
+        return (quote!(), quote!());
+    }
+    let param_idents = options
+        .params
+        .iter()
+        .map(|(ident, _)| ident)
+        .collect::<Vec<_>>();
+    let param_types = options.params.iter().map(|(_, ty)| ty).collect::<Vec<_>>();
+    let param_getters = param_idents.iter().zip(&param_types).map(|(ident, ty)| {
+        let unwrap = if checked {
+            quote!(.expect("bug in #[facet::container]: parameters were not checked"))
+        } else {
+            quote! {
+                .ok_or(::#facet_crate::FactoryError::MissingParameter {
+                    name: stringify!(#ident),
+                })?
+            }
+        };
+        quote! {
+            <B as ::#facet_crate::BuildParam<#ty>>::param(builder, stringify!(#ident)) #unwrap
+        }
+    });
+    (
+        quote!( #( + ::#facet_crate::BuildParam<#param_types> )* ),
+        quote! {
+            #[allow(dead_code)]
+            struct __FacetParams<'params> {
+                #( #param_idents: &'params #param_types, )*
+            }
+            #[allow(unused_variables)]
+            let params = __FacetParams {
+                #( #param_idents: #param_getters, )*
+            };
+        },
+    )
+}
+
+/// Generates the `check_params` method of async builds, which checks the
+/// builder has the factory parameters needed by the container and its
+/// delegates.
+fn gen_check_params(
+    facet_crate: &Ident,
+    members: &ContainerMembers,
+    options: &ContainerOptions,
+) -> TokenStream {
+    let delegate_types = &members.delegate_types;
+    let param_idents = options.params.iter().map(|(ident, _)| ident);
+    let param_types = options.params.iter().map(|(_, ty)| ty);
+    quote! {
+        fn check_params(builder: &B) -> ::std::result::Result<(), ::#facet_crate::FactoryError> {
+            #(
+                <#delegate_types as ::#facet_crate::AsyncBuildable<'builder, B>>
+                    ::check_params(builder)?;
+            )*
+            #(
+                if <B as ::#facet_crate::BuildParam<#param_types>>::param(
+                    builder,
+                    stringify!(#param_idents),
+                )
+                .is_none()
+                {
+                    return Err(::#facet_crate::FactoryError::MissingParameter {
+                        name: stringify!(#param_idents),
+                    });
+                }
+            )*
+            Ok(())
+        }
+    }
+}
+
+fn build_params_name(container: &ItemStruct) -> Ident {
+    format_ident!("{}BuildParams", container.ident)
+}
+
+/// Generates the struct that holds the build parameters of containers that
+/// record them, and the `build_params` accessor for them.
+fn gen_build_params(container: &ItemStruct, options: &ContainerOptions) -> TokenStream {
+    let vis = &container.vis;
+    let container_name = &container.ident;
+    let build_params_name = build_params_name(container);
+    let generics = &container.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let param_idents = options.params.iter().map(|(ident, _)| ident);
+    let param_types = options.params.iter().map(|(_, ty)| ty);
+    let build_params_doc = format!(
+        "The factory parameters that [`{}`] was built with.",
+        container_name
+    );
+
+    // The parameters of the container are not used by the build
+    // parameters, but are kept so that they have the same generics.
+    let lifetimes = generics.lifetimes().map(|def| &def.lifetime);
+    let type_params = generics.type_params().map(|param| &param.ident);
+    let phantom_type = quote! {
+        ::std::marker::PhantomData<(#( &#lifetimes (), )* fn() -> (#( #type_params, )*))>
+    };
+
+    quote! {
+        #[doc = #build_params_doc]
+        #[derive(Clone, Debug)]
+        #vis struct #build_params_name #generics #where_clause {
+            #(
+                #[allow(missing_docs)]
+                #vis #param_idents: #param_types,
+            )*
+            __facet_params: #phantom_type,
+        }
+
+        impl #impl_generics #container_name #ty_generics #where_clause {
+            /// The factory parameters that this container was built with.
+            #vis fn build_params(&self) -> &#build_params_name #ty_generics {
+                &self.__facet_build_params
+            }
+        }
+    }
+}
+
+/// Generates the initializer of the build parameters field of containers
+/// that record them, which copies the parameters from `params`.
+fn gen_build_params_field(container: &ItemStruct, options: &ContainerOptions) -> TokenStream {
+    if !options.record_params {
+        return quote!();
+    }
+    let build_params_name = build_params_name(container);
+    let param_idents = options.params.iter().map(|(ident, _)| ident);
+    quote! {
+        __facet_build_params: #build_params_name {
+            #( #param_idents: ::std::clone::Clone::clone(params.#param_idents), )*
+            __facet_params: ::std::marker::PhantomData,
+        },
+    }
+}
+
+/// Generates the initializer of the usage field of containers that track
+/// usage, which starts with none of the facets used.
+fn gen_usage_field(
+    facet_crate: &Ident,
+    members: &ContainerMembers,
+    options: &ContainerOptions,
+) -> TokenStream {
+    if !options.track_usage {
+        return quote!();
+    }
+    let tracked_facet_idents = members.tracked_facet_idents();
+    quote! {
+        __facet_usage: ::#facet_crate::FacetUsage::new(&[
+            #( stringify!(#tracked_facet_idents), )*
+        ]),
     }
-    .into()
 }
 
-fn gen_container(mut container: ItemStruct) -> Result<TokenStream, Error> {
-    let facet_crate = format_ident!("{}", facet_crate_name());
-    let members = ContainerMembers::extract(&mut container)?;
+fn gen_container_usage_impl(facet_crate: &Ident, container: &ItemStruct) -> TokenStream {
     let container_name = &container.ident;
-
-    let attr_impls = gen_attr_impls(&facet_crate, container_name, &members);
-    let buildable_impl = gen_buildable_impl(&facet_crate, container_name, &members);
-    let async_buildable_impl = gen_async_buildable_impl(&facet_crate, container_name, &members);
-
-    Ok(quote! {
-        #container
-
-        #( #attr_impls )*
-
-        #buildable_impl
-
-        #async_buildable_impl
-    })
+    let (impl_generics, ty_generics, where_clause) = container.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::#facet_crate::ContainerUsage
+            for #container_name #ty_generics #where_clause
+        {
+            fn facet_usage(&self) -> &::#facet_crate::FacetUsage {
+                &self.__facet_usage
+            }
+        }
+    }
 }
 
 fn gen_buildable_impl(
     facet_crate: &Ident,
-    container_name: &Ident,
+    container: &ItemStruct,
     members: &ContainerMembers,
+    options: &ContainerOptions,
 ) -> TokenStream {
+    let ptr = facet_ptr(options.local);
+    let builder_bound = if options.local {
+        quote!(::std::marker::Sized)
+    } else {
+        quote!(::std::marker::Send + ::std::marker::Sync)
+    };
     let facet_idents = &members.facet_idents;
     let facet_types = &members.facet_types;
     let field_idents = &members.field_idents;
     let field_inits = &members.field_inits;
+    let lazy_facet_idents = &members.lazy_facet_idents;
+    let lazy_facet_types = &members.lazy_facet_types;
+    let weak_facet_idents = &members.weak_facet_idents;
+    let weak_facet_types = &members.weak_facet_types;
+    let swappable_facet_idents = &members.swappable_facet_idents;
+    let swappable_facet_types = &members.swappable_facet_types;
+    let boxed_facet_idents = &members.boxed_facet_idents;
+    let boxed_facet_types = &members.boxed_facet_types;
+    let mutable_facet_idents = &members.mutable_facet_idents;
+    let mutable_facet_types = &members.mutable_facet_types;
+    let keyed_facet_idents = &members.keyed_facet_idents;
+    let keyed_facet_types = &members.keyed_facet_types;
+    let keyed_facet_keys = &members.keyed_facet_keys;
     let delegate_idents = &members.delegate_idents;
     let delegate_types = &members.delegate_types;
+    let (build_order_bound, build_order_field) = gen_build_order(facet_crate, members);
+    let (params_bound, params_binding) = gen_params(facet_crate, options, false);
+    let usage_field = gen_usage_field(facet_crate, members, options);
+    let build_params_field = gen_build_params_field(container, options);
+    let arc = quote!(::std::sync::Arc);
+    let builder_bounds = [
+        builder_bounds(facet_crate, quote!(Builder), &ptr, facet_types),
+        // Lazy facets can only be built by async factories, so synchronous
+        // factories can't build containers that have them.
+        builder_bounds(
+            facet_crate,
+            quote!(AsyncLazyBuilderFor),
+            &arc,
+            lazy_facet_types,
+        ),
+        builder_bounds(facet_crate, quote!(Builder), &arc, weak_facet_types),
+        builder_bounds(facet_crate, quote!(Builder), &arc, swappable_facet_types),
+        builder_bounds(
+            facet_crate,
+            quote!(Builder),
+            &quote!(::std::boxed::Box),
+            boxed_facet_types,
+        ),
+        builder_bounds(
+            facet_crate,
+            quote!(Builder),
+            &quote!(::std::boxed::Box),
+            mutable_facet_types,
+        ),
+        keyed_builder_bounds(facet_crate, quote!(Builder), members),
+    ]
+    .concat();
+    let container_name = &container.ident;
+    let generics = extend_generics(&container.generics, quote!(B));
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, ty_generics, _) = container.generics.split_for_impl();
+    let where_predicates = where_predicates(&container.generics);
 
     quote! {
-        impl<B> ::#facet_crate::Buildable<B> for #container_name
-        where B: ::std::marker::Send + ::std::marker::Sync
-            #( + ::#facet_crate::Builder<::std::sync::Arc<#facet_types>> )*,
+        impl #impl_generics ::#facet_crate::Buildable<B> for #container_name #ty_generics
+        where B: #builder_bound
+            #( #builder_bounds )*
+            #build_order_bound
+            #params_bound,
             #( #delegate_types: ::#facet_crate::Buildable<B>, )*
+            #( #where_predicates, )*
         {
            fn build(builder: &mut B) -> ::std::result::Result<Self, ::#facet_crate::FactoryError> {
 
@@ -193,12 +1723,67 @@ fn gen_buildable_impl(
                 // Build each facet.
                 #(
                     let #facet_idents =
+                        <B as ::#facet_crate::Builder<#ptr<#facet_types>>>::build(builder)?;
+                )*
+
+                // Build each keyed facet.
+                #(
+                    let #keyed_facet_idents = ::#facet_crate::Keyed::into_inner(
                         <B as ::#facet_crate::Builder<
-                            ::std::sync::Arc<#facet_types>
+                            ::#facet_crate::Keyed<
+                                #keyed_facet_keys,
+                                ::std::sync::Arc<#keyed_facet_types>,
+                            >
+                        >>::build(builder)?
+                    );
+                )*
+
+                // Get lazy facets, which will be built on first access.
+                #(
+                    let #lazy_facet_idents =
+                        <B as ::#facet_crate::AsyncLazyBuilderFor<
+                            ::std::sync::Arc<#lazy_facet_types>
+                        >>::get_lazy(builder);
+                )*
+
+                // Build each weakly-held facet and downgrade it.
+                #(
+                    let #weak_facet_idents = ::#facet_crate::WeakFacet::from(
+                        &<B as ::#facet_crate::Builder<
+                            ::std::sync::Arc<#weak_facet_types>
+                        >>::build(builder)?
+                    );
+                )*
+
+                // Build each swappable facet.
+                #(
+                    let #swappable_facet_idents = ::#facet_crate::SwappableFacet::new(
+                        <B as ::#facet_crate::Builder<
+                            ::std::sync::Arc<#swappable_facet_types>
+                        >>::build(builder)?
+                    );
+                )*
+
+                // Build each boxed facet, which is owned by this container.
+                #(
+                    let #boxed_facet_idents =
+                        <B as ::#facet_crate::Builder<
+                            ::std::boxed::Box<#boxed_facet_types>
                         >>::build(builder)?;
                 )*
 
+                // Build each mutable facet, which is owned by this container
+                // and held behind a lock.
+                #(
+                    let #mutable_facet_idents = ::std::sync::Arc::new(::std::sync::RwLock::new(
+                        <B as ::#facet_crate::Builder<
+                            ::std::boxed::Box<#mutable_facet_types>
+                        >>::build(builder)?
+                    ));
+                )*
+
                 // Initialize the other fields.
+                #params_binding
                 #(
                     let #field_idents = #field_inits;
                 )*
@@ -207,31 +1792,184 @@ fn gen_buildable_impl(
                     #( #delegate_idents, )*
                     #( #field_idents, )*
                     #( #facet_idents, )*
+                    #( #keyed_facet_idents, )*
+                    #( #lazy_facet_idents, )*
+                    #( #weak_facet_idents, )*
+                    #( #swappable_facet_idents, )*
+                    #( #boxed_facet_idents, )*
+                    #( #mutable_facet_idents, )*
+                    #build_order_field
+                    #usage_field
+                    #build_params_field
                 })
            }
         }
     }
 }
 
+/// Returns the bounds on the builder for facets of the given types, using
+/// the spans of the types of the container's fields, so that errors for
+/// facets the factory can't build point at the fields that need them.
+fn builder_bounds(
+    facet_crate: &Ident,
+    builder_trait: TokenStream,
+    ptr: &TokenStream,
+    facet_types: &[Type],
+) -> Vec<TokenStream> {
+    facet_types
+        .iter()
+        .map(|ty| quote_spanned!(ty.span()=> + ::#facet_crate::#builder_trait<#ptr<#ty>>))
+        .collect()
+}
+
+/// Returns the bounds on the builder for the keyed facets of the container,
+/// using the spans of the types of the fields as with `builder_bounds`.
+fn keyed_builder_bounds(
+    facet_crate: &Ident,
+    builder_trait: TokenStream,
+    members: &ContainerMembers,
+) -> Vec<TokenStream> {
+    members
+        .keyed_facet_types
+        .iter()
+        .zip(&members.keyed_facet_keys)
+        .map(|(ty, key)| {
+            quote_spanned! {ty.span()=>
+                + ::#facet_crate::#builder_trait<
+                    ::#facet_crate::Keyed<#key, ::std::sync::Arc<#ty>>
+                >
+            }
+        })
+        .collect()
+}
+
+/// Generates the `ParallelBuildable` implementation for the container, which
+/// marks the facets it needs so that synchronous factories can build them in
+/// parallel before the container itself is built.
+fn gen_parallel_buildable_impl(
+    facet_crate: &Ident,
+    container: &ItemStruct,
+    members: &ContainerMembers,
+) -> TokenStream {
+    let needed_types = members
+        .facet_types
+        .iter()
+        .chain(&members.lazy_facet_types)
+        .chain(&members.weak_facet_types)
+        .chain(&members.swappable_facet_types)
+        .map(|ty| quote!(::std::sync::Arc<#ty>))
+        .chain(
+            members
+                .boxed_facet_types
+                .iter()
+                .chain(&members.mutable_facet_types)
+                .map(|ty| quote!(::std::boxed::Box<#ty>)),
+        )
+        .chain(
+            members
+                .keyed_facet_keys
+                .iter()
+                .zip(&members.keyed_facet_types)
+                .map(|(key, ty)| quote!(::#facet_crate::Keyed<#key, ::std::sync::Arc<#ty>>)),
+        )
+        .collect::<Vec<_>>();
+    let delegate_types = &members.delegate_types;
+    let container_name = &container.ident;
+    let generics = extend_generics(&container.generics, quote!(N));
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, ty_generics, _) = container.generics.split_for_impl();
+    let where_predicates = where_predicates(&container.generics);
+
+    quote! {
+        impl #impl_generics ::#facet_crate::ParallelBuildable<N> for #container_name #ty_generics
+        where
+            #( N: ::#facet_crate::NeedFacet<#needed_types>, )*
+            #( #delegate_types: ::#facet_crate::ParallelBuildable<N>, )*
+            #( #where_predicates, )*
+        {
+            fn mark_needed(needed: &mut N) {
+                #(
+                    <N as ::#facet_crate::NeedFacet<#needed_types>>::need(needed);
+                )*
+                #(
+                    <#delegate_types as ::#facet_crate::ParallelBuildable<N>>
+                        ::mark_needed(needed);
+                )*
+            }
+        }
+    }
+}
+
 fn gen_async_buildable_impl(
     facet_crate: &Ident,
-    container_name: &Ident,
+    container: &ItemStruct,
     members: &ContainerMembers,
+    options: &ContainerOptions,
 ) -> TokenStream {
     let facet_idents = &members.facet_idents;
     let facet_types = &members.facet_types;
     let field_idents = &members.field_idents;
-    let field_inits = &members.field_inits;
+    let field_inits = members.field_inits.iter().map(|init| match init {
+        Expr::Async(_) => quote!(#init.await),
+        _ => quote!(#init),
+    });
+    let lazy_facet_idents = &members.lazy_facet_idents;
+    let lazy_facet_types = &members.lazy_facet_types;
+    let weak_facet_idents = &members.weak_facet_idents;
+    let weak_facet_types = &members.weak_facet_types;
+    let swappable_facet_idents = &members.swappable_facet_idents;
+    let swappable_facet_types = &members.swappable_facet_types;
+    let keyed_facet_idents = &members.keyed_facet_idents;
+    let keyed_facet_types = &members.keyed_facet_types;
+    let keyed_facet_keys = &members.keyed_facet_keys;
     let delegate_idents = &members.delegate_idents;
     let delegate_types = &members.delegate_types;
+    let (build_order_bound, build_order_field) = gen_build_order(facet_crate, members);
+    let (params_bound, params_binding) = gen_params(facet_crate, options, true);
+    let param_types = options.params.iter().map(|(_, ty)| ty);
+    let check_params = gen_check_params(facet_crate, members, options);
+    let usage_field = gen_usage_field(facet_crate, members, options);
+    let build_params_field = gen_build_params_field(container, options);
+    let arc = quote!(::std::sync::Arc);
+    let builder_bounds = [
+        builder_bounds(facet_crate, quote!(AsyncBuilderFor), &arc, facet_types),
+        builder_bounds(facet_crate, quote!(AsyncBuilderFor), &arc, weak_facet_types),
+        builder_bounds(
+            facet_crate,
+            quote!(AsyncBuilderFor),
+            &arc,
+            swappable_facet_types,
+        ),
+        keyed_builder_bounds(facet_crate, quote!(AsyncBuilderFor), members),
+        builder_bounds(
+            facet_crate,
+            quote!(AsyncLazyBuilderFor),
+            &arc,
+            lazy_facet_types,
+        ),
+    ]
+    .concat();
+
+    let container_name = &container.ident;
+    let generics = extend_generics(&container.generics, quote!('builder, B));
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, ty_generics, _) = container.generics.split_for_impl();
+    let where_predicates = where_predicates(&container.generics);
 
     // Desugared async-trait so that the builder lifetime can be specified.
     quote! {
-        impl<'builder, B> ::#facet_crate::AsyncBuildable<'builder, B> for #container_name
+        impl #impl_generics ::#facet_crate::AsyncBuildable<'builder, B>
+            for #container_name #ty_generics
         where B: ::std::marker::Send + ::std::marker::Sync + ::#facet_crate::AsyncBuilder
-            #( + ::#facet_crate::AsyncBuilderFor<::std::sync::Arc<#facet_types>> )*
+            #( #builder_bounds )*
+            #build_order_bound
+            #params_bound
             + 'builder,
             #( #delegate_types: ::#facet_crate::AsyncBuildable<'builder, B>, )*
+            // Parameters are borrowed from the builder across initializers
+            // that are awaited.
+            #( #param_types: ::std::marker::Sync, )*
+            #( #where_predicates, )*
         {
             fn build_async(mut builder: B) -> ::std::pin::Pin<::std::boxed::Box<
                 dyn std::future::Future<
@@ -242,12 +1980,14 @@ fn gen_async_buildable_impl(
                 let build = async move {
                     // Mark needed facets as needed.
                     Self::mark_needed(&mut builder);
+                    Self::check_params(&builder)?;
 
                     // Build the needed facets.
                     <B as ::#facet_crate::AsyncBuilder>::build_needed(&mut builder).await?;
+                    <B as ::#facet_crate::AsyncBuilder>::post_build(&mut builder).await?;
 
                     // Build ourself.
-                    Ok(Self::construct(&builder))
+                    Ok(Self::construct(&builder).await)
 
                 };
                 ::std::boxed::Box::pin(build)
@@ -260,6 +2000,24 @@ fn gen_async_buildable_impl(
                         ::std::sync::Arc<#facet_types>
                     >>::need(builder);
                 )*
+                #(
+                    <B as ::#facet_crate::AsyncBuilderFor<
+                        ::std::sync::Arc<#weak_facet_types>
+                    >>::need(builder);
+                )*
+                #(
+                    <B as ::#facet_crate::AsyncBuilderFor<
+                        ::std::sync::Arc<#swappable_facet_types>
+                    >>::need(builder);
+                )*
+                #(
+                    <B as ::#facet_crate::AsyncBuilderFor<
+                        ::#facet_crate::Keyed<
+                            #keyed_facet_keys,
+                            ::std::sync::Arc<#keyed_facet_types>,
+                        >
+                    >>::need(builder);
+                )*
 
                 // Mark facets our delegates need as needed.
                 #(
@@ -268,12 +2026,21 @@ fn gen_async_buildable_impl(
                 )*
            }
 
-            fn construct(builder: &B) -> Self {
+           #check_params
+
+            fn construct<'construct>(builder: &'construct B) -> ::std::pin::Pin<::std::boxed::Box<
+                dyn std::future::Future<Output = Self> + ::std::marker::Send + 'construct
+            >>
+            where
+                'builder: 'construct,
+                Self: 'construct,
+            {
+                ::std::boxed::Box::pin(async move {
                 // Build delegates.
                 #(
                     let #delegate_idents =
                         <#delegate_types as ::#facet_crate::AsyncBuildable<'builder, B>>
-                            ::construct(builder);
+                            ::construct(builder).await;
                 )*
 
                 // Get the facets out of the builder.
@@ -283,8 +2050,45 @@ fn gen_async_buildable_impl(
                             ::std::sync::Arc<#facet_types>
                         >>::get(builder);
                 )*
+                #(
+                    let #keyed_facet_idents = ::#facet_crate::Keyed::into_inner(
+                        <B as ::#facet_crate::AsyncBuilderFor<
+                            ::#facet_crate::Keyed<
+                                #keyed_facet_keys,
+                                ::std::sync::Arc<#keyed_facet_types>,
+                            >
+                        >>::get(builder)
+                    );
+                )*
+
+                // Get lazy facets, which will be built on first access.
+                #(
+                    let #lazy_facet_idents =
+                        <B as ::#facet_crate::AsyncLazyBuilderFor<
+                            ::std::sync::Arc<#lazy_facet_types>
+                        >>::get_lazy(builder);
+                )*
+
+                // Get weak references to weakly-held facets.
+                #(
+                    let #weak_facet_idents = ::#facet_crate::WeakFacet::from(
+                        &<B as ::#facet_crate::AsyncBuilderFor<
+                            ::std::sync::Arc<#weak_facet_types>
+                        >>::get(builder)
+                    );
+                )*
+
+                // Get the initial instances of swappable facets.
+                #(
+                    let #swappable_facet_idents = ::#facet_crate::SwappableFacet::new(
+                        <B as ::#facet_crate::AsyncBuilderFor<
+                            ::std::sync::Arc<#swappable_facet_types>
+                        >>::get(builder)
+                    );
+                )*
 
-                // Initialize other fields.
+                // Initialize other fields, awaiting async initializers.
+                #params_binding
                 #(
                     let #field_idents = #field_inits;
                 )*
@@ -293,54 +2097,369 @@ fn gen_async_buildable_impl(
                     #( #delegate_idents, )*
                     #( #field_idents, )*
                     #( #facet_idents, )*
+                    #( #keyed_facet_idents, )*
+                    #( #lazy_facet_idents, )*
+                    #( #weak_facet_idents, )*
+                    #( #swappable_facet_idents, )*
+                    #build_order_field
+                    #usage_field
+                    #build_params_field
                 }
+                })
             }
         }
 
     }
 }
 
+fn gen_accessors(
+    facet_crate: &Ident,
+    container: &ItemStruct,
+    members: &ContainerMembers,
+) -> TokenStream {
+    let container_name = &container.ident;
+    let vis = &container.vis;
+    let lazy_facet_idents = &members.lazy_facet_idents;
+    let lazy_facet_types = &members.lazy_facet_types;
+    let lazy_facet_arc_methods = lazy_facet_idents
+        .iter()
+        .map(|ident| format_ident!("{}_arc", ident))
+        .collect::<Vec<_>>();
+    let weak_facet_idents = &members.weak_facet_idents;
+    let weak_facet_types = &members.weak_facet_types;
+    let swappable_facet_idents = &members.swappable_facet_idents;
+    let swappable_facet_types = &members.swappable_facet_types;
+    let swappable_replace_methods = swappable_facet_idents
+        .iter()
+        .map(|ident| format_ident!("replace_{}", ident))
+        .collect::<Vec<_>>();
+    let mutable_facet_idents = &members.mutable_facet_idents;
+    let mutable_facet_types = &members.mutable_facet_types;
+    let mutable_read_methods = mutable_facet_idents
+        .iter()
+        .map(|ident| format_ident!("{}_read", ident))
+        .collect::<Vec<_>>();
+    let mutable_write_methods = mutable_facet_idents
+        .iter()
+        .map(|ident| format_ident!("{}_write", ident))
+        .collect::<Vec<_>>();
+
+    if lazy_facet_idents.is_empty()
+        && weak_facet_idents.is_empty()
+        && swappable_facet_idents.is_empty()
+        && mutable_facet_idents.is_empty()
+    {
+        return quote!();
+    }
+
+    let (impl_generics, ty_generics, where_clause) = container.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #container_name #ty_generics #where_clause {
+            #(
+                /// Access this lazy facet by reference, building it if this
+                /// is the first access.
+                #vis async fn #lazy_facet_idents(
+                    &self,
+                ) -> ::std::result::Result<&(#lazy_facet_types), ::#facet_crate::FactoryError> {
+                    let facet = self.#lazy_facet_idents.get().await?;
+                    Ok(facet.as_ref())
+                }
+
+                /// Access a cloneable reference to this lazy facet, building
+                /// it if this is the first access.
+                #vis async fn #lazy_facet_arc_methods(
+                    &self,
+                ) -> ::std::result::Result<
+                    ::std::sync::Arc<#lazy_facet_types>,
+                    ::#facet_crate::FactoryError,
+                > {
+                    let facet = self.#lazy_facet_idents.get().await?;
+                    Ok(facet.clone())
+                }
+            )*
+
+            #(
+                /// Attempt to upgrade this weakly-held facet, returning
+                /// `None` if it has been dropped.
+                #vis fn #weak_facet_idents(&self) -> ::std::option::Option<
+                    ::std::sync::Arc<#weak_facet_types>
+                > {
+                    self.#weak_facet_idents.upgrade()
+                }
+            )*
+
+            #(
+                /// Access the current instance of this swappable facet.
+                #vis fn #swappable_facet_idents(&self) -> ::std::sync::Arc<#swappable_facet_types> {
+                    self.#swappable_facet_idents.load()
+                }
+
+                /// Replace this swappable facet with a new instance,
+                /// returning the previous instance.
+                #vis fn #swappable_replace_methods(
+                    &self,
+                    facet: ::std::sync::Arc<#swappable_facet_types>,
+                ) -> ::std::sync::Arc<#swappable_facet_types> {
+                    self.#swappable_facet_idents.replace(facet)
+                }
+            )*
+
+            #(
+                /// Lock this mutable facet for reading, blocking until any
+                /// writer has released it.
+                #vis fn #mutable_read_methods(&self) -> ::std::sync::RwLockReadGuard<
+                    '_,
+                    ::std::boxed::Box<#mutable_facet_types>,
+                > {
+                    self.#mutable_facet_idents.read().expect("lock poisoned")
+                }
+
+                /// Lock this mutable facet for writing, blocking until all
+                /// other readers and writers have released it.  The facet
+                /// can be mutated, or replaced by assigning a new `Box`.
+                #vis fn #mutable_write_methods(&self) -> ::std::sync::RwLockWriteGuard<
+                    '_,
+                    ::std::boxed::Box<#mutable_facet_types>,
+                > {
+                    self.#mutable_facet_idents.write().expect("lock poisoned")
+                }
+            )*
+        }
+    }
+}
+
+fn gen_downcast(
+    facet_crate: &Ident,
+    container: &ItemStruct,
+    members: &ContainerMembers,
+) -> TokenStream {
+    let container_name = &container.ident;
+    let vis = &container.vis;
+    let downcast_facet_idents = &members.downcast_facet_idents;
+
+    if downcast_facet_idents.is_empty() {
+        return quote!();
+    }
+
+    let (impl_generics, ty_generics, where_clause) = container.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #container_name #ty_generics #where_clause {
+            /// Access the concrete implementation of the first downcastable
+            /// facet whose implementation is of type `T`.
+            #vis fn downcast_facet<T: ::std::any::Any>(&self) -> ::std::option::Option<&T> {
+                #(
+                    if let ::std::option::Option::Some(facet) =
+                        ::#facet_crate::AsAny::as_any(&*self.#downcast_facet_idents)
+                            .downcast_ref::<T>()
+                    {
+                        return ::std::option::Option::Some(facet);
+                    }
+                )*
+                ::std::option::Option::None
+            }
+        }
+    }
+}
+
 fn gen_attr_impls(
     facet_crate: &Ident,
-    container_name: &Ident,
+    container: &ItemStruct,
     members: &ContainerMembers,
+    options: &ContainerOptions,
 ) -> Vec<TokenStream> {
     let mut output = Vec::new();
+    // Local containers provide access to `Rc`s of their facets rather than
+    // `Arc`s.
+    let (ptr, facet_ptr_trait, facet_ptr_method) = if options.local {
+        (quote!(::std::rc::Rc), quote!(FacetRc), quote!(facet_rc))
+    } else {
+        (
+            quote!(::std::sync::Arc),
+            quote!(FacetArc),
+            quote!(facet_arc),
+        )
+    };
     let facet_idents = &members.facet_idents;
-    let facet_types = &members.facet_types;
+    let facet_ref_types = &members.facet_ref_types;
     let delegate_idents = &members.delegate_idents;
     let delegate_facets = &members.delegate_facets;
+    let container_name = &container.ident;
+    let (impl_generics, ty_generics, where_clause) = container.generics.split_for_impl();
+
+    // Containers that track usage flag each facet field when it is accessed,
+    // and accesses are recorded for the access report if access tracking is
+    // enabled.
+    let tracked_facet_idents = members.tracked_facet_idents();
+    let access_attr = access_attr();
+    let mark_used = |facet_ident: &Ident| {
+        let record_access = gen_record_access(facet_crate, container_name, facet_ident);
+        match tracked_facet_idents
+            .iter()
+            .position(|ident| *ident == facet_ident)
+        {
+            Some(index) if options.track_usage => {
+                quote!(self.__facet_usage.mark(#index); #record_access)
+            }
+            _ => record_access,
+        }
+    };
+
+    // Facets that are supertraits of other facets are accessed by upcasting
+    // those facets.
+    let facets = facet_idents.iter().zip(facet_ref_types).chain(
+        members
+            .supertrait_facet_idents
+            .iter()
+            .zip(&members.supertrait_types),
+    );
+
+    for (facet_ident, facet_type) in facets {
+        let mark_used = mark_used(facet_ident);
+        output.push(quote! {
+            impl #impl_generics ::#facet_crate::FacetRef<#facet_type>
+                for #container_name #ty_generics #where_clause
+            {
+                #[inline]
+                #access_attr
+                fn facet_ref(&self) -> &(#facet_type)
+                {
+                    #mark_used
+                    self.#facet_ident.as_ref()
+                }
+            }
+
+            impl #impl_generics ::#facet_crate::FacetRef<#facet_type>
+                for &#container_name #ty_generics #where_clause
+            {
+                #[inline]
+                #access_attr
+                fn facet_ref(&self) -> &(#facet_type)
+                {
+                    #mark_used
+                    (*self).#facet_ident.as_ref()
+                }
+            }
+
+            impl #impl_generics ::#facet_crate::#facet_ptr_trait<#facet_type>
+                for #container_name #ty_generics #where_clause
+            {
+                #[inline]
+                #access_attr
+                fn #facet_ptr_method(&self) -> #ptr<#facet_type>
+                {
+                    #mark_used
+                    self.#facet_ident.clone()
+                }
+            }
+
+            impl #impl_generics ::#facet_crate::#facet_ptr_trait<#facet_type>
+                for &#container_name #ty_generics #where_clause
+            {
+                #[inline]
+                #access_attr
+                fn #facet_ptr_method(&self) -> #ptr<#facet_type>
+                {
+                    #mark_used
+                    (*self).#facet_ident.clone()
+                }
+            }
+
+        });
+    }
+
+    // Boxed facets can only be accessed by reference, as the container owns
+    // them.
+    let boxed_facets = members
+        .boxed_facet_idents
+        .iter()
+        .zip(&members.boxed_facet_types);
 
-    for (facet_ident, facet_type) in facet_idents.iter().zip(facet_types) {
+    for (facet_ident, facet_type) in boxed_facets {
+        let mark_used = mark_used(facet_ident);
         output.push(quote! {
-            impl ::#facet_crate::FacetRef<#facet_type> for #container_name {
+            impl #impl_generics ::#facet_crate::FacetRef<#facet_type>
+                for #container_name #ty_generics #where_clause
+            {
                 #[inline]
+                #access_attr
                 fn facet_ref(&self) -> &(#facet_type)
                 {
+                    #mark_used
                     self.#facet_ident.as_ref()
                 }
             }
 
-            impl ::#facet_crate::FacetRef<#facet_type> for &#container_name {
+            impl #impl_generics ::#facet_crate::FacetRef<#facet_type>
+                for &#container_name #ty_generics #where_clause
+            {
                 #[inline]
+                #access_attr
                 fn facet_ref(&self) -> &(#facet_type)
                 {
+                    #mark_used
+                    (*self).#facet_ident.as_ref()
+                }
+            }
+        });
+    }
+
+    // Keyed facets are accessed by their key, as the container may hold other
+    // instances of the same facet.
+    let keyed_facets = members
+        .keyed_facet_idents
+        .iter()
+        .zip(&members.keyed_facet_ref_types)
+        .zip(&members.keyed_facet_keys);
+
+    for ((facet_ident, facet_type), key) in keyed_facets {
+        let mark_used = mark_used(facet_ident);
+        output.push(quote! {
+            impl #impl_generics ::#facet_crate::KeyedFacetRef<#key, #facet_type>
+                for #container_name #ty_generics #where_clause
+            {
+                #[inline]
+                #access_attr
+                fn keyed_facet_ref(&self) -> &(#facet_type)
+                {
+                    #mark_used
+                    self.#facet_ident.as_ref()
+                }
+            }
+
+            impl #impl_generics ::#facet_crate::KeyedFacetRef<#key, #facet_type>
+                for &#container_name #ty_generics #where_clause
+            {
+                #[inline]
+                #access_attr
+                fn keyed_facet_ref(&self) -> &(#facet_type)
+                {
+                    #mark_used
                     (*self).#facet_ident.as_ref()
                 }
             }
 
-            impl ::#facet_crate::FacetArc<#facet_type> for #container_name {
+            impl #impl_generics ::#facet_crate::KeyedFacetArc<#key, #facet_type>
+                for #container_name #ty_generics #where_clause
+            {
                 #[inline]
-                fn facet_arc(&self) -> ::std::sync::Arc<#facet_type>
+                #access_attr
+                fn keyed_facet_arc(&self) -> ::std::sync::Arc<#facet_type>
                 {
+                    #mark_used
                     self.#facet_ident.clone()
                 }
             }
 
-            impl ::#facet_crate::FacetArc<#facet_type> for &#container_name {
+            impl #impl_generics ::#facet_crate::KeyedFacetArc<#key, #facet_type>
+                for &#container_name #ty_generics #where_clause
+            {
                 #[inline]
-                fn facet_arc(&self) -> ::std::sync::Arc<#facet_type>
+                #access_attr
+                fn keyed_facet_arc(&self) -> ::std::sync::Arc<#facet_type>
                 {
+                    #mark_used
                     (*self).#facet_ident.clone()
                 }
             }
@@ -351,31 +2470,47 @@ fn gen_attr_impls(
     for (delegate_ident, delegate_facet) in delegate_idents.iter().zip(delegate_facets) {
         output.push(quote! {
             #(
-                impl ::#facet_crate::FacetRef<#delegate_facet> for #container_name {
+                impl #impl_generics ::#facet_crate::FacetRef<#delegate_facet>
+                    for #container_name #ty_generics #where_clause
+                {
                     #[inline]
+                    #access_attr
                     fn facet_ref(&self) -> &(#delegate_facet) {
                         self.#delegate_ident.facet_ref()
                     }
                 }
 
-                impl ::#facet_crate::FacetRef<#delegate_facet> for &#container_name {
+                impl #impl_generics ::#facet_crate::FacetRef<#delegate_facet>
+                    for &#container_name #ty_generics #where_clause
+                {
                     #[inline]
+                    #access_attr
                     fn facet_ref(&self) -> &(#delegate_facet) {
                         self.#delegate_ident.facet_ref()
                     }
                 }
 
-                impl ::#facet_crate::FacetArc<#delegate_facet> for #container_name {
+                impl #impl_generics ::#facet_crate::#facet_ptr_trait<#delegate_facet>
+                    for #container_name #ty_generics #where_clause
+                {
                     #[inline]
-                    fn facet_arc(&self) -> ::std::sync::Arc<#delegate_facet> {
-                        self.#delegate_ident.facet_arc()
+                    #access_attr
+                    fn #facet_ptr_method(&self) -> #ptr<#delegate_facet> {
+                        ::#facet_crate::#facet_ptr_trait::<#delegate_facet>::#facet_ptr_method(
+                            &self.#delegate_ident
+                        )
                     }
                 }
 
-                impl ::#facet_crate::FacetArc<#delegate_facet> for &#container_name {
+                impl #impl_generics ::#facet_crate::#facet_ptr_trait<#delegate_facet>
+                    for &#container_name #ty_generics #where_clause
+                {
                     #[inline]
-                    fn facet_arc(&self) -> ::std::sync::Arc<#delegate_facet> {
-                        self.#delegate_ident.facet_arc()
+                    #access_attr
+                    fn #facet_ptr_method(&self) -> #ptr<#delegate_facet> {
+                        ::#facet_crate::#facet_ptr_trait::<#delegate_facet>::#facet_ptr_method(
+                            &self.#delegate_ident
+                        )
                     }
                 }
             )*
@@ -385,16 +2520,85 @@ fn gen_attr_impls(
     output
 }
 
-fn extract_delegate_facets(attr: &Attribute) -> Result<Vec<Type>, Error> {
+/// Record an access of a facet field of a container for the access report,
+/// if the `access_tracking` feature is enabled.
+#[cfg(feature = "access_tracking")]
+fn gen_record_access(
+    facet_crate: &Ident,
+    container_name: &Ident,
+    facet_ident: &Ident,
+) -> TokenStream {
+    quote! {
+        ::#facet_crate::record_access(stringify!(#container_name), stringify!(#facet_ident));
+    }
+}
+
+#[cfg(not(feature = "access_tracking"))]
+fn gen_record_access(
+    _facet_crate: &Ident,
+    _container_name: &Ident,
+    _facet_ident: &Ident,
+) -> TokenStream {
+    quote!()
+}
+
+fn extract_delegate_facets(attr: &Attribute, local: bool) -> Result<Vec<Type>, Error> {
     let mut facets = Vec::new();
+    if attr.tokens.is_empty() {
+        // A nested container that doesn't delegate any facets.
+        return Ok(facets);
+    }
     let args: Punctuated<Type, Token![,]> = attr.parse_args_with(Punctuated::parse_terminated)?;
     for mut arg in args {
         if let Type::TraitObject(obj) = &mut arg {
-            obj.bounds.push(syn::parse2(quote!(::std::marker::Send))?);
-            obj.bounds.push(syn::parse2(quote!(::std::marker::Sync))?);
-            obj.bounds.push(syn::parse2(quote!('static))?);
+            add_trait_object_bounds(obj, local)?;
         }
         facets.push(arg);
     }
     Ok(facets)
 }
+
+/// Adds the bounds that facet trait objects have.  Facets of local
+/// containers don't need to be `Send` or `Sync`.
+fn add_trait_object_bounds(obj: &mut TypeTraitObject, local: bool) -> Result<(), Error> {
+    if !local {
+        obj.bounds.push(syn::parse2(quote!(::std::marker::Send))?);
+        obj.bounds.push(syn::parse2(quote!(::std::marker::Sync))?);
+    }
+    obj.bounds.push(syn::parse2(quote!('static))?);
+    Ok(())
+}
+
+/// Returns the smart pointer that facets are held in.
+fn facet_ptr(local: bool) -> TokenStream {
+    if local {
+        quote!(::std::rc::Rc)
+    } else {
+        quote!(::std::sync::Arc)
+    }
+}
+
+/// Returns the container's generics with additional parameters, for impls
+/// that are generic over more than the container.
+fn extend_generics(generics: &Generics, extra: TokenStream) -> Generics {
+    let extra: Punctuated<GenericParam, Token![,]> = Punctuated::parse_terminated
+        .parse2(extra)
+        .expect("invalid generic parameters");
+    // Lifetimes must come before other generic parameters.
+    let (lifetimes, others): (Vec<_>, Vec<_>) = extra
+        .into_iter()
+        .chain(generics.params.iter().cloned())
+        .partition(|param| matches!(param, GenericParam::Lifetime(_)));
+    let mut extended = generics.clone();
+    extended.params = lifetimes.into_iter().chain(others).collect();
+    extended
+}
+
+/// Returns the where clause predicates of the container's generics, for
+/// impls that add predicates of their own.
+fn where_predicates(generics: &Generics) -> Vec<&WherePredicate> {
+    match &generics.where_clause {
+        Some(where_clause) => where_clause.predicates.iter().collect(),
+        None => Vec::new(),
+    }
+}
@@ -0,0 +1,136 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Error, Fields, GenericParam, ItemStruct, Member};
+
+use crate::facet_crate_name;
+use crate::util::access_attr;
+
+pub fn delegate(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let _ = parse_macro_input!(attr as syn::parse::Nothing);
+    let wrapper = parse_macro_input!(item as ItemStruct);
+
+    match gen_delegate(wrapper) {
+        Ok(output) => output,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
+
+fn gen_delegate(mut wrapper: ItemStruct) -> Result<TokenStream, Error> {
+    let facet_crate = format_ident!("{}", facet_crate_name());
+
+    // Find the inner container.  Single-field tuple structs delegate to that
+    // field, otherwise the field must be marked with `#[delegate]`.
+    let is_newtype =
+        matches!(&wrapper.fields, Fields::Unnamed(fields) if fields.unnamed.len() == 1);
+    let mut inner = None;
+    for (index, field) in wrapper.fields.iter_mut().enumerate() {
+        let attrs_len = field.attrs.len();
+        field.attrs.retain(|attr| !attr.path.is_ident("delegate"));
+        if field.attrs.len() != attrs_len || is_newtype {
+            if inner.is_some() {
+                return Err(Error::new(
+                    field.span(),
+                    "facet::delegate struct must have exactly one '#[delegate]' field",
+                ));
+            }
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(index.into()),
+            };
+            inner = Some((member, field.ty.clone()));
+        }
+    }
+    let (inner_member, inner_ty) = inner.ok_or_else(|| {
+        Error::new(
+            wrapper.span(),
+            concat!(
+                "facet::delegate struct must be a single-field tuple struct ",
+                "or have a field marked with '#[delegate]'",
+            ),
+        )
+    })?;
+
+    let wrapper_name = &wrapper.ident;
+    let (_, ty_generics, _) = wrapper.generics.split_for_impl();
+
+    let mut ref_generics = wrapper.generics.clone();
+    ref_generics
+        .params
+        .push(GenericParam::Type(syn::parse2(quote! {
+            __Facet: ?::std::marker::Sized + ::std::marker::Send + ::std::marker::Sync + 'static
+        })?));
+    let mut arc_generics = ref_generics.clone();
+    ref_generics
+        .make_where_clause()
+        .predicates
+        .push(syn::parse2(
+            quote!(#inner_ty: ::#facet_crate::FacetRef<__Facet>),
+        )?);
+    arc_generics
+        .make_where_clause()
+        .predicates
+        .push(syn::parse2(
+            quote!(#inner_ty: ::#facet_crate::FacetArc<__Facet>),
+        )?);
+    let (ref_impl_generics, _, ref_where_clause) = ref_generics.split_for_impl();
+    let (arc_impl_generics, _, arc_where_clause) = arc_generics.split_for_impl();
+    let access_attr = access_attr();
+
+    Ok(quote! {
+        #wrapper
+
+        impl #ref_impl_generics ::#facet_crate::FacetRef<__Facet>
+            for #wrapper_name #ty_generics #ref_where_clause
+        {
+            #[inline]
+            #access_attr
+            fn facet_ref(&self) -> &__Facet {
+                ::#facet_crate::FacetRef::<__Facet>::facet_ref(&self.#inner_member)
+            }
+        }
+
+        impl #ref_impl_generics ::#facet_crate::FacetRef<__Facet>
+            for &#wrapper_name #ty_generics #ref_where_clause
+        {
+            #[inline]
+            #access_attr
+            fn facet_ref(&self) -> &__Facet {
+                ::#facet_crate::FacetRef::<__Facet>::facet_ref(&(*self).#inner_member)
+            }
+        }
+
+        impl #arc_impl_generics ::#facet_crate::FacetArc<__Facet>
+            for #wrapper_name #ty_generics #arc_where_clause
+        {
+            #[inline]
+            #access_attr
+            fn facet_arc(&self) -> ::std::sync::Arc<__Facet> {
+                ::#facet_crate::FacetArc::<__Facet>::facet_arc(&self.#inner_member)
+            }
+        }
+
+        impl #arc_impl_generics ::#facet_crate::FacetArc<__Facet>
+            for &#wrapper_name #ty_generics #arc_where_clause
+        {
+            #[inline]
+            #access_attr
+            fn facet_arc(&self) -> ::std::sync::Arc<__Facet> {
+                ::#facet_crate::FacetArc::<__Facet>::facet_arc(&(*self).#inner_member)
+            }
+        }
+    })
+}
@@ -7,106 +7,839 @@
  * of this source tree.
  */
 
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
 use syn::spanned::Spanned;
-use syn::{parse_macro_input, Error, Item};
+use syn::visit_mut::VisitMut;
+use syn::{
+    parse_macro_input, Error, FnArg, GenericParam, Generics, Ident, Item, ItemTrait, Lifetime,
+    Meta, ParenthesizedGenericArguments, Pat, ReturnType, Token, TraitItem, TraitItemMethod, Type,
+    TypeBareFn, TypeReference,
+};
 
 use crate::facet_crate_name;
+use crate::util::{access_attr, parse_facet_name, snakify_pascal_case};
+
+/// Options for a facet, given as `#[facet::facet(option, ...)]`.
+#[derive(Debug, Default)]
+struct FacetAttr {
+    /// The snake case name of the facet, if it is different from the name
+    /// derived from the name of the type.
+    name: Option<Ident>,
+
+    /// The facet is for local containers, which hold it in an `Rc` and don't
+    /// require it to be `Send` or `Sync`.
+    local: bool,
+
+    /// The facet trait has `AsAny` as a supertrait, so that trait objects
+    /// can be downcast to their concrete type.
+    downcast: bool,
+
+    /// The facet is only held in a `Box` by the containers that own it, so
+    /// it can be accessed by reference but not shared.
+    boxed: bool,
+
+    /// The facet trait has `DynClone` as a supertrait, so that facets can
+    /// be cloned into new boxes.
+    clone: bool,
+
+    /// The facet trait is implemented for references, boxes and `Arc`s of
+    /// its implementations, so that wrappers holding another implementation
+    /// don't need to forward each method by hand.
+    forward: bool,
+
+    /// The implementation used by factories that list the facet in their
+    /// `defaults`, given as `default = Type`.  It is constructed with
+    /// `Default::default()`.
+    default: Option<Type>,
+}
+
+impl Parse for FacetAttr {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let mut attr = FacetAttr::default();
+        while !input.is_empty() {
+            let fork = input.fork();
+            if fork.parse::<Ident>().is_ok_and(|ident| ident == "default") && fork.peek(Token![=]) {
+                input.parse::<Ident>()?;
+                input.parse::<Token![=]>()?;
+                attr.default = Some(input.parse()?);
+                if !input.is_empty() {
+                    input.parse::<Token![,]>()?;
+                }
+                continue;
+            }
+            let arg = input.parse::<Meta>()?;
+            match &arg {
+                Meta::NameValue(name_value) if name_value.path.is_ident("name") => {
+                    attr.name = Some(parse_facet_name(&name_value.lit)?);
+                }
+                Meta::Path(path) if path.is_ident("local") => attr.local = true,
+                Meta::Path(path) if path.is_ident("downcast") => attr.downcast = true,
+                Meta::Path(path) if path.is_ident("boxed") => attr.boxed = true,
+                Meta::Path(path) if path.is_ident("clone") => attr.clone = true,
+                Meta::Path(path) if path.is_ident("forward") => attr.forward = true,
+                _ => return Err(Error::new(arg.span(), "unrecognised facet option")),
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(attr)
+    }
+}
 
 pub fn facet(
     attr: proc_macro::TokenStream,
     item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    let _ = parse_macro_input!(attr as syn::parse::Nothing);
+    let attr = parse_macro_input!(attr as FacetAttr);
     let facet = parse_macro_input!(item as Item);
 
-    match gen_attribute(facet) {
+    match gen_attribute(facet, attr) {
         Ok(output) => output,
         Err(e) => e.to_compile_error(),
     }
     .into()
 }
 
-fn gen_attribute(facet: Item) -> Result<TokenStream, Error> {
+fn gen_attribute(mut facet: Item, attr: FacetAttr) -> Result<TokenStream, Error> {
+    let facet_crate = format_ident!("{}", facet_crate_name());
+
+    match &mut facet {
+        Item::Trait(facet) => {
+            if attr.forward && (attr.downcast || attr.clone) {
+                return Err(Error::new(
+                    facet.span(),
+                    "facet::facet 'forward' cannot be used with 'downcast' or 'clone', as references and `Arc`s can't be downcast or cloned into boxes",
+                ));
+            }
+            if attr.downcast {
+                facet
+                    .supertraits
+                    .push(syn::parse2(quote!(::#facet_crate::AsAny))?);
+            }
+            if attr.clone {
+                facet
+                    .supertraits
+                    .push(syn::parse2(quote!(::#facet_crate::dyn_clone::DynClone))?);
+            }
+        }
+        _ if attr.downcast => {
+            return Err(Error::new(
+                facet.span(),
+                "facet::facet 'downcast' is only supported for traits",
+            ));
+        }
+        _ if attr.clone => {
+            return Err(Error::new(
+                facet.span(),
+                "facet::facet 'clone' is only supported for traits, as other facets can implement `Clone`",
+            ));
+        }
+        _ if attr.forward => {
+            return Err(Error::new(
+                facet.span(),
+                "facet::facet 'forward' is only supported for traits",
+            ));
+        }
+        _ => {}
+    }
+
+    // Traits with native async methods aren't dyn-compatible, so containers
+    // hold them through a generated dyn-compatible version of the trait.
+    let dyn_trait = match &mut facet {
+        Item::Trait(facet) if has_native_async(facet) => Some(gen_dyn_trait(facet, attr.local)?),
+        _ => None,
+    };
+
+    let forward_impls = match &facet {
+        Item::Trait(facet) if attr.forward => gen_forward_impls(facet, dyn_trait.is_some())?,
+        _ => quote!(),
+    };
+
     let vis;
     let name;
+    let mut generics;
     let facet_ty;
 
     match &facet {
         Item::Trait(facet) => {
             vis = &facet.vis;
             name = &facet.ident;
-            facet_ty = quote!(dyn #name + ::std::marker::Send + ::std::marker::Sync + 'static);
+            generics = facet_generics(&facet.generics)?;
+            let (_, ty_generics, _) = generics.split_for_impl();
+            let dyn_name = match &dyn_trait {
+                Some(_) => format_ident!("Dyn{}", name),
+                None => name.clone(),
+            };
+            facet_ty = if attr.local {
+                quote!(dyn #dyn_name #ty_generics + 'static)
+            } else {
+                quote! {
+                    dyn #dyn_name #ty_generics
+                        + ::std::marker::Send + ::std::marker::Sync + 'static
+                }
+            };
         }
         Item::Struct(facet) => {
             vis = &facet.vis;
             name = &facet.ident;
-            facet_ty = quote!(#name);
+            generics = facet_generics(&facet.generics)?;
+            facet_ty = generic_type_facet(name, &mut generics, attr.local)?;
         }
         Item::Enum(facet) => {
             vis = &facet.vis;
             name = &facet.ident;
-            facet_ty = quote!(#name);
+            generics = facet_generics(&facet.generics)?;
+            facet_ty = generic_type_facet(name, &mut generics, attr.local)?;
         }
         _ => return Err(Error::new(facet.span(), "expected trait, struct or enum")),
     }
 
-    let facet_crate = format_ident!("{}", facet_crate_name());
-    let snake_name = snakify_pascal_case(name.to_string());
+    let snake_name = match &attr.name {
+        Some(name) => name.clone(),
+        None => format_ident!(
+            "{}",
+            snakify_pascal_case(name.to_string()),
+            span = name.span()
+        ),
+    };
     let trait_ref_name = format_ident!("{}Ref", name);
-    let trait_ref_method = format_ident!("{}", snake_name, span = name.span());
+    let trait_ref_method = snake_name.clone();
     let trait_arc_name = format_ident!("{}Arc", name);
-    let trait_arc_method = format_ident!("{}_arc", snake_name, span = name.span());
+    let trait_arc_method = format_ident!("{}_arc", snake_name);
     let arc_trait_name = format_ident!("Arc{}", name);
+    let weak_trait_name = format_ident!("Weak{}", name);
+
+    // Generic facets have generic traits and aliases, which are used with
+    // the same arguments as the facet.  The container type parameter of the
+    // blanket implementations is renamed so that it can't clash with the
+    // facet's own parameters.
+    let (decl_generics, args, where_clause) = generics.split_for_impl();
+    let alias_generics = alias_generics(&generics);
+    let container = if generics.params.is_empty() {
+        format_ident!("T")
+    } else {
+        format_ident!("__FacetContainer")
+    };
+    let ref_impl_generics = extend_generics(
+        &generics,
+        quote!(#container: ::#facet_crate::FacetRef<#facet_ty>),
+    );
+    let (ref_impl_generics, _, _) = ref_impl_generics.split_for_impl();
+    let rc_impl_generics = extend_generics(
+        &generics,
+        quote!(#container: ::#facet_crate::FacetRc<#facet_ty> + ::#facet_crate::FacetRef<#facet_ty>),
+    );
+    let (rc_impl_generics, _, _) = rc_impl_generics.split_for_impl();
+    let arc_impl_generics = extend_generics(
+        &generics,
+        quote!(#container: ::#facet_crate::FacetArc<#facet_ty> + ::#facet_crate::FacetRef<#facet_ty>),
+    );
+    let (arc_impl_generics, _, _) = arc_impl_generics.split_for_impl();
+    let access_attr = access_attr();
+
+    let default_impl = match &attr.default {
+        Some(default) if attr.local || attr.boxed => {
+            return Err(Error::new(
+                default.span(),
+                "facet::facet 'default' cannot be used with 'local' or 'boxed' facets",
+            ));
+        }
+        Some(default) => quote! {
+            impl #decl_generics ::#facet_crate::FacetDefault for #facet_ty #where_clause {
+                fn default_facet() -> ::std::sync::Arc<Self> {
+                    ::std::sync::Arc::new(<#default as ::std::default::Default>::default())
+                }
+            }
+        },
+        None => quote!(),
+    };
+    // Cloneable facets can be cloned into new boxes, either from a box or
+    // from a container.
+    let (clone_impl, clone_method) = if attr.clone {
+        let clone_method = format_ident!("clone_{}", snake_name);
+        (
+            quote! {
+                impl #decl_generics ::std::clone::Clone
+                    for ::std::boxed::Box<#facet_ty> #where_clause
+                {
+                    fn clone(&self) -> Self {
+                        ::#facet_crate::dyn_clone::clone_box(&**self)
+                    }
+                }
+            },
+            quote! {
+                /// Clone #name from a facet container into a new box.
+                fn #clone_method(&self) -> ::std::boxed::Box<#facet_ty> {
+                    ::#facet_crate::dyn_clone::clone_box(self.#trait_ref_method())
+                }
+            },
+        )
+    } else {
+        (quote!(), quote!())
+    };
+    let facet = quote! {
+        #facet
+
+        #dyn_trait
+
+        #default_impl
+
+        #clone_impl
+
+        #forward_impls
+    };
+
+    if attr.boxed {
+        let box_trait_name = format_ident!("Box{}", name);
+        return Ok(quote! {
+            #facet
+
+            /// Access #name by reference from a facet container.
+            #vis trait #trait_ref_name #decl_generics #where_clause {
+                /// Access #name by reference from a facet container.
+                fn #trait_ref_method(&self) -> &(#facet_ty);
+
+                #clone_method
+            }
+
+            impl #ref_impl_generics #trait_ref_name #args for #container #where_clause {
+                #[inline]
+                #access_attr
+                fn #trait_ref_method(&self) -> &(#facet_ty) {
+                    self.facet_ref()
+                }
+            }
+
+            /// Uniquely owned container for #name.
+            #vis type #box_trait_name #alias_generics = ::std::boxed::Box<#facet_ty>;
+        });
+    }
+
+    if attr.local {
+        let trait_rc_name = format_ident!("{}Rc", name);
+        let trait_rc_method = format_ident!("{}_rc", snake_name);
+        let rc_trait_name = format_ident!("Rc{}", name);
+        return Ok(quote! {
+            #facet
+
+            /// Access #name by reference from a facet container.
+            #vis trait #trait_ref_name #decl_generics #where_clause {
+                /// Access #name by reference from a facet container.
+                fn #trait_ref_method(&self) -> &(#facet_ty);
+
+                #clone_method
+            }
+
+            impl #ref_impl_generics #trait_ref_name #args for #container #where_clause {
+                #[inline]
+                #access_attr
+                fn #trait_ref_method(&self) -> &(#facet_ty) {
+                    self.facet_ref()
+                }
+            }
+
+            /// Access a cloneable reference to #name from a local facet
+            /// container.
+            #vis trait #trait_rc_name #decl_generics: #trait_ref_name #args #where_clause {
+                /// Access a cloneable reference to #name from a local facet
+                /// container.
+                fn #trait_rc_method(&self) -> ::std::rc::Rc<#facet_ty>;
+            }
+
+            impl #rc_impl_generics #trait_rc_name #args for #container #where_clause {
+                #[inline]
+                #access_attr
+                fn #trait_rc_method(&self) -> ::std::rc::Rc<#facet_ty> {
+                    self.facet_rc()
+                }
+            }
+
+            /// Cloneable container for #name.
+            #vis type #rc_trait_name #alias_generics = ::std::rc::Rc<#facet_ty>;
+        });
+    }
 
     Ok(quote! {
         #facet
 
         /// Access #name by reference from a facet container.
-        #vis trait #trait_ref_name {
+        #vis trait #trait_ref_name #decl_generics #where_clause {
             /// Access #name by reference from a facet container.
             fn #trait_ref_method(&self) -> &(#facet_ty);
+
+            #clone_method
         }
 
-        impl<T: ::#facet_crate::FacetRef<#facet_ty>> #trait_ref_name for T {
+        impl #ref_impl_generics #trait_ref_name #args for #container #where_clause {
             #[inline]
+            #access_attr
             fn #trait_ref_method(&self) -> &(#facet_ty) {
                 self.facet_ref()
             }
         }
 
         /// Access a cloneable reference to #name from a facet container.
-        #vis trait #trait_arc_name: #trait_ref_name {
+        #vis trait #trait_arc_name #decl_generics: #trait_ref_name #args #where_clause {
             /// Access a cloneable reference to #name from a facet container.
             fn #trait_arc_method(&self) -> ::std::sync::Arc<#facet_ty>;
         }
 
-        impl<T: ::#facet_crate::FacetArc<#facet_ty> + ::#facet_crate::FacetRef<#facet_ty>> #trait_arc_name for T {
+        impl #arc_impl_generics #trait_arc_name #args for #container #where_clause {
             #[inline]
+            #access_attr
             fn #trait_arc_method(&self) -> ::std::sync::Arc<#facet_ty> {
                 self.facet_arc()
             }
         }
 
         /// Cloneable container for #name.
-        #vis type #arc_trait_name = ::std::sync::Arc<#facet_ty>;
+        #vis type #arc_trait_name #alias_generics = ::std::sync::Arc<#facet_ty>;
+
+        /// Weak reference to #name.
+        #vis type #weak_trait_name #alias_generics = ::#facet_crate::WeakFacet<#facet_ty>;
     })
 }
 
-/// Converts a Pascal case name like `SomeTraitName` to snake case like
-/// `some_trait_name`.
-fn snakify_pascal_case(pascal: impl AsRef<str>) -> String {
-    let mut snake = String::new();
-    for ch in pascal.as_ref().chars() {
-        if ch.is_uppercase() {
-            if !snake.is_empty() {
-                snake.push('_');
+/// Returns the generics of a facet, with every type parameter required to be
+/// `'static`, as facets are held by containers as `'static` trait objects
+/// or types.  Facets can't have lifetime parameters.
+fn facet_generics(generics: &Generics) -> Result<Generics, Error> {
+    let mut generics = generics.clone();
+    for param in generics.params.iter_mut() {
+        match param {
+            GenericParam::Type(param) => {
+                param.default = None;
+                param.eq_token = None;
+                param.bounds.push(syn::parse2(quote!('static))?);
+            }
+            GenericParam::Lifetime(param) => {
+                return Err(Error::new(
+                    param.span(),
+                    "facets cannot have lifetime parameters",
+                ));
             }
-            snake.extend(ch.to_lowercase());
-        } else {
-            snake.push(ch);
+            GenericParam::Const(param) => {
+                param.default = None;
+                param.eq_token = None;
+            }
+        }
+    }
+    Ok(generics)
+}
+
+/// Returns the facet type of a struct or enum facet.  Unless the facet is
+/// local, generic facets are only facets when their arguments make them
+/// `Send` and `Sync`, so this is added to the where clause of their traits.
+fn generic_type_facet(
+    name: &Ident,
+    generics: &mut Generics,
+    local: bool,
+) -> Result<TokenStream, Error> {
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let facet_ty = quote!(#name #ty_generics);
+    if !local && !generics.params.is_empty() {
+        generics
+            .make_where_clause()
+            .predicates
+            .push(syn::parse2(quote! {
+                #facet_ty: ::std::marker::Send + ::std::marker::Sync
+            })?);
+    }
+    Ok(facet_ty)
+}
+
+/// Returns the generics of the aliases of a generic facet, which don't
+/// repeat the bounds of the facet's parameters as type aliases don't
+/// enforce them.
+fn alias_generics(generics: &Generics) -> TokenStream {
+    if generics.params.is_empty() {
+        return quote!();
+    }
+    let params = generics.params.iter().map(|param| match param {
+        GenericParam::Type(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+        GenericParam::Const(param) => {
+            let ident = &param.ident;
+            let ty = &param.ty;
+            quote!(const #ident: #ty)
+        }
+        GenericParam::Lifetime(param) => {
+            let lifetime = &param.lifetime;
+            quote!(#lifetime)
+        }
+    });
+    quote!(< #( #params ),* >)
+}
+
+/// Returns the generics extended with an extra parameter.
+fn extend_generics(generics: &Generics, extra: TokenStream) -> Generics {
+    let mut generics = generics.clone();
+    generics
+        .params
+        .push(syn::parse2(extra).expect("invalid generic parameter"));
+    generics
+}
+
+/// Generates implementations of a facet trait for references, boxes and
+/// `Arc`s of its implementations, which forward each method of the trait to
+/// the implementation they point to.  Traits with native async methods are
+/// held by containers through their dyn-compatible version, so they are
+/// implemented for pointers to implementations of that instead.
+fn gen_forward_impls(facet: &ItemTrait, has_dyn_trait: bool) -> Result<TokenStream, Error> {
+    let name = &facet.ident;
+    let inner_name = if has_dyn_trait {
+        format_ident!("Dyn{}", name)
+    } else {
+        name.clone()
+    };
+    let inner = if facet.generics.params.is_empty() {
+        format_ident!("T")
+    } else {
+        format_ident!("__FacetInner")
+    };
+    let (_, args, _) = facet.generics.split_for_impl();
+    let trait_path = quote!(<#inner as #inner_name #args>);
+
+    let mut methods = Vec::new();
+    for item in &facet.items {
+        match item {
+            TraitItem::Method(method) => methods.push(gen_forward_method(&trait_path, method)?),
+            _ => {
+                return Err(Error::new(
+                    item.span(),
+                    "facet traits with 'forward' can only contain methods",
+                ));
+            }
+        }
+    }
+
+    // Traits that use `#[async_trait]` need it on their implementations
+    // too, so that the async methods are rewritten in the same way.  Unless
+    // the futures are `?Send`, they hold `&self`, so the implementation
+    // must be `Sync`, and so must be the pointer to it.
+    let async_trait = facet
+        .attrs
+        .iter()
+        .filter(|attr| {
+            attr.path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "async_trait")
+        })
+        .collect::<Vec<_>>();
+    let send_sync = match async_trait.first() {
+        Some(attr) if !attr.tokens.to_string().contains("Send") => {
+            quote!(+ ::std::marker::Send + ::std::marker::Sync)
+        }
+        _ => quote!(),
+    };
+
+    // Only the implementation for references needs a lifetime parameter.
+    let lifetime = Lifetime::new("'__facet", Span::call_site());
+    let targets = [
+        (Some(&lifetime), quote!(&#lifetime #inner)),
+        (None, quote!(::std::boxed::Box<#inner>)),
+        (None, quote!(::std::sync::Arc<#inner>)),
+    ];
+    let impls = targets.iter().map(|(lifetime, target)| {
+        let mut generics = extend_generics(
+            &facet.generics,
+            quote!(#inner: #inner_name #args + ?::std::marker::Sized #send_sync),
+        );
+        if let Some(lifetime) = lifetime {
+            generics.params.insert(
+                0,
+                syn::parse2(quote!(#lifetime)).expect("lifetime should parse"),
+            );
+        }
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        quote! {
+            #( #async_trait )*
+            impl #impl_generics #name #args for #target #where_clause {
+                #( #methods )*
+            }
+        }
+    });
+    Ok(quote!( #( #impls )* ))
+}
+
+/// Generates a method of a forwarding implementation of a facet trait,
+/// which calls the method of the implementation that `self` points to.
+fn gen_forward_method(
+    trait_path: &TokenStream,
+    method: &TraitItemMethod,
+) -> Result<TokenStream, Error> {
+    let mut sig = method.sig.clone();
+    let method_ident = &sig.ident;
+    let mut args = Vec::new();
+    for (index, input) in sig.inputs.iter_mut().enumerate() {
+        match input {
+            FnArg::Receiver(receiver)
+                if receiver.reference.is_some() && receiver.mutability.is_none() => {}
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new(
+                    receiver.span(),
+                    "methods of facet traits with 'forward' must take `&self`",
+                ));
+            }
+            FnArg::Typed(pat_type) => {
+                let arg = format_ident!("__arg{}", index);
+                *pat_type.pat = Pat::Verbatim(quote!(#arg));
+                args.push(arg);
+            }
+        }
+    }
+    if sig.receiver().is_none() {
+        return Err(Error::new(
+            sig.span(),
+            "methods of facet traits with 'forward' must take `&self`",
+        ));
+    }
+    let call = quote!(#trait_path::#method_ident(&**self, #( #args ),*));
+    let body = if sig.asyncness.is_some() {
+        quote!(#call.await)
+    } else {
+        call
+    };
+    Ok(quote! {
+        #[inline]
+        #sig {
+            #body
+        }
+    })
+}
+
+/// Returns true if the trait has native `async fn` methods, rather than
+/// having them made dyn-compatible by `#[async_trait]`.
+fn has_native_async(facet: &ItemTrait) -> bool {
+    let uses_async_trait = facet.attrs.iter().any(|attr| {
+        attr.path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "async_trait")
+    });
+    !uses_async_trait
+        && facet
+            .items
+            .iter()
+            .any(|item| matches!(item, TraitItem::Method(method) if method.sig.asyncness.is_some()))
+}
+
+/// Rewrites the native async methods of a facet trait to return futures that
+/// are `Send` (unless the facet is local), and generates `Dyn{Trait}`, a
+/// dyn-compatible version of the trait whose async methods return boxed
+/// futures.  It is implemented for every implementation of the trait, so
+/// implementations can use native `async fn` and be held by containers as
+/// `dyn Dyn{Trait}`.
+fn gen_dyn_trait(facet: &mut ItemTrait, local: bool) -> Result<TokenStream, Error> {
+    let name = &facet.ident;
+    let vis = &facet.vis;
+    let dyn_name = format_ident!("Dyn{}", name);
+    let send = if local {
+        quote!()
+    } else {
+        quote!(+ ::std::marker::Send)
+    };
+    if !facet.generics.params.is_empty() {
+        return Err(Error::new(
+            facet.generics.span(),
+            "facet traits with native async methods cannot be generic",
+        ));
+    }
+
+    let mut dyn_methods = Vec::new();
+    let mut dyn_impls = Vec::new();
+    for item in &mut facet.items {
+        let method = match item {
+            TraitItem::Method(method) => method,
+            _ => {
+                return Err(Error::new(
+                    item.span(),
+                    "facet traits with native async methods can only contain methods",
+                ));
+            }
+        };
+        let (dyn_method, dyn_impl) = gen_dyn_method(name, method, &send)?;
+        dyn_methods.push(dyn_method);
+        dyn_impls.push(dyn_impl);
+        if method.sig.asyncness.is_some() {
+            make_send_async(method, &send);
+        }
+    }
+
+    let supertraits = &facet.supertraits;
+    let colon = facet.colon_token;
+    let dyn_doc = format!(
+        concat!(
+            "Dyn-compatible version of [`{}`], which is implemented for all of ",
+            "its implementations.  Facet containers hold `{}` facets as `dyn {}`."
+        ),
+        name, name, dyn_name,
+    );
+    Ok(quote! {
+        #[doc = #dyn_doc]
+        #vis trait #dyn_name #colon #supertraits {
+            #( #dyn_methods )*
+        }
+
+        impl<T: #name> #dyn_name for T {
+            #( #dyn_impls )*
+        }
+    })
+}
+
+/// Generates the method of the dyn-compatible trait that corresponds to a
+/// method of the facet trait, and its implementation, which calls the facet
+/// trait's method and boxes the future if the method is async.
+fn gen_dyn_method(
+    name: &Ident,
+    method: &TraitItemMethod,
+    send: &TokenStream,
+) -> Result<(TokenStream, TokenStream), Error> {
+    let mut sig = method.sig.clone();
+    if sig
+        .generics
+        .params
+        .iter()
+        .any(|param| !matches!(param, GenericParam::Lifetime(_)))
+    {
+        return Err(Error::new(
+            sig.generics.span(),
+            "methods of facet traits with native async methods cannot be generic",
+        ));
+    }
+    let method_ident = &sig.ident;
+    let mut args = Vec::new();
+    for (index, input) in sig.inputs.iter_mut().enumerate() {
+        match input {
+            FnArg::Receiver(receiver) if receiver.reference.is_some() => {}
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new(
+                    receiver.span(),
+                    "methods of facet traits must take `self` by reference",
+                ));
+            }
+            FnArg::Typed(pat_type) => {
+                let arg = format_ident!("__arg{}", index);
+                *pat_type.pat = Pat::Verbatim(quote!(#arg));
+                args.push(arg);
+            }
+        }
+    }
+    let call = quote!(<Self as #name>::#method_ident(self, #( #args ),*));
+    let attrs = method.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+
+    if sig.asyncness.take().is_none() {
+        return Ok((
+            quote! {
+                #( #attrs )*
+                #sig;
+            },
+            quote! {
+                #[inline]
+                #sig {
+                    #call
+                }
+            },
+        ));
+    }
+
+    // The boxed future borrows `self` and all of the arguments, so they must
+    // all outlive it.
+    let future_lifetime = Lifetime::new("'__facet", Span::call_site());
+    let mut lifetimes = NameElidedLifetimes(&future_lifetime);
+    for input in sig.inputs.iter_mut() {
+        match input {
+            FnArg::Receiver(receiver) => {
+                if let Some((_, lifetime)) = &mut receiver.reference {
+                    if lifetime.is_none() {
+                        *lifetime = Some(future_lifetime.clone());
+                    }
+                }
+            }
+            FnArg::Typed(pat_type) => lifetimes.visit_type_mut(&mut pat_type.ty),
+        }
+    }
+    for param in sig.generics.params.iter_mut() {
+        if let GenericParam::Lifetime(param) = param {
+            param.bounds.push(future_lifetime.clone());
+        }
+    }
+    sig.generics
+        .params
+        .insert(0, syn::parse2(quote!(#future_lifetime))?);
+    let output = match &sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    sig.output = syn::parse2(quote! {
+        -> ::std::pin::Pin<::std::boxed::Box<
+            dyn ::std::future::Future<Output = #output> #send + #future_lifetime
+        >>
+    })?;
+    Ok((
+        quote! {
+            #( #attrs )*
+            #sig;
+        },
+        quote! {
+            #[inline]
+            #sig {
+                ::std::boxed::Box::pin(#call)
+            }
+        },
+    ))
+}
+
+/// Rewrites a native async method of a facet trait to return a future that
+/// is `Send`, so that the dyn-compatible trait can box it.  Implementations
+/// can still use `async fn`.
+fn make_send_async(method: &mut TraitItemMethod, send: &TokenStream) {
+    method.sig.asyncness = None;
+    let output = match &method.sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    method.sig.output = syn::parse2(quote! {
+        -> impl ::std::future::Future<Output = #output> #send
+    })
+    .expect("future return type should parse");
+    if let Some(body) = &method.default {
+        method.default =
+            Some(syn::parse2(quote!({ async move #body })).expect("async block should parse"));
+    }
+}
+
+/// Names the elided lifetimes of references in a type, so that they can be
+/// required to outlive a boxed future.  Elided lifetimes in the arguments of
+/// `Fn` traits and function pointers are higher-ranked, rather than borrowed
+/// from the caller, so they are left alone.
+struct NameElidedLifetimes<'a>(&'a Lifetime);
+
+impl VisitMut for NameElidedLifetimes<'_> {
+    fn visit_parenthesized_generic_arguments_mut(
+        &mut self,
+        _arguments: &mut ParenthesizedGenericArguments,
+    ) {
+    }
+
+    fn visit_type_bare_fn_mut(&mut self, _bare_fn: &mut TypeBareFn) {}
+
+    fn visit_type_reference_mut(&mut self, reference: &mut TypeReference) {
+        if reference.lifetime.is_none() {
+            reference.lifetime = Some(self.0.clone());
+        }
+        syn::visit_mut::visit_type_reference_mut(self, reference);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = self.0.clone();
         }
     }
-    snake
 }
@@ -9,18 +9,22 @@
 
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
-use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, quote, quote_spanned};
 use syn::parse::{Parse, ParseStream};
-use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
+use syn::visit::Visit;
 use syn::{
-    parse_macro_input, Error, FnArg, GenericArgument, Ident, ImplItem, ItemImpl, Pat, PatType,
-    PathArguments, ReturnType, Signature, Token, Type,
+    parse_macro_input, Attribute, Error, Expr, ExprPath, FnArg, GenericArgument, Ident, ImplItem,
+    ImplItemMethod, ItemImpl, Lit, LitStr, Meta, Pat, PatType, Path, PathArguments, ReturnType,
+    Signature, Token, Type,
 };
 
 use crate::facet_crate_name;
-use crate::util::{Asyncness, Fallibility};
+use crate::util::{
+    parse_facet_key, snakify_pascal_case, static_facet_key, unrecognised_facet_name, Asyncness,
+    Fallibility,
+};
 
 pub fn factory(
     attr: proc_macro::TokenStream,
@@ -36,431 +40,3368 @@ pub fn factory(
     .into()
 }
 
+/// Post-build hooks are extracted by `#[facet::factory]` from the methods of
+/// its impl, so this is only expanded for methods outside a factory impl.
+pub fn post_build(
+    _attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    Error::new_spanned(
+        TokenStream::from(item),
+        "#[facet::post_build] can only be used on methods of a #[facet::factory] impl",
+    )
+    .to_compile_error()
+    .into()
+}
+
 fn gen_factory(params: Params, mut factory_impl: ItemImpl) -> Result<TokenStream, Error> {
     let factory_ty = extract_type_ident(&factory_impl.self_ty)?;
 
+    let facet_crate = format_ident!("{}", facet_crate_name());
+    if params.params_struct {
+        borrow_params_fields(&facet_crate, &params, &mut factory_impl)?;
+    }
+    add_default_methods(&facet_crate, &params, &mut factory_impl)?;
+    let alternates = add_alternate_selection(&params, &mut factory_impl)?;
+    if let (Some(factory_trait), Some(facet_ident)) =
+        (&params.factory_trait, alternates.keys().next())
+    {
+        return Err(Error::new(
+            facet_ident.span(),
+            format!(
+                "facet::factory_trait '{}' can't have alternate factory methods",
+                factory_trait
+            ),
+        ));
+    }
     let facets = Facets::extract_from_impl(&params, &mut factory_impl)?;
 
     let factory_builder = gen_factory_builder(&params, &factory_ty, &facets)?;
+    // The facet graph, and the validation that uses it, are only generated
+    // with the `graph` feature.
+    let (facet_graph, validate) = if cfg!(feature = "graph") {
+        (
+            gen_facet_graph(&params, &factory_ty, &facets, &alternates),
+            gen_validate(&params, &factory_ty, &facets),
+        )
+    } else {
+        (quote!(), quote!())
+    };
+    let static_facets = gen_static_facets(&facet_crate, &factory_impl, &facets);
+
+    // Alternate factory methods are not facets in their own right, so they
+    // are moved out of the factory impl.
+    let (impl_generics, _, where_clause) = factory_impl.generics.split_for_impl();
+    let self_ty = &factory_impl.self_ty;
+    let alternate_methods = alternates
+        .values()
+        .flatten()
+        .map(|alternate| &alternate.method);
+    let alternates_impl = quote! {
+        impl #impl_generics #self_ty #where_clause {
+            #( #alternate_methods )*
+        }
+    };
+
+    // The factory methods of factory traits are the trait's own methods, so
+    // the impl only declared them.
+    let factory_impl = match params.factory_trait {
+        Some(_) => quote!(),
+        None => quote!(#factory_impl),
+    };
 
     Ok(quote! {
         #factory_impl
 
+        #alternates_impl
+
         #factory_builder
-    })
-}
 
-fn gen_factory_builder(
-    params: &Params,
-    factory_ty: &Ident,
-    facets: &Facets,
-) -> Result<TokenStream, Error> {
-    let facet_idents = &facets.facet_idents;
-    let facet_params = &facets.facet_params;
+        #facet_graph
 
-    let facet_crate = format_ident!("{}", facet_crate_name());
-    let builder_ident = format_ident!("{}Builder", factory_ty);
+        #validate
 
-    let is_async = Asyncness::any(facets.facet_asyncnesses.iter());
+        #static_facets
+    })
+}
 
-    let facet_params_map = facet_idents
+/// Generate the `StaticFacet` impls that give the concrete type of each facet
+/// the factory builds by name, for containers that use static dispatch.  Only
+/// facets built as an `Arc` or `Rc` of a concrete type have one.
+fn gen_static_facets(facet_crate: &Ident, factory_impl: &ItemImpl, facets: &Facets) -> TokenStream {
+    let (impl_generics, _, where_clause) = factory_impl.generics.split_for_impl();
+    let self_ty = &factory_impl.self_ty;
+    let static_facets = facets
         .iter()
-        .zip(facet_params)
-        .collect::<BTreeMap<_, _>>();
+        .filter_map(|(facet_ident, facet_type, _, _, _, _)| {
+            let concrete_type = concrete_facet_type(facet_type)?;
+            let key = static_facet_key(facet_ident);
+            Some(quote! {
+                impl #impl_generics ::#facet_crate::StaticFacet<#key> for #self_ty #where_clause {
+                    type Impl = #concrete_type;
+                }
+            })
+        });
+    quote!( #( #static_facets )* )
+}
 
-    for facet_ident in facet_idents {
-        check_no_cycles(facet_ident, &facet_params_map)?;
+/// Returns the concrete type `T` of a facet built as an `Arc<T>` or `Rc<T>`.
+fn concrete_facet_type(facet_type: &Type) -> Option<&Type> {
+    let segment = match facet_type {
+        Type::Path(type_path) => type_path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Arc" && segment.ident != "Rc" {
+        return None;
     }
-
-    let builder = match is_async {
-        Asyncness::Synchronous => {
-            gen_sync_factory_builder(&facet_crate, factory_ty, &builder_ident, params, facets)?
-        }
-        Asyncness::Asynchronous => {
-            gen_async_factory_builder(&facet_crate, factory_ty, &builder_ident, params, facets)?
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            match arguments.args.first()? {
+                GenericArgument::Type(ty @ Type::Path(_)) => Some(ty),
+                _ => None,
+            }
         }
-    };
+        _ => None,
+    }
+}
 
-    Ok(builder)
+/// Generates the factory for a trait marked with `#[facet::factory_trait]`,
+/// from an impl for the trait's trait objects that declares each method of
+/// the trait.
+pub(crate) fn gen_trait_factory(
+    mut params: Params,
+    factory_trait: &Ident,
+    factory_impl: ItemImpl,
+) -> Result<TokenStream, Error> {
+    if params.delegate.is_some()
+        || params.extern_factory.is_some()
+        || params.memoize
+        || !params.defaults.is_empty()
+        || params.params_struct
+    {
+        return Err(Error::new(
+            factory_trait.span(),
+            concat!(
+                "facet::factory_trait doesn't support 'delegate', 'extern', 'memoize', ",
+                "'defaults' or 'params'"
+            ),
+        ));
+    }
+    params.factory_trait = Some(factory_trait.clone());
+    gen_factory(params, factory_impl)
 }
 
-fn gen_sync_factory_builder(
+/// Generates a call to a factory method.  The methods of factory traits are
+/// called through the trait, as the trait objects' inherent methods would
+/// otherwise be ambiguous with them.
+fn gen_method_call(
+    params: &Params,
+    factory: TokenStream,
+    method: &Ident,
+    args: &[TokenStream],
+) -> TokenStream {
+    match &params.factory_trait {
+        Some(factory_trait) => quote!(#factory_trait::#method(#factory, #( #args ),*)),
+        None => quote!(#factory.#method( #( #args ),* )),
+    }
+}
+
+/// Rewrites the factory methods of a factory whose parameters are a single
+/// struct to take the struct, binding the fields they borrow from it at the
+/// start of the method.  Any argument taken by reference that doesn't name a
+/// facet is a field of the struct.
+fn borrow_params_fields(
     facet_crate: &Ident,
-    factory_ty: &Ident,
-    builder_ident: &Ident,
     params: &Params,
-    facets: &Facets,
-) -> Result<TokenStream, Error> {
-    let builder_facets_ident = format_ident!("{}BuilderFacets", factory_ty);
-    let param_idents = &params.param_idents;
-    let param_types = &params.param_types;
-    let facet_idents = &facets.facet_idents;
-    let facet_types = &facets.facet_types;
-    let facet_types_map = facet_idents
+    factory_impl: &mut ItemImpl,
+) -> Result<(), Error> {
+    // The facets are only known once the factory methods have been
+    // extracted, which is done on a copy of the impl.
+    let mut extracted_impl = factory_impl.clone();
+    add_default_methods(facet_crate, params, &mut extracted_impl)?;
+    add_alternate_selection(params, &mut extracted_impl)?;
+    let facets = Facets::extract_from_impl(params, &mut extracted_impl)?;
+    let facet_idents = facets
+        .facet_idents
         .iter()
-        .zip(facet_types)
-        .collect::<BTreeMap<_, _>>();
-
-    let mut builder_impls = Vec::new();
+        .chain(facets.boxed_facets.iter().map(|boxed| &boxed.ident))
+        .collect::<BTreeSet<_>>();
 
-    for (facet_ident, facet_type, fallibility, asyncness, facet_params) in facets.iter() {
-        let mut call_params = Vec::new();
-        let mut make_facets = Vec::new();
-
-        for facet_param in facet_params {
-            match facet_param {
-                FactoryParam::Facet(ident) => {
-                    let param_type = facet_types_map
-                        .get(ident)
-                        .ok_or_else(|| Error::new(ident.span(), "unrecognised facet name"))?;
-                    make_facets.push(quote! {
-                        let #ident: #param_type = self.build()?;
-                    });
-                    call_params.push(quote!(&#ident));
-                }
-                FactoryParam::Param(ident) => {
-                    call_params.push(quote!(&self.facets.#ident));
+    let params_ident = &params.param_idents[0];
+    let params_type = &params.param_types[0];
+    for item in &mut factory_impl.items {
+        if let ImplItem::Method(method) = item {
+            let mut takes_params = method.sig.inputs.iter().any(|input| {
+                matches!(input, FnArg::Typed(pat_type)
+                    if matches!(&*pat_type.pat, Pat::Ident(pat_ident) if &pat_ident.ident == params_ident))
+            });
+            let mut field_bindings = Vec::new();
+            for input in std::mem::take(&mut method.sig.inputs) {
+                if let FnArg::Typed(PatType { pat, ty, .. }) = &input {
+                    if let (Pat::Ident(pat_ident), Type::Reference(_)) = (&**pat, &**ty) {
+                        let field = strip_leading_underscore(&pat_ident.ident);
+                        if field != *params_ident && !facet_idents.contains(&field) {
+                            field_bindings.push(syn::parse_quote! {
+                                let #pat: #ty = &#params_ident.#field;
+                            });
+                            if !takes_params {
+                                method
+                                    .sig
+                                    .inputs
+                                    .push(syn::parse_quote!(#params_ident: &#params_type));
+                                takes_params = true;
+                            }
+                            continue;
+                        }
+                    }
                 }
+                method.sig.inputs.push(input);
             }
+            method.block.stmts.splice(0..0, field_bindings);
         }
+    }
+    Ok(())
+}
 
-        if asyncness == Asyncness::Asynchronous {
-            panic!("should not generate sync builder for async factory");
-        }
-        let maybe_map_err = fallibility.maybe(quote! {
-            .map_err(|e| ::#facet_crate::FactoryError::FacetBuildFailed {
-                name: stringify!(#facet_ident),
-                source: e.into(),
-            })?
-        });
-
-        builder_impls.push(quote! {
-
-            impl ::#facet_crate::Builder<#facet_type> for #builder_ident<'_> {
+/// An alternate factory method for a facet, declared with
+/// `#[facet(alternate_of = facet, select = "condition")]`, which builds the
+/// facet instead of the facet's own factory method when its condition holds.
+struct Alternate {
+    method: ImplItemMethod,
+    condition: LitStr,
+    params: Vec<FactoryParam>,
+}
 
-                fn build<'builder>(&'builder mut self) -> ::std::result::Result<
-                    #facet_type,
-                    ::#facet_crate::FactoryError,
-                >  {
-                    if let Some(facet) = self.facets.#facet_ident.as_ref() {
-                        return Ok(facet.clone());
-                    }
-                    use ::#facet_crate::Builder as _;
-                    #( #make_facets )*
-                    let #facet_ident =
-                        self.factory.#facet_ident( #( #call_params ),* )
-                            #maybe_map_err;
-                    debug_assert!(self.facets.#facet_ident.is_none());
-                    self.facets.#facet_ident = Some(#facet_ident.clone());
-                    Ok(#facet_ident)
+/// Removes the alternate factory methods from the factory impl, and rewrites
+/// the factory method of each facet that has alternates to select between
+/// them at build time.  The rewritten method takes the parameters of all of
+/// the alternates, so the facet depends on all of their dependencies, and
+/// calls the first alternate whose condition holds, or runs its own body if
+/// none do.
+fn add_alternate_selection(
+    params: &Params,
+    factory_impl: &mut ItemImpl,
+) -> Result<BTreeMap<Ident, Vec<Alternate>>, Error> {
+    let mut alternates = BTreeMap::<Ident, Vec<Alternate>>::new();
+    let mut items = Vec::new();
+    for item in factory_impl.items.drain(..) {
+        let mut method = match item {
+            ImplItem::Method(method) => method,
+            item => {
+                items.push(item);
+                continue;
+            }
+        };
+        let mut alternate_of = None;
+        for attr in &method.attrs {
+            if attr.path.is_ident("facet") {
+                if let Some(options) = parse_alternate_options(attr)? {
+                    alternate_of = Some(options);
                 }
             }
+        }
+        match alternate_of {
+            Some((facet_ident, condition)) => {
+                method.attrs.retain(|attr| !attr.path.is_ident("facet"));
+                let method_params = Facets::extract_facet_params(params, &method.sig)?;
+                alternates.entry(facet_ident).or_default().push(Alternate {
+                    method,
+                    condition,
+                    params: method_params,
+                });
+            }
+            None => items.push(ImplItem::Method(method)),
+        }
+    }
+    factory_impl.items = items;
 
+    let method_idents = factory_impl
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Method(method) => Some(method.sig.ident.clone()),
+            _ => None,
         })
+        .collect::<Vec<_>>();
+    for (facet_ident, facet_alternates) in &alternates {
+        let method = factory_impl
+            .items
+            .iter_mut()
+            .find_map(|item| match item {
+                ImplItem::Method(method) if method.sig.ident == *facet_ident => Some(method),
+                _ => None,
+            })
+            .ok_or_else(|| unrecognised_facet_name(facet_ident, &method_idents))?;
+        gen_select_method(params, method, facet_alternates)?;
     }
+    Ok(alternates)
+}
 
-    let builder = quote! {
-        #[doc(hidden)]
-        pub struct #builder_facets_ident {
-            #(
-                #param_idents: #param_types,
-            )*
-            #(
-                #facet_idents: ::std::option::Option<#facet_types>,
-            )*
+/// Parses the options of a `#[facet(...)]` attribute of an alternate factory
+/// method, returning the facet it is an alternate of and its condition, or
+/// `None` if the attribute doesn't declare an alternate.
+fn parse_alternate_options(attr: &Attribute) -> Result<Option<(Ident, LitStr)>, Error> {
+    let is_alternate = attr.tokens.clone().into_iter().any(|token| {
+        match token {
+        TokenTree::Group(group) => group.stream().into_iter().any(|token| {
+            matches!(token, TokenTree::Ident(ident) if ident == "alternate_of" || ident == "select")
+        }),
+        _ => false,
+    }
+    });
+    if !is_alternate {
+        return Ok(None);
+    }
+    attr.parse_args_with(|input: ParseStream| {
+        let mut alternate_of = None;
+        let mut select = None;
+        while !input.is_empty() {
+            let option = input.parse::<Ident>()?;
+            if option == "alternate_of" {
+                input.parse::<Token![=]>()?;
+                alternate_of = Some(input.parse::<Ident>()?);
+            } else if option == "select" {
+                input.parse::<Token![=]>()?;
+                select = Some(input.parse::<LitStr>()?);
+            } else {
+                return Err(Error::new(
+                    option.span(),
+                    "alternate factory methods can only have 'alternate_of' and 'select' options",
+                ));
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        match (alternate_of, select) {
+            (Some(alternate_of), Some(select)) => Ok(Some((alternate_of, select))),
+            (Some(alternate_of), None) => Err(Error::new(
+                alternate_of.span(),
+                "alternate factory methods must have a 'select' condition",
+            )),
+            (None, _) => Err(Error::new(
+                attr.span(),
+                "facet 'select' can only be used with 'alternate_of'",
+            )),
         }
+    })
+}
 
-        impl #builder_facets_ident {
-            #[doc(hidden)]
-            pub fn new( #( #param_idents: #param_types, )* ) -> Self {
-                Self {
-                    #( #param_idents, )*
-                    #(
-                        #facet_idents: ::std::default::Default::default(),
-                    )*
+/// Rewrites the factory method of a facet to select between its alternates.
+fn gen_select_method(
+    params: &Params,
+    method: &mut ImplItemMethod,
+    alternates: &[Alternate],
+) -> Result<(), Error> {
+    // The parameters of the rewritten method, by name, with the identifier
+    // the method binds each one to and its type.
+    let mut method_params = BTreeMap::new();
+    for input in &method.sig.inputs {
+        if let FnArg::Typed(pat_type) = input {
+            let ident = alternate_param_ident(pat_type)?;
+            let ty = &pat_type.ty;
+            method_params.insert(
+                strip_leading_underscore(&ident),
+                (ident, quote!(#ty).to_string()),
+            );
+        }
+    }
+
+    let output = &method.sig.output;
+    let output = quote!(#output).to_string();
+    let mut branches = Vec::new();
+    for alternate in alternates {
+        let sig = &alternate.method.sig;
+        let alternate_output = &sig.output;
+        if quote!(#alternate_output).to_string() != output {
+            return Err(Error::new(
+                sig.span(),
+                format!(
+                    "alternate factory method '{}' must return the same type as '{}'",
+                    sig.ident, method.sig.ident
+                ),
+            ));
+        }
+        let mut args = Vec::new();
+        for input in &sig.inputs {
+            let pat_type = match input {
+                FnArg::Receiver(receiver) if receiver.reference.is_some() => continue,
+                FnArg::Receiver(receiver) => {
+                    return Err(Error::new(
+                        receiver.span(),
+                        "alternate factory methods must take '&self'",
+                    ));
+                }
+                FnArg::Typed(pat_type) => pat_type,
+            };
+            let ty = &pat_type.ty;
+            let ty_string = quote!(#ty).to_string();
+            let name = strip_leading_underscore(&alternate_param_ident(pat_type)?);
+            match method_params.get(&name) {
+                Some((method_ident, method_ty)) => {
+                    if *method_ty != ty_string {
+                        return Err(Error::new(
+                            ty.span(),
+                            format!(
+                                "parameter '{}' of alternate factory method '{}' must have the same type as in '{}'",
+                                name, sig.ident, method.sig.ident
+                            ),
+                        ));
+                    }
+                    args.push(method_ident.clone());
+                }
+                None => {
+                    // Parameters are bound to their names, so that
+                    // conditions can use them.
+                    method.sig.inputs.push(syn::parse2(quote!(#name: #ty))?);
+                    method_params.insert(name.clone(), (name.clone(), ty_string));
+                    args.push(name);
                 }
             }
         }
-
-        #(
-            #builder_impls
-        )*
-
-        #[doc(hidden)]
-        pub struct #builder_ident<'factory> {
-            factory: &'factory #factory_ty,
-            facets: #builder_facets_ident,
+        let alternate_ident = &sig.ident;
+        let condition = alternate.condition.parse::<Expr>()?;
+        // Conditions can also use factory parameters that none of the
+        // alternates take, which the rewritten method takes by reference.
+        for (param_ident, param_ty) in condition_params(params, &condition) {
+            if !method_params.contains_key(param_ident) {
+                method
+                    .sig
+                    .inputs
+                    .push(syn::parse2(quote!(#param_ident: &#param_ty))?);
+                method_params.insert(
+                    param_ident.clone(),
+                    (param_ident.clone(), quote!(&#param_ty).to_string()),
+                );
+            }
         }
+        let call = if sig.asyncness.is_some() {
+            // The rewritten method must be async if any of its alternates
+            // are.
+            method.sig.asyncness = sig.asyncness;
+            quote!(self.#alternate_ident( #( #args ),* ).await)
+        } else {
+            quote!(self.#alternate_ident( #( #args ),* ))
+        };
+        branches.push(quote!(if #condition { #call } else));
+    }
+    let block = &method.block;
+    method.block = syn::parse2(quote!({ #( #branches )* #block }))?;
+    Ok(())
+}
 
-        impl #factory_ty {
-            /// Build an instance of a container from this factory.
-            pub fn build<'factory, T>(
-                &'factory self,
-                #( #param_idents: #param_types ),*
-            ) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
-            where
-                T: ::#facet_crate::Buildable<#builder_ident<'factory>>,
-            {
-                let mut builder = #builder_ident {
-                    factory: &self,
-                    facets: #builder_facets_ident::new(#( #param_idents, )*),
-                };
-                T::build(&mut builder)
+/// Returns the factory parameters that a condition uses.
+fn condition_params<'a>(params: &'a Params, condition: &Expr) -> Vec<(&'a Ident, &'a Type)> {
+    struct FindParams<'a> {
+        params: &'a Params,
+        found: Vec<(&'a Ident, &'a Type)>,
+    }
+
+    impl<'ast> Visit<'ast> for FindParams<'_> {
+        fn visit_expr_path(&mut self, expr: &'ast ExprPath) {
+            if expr.qself.is_none() {
+                if let Some(ident) = expr.path.get_ident() {
+                    let param = self
+                        .params
+                        .param_idents
+                        .iter()
+                        .zip(&self.params.param_types)
+                        .find(|(param_ident, _)| *param_ident == ident);
+                    if let Some(param) = param {
+                        if !self.found.contains(&param) {
+                            self.found.push(param);
+                        }
+                    }
+                }
             }
         }
+    }
+
+    let mut finder = FindParams {
+        params,
+        found: Vec::new(),
     };
+    finder.visit_expr(condition);
+    finder.found
+}
 
-    Ok(builder)
+/// Returns the identifier a parameter of a factory method with alternates is
+/// bound to.
+fn alternate_param_ident(pat_type: &PatType) -> Result<Ident, Error> {
+    match &*pat_type.pat {
+        Pat::Ident(pat_ident) => Ok(pat_ident.ident.clone()),
+        _ => Err(Error::new(pat_type.span(), "expected 'ident: Type'")),
+    }
 }
 
-fn gen_async_factory_builder(
+/// Adds a factory method for each of the factory's `defaults`, which builds
+/// the facet with its default implementation.
+fn add_default_methods(
     facet_crate: &Ident,
-    factory_ty: &Ident,
-    builder_ident: &Ident,
     params: &Params,
+    factory_impl: &mut ItemImpl,
+) -> Result<(), Error> {
+    for (name, path) in &params.defaults {
+        let defined = factory_impl.items.iter().any(|item| match item {
+            ImplItem::Method(method) => method.sig.ident == *name,
+            _ => false,
+        });
+        if defined {
+            return Err(Error::new(
+                name.span(),
+                format!(
+                    "factory method '{}' is already defined, so the facet can't use its default",
+                    name
+                ),
+            ));
+        }
+        let mut arc_path = path.clone();
+        if let Some(last) = arc_path.segments.last_mut() {
+            last.ident = format_ident!("Arc{}", last.ident, span = last.ident.span());
+        }
+        factory_impl
+            .items
+            .push(ImplItem::Method(syn::parse2(quote! {
+                fn #name(&self) -> #arc_path {
+                    ::#facet_crate::FacetDefault::default_facet()
+                }
+            })?));
+    }
+    Ok(())
+}
+
+fn gen_facet_graph(
+    params: &Params,
+    factory_ty: &Ident,
     facets: &Facets,
-) -> Result<TokenStream, Error> {
-    let builder_facets_ident = format_ident!("{}BuilderFacets", factory_ty);
-    let builder_facets_needed_ident = format_ident!("{}BuilderFacetsNeeded", factory_ty);
-    let builder_params_ident = format_ident!("{}BuilderParams", factory_ty);
+    alternates: &BTreeMap<Ident, Vec<Alternate>>,
+) -> TokenStream {
+    let facet_crate = format_ident!("{}", facet_crate_name());
+    let mut nodes = Vec::new();
 
-    let param_idents = &params.param_idents;
-    let param_types = &params.param_types;
-    let facet_idents = &facets.facet_idents;
-    let facet_types = &facets.facet_types;
-    let facet_types_map = facet_idents
+    let facet_params = facets
         .iter()
-        .zip(facet_types)
-        .collect::<BTreeMap<_, _>>();
-
-    let mut heads: BTreeSet<_> = facet_idents.iter().collect();
-    let mut facet_build_futs = BTreeMap::new();
-    let mut facet_build_graph = BTreeMap::new();
-    let mut builder_impls = Vec::new();
-    let mut build_facets = Vec::new();
-    let mut store_facets = Vec::new();
-
-    for (facet_ident, facet_type, fallibility, asyncness, facet_params) in facets.iter() {
-        let mut dependent_facets = Vec::new();
-        let mut mark_facets_needed = Vec::new();
-        let mut call_params = Vec::new();
-        let mut deps = Vec::new();
+        .map(|(facet_ident, _, _, _, facet_params, _)| (facet_ident, facet_params))
+        .chain(
+            facets
+                .boxed_facets
+                .iter()
+                .map(|boxed| (&boxed.ident, boxed.params.as_slice())),
+        );
 
+    for (facet_ident, facet_params) in facet_params {
+        let mut dependencies = Vec::new();
+        let mut weak_dependencies = Vec::new();
+        let mut params = Vec::new();
         for facet_param in facet_params {
             match facet_param {
-                FactoryParam::Facet(ident) => {
-                    let param_type = facet_types_map
-                        .get(ident)
-                        .ok_or_else(|| Error::new(ident.span(), "unrecognised facet name"))?;
-                    mark_facets_needed.push(quote! {
-                        ::#facet_crate::AsyncBuilderFor::<#param_type>::need(self);
-                    });
-                    dependent_facets.push(ident);
-                    call_params.push(quote!(#ident.as_ref().unwrap()));
-                    heads.remove(&ident);
-                    deps.push(ident);
+                FactoryParam::Facet(ident, _) => dependencies.push(ident),
+                FactoryParam::WeakFacet(ident, _) => weak_dependencies.push(ident),
+                FactoryParam::Param(ident)
+                | FactoryParam::OwnedParam(ident)
+                | FactoryParam::CopiedParam(ident) => params.push(ident),
+            }
+        }
+        let facet_alternates = alternates
+            .get(facet_ident)
+            .into_iter()
+            .flatten()
+            .map(|alternate| {
+                let method_ident = &alternate.method.sig.ident;
+                let condition = &alternate.condition;
+                let mut dependencies = Vec::new();
+                let mut params = Vec::new();
+                for facet_param in &alternate.params {
+                    match facet_param {
+                        FactoryParam::Facet(ident, _) => dependencies.push(ident),
+                        FactoryParam::WeakFacet(..) => {}
+                        FactoryParam::Param(ident)
+                        | FactoryParam::OwnedParam(ident)
+                        | FactoryParam::CopiedParam(ident) => params.push(ident),
+                    }
                 }
-                FactoryParam::Param(ident) => {
-                    call_params.push(quote!(&__self_params.#ident));
+                quote! {
+                    ::#facet_crate::FacetAlternate {
+                        method: stringify!(#method_ident),
+                        condition: #condition,
+                        dependencies: ::std::vec![ #( stringify!(#dependencies), )* ],
+                        params: ::std::vec![ #( stringify!(#params), )* ],
+                    }
                 }
+            });
+        nodes.push(quote! {
+            ::#facet_crate::FacetNode {
+                name: stringify!(#facet_ident),
+                dependencies: ::std::vec![ #( stringify!(#dependencies), )* ],
+                weak_dependencies: ::std::vec![ #( stringify!(#weak_dependencies), )* ],
+                params: ::std::vec![ #( stringify!(#params), )* ],
+                consumers: ::std::vec::Vec::new(),
+                alternates: ::std::vec![ #( #facet_alternates, )* ],
             }
-        }
+        });
+    }
 
-        let maybe_dot_await_factory = asyncness.maybe(quote!(.await));
+    // The facets of the extern factory that this factory doesn't build
+    // itself are part of its graph.
+    let extern_nodes = params.extern_factory.as_ref().map(|extern_factory| {
+        let path = &extern_factory.path;
+        quote! {
+            for node in <#path>::facet_graph().facets {
+                if !facets.iter().any(|facet| facet.name == node.name) {
+                    facets.push(node);
+                }
+            }
+        }
+    });
+    let facets_mut = extern_nodes.as_ref().map(|_| quote!(mut));
+
+    quote! {
+        impl #factory_ty {
+            /// Describe the facets this factory can build and their
+            /// dependencies.
+            pub fn facet_graph() -> ::#facet_crate::FacetGraph {
+                let #facets_mut facets: ::std::vec::Vec<::#facet_crate::FacetNode> =
+                    ::std::vec![ #( #nodes, )* ];
+                #extern_nodes
+                ::#facet_crate::FacetGraph {
+                    factory: stringify!(#factory_ty),
+                    facets,
+                }
+            }
+
+            /// Render the dependency graph of the facets this factory can
+            /// build in Graphviz DOT format.
+            pub fn dependency_dot() -> ::std::string::String {
+                Self::facet_graph().to_dot()
+            }
+        }
+    }
+}
+
+/// Generates the `validate` method, which checks that the factory has a
+/// method for every facet a container needs, and that the registry has the
+/// implementations that the factory methods of those facets would resolve.
+/// Delegating factories only provide `build`, so they aren't validated.
+fn gen_validate(params: &Params, factory_ty: &Ident, facets: &Facets) -> TokenStream {
+    if params.delegate.is_some() {
+        return quote!();
+    }
+    let facet_crate = format_ident!("{}", facet_crate_name());
+    let param_idents = &params.param_idents;
+    let param_types = &params.param_types;
+    // Only the parameters that name registry implementations are needed.
+    let ignore_params = if param_idents.is_empty() {
+        quote!()
+    } else {
+        quote!(let _ = (#( #param_idents, )*);)
+    };
+    let registry_checks =
+        facets
+            .iter()
+            .filter_map(|(facet_ident, facet_type, _, _, _, options)| {
+                let implementation = match options.registry.as_ref()? {
+                    RegistrySource::Param(param) => quote! {
+                        ::std::option::Option::Some(::std::convert::AsRef::<str>::as_ref(#param))
+                    },
+                    RegistrySource::Only => quote!(::std::option::Option::None),
+                };
+                Some(quote! {
+                    validator.check_registered(
+                        ::#facet_crate::registry(),
+                        stringify!(#facet_ident),
+                        ::std::marker::PhantomData::<#facet_type>,
+                        #implementation,
+                    );
+                })
+            });
+    quote! {
+        impl #factory_ty {
+            /// Check, without building anything, that this factory can
+            /// build a container with these parameters, returning a report
+            /// of the facets the container needs that the factory can't
+            /// provide.
+            pub fn validate<T>(
+                &self,
+                #( #param_idents: &#param_types ),*
+            ) -> ::#facet_crate::ValidationReport
+            where
+                T: ::#facet_crate::ContainerFacets,
+            {
+                #ignore_params
+                let mut validator = ::#facet_crate::Validator::new::<T>(Self::facet_graph());
+                #( #registry_checks )*
+                validator.finish()
+            }
+        }
+    }
+}
+
+fn gen_factory_builder(
+    params: &Params,
+    factory_ty: &Ident,
+    facets: &Facets,
+) -> Result<TokenStream, Error> {
+    let facet_idents = &facets.facet_idents;
+    let facet_params = &facets.facet_params;
+
+    let facet_crate = format_ident!("{}", facet_crate_name());
+    let builder_ident = format_ident!("{}Builder", factory_ty);
+
+    let is_async = Asyncness::any(facets.facet_asyncnesses.iter());
+
+    let facet_params_map = facet_idents
+        .iter()
+        .zip(facet_params)
+        .collect::<BTreeMap<_, _>>();
+
+    for facet_ident in facet_idents {
+        check_no_cycles(facet_ident, &facet_params_map)?;
+    }
+
+    if let Some(boxed) = facets.boxed_facets.first() {
+        if is_async == Asyncness::Asynchronous || params.delegate.is_some() {
+            return Err(Error::new(
+                boxed.ident.span(),
+                concat!(
+                    "boxed facets can only be built by synchronous factories ",
+                    "that don't delegate to another factory"
+                ),
+            ));
+        }
+    }
+
+    if let Some(hook) = facets
+        .post_build_hooks
+        .iter()
+        .find(|hook| hook.asyncness.is_async())
+    {
+        if is_async == Asyncness::Synchronous {
+            return Err(Error::new(
+                hook.ident.span(),
+                "async post-build hooks can only be used in async factories",
+            ));
+        }
+    }
+
+    check_factory_scoped(facets)?;
+
+    if let Some(owned) = facets.owned_params().first() {
+        if is_async == Asyncness::Asynchronous {
+            return Err(Error::new(
+                owned.span(),
+                concat!(
+                    "factory parameters can only be taken by value in synchronous ",
+                    "factories, as async factories may build lazy facets more than once"
+                ),
+            ));
+        }
+    }
+
+    if let Some(extern_factory) = &params.extern_factory {
+        let span = extern_factory.path.span();
+        if params.delegate.is_some() {
+            return Err(Error::new(
+                span,
+                "facet::factory can't have both an extern factory and an inner factory",
+            ));
+        }
+        if params.memoize {
+            return Err(Error::new(
+                span,
+                "facet::factory 'memoize' is not supported for factories with an extern factory",
+            ));
+        }
+        if is_async == Asyncness::Asynchronous {
+            return Err(Error::new(
+                span,
+                "extern factories can only be used by synchronous factories",
+            ));
+        }
+    }
+
+    if let Some((inner_ident, inner_ty)) = &params.delegate {
+        if params.memoize {
+            return Err(Error::new(
+                inner_ident.span(),
+                "facet::factory 'memoize' is not supported for delegating factories",
+            ));
+        }
+        return gen_delegating_factory(
+            &facet_crate,
+            factory_ty,
+            params,
+            facets,
+            inner_ident,
+            inner_ty,
+            is_async,
+        );
+    }
+
+    let (builder, container_builder) = match is_async {
+        Asyncness::Synchronous => (
+            gen_sync_factory_builder(&facet_crate, factory_ty, &builder_ident, params, facets)?,
+            gen_container_builder(
+                &facet_crate,
+                factory_ty,
+                params,
+                is_async,
+                quote!(T: ::#facet_crate::Buildable<#builder_ident<'factory>>,),
+            ),
+        ),
+        Asyncness::Asynchronous => (
+            gen_async_factory_builder(&facet_crate, factory_ty, &builder_ident, params, facets)?,
+            gen_container_builder(
+                &facet_crate,
+                factory_ty,
+                params,
+                is_async,
+                quote!(T: ::#facet_crate::AsyncBuildable<'builder, #builder_ident<'factory>>,),
+            ),
+        ),
+    };
+
+    Ok(quote! {
+        #builder
+
+        #container_builder
+    })
+}
+
+/// Generate the fluent builder returned by the factory's `builder` method,
+/// which allows the factory parameters to be given by name, and omitted if
+/// they have defaults.  `where_clause` gives the bounds on the container
+/// type `T` needed to build it with the factory.
+fn gen_container_builder(
+    facet_crate: &Ident,
+    factory_ty: &Ident,
+    params: &Params,
+    is_async: Asyncness,
+    where_clause: TokenStream,
+) -> TokenStream {
+    let container_builder_ident = format_ident!("{}ContainerBuilder", factory_ty);
+    let param_idents = &params.param_idents;
+    let param_types = &params.param_types;
+    let setter_docs = param_idents
+        .iter()
+        .map(|ident| format!("Set the `{}` parameter of the build.", ident));
+    let get_params = param_idents
+        .iter()
+        .zip(&params.param_defaults)
+        .map(|(ident, default)| {
+            let missing = match default {
+                Some(default) => quote!(#default),
+                None => quote! {
+                    return ::std::result::Result::Err(
+                        ::#facet_crate::FactoryError::MissingParameter {
+                            name: stringify!(#ident),
+                        }
+                    )
+                },
+            };
+            quote! {
+                let #ident = match self.#ident {
+                    ::std::option::Option::Some(#ident) => #ident,
+                    ::std::option::Option::None => #missing,
+                };
+            }
+        });
+    let container_builder_doc = format!(
+        "A build of a container of type `T` by [`{}`], with parameters given by name.",
+        factory_ty
+    );
+    let build = match is_async {
+        Asyncness::Synchronous => quote! {
+            /// Build the container.  This fails if any parameters without
+            /// defaults have not been given.
+            pub fn build(self) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
+            where
+                #where_clause
+            {
+                #( #get_params )*
+                self.factory.build::<T>(#( #param_idents, )*)
+            }
+        },
+        Asyncness::Asynchronous => quote! {
+            /// Build the container.  This fails if any parameters without
+            /// defaults have not been given.
+            pub async fn build<'builder>(
+                self,
+            ) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
+            where
+                #where_clause
+            {
+                #( #get_params )*
+                self.factory.build::<T>(#( #param_idents, )*).await
+            }
+        },
+    };
+
+    quote! {
+        #[doc = #container_builder_doc]
+        pub struct #container_builder_ident<'factory, T> {
+            factory: &'factory #factory_ty,
+            #( #param_idents: ::std::option::Option<#param_types>, )*
+            container: ::std::marker::PhantomData<fn() -> T>,
+        }
+
+        impl<'factory, T> #container_builder_ident<'factory, T> {
+            #(
+                #[doc = #setter_docs]
+                pub fn #param_idents(mut self, #param_idents: #param_types) -> Self {
+                    self.#param_idents = ::std::option::Option::Some(#param_idents);
+                    self
+                }
+            )*
+
+            #build
+        }
+
+        impl #factory_ty {
+            /// Start a build of a container from this factory, with the
+            /// parameters given by name rather than by position.
+            pub fn builder<T>(&self) -> #container_builder_ident<'_, T> {
+                #container_builder_ident {
+                    factory: self,
+                    #( #param_idents: ::std::option::Option::None, )*
+                    container: ::std::marker::PhantomData,
+                }
+            }
+        }
+    }
+}
+
+fn gen_sync_factory_builder(
+    facet_crate: &Ident,
+    factory_ty: &Ident,
+    builder_ident: &Ident,
+    params: &Params,
+    facets: &Facets,
+) -> Result<TokenStream, Error> {
+    let builder_facets_ident = format_ident!("{}BuilderFacets", factory_ty);
+    let builder_weak_facets_ident = format_ident!("{}BuilderWeakFacets", factory_ty);
+    let param_idents = &params.param_idents;
+    let param_types = &params.param_types;
+    let facet_idents = &facets.facet_idents;
+    let facet_types = &facets.facet_types;
+    let mut facet_types_map = facet_idents
+        .iter()
+        .zip(facet_types)
+        .collect::<BTreeMap<_, _>>();
+    let mut facet_options_map = facet_idents
+        .iter()
+        .zip(&facets.facet_options)
+        .collect::<BTreeMap<_, _>>();
+
+    // Facets that are built by an extern factory are built by its builder,
+    // which this factory's builder holds, so this builder forwards them.
+    let extern_facets = facets.extern_facets(params);
+    let extern_options = MethodOptions::default();
+    for (ident, ty) in &extern_facets {
+        facet_types_map.insert(ident, ty);
+        facet_options_map.insert(ident, &extern_options);
+    }
+    let (extern_field, extern_init, extern_post_build) = match &params.extern_factory {
+        Some(extern_factory) => {
+            let path = &extern_factory.path;
+            (
+                quote!(extern_builder: <#path as ::#facet_crate::FactoryBuilder<'factory>>::Builder,),
+                quote! {
+                    extern_builder: ::std::convert::AsRef::<#path>::as_ref(self).__facet_builder(
+                        #( ::std::clone::Clone::clone(&#param_idents), )*
+                    ),
+                },
+                quote!(::#facet_crate::PostBuild::post_build(&mut self.extern_builder)?;),
+            )
+        }
+        None => (quote!(), quote!(), quote!()),
+    };
+    let weak_targets = facets.weak_targets()?;
+    let weak_facets = gen_weak_facets(&builder_weak_facets_ident, &weak_targets);
+    let weak_target_idents = weak_targets.keys().collect::<Vec<_>>();
+    let post_build_hooks = gen_post_build_hooks(facet_crate, params, facets, quote!(self.facets));
+    let builder_needed_ident = format_ident!("{}BuilderNeeded", factory_ty);
+    let builder_state_ident = format_ident!("{}BuilderState", factory_ty);
+
+    // Parameters taken by value are held until the factory method that
+    // consumes them is called.  They can't be compared by rebuilds, so they
+    // don't need to implement `PartialEq`.
+    let owned_params = facets.owned_params();
+    let mut param_field_types = Vec::new();
+    let mut param_field_inits = Vec::new();
+    let mut compared_param_types = Vec::new();
+    for (param_ident, param_type) in param_idents.iter().zip(param_types) {
+        if owned_params.contains(param_ident) {
+            param_field_types.push(quote!(::#facet_crate::OwnedParam<#param_type>));
+            param_field_inits.push(quote!(::#facet_crate::OwnedParam::new(#param_ident)));
+        } else {
+            param_field_types.push(quote!(#param_type));
+            param_field_inits.push(quote!(#param_ident));
+            if !is_facebook_init(param_type) {
+                compared_param_types.push(param_type);
+            }
+        }
+    }
+    let (check_changed, reuse_unchanged) = gen_reuse_unchanged(
+        params,
+        facets,
+        quote!(existing.state().facets),
+        quote!(existing.state().facets),
+    );
+
+    let build_param_impls = gen_build_param_impls(
+        facet_crate,
+        builder_ident,
+        params,
+        facets,
+        quote!(self.facets),
+    );
+
+    let mut builder_impls = Vec::new();
+
+    for (_, facet_type) in &extern_facets {
+        builder_impls.push(quote_spanned! {facet_type.span()=>
+            impl ::#facet_crate::Builder<#facet_type> for #builder_ident<'_> {
+                fn build(&mut self) -> ::std::result::Result<
+                    #facet_type,
+                    ::#facet_crate::FactoryError,
+                > {
+                    ::#facet_crate::Builder::<#facet_type>::build(&mut self.extern_builder)
+                }
+            }
+
+            impl ::#facet_crate::InjectFacet<#facet_type> for #builder_ident<'_> {
+                fn inject(&mut self, facet: #facet_type) {
+                    ::#facet_crate::InjectFacet::<#facet_type>::inject(
+                        &mut self.extern_builder,
+                        facet,
+                    );
+                }
+            }
+        });
+    }
+
+    for (facet_ident, facet_type, fallibility, asyncness, facet_params, options) in facets.iter() {
+        let (call_params, make_facets) = gen_sync_call_params(
+            facet_crate,
+            facet_ident,
+            facet_params,
+            &facet_types_map,
+            &facet_options_map,
+        )?;
+
+        let maybe_set_weak = if weak_targets.contains_key(facet_ident) {
+            quote!(self.weak.#facet_ident.set(&#facet_ident);)
+        } else {
+            quote!()
+        };
+
+        if asyncness == Asyncness::Asynchronous {
+            panic!("should not generate sync builder for async factory");
+        }
+        let maybe_map_err = fallibility.maybe(quote! {
+            .map_err(|e| ::#facet_crate::FactoryError::FacetBuildFailed {
+                name: stringify!(#facet_ident),
+                source: e.into(),
+            })?
+        });
+
+        let call = gen_factory_call(
+            facet_crate,
+            facet_ident,
+            gen_method_call(params, quote!(self.factory), facet_ident, &call_params),
+            asyncness,
+            fallibility,
+            options,
+        );
+        let call = quote! {{
+            let __start = ::std::time::Instant::now();
+            let facet = #call #maybe_map_err;
+            self.recorder.record(stringify!(#facet_ident), __start.elapsed());
+            facet
+        }};
+        let build_facet = match options.scope {
+            Scope::Build => quote! {
+                #( #make_facets )*
+                let #facet_ident = #call;
+            },
+            Scope::Factory => quote! {
+                let __facet_cache = ::#facet_crate::FactoryScope::facet_cache(self.factory);
+                let #facet_ident = match __facet_cache
+                    .get::<#facet_type>(stringify!(#facet_ident))
+                {
+                    Some(facet) => facet,
+                    None => {
+                        #( #make_facets )*
+                        let facet = #call;
+                        __facet_cache.insert(stringify!(#facet_ident), facet)
+                    }
+                };
+            },
+        };
+
+        let facet_builder_type = builder_type(facet_crate, facet_type, options);
+        let built_facet = wrap_keyed(facet_crate, options, quote!(facet.clone()));
+        let new_facet = wrap_keyed(facet_crate, options, quote!(#facet_ident));
+
+        // Keyed facets can't be injected, as other facets may have the same
+        // type.
+        if options.key.is_none() {
+            let maybe_set_injected_weak = if weak_targets.contains_key(facet_ident) {
+                quote!(self.weak.#facet_ident.set(&facet);)
+            } else {
+                quote!()
+            };
+            builder_impls.push(quote! {
+                impl ::#facet_crate::InjectFacet<#facet_type> for #builder_ident<'_> {
+                    fn inject(&mut self, facet: #facet_type) {
+                        #maybe_set_injected_weak
+                        self.facets.#facet_ident = Some(facet);
+                    }
+                }
+            });
+        }
+
+        builder_impls.push(quote! {
+
+            impl ::#facet_crate::Builder<#facet_builder_type> for #builder_ident<'_> {
+
+                fn build<'builder>(&'builder mut self) -> ::std::result::Result<
+                    #facet_builder_type,
+                    ::#facet_crate::FactoryError,
+                >  {
+                    if let Some(facet) = self.facets.#facet_ident.as_ref() {
+                        return Ok(#built_facet);
+                    }
+                    use ::#facet_crate::Builder as _;
+                    #build_facet
+                    debug_assert!(self.facets.#facet_ident.is_none());
+                    self.facets.#facet_ident = Some(#facet_ident.clone());
+                    #maybe_set_weak
+                    self.order.push(stringify!(#facet_ident));
+                    Ok(#new_facet)
+                }
+            }
+
+        })
+    }
+
+    let sync_build = if params.memoize {
+        // The facets built for each set of parameters are cached, along with
+        // the order they were built in, so that later builds with the same
+        // parameters only build the facets that weren't needed before.
+        let cached_indexes = (0..facet_idents.len()).map(syn::Index::from);
+        let facets_to_cache = facet_idents.iter();
+        quote! {
+            let __memo_key = ( #( ::std::clone::Clone::clone(&#param_idents), )* );
+            let __build_cache = ::#facet_crate::FactoryMemo::build_cache(self);
+            let mut builder = #builder_ident {
+                factory: self,
+                #extern_init
+                facets: #builder_facets_ident::new(#( #param_idents, )*),
+                weak: #builder_weak_facets_ident::default(),
+                order: ::std::vec::Vec::new(),
+                recorder: ::#facet_crate::BuildRecorder::default(),
+            };
+            if let Some((order, cached)) = __build_cache.get::<
+                ( #( #param_types, )* ),
+                (
+                    ::std::vec::Vec<&'static str>,
+                    ( #( ::std::option::Option<#facet_types>, )* ),
+                ),
+            >(&__memo_key) {
+                #( builder.facets.#facet_idents = cached.#cached_indexes; )*
+                builder.order = order;
+                #(
+                    if let Some(facet) = builder.facets.#weak_target_idents.as_ref() {
+                        builder.weak.#weak_target_idents.set(facet);
+                    }
+                )*
+            }
+            let container = T::build(&mut builder)?;
+            ::#facet_crate::PostBuild::post_build(&mut builder)?;
+            __build_cache.insert(
+                __memo_key,
+                (
+                    builder.order.clone(),
+                    ( #( builder.facets.#facets_to_cache.clone(), )* ),
+                ),
+            );
+            Ok(container)
+        }
+    } else {
+        quote! {
+            let mut builder = #builder_ident {
+                factory: self,
+                #extern_init
+                facets: #builder_facets_ident::new(#( #param_idents, )*),
+                weak: #builder_weak_facets_ident::default(),
+                order: ::std::vec::Vec::new(),
+                recorder: ::#facet_crate::BuildRecorder::default(),
+            };
+            let container = T::build(&mut builder)?;
+            ::#facet_crate::PostBuild::post_build(&mut builder)?;
+            Ok(container)
+        }
+    };
+
+    let (parallel_defs, parallel_build) = gen_parallel_build(
+        facet_crate,
+        factory_ty,
+        params,
+        quote! {
+            #builder_ident {
+                factory: self,
+                facets: #builder_facets_ident::new(#( #param_idents, )*),
+                weak: #builder_weak_facets_ident::default(),
+                order: ::std::vec::Vec::new(),
+                recorder: ::#facet_crate::BuildRecorder::default(),
+            }
+        },
+        facets,
+        &facet_types_map,
+        &facet_options_map,
+    )?;
+
+    // Boxed facets are built afresh each time they are needed, as they are
+    // owned by the container that needs them.
+    for boxed in &facets.boxed_facets {
+        let facet_ident = &boxed.ident;
+        let facet_type = &boxed.ty;
+        let (call_params, make_facets) = gen_sync_call_params(
+            facet_crate,
+            facet_ident,
+            &boxed.params,
+            &facet_types_map,
+            &facet_options_map,
+        )?;
+        let maybe_map_err = boxed.fallibility.maybe(quote! {
+            .map_err(|e| ::#facet_crate::FactoryError::FacetBuildFailed {
+                name: stringify!(#facet_ident),
+                source: e.into(),
+            })?
+        });
+        let call = gen_factory_call(
+            facet_crate,
+            facet_ident,
+            gen_method_call(params, quote!(self.factory), facet_ident, &call_params),
+            Asyncness::Synchronous,
+            boxed.fallibility,
+            &boxed.options,
+        );
+        builder_impls.push(quote! {
+            impl ::#facet_crate::Builder<#facet_type> for #builder_ident<'_> {
+                fn build(&mut self) -> ::std::result::Result<
+                    #facet_type,
+                    ::#facet_crate::FactoryError,
+                > {
+                    use ::#facet_crate::Builder as _;
+                    #( #make_facets )*
+                    let __start = ::std::time::Instant::now();
+                    let facet = #call #maybe_map_err;
+                    self.recorder.record(stringify!(#facet_ident), __start.elapsed());
+                    Ok(facet)
+                }
+            }
+        });
+    }
+
+    // Factories with an extern factory can't build in parallel, as facets
+    // of the extern factory must be built by its builder, and can't rebuild
+    // containers, as the extern builder doesn't keep its facets.
+    let (parallel_defs, build_parallel, rebuild_methods) = if params.extern_factory.is_some() {
+        (quote!(), quote!(), quote!())
+    } else {
+        (
+            parallel_defs,
+            quote! {
+                /// Build an instance of a container from this factory, building
+                /// facets that don't depend on each other in parallel on scoped
+                /// threads.
+                pub fn build_parallel<'factory, T>(
+                    &'factory self,
+                    #( #param_idents: #param_types ),*
+                ) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
+                where
+                    T: ::#facet_crate::Buildable<#builder_ident<'factory>>
+                        + ::#facet_crate::ParallelBuildable<#builder_needed_ident>,
+                    // The factory, parameters and facets are shared with the
+                    // threads that build the facets.  The bounds are
+                    // higher-ranked so that they are only checked when this
+                    // method is used.
+                    for<'a> #factory_ty: ::std::marker::Sync,
+                    for<'a> #builder_facets_ident: ::std::marker::Sync,
+                    for<'a> #builder_weak_facets_ident: ::std::marker::Sync,
+                    #( for<'a> #facet_types: ::std::marker::Send, )*
+                {
+                    #parallel_build
+                }
+            },
+            quote! {
+                /// Build an instance of a container from this factory that can
+                /// be rebuilt incrementally when the parameters change.
+                pub fn build_rebuildable<'factory, T>(
+                    &'factory self,
+                    #( #param_idents: #param_types ),*
+                ) -> ::std::result::Result<
+                    ::#facet_crate::Rebuildable<T, #builder_state_ident>,
+                    ::#facet_crate::FactoryError,
+                >
+                where
+                    T: ::#facet_crate::Buildable<#builder_ident<'factory>>,
+                {
+                    let mut builder = #builder_ident {
+                        factory: self,
+                            facets: #builder_facets_ident::new(#( #param_idents, )*),
+                        weak: #builder_weak_facets_ident::default(),
+                        order: ::std::vec::Vec::new(),
+                        recorder: ::#facet_crate::BuildRecorder::default(),
+                    };
+                    let container = T::build(&mut builder)?;
+                    ::#facet_crate::PostBuild::post_build(&mut builder)?;
+                    Ok(::#facet_crate::Rebuildable::new(
+                        container,
+                        #builder_state_ident {
+                            facets: builder.facets,
+                            order: builder.order,
+                        },
+                    ))
+                }
+
+                /// Rebuild a container with new parameters.  Facets that don't
+                /// depend on any of the parameters that have changed, either
+                /// directly or through their dependencies, are reused from the
+                /// existing container's build rather than built again.
+                pub fn rebuild<'factory, T>(
+                    &'factory self,
+                    existing: &::#facet_crate::Rebuildable<T, #builder_state_ident>,
+                    #( #param_idents: #param_types ),*
+                ) -> ::std::result::Result<
+                    ::#facet_crate::Rebuildable<T, #builder_state_ident>,
+                    ::#facet_crate::FactoryError,
+                >
+                where
+                    T: ::#facet_crate::Buildable<#builder_ident<'factory>>,
+                    // Parameters can only be compared if they implement
+                    // `PartialEq`.  The bound is higher-ranked so that it is
+                    // only checked when this method is used.
+                    #( for<'a> #compared_param_types: ::std::cmp::PartialEq, )*
+                {
+                    #check_changed
+                    let mut facets = #builder_facets_ident::new(#( #param_idents, )*);
+                    #reuse_unchanged
+                    let order = existing
+                        .state()
+                        .order
+                        .iter()
+                        .copied()
+                        .filter(|name| match *name {
+                            #( stringify!(#facet_idents) => facets.#facet_idents.is_some(), )*
+                            _ => false,
+                        })
+                        .collect();
+                    let mut builder = #builder_ident {
+                        factory: self,
+                            facets,
+                        weak: #builder_weak_facets_ident::default(),
+                        order,
+                        recorder: ::#facet_crate::BuildRecorder::default(),
+                    };
+                    #(
+                        if let Some(facet) = builder.facets.#weak_target_idents.as_ref() {
+                            builder.weak.#weak_target_idents.set(facet);
+                        }
+                    )*
+                    let container = T::build(&mut builder)?;
+                    ::#facet_crate::PostBuild::post_build(&mut builder)?;
+                    Ok(::#facet_crate::Rebuildable::new(
+                        container,
+                        #builder_state_ident {
+                            facets: builder.facets,
+                            order: builder.order,
+                        },
+                    ))
+                }
+            },
+        )
+    };
+
+    let builder = quote! {
+        #[doc(hidden)]
+        pub struct #builder_facets_ident {
+            #(
+                #param_idents: #param_field_types,
+            )*
+            #(
+                #facet_idents: ::std::option::Option<#facet_types>,
+            )*
+        }
+
+        impl #builder_facets_ident {
+            #[doc(hidden)]
+            pub fn new( #( #param_idents: #param_types, )* ) -> Self {
+                Self {
+                    #( #param_idents: #param_field_inits, )*
+                    #(
+                        #facet_idents: ::std::default::Default::default(),
+                    )*
+                }
+            }
+        }
+
+        #weak_facets
+
+        #parallel_defs
+
+        #(
+            #builder_impls
+        )*
+
+        #[doc(hidden)]
+        pub struct #builder_ident<'factory> {
+            factory: &'factory #factory_ty,
+            #extern_field
+            facets: #builder_facets_ident,
+            weak: #builder_weak_facets_ident,
+            order: ::std::vec::Vec<&'static str>,
+            recorder: ::#facet_crate::BuildRecorder,
+        }
+
+        impl ::#facet_crate::BuildOrder for #builder_ident<'_> {
+            fn build_order(&self) -> ::std::vec::Vec<&'static str> {
+                self.order.clone()
+            }
+        }
+
+        impl ::#facet_crate::PostBuild for #builder_ident<'_> {
+            fn post_build(
+                &mut self
+            ) -> ::std::result::Result<(), ::#facet_crate::FactoryError> {
+                #extern_post_build
+                #post_build_hooks
+                Ok(())
+            }
+        }
+
+        #build_param_impls
+
+        impl<'factory> ::#facet_crate::FactoryBuilder<'factory> for #factory_ty {
+            type Builder = #builder_ident<'factory>;
+        }
+
+        // The parameters and facets of a build, kept so that the container
+        // can be rebuilt incrementally.
+        #[doc(hidden)]
+        pub struct #builder_state_ident {
+            facets: #builder_facets_ident,
+            order: ::std::vec::Vec<&'static str>,
+        }
+
+        impl #factory_ty {
+            // Start a build by this factory, for factories that use it as
+            // their extern factory.
+            #[doc(hidden)]
+            pub fn __facet_builder<'factory>(
+                &'factory self,
+                #( #param_idents: #param_types ),*
+            ) -> #builder_ident<'factory> {
+                #builder_ident {
+                    factory: self,
+                    #extern_init
+                    facets: #builder_facets_ident::new(#( #param_idents, )*),
+                    weak: #builder_weak_facets_ident::default(),
+                    order: ::std::vec::Vec::new(),
+                    recorder: ::#facet_crate::BuildRecorder::default(),
+                }
+            }
+
+            /// Build an instance of a container from this factory.
+            pub fn build<'factory, T>(
+                &'factory self,
+                #( #param_idents: #param_types ),*
+            ) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
+            where
+                T: ::#facet_crate::Buildable<#builder_ident<'factory>>,
+            {
+                #sync_build
+            }
+
+            #build_parallel
+
+            /// Build an instance of a container from this factory, and
+            /// report how long each facet took to build.
+            pub fn build_instrumented<'factory, T>(
+                &'factory self,
+                #( #param_idents: #param_types ),*
+            ) -> ::std::result::Result<(T, ::#facet_crate::BuildReport), ::#facet_crate::FactoryError>
+            where
+                T: ::#facet_crate::Buildable<#builder_ident<'factory>>,
+            {
+                let start = ::std::time::Instant::now();
+                let mut builder = #builder_ident {
+                    factory: self,
+                    #extern_init
+                    facets: #builder_facets_ident::new(#( #param_idents, )*),
+                    weak: #builder_weak_facets_ident::default(),
+                    order: ::std::vec::Vec::new(),
+                    recorder: ::#facet_crate::BuildRecorder::default(),
+                };
+                let container = T::build(&mut builder)?;
+                ::#facet_crate::PostBuild::post_build(&mut builder)?;
+                Ok((container, builder.recorder.report(start.elapsed())))
+            }
+
+            /// Build only the given facet fields of a container from this
+            /// factory.
+            pub fn build_partial<'factory, T>(
+                &'factory self,
+                #( #param_idents: #param_types, )*
+                facets: &::#facet_crate::FacetSet,
+            ) -> ::std::result::Result<T::Partial, ::#facet_crate::FactoryError>
+            where
+                T: ::#facet_crate::PartialBuildable<#builder_ident<'factory>>,
+            {
+                let mut builder = #builder_ident {
+                    factory: self,
+                    #extern_init
+                    facets: #builder_facets_ident::new(#( #param_idents, )*),
+                    weak: #builder_weak_facets_ident::default(),
+                    order: ::std::vec::Vec::new(),
+                    recorder: ::#facet_crate::BuildRecorder::default(),
+                };
+                T::build_partial(&mut builder, facets)
+            }
+
+            /// Start a build of an instance of a container from this
+            /// factory, which can be given facets that have already been
+            /// built.
+            pub fn build_with<'factory, T>(
+                &'factory self,
+                #( #param_idents: #param_types ),*
+            ) -> ::#facet_crate::BuildWith<#builder_ident<'factory>, T>
+            where
+                T: ::#facet_crate::Buildable<#builder_ident<'factory>>,
+            {
+                ::#facet_crate::BuildWith::new(#builder_ident {
+                    factory: self,
+                    #extern_init
+                    facets: #builder_facets_ident::new(#( #param_idents, )*),
+                    weak: #builder_weak_facets_ident::default(),
+                    order: ::std::vec::Vec::new(),
+                    recorder: ::#facet_crate::BuildRecorder::default(),
+                })
+            }
+
+            #rebuild_methods
+        }
+    };
+
+    Ok(builder)
+}
+
+/// Generates the support for parallel builds by a synchronous factory: the
+/// set of needed facets that containers mark, and the body of the
+/// `build_parallel` method.  The needed facets are built in rounds, with
+/// each round building every needed facet whose dependencies have all been
+/// built on its own scoped thread.  The container is then built as normal,
+/// finding its facets already built.
+fn gen_parallel_build(
+    facet_crate: &Ident,
+    factory_ty: &Ident,
+    params: &Params,
+    builder: TokenStream,
+    facets: &Facets,
+    facet_types_map: &BTreeMap<&Ident, &Type>,
+    facet_options_map: &BTreeMap<&Ident, &MethodOptions>,
+) -> Result<(TokenStream, TokenStream), Error> {
+    let builder_needed_ident = format_ident!("{}BuilderNeeded", factory_ty);
+    let facet_idents = &facets.facet_idents;
+    let weak_targets = facets.weak_targets()?;
+
+    let need_deps = |facet_params: &[FactoryParam]| -> Result<Vec<TokenStream>, Error> {
+        let mut need_deps = Vec::new();
+        for facet_param in facet_params {
+            if let FactoryParam::Facet(ident, _) = facet_param {
+                let param_type = facet_types_map.get(ident).ok_or_else(|| {
+                    unrecognised_facet_name(ident, facet_types_map.keys().copied())
+                })?;
+                let param_builder_type =
+                    builder_type(facet_crate, param_type, facet_options_map[ident]);
+                need_deps.push(quote! {
+                    <Self as ::#facet_crate::NeedFacet<#param_builder_type>>::need(self);
+                });
+            }
+        }
+        Ok(need_deps)
+    };
+
+    let mut need_impls = Vec::new();
+    let mut dependents = BTreeMap::new();
+    let mut handle_idents = Vec::new();
+    let mut spawn_builds = Vec::new();
+    let mut store_facets = Vec::new();
+
+    for (facet_ident, facet_type, fallibility, _, facet_params, options) in facets.iter() {
+        let facet_builder_type = builder_type(facet_crate, facet_type, options);
+        let need_deps = need_deps(facet_params)?;
+        need_impls.push(quote! {
+            impl ::#facet_crate::NeedFacet<#facet_builder_type> for #builder_needed_ident {
+                fn need(&mut self) {
+                    if self.#facet_ident {
+                        return;
+                    }
+                    self.#facet_ident = true;
+                    #( #need_deps )*
+                }
+            }
+        });
+
+        let mut dep_idents = Vec::new();
+        let mut dep_types = Vec::new();
+        let mut call_params = Vec::new();
+        for facet_param in facet_params {
+            match facet_param {
+                FactoryParam::Facet(ident, _) => {
+                    dependents
+                        .entry(ident)
+                        .or_insert_with(Vec::new)
+                        .push(facet_ident);
+                    dep_idents.push(ident);
+                    dep_types.push(facet_types_map[ident]);
+                    call_params.push(quote!(&#ident));
+                }
+                FactoryParam::WeakFacet(ident, _) => {
+                    call_params.push(quote!(&weak.#ident));
+                }
+                FactoryParam::Param(ident) => {
+                    call_params.push(quote!(&facets.#ident));
+                }
+                FactoryParam::OwnedParam(ident) => {
+                    call_params.push(quote!(facets.#ident.take(stringify!(#ident))));
+                }
+                FactoryParam::CopiedParam(ident) => {
+                    call_params.push(quote!(facets.#ident));
+                }
+            }
+        }
+
+        let maybe_map_err = fallibility.maybe(quote! {
+            .map_err(|e| ::#facet_crate::FactoryError::FacetBuildFailed {
+                name: stringify!(#facet_ident),
+                source: e.into(),
+            })?
+        });
+        let call = gen_factory_call(
+            facet_crate,
+            facet_ident,
+            gen_method_call(params, quote!(factory), facet_ident, &call_params),
+            Asyncness::Synchronous,
+            fallibility,
+            options,
+        );
+        let call = quote! {{
+            // The dependencies were built in an earlier round.
+            #(
+                let #dep_idents: #dep_types = facets
+                    .#dep_idents
+                    .clone()
+                    .expect("bug in #[facet::factory]: dependency not built");
+            )*
+            let __start = ::std::time::Instant::now();
+            let facet = #call #maybe_map_err;
+            recorder.record(stringify!(#facet_ident), __start.elapsed());
+            facet
+        }};
+        let build_facet = match options.scope {
+            Scope::Build => call,
+            Scope::Factory => quote! {{
+                let __facet_cache = ::#facet_crate::FactoryScope::facet_cache(factory);
+                match __facet_cache.get::<#facet_type>(stringify!(#facet_ident)) {
+                    Some(facet) => facet,
+                    None => __facet_cache.insert(stringify!(#facet_ident), #call),
+                }
+            }},
+        };
+
+        let handle_ident = format_ident!("__build_{}", facet_ident);
+        spawn_builds.push(quote! {
+            let #handle_ident = if needed.#facet_ident
+                && facets.#facet_ident.is_none()
+                #( && facets.#dep_idents.is_some() )*
+            {
+                Some(scope.spawn(move || {
+                    Ok::<_, ::#facet_crate::FactoryError>(#build_facet)
+                }))
+            } else {
+                None
+            };
+        });
+        let maybe_set_weak = if weak_targets.contains_key(facet_ident) {
+            quote!(builder.weak.#facet_ident.set(&facet);)
+        } else {
+            quote!()
+        };
+        store_facets.push(quote! {
+            if let Some(facet) = #handle_ident {
+                let facet = facet.map_err(|e| {
+                    e.with_needed_by(|facet| needed.needed_by(facet))
+                })?;
+                #maybe_set_weak
+                builder.facets.#facet_ident = Some(facet);
+                builder.order.push(stringify!(#facet_ident));
+                __built = true;
+            }
+        });
+        handle_idents.push(handle_ident);
+    }
+
+    // Boxed facets are built with the container, but their dependencies can
+    // be built in parallel beforehand.
+    for boxed in &facets.boxed_facets {
+        let facet_type = &boxed.ty;
+        let need_deps = need_deps(&boxed.params)?;
+        need_impls.push(quote! {
+            impl ::#facet_crate::NeedFacet<#facet_type> for #builder_needed_ident {
+                fn need(&mut self) {
+                    #( #need_deps )*
+                }
+            }
+        });
+    }
+
+    let find_needed_by = dependents.iter().map(|(ident, dependents)| {
+        quote! {
+            if facet == stringify!(#ident) {
+                #(
+                    if self.#dependents {
+                        return Some(stringify!(#dependents));
+                    }
+                )*
+            }
+        }
+    });
+
+    let defs = quote! {
+        #[doc(hidden)]
+        #[derive(Default)]
+        pub struct #builder_needed_ident {
+            #(
+                #facet_idents: bool,
+            )*
+        }
+
+        impl #builder_needed_ident {
+            // Returns a needed facet that depends on the named facet.
+            fn needed_by(&self, facet: &str) -> ::std::option::Option<&'static str> {
+                #( #find_needed_by )*
+                None
+            }
+        }
+
+        #( #need_impls )*
+    };
+
+    // Factories that only build boxed facets have nothing to build in
+    // parallel, as boxed facets are built by the container.
+    if handle_idents.is_empty() {
+        let body = quote! {
+            let mut builder = #builder;
+            let container = T::build(&mut builder)?;
+            ::#facet_crate::PostBuild::post_build(&mut builder)?;
+            Ok(container)
+        };
+        return Ok((defs, body));
+    }
+
+    let body = quote! {
+        let mut builder = #builder;
+        let mut needed = #builder_needed_ident::default();
+        <T as ::#facet_crate::ParallelBuildable<#builder_needed_ident>>::mark_needed(&mut needed);
+        loop {
+            let factory = builder.factory;
+            let facets = &builder.facets;
+            let weak = &builder.weak;
+            let recorder = &builder.recorder;
+            let ( #( #handle_idents, )* ) = ::std::thread::scope(|scope| {
+                #( #spawn_builds )*
+                (
+                    #(
+                        #handle_idents.map(|handle| {
+                            handle
+                                .join()
+                                .unwrap_or_else(|payload| ::std::panic::resume_unwind(payload))
+                        }),
+                    )*
+                )
+            });
+            let mut __built = false;
+            #( #store_facets )*
+            if !__built {
+                break;
+            }
+        }
+        let container = T::build(&mut builder)?;
+        ::#facet_crate::PostBuild::post_build(&mut builder)?;
+        Ok(container)
+    };
+
+    Ok((defs, body))
+}
+
+/// Generates the arguments for a call to a factory method in a synchronous
+/// builder, and the statements that build the facets it depends on.
+fn gen_sync_call_params(
+    facet_crate: &Ident,
+    facet_ident: &Ident,
+    facet_params: &[FactoryParam],
+    facet_types_map: &BTreeMap<&Ident, &Type>,
+    facet_options_map: &BTreeMap<&Ident, &MethodOptions>,
+) -> Result<(Vec<TokenStream>, Vec<TokenStream>), Error> {
+    let mut call_params = Vec::new();
+    let mut make_facets = Vec::new();
+
+    for facet_param in facet_params {
+        match facet_param {
+            FactoryParam::Facet(ident, _) => {
+                let param_type = facet_types_map.get(ident).ok_or_else(|| {
+                    unrecognised_facet_name(ident, facet_types_map.keys().copied())
+                })?;
+                let param_options = facet_options_map[ident];
+                if param_options.key.is_some() {
+                    let param_builder_type = builder_type(facet_crate, param_type, param_options);
+                    make_facets.push(quote! {
+                        let #ident: #param_type = ::#facet_crate::Keyed::into_inner(
+                            <Self as ::#facet_crate::Builder<#param_builder_type>>::build(
+                                self
+                            )
+                            .map_err(|e| e.needed_by(stringify!(#facet_ident)))?
+                        );
+                    });
+                } else {
+                    make_facets.push(quote! {
+                        let #ident: #param_type = self
+                            .build()
+                            .map_err(|e| e.needed_by(stringify!(#facet_ident)))?;
+                    });
+                }
+                call_params.push(quote!(&#ident));
+            }
+            FactoryParam::WeakFacet(ident, _) => {
+                call_params.push(quote!(&self.weak.#ident));
+            }
+            FactoryParam::Param(ident) => {
+                call_params.push(quote!(&self.facets.#ident));
+            }
+            FactoryParam::OwnedParam(ident) => {
+                call_params.push(quote!(self.facets.#ident.take(stringify!(#ident))));
+            }
+            FactoryParam::CopiedParam(ident) => {
+                call_params.push(quote!(self.facets.#ident));
+            }
+        }
+    }
+
+    Ok((call_params, make_facets))
+}
+
+fn gen_async_factory_builder(
+    facet_crate: &Ident,
+    factory_ty: &Ident,
+    builder_ident: &Ident,
+    params: &Params,
+    facets: &Facets,
+) -> Result<TokenStream, Error> {
+    let builder_facets_ident = format_ident!("{}BuilderFacets", factory_ty);
+    let builder_facets_needed_ident = format_ident!("{}BuilderFacetsNeeded", factory_ty);
+    let builder_params_ident = format_ident!("{}BuilderParams", factory_ty);
+    let builder_weak_facets_ident = format_ident!("{}BuilderWeakFacets", factory_ty);
+
+    let param_idents = &params.param_idents;
+    let param_types = &params.param_types;
+    let facet_idents = &facets.facet_idents;
+    let facet_types = &facets.facet_types;
+    let facet_types_map = facet_idents
+        .iter()
+        .zip(facet_types)
+        .collect::<BTreeMap<_, _>>();
+    let facet_options_map = facet_idents
+        .iter()
+        .zip(&facets.facet_options)
+        .collect::<BTreeMap<_, _>>();
+
+    let weak_targets = facets.weak_targets()?;
+    let weak_facets = gen_weak_facets(&builder_weak_facets_ident, &weak_targets);
+    let weak_target_idents = weak_targets.keys().collect::<Vec<_>>();
+    let post_build_hooks = gen_post_build_hooks(facet_crate, params, facets, quote!(self.params));
+    let builder_state_ident = format_ident!("{}BuilderState", factory_ty);
+    let compared_param_types = param_types
+        .iter()
+        .filter(|param_type| !is_facebook_init(param_type));
+    let (check_changed, reuse_unchanged) = gen_reuse_unchanged(
+        params,
+        facets,
+        quote!(existing.state().params),
+        quote!(existing.state().facets),
+    );
+
+    let build_param_impls = gen_build_param_impls(
+        facet_crate,
+        builder_ident,
+        params,
+        facets,
+        quote!(self.params),
+    );
+
+    let mut heads: BTreeSet<_> = facet_idents.iter().collect();
+    let mut facet_build_futs = BTreeMap::new();
+    let mut facet_build_graph = BTreeMap::new();
+    let mut builder_impls = Vec::new();
+    let mut build_facets = Vec::new();
+    let mut store_facets = Vec::new();
+
+    for (facet_ident, facet_type, fallibility, asyncness, facet_params, options) in facets.iter() {
+        let mut dependent_facets = Vec::new();
+        let mut mark_facets_needed = Vec::new();
+        let mut call_params = Vec::new();
+        let mut deps = Vec::new();
+
+        for facet_param in facet_params {
+            match facet_param {
+                FactoryParam::Facet(ident, _) => {
+                    let param_type = facet_types_map.get(ident).ok_or_else(|| {
+                        unrecognised_facet_name(ident, facet_types_map.keys().copied())
+                    })?;
+                    let param_options = facet_options_map[ident];
+                    let param_builder_type = builder_type(facet_crate, param_type, param_options);
+                    mark_facets_needed.push(quote! {
+                        ::#facet_crate::AsyncBuilderFor::<#param_builder_type>::need(self);
+                    });
+                    dependent_facets.push(ident);
+                    call_params.push(quote!(#ident.as_ref().unwrap()));
+                    heads.remove(&ident);
+                    deps.push(ident);
+                }
+                FactoryParam::WeakFacet(ident, _) => {
+                    call_params.push(quote!(&__self_weak.#ident));
+                }
+                FactoryParam::Param(ident) => {
+                    call_params.push(quote!(&__self_params.#ident));
+                }
+                FactoryParam::OwnedParam(_) => {
+                    panic!("should not generate async builder for by-value parameters");
+                }
+                FactoryParam::CopiedParam(ident) => {
+                    call_params.push(quote!(__self_params.#ident));
+                }
+            }
+        }
+
+        let call = gen_factory_call(
+            facet_crate,
+            facet_ident,
+            gen_method_call(params, quote!(__self_factory), facet_ident, &call_params),
+            asyncness,
+            fallibility,
+            options,
+        );
+        let maybe_map_err = fallibility.maybe(quote! {
+            .map_err(|e| ::#facet_crate::AsyncFactoryError::from(
+                ::#facet_crate::FactoryError::FacetBuildFailed {
+                    name: stringify!(#facet_ident),
+                        source: e.into(),
+                }))?
+        });
+
+        // Factory-scoped facets that have already been built are taken from
+        // the factory's cache.  Facets that are already present, either from
+        // the cache or because they were injected, don't need building, and
+        // neither do their dependencies.
+        let (maybe_use_cached, maybe_cache) = match options.scope {
+            Scope::Build => (quote!(), quote!()),
+            Scope::Factory => (
+                quote! {
+                    if self.facets.#facet_ident.is_none() {
+                        self.facets.#facet_ident =
+                            ::#facet_crate::FactoryScope::facet_cache(self.factory)
+                                .get::<#facet_type>(stringify!(#facet_ident));
+                    }
+                },
+                quote! {
+                    let facet = ::#facet_crate::FactoryScope::facet_cache(__self_factory)
+                        .insert(stringify!(#facet_ident), facet);
+                },
+            ),
+        };
+
+        let facet_builder_type = builder_type(facet_crate, facet_type, options);
+        let get_facet = wrap_keyed(
+            facet_crate,
+            options,
+            quote! {
+                self.facets.#facet_ident.clone().expect(
+                    concat!(
+                        "bug in #[facet::factory]: facet '",
+                        stringify!(#facet_ident),
+                        "' was not marked as needed",
+                    )
+                )
+            },
+        );
+
+        facet_build_graph.insert(facet_ident, deps);
+
+        // Keyed facets can't be injected, as other facets may have the same
+        // type.
+        if options.key.is_none() {
+            builder_impls.push(quote! {
+                impl ::#facet_crate::InjectFacet<#facet_type> for #builder_ident<'_> {
+                    fn inject(&mut self, facet: #facet_type) {
+                        self.facets.#facet_ident = Some(facet);
+                    }
+                }
+            });
+        }
+
+        builder_impls.push(quote! {
+
+            impl ::#facet_crate::AsyncBuilderFor<#facet_builder_type> for #builder_ident<'_> {
+
+                fn need(&mut self) {
+                    #maybe_use_cached
+                    if self.facets.#facet_ident.is_some() {
+                        return;
+                    }
+                    self.needed.#facet_ident = true;
+                    #( #mark_facets_needed )*
+                }
+
+                fn get(&self) -> #facet_builder_type {
+                    // The proc macro should have arranged for all needed
+                    // facets to have been marked as needed and thus built. It
+                    // is invalid for this to be called if the facet wasn't
+                    // built.
+                    #get_facet
+                }
+            }
+
+        });
+
+        // Keyed facets can't be lazy, as containers may only hold one lazy
+        // instance of each facet.  Lazy facets are built after the container
+        // is, so they hold a handle to the factory, which is only possible
+        // if the factory is `Clone`.
+        if options.key.is_none() {
+            builder_impls.push(quote! {
+
+                impl<'factory> ::#facet_crate::AsyncLazyBuilderFor<#facet_type>
+                    for #builder_ident<'factory>
+                where
+                    #factory_ty: ::#facet_crate::LazyFactory<'factory, #factory_ty>,
+                {
+                    fn get_lazy(&self) -> ::#facet_crate::LazyFacet<#facet_type> {
+                        // If another facet needed this one, then it has
+                        // already been built.
+                        if let Some(facet) = self.facets.#facet_ident.clone() {
+                            return ::#facet_crate::LazyFacet::ready(facet);
+                        }
+                        // Otherwise it is built on first access, along with
+                        // any of its dependencies that haven't been built
+                        // yet, reusing the facets that have.
+                        let factory =
+                            <#factory_ty as ::#facet_crate::LazyFactory<'factory, #factory_ty>>
+                                ::lazy_handle(self.factory);
+                        let params = self.params.clone();
+                        let facets = self.facets.clone();
+                        let weak = self.weak.clone();
+                        let recorder = self.recorder.clone();
+                        ::#facet_crate::LazyFacet::new(move || {
+                            let factory = factory.clone();
+                            let params = params.clone();
+                            let facets = facets.clone();
+                            let weak = weak.clone();
+                            let recorder = recorder.clone();
+                            ::std::boxed::Box::pin(async move {
+                                let mut builder = #builder_ident {
+                                    factory: &*factory,
+                                    params,
+                                    facets,
+                                    needed: #builder_facets_needed_ident::default(),
+                                    weak,
+                                    limit: ::#facet_crate::BuildLimit::unlimited(),
+                                    recorder,
+                                };
+                                ::#facet_crate::AsyncBuilderFor::<#facet_type>::need(&mut builder);
+                                ::#facet_crate::AsyncBuilder::build_needed(&mut builder).await?;
+                                Ok::<_, ::#facet_crate::FactoryError>(
+                                    ::#facet_crate::AsyncBuilderFor::<#facet_type>::get(&builder)
+                                )
+                            })
+                        })
+                    }
+                }
+
+            });
+        }
+
+        let get_dependent_facets = if dependent_facets.is_empty() {
+            quote!()
+        } else {
+            quote! {
+                let ( #( #dependent_facets, )* ) =
+                    ::#facet_crate::futures::try_join!(
+                        #( #dependent_facets.clone(), )*
+                    )?;
+            }
+        };
+
+        facet_build_futs.insert(
+            facet_ident,
+            quote! {
+                let #facet_ident = async {
+                    if __self_needed.#facet_ident {
+                        #get_dependent_facets
+                        let _permit = __self_limit.acquire().await;
+                        __self_recorder.started(stringify!(#facet_ident));
+                        let __start = ::std::time::Instant::now();
+                        let facet = async {
+                            let facet = #call #maybe_map_err;
+                            Ok::<_, ::#facet_crate::AsyncFactoryError>(facet)
+                        }
+                        .await
+                        .map_err(|e| {
+                            __self_recorder.failed(stringify!(#facet_ident), __start.elapsed(), &e);
+                            e
+                        })?;
+                        __self_recorder.record(stringify!(#facet_ident), __start.elapsed());
+                        #maybe_cache
+                        Ok::<_, ::#facet_crate::AsyncFactoryError>(Some(facet))
+                    } else {
+                        // The facet may already be present, in which case
+                        // it can be used by the facets that depend on it.
+                        Ok::<_, ::#facet_crate::AsyncFactoryError>(
+                            __self_present.#facet_ident.clone()
+                        )
+                    }
+                }.shared();
+            },
+        );
+
+        // Facets that were already present are already stored.
+        store_facets.push(quote! {
+            if let Some(facet) = #facet_ident {
+                __self_facets.#facet_ident = Some(facet);
+            }
+        });
+    }
+
+    // Group facets into based on their depth from the heads of the dependency
+    // graph.  This will be used to order construction of the facets in
+    // topological order.
+    let mut ident_depths = BTreeMap::new();
+    let mut queue: VecDeque<_> = heads.into_iter().map(|head| (head, 0)).collect();
+    let mut max_depth = 0;
+    while let Some((ident, depth)) = queue.pop_front() {
+        ident_depths.insert(ident, depth);
+        max_depth = depth;
+        for dep in facet_build_graph.get(&ident).unwrap().iter() {
+            queue.push_back((dep, depth + 1));
+        }
+    }
+    let mut levels = vec![vec![]; max_depth + 1];
+    for (ident, depth) in ident_depths.into_iter() {
+        levels[depth].push(ident);
+    }
+
+    // Facets that fail to build report the chain of needed facets that
+    // depend on them.
+    let mut dependents = BTreeMap::new();
+    for (ident, deps) in facet_build_graph.iter() {
+        for dep in deps.iter() {
+            dependents.entry(*dep).or_insert_with(Vec::new).push(*ident);
+        }
+    }
+    let find_needed_by = dependents.iter().map(|(ident, dependents)| {
+        quote! {
+            if facet == stringify!(#ident) {
+                #(
+                    if self.#dependents {
+                        return Some(stringify!(#dependents));
+                    }
+                )*
+            }
+        }
+    });
+
+    let mut ordered_idents = Vec::new();
+    for idents in levels.into_iter().rev() {
+        for ident in idents.iter() {
+            build_facets.push(facet_build_futs.remove(ident).unwrap());
+            ordered_idents.push(*ident);
+        }
+    }
+
+    let drop_order = ordered_idents.iter().rev();
+
+    let async_build = if params.memoize {
+        // The facets built for each set of parameters are cached, so that
+        // later builds with the same parameters only build the facets that
+        // weren't needed before.
+        quote! {
+            let __memo_key = ( #( ::std::clone::Clone::clone(&#param_idents), )* );
+            let __build_cache = ::#facet_crate::FactoryMemo::build_cache(self);
+            let mut builder = #builder_ident {
+                factory: self,
+                params: ::std::sync::Arc::new(
+                    #builder_params_ident::new(#( #param_idents, )*)
+                ),
+                facets: __build_cache
+                    .get::<( #( #param_types, )* ), #builder_facets_ident>(&__memo_key)
+                    .unwrap_or_default(),
+                needed: #builder_facets_needed_ident::default(),
+                weak: #builder_weak_facets_ident::default(),
+                limit: ::#facet_crate::BuildLimit::unlimited(),
+                recorder: ::std::default::Default::default(),
+            };
+            T::mark_needed(&mut builder);
+            T::check_params(&builder)?;
+            <#builder_ident as ::#facet_crate::AsyncBuilder>::build_needed(&mut builder).await?;
+            <#builder_ident as ::#facet_crate::AsyncBuilder>::post_build(&mut builder).await?;
+            let container = T::construct(&builder).await;
+            __build_cache.insert(__memo_key, builder.facets.clone());
+            Ok(container)
+        }
+    } else {
+        quote! {
+            let builder = #builder_ident {
+                factory: self,
+                params: ::std::sync::Arc::new(
+                    #builder_params_ident::new(#( #param_idents, )*)
+                ),
+                facets: #builder_facets_ident::default(),
+                needed: #builder_facets_needed_ident::default(),
+                weak: #builder_weak_facets_ident::default(),
+                limit: ::#facet_crate::BuildLimit::unlimited(),
+                recorder: ::std::default::Default::default(),
+            };
+            T::build_async(builder).await
+        }
+    };
+
+    let builder = quote! {
+        #[doc(hidden)]
+        pub struct #builder_params_ident {
+            #(
+                #param_idents: #param_types,
+            )*
+        }
+
+        #[doc(hidden)]
+        #[derive(Clone, Default)]
+        pub struct #builder_facets_ident {
+            #(
+                #facet_idents: ::std::option::Option<#facet_types>,
+            )*
+        }
+
+        impl ::std::ops::Drop for #builder_facets_ident {
+            fn drop(&mut self) {
+                // Drop facets in reverse dependency order, so that a build
+                // that is abandoned part way through drops each facet before
+                // the facets it depends on.
+                #( self.#drop_order.take(); )*
+            }
+        }
+
+        #[doc(hidden)]
+        #[derive(Default)]
+        pub struct #builder_facets_needed_ident {
+            #(
+                #facet_idents: bool,
+            )*
+        }
+
+        impl #builder_facets_needed_ident {
+            // Returns a needed facet that depends on the named facet.
+            fn needed_by(&self, facet: &str) -> ::std::option::Option<&'static str> {
+                #( #find_needed_by )*
+                None
+            }
+        }
+
+        impl #builder_params_ident {
+            #[doc(hidden)]
+            pub fn new( #( #param_idents: #param_types, )* ) -> Self {
+                Self {
+                    #( #param_idents, )*
+                }
+            }
+        }
+
+        #[::#facet_crate::async_trait::async_trait]
+        impl ::#facet_crate::AsyncBuilder for #builder_ident<'_> {
+            async fn build_needed(
+                &mut self
+            ) -> ::std::result::Result<(), ::#facet_crate::FactoryError> {
+                {
+                    use ::#facet_crate::futures::future::FutureExt;
+                    let __self_facets = &mut self.facets;
+                    let __self_needed = &self.needed;
+                    let __self_params = &self.params;
+                    let __self_weak = &self.weak;
+                    let __self_factory = self.factory;
+                    let __self_limit = &self.limit;
+                    let __self_recorder = &*self.recorder;
+                    let __self_present = __self_facets.clone();
+                    #( #build_facets )*
+                    let ( #( #facet_idents, )* ) =
+                        ::#facet_crate::futures::try_join!( #( #facet_idents.clone(), )* )
+                        .map_err(|e| {
+                            e.factory_error()
+                                .with_needed_by(|facet| __self_needed.needed_by(facet))
+                        })?;
+                    #( #store_facets )*
+                    #(
+                        if let Some(facet) = __self_facets.#weak_target_idents.as_ref() {
+                            __self_weak.#weak_target_idents.set(facet);
+                        }
+                    )*
+                }
+                // The needed facets have now been built, so later builds
+                // only build facets that are newly needed.
+                self.needed = ::std::default::Default::default();
+                Ok(())
+            }
+
+            async fn post_build(
+                &mut self
+            ) -> ::std::result::Result<(), ::#facet_crate::FactoryError> {
+                #post_build_hooks
+                Ok(())
+            }
+        }
+
+        #weak_facets
+
+        // The parameters and facets of a build, kept so that the container
+        // can be rebuilt incrementally.
+        #[doc(hidden)]
+        pub struct #builder_state_ident {
+            params: ::std::sync::Arc<#builder_params_ident>,
+            facets: #builder_facets_ident,
+        }
+
+        impl ::#facet_crate::BuildOrder for #builder_ident<'_> {
+            fn build_order(&self) -> ::std::vec::Vec<&'static str> {
+                // Facets are built concurrently, but each facet is only
+                // started after its dependencies, so report them in
+                // topological order.
+                let mut order = ::std::vec::Vec::new();
+                #(
+                    if self.facets.#ordered_idents.is_some() {
+                        order.push(stringify!(#ordered_idents));
+                    }
+                )*
+                order
+            }
+        }
+
+        #build_param_impls
+
+        #(
+            #builder_impls
+        )*
+
+        impl<'factory> ::#facet_crate::FactoryBuilder<'factory> for #factory_ty {
+            type Builder = #builder_ident<'factory>;
+        }
+
+        #[doc(hidden)]
+        pub struct #builder_ident<'factory> {
+            factory: &'factory #factory_ty,
+            params: ::std::sync::Arc<#builder_params_ident>,
+            facets: #builder_facets_ident,
+            needed: #builder_facets_needed_ident,
+            weak: #builder_weak_facets_ident,
+            limit: ::#facet_crate::BuildLimit,
+            recorder: ::std::sync::Arc<::#facet_crate::BuildRecorder>,
+        }
+
+        impl #factory_ty {
+            /// Build an instance of a container from this factory.
+            pub async fn build<'factory, 'builder, T>(
+                &'factory self,
+                #( #param_idents: #param_types ),*
+            ) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
+            where
+                T: ::#facet_crate::AsyncBuildable<'builder, #builder_ident<'factory>>,
+            {
+                #async_build
+            }
+
+            /// Build an instance of a container from this factory, or fail
+            /// with `FactoryError::BuildCancelled` if `cancelled` completes
+            /// before the build does.
+            pub async fn build_cancellable<'factory, 'builder, T>(
+                &'factory self,
+                #( #param_idents: #param_types, )*
+                cancelled: impl ::std::future::Future<Output = ()>,
+            ) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
+            where
+                T: ::#facet_crate::AsyncBuildable<'builder, #builder_ident<'factory>>,
+            {
+                let builder = #builder_ident {
+                    factory: self,
+                    params: ::std::sync::Arc::new(
+                        #builder_params_ident::new(#( #param_idents, )*)
+                    ),
+                    facets: #builder_facets_ident::default(),
+                    needed: #builder_facets_needed_ident::default(),
+                    weak: #builder_weak_facets_ident::default(),
+                    limit: ::#facet_crate::BuildLimit::unlimited(),
+                    recorder: ::std::default::Default::default(),
+                };
+                ::#facet_crate::build_cancellable(T::build_async(builder), cancelled).await
+            }
+
+            /// Build an instance of a container from this factory, building
+            /// at most `concurrency` facets at a time.
+            pub async fn build_with_concurrency<'factory, 'builder, T>(
+                &'factory self,
+                #( #param_idents: #param_types, )*
+                concurrency: usize,
+            ) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
+            where
+                T: ::#facet_crate::AsyncBuildable<'builder, #builder_ident<'factory>>,
+            {
+                let builder = #builder_ident {
+                    factory: self,
+                    params: ::std::sync::Arc::new(
+                        #builder_params_ident::new(#( #param_idents, )*)
+                    ),
+                    facets: #builder_facets_ident::default(),
+                    needed: #builder_facets_needed_ident::default(),
+                    weak: #builder_weak_facets_ident::default(),
+                    limit: ::#facet_crate::BuildLimit::new(concurrency),
+                    recorder: ::std::default::Default::default(),
+                };
+                T::build_async(builder).await
+            }
+
+            /// Build an instance of a container from this factory, and
+            /// report how long each facet took to build.
+            pub async fn build_instrumented<'factory, 'builder, T>(
+                &'factory self,
+                #( #param_idents: #param_types ),*
+            ) -> ::std::result::Result<
+                (T, ::#facet_crate::BuildReport),
+                ::#facet_crate::FactoryError,
+            >
+            where
+                T: ::#facet_crate::AsyncBuildable<'builder, #builder_ident<'factory>>,
+            {
+                let start = ::std::time::Instant::now();
+                let recorder = ::std::sync::Arc::new(::#facet_crate::BuildRecorder::default());
+                let builder = #builder_ident {
+                    factory: self,
+                    params: ::std::sync::Arc::new(
+                        #builder_params_ident::new(#( #param_idents, )*)
+                    ),
+                    facets: #builder_facets_ident::default(),
+                    needed: #builder_facets_needed_ident::default(),
+                    weak: #builder_weak_facets_ident::default(),
+                    limit: ::#facet_crate::BuildLimit::unlimited(),
+                    recorder: recorder.clone(),
+                };
+                let container = T::build_async(builder).await?;
+                Ok((container, recorder.report(start.elapsed())))
+            }
+
+            /// Build an instance of a container from this factory, calling
+            /// `on_event` as each facet starts, completes or fails to build.
+            pub async fn build_with_progress<'factory, 'builder, T>(
+                &'factory self,
+                #( #param_idents: #param_types, )*
+                on_event: impl Fn(::#facet_crate::BuildEvent) + Send + Sync + 'static,
+            ) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
+            where
+                T: ::#facet_crate::AsyncBuildable<'builder, #builder_ident<'factory>>,
+            {
+                let builder = #builder_ident {
+                    factory: self,
+                    params: ::std::sync::Arc::new(
+                        #builder_params_ident::new(#( #param_idents, )*)
+                    ),
+                    facets: #builder_facets_ident::default(),
+                    needed: #builder_facets_needed_ident::default(),
+                    weak: #builder_weak_facets_ident::default(),
+                    limit: ::#facet_crate::BuildLimit::unlimited(),
+                    recorder: ::std::sync::Arc::new(
+                        ::#facet_crate::BuildRecorder::with_observer(on_event)
+                    ),
+                };
+                T::build_async(builder).await
+            }
+
+            /// Build only the given facet fields of a container from this
+            /// factory.
+            pub async fn build_partial<'factory, 'builder, T>(
+                &'factory self,
+                #( #param_idents: #param_types, )*
+                facets: &::#facet_crate::FacetSet,
+            ) -> ::std::result::Result<T::Partial, ::#facet_crate::FactoryError>
+            where
+                T: ::#facet_crate::AsyncPartialBuildable<'builder, #builder_ident<'factory>>,
+            {
+                let builder = #builder_ident {
+                    factory: self,
+                    params: ::std::sync::Arc::new(
+                        #builder_params_ident::new(#( #param_idents, )*)
+                    ),
+                    facets: #builder_facets_ident::default(),
+                    needed: #builder_facets_needed_ident::default(),
+                    weak: #builder_weak_facets_ident::default(),
+                    limit: ::#facet_crate::BuildLimit::unlimited(),
+                    recorder: ::std::default::Default::default(),
+                };
+                T::build_partial_async(builder, facets.clone()).await
+            }
+
+            /// Start a build of an instance of a container from this
+            /// factory, which can be given facets that have already been
+            /// built.
+            pub fn build_with<'factory, 'builder, T>(
+                &'factory self,
+                #( #param_idents: #param_types ),*
+            ) -> ::#facet_crate::AsyncBuildWith<#builder_ident<'factory>, T>
+            where
+                T: ::#facet_crate::AsyncBuildable<'builder, #builder_ident<'factory>>,
+            {
+                ::#facet_crate::AsyncBuildWith::new(#builder_ident {
+                    factory: self,
+                    params: ::std::sync::Arc::new(
+                        #builder_params_ident::new(#( #param_idents, )*)
+                    ),
+                    facets: #builder_facets_ident::default(),
+                    needed: #builder_facets_needed_ident::default(),
+                    weak: #builder_weak_facets_ident::default(),
+                    limit: ::#facet_crate::BuildLimit::unlimited(),
+                    recorder: ::std::default::Default::default(),
+                })
+            }
+
+            /// Build an instance of a container from this factory that can
+            /// be rebuilt incrementally when the parameters change.
+            pub async fn build_rebuildable<'factory, 'builder, T>(
+                &'factory self,
+                #( #param_idents: #param_types ),*
+            ) -> ::std::result::Result<
+                ::#facet_crate::Rebuildable<T, #builder_state_ident>,
+                ::#facet_crate::FactoryError,
+            >
+            where
+                T: ::#facet_crate::AsyncBuildable<'builder, #builder_ident<'factory>>,
+            {
+                let mut builder = #builder_ident {
+                    factory: self,
+                    params: ::std::sync::Arc::new(
+                        #builder_params_ident::new(#( #param_idents, )*)
+                    ),
+                    facets: #builder_facets_ident::default(),
+                    needed: #builder_facets_needed_ident::default(),
+                    weak: #builder_weak_facets_ident::default(),
+                    limit: ::#facet_crate::BuildLimit::unlimited(),
+                    recorder: ::std::default::Default::default(),
+                };
+                T::mark_needed(&mut builder);
+                T::check_params(&builder)?;
+                <#builder_ident as ::#facet_crate::AsyncBuilder>::build_needed(&mut builder).await?;
+                <#builder_ident as ::#facet_crate::AsyncBuilder>::post_build(&mut builder).await?;
+                let container = T::construct(&builder).await;
+                Ok(::#facet_crate::Rebuildable::new(
+                    container,
+                    #builder_state_ident {
+                        params: builder.params,
+                        facets: builder.facets,
+                    },
+                ))
+            }
+
+            /// Rebuild a container with new parameters.  Facets that don't
+            /// depend on any of the parameters that have changed, either
+            /// directly or through their dependencies, are reused from the
+            /// existing container's build rather than built again.
+            pub async fn rebuild<'factory, 'builder, T>(
+                &'factory self,
+                existing: &::#facet_crate::Rebuildable<T, #builder_state_ident>,
+                #( #param_idents: #param_types ),*
+            ) -> ::std::result::Result<
+                ::#facet_crate::Rebuildable<T, #builder_state_ident>,
+                ::#facet_crate::FactoryError,
+            >
+            where
+                T: ::#facet_crate::AsyncBuildable<'builder, #builder_ident<'factory>>,
+                // Parameters can only be compared if they implement
+                // `PartialEq`.  The bound is higher-ranked so that it is
+                // only checked when this method is used.
+                #( for<'a> #compared_param_types: ::std::cmp::PartialEq, )*
+            {
+                #check_changed
+                let mut facets = #builder_facets_ident::default();
+                #reuse_unchanged
+                let mut builder = #builder_ident {
+                    factory: self,
+                    params: ::std::sync::Arc::new(
+                        #builder_params_ident::new(#( #param_idents, )*)
+                    ),
+                    facets,
+                    needed: #builder_facets_needed_ident::default(),
+                    weak: #builder_weak_facets_ident::default(),
+                    limit: ::#facet_crate::BuildLimit::unlimited(),
+                    recorder: ::std::default::Default::default(),
+                };
+                T::mark_needed(&mut builder);
+                T::check_params(&builder)?;
+                <#builder_ident as ::#facet_crate::AsyncBuilder>::build_needed(&mut builder).await?;
+                <#builder_ident as ::#facet_crate::AsyncBuilder>::post_build(&mut builder).await?;
+                let container = T::construct(&builder).await;
+                Ok(::#facet_crate::Rebuildable::new(
+                    container,
+                    #builder_state_ident {
+                        params: builder.params,
+                        facets: builder.facets,
+                    },
+                ))
+            }
+        }
+    };
+
+    Ok(builder)
+}
+
+/// Generates the build method for a factory that delegates to an inner
+/// factory.  The facets built by this factory's methods override those of
+/// the inner factory: they are built first, and then given to a build by the
+/// inner factory, which builds all other facets.
+fn gen_delegating_factory(
+    facet_crate: &Ident,
+    factory_ty: &Ident,
+    params: &Params,
+    facets: &Facets,
+    inner_ident: &Ident,
+    inner_ty: &Type,
+    is_async: Asyncness,
+) -> Result<TokenStream, Error> {
+    let param_idents = &params.param_idents;
+    let param_types = &params.param_types;
+    let facet_types_map = facets
+        .facet_idents
+        .iter()
+        .zip(&facets.facet_types)
+        .collect::<BTreeMap<_, _>>();
+
+    // Overriding facets that depend on facets of the inner factory, either
+    // directly or through other overriding facets.
+    let mut needs_inner = BTreeSet::new();
+    loop {
+        let mut changed = false;
+        for (facet_ident, _, _, _, facet_params, _) in facets.iter() {
+            if needs_inner.contains(facet_ident) {
+                continue;
+            }
+            let depends_on_inner = facet_params.iter().any(|facet_param| match facet_param {
+                FactoryParam::Facet(ident, _) => {
+                    !facet_types_map.contains_key(ident) || needs_inner.contains(ident)
+                }
+                _ => false,
+            });
+            if depends_on_inner {
+                needs_inner.insert(facet_ident);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // Overriding facets are built in dependency order.  Those that don't
+    // depend on the inner factory are built first, so that facets of the
+    // inner factory that depend on them are built with the overrides.
+    let mut ordered = Vec::new();
+    let mut done = BTreeSet::new();
+    let mut remaining = facets.iter().collect::<Vec<_>>();
+    while !remaining.is_empty() {
+        let next = remaining
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, _, _, _, facet_params, _))| {
+                facet_params.iter().all(|facet_param| match facet_param {
+                    FactoryParam::Facet(ident, _) => {
+                        !facet_types_map.contains_key(ident) || done.contains(ident)
+                    }
+                    _ => true,
+                })
+            })
+            .min_by_key(|(index, (facet_ident, ..))| (needs_inner.contains(facet_ident), *index))
+            .map(|(index, _)| index)
+            .expect("cyclic dependencies should have been rejected");
+        let facet = remaining.remove(next);
+        done.insert(facet.0);
+        ordered.push(facet);
+    }
+
+    let mut inner_bounds = Vec::new();
+    let mut build_overrides = Vec::new();
+    let mut need_inner_facets = Vec::new();
+    let mut build_inner_overrides = Vec::new();
+
+    for (facet_ident, facet_type, fallibility, asyncness, facet_params, options) in ordered {
+        if options.key.is_some() || options.scope != Scope::Build {
+            return Err(Error::new(
+                facet_ident.span(),
+                "delegating factories cannot override keyed or factory-scoped facets",
+            ));
+        }
+        let mut get_inner_facets = Vec::new();
+        let mut call_params = Vec::new();
+        for facet_param in facet_params {
+            match facet_param {
+                FactoryParam::Facet(ident, param_type) => {
+                    if !facet_types_map.contains_key(&ident) {
+                        get_inner_facets.push(match is_async {
+                            Asyncness::Synchronous => {
+                                inner_bounds.push(quote!(::#facet_crate::Builder<#param_type>));
+                                quote! {
+                                    let #ident: #param_type =
+                                        ::#facet_crate::Builder::<#param_type>::build(
+                                            build.builder_mut()
+                                        )
+                                        .map_err(|e| e.needed_by(stringify!(#facet_ident)))?;
+                                }
+                            }
+                            Asyncness::Asynchronous => {
+                                inner_bounds
+                                    .push(quote!(::#facet_crate::AsyncBuilderFor<#param_type>));
+                                need_inner_facets.push(quote! {
+                                    ::#facet_crate::AsyncBuilderFor::<#param_type>::need(
+                                        build.builder_mut()
+                                    );
+                                });
+                                quote! {
+                                    let #ident: #param_type =
+                                        ::#facet_crate::AsyncBuilderFor::<#param_type>::get(
+                                            build.builder_mut()
+                                        );
+                                }
+                            }
+                        });
+                    }
+                    call_params.push(quote!(&#ident));
+                }
+                FactoryParam::WeakFacet(ident, _) => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "delegating factory methods cannot take weak references to facets",
+                    ));
+                }
+                FactoryParam::Param(ident) => {
+                    call_params.push(quote!(&#ident));
+                }
+                FactoryParam::CopiedParam(ident) => {
+                    call_params.push(quote!(#ident));
+                }
+                FactoryParam::OwnedParam(ident) => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "delegating factory methods cannot take factory parameters by value",
+                    ));
+                }
+            }
+        }
+        inner_bounds.push(quote!(::#facet_crate::InjectFacet<#facet_type>));
+
+        let call = gen_factory_call(
+            facet_crate,
+            facet_ident,
+            quote!(self.#facet_ident( #( #call_params ),* )),
+            asyncness,
+            fallibility,
+            options,
+        );
         let maybe_map_err = fallibility.maybe(quote! {
-            .map_err(|e| ::#facet_crate::AsyncFactoryError::from(
-                ::#facet_crate::FactoryError::FacetBuildFailed {
-                    name: stringify!(#facet_ident),
-                    source: e.into(),
-                }))?
+            .map_err(|e| ::#facet_crate::FactoryError::FacetBuildFailed {
+                name: stringify!(#facet_ident),
+                source: e.into(),
+            })?
         });
+        let build_override = quote! {
+            let #facet_ident: #facet_type = {
+                #( #get_inner_facets )*
+                #call #maybe_map_err
+            };
+            build = build.facet::<#facet_type>(#facet_ident.clone());
+        };
+        if needs_inner.contains(facet_ident) && is_async == Asyncness::Asynchronous {
+            build_inner_overrides.push(build_override);
+        } else {
+            build_overrides.push(build_override);
+        }
+    }
 
-        facet_build_graph.insert(facet_ident, deps);
-        builder_impls.push(quote! {
+    let build_inner_facets = if need_inner_facets.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            #( #need_inner_facets )*
+            ::#facet_crate::AsyncBuilder::build_needed(build.builder_mut()).await?;
+        }
+    };
 
-            impl ::#facet_crate::AsyncBuilderFor<#facet_type> for #builder_ident<'_> {
+    let inner_builder = quote! {
+        <#inner_ty as ::#facet_crate::FactoryBuilder<'factory>>::Builder
+    };
+    let container_builder = match is_async {
+        Asyncness::Synchronous => gen_container_builder(
+            facet_crate,
+            factory_ty,
+            params,
+            is_async,
+            quote! {
+                T: ::#facet_crate::Buildable<#inner_builder>,
+                #inner_builder: #( #inner_bounds + )* ::std::marker::Sized,
+            },
+        ),
+        Asyncness::Asynchronous => gen_container_builder(
+            facet_crate,
+            factory_ty,
+            params,
+            is_async,
+            quote! {
+                T: ::#facet_crate::AsyncBuildable<'builder, #inner_builder>,
+                #inner_builder: #( #inner_bounds + )* ::#facet_crate::AsyncBuilder,
+            },
+        ),
+    };
 
-                fn need(&mut self) {
-                    self.needed.#facet_ident = true;
-                    #( #mark_facets_needed )*
+    let output = match is_async {
+        Asyncness::Synchronous => quote! {
+            impl #factory_ty {
+                /// Build an instance of a container from this factory,
+                /// building facets this factory doesn't override with the
+                /// inner factory.
+                pub fn build<'factory, T>(
+                    &'factory self,
+                    #( #param_idents: #param_types ),*
+                ) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
+                where
+                    T: ::#facet_crate::Buildable<#inner_builder>,
+                    #inner_builder: #( #inner_bounds + )* ::std::marker::Sized,
+                {
+                    let mut build = self.#inner_ident.build_with::<T>(
+                        #( ::std::clone::Clone::clone(&#param_idents), )*
+                    );
+                    #( #build_overrides )*
+                    build.finish()
                 }
+            }
 
-                fn get(&self) -> #facet_type {
-                    // The proc macro should have arranged for all needed
-                    // facets to have been marked as needed and thus built. It
-                    // is invalid for this to be called if the facet wasn't
-                    // built.
-                    self.facets.#facet_ident.clone().expect(
-                        concat!(
-                            "bug in #[facet::factory]: facet '",
-                            stringify!(#facet_ident),
-                            "' was not marked as needed",
-                        )
-                    )
+            #container_builder
+        },
+        Asyncness::Asynchronous => quote! {
+            impl #factory_ty {
+                /// Build an instance of a container from this factory,
+                /// building facets this factory doesn't override with the
+                /// inner factory.
+                pub async fn build<'factory, 'builder, T>(
+                    &'factory self,
+                    #( #param_idents: #param_types ),*
+                ) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
+                where
+                    T: ::#facet_crate::AsyncBuildable<'builder, #inner_builder>,
+                    #inner_builder: #( #inner_bounds + )* ::#facet_crate::AsyncBuilder,
+                {
+                    let mut build = self.#inner_ident.build_with::<T>(
+                        #( ::std::clone::Clone::clone(&#param_idents), )*
+                    );
+                    #( #build_overrides )*
+                    #build_inner_facets
+                    #( #build_inner_overrides )*
+                    build.finish().await
                 }
             }
 
-        });
+            #container_builder
+        },
+    };
 
-        let get_dependent_facets = if dependent_facets.is_empty() {
-            quote!()
+    Ok(output)
+}
+
+/// Generate the calls to the factory's post-build hooks, in the order they
+/// are declared.  Each hook is only called if every facet it takes was built.
+/// `params_expr` is where the builder holds the factory parameters.
+fn gen_post_build_hooks(
+    facet_crate: &Ident,
+    params: &Params,
+    facets: &Facets,
+    params_expr: TokenStream,
+) -> TokenStream {
+    let hooks = facets.post_build_hooks.iter().map(|hook| {
+        let hook_ident = &hook.ident;
+        let mut built_idents = Vec::new();
+        let mut call_params = Vec::new();
+        for hook_param in &hook.params {
+            match hook_param {
+                FactoryParam::Facet(ident, _) => {
+                    built_idents.push(ident);
+                    call_params.push(quote!(#ident));
+                }
+                FactoryParam::Param(ident) => call_params.push(quote!(&#params_expr.#ident)),
+                FactoryParam::CopiedParam(ident) => call_params.push(quote!(#params_expr.#ident)),
+                FactoryParam::OwnedParam(_) | FactoryParam::WeakFacet(..) => {
+                    panic!("post-build hooks should not take parameters by value or weak facets");
+                }
+            }
+        }
+        let call = gen_method_call(params, quote!(self.factory), hook_ident, &call_params);
+        let call = match hook.asyncness {
+            Asyncness::Asynchronous => quote!(#call.await),
+            Asyncness::Synchronous => call,
+        };
+        let call = match hook.fallibility {
+            Fallibility::Fallible => quote! {
+                #call.map_err(|e| ::#facet_crate::FactoryError::PostBuildFailed {
+                    name: stringify!(#hook_ident),
+                    source: e.into(),
+                })?;
+            },
+            Fallibility::Infallible => quote!(#call;),
+        };
+        if built_idents.is_empty() {
+            quote!({ #call })
         } else {
             quote! {
-                let ( #( #dependent_facets, )* ) =
-                    ::#facet_crate::futures::try_join!(
-                        #( #dependent_facets.clone(), )*
-                    )?;
+                if let ( #( ::std::option::Option::Some(#built_idents), )* ) =
+                    ( #( self.facets.#built_idents.as_ref(), )* )
+                {
+                    #call
+                }
             }
-        };
+        }
+    });
+    quote!(#( #hooks )*)
+}
 
-        facet_build_futs.insert(
-            facet_ident,
+/// Generate the checks of which factory parameters have changed since a
+/// previous build, and the statements that copy the facets that don't depend
+/// on any of the changed parameters from the previous build's facets.
+fn gen_reuse_unchanged(
+    params: &Params,
+    facets: &Facets,
+    old_params: TokenStream,
+    old_facets: TokenStream,
+) -> (TokenStream, TokenStream) {
+    // Parameters taken by value have been consumed by the existing build,
+    // so they are always treated as changed.  There is only one
+    // `FacebookInit` for the process, so it never changes.
+    let owned_params = facets.owned_params();
+    let check_changed = params
+        .param_idents
+        .iter()
+        .zip(&params.param_types)
+        .map(|(ident, ty)| {
+            let changed_ident = format_ident!("__changed_{}", ident);
+            if owned_params.contains(ident) {
+                quote!(let #changed_ident = true;)
+            } else if is_facebook_init(ty) {
+                quote!(let #changed_ident = false;)
+            } else {
+                quote!(let #changed_ident = #old_params.#ident != #ident;)
+            }
+        });
+    let check_changed = quote!(#( #check_changed )*);
+    let reuse = facets
+        .param_inputs()
+        .into_iter()
+        .map(|(facet_ident, inputs)| {
+            let changed = inputs
+                .into_iter()
+                .map(|ident| format_ident!("__changed_{}", ident));
             quote! {
-                let #facet_ident = async {
-                    if __self_needed.#facet_ident {
-                        #get_dependent_facets
-                        Ok::<_, ::#facet_crate::AsyncFactoryError>(Some(
-                            __self_factory.#facet_ident( #( #call_params, )* )
-                                #maybe_dot_await_factory
-                                #maybe_map_err
-                        ))
-                    } else {
-                        Ok::<_, ::#facet_crate::AsyncFactoryError>(None)
-                    }
-                }.shared();
-            },
-        );
-
-        store_facets.push(quote! {
-            __self_facets.#facet_ident = #facet_ident;
+                if !(#( #changed || )* false) {
+                    facets.#facet_ident = #old_facets.#facet_ident.clone();
+                }
+            }
         });
+    (check_changed, quote!(#( #reuse )*))
+}
+
+/// Generate a call to a factory method that builds a facet, applying the
+/// method's options.  Panics in factory methods are caught and reported as
+/// build errors if the factory has `catch_panics`, unless the method has
+/// `#[facet(propagate_panic)]`.
+fn gen_factory_call(
+    facet_crate: &Ident,
+    facet_ident: &Ident,
+    call: TokenStream,
+    asyncness: Asyncness,
+    fallibility: Fallibility,
+    options: &MethodOptions,
+) -> TokenStream {
+    let call = gen_stats_call(facet_crate, facet_ident, call, asyncness, fallibility);
+    let call = gen_traced_call(facet_crate, facet_ident, call, asyncness);
+    if let Some(retries) = options.retries {
+        // Retried calls are always fallible, so wrap the result back up for
+        // the caller to unwrap.
+        let backoff = options.backoff.unwrap_or(0);
+        let timeout = match options.timeout {
+            Some(millis) => quote! {
+                ::std::option::Option::Some(::std::time::Duration::from_millis(#millis))
+            },
+            None => quote!(::std::option::Option::None),
+        };
+        let catch_panics = options.catch_panic;
+        return quote! {
+            ::std::result::Result::<_, ::#facet_crate::FactoryError>::Ok(
+                ::#facet_crate::build_with_retries(
+                    stringify!(#facet_ident),
+                    #retries,
+                    ::std::time::Duration::from_millis(#backoff),
+                    #timeout,
+                    #catch_panics,
+                    || #call,
+                )
+                .await?
+            )
+        };
+    }
+    if asyncness == Asyncness::Synchronous {
+        return if options.catch_panic {
+            quote! {
+                ::#facet_crate::catch_build_panic(stringify!(#facet_ident), || #call)?
+            }
+        } else {
+            call
+        };
+    }
+    let mut build = call;
+    let mut check_errors = Vec::new();
+    if options.catch_panic {
+        build = quote! {
+            ::#facet_crate::catch_async_build_panic(stringify!(#facet_ident), #build)
+        };
+        check_errors.push(quote!(?));
+    }
+    if let Some(millis) = options.timeout {
+        build = quote! {
+            ::#facet_crate::build_with_timeout(
+                stringify!(#facet_ident),
+                ::std::time::Duration::from_millis(#millis),
+                #build,
+            )
+        };
+        check_errors.push(quote!(?));
     }
+    quote!(#build.await #( #check_errors )*)
+}
 
-    // Group facets into based on their depth from the heads of the dependency
-    // graph.  This will be used to order construction of the facets in
-    // topological order.
-    let mut ident_depths = BTreeMap::new();
-    let mut queue: VecDeque<_> = heads.into_iter().map(|head| (head, 0)).collect();
-    let mut max_depth = 0;
-    while let Some((ident, depth)) = queue.pop_front() {
-        ident_depths.insert(ident, depth);
-        max_depth = depth;
-        for dep in facet_build_graph.get(&ident).unwrap().iter() {
-            queue.push_back((dep, depth + 1));
-        }
+/// Wrap a call to a factory method in a `tracing` span named after the
+/// facet, if the `tracing` feature is enabled.
+#[cfg(feature = "tracing")]
+fn gen_traced_call(
+    facet_crate: &Ident,
+    facet_ident: &Ident,
+    call: TokenStream,
+    asyncness: Asyncness,
+) -> TokenStream {
+    let span = quote! {
+        ::#facet_crate::tracing::info_span!(
+            stringify!(#facet_ident),
+            facet = stringify!(#facet_ident),
+        )
+    };
+    match asyncness {
+        Asyncness::Synchronous => quote! {{
+            let _span = #span.entered();
+            #call
+        }},
+        Asyncness::Asynchronous => quote! {
+            ::#facet_crate::tracing::Instrument::instrument(#call, #span)
+        },
     }
-    let mut levels = vec![vec![]; max_depth + 1];
-    for (ident, depth) in ident_depths.into_iter() {
-        levels[depth].push(ident);
+}
+
+#[cfg(not(feature = "tracing"))]
+fn gen_traced_call(
+    _facet_crate: &Ident,
+    _facet_ident: &Ident,
+    call: TokenStream,
+    _asyncness: Asyncness,
+) -> TokenStream {
+    call
+}
+
+/// Wrap a call to a factory method so that the build is recorded in the
+/// facet's stats, if the `stats` feature is enabled.
+#[cfg(feature = "stats")]
+fn gen_stats_call(
+    facet_crate: &Ident,
+    facet_ident: &Ident,
+    call: TokenStream,
+    asyncness: Asyncness,
+    fallibility: Fallibility,
+) -> TokenStream {
+    let is_error = match fallibility {
+        Fallibility::Fallible => quote!(|result| result.is_err()),
+        Fallibility::Infallible => quote!(|_| false),
+    };
+    match asyncness {
+        Asyncness::Synchronous => quote! {
+            ::#facet_crate::build_with_stats(stringify!(#facet_ident), || #call, #is_error)
+        },
+        Asyncness::Asynchronous => quote! {
+            ::#facet_crate::build_with_stats_async(stringify!(#facet_ident), #call, #is_error)
+        },
     }
+}
 
-    for idents in levels.into_iter().rev() {
-        for ident in idents.iter() {
-            build_facets.push(facet_build_futs.remove(ident).unwrap());
+#[cfg(not(feature = "stats"))]
+fn gen_stats_call(
+    _facet_crate: &Ident,
+    _facet_ident: &Ident,
+    call: TokenStream,
+    _asyncness: Asyncness,
+    _fallibility: Fallibility,
+) -> TokenStream {
+    call
+}
+
+/// Generates the `BuildParam` implementations that give containers access
+/// to the factory parameters by name, with one implementation for each
+/// parameter type.  Parameters taken by value are consumed by the build, so
+/// they are not available to containers.
+fn gen_build_param_impls(
+    facet_crate: &Ident,
+    builder_ident: &Ident,
+    params: &Params,
+    facets: &Facets,
+    params_expr: TokenStream,
+) -> TokenStream {
+    let owned_params = facets.owned_params();
+    let mut params_by_type: Vec<(&Type, Vec<&Ident>)> = Vec::new();
+    for (param_ident, param_type) in params.param_idents.iter().zip(&params.param_types) {
+        if owned_params.contains(param_ident) {
+            continue;
+        }
+        let type_string = quote!(#param_type).to_string();
+        match params_by_type
+            .iter_mut()
+            .find(|(ty, _)| quote!(#ty).to_string() == type_string)
+        {
+            Some((_, idents)) => idents.push(param_ident),
+            None => params_by_type.push((param_type, vec![param_ident])),
         }
     }
-
-    let builder = quote! {
-        #[doc(hidden)]
-        pub struct #builder_params_ident {
-            #(
-                #param_idents: #param_types,
-            )*
+    let impls = params_by_type.iter().map(|(param_type, param_idents)| {
+        quote! {
+            impl ::#facet_crate::BuildParam<#param_type> for #builder_ident<'_> {
+                fn param(&self, name: &str) -> ::std::option::Option<&#param_type> {
+                    match name {
+                        #( stringify!(#param_idents) => Some(&#params_expr.#param_idents), )*
+                        _ => None,
+                    }
+                }
+            }
         }
+    });
+    quote!( #( #impls )* )
+}
 
+fn gen_weak_facets(
+    builder_weak_facets_ident: &Ident,
+    weak_targets: &BTreeMap<&Ident, &Type>,
+) -> TokenStream {
+    let weak_idents = weak_targets.keys();
+    let weak_types = weak_targets.values();
+    quote! {
         #[doc(hidden)]
-        #[derive(Default)]
-        pub struct #builder_facets_ident {
+        #[derive(Clone, Default)]
+        pub struct #builder_weak_facets_ident {
             #(
-                #facet_idents: ::std::option::Option<#facet_types>,
+                #weak_idents: #weak_types,
             )*
         }
+    }
+}
 
-        #[doc(hidden)]
-        #[derive(Default)]
-        pub struct #builder_facets_needed_ident {
-            #(
-                #facet_idents: bool,
-            )*
-        }
+#[derive(Debug)]
+pub(crate) struct Params {
+    param_idents: Vec<Ident>,
+    param_types: Vec<Type>,
 
-        impl #builder_params_ident {
-            #[doc(hidden)]
-            pub fn new( #( #param_idents: #param_types, )* ) -> Self {
-                Self {
-                    #( #param_idents, )*
-                }
-            }
-        }
+    /// The values of parameters that have defaults, given as `#[default]`
+    /// for the type's default value or `#[default(value)]`.  Defaults are
+    /// used by the fluent builder when the parameter is not given.
+    param_defaults: Vec<Option<Expr>>,
 
-        #[::#facet_crate::async_trait::async_trait]
-        impl ::#facet_crate::AsyncBuilder for #builder_ident<'_> {
-            async fn build_needed(
-                &mut self
-            ) -> ::std::result::Result<(), ::#facet_crate::FactoryError> {
-                use ::#facet_crate::futures::future::FutureExt;
-                let __self_facets = &mut self.facets;
-                let __self_needed = &self.needed;
-                let __self_params = &self.params;
-                let __self_factory = self.factory;
-                #( #build_facets )*
-                let ( #( #facet_idents, )* ) =
-                    ::#facet_crate::futures::try_join!( #( #facet_idents.clone(), )* )
-                    .map_err(|e| e.factory_error())?;
-                #( #store_facets )*
-                Ok(())
-            }
-        }
+    /// The field and type of the inner factory that this factory delegates
+    /// facets it doesn't build itself to, given as
+    /// `delegate = field: Type`.
+    delegate: Option<(Ident, Type)>,
 
-        #(
-            #builder_impls
-        )*
+    /// The factory in another crate that builds the facets this factory's
+    /// methods depend on but don't build, given as `extern = Path` or
+    /// `extern = Path(facet: Type, ...)`.
+    extern_factory: Option<ExternFactory>,
 
-        #[doc(hidden)]
-        pub struct #builder_ident<'factory> {
-            factory: &'factory #factory_ty,
-            params: #builder_params_ident,
-            facets: #builder_facets_ident,
-            needed: #builder_facets_needed_ident,
-        }
+    /// Builds are memoized on the factory's `BuildCache`, keyed on their
+    /// parameters, given as `memoize`.
+    memoize: bool,
 
-        impl #factory_ty {
-            /// Build an instance of a container from this factory.
-            pub async fn build<'factory, 'builder, T>(
-                &'factory self,
-                #( #param_idents: #param_types ),*
-            ) -> ::std::result::Result<T, ::#facet_crate::FactoryError>
-            where
-                T: ::#facet_crate::AsyncBuildable<'builder, #builder_ident<'factory>>,
+    /// Panics in factory methods are caught and reported as build errors,
+    /// given as `catch_panics`.
+    catch_panics: bool,
+
+    /// Facets that the factory builds with their default implementations,
+    /// given as `defaults(Facet, name = Facet, ...)`.  Each is given the name
+    /// derived from the facet type unless another name is given.
+    defaults: Vec<(Ident, Path)>,
+
+    /// The factory trait, for factories that are trait objects of a trait
+    /// marked with `#[facet::factory_trait]`.  Their factory methods are
+    /// methods of the trait, so they are called through it.
+    factory_trait: Option<Ident>,
+
+    /// The factory's parameters are a single struct, given as
+    /// `params = Type`.  The struct is the factory's only parameter, named
+    /// `params`, and factory methods can borrow its fields by name.
+    params_struct: bool,
+}
+
+impl Parse for Params {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let mut param_idents = Vec::new();
+        let mut param_types = Vec::new();
+        let mut param_defaults = Vec::new();
+        let mut delegate = None;
+        let mut extern_factory = None;
+        let mut memoize = false;
+        let mut catch_panics = false;
+        let mut defaults = Vec::new();
+        let mut params_struct = None;
+        let mut args = Vec::new();
+        while !input.is_empty() {
+            let fork = input.fork();
+            let keyword = fork.parse::<Ident>().ok();
+            if keyword.as_ref().is_some_and(|ident| ident == "delegate") && fork.peek(Token![=]) {
+                let delegate_ident: Ident = input.parse()?;
+                input.parse::<Token![=]>()?;
+                let field: Ident = input.parse()?;
+                input.parse::<Token![:]>()?;
+                let ty: Type = input.parse()?;
+                if delegate.is_some() {
+                    return Err(Error::new(
+                        delegate_ident.span(),
+                        "facet::factory can only delegate to one inner factory",
+                    ));
+                }
+                delegate = Some((field, ty));
+            } else if input.peek(Token![extern]) && input.peek2(Token![=]) {
+                let extern_token = input.parse::<Token![extern]>()?;
+                input.parse::<Token![=]>()?;
+                let parsed = input.parse::<ExternFactory>()?;
+                if extern_factory.is_some() {
+                    return Err(Error::new(
+                        extern_token.span(),
+                        "facet::factory can only have one extern factory",
+                    ));
+                }
+                extern_factory = Some(parsed);
+            } else if keyword.as_ref().is_some_and(|ident| ident == "params")
+                && fork.peek(Token![=])
             {
-                let builder = #builder_ident {
-                    factory: &self,
-                    params: #builder_params_ident::new(#( #param_idents, )*),
-                    facets: #builder_facets_ident::default(),
-                    needed: #builder_facets_needed_ident::default(),
-                };
-                T::build_async(builder).await
+                let params_ident: Ident = input.parse()?;
+                input.parse::<Token![=]>()?;
+                let ty: Type = input.parse()?;
+                if params_struct.is_some() {
+                    return Err(Error::new(
+                        params_ident.span(),
+                        "facet::factory can only have one parameters struct",
+                    ));
+                }
+                params_struct = Some((params_ident, ty));
+            } else if keyword.as_ref().is_some_and(|ident| ident == "memoize")
+                && (fork.is_empty() || fork.peek(Token![,]))
+            {
+                input.parse::<Ident>()?;
+                memoize = true;
+            } else if keyword.as_ref().is_some_and(|ident| ident == "catch_panics")
+                && (fork.is_empty() || fork.peek(Token![,]))
+            {
+                input.parse::<Ident>()?;
+                catch_panics = true;
+            } else if keyword.as_ref().is_some_and(|ident| ident == "defaults")
+                && fork.peek(syn::token::Paren)
+            {
+                input.parse::<Ident>()?;
+                let content;
+                syn::parenthesized!(content in input);
+                while !content.is_empty() {
+                    let name = if content.peek(Ident) && content.peek2(Token![=]) {
+                        let name = content.parse::<Ident>()?;
+                        content.parse::<Token![=]>()?;
+                        Some(name)
+                    } else {
+                        None
+                    };
+                    let path = content.parse::<Path>()?;
+                    let name = match name {
+                        Some(name) => name,
+                        None => {
+                            let last = &path
+                                .segments
+                                .last()
+                                .ok_or_else(|| Error::new(path.span(), "expected facet type"))?
+                                .ident;
+                            format_ident!(
+                                "{}",
+                                snakify_pascal_case(last.to_string()),
+                                span = last.span()
+                            )
+                        }
+                    };
+                    defaults.push((name, path));
+                    if !content.is_empty() {
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+            } else {
+                args.push(input.parse::<FnArg>()?);
+            }
+            if input.is_empty() {
+                break;
             }
+            input.parse::<Token![,]>()?;
         }
-    };
-
-    Ok(builder)
-}
-
-#[derive(Debug)]
-struct Params {
-    param_idents: Vec<Ident>,
-    param_types: Vec<Type>,
-}
-
-impl Parse for Params {
-    fn parse(input: ParseStream) -> Result<Self, Error> {
-        let mut param_idents = Vec::new();
-        let mut param_types = Vec::new();
-        for arg in Punctuated::<FnArg, Token![,]>::parse_terminated(input)? {
+        if let (Some((params_ident, _)), Some(arg)) = (&params_struct, args.first()) {
+            return Err(Error::new(
+                arg.span(),
+                format!(
+                    "facet::factory can't have other parameters with '{} = ...'",
+                    params_ident
+                ),
+            ));
+        }
+        if let Some((params_ident, ty)) = &params_struct {
+            param_defaults.push(None);
+            param_idents.push(params_ident.clone());
+            param_types.push(ty.clone());
+        }
+        for arg in args {
             match arg {
                 FnArg::Typed(pat_type) => match *pat_type.pat {
                     Pat::Ident(pat_ident) => {
+                        param_defaults.push(parse_param_default(&pat_type.attrs)?);
                         param_idents.push(pat_ident.ident);
                         param_types.push(*pat_type.ty);
                     }
@@ -477,29 +3418,221 @@ impl Parse for Params {
         Ok(Params {
             param_idents,
             param_types,
+            param_defaults,
+            delegate,
+            extern_factory,
+            memoize,
+            catch_panics,
+            defaults,
+            factory_trait: None,
+            params_struct: params_struct.is_some(),
         })
     }
 }
 
+/// A factory in another crate that builds some of the facets of a factory.
+#[derive(Debug)]
+pub(crate) struct ExternFactory {
+    /// The path to the extern factory.
+    path: Path,
+
+    /// Facets of the extern factory that containers built by this factory
+    /// may need, in addition to those that this factory's methods depend on.
+    facets: Vec<(Ident, Type)>,
+}
+
+impl Parse for ExternFactory {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let path = Path::parse_mod_style(input)?;
+        let mut facets = Vec::new();
+        if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            while !content.is_empty() {
+                let ident = content.parse::<Ident>()?;
+                content.parse::<Token![:]>()?;
+                facets.push((ident, content.parse::<Type>()?));
+                if !content.is_empty() {
+                    content.parse::<Token![,]>()?;
+                }
+            }
+        }
+        Ok(ExternFactory { path, facets })
+    }
+}
+
+/// Parse the default value of a factory parameter from its attributes.
+fn parse_param_default(attrs: &[Attribute]) -> Result<Option<Expr>, Error> {
+    let mut default = None;
+    for attr in attrs {
+        if !attr.path.is_ident("default") {
+            return Err(Error::new(
+                attr.span(),
+                "unrecognised factory parameter attribute",
+            ));
+        }
+        default = Some(if attr.tokens.is_empty() {
+            syn::parse2(quote!(::std::default::Default::default()))?
+        } else {
+            attr.parse_args()?
+        });
+    }
+    Ok(default)
+}
+
 struct Facets {
     facet_idents: Vec<Ident>,
     facet_types: Vec<Type>,
     facet_fallibilities: Vec<Fallibility>,
     facet_asyncnesses: Vec<Asyncness>,
     facet_params: Vec<Vec<FactoryParam>>,
+    facet_options: Vec<MethodOptions>,
+    boxed_facets: Vec<BoxedFacet>,
+    post_build_hooks: Vec<PostBuildHook>,
+}
+
+/// A method of the factory marked with `#[facet::post_build]`, which is run
+/// once the facets a container needs have been built, if every facet it
+/// takes was built.
+struct PostBuildHook {
+    ident: Ident,
+    fallibility: Fallibility,
+    asyncness: Asyncness,
+    params: Vec<FactoryParam>,
+}
+
+/// A factory method that builds a boxed facet.  Boxed facets are owned by the
+/// container they are built for, so they are built again for each container
+/// that needs them, and other facets can't depend on them.
+struct BoxedFacet {
+    ident: Ident,
+    ty: Type,
+    fallibility: Fallibility,
+    params: Vec<FactoryParam>,
+    options: MethodOptions,
 }
 
 impl Facets {
     fn iter(
         &self,
-    ) -> impl Iterator<Item = (&Ident, &Type, Fallibility, Asyncness, &[FactoryParam])> {
+    ) -> impl Iterator<
+        Item = (
+            &Ident,
+            &Type,
+            Fallibility,
+            Asyncness,
+            &[FactoryParam],
+            &MethodOptions,
+        ),
+    > {
         self.facet_idents
             .iter()
             .zip(self.facet_types.iter())
             .zip(self.facet_fallibilities.iter())
             .zip(self.facet_asyncnesses.iter())
             .zip(self.facet_params.iter())
-            .map(|((((ident, ty), fall), asy), params)| (ident, ty, *fall, *asy, params.as_slice()))
+            .zip(self.facet_options.iter())
+            .map(|(((((ident, ty), fall), asy), params), options)| {
+                (ident, ty, *fall, *asy, params.as_slice(), options)
+            })
+    }
+
+    /// Returns the facets that are referred to weakly by any factory method,
+    /// along with the type of the weak reference.
+    /// Returns the factory parameters that are taken by value by a factory
+    /// method.
+    fn owned_params(&self) -> BTreeSet<&Ident> {
+        self.facet_params
+            .iter()
+            .flatten()
+            .filter_map(|facet_param| match facet_param {
+                FactoryParam::OwnedParam(ident) => Some(ident),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the facets that are built by the extern factory: those listed
+    /// with the extern factory, and those that this factory's methods depend
+    /// on but don't build.
+    fn extern_facets<'a>(&'a self, params: &'a Params) -> Vec<(&'a Ident, &'a Type)> {
+        let extern_factory = match &params.extern_factory {
+            Some(extern_factory) => extern_factory,
+            None => return Vec::new(),
+        };
+        let mut extern_facets: Vec<(&Ident, &Type)> = Vec::new();
+        let listed = extern_factory.facets.iter().map(|(ident, ty)| (ident, ty));
+        let dependencies = self
+            .facet_params
+            .iter()
+            .flatten()
+            .chain(self.boxed_facets.iter().flat_map(|boxed| &boxed.params))
+            .filter_map(|facet_param| match facet_param {
+                FactoryParam::Facet(ident, ty) => Some((ident, &**ty)),
+                _ => None,
+            });
+        for (ident, ty) in listed.chain(dependencies) {
+            if !self.facet_idents.contains(ident)
+                && !extern_facets
+                    .iter()
+                    .any(|(extern_ident, _)| *extern_ident == ident)
+            {
+                extern_facets.push((ident, ty));
+            }
+        }
+        extern_facets
+    }
+
+    fn weak_targets(&self) -> Result<BTreeMap<&Ident, &Type>, Error> {
+        let mut weak_targets = BTreeMap::new();
+        for facet_param in self.facet_params.iter().flatten() {
+            if let FactoryParam::WeakFacet(ident, weak_type) = facet_param {
+                if !self.facet_idents.contains(ident) {
+                    return Err(unrecognised_facet_name(ident, &self.facet_idents));
+                }
+                weak_targets.entry(ident).or_insert(&**weak_type);
+            }
+        }
+        Ok(weak_targets)
+    }
+
+    /// Returns the factory parameters that each facet depends on, directly
+    /// or through the facets it depends on.  Weak dependencies are included,
+    /// as a facet that holds a weak reference to another facet must be
+    /// rebuilt when that facet is.
+    fn param_inputs(&self) -> BTreeMap<&Ident, BTreeSet<&Ident>> {
+        let facet_params_map = self
+            .facet_idents
+            .iter()
+            .zip(&self.facet_params)
+            .collect::<BTreeMap<_, _>>();
+        let mut param_inputs = BTreeMap::new();
+        for facet_ident in &self.facet_idents {
+            let mut inputs = BTreeSet::new();
+            let mut seen = BTreeSet::new();
+            let mut queue = VecDeque::new();
+            queue.push_back(facet_ident);
+            while let Some(ident) = queue.pop_front() {
+                if !seen.insert(ident) {
+                    continue;
+                }
+                for facet_param in facet_params_map.get(ident).into_iter().copied().flatten() {
+                    match facet_param {
+                        FactoryParam::Param(param_ident)
+                        | FactoryParam::OwnedParam(param_ident)
+                        | FactoryParam::CopiedParam(param_ident) => {
+                            inputs.insert(param_ident);
+                        }
+                        FactoryParam::Facet(dep_ident, _)
+                        | FactoryParam::WeakFacet(dep_ident, _) => {
+                            queue.push_back(dep_ident);
+                        }
+                    }
+                }
+            }
+            param_inputs.insert(facet_ident, inputs);
+        }
+        param_inputs
     }
 
     fn extract_from_impl(params: &Params, factory: &mut ItemImpl) -> Result<Self, Error> {
@@ -508,15 +3641,223 @@ impl Facets {
         let mut facet_fallibilities = Vec::new();
         let mut facet_asyncnesses = Vec::new();
         let mut facet_params = Vec::new();
+        let mut facet_options = Vec::new();
+        let mut boxed_facets = Vec::new();
+        let mut post_build_hooks = Vec::new();
+        let mut derived_methods = Vec::new();
         for item in &mut factory.items {
             if let ImplItem::Method(method) = item {
+                if let Some(index) = method.attrs.iter().position(is_post_build_attr) {
+                    method.attrs.remove(index);
+                    post_build_hooks.push(Self::extract_post_build_hook(params, method)?);
+                    continue;
+                }
+                let mut options = MethodOptions::default();
+                let mut new_attrs = Vec::new();
+                for attr in method.attrs.drain(..) {
+                    if attr.path.is_ident("facet") {
+                        options.parse(&attr)?;
+                    } else {
+                        new_attrs.push(attr);
+                    }
+                }
+                method.attrs = new_attrs;
+                if options.propagate_panic && !params.catch_panics {
+                    return Err(Error::new(
+                        method.sig.span(),
+                        "facet 'propagate_panic' can only be used in factories with 'catch_panics'",
+                    ));
+                }
+                options.catch_panic = params.catch_panics && !options.propagate_panic;
+                if options.timeout.is_some() && method.sig.asyncness.is_none() {
+                    return Err(Error::new(
+                        method.sig.span(),
+                        "facet timeouts can only be used with async factory methods",
+                    ));
+                }
+                if options.backoff.is_some() && options.retries.is_none() {
+                    return Err(Error::new(
+                        method.sig.span(),
+                        "facet backoff can only be used with retries",
+                    ));
+                }
+                if let Some(RegistrySource::Param(param)) = &options.registry {
+                    if !params.param_idents.contains(param) {
+                        return Err(Error::new(
+                            param.span(),
+                            format!(
+                                "facet registry parameter '{}' is not a factory parameter",
+                                param
+                            ),
+                        ));
+                    }
+                }
                 let method_params = Self::extract_facet_params(params, &method.sig)?;
                 let (facet_ty, fallibility) = Self::extract_facet_return_type(&mut method.sig)?;
+                if options.registry.is_some()
+                    && (options.boxed
+                        || is_box_type(&facet_ty)
+                        || matches!(facet_ty, Type::Tuple(_)))
+                {
+                    return Err(Error::new(
+                        method.sig.span(),
+                        "facet registry can only be used with factory methods that build a single shared facet",
+                    ));
+                }
+                if options.retries.is_some()
+                    && (method.sig.asyncness.is_none() || fallibility == Fallibility::Infallible)
+                {
+                    return Err(Error::new(
+                        method.sig.span(),
+                        "facet retries can only be used with fallible async factory methods",
+                    ));
+                }
+                if options.boxed || is_box_type(&facet_ty) {
+                    Self::check_boxed(&method.sig, &facet_ty, &options)?;
+                    boxed_facets.push(BoxedFacet {
+                        ident: method.sig.ident.clone(),
+                        ty: facet_ty,
+                        fallibility,
+                        params: method_params,
+                        options,
+                    });
+                    continue;
+                }
+                if let Type::Tuple(tuple) = &facet_ty {
+                    for (index, element_ty) in tuple.elems.iter().enumerate() {
+                        derived_methods.push(Self::gen_tuple_element_method(
+                            &method.sig.ident,
+                            &facet_ty,
+                            index,
+                            element_ty,
+                            &options,
+                        )?);
+                    }
+                }
+                for also in &options.also {
+                    derived_methods.push(Self::gen_also_method(
+                        &method.sig.ident,
+                        &facet_ty,
+                        also,
+                        &options,
+                    )?);
+                }
                 facet_idents.push(method.sig.ident.clone());
                 facet_types.push(facet_ty);
                 facet_fallibilities.push(fallibility);
                 facet_asyncnesses.push(method.sig.asyncness.as_ref().into());
                 facet_params.push(method_params);
+                facet_options.push(options);
+            }
+        }
+        // Methods that derive facets from the facets built by other methods,
+        // either as additional facet traits or as the elements of tuples, are
+        // added to the factory.
+        for method in derived_methods {
+            facet_params.push(Self::extract_facet_params(params, &method.sig)?);
+            facet_idents.push(method.sig.ident.clone());
+            facet_types.push(Self::extract_facet_return_type(&mut method.sig.clone())?.0);
+            facet_fallibilities.push(Fallibility::Infallible);
+            facet_asyncnesses.push(Asyncness::Synchronous);
+            facet_options.push(MethodOptions::default());
+            factory.items.push(ImplItem::Method(method));
+        }
+        // Boxed facets are owned by the container they are built for, so no
+        // other facet can hold them.
+        let dependencies = facet_params
+            .iter()
+            .chain(boxed_facets.iter().map(|boxed| &boxed.params))
+            .chain(post_build_hooks.iter().map(|hook| &hook.params))
+            .flatten();
+        for facet_param in dependencies {
+            if let FactoryParam::Facet(ident, _) | FactoryParam::WeakFacet(ident, _) = facet_param {
+                if boxed_facets.iter().any(|boxed| &boxed.ident == ident) {
+                    return Err(Error::new(
+                        ident.span(),
+                        "boxed facets cannot be dependencies of other facets",
+                    ));
+                }
+            }
+        }
+        // Parameters taken by value are consumed by the one factory method
+        // that takes them, so no other method can use them.  Boxed facets are
+        // built each time they are needed, so they can't consume parameters.
+        let mut param_users = BTreeMap::new();
+        let methods = facet_idents
+            .iter()
+            .zip(&facet_params)
+            .chain(
+                boxed_facets
+                    .iter()
+                    .map(|boxed| (&boxed.ident, &boxed.params)),
+            )
+            .chain(
+                post_build_hooks
+                    .iter()
+                    .map(|hook| (&hook.ident, &hook.params)),
+            );
+        for (method_ident, method_params) in methods {
+            for facet_param in method_params {
+                match facet_param {
+                    FactoryParam::Param(ident) => {
+                        param_users
+                            .entry(ident)
+                            .or_insert_with(Vec::new)
+                            .push((method_ident, None));
+                    }
+                    FactoryParam::OwnedParam(ident) => {
+                        if boxed_facets
+                            .iter()
+                            .any(|boxed| &boxed.ident == method_ident)
+                        {
+                            return Err(Error::new(
+                                ident.span(),
+                                "boxed facets cannot take factory parameters by value",
+                            ));
+                        }
+                        param_users
+                            .entry(ident)
+                            .or_insert_with(Vec::new)
+                            .push((method_ident, Some(ident)));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for (param_ident, users) in param_users {
+            if users.len() < 2 {
+                continue;
+            }
+            if let Some((method_ident, Some(owned_ident))) =
+                users.iter().find(|(_, owned)| owned.is_some())
+            {
+                return Err(Error::new(
+                    owned_ident.span(),
+                    format!(
+                        concat!(
+                            "factory parameter '{}' is taken by value by '{}', ",
+                            "so no other factory method can use it"
+                        ),
+                        param_ident, method_ident,
+                    ),
+                ));
+            }
+        }
+        // Post-build hooks are given facets that have already been built, so
+        // they can only take facets that this factory builds.
+        for hook in &post_build_hooks {
+            if params.delegate.is_some() {
+                return Err(Error::new(
+                    hook.ident.span(),
+                    "post-build hooks are not supported for delegating factories",
+                ));
+            }
+            for hook_param in &hook.params {
+                if let FactoryParam::Facet(ident, _) = hook_param {
+                    if !facet_idents.contains(ident) {
+                        return Err(unrecognised_facet_name(ident, &facet_idents));
+                    }
+                }
             }
         }
         Ok(Facets {
@@ -525,6 +3866,172 @@ impl Facets {
             facet_fallibilities,
             facet_asyncnesses,
             facet_params,
+            facet_options,
+            boxed_facets,
+            post_build_hooks,
+        })
+    }
+
+    /// Extract a post-build hook from a method marked with
+    /// `#[facet::post_build]`.  Hooks can take facets and factory parameters
+    /// by reference, and return nothing or a `Result`.
+    fn extract_post_build_hook(
+        params: &Params,
+        method: &ImplItemMethod,
+    ) -> Result<PostBuildHook, Error> {
+        if let Some(attr) = method.attrs.iter().find(|attr| attr.path.is_ident("facet")) {
+            return Err(Error::new(
+                attr.span(),
+                "post-build hooks can't have facet options",
+            ));
+        }
+        let hook_params = Self::extract_facet_params(params, &method.sig)?;
+        for hook_param in &hook_params {
+            if let FactoryParam::OwnedParam(ident) | FactoryParam::WeakFacet(ident, _) = hook_param
+            {
+                return Err(Error::new(
+                    ident.span(),
+                    concat!(
+                        "post-build hooks can't take factory parameters by value ",
+                        "or weak references to facets"
+                    ),
+                ));
+            }
+        }
+        let fallibility = match &method.sig.output {
+            ReturnType::Default => Fallibility::Infallible,
+            ReturnType::Type(_, ty) => match &**ty {
+                Type::Tuple(tuple) if tuple.elems.is_empty() => Fallibility::Infallible,
+                Type::Path(type_path)
+                    if type_path
+                        .path
+                        .segments
+                        .last()
+                        .is_some_and(|segment| segment.ident == "Result") =>
+                {
+                    Fallibility::Fallible
+                }
+                _ => {
+                    return Err(Error::new(
+                        ty.span(),
+                        "post-build hooks must return nothing or a Result",
+                    ));
+                }
+            },
+        };
+        Ok(PostBuildHook {
+            ident: method.sig.ident.clone(),
+            fallibility,
+            asyncness: method.sig.asyncness.as_ref().into(),
+            params: hook_params,
+        })
+    }
+
+    /// Check that a factory method that builds a boxed facet doesn't use any
+    /// options that require the facet to be shared.
+    fn check_boxed(sig: &Signature, facet_ty: &Type, options: &MethodOptions) -> Result<(), Error> {
+        if sig.asyncness.is_some() {
+            return Err(Error::new(
+                sig.span(),
+                "boxed facets can only be built by synchronous factory methods",
+            ));
+        }
+        if !is_box_type(facet_ty) && matches!(facet_ty, Type::Tuple(_)) {
+            return Err(Error::new(
+                facet_ty.span(),
+                "boxed facets must be returned in a Box, not a tuple",
+            ));
+        }
+        if options.key.is_some() || options.scope != Scope::Build || !options.also.is_empty() {
+            return Err(Error::new(
+                sig.span(),
+                "boxed facets cannot be keyed, factory-scoped or provided as other facet traits",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Generate a factory method that provides the facet built by another
+    /// factory method as the facet trait given by `#[facet(also = Trait)]`,
+    /// sharing the same instance.
+    fn gen_also_method(
+        facet_ident: &Ident,
+        facet_ty: &Type,
+        also: &Path,
+        options: &MethodOptions,
+    ) -> Result<ImplItemMethod, Error> {
+        if options.key.is_some() {
+            return Err(Error::new(
+                also.span(),
+                "facet 'also' cannot be used with keyed facets",
+            ));
+        }
+        let ptr = match facet_ty {
+            Type::Path(type_path) => type_path.path.segments.last().map(|segment| &segment.ident),
+            _ => None,
+        };
+        let also_ty = match ptr {
+            Some(ptr) if ptr == "Arc" => quote! {
+                ::std::sync::Arc<dyn #also + ::std::marker::Send + ::std::marker::Sync + 'static>
+            },
+            Some(ptr) if ptr == "Rc" => quote!(::std::rc::Rc<dyn #also + 'static>),
+            _ => {
+                return Err(Error::new(
+                    facet_ty.span(),
+                    concat!(
+                        "facet 'also' requires the factory method to return an Arc ",
+                        "or Rc of the implementation type",
+                    ),
+                ));
+            }
+        };
+        let also_ident = also
+            .segments
+            .last()
+            .map(|segment| &segment.ident)
+            .ok_or_else(|| Error::new(also.span(), "expected the name of a facet trait"))?;
+        let method_ident = format_ident!(
+            "{}_as_{}",
+            facet_ident,
+            snakify_pascal_case(also_ident.to_string()),
+            span = also_ident.span(),
+        );
+        syn::parse2(quote! {
+            #[doc(hidden)]
+            fn #method_ident(&self, #facet_ident: &#facet_ty) -> #also_ty {
+                #facet_ident.clone()
+            }
+        })
+    }
+
+    /// Generate a factory method that provides one element of the tuple of
+    /// facets built by another factory method as a facet in its own right.
+    /// The facet is named after its type, e.g. `my_trait` for `ArcMyTrait`,
+    /// or after the method and the position in the tuple if the name can't
+    /// be derived from the type.
+    fn gen_tuple_element_method(
+        facet_ident: &Ident,
+        facet_ty: &Type,
+        index: usize,
+        element_ty: &Type,
+        options: &MethodOptions,
+    ) -> Result<ImplItemMethod, Error> {
+        if options.key.is_some() || !options.also.is_empty() {
+            return Err(Error::new(
+                facet_ty.span(),
+                "factory methods that return tuples cannot be keyed or use 'also'",
+            ));
+        }
+        let method_ident = match facet_name_from_type(element_ty) {
+            Some(name) => format_ident!("{}", name, span = element_ty.span()),
+            None => format_ident!("{}_{}", facet_ident, index, span = element_ty.span()),
+        };
+        let index = syn::Index::from(index);
+        syn::parse2(quote! {
+            #[doc(hidden)]
+            fn #method_ident(&self, #facet_ident: &#facet_ty) -> #element_ty {
+                #facet_ident.#index.clone()
+            }
         })
     }
 
@@ -543,14 +4050,38 @@ impl Facets {
 
     fn extract_facet_return_type(sig: &mut Signature) -> Result<(Type, Fallibility), Error> {
         if let ReturnType::Type(_, ty) = &mut sig.output {
+            if let Type::Tuple(tuple) = &**ty {
+                if tuple.elems.len() > 1 {
+                    // A tuple of facets, each of which becomes a facet in
+                    // its own right.
+                    let facet_ty = (**ty).clone();
+                    return Ok((facet_ty, Fallibility::Infallible));
+                }
+            }
             if let Type::Path(type_path) = &mut **ty {
                 if let Some(segment) = type_path.path.segments.last_mut() {
+                    if segment.ident == "Arc" || segment.ident == "Rc" || segment.ident == "Box" {
+                        // An `Arc` or `Rc` of a concrete facet type, for
+                        // containers that use static dispatch, or a boxed
+                        // facet.
+                        let facet_ty = (**ty).clone();
+                        return Ok((facet_ty, Fallibility::Infallible));
+                    }
+                    let name = segment.ident.to_string();
                     match &mut segment.arguments {
                         PathArguments::None => {
                             // The type path should be directly to the facet.
                             let facet_ty = (**ty).clone();
                             return Ok((facet_ty, Fallibility::Infallible));
                         }
+                        PathArguments::AngleBracketed(_)
+                            if name.starts_with("Arc") || name.starts_with("Rc") =>
+                        {
+                            // The alias of a generic facet, like
+                            // `ArcStore<K, V>`.
+                            let facet_ty = (**ty).clone();
+                            return Ok((facet_ty, Fallibility::Infallible));
+                        }
                         PathArguments::AngleBracketed(arguments) => {
                             if let Some(GenericArgument::Type(first_ty)) =
                                 arguments.args.first_mut()
@@ -569,17 +4100,225 @@ impl Facets {
             sig.span(),
             concat!(
                 "invalid return type ",
-                "(note: factory methods must return either an ArcFacet alias or ",
-                "a Result<ArcFacet, _>)",
+                "(note: factory methods must return either an ArcFacet alias, ",
+                "an Arc<Facet>, a Box<Facet>, a tuple of those, or a Result of one of those)",
             ),
         ))
     }
 }
 
+/// Options for a factory method, given by `#[facet(...)]` attributes.
+#[derive(Debug, Default)]
+struct MethodOptions {
+    /// How widely the built facet is shared.
+    scope: Scope,
+
+    /// The key that distinguishes this instance of the facet from other
+    /// instances of the same facet.
+    key: Option<Type>,
+
+    /// How long, in milliseconds, an async factory method may take to build
+    /// the facet.
+    timeout: Option<u64>,
+
+    /// How many times a failed async factory method is retried.
+    retries: Option<u32>,
+
+    /// How long, in milliseconds, to wait before the first retry.
+    backoff: Option<u64>,
+
+    /// Panics in the factory method are propagated to the caller of `build`
+    /// even though the factory catches panics.
+    propagate_panic: bool,
+
+    /// Panics in the factory method are caught and reported as a
+    /// `FactoryError`, as the factory has `catch_panics` and the method
+    /// doesn't have `propagate_panic`.
+    catch_panic: bool,
+
+    /// Other facet traits that the built facet is also provided as.
+    also: Vec<Path>,
+
+    /// The facet is returned in a `Box` and is owned by the container it is
+    /// built for.
+    boxed: bool,
+
+    /// The factory method resolves the facet's implementation from the
+    /// global registry, so validation checks that it is registered.
+    registry: Option<RegistrySource>,
+}
+
+/// How a factory method chooses the implementation it resolves from the
+/// registry.
+#[derive(Debug)]
+enum RegistrySource {
+    /// The implementation is named by a factory parameter, given as
+    /// `#[facet(registry = param)]`.
+    Param(Ident),
+
+    /// The only registered implementation is used, given as
+    /// `#[facet(registry)]`.
+    Only,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// The facet is built once for each build of a container.
+    #[default]
+    Build,
+
+    /// The facet is built once and cached on the factory, so that it is
+    /// shared by all containers built by the factory.
+    Factory,
+}
+
+impl MethodOptions {
+    fn parse(&mut self, attr: &Attribute) -> Result<(), Error> {
+        attr.parse_args_with(|input: ParseStream| {
+            while !input.is_empty() {
+                let fork = input.fork();
+                let option = fork.parse::<Ident>().ok().filter(|_| fork.peek(Token![=]));
+                if option.as_ref().is_some_and(|ident| ident == "also") {
+                    input.parse::<Ident>()?;
+                    input.parse::<Token![=]>()?;
+                    self.also.push(input.parse()?);
+                } else if option.as_ref().is_some_and(|ident| ident == "registry") {
+                    input.parse::<Ident>()?;
+                    input.parse::<Token![=]>()?;
+                    self.registry = Some(RegistrySource::Param(input.parse()?));
+                } else {
+                    self.parse_meta(input.parse()?)?;
+                }
+                if input.is_empty() {
+                    break;
+                }
+                input.parse::<Token![,]>()?;
+            }
+            Ok(())
+        })
+    }
+
+    fn parse_meta(&mut self, arg: Meta) -> Result<(), Error> {
+        match &arg {
+            Meta::NameValue(name_value) if name_value.path.is_ident("scope") => {
+                self.scope = match &name_value.lit {
+                    Lit::Str(s) if s.value() == "build" => Scope::Build,
+                    Lit::Str(s) if s.value() == "factory" => Scope::Factory,
+                    _ => {
+                        return Err(Error::new(
+                            name_value.lit.span(),
+                            "facet scope must be either \"build\" or \"factory\"",
+                        ));
+                    }
+                }
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("key") => {
+                self.key = Some(parse_facet_key(&name_value.lit)?);
+            }
+            Meta::Path(path) if path.is_ident("propagate_panic") => {
+                self.propagate_panic = true;
+            }
+            Meta::Path(path) if path.is_ident("boxed") => {
+                self.boxed = true;
+            }
+            Meta::Path(path) if path.is_ident("registry") => {
+                self.registry = Some(RegistrySource::Only);
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("timeout") => {
+                self.timeout = Some(parse_duration(&name_value.lit, "timeout")?);
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("retries") => {
+                self.retries = Some(match &name_value.lit {
+                    Lit::Int(retries) => retries.base10_parse()?,
+                    _ => {
+                        return Err(Error::new(
+                            name_value.lit.span(),
+                            "facet retries must be an integer",
+                        ));
+                    }
+                });
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("backoff") => {
+                self.backoff = Some(parse_duration(&name_value.lit, "backoff")?);
+            }
+            _ => return Err(Error::new(arg.span(), "unrecognised facet option")),
+        }
+        Ok(())
+    }
+}
+
+/// Parse a duration option, such as a timeout, given as a number followed
+/// by a unit, e.g. `"30s"`, returning the number of milliseconds.
+fn parse_duration(lit: &Lit, option: &str) -> Result<u64, Error> {
+    let invalid = || {
+        Error::new(
+            lit.span(),
+            format!(
+                concat!(
+                    "facet {} must be a string containing a number followed by ",
+                    "a unit of \"ms\", \"s\", \"m\" or \"h\"",
+                ),
+                option,
+            ),
+        )
+    };
+    let timeout = match lit {
+        Lit::Str(s) => s.value(),
+        _ => return Err(invalid()),
+    };
+    let unit_start = timeout
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (value, unit) = timeout.split_at(unit_start);
+    let value: u64 = value.parse().map_err(|_| invalid())?;
+    let millis_per_unit = match unit {
+        "ms" => 1,
+        "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        _ => return Err(invalid()),
+    };
+    value.checked_mul(millis_per_unit).ok_or_else(invalid)
+}
+
+/// Returns true if the type is a `Box`, and so is built as a boxed facet.
+fn is_box_type(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Box"),
+        _ => false,
+    }
+}
+
+/// The type that builders build for a facet.  Keyed facets are wrapped in
+/// `Keyed` to distinguish them from other instances of the same facet.
+fn builder_type(facet_crate: &Ident, facet_type: &Type, options: &MethodOptions) -> TokenStream {
+    match &options.key {
+        Some(key) => quote!(::#facet_crate::Keyed<#key, #facet_type>),
+        None => quote!(#facet_type),
+    }
+}
+
+/// Wrap a built facet in `Keyed` if it is a keyed facet.
+fn wrap_keyed(facet_crate: &Ident, options: &MethodOptions, facet: TokenStream) -> TokenStream {
+    match &options.key {
+        Some(_) => quote!(::#facet_crate::Keyed::new(#facet)),
+        None => facet,
+    }
+}
+
 #[derive(Debug)]
 enum FactoryParam {
     Param(Ident),
-    Facet(Ident),
+    OwnedParam(Ident),
+    /// A `FacebookInit` parameter, which is copied into each factory method
+    /// that takes it by value.
+    CopiedParam(Ident),
+    Facet(Ident, Box<Type>),
+    WeakFacet(Ident, Box<Type>),
 }
 
 impl FactoryParam {
@@ -589,21 +4328,104 @@ impl FactoryParam {
             _ => return Err(Error::new(pat_type.span(), "expected 'ident: Type'")),
         };
         match &*pat_type.ty {
-            Type::Reference(_) => {
+            Type::Reference(reference) => {
                 if params.param_idents.contains(&ident) {
                     Ok(FactoryParam::Param(ident))
+                } else if is_weak_alias(&reference.elem) {
+                    Ok(FactoryParam::WeakFacet(ident, reference.elem.clone()))
                 } else {
-                    Ok(FactoryParam::Facet(ident))
+                    Ok(FactoryParam::Facet(ident, reference.elem.clone()))
                 }
             }
-            _ => Err(Error::new(
-                pat_type.span(),
-                concat!(
-                    "factory methods must take a reference to a factory parameter ",
-                    "or a reference to a facet"
-                ),
-            )),
+            _ => match params.param_idents.iter().position(|param| param == &ident) {
+                Some(index) if is_facebook_init(&params.param_types[index]) => {
+                    Ok(FactoryParam::CopiedParam(ident))
+                }
+                Some(_) => Ok(FactoryParam::OwnedParam(ident)),
+                None => Err(Error::new(
+                    pat_type.span(),
+                    concat!(
+                        "factory methods must take a factory parameter by reference or ",
+                        "by value, or a reference to a facet"
+                    ),
+                )),
+            },
+        }
+    }
+}
+
+/// Returns true if the attribute is `#[facet::post_build]`, or
+/// `#[post_build]` if it has been imported.
+fn is_post_build_attr(attr: &Attribute) -> bool {
+    let segments = &attr.path.segments;
+    segments.len() <= 2
+        && segments
+            .last()
+            .is_some_and(|segment| segment.ident == "post_build")
+}
+
+/// Returns true if the type is `FacebookInit`, the proof that the process
+/// has been initialized, which is `Copy` and can be given to any number of
+/// factory methods.
+fn is_facebook_init(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "FacebookInit"),
+        _ => false,
+    }
+}
+
+/// Returns true if the type is a weak facet alias (e.g. `WeakMyTrait`) or
+/// a `WeakFacet`.
+fn is_weak_alias(ty: &Type) -> bool {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            return segment.ident.to_string().starts_with("Weak");
+        }
+    }
+    false
+}
+
+/// Derive the name of a facet from its type: `ArcMyTrait`, `Arc<MyStruct>`
+/// and `Arc<dyn MyTrait>` all give `my_trait` or `my_struct`.
+fn facet_name_from_type(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(type_path) => {
+            let segment = type_path.path.segments.last()?;
+            match &segment.arguments {
+                PathArguments::None => {
+                    let name = segment.ident.to_string();
+                    let name = name
+                        .strip_prefix("Arc")
+                        .or_else(|| name.strip_prefix("Rc"))
+                        .filter(|name| !name.is_empty())?;
+                    Some(snakify_pascal_case(name))
+                }
+                PathArguments::AngleBracketed(arguments)
+                    if segment.ident == "Arc" || segment.ident == "Rc" =>
+                {
+                    match arguments.args.first()? {
+                        GenericArgument::Type(Type::Path(inner)) => Some(snakify_pascal_case(
+                            inner.path.segments.last()?.ident.to_string(),
+                        )),
+                        GenericArgument::Type(Type::TraitObject(obj)) => {
+                            obj.bounds.iter().find_map(|bound| match bound {
+                                syn::TypeParamBound::Trait(bound) => Some(snakify_pascal_case(
+                                    bound.path.segments.last()?.ident.to_string(),
+                                )),
+                                _ => None,
+                            })
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
         }
+        _ => None,
     }
 }
 
@@ -627,6 +4449,45 @@ fn strip_leading_underscore(ident: &Ident) -> Ident {
     }
 }
 
+/// Check that factory-scoped facets only depend on other factory-scoped
+/// facets.  They are cached on the factory and shared by every build, so they
+/// can't use the parameters or build-scoped facets of whichever build happens
+/// to build them first.
+fn check_factory_scoped(facets: &Facets) -> Result<(), Error> {
+    let scopes = facets
+        .iter()
+        .map(|(ident, _, _, _, _, options)| (ident, options.scope))
+        .collect::<BTreeMap<_, _>>();
+    for (_, _, _, _, facet_params, options) in facets.iter() {
+        if options.scope != Scope::Factory {
+            continue;
+        }
+        for facet_param in facet_params {
+            match facet_param {
+                FactoryParam::Param(ident) | FactoryParam::OwnedParam(ident) => {
+                    return Err(Error::new(
+                        ident.span(),
+                        concat!(
+                            "factory-scoped facets can't take factory parameters, ",
+                            "as they are shared by builds with different parameters"
+                        ),
+                    ));
+                }
+                FactoryParam::Facet(ident, _) | FactoryParam::WeakFacet(ident, _) => {
+                    if scopes.get(ident) != Some(&Scope::Factory) {
+                        return Err(Error::new(
+                            ident.span(),
+                            "factory-scoped facets can only depend on other factory-scoped facets",
+                        ));
+                    }
+                }
+                FactoryParam::CopiedParam(_) => {}
+            }
+        }
+    }
+    Ok(())
+}
+
 fn check_no_cycles(
     top_ident: &Ident,
     ident_map: &BTreeMap<&Ident, &Vec<FactoryParam>>,
@@ -639,7 +4500,7 @@ fn check_no_cycles(
     while let Some((ident, route)) = queue.pop_front() {
         if let Some(params) = ident_map.get(&ident) {
             for param in *params {
-                if let FactoryParam::Facet(param_ident) = param {
+                if let FactoryParam::Facet(param_ident, _) = param {
                     seen.entry(param_ident).or_insert_with(|| {
                         let mut param_route = route.clone();
                         param_route.push(param_ident);
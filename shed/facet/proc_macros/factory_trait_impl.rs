@@ -0,0 +1,96 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Error, ItemImpl, ItemTrait, TraitItem};
+
+use crate::factory_impl::{gen_trait_factory, Params};
+
+pub fn factory_trait(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let params = parse_macro_input!(attr as Params);
+    let factory_trait = parse_macro_input!(item as ItemTrait);
+
+    match gen_factory_trait(params, factory_trait) {
+        Ok(output) => output,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
+
+fn gen_factory_trait(params: Params, mut factory_trait: ItemTrait) -> Result<TokenStream, Error> {
+    if !factory_trait.generics.params.is_empty() {
+        return Err(Error::new(
+            factory_trait.generics.span(),
+            "facet::factory_trait does not support generic traits",
+        ));
+    }
+
+    let vis = &factory_trait.vis;
+    let name = factory_trait.ident.clone();
+    let dyn_name = format_ident!("Dyn{}", name);
+
+    // Factories are shared by the facets they build, which may be built on
+    // other threads.
+    if factory_trait.colon_token.is_none() {
+        factory_trait.colon_token = Some(Default::default());
+    }
+    factory_trait
+        .supertraits
+        .push(syn::parse2(quote!(::std::marker::Send))?);
+    factory_trait
+        .supertraits
+        .push(syn::parse2(quote!(::std::marker::Sync))?);
+
+    // The factory is generated from an impl for the trait objects that
+    // declares each factory method with its facet options, which are
+    // removed from the trait.
+    let mut methods = Vec::new();
+    for item in &mut factory_trait.items {
+        let method = match item {
+            TraitItem::Method(method) => method,
+            _ => continue,
+        };
+        let (facet_attrs, attrs) = method
+            .attrs
+            .drain(..)
+            .partition::<Vec<_>, _>(|attr| attr.path.is_ident("facet"));
+        method.attrs = attrs;
+        let sig = &method.sig;
+        methods.push(quote! {
+            #( #facet_attrs )*
+            #sig {
+                ::std::unreachable!()
+            }
+        });
+    }
+    let factory_impl: ItemImpl = syn::parse2(quote! {
+        impl #dyn_name {
+            #( #methods )*
+        }
+    })?;
+    let factory = gen_trait_factory(params, &name, factory_impl)?;
+
+    let dyn_doc = format!(
+        "Trait object of any [`{}`], which is a facet factory that builds containers with the trait's methods.",
+        name
+    );
+    Ok(quote! {
+        #factory_trait
+
+        #[doc = #dyn_doc]
+        #vis type #dyn_name = dyn #name;
+
+        #factory
+    })
+}
@@ -18,8 +18,12 @@ extern crate proc_macro;
 use proc_macro_crate::{crate_name, FoundCrate};
 
 mod container_impl;
+mod delegate_impl;
 mod facet_impl;
 mod factory_impl;
+mod factory_trait_impl;
+mod mock_impl;
+mod test_impl;
 mod util;
 
 fn facet_crate_name() -> String {
@@ -39,6 +43,17 @@ pub fn container(
     container_impl::container(attr, item)
 }
 
+/// Mark a wrapper `struct` as delegating facet access to the container it
+/// wraps.  See the crate-level documentation for the `facet` crate for
+/// details.
+#[proc_macro_attribute]
+pub fn delegate(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    delegate_impl::delegate(attr, item)
+}
+
 /// Mark a `trait` as a facet.  See the crate-level documentation for the
 /// `facet` crate for details.
 #[proc_macro_attribute]
@@ -58,3 +73,46 @@ pub fn factory(
 ) -> proc_macro::TokenStream {
     factory_impl::factory(attr, item)
 }
+
+/// Mark a `trait` as defining a facet factory, whose trait objects can
+/// build containers.  See the crate-level documentation for the `facet`
+/// crate for details.
+#[proc_macro_attribute]
+pub fn factory_trait(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    factory_trait_impl::factory_trait(attr, item)
+}
+
+/// Mark a method of a facet factory as a hook that is run after the facets
+/// a container needs have been built.  See the crate-level documentation for
+/// the `facet` crate for details.
+#[proc_macro_attribute]
+pub fn post_build(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    factory_impl::post_build(attr, item)
+}
+
+/// Generate a mock implementation of a facet `trait`.  See the crate-level
+/// documentation for the `facet` crate for details.
+#[proc_macro_attribute]
+pub fn mock(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    mock_impl::mock(attr, item)
+}
+
+/// Mark a function as a test that is given a container built by a facet
+/// factory.  See the crate-level documentation for the `facet` crate for
+/// details.
+#[proc_macro_attribute]
+pub fn test(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    test_impl::test(attr, item)
+}
@@ -0,0 +1,263 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::{
+    parse_macro_input, Error, FnArg, GenericParam, ItemTrait, Lifetime, ReturnType, TraitItem, Type,
+};
+
+use crate::facet_crate_name;
+use crate::util::Asyncness;
+
+pub fn mock(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let _ = parse_macro_input!(attr as syn::parse::Nothing);
+    let facet = parse_macro_input!(item as ItemTrait);
+
+    match gen_mock(facet) {
+        Ok(output) => output,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
+
+fn gen_mock(facet: ItemTrait) -> Result<TokenStream, Error> {
+    if !facet.generics.params.is_empty() {
+        return Err(Error::new(
+            facet.generics.span(),
+            "facet::mock does not support generic traits",
+        ));
+    }
+
+    let facet_crate = format_ident!("{}", facet_crate_name());
+    let vis = &facet.vis;
+    let name = &facet.ident;
+    let mock_name = format_ident!("Mock{}", name);
+
+    let mut method_idents = Vec::new();
+    let mut method_docs = Vec::new();
+    let mut arg_types = Vec::new();
+    let mut return_types = Vec::new();
+    let mut unit_idents = Vec::new();
+    let mut method_impls = Vec::new();
+    let mut is_async = Asyncness::Synchronous;
+
+    for item in &facet.items {
+        let method = match item {
+            TraitItem::Method(method) => method,
+            TraitItem::Const(item) if item.default.is_none() => {
+                return Err(Error::new(
+                    item.span(),
+                    "facet::mock does not support associated constants",
+                ));
+            }
+            TraitItem::Type(item) if item.default.is_none() => {
+                return Err(Error::new(
+                    item.span(),
+                    "facet::mock does not support associated types",
+                ));
+            }
+            _ => continue,
+        };
+        if method.default.is_some() {
+            // Methods with default implementations keep them.
+            continue;
+        }
+
+        // Methods can have lifetime parameters, but not type or const
+        // parameters, as the mock records the arguments of each call.
+        let sig = &method.sig;
+        if sig
+            .generics
+            .params
+            .iter()
+            .any(|param| !matches!(param, GenericParam::Lifetime(_)))
+        {
+            return Err(Error::new(
+                sig.generics.span(),
+                "facet::mock does not support generic methods",
+            ));
+        }
+        let method_lifetimes = sig
+            .generics
+            .lifetimes()
+            .map(|def| &def.lifetime)
+            .collect::<Vec<_>>();
+
+        let mut receiver = None;
+        let mut params = Vec::new();
+        let mut record_args = Vec::new();
+        let mut owned_types = Vec::new();
+        for (index, input) in sig.inputs.iter().enumerate() {
+            match input {
+                FnArg::Receiver(self_arg)
+                    if self_arg.reference.is_some() && self_arg.mutability.is_none() =>
+                {
+                    receiver = Some(self_arg);
+                }
+                FnArg::Receiver(receiver) => {
+                    return Err(Error::new(
+                        receiver.span(),
+                        "facet::mock methods must take '&self'",
+                    ));
+                }
+                FnArg::Typed(pat_type) => {
+                    let arg = format_ident!("__arg{}", index);
+                    let ty = &pat_type.ty;
+                    params.push(quote!(#arg: #ty));
+                    // Arguments passed by reference are recorded as owned
+                    // copies, other arguments are recorded by moving them.
+                    let recorded_ty = match &**ty {
+                        Type::Reference(reference) => &*reference.elem,
+                        ty => ty,
+                    };
+                    if borrows_from(recorded_ty, &method_lifetimes) {
+                        return Err(Error::new(
+                            ty.span(),
+                            "facet::mock cannot record arguments that borrow with the method's lifetimes",
+                        ));
+                    }
+                    match &**ty {
+                        Type::Reference(reference) => {
+                            let elem = &reference.elem;
+                            record_args.push(quote!(::std::borrow::ToOwned::to_owned(&*#arg)));
+                            owned_types.push(quote!(<#elem as ::std::borrow::ToOwned>::Owned));
+                        }
+                        Type::ImplTrait(_) => {
+                            return Err(Error::new(
+                                ty.span(),
+                                "facet::mock does not support generic methods",
+                            ));
+                        }
+                        _ => {
+                            record_args.push(quote!(#arg));
+                            owned_types.push(quote!(#ty));
+                        }
+                    }
+                }
+            }
+        }
+
+        let receiver = receiver
+            .ok_or_else(|| Error::new(sig.span(), "facet::mock methods must take '&self'"))?;
+
+        let return_type = match &sig.output {
+            ReturnType::Default => {
+                unit_idents.push(&sig.ident);
+                quote!(())
+            }
+            ReturnType::Type(_, ty) => match &**ty {
+                Type::Tuple(tuple) if tuple.elems.is_empty() => {
+                    unit_idents.push(&sig.ident);
+                    quote!(())
+                }
+                ty if matches!(ty, Type::Reference(_) | Type::ImplTrait(_))
+                    || borrows_from(ty, &method_lifetimes) =>
+                {
+                    return Err(Error::new(
+                        ty.span(),
+                        "facet::mock methods must return owned, concrete types",
+                    ));
+                }
+                _ => quote!(#ty),
+            },
+        };
+
+        let asyncness = Asyncness::from(sig.asyncness.as_ref());
+        if asyncness.is_async() {
+            is_async = Asyncness::Asynchronous;
+        }
+        let maybe_async = asyncness.maybe(quote!(async));
+        let method_ident = &sig.ident;
+        let (method_generics, _, method_where_clause) = sig.generics.split_for_impl();
+        method_impls.push(quote! {
+            #maybe_async fn #method_ident #method_generics(
+                #receiver,
+                #( #params, )*
+            ) -> #return_type #method_where_clause {
+                self.#method_ident.call(( #( #record_args, )* ))
+            }
+        });
+
+        method_docs.push(format!("Mock of `{}::{}`.", name, method_ident));
+        method_idents.push(method_ident);
+        arg_types.push(quote!(( #( #owned_types, )* )));
+        return_types.push(return_type);
+    }
+
+    let mock_doc = format!("Mock implementation of `{}`.", name);
+    let maybe_async_trait = is_async.maybe(quote!(#[::#facet_crate::async_trait::async_trait]));
+
+    Ok(quote! {
+        #facet
+
+        #[doc = #mock_doc]
+        #vis struct #mock_name {
+            #(
+                #[doc = #method_docs]
+                #vis #method_idents: ::#facet_crate::MockMethod<#arg_types, #return_types>,
+            )*
+        }
+
+        impl #mock_name {
+            /// Create a new mock.  Methods that return `()` do nothing,
+            /// all other methods must be given a return value before they
+            /// are called.
+            #vis fn new() -> Self {
+                let mock = Self {
+                    #(
+                        #method_idents: ::#facet_crate::MockMethod::new(
+                            concat!(stringify!(#name), "::", stringify!(#method_idents)),
+                        ),
+                    )*
+                };
+                #( mock.#unit_idents.returns(()); )*
+                mock
+            }
+        }
+
+        impl ::std::default::Default for #mock_name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        #maybe_async_trait
+        impl #name for #mock_name {
+            #( #method_impls )*
+        }
+    })
+}
+
+/// Returns true if the type mentions any of the lifetimes, which means values
+/// of the type borrow data that may not outlive the call.
+fn borrows_from(ty: &Type, lifetimes: &[&Lifetime]) -> bool {
+    struct FindLifetimes<'a> {
+        lifetimes: &'a [&'a Lifetime],
+        found: bool,
+    }
+
+    impl<'ast> Visit<'ast> for FindLifetimes<'_> {
+        fn visit_lifetime(&mut self, lifetime: &'ast Lifetime) {
+            self.found |= self.lifetimes.contains(&lifetime);
+        }
+    }
+
+    let mut finder = FindLifetimes {
+        lifetimes,
+        found: false,
+    };
+    finder.visit_type(ty);
+    finder.found
+}
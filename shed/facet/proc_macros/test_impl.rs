@@ -0,0 +1,117 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{parenthesized, parse_macro_input, Error, Expr, FnArg, Ident, ItemFn, Token};
+
+/// Options for a test, given as
+/// `#[facet::test(factory = Factory, params(...))]`.
+struct TestAttr {
+    /// The factory that builds the container for the test.
+    factory: Expr,
+
+    /// The parameters passed to the factory's build method.
+    params: Vec<Expr>,
+}
+
+impl Parse for TestAttr {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let mut factory = None;
+        let mut params = Vec::new();
+        while !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            if ident == "factory" {
+                input.parse::<Token![=]>()?;
+                factory = Some(input.parse()?);
+            } else if ident == "params" {
+                let content;
+                parenthesized!(content in input);
+                params.extend(Punctuated::<Expr, Token![,]>::parse_terminated(&content)?);
+            } else {
+                return Err(Error::new(ident.span(), "unrecognised test option"));
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        let factory = factory.ok_or_else(|| {
+            Error::new(
+                input.span(),
+                "facet::test requires a factory, given as `factory = Factory`",
+            )
+        })?;
+        Ok(TestAttr { factory, params })
+    }
+}
+
+pub fn test(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let attr = parse_macro_input!(attr as TestAttr);
+    let test = parse_macro_input!(item as ItemFn);
+
+    match gen_test(attr, test) {
+        Ok(output) => output,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
+
+fn gen_test(attr: TestAttr, mut test: ItemFn) -> Result<TokenStream, Error> {
+    if test.sig.inputs.len() != 1 {
+        return Err(Error::new(
+            test.sig.inputs.span(),
+            "facet::test functions must take exactly one argument, the container",
+        ));
+    }
+    let (pat, container_ty) = match test.sig.inputs.pop().map(|pair| pair.into_value()) {
+        Some(FnArg::Typed(pat_type)) => (pat_type.pat, pat_type.ty),
+        Some(FnArg::Receiver(r)) => {
+            return Err(Error::new(
+                r.span(),
+                "receivers not supported in facet::test functions",
+            ));
+        }
+        None => unreachable!("test has exactly one argument"),
+    };
+
+    let factory = &attr.factory;
+    let params = &attr.params;
+    // Async tests build the container with an async factory.
+    let (test_attr, build) = if test.sig.asyncness.is_some() {
+        (
+            quote!(#[::tokio::test]),
+            quote!(factory.build::<#container_ty>( #( #params, )* ).await),
+        )
+    } else {
+        (
+            quote!(#[::core::prelude::v1::test]),
+            quote!(factory.build::<#container_ty>( #( #params, )* )),
+        )
+    };
+    let body = &test.block;
+    test.block = syn::parse2(quote! {
+        {
+            let factory = #factory;
+            let #pat: #container_ty = #build.expect("failed to build container for test");
+            #body
+        }
+    })?;
+
+    Ok(quote! {
+        #test_attr
+        #test
+    })
+}
@@ -9,7 +9,7 @@
 
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::Token;
+use syn::{Error, Ident, Lit, Token, Type};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(crate) enum Asyncness {
@@ -50,6 +50,19 @@ impl Asyncness {
     }
 }
 
+/// The attribute for the methods that give access to facets, which must
+/// pass on their caller's location if the `access_tracking` feature is
+/// enabled, so that accesses are recorded against the code that made them.
+#[cfg(feature = "access_tracking")]
+pub(crate) fn access_attr() -> TokenStream {
+    quote!(#[track_caller])
+}
+
+#[cfg(not(feature = "access_tracking"))]
+pub(crate) fn access_attr() -> TokenStream {
+    quote!()
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(crate) enum Fallibility {
     /// Method returns the Facet infallibly
@@ -67,3 +80,103 @@ impl Fallibility {
         }
     }
 }
+
+/// Parse the name given to a facet with `name = "..."`, which must be a
+/// valid identifier.
+pub(crate) fn parse_facet_name(lit: &Lit) -> Result<Ident, Error> {
+    match lit {
+        Lit::Str(name) => name
+            .parse::<Ident>()
+            .map_err(|_| Error::new(name.span(), "facet name must be a valid identifier")),
+        _ => Err(Error::new(lit.span(), "facet name must be a string")),
+    }
+}
+
+/// Parse the key given to a keyed facet with `key = "..."`, which must be
+/// the name of a type.
+pub(crate) fn parse_facet_key(lit: &Lit) -> Result<Type, Error> {
+    match lit {
+        Lit::Str(key) => key
+            .parse::<Type>()
+            .map_err(|_| Error::new(key.span(), "facet key must be a type")),
+        _ => Err(Error::new(lit.span(), "facet key must be a string")),
+    }
+}
+
+/// Converts a Pascal case name like `SomeTraitName` to snake case like
+/// `some_trait_name`.
+pub(crate) fn snakify_pascal_case(pascal: impl AsRef<str>) -> String {
+    let mut snake = String::new();
+    for ch in pascal.as_ref().chars() {
+        if ch.is_uppercase() {
+            if !snake.is_empty() {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
+
+/// Returns the error for a factory method parameter that names a facet the
+/// factory doesn't build, suggesting the closest of the factory's facet and
+/// parameter names if it is similar enough to be a likely misspelling.
+pub(crate) fn unrecognised_facet_name<'a>(
+    ident: &Ident,
+    candidates: impl IntoIterator<Item = &'a Ident>,
+) -> Error {
+    match closest_name(&ident.to_string(), candidates) {
+        Some(closest) => Error::new(
+            ident.span(),
+            format!(
+                "unrecognised facet name '{}' (did you mean '{}'?)",
+                ident, closest
+            ),
+        ),
+        None => Error::new(ident.span(), format!("unrecognised facet name '{}'", ident)),
+    }
+}
+
+/// Returns the candidate closest to `name`, if it is within an edit distance
+/// of a third of the length of `name` (and at least one).
+fn closest_name<'a>(name: &str, candidates: impl IntoIterator<Item = &'a Ident>) -> Option<String> {
+    let max_distance = std::cmp::max(name.chars().count() / 3, 1);
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let candidate = candidate.to_string();
+            (edit_distance(name, &candidate), candidate)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_ch) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_ch) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_ch != *b_ch);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The key that identifies a facet by name in `StaticFacet`, which is the
+/// 128-bit FNV-1a hash of the name.
+pub(crate) fn static_facet_key(facet_ident: &Ident) -> proc_macro2::Literal {
+    let mut hash: u128 = 0x6c62272e07bb014262b821756295c58d;
+    for byte in facet_ident.to_string().bytes() {
+        hash ^= u128::from(byte);
+        hash = hash.wrapping_mul(0x0000000001000000000000000000013b);
+    }
+    proc_macro2::Literal::u128_unsuffixed(hash)
+}
@@ -0,0 +1,126 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Recording of the call sites that access facets, when the
+//! `access_tracking` feature is enabled.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::panic::Location;
+use std::sync::Mutex;
+
+type AccessKey = (&'static str, &'static str, &'static Location<'static>);
+
+/// The number of accesses of each facet field, by container, field and call
+/// site.
+static ACCESSES: Mutex<BTreeMap<AccessKey, u64>> = Mutex::new(BTreeMap::new());
+
+// Record an access of a facet field of a container.  Called by the facet
+// access traits generated for containers, which are all `#[track_caller]`,
+// so the location is that of the code that accessed the facet.
+#[doc(hidden)]
+#[track_caller]
+pub fn record_access(container: &'static str, facet: &'static str) {
+    let location = Location::caller();
+    let mut accesses = ACCESSES.lock().expect("lock poisoned");
+    *accesses.entry((container, facet, location)).or_default() += 1;
+}
+
+/// The accesses of a facet field of a container from one call site.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FacetAccess {
+    /// The name of the container.
+    pub container: &'static str,
+
+    /// The name of the facet field.
+    pub facet: &'static str,
+
+    /// The location of the code that accessed the facet.
+    pub location: &'static Location<'static>,
+
+    /// The number of times the facet was accessed from this location.
+    pub count: u64,
+}
+
+/// A report of the facets that have been accessed through containers, and
+/// where from.
+///
+/// This is returned by `access_report`.  Accesses are ordered by container,
+/// then facet field, then location.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AccessReport {
+    /// The recorded accesses.
+    pub accesses: Vec<FacetAccess>,
+}
+
+impl AccessReport {
+    /// The accesses of the facets of the named container.
+    pub fn container(&self, container: &str) -> Vec<&FacetAccess> {
+        self.accesses
+            .iter()
+            .filter(|access| access.container == container)
+            .collect()
+    }
+
+    /// The names of the facet fields of the named container that have been
+    /// accessed.
+    pub fn accessed_facets(&self, container: &str) -> Vec<&'static str> {
+        let mut facets: Vec<_> = self
+            .container(container)
+            .into_iter()
+            .map(|access| access.facet)
+            .collect();
+        facets.dedup();
+        facets
+    }
+}
+
+impl fmt::Display for AccessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for access in &self.accesses {
+            writeln!(
+                f,
+                "{}.{} accessed {} time{} at {}",
+                access.container,
+                access.facet,
+                access.count,
+                if access.count == 1 { "" } else { "s" },
+                access.location,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns a report of the facet accesses that have been recorded since
+/// the program started, or since the last call to `reset_access_report`.
+///
+/// Accesses are recorded for all containers when the `access_tracking`
+/// feature is enabled, which makes each access take a global lock, so it is
+/// intended for finding out which facets a program actually uses rather
+/// than for use in production.
+pub fn access_report() -> AccessReport {
+    let accesses = ACCESSES.lock().expect("lock poisoned");
+    AccessReport {
+        accesses: accesses
+            .iter()
+            .map(|(&(container, facet, location), &count)| FacetAccess {
+                container,
+                facet,
+                location,
+                count,
+            })
+            .collect(),
+    }
+}
+
+/// Discards the facet accesses that have been recorded so far.
+pub fn reset_access_report() {
+    ACCESSES.lock().expect("lock poisoned").clear();
+}
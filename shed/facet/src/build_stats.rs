@@ -0,0 +1,72 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Per-facet build stats, recorded when the `stats` feature is enabled.
+
+use std::future::Future;
+use std::time::Instant;
+
+use stats::prelude::*;
+
+define_stats! {
+    prefix = "facet";
+    build_count: dynamic_counter("{}.build_count", (facet: &'static str)),
+    build_errors: dynamic_counter("{}.build_errors", (facet: &'static str)),
+    build_time_ms: dynamic_histogram(
+        "{}.build_time_ms", (facet: &'static str);
+        10, 0, 10_000, Average, Sum, Count; P 50; P 90; P 99
+    ),
+}
+
+// Call a factory method to build a facet, recording the build in the
+// facet's stats.  `is_error` determines whether the method's output is a
+// failed build.  Builds that panic or time out are recorded as errors by the
+// code that catches them.
+#[doc(hidden)]
+pub fn build_with_stats<T>(
+    name: &'static str,
+    build: impl FnOnce() -> T,
+    is_error: impl FnOnce(&T) -> bool,
+) -> T {
+    let start = Instant::now();
+    STATS::build_count.increment_value(1, (name,));
+    let output = build();
+    record_outcome(name, start, is_error(&output));
+    output
+}
+
+// Async version of `build_with_stats`.  Builds that are cancelled are
+// counted, but are neither timed nor recorded as errors.
+#[doc(hidden)]
+pub async fn build_with_stats_async<F: Future>(
+    name: &'static str,
+    build: F,
+    is_error: impl FnOnce(&F::Output) -> bool,
+) -> F::Output {
+    let start = Instant::now();
+    STATS::build_count.increment_value(1, (name,));
+    let output = build.await;
+    record_outcome(name, start, is_error(&output));
+    output
+}
+
+fn record_outcome(name: &'static str, start: Instant, failed: bool) {
+    if failed {
+        record_build_error(name);
+    } else {
+        let millis = i64::try_from(start.elapsed().as_millis()).unwrap_or(i64::MAX);
+        STATS::build_time_ms.add_value(millis, (name,));
+    }
+}
+
+// Record a failed build of a facet whose factory method did not return,
+// because it panicked or timed out.
+pub(crate) fn record_build_error(name: &'static str) {
+    STATS::build_errors.increment_value(1, (name,));
+}
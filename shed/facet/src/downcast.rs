@@ -0,0 +1,29 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Downcasting of facets to their concrete types.
+
+use std::any::Any;
+
+/// Access to a value as `dyn Any`, so that it can be downcast to its
+/// concrete type.
+///
+/// Facet traits marked with `#[facet::facet(downcast)]` have this trait as
+/// a supertrait, so that facet trait objects can be downcast.
+pub trait AsAny: Any {
+    /// Access this value as `dyn Any`.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,176 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Runtime introspection of facet dependency graphs.
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// A facet that a factory knows how to build.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FacetNode {
+    /// The name of the facet, which is the name of the factory method that
+    /// builds it.
+    pub name: &'static str,
+
+    /// The names of the facets that this facet depends on.
+    pub dependencies: Vec<&'static str>,
+
+    /// The names of the facets that this facet holds weak references to.
+    /// These are not dependencies, and may form cycles.
+    pub weak_dependencies: Vec<&'static str>,
+
+    /// The names of the factory parameters that this facet uses.
+    pub params: Vec<&'static str>,
+
+    /// The container fields that consume this facet, as `Container.field`
+    /// paths.  This is only populated for containers that have been added
+    /// with [`FacetGraph::with_container`].
+    pub consumers: Vec<String>,
+
+    /// The alternate factory methods that can build this facet instead of
+    /// the facet's own method, in the order their conditions are checked.
+    /// The dependencies and parameters of the facet include those of all of
+    /// its alternates.
+    pub alternates: Vec<FacetAlternate>,
+}
+
+/// An alternate factory method for a facet, which builds the facet when its
+/// condition holds.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FacetAlternate {
+    /// The name of the alternate factory method.
+    pub method: &'static str,
+
+    /// The condition that selects this alternate, as written in its
+    /// `#[facet(select = "...")]` attribute.
+    pub condition: &'static str,
+
+    /// The names of the facets that the alternate depends on.
+    pub dependencies: Vec<&'static str>,
+
+    /// The names of the factory parameters that the alternate uses.
+    pub params: Vec<&'static str>,
+}
+
+/// The dependency graph of the facets a factory can build.
+///
+/// This is returned by the `facet_graph` method that is generated for
+/// each factory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FacetGraph {
+    /// The name of the factory.
+    pub factory: &'static str,
+
+    /// The facets the factory can build, in the order the factory methods
+    /// are defined.
+    pub facets: Vec<FacetNode>,
+}
+
+impl FacetGraph {
+    /// Returns the node for the named facet, if the factory can build it.
+    pub fn facet(&self, name: &str) -> Option<&FacetNode> {
+        self.facets.iter().find(|node| node.name == name)
+    }
+
+    /// Returns the dependency edges of the graph as `(facet, dependency)`
+    /// pairs.
+    pub fn edges(&self) -> impl Iterator<Item = (&'static str, &'static str)> + '_ {
+        self.facets
+            .iter()
+            .flat_map(|node| node.dependencies.iter().map(move |dep| (node.name, *dep)))
+    }
+
+    /// Render the graph in Graphviz DOT format.
+    ///
+    /// Facets are drawn as ellipses with an edge from each facet to the
+    /// facets it depends on, or a dotted edge for weak references.  Factory
+    /// parameters are drawn as boxes, with dashed edges from the facets that
+    /// use them.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        // Writing to a `String` cannot fail.
+        let _ = writeln!(dot, "digraph {} {{", self.factory);
+        let mut params = Vec::new();
+        for node in &self.facets {
+            let _ = writeln!(dot, "    \"{}\";", node.name);
+            for param in &node.params {
+                if !params.contains(param) {
+                    params.push(*param);
+                }
+            }
+        }
+        for param in &params {
+            let _ = writeln!(dot, "    \"param:{0}\" [label=\"{0}\", shape=box];", param);
+        }
+        for (facet, dependency) in self.edges() {
+            let _ = writeln!(dot, "    \"{}\" -> \"{}\";", facet, dependency);
+        }
+        for node in &self.facets {
+            for weak in &node.weak_dependencies {
+                let _ = writeln!(dot, "    \"{}\" -> \"{}\" [style=dotted];", node.name, weak);
+            }
+        }
+        for node in &self.facets {
+            for param in &node.params {
+                let _ = writeln!(
+                    dot,
+                    "    \"{}\" -> \"param:{}\" [style=dashed];",
+                    node.name, param
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Record the fields of container `C` as consumers of the facets they
+    /// hold.
+    pub fn with_container<C: ContainerFacets>(mut self) -> Self {
+        for field in C::facet_fields() {
+            if let Some(node) = self.facets.iter_mut().find(|node| node.name == field.facet) {
+                node.consumers
+                    .push(format!("{}.{}", C::container_name(), field.path));
+            }
+        }
+        self
+    }
+}
+
+/// A field of a container that holds a facet.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ContainerField {
+    /// The path to the field.  For facets that are delegated to a nested
+    /// container, this includes the name of the delegate field, e.g.
+    /// `inner.my_facet`.
+    pub path: String,
+
+    /// The name of the facet held by the field.
+    pub facet: &'static str,
+}
+
+/// Trait implemented by containers to describe the facets they hold.
+pub trait ContainerFacets {
+    /// The name of the container.
+    fn container_name() -> &'static str;
+
+    /// The fields of the container that hold facets, including the facets
+    /// of any nested containers.
+    fn facet_fields() -> Vec<ContainerField>;
+}
+
+impl<C: ContainerFacets> ContainerFacets for Arc<C> {
+    fn container_name() -> &'static str {
+        C::container_name()
+    }
+
+    fn facet_fields() -> Vec<ContainerField> {
+        C::facet_fields()
+    }
+}
@@ -0,0 +1,79 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Health checks of container facets.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::{join_all, BoxFuture};
+
+/// The health of a facet, as reported by its health check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HealthStatus {
+    /// The facet is working normally.
+    Healthy,
+
+    /// The facet is working, but with reduced capacity or performance.
+    Degraded(String),
+
+    /// The facet is not working.
+    Unhealthy(String),
+}
+
+impl HealthStatus {
+    /// Returns true if the facet is healthy.
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, HealthStatus::Healthy)
+    }
+}
+
+/// Trait for facets that can report their health, for example by checking
+/// that a connection is still open.
+///
+/// For dynamic facets, make this a supertrait of the facet trait.
+#[async_trait]
+pub trait FacetHealth {
+    /// Check the health of this facet.
+    async fn check_health(&self) -> HealthStatus;
+}
+
+/// Trait implemented by containers to check the health of their facets.
+#[async_trait]
+pub trait ContainerHealth {
+    // Futures that check the health of each of the facets of this container
+    // (and its nested containers) that are marked with `#[facet(health)]`,
+    // along with the name of the facet.
+    #[doc(hidden)]
+    fn health_checks(&self) -> Vec<(&'static str, BoxFuture<'_, HealthStatus>)>;
+
+    /// Check the health of the facets of this container that are marked with
+    /// `#[facet(health)]`, returning the status of each facet by name.
+    ///
+    /// The facets are checked concurrently.  Facets shared with nested
+    /// containers are only checked once.
+    async fn check_health(&self) -> BTreeMap<&'static str, HealthStatus> {
+        let mut names = Vec::new();
+        let mut checks = Vec::new();
+        for (name, check) in self.health_checks() {
+            if !names.contains(&name) {
+                names.push(name);
+                checks.push(check);
+            }
+        }
+        names.into_iter().zip(join_all(checks).await).collect()
+    }
+}
+
+impl<C: ContainerHealth> ContainerHealth for Arc<C> {
+    fn health_checks(&self) -> Vec<(&'static str, BoxFuture<'_, HealthStatus>)> {
+        C::health_checks(self)
+    }
+}
@@ -0,0 +1,108 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Builds that use facets that have already been built.
+
+use std::marker::PhantomData;
+
+use crate::{AsyncBuildable, Buildable, FactoryError, PostBuild};
+
+// Trait implemented by factory builders that can be given an already-built
+// facet of type T.
+#[doc(hidden)]
+pub trait InjectFacet<T> {
+    fn inject(&mut self, facet: T);
+}
+
+/// A build of a container of type `T` that can be given facets that have
+/// already been built.
+///
+/// This is returned by the `build_with` method that is generated for each
+/// synchronous factory.
+pub struct BuildWith<B, T> {
+    builder: B,
+    container: PhantomData<fn() -> T>,
+}
+
+impl<B, T> BuildWith<B, T> {
+    #[doc(hidden)]
+    pub fn new(builder: B) -> Self {
+        BuildWith {
+            builder,
+            container: PhantomData,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn builder_mut(&mut self) -> &mut B {
+        &mut self.builder
+    }
+
+    /// Use an already-built facet rather than building it with the factory.
+    pub fn facet<F>(mut self, facet: F) -> Self
+    where
+        B: InjectFacet<F>,
+    {
+        self.builder.inject(facet);
+        self
+    }
+
+    /// Build the container.
+    pub fn finish(mut self) -> Result<T, FactoryError>
+    where
+        T: Buildable<B>,
+        B: PostBuild,
+    {
+        let container = T::build(&mut self.builder)?;
+        self.builder.post_build()?;
+        Ok(container)
+    }
+}
+
+/// A build of a container of type `T` that can be given facets that have
+/// already been built.
+///
+/// This is returned by the `build_with` method that is generated for each
+/// asynchronous factory.
+pub struct AsyncBuildWith<B, T> {
+    builder: B,
+    container: PhantomData<fn() -> T>,
+}
+
+impl<B, T> AsyncBuildWith<B, T> {
+    #[doc(hidden)]
+    pub fn new(builder: B) -> Self {
+        AsyncBuildWith {
+            builder,
+            container: PhantomData,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn builder_mut(&mut self) -> &mut B {
+        &mut self.builder
+    }
+
+    /// Use an already-built facet rather than building it with the factory.
+    pub fn facet<F>(mut self, facet: F) -> Self
+    where
+        B: InjectFacet<F>,
+    {
+        self.builder.inject(facet);
+        self
+    }
+
+    /// Build the container.
+    pub async fn finish<'builder>(self) -> Result<T, FactoryError>
+    where
+        T: AsyncBuildable<'builder, B>,
+    {
+        T::build_async(self.builder).await
+    }
+}
@@ -0,0 +1,82 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Keyed facets.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+// An instance of a facet that is distinguished from other instances of the
+// same facet by the key type `K`.  Builders build these for keyed facets.
+#[doc(hidden)]
+pub struct Keyed<K: ?Sized, T> {
+    facet: T,
+    key: PhantomData<fn() -> Box<K>>,
+}
+
+impl<K: ?Sized, T> Keyed<K, T> {
+    #[doc(hidden)]
+    pub fn new(facet: T) -> Self {
+        Keyed {
+            facet,
+            key: PhantomData,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn into_inner(self) -> T {
+        self.facet
+    }
+}
+
+impl<K: ?Sized, T: Clone> Clone for Keyed<K, T> {
+    fn clone(&self) -> Self {
+        Keyed::new(self.facet.clone())
+    }
+}
+
+/// Trait implemented by containers that can provide a reference to the
+/// instance of the facet `T` with key `K`.
+pub trait KeyedFacetRef<K: ?Sized, T: ?Sized + Send + Sync + 'static> {
+    /// Access the facet with key `K` by reference.
+    fn keyed_facet_ref(&self) -> &T;
+}
+
+impl<K, T, C> KeyedFacetRef<K, T> for Arc<C>
+where
+    K: ?Sized,
+    T: ?Sized + Send + Sync + 'static,
+    C: KeyedFacetRef<K, T>,
+{
+    #[inline]
+    #[cfg_attr(feature = "access_tracking", track_caller)]
+    fn keyed_facet_ref(&self) -> &T {
+        <C as KeyedFacetRef<K, T>>::keyed_facet_ref(self)
+    }
+}
+
+/// Trait implemented by containers that can provide an arc to the instance
+/// of the facet `T` with key `K`.
+pub trait KeyedFacetArc<K: ?Sized, T: ?Sized + Send + Sync + 'static> {
+    /// Access a cloneable reference to the facet with key `K`.
+    fn keyed_facet_arc(&self) -> Arc<T>;
+}
+
+impl<K, T, C> KeyedFacetArc<K, T> for Arc<C>
+where
+    K: ?Sized,
+    T: ?Sized + Send + Sync + 'static,
+    C: KeyedFacetArc<K, T>,
+{
+    #[inline]
+    #[cfg_attr(feature = "access_tracking", track_caller)]
+    fn keyed_facet_arc(&self) -> Arc<T> {
+        <C as KeyedFacetArc<K, T>>::keyed_facet_arc(self)
+    }
+}
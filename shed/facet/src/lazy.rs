@@ -0,0 +1,92 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Lazily-built facets.
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use async_once_cell::AsyncOnceCell;
+use futures::future::{BoxFuture, FutureExt};
+
+use crate::FactoryError;
+
+type LazyInit<A> = Box<dyn Fn() -> BoxFuture<'static, Result<A, FactoryError>> + Send + Sync>;
+
+/// A facet that is built the first time it is accessed, rather than when
+/// the container is built.
+///
+/// Containers store this for fields marked with `#[facet(lazy)]`.  `A` is
+/// the `Arc`-wrapped facet type.
+pub struct LazyFacet<A> {
+    cell: AsyncOnceCell<A>,
+    init: LazyInit<A>,
+}
+
+impl<A> LazyFacet<A>
+where
+    A: Clone + Send + Sync + 'static,
+{
+    #[doc(hidden)]
+    pub fn new(
+        init: impl Fn() -> BoxFuture<'static, Result<A, FactoryError>> + Send + Sync + 'static,
+    ) -> Self {
+        LazyFacet {
+            cell: AsyncOnceCell::new(),
+            init: Box::new(init),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn ready(facet: A) -> Self {
+        Self::new(move || futures::future::ready(Ok(facet.clone())).boxed())
+    }
+
+    /// Get the facet, building it if this is the first access.
+    ///
+    /// If building the facet fails, the error is returned and the next
+    /// access will try to build the facet again.
+    pub async fn get(&self) -> Result<&A, FactoryError> {
+        self.cell.get_or_try_init(|| (self.init)()).await
+    }
+
+    /// Get the facet if it has already been accessed and built.
+    pub fn get_if_built(&self) -> Option<&A> {
+        self.cell.get()
+    }
+}
+
+/// Trait for factories that can build lazy facets, which is implemented for
+/// all factories that are `Clone`.  Lazy facets are built after the container
+/// is, so they hold a handle to a clone of the factory.
+///
+/// The lifetime is that of the builder, which is only there so that builders
+/// of factories that aren't `Clone` still compile when they don't build lazy
+/// facets.
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "the factory `{Self}` can't build lazy facets",
+    note = "lazy facets hold a clone of the factory, so it must be `Clone + Send + Sync + 'static`"
+)]
+pub trait LazyFactory<'factory, F: ?Sized> {
+    type Handle: Deref<Target = F> + Clone + Send + Sync + 'static;
+
+    fn lazy_handle(&self) -> Self::Handle;
+}
+
+impl<F> LazyFactory<'_, F> for F
+where
+    F: Clone + Send + Sync + 'static,
+{
+    type Handle = Arc<F>;
+
+    fn lazy_handle(&self) -> Arc<F> {
+        Arc::new(self.clone())
+    }
+}
@@ -97,6 +97,129 @@
 //! `Arc<MyStruct>` or `Arc<dyn MyTrait + Send + Sync>` that is used in
 //! factory definitions (see below).
 //!
+//! ### Weak Alias
+//!
+//! The weak alias (`WeakMyStruct` or `WeakMyTrait`) is an alias to
+//! `WeakFacet<MyStruct>` or `WeakFacet<dyn MyTrait + Send + Sync>`, a weak
+//! reference to the facet that can be used to break dependency cycles
+//! between factory methods (see below).
+//!
+//! ### Facet Names
+//!
+//! The name of a facet is the snake-case equivalent of the facet type name.
+//! It names the methods of the reference and arc traits, and by convention
+//! the factory method that builds the facet and the container fields that
+//! store it.  If the derived name is unsuitable, a different name can be
+//! given with `#[facet::facet(name = "...")]`:
+//!
+//! ```
+//! #[facet::facet(name = "http_client")]
+//! trait HTTPClientV2 {}
+//!
+//! fn my_function(container: impl HTTPClientV2Ref + HTTPClientV2Arc) {
+//!     let client: &dyn HTTPClientV2 = container.http_client();
+//!     let client: ArcHTTPClientV2 = container.http_client_arc();
+//! }
+//! ```
+//!
+//! Container fields can also store a facet under a different name, by
+//! giving the name of the facet with `#[facet(name = "...")]` on the field.
+//!
+//! ### Default Implementations
+//!
+//! A facet can declare a default implementation with
+//! `#[facet::facet(default = Type)]`, which is constructed with
+//! `Default::default()`.  Factories that don't need their own version of
+//! the facet can list it in `defaults(...)` instead of defining a factory
+//! method for it, and the facet is built with its default implementation.
+//! Each listed facet is given the name derived from its type, or a name can
+//! be given with `defaults(name = Facet)`.  Facets with defaults implement
+//! `FacetDefault`, and must not be `local` or `boxed`.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! #[facet::facet(default = NoopMetrics)]
+//! trait Metrics {
+//!     fn increment(&self, counter: &str);
+//! }
+//!
+//! #[derive(Default)]
+//! struct NoopMetrics;
+//!
+//! impl Metrics for NoopMetrics {
+//!     fn increment(&self, _counter: &str) {}
+//! }
+//!
+//! # #[facet::facet] struct Server { metrics: ArcMetrics }
+//! struct TestFactory;
+//!
+//! #[facet::factory(defaults(Metrics))]
+//! impl TestFactory {
+//!     fn server(&self, metrics: &ArcMetrics) -> ArcServer {
+//!         Arc::new(Server { metrics: metrics.clone() })
+//!     }
+//! }
+//!
+//! # #[facet::container] struct MyContainer { #[facet] server: Server }
+//! let container = TestFactory.build::<MyContainer>().unwrap();
+//! container.server.metrics.increment("requests");
+//! ```
+//!
+//! ### Generic Facets
+//!
+//! Facet traits, structs and enums can have type parameters, which must be
+//! `'static`.  The reference and arc traits and the aliases are generic
+//! with the same parameters, so each instantiation of the facet is a
+//! separate facet.  A container can hold several instantiations of the same
+//! facet in fields with different names, built by factory methods with the
+//! same names.  Generic struct and enum facets must be `Send` and `Sync`
+//! for the arguments they are used with, unless they are `local`.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! #[facet::facet]
+//! trait Store<V> {
+//!     fn get(&self, key: &str) -> Option<V>;
+//! }
+//!
+//! struct EmptyStore;
+//!
+//! impl<V> Store<V> for EmptyStore {
+//!     fn get(&self, _key: &str) -> Option<V> {
+//!         None
+//!     }
+//! }
+//!
+//! struct MyFactory;
+//!
+//! #[facet::factory()]
+//! impl MyFactory {
+//!     fn bytes_store(&self) -> ArcStore<Vec<u8>> {
+//!         Arc::new(EmptyStore)
+//!     }
+//!
+//!     fn string_store(&self) -> ArcStore<String> {
+//!         Arc::new(EmptyStore)
+//!     }
+//! }
+//!
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet]
+//!     bytes_store: dyn Store<Vec<u8>>,
+//!
+//!     #[facet]
+//!     string_store: dyn Store<String>,
+//! }
+//!
+//! fn use_strings(container: impl StoreRef<String>) -> Option<String> {
+//!     container.store().get("key")
+//! }
+//!
+//! let container = MyFactory.build::<MyContainer>().unwrap();
+//! assert_eq!(use_strings(&container), None);
+//! ```
+//!
 //! ## Factory
 //!
 //! A **factory** is defined by implementing a set of methods on a struct,
@@ -118,10 +241,17 @@
 //!   given in the factory attribute, and the type is a borrowed version
 //!   of the parameter type; or
 //!
+//! * a factory parameter by value, if no other factory method uses the
+//!   parameter (see [By-value Parameters](#by-value-parameters)); or
+//!
 //! * another facet that this factory can build, where the name must match the
 //!   name of the method that builds the facet, and the type must be a reference
 //!   to an `Arc`-wrapped facet.
 //!
+//! * a weak reference to another facet that this factory can build, where
+//!   the name must match the name of the method that builds the facet, and
+//!   the type must be a reference to the weak alias of the facet.
+//!
 //! You can use the arc alias generated by the facet macro (`ArcMyStruct` or
 //! `ArcMyTrait`) as a convenience for specifying the `Arc`-wrapped facets in
 //! both parameters and return types.
@@ -130,6 +260,11 @@
 //! on which other factory methods.  When factory methods depend on each other
 //! they must not form cycles, or you will get an error at compile time.
 //!
+//! Weak references do not count as dependencies, so two facets that need to
+//! refer to each other can do so by having one of them take a weak reference
+//! to the other.  The weak reference can be upgraded once the build has
+//! finished, provided the facet it refers to was needed by the build.
+//!
 //! For dynamic facets, the factory is free to select any implementor of the
 //! facet trait as the implementation it returns.  It should wrap this as a
 //! facet in an `Arc` using `Arc::new(...)`.
@@ -181,157 +316,2234 @@
 //! The macro will define a `build` method for each factory, which can be used
 //! to build containers (see below).
 //!
-//! ## Containers
+//! ### Named Parameters
 //!
-//! A **container** is a struct that contains facets.  Each field of a
-//! container can be one of:
+//! Factories with many parameters are easy to call incorrectly.  The
+//! `builder` method starts a build whose parameters are given by name, with
+//! a method for each parameter.  Parameters can be given defaults with
+//! `#[default]`, for the default value of their type, or
+//! `#[default(value)]`, and can then be omitted.  Building fails with
+//! `FactoryError::MissingParameter` if a parameter without a default is not
+//! given.
 //!
-//! * A **normal field**.  You must provide an initializer with the
-//!   `#[init(value)]` attribute.
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] struct Server { port: u16 }
+//! struct MyFactory;
 //!
-//! * A **facet**.  The facet must be marked with a `#[facet]` attribute,
-//!   the name must match that of the facet, and its type must be that of the
-//!   facet.  Dynamic facets must be marked with the `dyn` keyword.
+//! #[facet::factory(name: String, #[default(8080)] port: u16, #[default] verbose: bool)]
+//! impl MyFactory {
+//!     fn server(&self, port: &u16) -> ArcServer {
+//!         Arc::new(Server { port: *port })
+//!     }
+//! }
+//! # #[facet::container] struct MyContainer { #[facet] server: Server }
 //!
-//! * A **nested container**.  The container can be either stored inline
-//!   or inside an `Arc`.  Facets can be delegated to the inner container
-//!   by listing them in the `#[delegate(Facet, ...)]` attribute.  The
-//!   nested container must be constructible by the same factory, and
-//!   any common facets will be shared.
+//! let container = MyFactory
+//!     .builder::<MyContainer>()
+//!     .name(String::from("my_server"))
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(container.server.port, 8080);
+//! ```
 //!
-//! Initializers for normal fields may reference any of the facets that
-//! are part of the container, or any of the nested containers.
+//! ### Parameters Structs
 //!
-//! For example:
+//! Factories that take many parameters can take them as a single struct
+//! instead, with `params = Type`.  The `build` method then takes the struct,
+//! so parameters can be added without changing every call to it.  Factory
+//! methods borrow the fields they need by name, the same way as they would
+//! take separate parameters, or they can borrow the whole struct as
+//! `params`.
 //!
 //! ```
 //! # use std::sync::Arc;
-//! # #[facet::facet] trait MyTrait { fn get_name(&self) -> &str; }
-//! # #[facet::facet] struct MyStruct {}
-//! # #[facet::facet] trait OtherTrait {}
-//! # #[facet::container] struct NestedContainer { #[facet] other_trait: dyn OtherTrait }
+//! # #[facet::facet] struct Server { host: String, port: u16 }
+//! struct ServerParams {
+//!     host: String,
+//!     port: u16,
+//! }
+//!
+//! struct MyFactory;
+//!
+//! #[facet::factory(params = ServerParams)]
+//! impl MyFactory {
+//!     fn server(&self, host: &str, port: &u16) -> ArcServer {
+//!         Arc::new(Server { host: host.to_string(), port: *port })
+//!     }
+//! }
+//! # #[facet::container] struct MyContainer { #[facet] server: Server }
+//!
+//! let params = ServerParams { host: String::from("localhost"), port: 8080 };
+//! let container = MyFactory.build::<MyContainer>(params).unwrap();
+//! assert_eq!(container.server.port, 8080);
+//! ```
+//!
+//! Rebuilds compare the whole struct, so all facets that borrow any of its
+//! fields are rebuilt if any field changes.
+//!
+//! ### By-value Parameters
+//!
+//! A factory method of a synchronous factory can take a factory parameter
+//! by value rather than by reference, which moves the parameter into the
+//! method without needing to clone it.  This is only allowed if no other
+//! factory method uses the parameter, which is checked at compile time.  If
+//! the facet is not needed by the build, the parameter is dropped.
+//!
+//! Parameters taken by value are consumed by the build, so rebuilds always
+//! rebuild the facets that depend on them.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] struct Client { connection: Connection }
+//! struct Connection;
+//!
+//! struct MyFactory;
+//!
+//! #[facet::factory(connection: Connection)]
+//! impl MyFactory {
+//!     fn client(&self, connection: Connection) -> ArcClient {
+//!         Arc::new(Client { connection })
+//!     }
+//! }
+//! # #[facet::container] struct MyContainer { #[facet] client: Client }
+//! # MyFactory.build::<MyContainer>(Connection).unwrap();
+//! ```
+//!
+//! ### FacebookInit
+//!
+//! A `FacebookInit` factory parameter is `Copy`, so any number of factory
+//! methods can take it by value, and each is given a copy.  Like other
+//! parameters, it is not a facet, so it doesn't need to be stored on the
+//! factory or wrapped in a facet to be used.  There is only one
+//! `FacebookInit` for the process, so rebuilds never treat it as changed.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # use fbinit::FacebookInit;
+//! # #[facet::facet] struct Logger { fb: FacebookInit }
+//! # #[facet::facet] struct Client { fb: FacebookInit }
+//! struct MyFactory;
+//!
+//! #[facet::factory(fb: FacebookInit)]
+//! impl MyFactory {
+//!     fn logger(&self, fb: FacebookInit) -> ArcLogger {
+//!         Arc::new(Logger { fb })
+//!     }
+//!
+//!     fn client(&self, fb: FacebookInit, _logger: &ArcLogger) -> ArcClient {
+//!         Arc::new(Client { fb })
+//!     }
+//! }
+//! # #[facet::container] struct MyContainer { #[facet] client: Client }
+//! # MyFactory.build::<MyContainer>(unsafe { fbinit::assume_init() }).unwrap();
+//! ```
+//!
+//! ### Factory Scope
+//!
+//! Normally each facet is built once for each container that is built.  A
+//! factory method can instead be marked with `#[facet(scope = "factory")]`,
+//! in which case the facet is built once and cached on the factory, and the
+//! same `Arc` is shared by every container built by that factory.  This is
+//! useful for expensive facets such as connection pools.
+//!
+//! Factories with factory-scoped facets must implement `FactoryScope` to
+//! provide the `FacetCache` the facets are stored in.  As factory-scoped
+//! facets are shared by builds with different parameters, they can't take
+//! factory parameters, and can only depend on other factory-scoped facets.
+//!
+//! ```
+//! # #[facet::facet] struct Pool;
+//! # #[facet::facet] struct Session { name: String }
+//! use std::sync::Arc;
+//!
+//! use facet::{FacetCache, FactoryScope};
+//!
+//! #[derive(Default)]
+//! struct MyFactory {
+//!     cache: FacetCache,
+//! }
+//!
+//! impl FactoryScope for MyFactory {
+//!     fn facet_cache(&self) -> &FacetCache {
+//!         &self.cache
+//!     }
+//! }
+//!
+//! #[facet::factory(name: String)]
+//! impl MyFactory {
+//!     #[facet(scope = "factory")]
+//!     fn pool(&self) -> ArcPool {
+//!         Arc::new(Pool)
+//!     }
+//!
+//!     fn session(&self, name: &str) -> ArcSession {
+//!         Arc::new(Session { name: name.to_string() })
+//!     }
+//! }
+//!
 //! #[facet::container]
 //! struct MyContainer {
-//!     #[init(my_trait.get_name().to_string())]
-//!     name: String,
+//!     #[facet]
+//!     pool: Pool,
+//!
+//!     #[facet]
+//!     session: Session,
+//! }
+//!
+//! let factory = MyFactory::default();
+//! let first = factory.build::<MyContainer>("first".to_string()).unwrap();
+//! let second = factory.build::<MyContainer>("second".to_string()).unwrap();
+//! assert!(Arc::ptr_eq(&first.pool, &second.pool));
+//! assert!(!Arc::ptr_eq(&first.session, &second.session));
+//! ```
+//!
+//! ### Memoized Builds
+//!
+//! A factory can be declared with `memoize` to cache the facets built by
+//! each call to `build`, keyed on the parameters of the build.  Later builds
+//! with equal parameters reuse the same `Arc`s rather than building the
+//! facets again, and only build facets that weren't needed by an earlier
+//! build.  The other build methods are not memoized.
+//!
+//! Memoized factories must implement `FactoryMemo` to provide the
+//! `BuildCache` that builds are stored in, and their parameters must
+//! implement `Clone`, `Hash` and `Eq`.  Cached facets are kept until the
+//! cache is cleared with `BuildCache::clear`.
+//!
+//! ```
+//! # #[facet::facet] struct Repo { name: String }
+//! use std::sync::Arc;
+//!
+//! use facet::{BuildCache, FactoryMemo};
+//!
+//! #[derive(Default)]
+//! struct MyFactory {
+//!     builds: BuildCache,
+//! }
+//!
+//! impl FactoryMemo for MyFactory {
+//!     fn build_cache(&self) -> &BuildCache {
+//!         &self.builds
+//!     }
+//! }
+//!
+//! #[facet::factory(memoize, name: String)]
+//! impl MyFactory {
+//!     fn repo(&self, name: &str) -> ArcRepo {
+//!         Arc::new(Repo { name: name.to_string() })
+//!     }
+//! }
+//!
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet]
+//!     repo: Repo,
+//! }
+//!
+//! let factory = MyFactory::default();
+//! let first = factory.build::<MyContainer>("repo".to_string()).unwrap();
+//! let second = factory.build::<MyContainer>("repo".to_string()).unwrap();
+//! let other = factory.build::<MyContainer>("other".to_string()).unwrap();
+//! assert!(Arc::ptr_eq(&first.repo, &second.repo));
+//! assert!(!Arc::ptr_eq(&first.repo, &other.repo));
+//! ```
+//!
+//! ### Parallel Builds
+//!
+//! Synchronous factories build facets one at a time, as each is needed.
+//! Their `build_parallel` method instead builds facets that don't depend on
+//! each other at the same time on scoped threads, in rounds: each round
+//! builds every needed facet whose dependencies were built by an earlier
+//! round.  The factory, its parameters and its facets must all be `Send`
+//! and `Sync` for the factory to be used this way.  Parallel builds are not
+//! memoized.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] struct Db {}
+//! # #[facet::facet] struct Cache {}
+//! # #[facet::facet] struct Service { db: ArcDb, cache: ArcCache }
+//! # struct MyFactory;
+//! #[facet::factory()]
+//! impl MyFactory {
+//!     // `db` and `cache` are built concurrently, then `service`.
+//!     fn db(&self) -> ArcDb { Arc::new(Db {}) }
+//!     fn cache(&self) -> ArcCache { Arc::new(Cache {}) }
+//!     fn service(&self, db: &ArcDb, cache: &ArcCache) -> ArcService {
+//!         Arc::new(Service { db: db.clone(), cache: cache.clone() })
+//!     }
+//! }
+//! # #[facet::container] struct MyContainer { #[facet] service: Service }
+//!
+//! let container = MyFactory.build_parallel::<MyContainer>().unwrap();
+//! ```
+//!
+//! ### Multiple Facet Traits
+//!
+//! A single implementation can provide several facet traits.  A factory
+//! method that returns an `Arc` of the implementation type can be marked
+//! with `#[facet(also = Trait)]` for each facet trait it provides, in which
+//! case a single instance is built and shared by all of those facets.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait Db {}
+//! # #[facet::facet] trait Cache {}
+//! struct Store;
+//!
+//! impl Db for Store {}
+//! impl Cache for Store {}
+//!
+//! struct MyFactory;
+//!
+//! #[facet::factory()]
+//! impl MyFactory {
+//!     #[facet(also = Db, also = Cache)]
+//!     fn store(&self) -> Arc<Store> {
+//!         Arc::new(Store)
+//!     }
+//! }
+//! # #[facet::container] struct MyContainer { #[facet] db: dyn Db, #[facet] cache: dyn Cache }
+//! # MyFactory.build::<MyContainer>().unwrap();
+//! ```
+//!
+//! ### Tuples of Facets
+//!
+//! A factory method can build several tightly-coupled facets at once by
+//! returning a tuple of them, such as `(ArcClient, ArcMetrics)`.  Each
+//! element of the tuple becomes a facet in its own right, named after its
+//! type, so other factory methods can depend on `client` and `metrics`
+//! individually.  Elements whose names can't be derived from their types
+//! are named after the method and their position, e.g. `my_method_0`.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait Client {}
+//! # #[facet::facet] struct Metrics {}
+//! # struct MyClient { metrics: ArcMetrics }
+//! # impl Client for MyClient {}
+//! # #[facet::facet] struct Service {}
+//! struct MyFactory;
+//!
+//! #[facet::factory()]
+//! impl MyFactory {
+//!     fn client_with_metrics(&self) -> (ArcClient, ArcMetrics) {
+//!         let metrics = Arc::new(Metrics {});
+//!         (Arc::new(MyClient { metrics: metrics.clone() }), metrics)
+//!     }
+//!
+//!     fn service(&self, client: &ArcClient, metrics: &ArcMetrics) -> ArcService {
+//!         Arc::new(Service {})
+//!     }
+//! }
+//! # #[facet::container] struct MyContainer { #[facet] service: Service }
+//! # MyFactory.build::<MyContainer>().unwrap();
+//! ```
+//!
+//! ### Alternate Implementations
+//!
+//! A facet can have alternate factory methods, which are selected when the
+//! facet is built.  Mark each alternate with
+//! `#[facet(alternate_of = facet, select = "condition")]`.  The facet is
+//! built by the first alternate whose condition holds, or by the facet's own
+//! factory method if none do.  Conditions are expressions that can use the
+//! factory parameters, and the facets taken by the facet's factory method or
+//! its alternates.
+//!
+//! All alternates must return the same type as the facet's factory method.
+//! The facet depends on all the facets and parameters its alternates use,
+//! even if they aren't selected, and they are listed in the `alternates` of
+//! its node in the `facet_graph` when the `graph` feature is enabled.
+//! Options such as timeouts are given on the facet's own factory method, and
+//! apply whichever alternate is selected.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait Store {}
+//! # struct MemoryStore;
+//! # impl Store for MemoryStore {}
+//! # struct DiskStore;
+//! # impl Store for DiskStore {}
+//! # #[facet::facet] struct Disk {}
+//! # pub struct Config { use_disk: bool }
+//! struct MyFactory;
+//!
+//! #[facet::factory(config: Config)]
+//! impl MyFactory {
+//!     fn disk(&self) -> ArcDisk {
+//!         Arc::new(Disk {})
+//!     }
+//!
+//!     fn store(&self) -> ArcStore {
+//!         Arc::new(MemoryStore)
+//!     }
+//!
+//!     #[facet(alternate_of = store, select = "config.use_disk")]
+//!     fn disk_store(&self, disk: &ArcDisk) -> ArcStore {
+//!         Arc::new(DiskStore)
+//!     }
+//! }
+//! # #[facet::container] struct MyContainer { #[facet] store: dyn Store }
+//! # MyFactory.build::<MyContainer>(Config { use_disk: true }).unwrap();
+//! # #[cfg(feature = "graph")] {
+//! let graph = MyFactory::facet_graph();
+//! assert_eq!(graph.facet("store").unwrap().dependencies, ["disk"]);
+//! # }
+//! ```
+//!
+//! ### Panics
+//!
+//! Panics in factory methods normally unwind through the caller of `build`.
+//! Factories declared with `catch_panics` instead catch them, and the build
+//! fails with `FactoryError::FacetBuildPanicked`, which records the name of
+//! the facet and the panic message.  To let the panics of a particular
+//! factory method unwind in these factories, mark the method with
+//! `#[facet(propagate_panic)]`.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] struct Config;
+//! # #[facet::container] struct MyContainer { #[facet] config: Config }
+//! struct MyFactory;
+//!
+//! #[facet::factory(catch_panics)]
+//! impl MyFactory {
+//!     fn config(&self) -> ArcConfig {
+//!         panic!("no config")
+//!     }
+//! }
+//!
+//! match MyFactory.build::<MyContainer>() {
+//!     Err(facet::FactoryError::FacetBuildPanicked { name, message }) => {
+//!         assert_eq!(name, "config");
+//!         assert_eq!(message, "no config");
+//!     }
+//!     _ => panic!("build should fail"),
+//! }
+//! ```
+//!
+//! ### Post-build Hooks
+//!
+//! Methods of a factory marked with `#[facet::post_build]` are hooks that
+//! are run once the facets a container needs have been built, before the
+//! container is returned.  They can take facets and factory parameters by
+//! reference, like factory methods, and are only run if every facet they
+//! take was built for the container.  Hooks are run in the order they are
+//! declared, and can be async in async factories.  A hook that returns an
+//! error fails the build with `FactoryError::PostBuildFailed`.
+//!
+//! ```
+//! # use std::sync::{Arc, Mutex};
+//! # #[facet::facet] #[derive(Default)] struct Registry { names: Mutex<Vec<String>> }
+//! # #[facet::facet] struct Cache;
+//! struct MyFactory;
+//!
+//! #[facet::factory()]
+//! impl MyFactory {
+//!     fn registry(&self) -> ArcRegistry {
+//!         Arc::new(Registry::default())
+//!     }
+//!
+//!     fn cache(&self) -> ArcCache {
+//!         Arc::new(Cache)
+//!     }
+//!
+//!     #[facet::post_build]
+//!     fn register_cache(&self, registry: &ArcRegistry, _cache: &ArcCache) {
+//!         registry.names.lock().unwrap().push(String::from("cache"));
+//!     }
+//! }
+//! # #[facet::container] struct MyContainer { #[facet] registry: Registry, #[facet] cache: Cache }
+//!
+//! let container = MyFactory.build::<MyContainer>().unwrap();
+//! assert_eq!(*container.registry.names.lock().unwrap(), ["cache"]);
+//! ```
+//!
+//! ### Factory Delegation
+//!
+//! A factory can override some of the facets of another factory, and
+//! delegate all other facets to it, by naming a field that holds the inner
+//! factory with `delegate = field: Type` in the factory attribute.  This is
+//! useful for test factories that replace a few facets of a production
+//! factory.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait Clock { fn now(&self) -> u64; }
+//! # #[facet::facet] struct Session { started: u64 }
+//! # struct FixedClock(u64);
+//! # impl Clock for FixedClock { fn now(&self) -> u64 { self.0 } }
+//! struct ProdFactory;
+//!
+//! #[facet::factory(name: String)]
+//! impl ProdFactory {
+//!     fn clock(&self) -> ArcClock {
+//!         Arc::new(FixedClock(1000))
+//!     }
+//!
+//!     fn session(&self, clock: &ArcClock) -> ArcSession {
+//!         Arc::new(Session { started: clock.now() })
+//!     }
+//! }
+//!
+//! struct TestFactory {
+//!     prod: ProdFactory,
+//! }
+//!
+//! #[facet::factory(name: String, delegate = prod: ProdFactory)]
+//! impl TestFactory {
+//!     fn clock(&self) -> ArcClock {
+//!         Arc::new(FixedClock(42))
+//!     }
+//! }
+//!
+//! # #[facet::container] struct MyContainer { #[facet] session: Session }
+//! let factory = TestFactory { prod: ProdFactory };
+//! let container = factory.build::<MyContainer>("name".to_string()).unwrap();
+//! assert_eq!(container.session.started, 42);
+//! ```
+//!
+//! The delegating factory's methods are called before the build starts, and
+//! the facets they build are given to a build by the inner factory, as with
+//! `build_with` (see below).  They may depend on facets of the inner
+//! factory, in which case those facets are built by the inner factory.
+//! Overriding methods that only depend on parameters and other overriding
+//! facets are called first, so that the inner factory's facets use them.
+//!
+//! Both factories must take the same parameters, which must implement
+//! `Clone`.  A delegating factory is asynchronous if any of its methods are,
+//! and the inner factory must be of the same kind.  Keyed and
+//! factory-scoped facets cannot be overridden, and delegating factories only
+//! provide the `build` method.
+//!
+//! ### Extern Factories
+//!
+//! A factory can also build on a factory defined in another crate, which
+//! builds the facets it doesn't have methods for, by naming it with
+//! `extern = path::to::Factory` in the factory attribute.  The factory must
+//! implement `AsRef` for the extern factory, and the dependency graphs of
+//! the two factories are composed at compile time: the factory's methods
+//! can depend on the extern factory's facets, and so can its containers.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait Clock { fn now(&self) -> u64; }
+//! # #[facet::facet] struct Db { opened_at: u64 }
+//! # #[facet::facet] struct Service { db_opened_at: u64 }
+//! # struct FixedClock(u64);
+//! # impl Clock for FixedClock { fn now(&self) -> u64 { self.0 } }
+//! # mod base {
+//! # use super::*;
+//! pub struct BaseFactory;
+//!
+//! #[facet::factory(name: String)]
+//! impl BaseFactory {
+//!     fn clock(&self) -> ArcClock {
+//!         Arc::new(FixedClock(1000))
+//!     }
+//!
+//!     fn db(&self, clock: &ArcClock) -> ArcDb {
+//!         Arc::new(Db { opened_at: clock.now() })
+//!     }
+//! }
+//! # }
+//!
+//! struct ServiceFactory {
+//!     base: base::BaseFactory,
+//! }
+//!
+//! impl AsRef<base::BaseFactory> for ServiceFactory {
+//!     fn as_ref(&self) -> &base::BaseFactory {
+//!         &self.base
+//!     }
+//! }
+//!
+//! #[facet::factory(name: String, extern = base::BaseFactory(clock: ArcClock))]
+//! impl ServiceFactory {
+//!     fn service(&self, db: &ArcDb) -> ArcService {
+//!         Arc::new(Service { db_opened_at: db.opened_at })
+//!     }
+//! }
+//!
+//! # #[facet::container]
+//! # struct MyContainer {
+//! #     #[facet] clock: dyn Clock,
+//! #     #[facet] service: Service,
+//! # }
+//! # fn main() {
+//! let factory = ServiceFactory { base: base::BaseFactory };
+//! let container = factory.build::<MyContainer>("name".to_string()).unwrap();
+//! assert_eq!(container.service.db_opened_at, 1000);
+//! # }
+//! ```
+//!
+//! The facets of the extern factory that the factory's methods depend on
+//! are found automatically.  Any others that containers need, like `clock`
+//! above, are listed with their types after the path.  The extern factory's
+//! builder is used for these facets, so each is built at most once per
+//! build, and they can be injected with `build_with`.
+//!
+//! Both factories must take the same parameters, which must implement
+//! `Clone`, and they must both be synchronous.  Keyed facets of the extern
+//! factory can't be used, and factories with an extern factory don't
+//! provide `build_parallel` or incremental rebuilds.
+//!
+//! ### Factory Traits
+//!
+//! A factory can also be defined as a trait with `#[facet::factory_trait]`,
+//! whose methods are the factory methods.  Each implementation of the trait
+//! provides its own way of building the facets, and the trait's provided
+//! methods are shared by all of them.  The trait objects are factories, so
+//! code can hold a `Box<dyn MyFactory>` or an `Arc<dyn MyFactory>`, choose
+//! between production and test implementations at runtime, and still build
+//! containers with the usual compile-time checks.  `DynMyFactory` is an
+//! alias for `dyn MyFactory`, which has the factory's associated functions,
+//! such as `facet_graph`.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait Store { fn kind(&self) -> &str; }
+//! # struct Disk;
+//! # impl Store for Disk { fn kind(&self) -> &str { "disk" } }
+//! # struct Memory;
+//! # impl Store for Memory { fn kind(&self) -> &str { "memory" } }
+//! #[facet::factory_trait(name: String)]
+//! trait MyFactory {
+//!     fn store(&self, name: &str) -> ArcStore;
+//! }
+//!
+//! struct ProdFactory;
+//!
+//! impl MyFactory for ProdFactory {
+//!     fn store(&self, name: &str) -> ArcStore {
+//!         Arc::new(Disk)
+//!     }
+//! }
+//!
+//! struct TestFactory;
+//!
+//! impl MyFactory for TestFactory {
+//!     fn store(&self, name: &str) -> ArcStore {
+//!         Arc::new(Memory)
+//!     }
+//! }
+//!
+//! # #[facet::container] struct MyContainer { #[facet] store: dyn Store }
+//! let factory: Box<dyn MyFactory> = Box::new(TestFactory);
+//! let container = factory.build::<MyContainer>("name".to_string()).unwrap();
+//! assert_eq!(container.store.kind(), "memory");
+//! ```
+//!
+//! Factory traits have `Send` and `Sync` as supertraits.  Async factory
+//! traits must use `#[async_trait]`, after `#[facet::factory_trait]`, so
+//! that their trait objects can be built.  Factory traits cannot delegate,
+//! memoize, have defaults or have alternate factory methods.
+//!
+//! ### Plugin Registry
+//!
+//! Implementations of a facet can be registered by name in a
+//! `FacetRegistry`, usually the global one returned by `facet::registry()`,
+//! so that a factory can choose the implementation at runtime, for example
+//! from configuration.  The facet is still part of the factory's graph, so
+//! its dependencies are checked at compile time as usual.  Implementations
+//! are registered for the facet type, `dyn MyTrait + Send + Sync`, and each
+//! resolve constructs a new instance.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # use anyhow::Error;
+//! #[facet::facet]
+//! trait Storage {
+//!     fn kind(&self) -> &str;
+//! }
+//!
+//! struct MemoryStorage;
+//!
+//! impl Storage for MemoryStorage {
+//!     fn kind(&self) -> &str {
+//!         "memory"
+//!     }
+//! }
+//!
+//! struct MyFactory;
+//!
+//! #[facet::factory(storage_kind: String)]
+//! impl MyFactory {
+//!     fn storage(&self, storage_kind: &str) -> Result<ArcStorage, Error> {
+//!         Ok(facet::registry().resolve::<dyn Storage + Send + Sync>(storage_kind)?)
+//!     }
+//! }
+//!
+//! # #[facet::container] struct MyContainer { #[facet] storage: dyn Storage }
+//! facet::registry().register::<dyn Storage + Send + Sync>("memory", || {
+//!     Ok(Arc::new(MemoryStorage))
+//! });
+//!
+//! let container = MyFactory.build::<MyContainer>("memory".to_string())?;
+//! assert_eq!(container.storage.kind(), "memory");
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! ### Validation
+//!
+//! When the `graph` feature is enabled, each factory has a `validate` method
+//! that checks, without building anything, whether the factory could build
+//! a container with the given parameters.  It reports facets that the
+//! container needs but the factory has no method for, which can happen when
+//! the container is only known by name, and implementations that the factory
+//! would resolve from the global registry but that aren't registered.
+//!
+//! Factory methods that resolve from the registry are marked with
+//! `#[facet(registry = param)]`, where `param` is the factory parameter that
+//! names the implementation, or `#[facet(registry)]` if they use
+//! `resolve_only` to construct the only registered implementation.  Only the
+//! facets that the container needs are checked.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # use anyhow::Error;
+//! # #[facet::facet] trait Storage {}
+//! # struct MemoryStorage;
+//! # impl Storage for MemoryStorage {}
+//! # struct MyFactory;
+//! #[facet::factory(storage_kind: String)]
+//! impl MyFactory {
+//!     #[facet(registry = storage_kind)]
+//!     fn storage(&self, storage_kind: &str) -> Result<ArcStorage, Error> {
+//!         Ok(facet::registry().resolve::<dyn Storage + Send + Sync>(storage_kind)?)
+//!     }
+//! }
+//!
+//! # #[facet::container] struct MyContainer { #[facet] storage: dyn Storage }
+//! # #[cfg(feature = "graph")] {
+//! let report = MyFactory.validate::<MyContainer>(&"memory".to_string());
+//! assert!(!report.is_valid());
+//! println!("{}", report);
+//!
+//! facet::registry().register::<dyn Storage + Send + Sync>("memory", || {
+//!     Ok(Arc::new(MemoryStorage))
+//! });
+//! assert!(MyFactory.validate::<MyContainer>(&"memory".to_string()).is_valid());
+//! # }
+//! ```
+//!
+//! ## Containers
+//!
+//! A **container** is a struct that contains facets.  Each field of a
+//! container can be one of:
+//!
+//! * A **normal field**.  You must provide an initializer with the
+//!   `#[init(value)]` attribute.
+//!
+//! * A **facet**.  The facet must be marked with a `#[facet]` attribute,
+//!   the name must match that of the facet, and its type must be that of the
+//!   facet.  Dynamic facets must be marked with the `dyn` keyword.
+//!
+//! * A **weak facet**.  This is like a facet, but marked with
+//!   `#[facet(weak)]`.  The container only holds a weak reference to the
+//!   facet, so it must be kept alive by another facet.  The container has
+//!   an accessor method that attempts to upgrade it.
+//!
+//! * A **swappable facet**.  This is like a facet, but marked with
+//!   `#[facet(swappable)]`.  The container stores the facet in a
+//!   `SwappableFacet`, and has an accessor method that returns the current
+//!   instance and a `replace_` method that replaces it, so long-lived
+//!   services can switch to a new implementation without rebuilding the
+//!   container.
+//!
+//! * A **nested container**.  The container can be either stored inline
+//!   or inside an `Arc`.  Facets can be delegated to the inner container
+//!   by listing them in the `#[delegate(Facet, ...)]` attribute, or the
+//!   container can be nested without delegating any facets by using
+//!   `#[delegate]` on its own.  The nested container must be constructible
+//!   by the same factory, and is built as part of the same build, so any
+//!   common facets will be shared rather than built again.  This allows
+//!   narrower nested containers to be passed to subsystems that only need
+//!   some of the facets.
+//!
+//! Initializers for normal fields may reference any of the facets that
+//! are part of the container, or any of the nested containers.  An
+//! initializer can also be an `async` block, such as
+//! `#[init(async { my_trait.load_name().await })]`, which is awaited during
+//! the build.  Containers with async initializers can only be built by
+//! async factories.
+//!
+//! Initializers can also use factory parameters.  List the parameters the
+//! container needs, with their types, in `#[facet::container(params(...))]`,
+//! and initializers can access references to them through `params`.  The
+//! build fails with `FactoryError::MissingParameter` if the factory has no
+//! parameter with that name.  Parameters taken by value are not available
+//! to initializers, as they are consumed by the factory method that takes
+//! them.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] struct Pool {}
+//! # struct MyFactory;
+//! #[facet::factory(name: String, size: usize)]
+//! impl MyFactory {
+//!     fn pool(&self) -> ArcPool {
+//!         Arc::new(Pool {})
+//!     }
+//! }
+//!
+//! #[facet::container(params(name: String))]
+//! struct MyContainer {
+//!     #[init(params.name.clone())]
+//!     name: String,
+//!
+//!     #[facet]
+//!     pool: Pool,
+//! }
+//!
+//! # fn main() -> Result<(), anyhow::Error> {
+//! let my_container = MyFactory.build::<MyContainer>("name".to_string(), 4)?;
+//! assert_eq!(my_container.name, "name");
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! Containers declared with `record_params` as well as `params(...)` keep a
+//! copy of the values of those parameters, which `build_params` returns as
+//! a `<Container>BuildParams` struct with a field for each parameter.  This
+//! lets debugging tools find out what a live container was built with.  The
+//! recorded parameters must implement `Clone` and `Debug`.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] struct Pool {}
+//! # struct MyFactory;
+//! # #[facet::factory(name: String, size: usize)]
+//! # impl MyFactory {
+//! #     fn pool(&self) -> ArcPool { Arc::new(Pool {}) }
+//! # }
+//! #[facet::container(params(name: String, size: usize), record_params)]
+//! struct MyContainer {
+//!     #[facet]
+//!     pool: Pool,
+//! }
+//!
+//! # fn main() -> Result<(), anyhow::Error> {
+//! let my_container = MyFactory.build::<MyContainer>("name".to_string(), 4)?;
+//! assert_eq!(my_container.build_params().size, 4);
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! For example:
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait MyTrait { fn get_name(&self) -> &str; }
+//! # #[facet::facet] struct MyStruct {}
+//! # #[facet::facet] trait OtherTrait {}
+//! # #[facet::container] struct NestedContainer { #[facet] other_trait: dyn OtherTrait }
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[init(my_trait.get_name().to_string())]
+//!     name: String,
+//!
+//!     #[facet]
+//!     my_trait: dyn MyTrait,
+//!
+//!     #[facet]
+//!     my_struct: MyStruct,
+//!
+//!     #[delegate(dyn OtherTrait)]
+//!     my_nested_container: Arc<NestedContainer>,
+//! }
+//! ```
+//!
+//! If a dynamic facet's trait has supertraits that are also facets, the
+//! container can provide access to those facets by upcasting the facet,
+//! rather than storing them separately.  List the supertraits with
+//! `#[facet(supertraits(Trait, ...))]`:
+//!
+//! ```
+//! # #[facet::facet] trait Base {}
+//! #[facet::facet]
+//! trait Special: Base {}
+//!
+//! #[facet::container]
+//! struct SpecialContainer {
+//!     #[facet(supertraits(Base))]
+//!     special: dyn Special,
+//! }
+//!
+//! fn use_base(container: impl BaseRef) {}
+//!
+//! # fn example(container: SpecialContainer) {
+//! use_base(&container);
+//! # }
+//! ```
+//!
+//! Swappable facets are accessed through the container's accessor method
+//! rather than the facet's traits, as each access may return a different
+//! instance:
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait Config { fn value(&self) -> u32; }
+//! # struct FixedConfig(u32);
+//! # impl Config for FixedConfig { fn value(&self) -> u32 { self.0 } }
+//! # struct MyFactory;
+//! # #[facet::factory()]
+//! # impl MyFactory {
+//! #     fn config(&self) -> ArcConfig { Arc::new(FixedConfig(1)) }
+//! # }
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet(swappable)]
+//!     config: dyn Config,
+//! }
+//!
+//! # fn main() -> Result<(), anyhow::Error> {
+//! let container = MyFactory.build::<MyContainer>()?;
+//! assert_eq!(container.config().value(), 1);
+//! container.replace_config(Arc::new(FixedConfig(2)));
+//! assert_eq!(container.config().value(), 2);
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! Containers can be contructed using the `build` method of a factory.
+//! The build method must be passed the parameters defined on the factory
+//! attribute and these will be used as inputs for building this container.
+//!
+//! ```
+//! # struct MyFactory { config: String }
+//! # #[facet::factory(name: String, value: u32)] impl MyFactory {}
+//! # #[facet::container] struct MyContainer {}
+//! # fn main() -> Result<(), anyhow::Error> {
+//! let factory = MyFactory { config: String::from("config") };
+//! let my_container = factory.build::<MyContainer>("name".to_string(), 42)?;
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! The `build` method always returns `Result<Container, FactoryError>`, even
+//! if none of the factory methods are fallible.  If no methods are fallible
+//! then the result will always be `Ok`.  When a facet fails to build, each
+//! facet that depends on it fails with `FactoryError::DependencyFailed`, whose
+//! source is the error of the facet it depends on, and which is displayed with
+//! the chain of facets that led to the failure, e.g. `failed to build 'repo'
+//! (repo -> blobstore -> sql)`.  `FactoryError::path` returns that chain.
+//!
+//! The build only fails with the error of the facet that failed, such as
+//! `FactoryError::FacetBuildFailed`, if the container needs that facet
+//! directly.  Otherwise it fails with `FactoryError::DependencyFailed`, so
+//! code that matches on the error of the failed facet should match on
+//! `FactoryError::failure`, which skips the facets that depend on it:
+//!
+//! ```
+//! # fn check(result: Result<(), facet::FactoryError>) {
+//! if let Err(e) = result {
+//!     if let facet::FactoryError::FacetBuildFailed { name, source } = e.failure() {
+//!         eprintln!("{} failed: {}", name, source);
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! ### Pre-built Facets
+//!
+//! Facets that have already been built elsewhere can be given to a build
+//! with the `build_with` method, which returns a builder whose `facet`
+//! method takes an already-built facet.  The factory method for that facet
+//! is not called, and neither are the factory methods of any dependencies
+//! that are only needed by that facet.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait MyTrait {}
+//! # struct MyTraitImpl;
+//! # impl MyTrait for MyTraitImpl {}
+//! # struct MyFactory;
+//! # #[facet::factory(name: String)]
+//! # impl MyFactory {
+//! #     fn my_trait(&self, name: &str) -> ArcMyTrait {
+//! #        Arc::new(MyTraitImpl)
+//! #     }
+//! # }
+//! # #[facet::container] struct MyContainer { #[facet] my_trait: dyn MyTrait }
+//! # fn main() -> Result<(), anyhow::Error> {
+//! let existing: ArcMyTrait = Arc::new(MyTraitImpl);
+//! let my_container = MyFactory
+//!     .build_with::<MyContainer>("name".to_string())
+//!     .facet::<ArcMyTrait>(existing.clone())
+//!     .finish()?;
+//! assert!(Arc::ptr_eq(&my_container.my_trait, &existing));
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! Keyed facets (see below) cannot be given to a build in this way.
+//!
+//! ### Partial Builds
+//!
+//! Sometimes only a few of the facets of a large container are needed.  The
+//! `build_partial` method builds only the facet fields named in a
+//! `FacetSet`, along with their dependencies.  It returns a partial
+//! container, named after the container with a `Partial` prefix, in which
+//! each facet field is an `Option` that is `None` if the facet wasn't built.
+//! Accessing an unbuilt facet through the facet's traits panics.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait MyTrait {}
+//! # #[facet::facet] trait OtherTrait {}
+//! # struct MyTraitImpl;
+//! # impl MyTrait for MyTraitImpl {}
+//! # impl OtherTrait for MyTraitImpl {}
+//! # struct MyFactory;
+//! # #[facet::factory()]
+//! # impl MyFactory {
+//! #     fn my_trait(&self) -> ArcMyTrait { Arc::new(MyTraitImpl) }
+//! #     fn other_trait(&self) -> ArcOtherTrait { Arc::new(MyTraitImpl) }
+//! # }
+//! use facet::FacetSet;
+//!
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet]
+//!     my_trait: dyn MyTrait,
+//!
+//!     #[facet]
+//!     other_trait: dyn OtherTrait,
+//! }
+//!
+//! # fn main() -> Result<(), anyhow::Error> {
+//! let facets = FacetSet::new().with("my_trait");
+//! let partial: PartialMyContainer = MyFactory.build_partial::<MyContainer>(&facets)?;
+//! assert!(partial.my_trait.is_some());
+//! assert!(partial.other_trait.is_none());
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! Partial containers only hold the container's plain facet fields.  Keyed,
+//! lazy and weak facets, delegated containers and normal fields are not
+//! included.
+//!
+//! ### Incremental Rebuilds
+//!
+//! Containers that need rebuilding when their parameters change, such as
+//! when configuration is reloaded, can be built with `build_rebuildable`.
+//! This returns a `Rebuildable` that holds the container along with the
+//! parameters and facets of the build.  Passing it to `rebuild` with new
+//! parameters builds a new container, only calling the factory methods of
+//! facets that depend on a parameter that has changed, either directly or
+//! through their dependencies.  All other facets are shared with the
+//! existing container.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] struct Pool { size: u32 }
+//! # #[facet::facet] struct Clock {}
+//! # struct MyFactory;
+//! # #[facet::factory(pool_size: u32)]
+//! # impl MyFactory {
+//! #     fn pool(&self, pool_size: &u32) -> ArcPool { Arc::new(Pool { size: *pool_size }) }
+//! #     fn clock(&self) -> ArcClock { Arc::new(Clock {}) }
+//! # }
+//! # #[facet::container]
+//! # struct MyContainer {
+//! #     #[facet] pool: Pool,
+//! #     #[facet] clock: Clock,
+//! # }
+//! # fn main() -> Result<(), anyhow::Error> {
+//! let container = MyFactory.build_rebuildable::<MyContainer>(4)?;
+//! let rebuilt = MyFactory.rebuild(&container, 8)?;
+//! assert_eq!(rebuilt.pool.size, 8);
+//! assert!(Arc::ptr_eq(&container.clock, &rebuilt.clock));
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! The factory's parameters must implement `PartialEq` for `rebuild` to be
+//! used.
+//!
+//! ### Generic Containers
+//!
+//! Containers can have lifetime and type parameters, which may be used by
+//! normal fields, facets and nested containers.  The facet access traits and
+//! the build implementations are generic over the same parameters, so each
+//! instantiation of the container can be built by any factory that provides
+//! its facets.  Containers built by asynchronous factories must be `Send`.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait Db {}
+//! # struct MyDb;
+//! # impl Db for MyDb {}
+//! # struct MyFactory;
+//! # #[facet::factory()]
+//! # impl MyFactory {
+//! #     fn db(&self) -> ArcDb { Arc::new(MyDb) }
+//! # }
+//! #[facet::container]
+//! struct Ctx<T: Clone + Default + Send + Sync + 'static> {
+//!     #[init(T::default())]
+//!     extra: T,
+//!
+//!     #[facet]
+//!     db: dyn Db,
+//! }
+//!
+//! let ctx = MyFactory.build::<Ctx<Vec<String>>>().unwrap();
+//! assert!(ctx.extra.is_empty());
+//! ```
+//!
+//! ### Static Dispatch
+//!
+//! Containers marked with `#[facet::container(static_dispatch)]` store
+//! dynamic facets as their concrete types rather than as trait objects,
+//! avoiding dynamic dispatch when calling the facets.  The container becomes
+//! generic over the factory that builds it, and each dynamic facet is stored
+//! as the type built by the factory method with the same name as the field.
+//! The facets are still held in an `Arc`, as they may be shared with the
+//! facets that depend on them, so a `my_trait` field built by a method
+//! returning `Arc<MyTraitImpl>` is an `Arc<MyTraitImpl>`.  The container can
+//! still be used to access the facets through the `Ref` and `Arc` traits.
+//!
+//! Factories for these containers return `Arc`s of the concrete types, and
+//! their methods take the concrete types as dependencies.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait MyTrait { fn get(&self) -> u32; }
+//! struct MyTraitImpl;
+//!
+//! impl MyTrait for MyTraitImpl {
+//!     fn get(&self) -> u32 {
+//!         42
+//!     }
+//! }
+//!
+//! struct MyStaticFactory;
+//!
+//! #[facet::factory()]
+//! impl MyStaticFactory {
+//!     fn my_trait(&self) -> Arc<MyTraitImpl> {
+//!         Arc::new(MyTraitImpl)
+//!     }
+//! }
+//!
+//! #[facet::container(static_dispatch)]
+//! struct MyStaticContainer {
+//!     #[facet]
+//!     my_trait: dyn MyTrait,
+//! }
+//!
+//! let container = MyStaticFactory
+//!     .build::<MyStaticContainer<MyStaticFactory>>()
+//!     .unwrap();
+//! let my_trait: &Arc<MyTraitImpl> = &container.my_trait;
+//! assert_eq!(my_trait.get(), 42);
+//! ```
+//!
+//! ### Wrapper Structs
+//!
+//! Structs that wrap a container, for example to add extra fields, can be
+//! marked with `#[facet::delegate]` to give them access to all the facets
+//! of the container they wrap.  The wrapped container is either the only
+//! field of a tuple struct, or the field marked with `#[delegate]`.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait MyTrait {}
+//! # #[facet::container] struct MyContainer { #[facet] my_trait: dyn MyTrait }
+//! #[facet::delegate]
+//! struct MyWrapper(MyContainer);
+//!
+//! #[facet::delegate]
+//! struct MyOtherWrapper {
+//!     #[delegate]
+//!     container: Arc<MyContainer>,
+//!     extra: u32,
+//! }
+//!
+//! fn use_my_trait(container: impl MyTraitRef) {}
+//!
+//! # fn example(wrapper: MyWrapper, other_wrapper: MyOtherWrapper) {
+//! use_my_trait(&wrapper);
+//! use_my_trait(&other_wrapper);
+//! # }
+//! ```
+//!
+//! ### Debug
+//!
+//! Facets are not required to implement `Debug`, so containers don't
+//! implement it by default.  Containers marked with
+//! `#[facet::container(debug)]` implement `Debug` by showing the name of
+//! the facet in each facet field, and the values of normal fields and
+//! nested containers, which must implement `Debug` themselves.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait MyTrait {}
+//! # struct MyTraitImpl;
+//! # impl MyTrait for MyTraitImpl {}
+//! # struct MyFactory;
+//! # #[facet::factory()]
+//! # impl MyFactory {
+//! #     fn my_trait(&self) -> ArcMyTrait { Arc::new(MyTraitImpl) }
+//! # }
+//! #[facet::container(debug)]
+//! struct MyContainer {
+//!     #[init(3)]
+//!     count: u32,
+//!
+//!     #[facet]
+//!     my_trait: dyn MyTrait,
+//! }
+//!
+//! let container = MyFactory.build::<MyContainer>().unwrap();
+//! assert_eq!(
+//!     format!("{:?}", container),
+//!     "MyContainer { count: 3, my_trait: <my_trait> }",
+//! );
+//! ```
+//!
+//! ### Local Containers
+//!
+//! Facets are normally shared between threads, so they must be `Send` and
+//! `Sync`.  Facets marked with `#[facet::facet(local)]` don't have this
+//! requirement: they are held in an `Rc`, with an `RcMyTrait` alias in
+//! place of the arc alias, and a `MyTraitRc` trait in place of the arc
+//! trait.  Local facets can only be held in containers marked with
+//! `#[facet::container(local)]`, which are built synchronously and do not
+//! support lazy, weak, keyed or swappable facets, partial builds or
+//! shutdown.
+//!
+//! ```
+//! # use std::cell::Cell;
+//! # use std::rc::Rc;
+//! #[facet::facet(local)]
+//! trait Counter {
+//!     fn increment(&self) -> u32;
+//! }
+//!
+//! struct CellCounter(Cell<u32>);
+//!
+//! impl Counter for CellCounter {
+//!     fn increment(&self) -> u32 {
+//!         self.0.set(self.0.get() + 1);
+//!         self.0.get()
+//!     }
+//! }
+//!
+//! struct MyFactory;
+//!
+//! #[facet::factory()]
+//! impl MyFactory {
+//!     fn counter(&self) -> RcCounter {
+//!         Rc::new(CellCounter(Cell::new(0)))
+//!     }
+//! }
+//!
+//! #[facet::container(local)]
+//! struct MyContainer {
+//!     #[facet]
+//!     counter: dyn Counter,
+//! }
+//!
+//! let container = MyFactory.build::<MyContainer>().unwrap();
+//! assert_eq!(container.counter().increment(), 1);
+//! ```
+//!
+//! ### Boxed Facets
+//!
+//! Facets that are never shared can be marked with `#[facet::facet(boxed)]`,
+//! and held in container fields marked with `#[facet(boxed)]`.  The
+//! container owns these facets in a `Box`, avoiding the cost of reference
+//! counting them, so they are accessed via the ref trait and there is a
+//! `BoxMyTrait` alias in place of the arc alias and no arc trait.  Factory
+//! methods build boxed facets by returning a `Box`, or a `BoxMyTrait` alias
+//! if the method is marked with `#[facet(boxed)]`.
+//!
+//! Boxed facets are built separately for each container field that holds
+//! them, and they can't be dependencies of other facets.  They can only be
+//! built by synchronous factories, and containers with boxed facets don't
+//! support partial builds.
+//!
+//! ```
+//! #[facet::facet(boxed)]
+//! struct Scratch {
+//!     buffer: Vec<u8>,
+//! }
+//!
+//! struct MyFactory;
+//!
+//! #[facet::factory(size: usize)]
+//! impl MyFactory {
+//!     fn scratch(&self, size: &usize) -> Box<Scratch> {
+//!         Box::new(Scratch { buffer: vec![0; *size] })
+//!     }
+//! }
+//!
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet(boxed)]
+//!     scratch: Scratch,
+//! }
+//!
+//! let mut container = MyFactory.build::<MyContainer>(16).unwrap();
+//! container.scratch.buffer.push(1);
+//! assert_eq!(container.scratch().buffer.len(), 17);
+//! ```
+//!
+//! ### Mutable Facets
+//!
+//! Some facets legitimately need to be mutated or replaced once built, such
+//! as in-memory stores used in tests.  Container fields marked with
+//! `#[facet(mutable)]` hold the facet as an `Arc<RwLock<Box<MyTrait>>>`,
+//! and the container has `my_trait_read` and `my_trait_write` methods that
+//! lock it, so implementations of the facet don't need to do their own
+//! locking.  Clones of the container share the lock.
+//!
+//! Mutable facets are built from boxed facets, so each container that holds
+//! a mutable facet gets its own instance of it, and they have the same
+//! restrictions as boxed facets.
+//!
+//! ```
+//! # use std::collections::HashMap;
+//! #[facet::facet(boxed)]
+//! trait Store {
+//!     fn get(&self, key: &str) -> Option<String>;
+//!     fn set(&mut self, key: &str, value: &str);
+//! }
+//!
+//! #[derive(Default)]
+//! struct MemoryStore(HashMap<String, String>);
+//!
+//! impl Store for MemoryStore {
+//!     fn get(&self, key: &str) -> Option<String> {
+//!         self.0.get(key).cloned()
+//!     }
+//!
+//!     fn set(&mut self, key: &str, value: &str) {
+//!         self.0.insert(key.to_string(), value.to_string());
+//!     }
+//! }
+//!
+//! struct MyFactory;
+//!
+//! #[facet::factory()]
+//! impl MyFactory {
+//!     #[facet(boxed)]
+//!     fn store(&self) -> BoxStore {
+//!         Box::<MemoryStore>::default()
+//!     }
+//! }
+//!
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet(mutable)]
+//!     store: dyn Store,
+//! }
+//!
+//! let container = MyFactory.build::<MyContainer>().unwrap();
+//! container.store_write().set("key", "value");
+//! assert_eq!(container.store_read().get("key").as_deref(), Some("value"));
+//! ```
+//!
+//! ### Downcasting
+//!
+//! Code such as diagnostics occasionally needs the concrete implementation
+//! behind a facet.  Facet traits marked with `#[facet::facet(downcast)]`
+//! have `AsAny` as a supertrait, so their trait objects can be converted to
+//! `&dyn Any` with `as_any`.  Container fields marked with
+//! `#[facet(downcast)]` can be downcast with the container's
+//! `downcast_facet` method, which returns the first such facet whose
+//! implementation is of the requested type.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! #[facet::facet(downcast)]
+//! trait MyTrait {}
+//!
+//! struct MyTraitImpl {
+//!     connections: u32,
+//! }
+//!
+//! impl MyTrait for MyTraitImpl {}
+//!
+//! # struct MyFactory;
+//! # #[facet::factory()]
+//! # impl MyFactory {
+//! #     fn my_trait(&self) -> ArcMyTrait { Arc::new(MyTraitImpl { connections: 4 }) }
+//! # }
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet(downcast)]
+//!     my_trait: dyn MyTrait,
+//! }
+//!
+//! let container = MyFactory.build::<MyContainer>().unwrap();
+//! let my_trait = container.downcast_facet::<MyTraitImpl>().unwrap();
+//! assert_eq!(my_trait.connections, 4);
+//! ```
+//!
+//! ### Cloning
+//!
+//! Facets are shared between their users, but some facets are cheap
+//! value-like objects that callers want their own copies of.  Facet traits
+//! marked with `#[facet::facet(clone)]` have `DynClone` from the `dyn_clone`
+//! crate as a supertrait, so boxes of the facet implement `Clone`, and the
+//! facet's `Ref` trait has a method named after the facet with a `clone_`
+//! prefix that clones the facet from a container into a new box.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! #[facet::facet(clone)]
+//! trait Settings {
+//!     fn set_verbose(&mut self, verbose: bool);
+//! }
+//!
+//! #[derive(Clone)]
+//! struct SettingsImpl {
+//!     verbose: bool,
+//! }
+//!
+//! impl Settings for SettingsImpl {
+//!     fn set_verbose(&mut self, verbose: bool) {
+//!         self.verbose = verbose;
+//!     }
+//! }
+//!
+//! # struct MyFactory;
+//! # #[facet::factory()]
+//! # impl MyFactory {
+//! #     fn settings(&self) -> ArcSettings { Arc::new(SettingsImpl { verbose: false }) }
+//! # }
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet]
+//!     settings: dyn Settings,
+//! }
+//!
+//! let container = MyFactory.build::<MyContainer>().unwrap();
+//! let mut settings = container.clone_settings();
+//! settings.set_verbose(true);
+//! ```
+//!
+//! ### Forwarding
+//!
+//! Facet traits marked with `#[facet::facet(forward)]` are also implemented
+//! for `&T`, `Box<T>` and `Arc<T>` of any implementation `T`, including the
+//! facet's own trait objects, by forwarding each method to the
+//! implementation the pointer points to.  This lets decorators be generic
+//! over the implementation they wrap, and be given an `ArcMyTrait` without
+//! forwarding every method by hand.  The methods of these traits must take
+//! `&self`, and the option can't be combined with `downcast` or `clone`.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! #[facet::facet(forward)]
+//! trait Store {
+//!     fn get(&self, key: &str) -> Option<String>;
+//! }
+//!
+//! struct Logged<S>(S);
+//!
+//! impl<S: Store> Store for Logged<S> {
+//!     fn get(&self, key: &str) -> Option<String> {
+//!         println!("get {}", key);
+//!         self.0.get(key)
+//!     }
+//! }
+//!
+//! fn logged(inner: ArcStore) -> ArcStore {
+//!     Arc::new(Logged(inner))
+//! }
+//! ```
+//!
+//! ### Keyed Facets
+//!
+//! A container can hold several instances of the same facet by giving each
+//! one a key with `#[facet(key = "Key")]`, where `Key` is a marker type.
+//! The factory method that builds each instance must be given the same key.
+//! Keyed facets are accessed through the `KeyedFacetRef` and
+//! `KeyedFacetArc` traits rather than the facet's own traits.
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use facet::KeyedFacetRef;
+//!
+//! #[facet::facet]
+//! trait Database {
+//!     fn name(&self) -> &str;
+//! }
+//!
+//! struct NamedDatabase(&'static str);
+//!
+//! impl Database for NamedDatabase {
+//!     fn name(&self) -> &str {
+//!         self.0
+//!     }
+//! }
+//!
+//! struct Primary;
+//! struct Replica;
+//!
+//! struct MyFactory;
+//!
+//! #[facet::factory()]
+//! impl MyFactory {
+//!     #[facet(key = "Primary")]
+//!     fn db(&self) -> ArcDatabase {
+//!         Arc::new(NamedDatabase("primary"))
+//!     }
+//!
+//!     #[facet(key = "Replica")]
+//!     fn db_replica(&self) -> ArcDatabase {
+//!         Arc::new(NamedDatabase("replica"))
+//!     }
+//! }
+//!
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet(key = "Primary")]
+//!     db: dyn Database,
+//!
+//!     #[facet(key = "Replica")]
+//!     db_replica: dyn Database,
+//! }
+//!
+//! fn replica_name(container: &impl KeyedFacetRef<Replica, dyn Database + Send + Sync>) -> &str {
+//!     container.keyed_facet_ref().name()
+//! }
+//!
+//! let container = MyFactory.build::<MyContainer>().unwrap();
+//! assert_eq!(container.db.name(), "primary");
+//! assert_eq!(replica_name(&container), "replica");
+//! ```
+//!
+//! ### Container Traits
+//!
+//! Each container also defines a trait named after it with a `Like` suffix,
+//! which has a `FacetRef` bound for every facet the container provides,
+//! including those of delegated containers.  It is implemented for any type
+//! that provides all of those facets, so generic code can bound on
+//! `MyContainerLike` rather than listing each facet's `Ref` trait.
+//!
+//! ```
+//! # #[facet::facet] trait Db { fn query(&self) -> u32; }
+//! # #[facet::facet] struct Config { limit: u32 }
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet]
+//!     db: dyn Db,
+//!
+//!     #[facet]
+//!     config: Config,
+//! }
+//!
+//! fn limited_query(container: &impl MyContainerLike) -> u32 {
+//!     container.db().query().min(container.config().limit)
+//! }
+//! ```
+//!
+//! A container that holds a subset of the facets of other containers can
+//! list them with `#[facet::container(from(...))]` to generate `From`
+//! implementations that convert references to those containers by cloning
+//! the shared facets.  Facets are matched by type rather than by field name,
+//! and every field of the converted container must be a plain or keyed facet.
+//!
+//! ```
+//! # #[facet::facet] trait Db {}
+//! # #[facet::facet] struct Config { limit: u32 }
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet]
+//!     db: dyn Db,
+//!
+//!     #[facet]
+//!     config: Config,
+//! }
+//!
+//! #[facet::container(from(MyContainer))]
+//! struct ConfigView {
+//!     #[facet]
+//!     config: Config,
+//! }
+//!
+//! fn config_view(container: &MyContainer) -> ConfigView {
+//!     ConfigView::from(container)
+//! }
+//! ```
+//!
+//! Keyed facets cannot be lazy or weak.
+//!
+//! ### Weak Handles
+//!
+//! Containers declared with `#[facet::container(downgrade)]` have a
+//! `downgrade` method that returns a weak handle to the container, named
+//! after the container with a `Weak` prefix.  The handle holds its facets
+//! as weak references, so it doesn't keep them alive, and its `upgrade`
+//! method returns the container again, or `None` if any of its facets have
+//! been dropped.  Background tasks can hold the handle rather than the
+//! container, so that they don't keep every facet alive forever.  Every
+//! field of the container must be a plain, keyed or weak facet.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] struct Config { limit: u32 }
+//! #[facet::container(downgrade)]
+//! struct MyContainer {
+//!     #[facet]
+//!     config: Config,
+//! }
+//! # struct MyFactory;
+//! # #[facet::factory()]
+//! # impl MyFactory {
+//! #     fn config(&self) -> ArcConfig { Arc::new(Config { limit: 10 }) }
+//! # }
+//!
+//! let container = MyFactory.build::<MyContainer>().unwrap();
+//! let weak: WeakMyContainer = container.downgrade();
+//! assert_eq!(weak.upgrade().unwrap().config.limit, 10);
+//! drop(container);
+//! assert!(weak.upgrade().is_none());
+//! ```
+//!
+//! ## Async
+//!
+//! Async dynamic facets can be supported by using the `async-trait` crate,
+//! or by using native `async fn` in the trait (see [Native Async
+//! Traits](#native-async-traits)).
+//!
+//! Async factory methods are supported.  To make a factory async, mark one or
+//! more methods as `async`:
+//! ```
+//! # #[facet::facet] trait MyTrait {}
+//! #[facet::facet]
+//! #[async_trait::async_trait]
+//! trait OtherTrait {
+//!     async fn do_something(&self);
+//! }
+//!
+//! # use anyhow::Error;
+//! # use std::sync::Arc;
+//! # struct MyTraitImpl { config: () }
+//! # impl MyTrait for MyTraitImpl {}
+//! # impl MyTraitImpl {
+//! #     async fn new(_name: &str, _config: &str) -> Result<MyTraitImpl, Error> {
+//! #         Ok(MyTraitImpl { config: () })
+//! #     }
+//! # }
+//! # struct OtherTraitImpl;
+//! # #[async_trait::async_trait]
+//! # impl OtherTrait for OtherTraitImpl {
+//! #     async fn do_something(&self) {}
+//! # }
+//! # impl OtherTraitImpl {
+//! #     fn new(_my_trait: ArcMyTrait, value: u32) -> OtherTraitImpl {
+//! #         OtherTraitImpl
+//! #     }
+//! # }
+//! # struct MyAsyncFactory { config: String }
+//! #[facet::factory(name: String, value: u32)]
+//! impl MyAsyncFactory {
+//!     async fn my_trait(&self, name: &str) -> Result<ArcMyTrait, Error> {
+//!         Ok(Arc::new(
+//!             MyTraitImpl::new(name, self.config.as_str()).await?
+//!         ))
+//!     }
+//!
+//!     fn other_trait(&self, my_trait: &ArcMyTrait, value: &u32) -> ArcOtherTrait {
+//!         Arc::new(OtherTraitImpl::new(my_trait.clone(), *value))
+//!     }
+//! }
+//! ```
+//!
+//! For async factories, the build method is async:
+//!
+//! ```
+//! # #[facet::facet] trait MyTrait {}
+//! # struct MyTraitImpl;
+//! # impl MyTrait for MyTraitImpl {}
+//! # struct MyAsyncFactory { config: String }
+//! # #[facet::factory(name: String, value: u32)]
+//! # impl MyAsyncFactory {
+//! #     async fn my_trait(&self) -> ArcMyTrait {
+//! #        std::sync::Arc::new(MyTraitImpl)
+//! #     }
+//! # }
+//! # #[facet::container] struct MyContainer {}
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), anyhow::Error> {
+//! let factory = MyAsyncFactory { config: String::from("config") };
+//! let my_container = factory.build::<MyContainer>("name".to_string(), 42).await?;
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! The build method will attempt to build facets concurrently where it can.
+//! To limit how many facets are built at once, for example to avoid
+//! overwhelming a backend with new connections, use the
+//! `build_with_concurrency` method, which takes the maximum number of
+//! concurrent builds after the factory parameters.  Lazy facets are not
+//! subject to this limit.
+//!
+//! ```
+//! # #[facet::facet] trait MyTrait {}
+//! # struct MyTraitImpl;
+//! # impl MyTrait for MyTraitImpl {}
+//! # struct MyAsyncFactory;
+//! # #[facet::factory(name: String)]
+//! # impl MyAsyncFactory {
+//! #     async fn my_trait(&self) -> ArcMyTrait {
+//! #        std::sync::Arc::new(MyTraitImpl)
+//! #     }
+//! # }
+//! # #[facet::container] struct MyContainer {}
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), anyhow::Error> {
+//! let factory = MyAsyncFactory;
+//! let my_container = factory
+//!     .build_with_concurrency::<MyContainer>("name".to_string(), 4)
+//!     .await?;
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! ### Native Async Traits
+//!
+//! Traits with native `async fn` methods are not object safe, so for a trait
+//! that uses them without `#[async_trait]`, the `facet` macro also generates
+//! a `Dyn{Trait}` trait.  This has the same methods, but async methods return
+//! boxed futures, and it is implemented for every implementation of the
+//! original trait.  The reference and `Arc` traits use the `Dyn{Trait}` trait
+//! object, so containers must name it as the facet type.  Futures returned by
+//! the methods are `Send`, unless the facet is `local`.  The boxed futures
+//! borrow `self` and the arguments, so methods can return data borrowed from
+//! them, with elided or explicitly named lifetimes.  Elided lifetimes in
+//! `Fn` trait and function pointer arguments stay higher-ranked.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! #[facet::facet]
+//! trait Store {
+//!     async fn get(&self, key: &str) -> Option<String>;
+//! }
+//!
+//! struct EmptyStore;
+//!
+//! impl Store for EmptyStore {
+//!     async fn get(&self, _key: &str) -> Option<String> {
+//!         None
+//!     }
+//! }
+//!
+//! # struct MyFactory;
+//! # #[facet::factory()]
+//! # impl MyFactory {
+//! #     fn store(&self) -> ArcStore {
+//! #         Arc::new(EmptyStore)
+//! #     }
+//! # }
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet]
+//!     store: dyn DynStore,
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), anyhow::Error> {
+//! let my_container = MyFactory.build::<MyContainer>()?;
+//! assert_eq!(my_container.store().get("key").await, None);
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! ### Timeouts
+//!
+//! Async factory methods can be given a timeout with
+//! `#[facet(timeout = "30s")]`.  If the method takes longer than this to
+//! build the facet, the build fails with `FactoryError::FacetBuildTimedOut`.
+//! Timeouts can be given in milliseconds (`ms`), seconds (`s`), minutes
+//! (`m`) or hours (`h`), and must be used within a Tokio 0.2 or 1.x runtime.
+//!
+//! ```
+//! # #[facet::facet] trait MyTrait {}
+//! # struct MyTraitImpl;
+//! # impl MyTrait for MyTraitImpl {}
+//! # async fn connect() -> MyTraitImpl { MyTraitImpl }
+//! # struct MyAsyncFactory;
+//! #[facet::factory()]
+//! impl MyAsyncFactory {
+//!     #[facet(timeout = "30s")]
+//!     async fn my_trait(&self) -> ArcMyTrait {
+//!         std::sync::Arc::new(connect().await)
+//!     }
+//! }
+//! ```
+//!
+//! ### Retries
+//!
+//! Fallible async factory methods can be retried when they fail with
+//! `#[facet(retries = 3, backoff = "1s")]`.  The method is called again up
+//! to `retries` times, waiting for `backoff` before the first retry and
+//! twice as long before each subsequent retry, so like timeouts they must be
+//! used within a Tokio 0.2 or 1.x runtime.  Attempts that time out are also
+//! retried, but panics are not.  If every attempt fails, the build
+//! fails with `FactoryError::FacetBuildRetriesExhausted`, which records the
+//! number of attempts and the error from the final attempt.
+//!
+//! ```
+//! # use anyhow::Error;
+//! # #[facet::facet] trait MyTrait {}
+//! # struct MyTraitImpl;
+//! # impl MyTrait for MyTraitImpl {}
+//! # async fn connect() -> Result<MyTraitImpl, Error> { Ok(MyTraitImpl) }
+//! # struct MyAsyncFactory;
+//! #[facet::factory()]
+//! impl MyAsyncFactory {
+//!     #[facet(retries = 3, backoff = "1s", timeout = "30s")]
+//!     async fn my_trait(&self) -> Result<ArcMyTrait, Error> {
+//!         Ok(std::sync::Arc::new(connect().await?))
+//!     }
+//! }
+//! ```
+//!
+//! ### Cancellation
+//!
+//! Async factories also have a `build_cancellable` method, which takes the
+//! factory parameters followed by a future that completes when the build
+//! should be cancelled, such as `CancellationToken::cancelled` from
+//! `tokio-util`.  If it completes before the container has been built, the
+//! build is abandoned and fails with `FactoryError::BuildCancelled`.
+//!
+//! Abandoning a build, either by cancelling it or by dropping the future
+//! returned by any of the build methods, drops the facets that have already
+//! been built.  Facets are dropped in reverse dependency order, so each
+//! facet is dropped before the facets it depends on.
+//!
+//! ```
+//! # #[facet::facet] trait MyTrait {}
+//! # struct MyTraitImpl;
+//! # impl MyTrait for MyTraitImpl {}
+//! # struct MyAsyncFactory;
+//! # #[facet::factory()]
+//! # impl MyAsyncFactory {
+//! #     async fn my_trait(&self) -> ArcMyTrait { std::sync::Arc::new(MyTraitImpl) }
+//! # }
+//! # #[facet::container] struct MyContainer { #[facet] my_trait: dyn MyTrait }
+//! # async fn example(factory: MyAsyncFactory) {
+//! let (cancel, cancelled) = futures::channel::oneshot::channel::<()>();
+//! let build = factory.build_cancellable::<MyContainer>(async {
+//!     let _ = cancelled.await;
+//! });
+//! // Cancel the build from elsewhere.
+//! drop(cancel);
+//! assert!(matches!(build.await, Err(facet::FactoryError::BuildCancelled)));
+//! # }
+//! ```
+//!
+//! ### Lazy Facets
+//!
+//! Facets in a container can be marked as lazy with `#[facet(lazy)]`.  Lazy
+//! facets are not built when the container is built.  Instead, the
+//! container has an async accessor method that builds the facet on first
+//! access, along with any of its dependencies that weren't built with the
+//! container.  Dependencies that are only needed by lazy facets are built
+//! separately for each of them.
+//!
+//! ```
+//! # #[facet::facet] trait MyTrait {}
+//! #[facet::container]
+//! struct MyLazyContainer {
+//!     #[facet(lazy)]
+//!     my_trait: dyn MyTrait,
+//! }
+//!
+//! # async fn example(container: MyLazyContainer) -> Result<(), facet::FactoryError> {
+//! let my_trait: &dyn MyTrait = container.my_trait().await?;
+//! #     Ok(())
+//! # }
+//! ```
+//!
+//! Since lazy facets are built after the build method returns, they can only
+//! be built by async factories that are `Clone`, and the container holds a
+//! clone of the factory until they are built.  Building a container with
+//! lazy facets from a synchronous factory is a compile error.
+//!
+//! ## Shutdown
+//!
+//! Facets that need to be shut down, for example to stop background tasks
+//! or close connections, can implement the `FacetShutdown` trait.  Dynamic
+//! facets must make this a supertrait of the facet trait.  Container fields
+//! for these facets should be marked with `#[facet(shutdown)]`.
+//!
+//! Containers implement the `ContainerShutdown` trait, whose `shutdown` method
+//! shuts down the marked facets in the reverse of the order they were built,
+//! so that facets are shut down before the facets they depend on.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # use facet::{ContainerShutdown, FacetShutdown};
+//! #[facet::facet]
+//! trait MyService: FacetShutdown {}
+//!
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet(shutdown)]
+//!     my_service: dyn MyService,
+//! }
+//!
+//! # async fn example(container: MyContainer) {
+//! container.shutdown().await;
+//! # }
+//! ```
+//!
+//! ## Health Checks
+//!
+//! Facets that can report their health, for example by checking that a
+//! connection is still open, can implement the `FacetHealth` trait.  As with
+//! shutdown, dynamic facets must make this a supertrait of the facet trait,
+//! and container fields for these facets should be marked with
+//! `#[facet(health)]`.
+//!
+//! Containers implement the `ContainerHealth` trait, whose `check_health`
+//! method checks the marked facets of the container and its nested
+//! containers concurrently, and returns the `HealthStatus` of each facet by
+//! name.
+//!
+//! ```
+//! # use facet::{ContainerHealth, FacetHealth, HealthStatus};
+//! #[facet::facet]
+//! trait MyService: FacetHealth {}
+//!
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet(health)]
+//!     my_service: dyn MyService,
+//! }
+//!
+//! # async fn example(container: MyContainer) {
+//! let health = container.check_health().await;
+//! if !health["my_service"].is_healthy() {
+//!     // ...
+//! }
+//! # }
+//! ```
+//!
+//! ## Usage Tracking
+//!
+//! Containers declared with `#[facet::container(track_usage)]` record which
+//! of their facets are ever accessed through the container's facet traits,
+//! such as the `Ref` and `Arc` traits of each facet.  This makes it possible
+//! to find facets that are built but never used, for example by reporting
+//! the `unused_facets` of the `ContainerUsage` trait at shutdown.  Each
+//! facet is flagged atomically on its first access, so later accesses only
+//! read the flag.
+//!
+//! Accesses to the fields of the container directly are not tracked, and
+//! facets of nested containers are tracked by the nested container, if it
+//! also tracks usage.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] trait Db { fn query(&self) -> u32; }
+//! # #[facet::facet] struct Cache {}
+//! # struct MyDb;
+//! # impl Db for MyDb { fn query(&self) -> u32 { 1 } }
+//! # struct MyFactory;
+//! # #[facet::factory()]
+//! # impl MyFactory {
+//! #     fn db(&self) -> ArcDb { Arc::new(MyDb) }
+//! #     fn cache(&self) -> ArcCache { Arc::new(Cache {}) }
+//! # }
+//! use facet::ContainerUsage;
 //!
+//! #[facet::container(track_usage)]
+//! struct MyContainer {
 //!     #[facet]
-//!     my_trait: dyn MyTrait,
+//!     db: dyn Db,
 //!
 //!     #[facet]
-//!     my_struct: MyStruct,
-//!
-//!     #[delegate(dyn OtherTrait)]
-//!     my_nested_container: Arc<NestedContainer>,
+//!     cache: Cache,
 //! }
+//!
+//! let container = MyFactory.build::<MyContainer>().unwrap();
+//! container.db().query();
+//! assert_eq!(container.unused_facets(), vec!["cache"]);
 //! ```
 //!
-//! Containers can be contructed using the `build` method of a factory.
-//! The build method must be passed the parameters defined on the factory
-//! attribute and these will be used as inputs for building this container.
+//! ## Introspection
+//!
+//! When the `graph` feature is enabled, each factory has a `facet_graph`
+//! method that describes the facets it can build, the factory parameters
+//! they use, and the facets they depend on.  Containers can be added to the
+//! graph to record which of their fields consume each facet.  Without the
+//! feature, factories and containers don't generate this description.
 //!
 //! ```
-//! # struct MyFactory { config: String }
-//! # #[facet::factory(name: String, value: u32)] impl MyFactory {}
-//! # #[facet::container] struct MyContainer {}
-//! # fn main() -> Result<(), anyhow::Error> {
-//! let factory = MyFactory { config: String::from("config") };
-//! let my_container = factory.build::<MyContainer>("name".to_string(), 42)?;
-//! #     Ok(())
+//! # #[facet::facet] trait MyTrait {}
+//! # struct MyTraitImpl;
+//! # impl MyTrait for MyTraitImpl {}
+//! # struct MyFactory;
+//! # #[facet::factory(name: String)]
+//! # impl MyFactory {
+//! #     fn my_trait(&self, name: &str) -> ArcMyTrait {
+//! #        std::sync::Arc::new(MyTraitImpl)
+//! #     }
+//! # }
+//! # #[facet::container] struct MyContainer { #[facet] my_trait: dyn MyTrait }
+//! # #[cfg(feature = "graph")] {
+//! let graph = MyFactory::facet_graph().with_container::<MyContainer>();
+//! let my_trait = graph.facet("my_trait").unwrap();
+//! assert_eq!(my_trait.params, ["name"]);
+//! assert_eq!(my_trait.consumers, ["MyContainer.my_trait"]);
 //! # }
 //! ```
 //!
-//! The `build` method always returns `Result<Container, FactoryError>`, even
-//! if none of the factory methods are fallible.  If no methods are fallible
-//! then the result will always be `Ok`.
-//!
-//! ## Async
+//! The graph can also be rendered in Graphviz DOT format, either with
+//! `FacetGraph::to_dot` or with the `dependency_dot` method that is generated
+//! for each factory.
 //!
-//! Async dynamic facets can be supported by using the `async-trait` crate.
+//! To find out which facets are slow to build, use the `build_instrumented`
+//! method instead of `build`.  This returns a `BuildReport` alongside the
+//! container, which records how long each facet took to build.
 //!
-//! Async factory methods are supported.  To make a factory async, mark one or
-//! more methods as `async`:
 //! ```
 //! # #[facet::facet] trait MyTrait {}
-//! #[facet::facet]
-//! #[async_trait::async_trait]
-//! trait OtherTrait {
-//!     async fn do_something(&self);
-//! }
-//!
-//! # use anyhow::Error;
-//! # use std::sync::Arc;
-//! # struct MyTraitImpl { config: () }
+//! # struct MyTraitImpl;
 //! # impl MyTrait for MyTraitImpl {}
-//! # impl MyTraitImpl {
-//! #     async fn new(_name: &str, _config: &str) -> Result<MyTraitImpl, Error> {
-//! #         Ok(MyTraitImpl { config: () })
-//! #     }
-//! # }
-//! # struct OtherTraitImpl;
-//! # #[async_trait::async_trait]
-//! # impl OtherTrait for OtherTraitImpl {
-//! #     async fn do_something(&self) {}
-//! # }
-//! # impl OtherTraitImpl {
-//! #     fn new(_my_trait: ArcMyTrait, value: u32) -> OtherTraitImpl {
-//! #         OtherTraitImpl
+//! # struct MyFactory;
+//! # #[facet::factory(name: String)]
+//! # impl MyFactory {
+//! #     fn my_trait(&self, name: &str) -> ArcMyTrait {
+//! #        std::sync::Arc::new(MyTraitImpl)
 //! #     }
 //! # }
-//! # struct MyAsyncFactory { config: String }
-//! #[facet::factory(name: String, value: u32)]
-//! impl MyAsyncFactory {
-//!     async fn my_trait(&self, name: &str) -> Result<ArcMyTrait, Error> {
-//!         Ok(Arc::new(
-//!             MyTraitImpl::new(name, self.config.as_str()).await?
-//!         ))
-//!     }
-//!
-//!     fn other_trait(&self, my_trait: &ArcMyTrait, value: &u32) -> ArcOtherTrait {
-//!         Arc::new(OtherTraitImpl::new(my_trait.clone(), *value))
-//!     }
+//! # #[facet::container] struct MyContainer { #[facet] my_trait: dyn MyTrait }
+//! # fn main() -> Result<(), facet::FactoryError> {
+//! let (container, report) =
+//!     MyFactory.build_instrumented::<MyContainer>("name".to_string())?;
+//! for facet in report.slowest() {
+//!     println!("{} took {:?}", facet.name, facet.duration);
 //! }
+//! #     Ok(())
+//! # }
 //! ```
 //!
-//! For async factories, the build method is async:
+//! Async factories also have a `build_with_progress` method, which takes a
+//! callback that is passed a `BuildEvent` as each facet starts building,
+//! completes or fails.  This can drive progress logging or readiness gates
+//! during long builds.  The callback may be called concurrently from the
+//! facets being built, so send events to a channel if they need to be
+//! processed in order.
 //!
 //! ```
 //! # #[facet::facet] trait MyTrait {}
 //! # struct MyTraitImpl;
 //! # impl MyTrait for MyTraitImpl {}
-//! # struct MyAsyncFactory { config: String }
-//! # #[facet::factory(name: String, value: u32)]
+//! # struct MyAsyncFactory;
+//! # #[facet::factory(name: String)]
 //! # impl MyAsyncFactory {
 //! #     async fn my_trait(&self) -> ArcMyTrait {
 //! #        std::sync::Arc::new(MyTraitImpl)
 //! #     }
 //! # }
-//! # #[facet::container] struct MyContainer {}
+//! # #[facet::container] struct MyContainer { #[facet] my_trait: dyn MyTrait }
 //! # #[tokio::main]
-//! # async fn main() -> Result<(), anyhow::Error> {
-//! let factory = MyAsyncFactory { config: String::from("config") };
-//! let my_container = factory.build::<MyContainer>("name".to_string(), 42).await?;
+//! # async fn main() -> Result<(), facet::FactoryError> {
+//! let container = MyAsyncFactory
+//!     .build_with_progress::<MyContainer>("name".to_string(), |event| {
+//!         println!("{:?}", event);
+//!     })
+//!     .await?;
 //! #     Ok(())
 //! # }
 //! ```
 //!
-//! The build method will attempt to build facets concurrently where it can.
+//! When the `tracing` feature is enabled, each call to a factory method
+//! during a build is wrapped in a `tracing` span named after the facet, so
+//! the construction of facets shows up in traces.
+//!
+//! When the `stats` feature is enabled, each call to a factory method is
+//! recorded using the `stats` crate.  For a facet with the name `my_trait`,
+//! `facet.my_trait.build_count` counts the calls, `facet.my_trait.build_errors`
+//! counts the calls that fail, panic or time out, and
+//! `facet.my_trait.build_time_ms` is a histogram of how long successful calls
+//! took.
+//!
+//! When the `access_tracking` feature is enabled, each access of a facet
+//! through a container's facet traits is recorded along with the location of
+//! the code that made it.  `facet::access_report()` returns the accesses
+//! recorded so far, counted by container, facet field and call site, which
+//! shows which facets a program actually uses and so which can be removed
+//! from its containers.  Accessing a field of the container directly is not
+//! recorded.  The report's `Display` implementation lists each call site
+//! on its own line, e.g. `MyContainer.my_trait accessed 3 times at
+//! src/main.rs:12:5`.
+//!
+//! ## Mocks
+//!
+//! Marking a facet trait with `#[facet::mock]` generates a mock
+//! implementation of the trait (`MockMyTrait` for `MyTrait`) for use in test
+//! factories.  The mock has a `MockMethod` field for each method of the
+//! trait, which can be given a return value, and which records the
+//! arguments of each call.  Arguments passed by reference are recorded as
+//! owned copies.  Methods can have lifetime parameters, as long as the
+//! recorded arguments and return values don't borrow with them.
+//!
+//! Methods that return `()` do nothing by default.  Calling any other method
+//! that has not been given a return value panics.  Methods with default
+//! implementations in the trait are not mocked.
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! #[facet::facet]
+//! #[facet::mock]
+//! trait MyStore {
+//!     fn get(&self, key: &str) -> Option<String>;
+//!     fn put(&self, key: &str, value: String);
+//! }
+//!
+//! let store = Arc::new(MockMyStore::new());
+//! store.get.returns(Some(String::from("value")));
+//!
+//! let my_store: ArcMyStore = store.clone();
+//! my_store.put("key", String::from("value"));
+//! assert_eq!(my_store.get("key"), Some(String::from("value")));
+//! assert_eq!(
+//!     store.put.calls(),
+//!     [(String::from("key"), String::from("value"))],
+//! );
+//! assert_eq!(store.get.call_count(), 1);
+//! ```
+//!
+//! ### Tests
+//!
+//! Tests that use a container can be marked with
+//! `#[facet::test(factory = MyFactory, params(...))]` and take the container
+//! as their only argument.  The container is built by the given factory
+//! with the given parameters before the body of the test runs, and the test
+//! fails if the build fails.  Synchronous tests are given a container built
+//! by a synchronous factory; `async` tests are run with `#[tokio::test]`
+//! and are given a container built by an asynchronous factory.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # #[facet::facet] #[facet::mock] trait MyStore { fn get(&self, key: &str) -> Option<String>; }
+//! struct TestFactory;
+//!
+//! #[facet::factory(value: String)]
+//! impl TestFactory {
+//!     fn my_store(&self, value: &String) -> ArcMyStore {
+//!         let store = MockMyStore::new();
+//!         store.get.returns(Some(value.clone()));
+//!         Arc::new(store)
+//!     }
+//! }
+//!
+//! #[facet::container]
+//! struct MyContainer {
+//!     #[facet]
+//!     my_store: dyn MyStore,
+//! }
+//!
+//! #[facet::test(factory = TestFactory, params(String::from("value")))]
+//! fn my_test(container: MyContainer) {
+//!     assert_eq!(container.my_store.get("key"), Some(String::from("value")));
+//! }
+//! ```
 
 extern crate facet_proc_macros;
-pub use facet_proc_macros::{container, facet, factory};
+pub use facet_proc_macros::{
+    container, delegate, facet, factory, factory_trait, mock, post_build, test,
+};
+
+#[cfg(feature = "access_tracking")]
+mod access;
+#[cfg(feature = "stats")]
+mod build_stats;
+mod downcast;
+#[cfg(feature = "graph")]
+mod graph;
+mod health;
+mod inject;
+mod keyed;
+mod lazy;
+mod local;
+mod memo;
+mod mock;
+mod partial;
+mod rebuild;
+mod registry;
+mod report;
+mod scope;
+mod shutdown;
+mod static_dispatch;
+mod swap;
+mod usage;
+#[cfg(feature = "graph")]
+mod validate;
+mod weak;
+
+#[cfg(feature = "access_tracking")]
+#[doc(hidden)]
+pub use access::record_access;
+#[cfg(feature = "access_tracking")]
+pub use access::{AccessReport, FacetAccess, access_report, reset_access_report};
+#[cfg(feature = "stats")]
+#[doc(hidden)]
+pub use build_stats::{build_with_stats, build_with_stats_async};
+pub use downcast::AsAny;
+#[cfg(feature = "graph")]
+pub use graph::{ContainerFacets, ContainerField, FacetAlternate, FacetGraph, FacetNode};
+pub use health::{ContainerHealth, FacetHealth, HealthStatus};
+pub use inject::{AsyncBuildWith, BuildWith, InjectFacet};
+pub use keyed::{Keyed, KeyedFacetArc, KeyedFacetRef};
+pub use lazy::{LazyFacet, LazyFactory};
+pub use local::FacetRc;
+pub use memo::{BuildCache, FactoryMemo};
+pub use mock::MockMethod;
+pub use partial::{AsyncPartialBuildable, FacetSet, PartialBuildable};
+pub use rebuild::Rebuildable;
+pub use registry::{FacetRegistry, RegistryError, registry};
+pub use report::{BuildEvent, BuildRecorder, BuildReport, FacetBuildTime};
+pub use scope::{FacetCache, FactoryScope};
+pub use shutdown::{ContainerShutdown, FacetShutdown};
+pub use static_dispatch::StaticFacet;
+pub use swap::SwappableFacet;
+pub use usage::{ContainerUsage, FacetUsage};
+#[cfg(feature = "graph")]
+#[doc(hidden)]
+pub use validate::Validator;
+#[cfg(feature = "graph")]
+pub use validate::{ValidationIssue, ValidationReport};
+pub use weak::WeakFacet;
 
+use std::any::Any;
 use std::future::Future;
+use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use thiserror::Error;
 
 #[doc(hidden)]
 pub extern crate async_trait;
 
+#[doc(hidden)]
+pub extern crate dyn_clone;
+
 #[doc(hidden)]
 pub extern crate futures;
 
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub extern crate tracing;
+
 /// An error during construction by a facet factory.
 #[derive(Debug, Error)]
 pub enum FactoryError {
@@ -344,6 +2556,152 @@ pub enum FactoryError {
         /// The error encountered when building the facet.
         source: anyhow::Error,
     },
+
+    /// A facet failed to build on every attempt allowed by its retry
+    /// policy.
+    #[error("failed to build '{name}' after {attempts} attempts")]
+    FacetBuildRetriesExhausted {
+        /// The name of the facet that failed to build.
+        name: &'static str,
+
+        /// The number of attempts made to build the facet.
+        attempts: u32,
+
+        /// The error encountered on the final attempt.
+        source: anyhow::Error,
+    },
+
+    /// A facet couldn't be built because a facet it depends on failed to
+    /// build.
+    #[error("failed to build '{name}' ({})", dependency_path(name, source))]
+    DependencyFailed {
+        /// The name of the facet that couldn't be built.
+        name: &'static str,
+
+        /// The error from building the dependency.
+        source: Box<FactoryError>,
+    },
+
+    /// A facet took longer to build than its timeout allowed.
+    #[error("timed out building '{name}' after {duration:?}")]
+    FacetBuildTimedOut {
+        /// The name of the facet that timed out.
+        name: &'static str,
+
+        /// The timeout that was exceeded.
+        duration: Duration,
+    },
+
+    /// A facet panicked while it was being built.
+    #[error("panicked while building '{name}': {message}")]
+    FacetBuildPanicked {
+        /// The name of the facet that panicked.
+        name: &'static str,
+
+        /// The message the facet panicked with.
+        message: String,
+    },
+
+    /// An async build was cancelled before it completed.
+    #[error("build cancelled")]
+    BuildCancelled,
+
+    /// A build was not given a factory parameter that has no default, or a
+    /// container needs a factory parameter that the factory doesn't have.
+    #[error("missing factory parameter '{name}'")]
+    MissingParameter {
+        /// The name of the parameter.
+        name: &'static str,
+    },
+
+    /// A partial build was asked to build a field that is not a facet field
+    /// of the container.
+    #[error("'{field}' is not a facet field of '{container}'")]
+    UnknownFacetField {
+        /// The name of the container.
+        container: &'static str,
+
+        /// The name of the field.
+        field: String,
+    },
+
+    /// A post-build hook of the factory failed.
+    #[error("post-build hook '{name}' failed")]
+    PostBuildFailed {
+        /// The name of the hook.
+        name: &'static str,
+
+        /// The error returned by the hook.
+        source: anyhow::Error,
+    },
+}
+
+impl FactoryError {
+    /// Returns the chain of facets that led to the facet that failed to
+    /// build, starting with the facet needed by the container and ending
+    /// with the facet that failed.  This is empty if the error isn't a
+    /// failure to build a facet.
+    pub fn path(&self) -> Vec<&'static str> {
+        let mut path = Vec::new();
+        let mut error = self;
+        while let FactoryError::DependencyFailed { name, source } = error {
+            path.push(*name);
+            error = source;
+        }
+        path.extend(error.failed_facet());
+        path
+    }
+
+    /// Returns the error from the facet that failed to build, skipping the
+    /// `DependencyFailed` errors of the facets that depend on it.
+    pub fn failure(&self) -> &FactoryError {
+        match self {
+            FactoryError::DependencyFailed { source, .. } => source.failure(),
+            error => error,
+        }
+    }
+
+    // Record that the facet that failed to build was needed by the facet
+    // `name`.
+    #[doc(hidden)]
+    pub fn needed_by(self, name: &'static str) -> Self {
+        match self.failed_facet() {
+            Some(_) => FactoryError::DependencyFailed {
+                name,
+                source: Box::new(self),
+            },
+            None => self,
+        }
+    }
+
+    // Record the chain of facets that needed the facet that failed to build,
+    // using `needed_by` to find the facet that needed each facet.
+    #[doc(hidden)]
+    pub fn with_needed_by(mut self, needed_by: impl Fn(&str) -> Option<&'static str>) -> Self {
+        while let Some(name) = self.failed_facet().and_then(&needed_by) {
+            self = self.needed_by(name);
+        }
+        self
+    }
+
+    // The name of the facet this error is for, if it is a failure to build a
+    // facet that the facets depending on it record.
+    fn failed_facet(&self) -> Option<&'static str> {
+        match self {
+            FactoryError::FacetBuildFailed { name, .. }
+            | FactoryError::FacetBuildRetriesExhausted { name, .. }
+            | FactoryError::DependencyFailed { name, .. } => Some(*name),
+            _ => None,
+        }
+    }
+}
+
+// The chain of facets from the facet `name` to the facet that failed to build,
+// as displayed by `FactoryError::DependencyFailed`.
+fn dependency_path(name: &'static str, source: &FactoryError) -> String {
+    let mut path = vec![name];
+    path.extend(source.path());
+    path.join(" -> ")
 }
 
 // Clonable wrapper for `FactoryError` in async builders.
@@ -372,6 +2730,222 @@ impl AsyncFactoryError {
             .take()
             .expect("bug in #[facet::factory]: factory error already taken")
     }
+
+    // Describe the error and its sources, for reporting before the error is
+    // taken.
+    pub(crate) fn describe(&self) -> String {
+        let error = self.error.lock().unwrap();
+        let error = match error.as_ref() {
+            Some(error) => error,
+            None => return String::from("factory error already taken"),
+        };
+        let mut description = error.to_string();
+        let mut source = std::error::Error::source(error);
+        while let Some(error) = source {
+            description.push_str(": ");
+            description.push_str(&error.to_string());
+            source = error.source();
+        }
+        description
+    }
+}
+
+// Build a facet, failing if it takes longer than `duration`.  Used by async
+// builders for factory methods that have a timeout.
+#[doc(hidden)]
+pub async fn build_with_timeout<F: Future>(
+    name: &'static str,
+    duration: Duration,
+    build: F,
+) -> Result<F::Output, FactoryError> {
+    tokio_shim::time::timeout(duration, build).await.map_err(|_| {
+        #[cfg(feature = "stats")]
+        build_stats::record_build_error(name);
+        FactoryError::FacetBuildTimedOut { name, duration }
+    })
+}
+
+// Build a facet, retrying failed attempts up to `retries` times.  The delay
+// before each retry starts at `backoff` and doubles after each attempt.  Each
+// attempt is subject to the timeout, if there is one, and timeouts are
+// retried.  Panics are not retried.  Used by async builders for factory
+// methods that have a retry policy.
+#[doc(hidden)]
+pub async fn build_with_retries<T, E, F>(
+    name: &'static str,
+    retries: u32,
+    backoff: Duration,
+    timeout: Option<Duration>,
+    catch_panics: bool,
+    mut attempt: impl FnMut() -> F,
+) -> Result<T, FactoryError>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<anyhow::Error>,
+{
+    let mut attempts = 0;
+    let mut delay = backoff;
+    loop {
+        attempts += 1;
+        let build = async {
+            if catch_panics {
+                catch_async_build_panic(name, attempt()).await
+            } else {
+                Ok(attempt().await)
+            }
+        };
+        let result = match timeout {
+            Some(duration) => build_with_timeout(name, duration, build)
+                .await
+                .and_then(|result| result),
+            None => build.await,
+        };
+        let error = match result {
+            Ok(Ok(facet)) => return Ok(facet),
+            Ok(Err(e)) => e.into(),
+            Err(e @ FactoryError::FacetBuildTimedOut { .. }) => e.into(),
+            Err(e) => return Err(e),
+        };
+        if attempts > retries {
+            return Err(FactoryError::FacetBuildRetriesExhausted {
+                name,
+                attempts,
+                source: error,
+            });
+        }
+        tokio_shim::time::sleep(delay).await;
+        delay = delay.saturating_mul(2);
+    }
+}
+
+// Build a container, abandoning the build if `cancelled` completes first.
+// The abandoned build is dropped on return, which drops any facets that have
+// already been built.  Used by
+// the `build_cancellable` method of async factories.
+#[doc(hidden)]
+pub async fn build_cancellable<T>(
+    build: impl Future<Output = Result<T, FactoryError>>,
+    cancelled: impl Future<Output = ()>,
+) -> Result<T, FactoryError> {
+    futures::pin_mut!(build, cancelled);
+    match futures::future::select(build, cancelled).await {
+        futures::future::Either::Left((result, _)) => result,
+        futures::future::Either::Right(_) => Err(FactoryError::BuildCancelled),
+    }
+}
+
+// Build a facet, converting any panic into an error.  Used by builders of
+// factories that catch panics, for factory methods that don't propagate them.
+#[doc(hidden)]
+pub fn catch_build_panic<T>(
+    name: &'static str,
+    build: impl FnOnce() -> T,
+) -> Result<T, FactoryError> {
+    std::panic::catch_unwind(AssertUnwindSafe(build)).map_err(|payload| {
+        #[cfg(feature = "stats")]
+        build_stats::record_build_error(name);
+        FactoryError::FacetBuildPanicked {
+            name,
+            message: panic_message(payload),
+        }
+    })
+}
+
+// Async version of `catch_build_panic`.
+#[doc(hidden)]
+pub async fn catch_async_build_panic<F: Future>(
+    name: &'static str,
+    build: F,
+) -> Result<F::Output, FactoryError> {
+    use futures::future::FutureExt;
+    AssertUnwindSafe(build)
+        .catch_unwind()
+        .await
+        .map_err(|payload| {
+            #[cfg(feature = "stats")]
+            build_stats::record_build_error(name);
+            FactoryError::FacetBuildPanicked {
+                name,
+                message: panic_message(payload),
+            }
+        })
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("unknown panic payload")
+    }
+}
+
+// A factory parameter taken by value by a factory method, held by the
+// builder until that method consumes it.
+#[doc(hidden)]
+pub struct OwnedParam<T> {
+    value: Mutex<Option<T>>,
+}
+
+impl<T> OwnedParam<T> {
+    #[doc(hidden)]
+    pub fn new(value: T) -> Self {
+        OwnedParam {
+            value: Mutex::new(Some(value)),
+        }
+    }
+
+    // Take the parameter.  Only one factory method takes each parameter by
+    // value, and each facet is only built once per build, so the parameter
+    // is only taken once.
+    #[doc(hidden)]
+    pub fn take(&self, name: &'static str) -> T {
+        self.value
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_else(|| panic!("bug in #[facet::factory]: parameter '{}' taken twice", name))
+    }
+}
+
+// Limit on the number of facets that async builders build concurrently.
+#[doc(hidden)]
+pub struct BuildLimit {
+    semaphore: Option<tokio::sync::Semaphore>,
+}
+
+impl BuildLimit {
+    #[doc(hidden)]
+    pub fn unlimited() -> Self {
+        BuildLimit { semaphore: None }
+    }
+
+    #[doc(hidden)]
+    pub fn new(concurrency: usize) -> Self {
+        assert!(
+            concurrency > 0,
+            "facet build concurrency must be at least 1"
+        );
+        BuildLimit {
+            semaphore: Some(tokio::sync::Semaphore::new(concurrency)),
+        }
+    }
+
+    // Wait until another facet may be built.  The returned permit should
+    // be held while the facet is built.
+    #[doc(hidden)]
+    pub async fn acquire(&self) -> Option<tokio::sync::SemaphorePermit<'_>> {
+        match &self.semaphore {
+            Some(semaphore) => Some(
+                semaphore
+                    .acquire()
+                    .await
+                    .expect("bug in #[facet::factory]: build limit closed"),
+            ),
+            None => None,
+        }
+    }
 }
 
 // Trait implemented by containers that are buildable by factory builders.
@@ -390,6 +2964,31 @@ where
     }
 }
 
+// Trait implemented by containers that can have their facets built in
+// parallel by synchronous factory builders.  The facets the container needs
+// are marked as needed in `N` before any of them are built.
+#[doc(hidden)]
+pub trait ParallelBuildable<N> {
+    fn mark_needed(needed: &mut N);
+}
+
+impl<N, T> ParallelBuildable<N> for Arc<T>
+where
+    T: ParallelBuildable<N>,
+{
+    #[inline]
+    fn mark_needed(needed: &mut N) {
+        T::mark_needed(needed);
+    }
+}
+
+// Trait implemented by the needed facets of synchronous factory builders,
+// to mark facets of type T (and their dependencies) as needed.
+#[doc(hidden)]
+pub trait NeedFacet<T> {
+    fn need(&mut self);
+}
+
 // Trait implemented by containers that are buildable by async factory builders.
 // Desugared async-trait so that the builder lifetime can be specified.
 #[doc(hidden)]
@@ -400,7 +2999,13 @@ pub trait AsyncBuildable<'builder, B>: Sized {
 
     fn mark_needed(builder: &mut B);
 
-    fn construct(builder: &B) -> Self;
+    // Check the builder has the factory parameters the container needs.
+    fn check_params(builder: &B) -> Result<(), FactoryError>;
+
+    fn construct<'a>(builder: &'a B) -> Pin<Box<dyn Future<Output = Self> + Send + 'a>>
+    where
+        'builder: 'a,
+        Self: 'a;
 }
 
 impl<'builder, B, T> AsyncBuildable<'builder, B> for Arc<T>
@@ -419,13 +3024,55 @@ where
         T::mark_needed(builder);
     }
 
-    fn construct(builder: &B) -> Self {
-        Arc::new(T::construct(builder))
+    fn check_params(builder: &B) -> Result<(), FactoryError> {
+        T::check_params(builder)
+    }
+
+    fn construct<'a>(builder: &'a B) -> Pin<Box<dyn Future<Output = Self> + Send + 'a>>
+    where
+        'builder: 'a,
+        Self: 'a,
+    {
+        let construct = T::construct(builder);
+        Box::pin(async move { Arc::new(construct.await) })
     }
 }
 
+// Trait implemented by factories to name the type of builder used by their
+// builds, so that other factories can delegate to them.
+#[doc(hidden)]
+pub trait FactoryBuilder<'factory> {
+    type Builder;
+}
+
+// Trait implemented by factory builders to report the names of the facets
+// that have been built, in the order they were built.
+#[doc(hidden)]
+pub trait BuildOrder {
+    fn build_order(&self) -> Vec<&'static str>;
+}
+
+/// Trait implemented by facets declared with a default implementation,
+/// using `#[facet::facet(default = Type)]`.
+pub trait FacetDefault {
+    /// Construct the default implementation of this facet.
+    fn default_facet() -> Arc<Self>;
+}
+
+// Trait implemented by factory builders to give containers access to the
+// factory parameters of type T, by name.
+#[doc(hidden)]
+pub trait BuildParam<T> {
+    fn param(&self, name: &str) -> Option<&T>;
+}
+
 // Trait implemented by factory builders that can build facets of type T.
 #[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "the factory can't build the facet `{T}`",
+    label = "the factory has no method to build this facet",
+    note = "a factory builds a facet with the method named after the facet, or with `#[facet::factory(defaults(...))]`"
+)]
 pub trait Builder<T: Sized> {
     fn build(&mut self) -> Result<T, FactoryError>;
 }
@@ -433,6 +3080,11 @@ pub trait Builder<T: Sized> {
 // Trait implemented by factory builders that can asynchronously build facets
 // of type T.
 #[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "the factory can't build the facet `{T}`",
+    label = "the factory has no method to build this facet",
+    note = "a factory builds a facet with the method named after the facet, or with `#[facet::factory(defaults(...))]`"
+)]
 pub trait AsyncBuilderFor<T: Sized> {
     // Mark this facet type (and its dependencies) as needed.
     fn need(&mut self);
@@ -441,27 +3093,52 @@ pub trait AsyncBuilderFor<T: Sized> {
     fn get(&self) -> T;
 }
 
+// Trait implemented by async factory builders that can build facets of type
+// T lazily, on first access.
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "the factory can't lazily build the facet `{T}`",
+    label = "the factory has no method to build this facet, or isn't async",
+    note = "lazy facets are built by async factories, with the method named after the facet"
+)]
+pub trait AsyncLazyBuilderFor<T: Clone + Send + Sync + 'static> {
+    // Get a lazy instance of this facet, which will be built on first access.
+    fn get_lazy(&self) -> LazyFacet<T>;
+}
+
 // Trait implemented by factory builds to trigger parallel async build of
 // facets marked as needed.
 #[doc(hidden)]
 #[async_trait::async_trait]
 pub trait AsyncBuilder {
     async fn build_needed(&mut self) -> Result<(), FactoryError>;
+
+    // Run the factory's post-build hooks, once the facets the container
+    // needs have been built.
+    async fn post_build(&mut self) -> Result<(), FactoryError>;
+}
+
+// Trait implemented by synchronous factory builders to run the factory's
+// post-build hooks, once the facets the container needs have been built.
+#[doc(hidden)]
+pub trait PostBuild {
+    fn post_build(&mut self) -> Result<(), FactoryError>;
 }
 
 // Trait implemented by containers that can provide a reference to facets of
 // type T.
 #[doc(hidden)]
-pub trait FacetRef<T: ?Sized + Send + Sync + 'static> {
+pub trait FacetRef<T: ?Sized + 'static> {
     fn facet_ref(&self) -> &T;
 }
 
 impl<T, C> FacetRef<T> for Arc<C>
 where
-    T: ?Sized + Send + Sync + 'static,
+    T: ?Sized + 'static,
     C: FacetRef<T>,
 {
     #[inline]
+    #[cfg_attr(feature = "access_tracking", track_caller)]
     fn facet_ref(&self) -> &T {
         <C as FacetRef<T>>::facet_ref(self)
     }
@@ -469,10 +3146,11 @@ where
 
 impl<T, C> FacetRef<T> for &Arc<C>
 where
-    T: ?Sized + Send + Sync + 'static,
+    T: ?Sized + 'static,
     C: FacetRef<T>,
 {
     #[inline]
+    #[cfg_attr(feature = "access_tracking", track_caller)]
     fn facet_ref(&self) -> &T {
         <C as FacetRef<T>>::facet_ref(*self)
     }
@@ -481,6 +3159,10 @@ where
 // Trait implemented by containers that can provide an arc to facets of
 // type T.
 #[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "the container `{Self}` doesn't provide the facet `{T}`",
+    label = "the container has no facet field or delegate for this facet"
+)]
 pub trait FacetArc<T: ?Sized + Send + Sync + 'static> {
     fn facet_arc(&self) -> Arc<T>;
 }
@@ -491,6 +3173,7 @@ where
     C: FacetArc<T>,
 {
     #[inline]
+    #[cfg_attr(feature = "access_tracking", track_caller)]
     fn facet_arc(&self) -> Arc<T> {
         <C as FacetArc<T>>::facet_arc(self)
     }
@@ -502,6 +3185,7 @@ where
     C: FacetArc<T>,
 {
     #[inline]
+    #[cfg_attr(feature = "access_tracking", track_caller)]
     fn facet_arc(&self) -> Arc<T> {
         <C as FacetArc<T>>::facet_arc(*self)
     }
@@ -0,0 +1,119 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Local facets and containers, which are not `Send` or `Sync`.
+
+use std::rc::Rc;
+
+use futures::future::BoxFuture;
+
+#[cfg(feature = "graph")]
+use crate::{ContainerFacets, ContainerField};
+use crate::{
+    Buildable, ContainerHealth, ContainerShutdown, ContainerUsage, FacetRef, FacetUsage,
+    FactoryError, HealthStatus,
+};
+
+// Trait implemented by local containers that can provide an rc to facets of
+// type T.
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "the container `{Self}` doesn't provide the facet `{T}`",
+    label = "the container has no facet field or delegate for this facet"
+)]
+pub trait FacetRc<T: ?Sized + 'static> {
+    fn facet_rc(&self) -> Rc<T>;
+}
+
+impl<T, C> FacetRc<T> for Rc<C>
+where
+    T: ?Sized + 'static,
+    C: FacetRc<T>,
+{
+    #[inline]
+    #[cfg_attr(feature = "access_tracking", track_caller)]
+    fn facet_rc(&self) -> Rc<T> {
+        <C as FacetRc<T>>::facet_rc(self)
+    }
+}
+
+impl<T, C> FacetRc<T> for &Rc<C>
+where
+    T: ?Sized + 'static,
+    C: FacetRc<T>,
+{
+    #[inline]
+    #[cfg_attr(feature = "access_tracking", track_caller)]
+    fn facet_rc(&self) -> Rc<T> {
+        <C as FacetRc<T>>::facet_rc(*self)
+    }
+}
+
+impl<T, C> FacetRef<T> for Rc<C>
+where
+    T: ?Sized + 'static,
+    C: FacetRef<T>,
+{
+    #[inline]
+    #[cfg_attr(feature = "access_tracking", track_caller)]
+    fn facet_ref(&self) -> &T {
+        <C as FacetRef<T>>::facet_ref(self)
+    }
+}
+
+impl<T, C> FacetRef<T> for &Rc<C>
+where
+    T: ?Sized + 'static,
+    C: FacetRef<T>,
+{
+    #[inline]
+    #[cfg_attr(feature = "access_tracking", track_caller)]
+    fn facet_ref(&self) -> &T {
+        <C as FacetRef<T>>::facet_ref(*self)
+    }
+}
+
+impl<B, T> Buildable<B> for Rc<T>
+where
+    T: Buildable<B>,
+{
+    #[inline]
+    fn build(builder: &mut B) -> Result<Rc<T>, FactoryError> {
+        Ok(Rc::new(T::build(builder)?))
+    }
+}
+
+#[cfg(feature = "graph")]
+impl<C: ContainerFacets> ContainerFacets for Rc<C> {
+    fn container_name() -> &'static str {
+        C::container_name()
+    }
+
+    fn facet_fields() -> Vec<ContainerField> {
+        C::facet_fields()
+    }
+}
+
+impl<C: ContainerShutdown> ContainerShutdown for Rc<C> {
+    fn shutdown_facets(&self) -> Vec<(usize, &'static str, BoxFuture<'_, ()>)> {
+        C::shutdown_facets(self)
+    }
+}
+
+impl<C: ContainerHealth> ContainerHealth for Rc<C> {
+    fn health_checks(&self) -> Vec<(&'static str, BoxFuture<'_, HealthStatus>)> {
+        C::health_checks(self)
+    }
+}
+
+impl<C: ContainerUsage> ContainerUsage for Rc<C> {
+    fn facet_usage(&self) -> &FacetUsage {
+        C::facet_usage(self)
+    }
+}
@@ -0,0 +1,72 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Memoized builds.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+/// Cache of the facets built by a memoized factory, keyed on the parameters
+/// of each build.
+#[derive(Default)]
+pub struct BuildCache {
+    // A `HashMap` from the parameters of each build to the facets it built.
+    // Its type depends on the factory, so it is created by the first build.
+    builds: Mutex<Option<Box<dyn Any + Send + Sync>>>,
+}
+
+impl BuildCache {
+    /// Create a new, empty cache.
+    pub fn new() -> Self {
+        BuildCache::default()
+    }
+
+    #[doc(hidden)]
+    pub fn get<K, V>(&self, key: &K) -> Option<V>
+    where
+        K: Hash + Eq + Send + Sync + 'static,
+        V: Clone + Send + Sync + 'static,
+    {
+        let builds = self.builds.lock().expect("lock poisoned");
+        builds
+            .as_ref()?
+            .downcast_ref::<HashMap<K, V>>()?
+            .get(key)
+            .cloned()
+    }
+
+    #[doc(hidden)]
+    pub fn insert<K, V>(&self, key: K, facets: V)
+    where
+        K: Hash + Eq + Send + Sync + 'static,
+        V: Clone + Send + Sync + 'static,
+    {
+        let mut builds = self.builds.lock().expect("lock poisoned");
+        let builds = builds.get_or_insert_with(|| Box::new(HashMap::<K, V>::new()));
+        if let Some(builds) = builds.downcast_mut::<HashMap<K, V>>() {
+            builds.insert(key, facets);
+        }
+    }
+
+    /// Remove all cached builds, so that the facets they built will be built
+    /// again by the next build that needs them.
+    pub fn clear(&self) {
+        let mut builds = self.builds.lock().expect("lock poisoned");
+        *builds = None;
+    }
+}
+
+/// Trait implemented by memoized factories, to provide the cache that the
+/// facets built by each build are stored in.
+pub trait FactoryMemo {
+    /// The cache of builds for this factory.
+    fn build_cache(&self) -> &BuildCache;
+}
@@ -0,0 +1,80 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Mock implementations of facet traits.
+
+use std::sync::{Arc, Mutex};
+
+type MockHandler<A, R> = Arc<dyn Fn(&A) -> R + Send + Sync>;
+
+/// A mocked method of a facet trait.
+///
+/// Mocks generated by `#[facet::mock]` have one of these for each method of
+/// the trait.  `A` is a tuple of the (owned) arguments the method was
+/// called with, and `R` is the return type of the method.
+pub struct MockMethod<A, R> {
+    name: &'static str,
+    handler: Mutex<Option<MockHandler<A, R>>>,
+    calls: Mutex<Vec<A>>,
+}
+
+impl<A, R> MockMethod<A, R> {
+    #[doc(hidden)]
+    pub fn new(name: &'static str) -> Self {
+        MockMethod {
+            name,
+            handler: Mutex::new(None),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Make the method return a clone of `value` whenever it is called.
+    pub fn returns(&self, value: R)
+    where
+        R: Clone + Send + Sync + 'static,
+    {
+        self.returns_with(move |_| value.clone());
+    }
+
+    /// Make the method call `handler` with its arguments whenever it is
+    /// called, and return the result.
+    pub fn returns_with(&self, handler: impl Fn(&A) -> R + Send + Sync + 'static) {
+        *self.handler.lock().expect("lock poisoned") = Some(Arc::new(handler));
+    }
+
+    /// The arguments of each call to the method so far, in the order they
+    /// were made.
+    pub fn calls(&self) -> Vec<A>
+    where
+        A: Clone,
+    {
+        self.calls.lock().expect("lock poisoned").clone()
+    }
+
+    /// The number of times the method has been called.
+    pub fn call_count(&self) -> usize {
+        self.calls.lock().expect("lock poisoned").len()
+    }
+
+    #[doc(hidden)]
+    pub fn call(&self, args: A) -> R {
+        // Release the lock before calling the handler, so that the handler
+        // can reconfigure the mock.
+        let handler = self.handler.lock().expect("lock poisoned").clone();
+        let handler = handler.unwrap_or_else(|| {
+            panic!(
+                "mock method '{}' was called without a return value",
+                self.name
+            )
+        });
+        let result = handler(&args);
+        self.calls.lock().expect("lock poisoned").push(args);
+        result
+    }
+}
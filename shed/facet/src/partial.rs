@@ -0,0 +1,91 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Builds of a subset of the facets of a container.
+
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::pin::Pin;
+
+use crate::FactoryError;
+
+/// A set of container facet fields to build in a partial build.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FacetSet {
+    fields: BTreeSet<String>,
+}
+
+impl FacetSet {
+    /// Create an empty set of facet fields.
+    pub fn new() -> Self {
+        FacetSet::default()
+    }
+
+    /// Add a facet field to the set.
+    pub fn with(mut self, field: impl Into<String>) -> Self {
+        self.fields.insert(field.into());
+        self
+    }
+
+    /// Returns true if the named facet field is in the set.
+    pub fn contains(&self, field: &str) -> bool {
+        self.fields.contains(field)
+    }
+
+    /// Iterate over the names of the facet fields in the set.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(String::as_str)
+    }
+
+    // Check that all the fields in the set are facet fields of the container.
+    #[doc(hidden)]
+    pub fn check_fields(
+        &self,
+        container: &'static str,
+        facet_fields: &[&'static str],
+    ) -> Result<(), FactoryError> {
+        match self.iter().find(|field| !facet_fields.contains(field)) {
+            Some(field) => Err(FactoryError::UnknownFacetField {
+                container,
+                field: field.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for FacetSet {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        FacetSet {
+            fields: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+// Trait implemented by containers that can be partially built by factory
+// builders.
+#[doc(hidden)]
+pub trait PartialBuildable<B>: Sized {
+    type Partial;
+
+    fn build_partial(builder: &mut B, facets: &FacetSet) -> Result<Self::Partial, FactoryError>;
+}
+
+// Trait implemented by containers that can be partially built by async
+// factory builders.  Desugared async-trait so that the builder lifetime can
+// be specified.
+#[doc(hidden)]
+pub trait AsyncPartialBuildable<'builder, B>: Sized {
+    type Partial;
+
+    fn build_partial_async(
+        builder: B,
+        facets: FacetSet,
+    ) -> Pin<Box<dyn Future<Output = Result<Self::Partial, FactoryError>> + Send + 'builder>>;
+}
@@ -0,0 +1,55 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Containers that can be rebuilt incrementally when parameters change.
+
+use std::ops::Deref;
+
+/// A container built by a factory's `build_rebuildable` method, which can be
+/// passed to the factory's `rebuild` method to build a new container with
+/// new parameters.
+///
+/// Alongside the container, this holds the parameters and facets of the
+/// build, so that a rebuild only needs to call the factory methods of
+/// facets whose parameters, or whose dependencies' parameters, have
+/// changed.  The other facets are shared with the new container.
+pub struct Rebuildable<C, S> {
+    container: C,
+    state: S,
+}
+
+impl<C, S> Rebuildable<C, S> {
+    #[doc(hidden)]
+    pub fn new(container: C, state: S) -> Self {
+        Rebuildable { container, state }
+    }
+
+    #[doc(hidden)]
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Returns the container.
+    pub fn container(&self) -> &C {
+        &self.container
+    }
+
+    /// Discards the state of the build, returning the container.
+    pub fn into_inner(self) -> C {
+        self.container
+    }
+}
+
+impl<C, S> Deref for Rebuildable<C, S> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.container
+    }
+}
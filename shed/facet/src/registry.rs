@@ -0,0 +1,192 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Runtime registry of facet implementations.
+
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use thiserror::Error;
+
+type Constructor<T> = Arc<dyn Fn() -> Result<Arc<T>, anyhow::Error> + Send + Sync>;
+
+/// An error resolving an implementation from a `FacetRegistry`.
+#[derive(Debug, Error)]
+pub enum RegistryError {
+    /// No implementation of the facet is registered with the name.
+    #[error("no implementation of '{facet}' is registered as '{name}'")]
+    NotRegistered {
+        /// The type of the facet.
+        facet: &'static str,
+
+        /// The name that was resolved.
+        name: String,
+    },
+
+    /// No implementation of the facet is registered.
+    #[error("no implementation of '{facet}' is registered")]
+    NoneRegistered {
+        /// The type of the facet.
+        facet: &'static str,
+    },
+
+    /// More than one implementation of the facet is registered, so there
+    /// is no only implementation to resolve.
+    #[error("implementation of '{facet}' is ambiguous: {}", .names.join(", "))]
+    Ambiguous {
+        /// The type of the facet.
+        facet: &'static str,
+
+        /// The names of the registered implementations.
+        names: Vec<String>,
+    },
+
+    /// The constructor of the implementation failed.
+    #[error("failed to construct implementation '{name}' of '{facet}'")]
+    ConstructionFailed {
+        /// The type of the facet.
+        facet: &'static str,
+
+        /// The name of the implementation.
+        name: String,
+
+        /// The error returned by the constructor.
+        source: anyhow::Error,
+    },
+}
+
+/// Registry of implementations of facets, each registered by name, so that
+/// factories can choose an implementation at runtime, for example from
+/// configuration.
+///
+/// Implementations are registered for a facet type, such as
+/// `dyn MyTrait + Send + Sync`, so the same name can be used by
+/// implementations of different facets.
+#[derive(Default)]
+pub struct FacetRegistry {
+    constructors: Mutex<HashMap<(TypeId, String), Box<dyn Any + Send + Sync>>>,
+}
+
+impl FacetRegistry {
+    /// Create a new, empty registry.
+    pub fn new() -> Self {
+        FacetRegistry::default()
+    }
+
+    /// Register a constructor for an implementation of the facet `T` called
+    /// `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an implementation of `T` is already registered with this
+    /// name.
+    pub fn register<T>(
+        &self,
+        name: impl Into<String>,
+        constructor: impl Fn() -> Result<Arc<T>, anyhow::Error> + Send + Sync + 'static,
+    ) -> &Self
+    where
+        T: ?Sized + Send + Sync + 'static,
+    {
+        let name = name.into();
+        let constructor: Constructor<T> = Arc::new(constructor);
+        let mut constructors = self.constructors.lock().expect("lock poisoned");
+        if constructors
+            .insert((TypeId::of::<T>(), name.clone()), Box::new(constructor))
+            .is_some()
+        {
+            panic!(
+                "implementation '{}' of '{}' is already registered",
+                name,
+                type_name::<T>()
+            );
+        }
+        self
+    }
+
+    /// Construct the implementation of the facet `T` registered as `name`.
+    ///
+    /// Each call constructs a new instance of the implementation.
+    pub fn resolve<T>(&self, name: &str) -> Result<Arc<T>, RegistryError>
+    where
+        T: ?Sized + Send + Sync + 'static,
+    {
+        // The constructor is called without the lock held, so that it can
+        // resolve other implementations.
+        let constructor = {
+            let constructors = self.constructors.lock().expect("lock poisoned");
+            constructors
+                .get(&(TypeId::of::<T>(), name.to_string()))
+                .and_then(|constructor| constructor.downcast_ref::<Constructor<T>>())
+                .cloned()
+        };
+        let constructor = constructor.ok_or_else(|| RegistryError::NotRegistered {
+            facet: type_name::<T>(),
+            name: name.to_string(),
+        })?;
+        constructor().map_err(|source| RegistryError::ConstructionFailed {
+            facet: type_name::<T>(),
+            name: name.to_string(),
+            source,
+        })
+    }
+
+    /// Construct the only registered implementation of the facet `T`, for
+    /// facets whose implementation is provided by whichever plugin is
+    /// linked in.
+    pub fn resolve_only<T>(&self) -> Result<Arc<T>, RegistryError>
+    where
+        T: ?Sized + Send + Sync + 'static,
+    {
+        let mut names = self.names::<T>();
+        match names.len() {
+            0 => Err(RegistryError::NoneRegistered {
+                facet: type_name::<T>(),
+            }),
+            1 => self.resolve(&names.remove(0)),
+            _ => Err(RegistryError::Ambiguous {
+                facet: type_name::<T>(),
+                names,
+            }),
+        }
+    }
+
+    /// Returns true if an implementation of the facet `T` is registered as
+    /// `name`.
+    pub fn contains<T>(&self, name: &str) -> bool
+    where
+        T: ?Sized + Send + Sync + 'static,
+    {
+        let constructors = self.constructors.lock().expect("lock poisoned");
+        constructors.contains_key(&(TypeId::of::<T>(), name.to_string()))
+    }
+
+    /// The names of the implementations of the facet `T` that are
+    /// registered, in sorted order.
+    pub fn names<T>(&self) -> Vec<String>
+    where
+        T: ?Sized + Send + Sync + 'static,
+    {
+        let constructors = self.constructors.lock().expect("lock poisoned");
+        let mut names = constructors
+            .keys()
+            .filter(|(type_id, _)| *type_id == TypeId::of::<T>())
+            .map(|(_, name)| name.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+}
+
+/// The global registry of facet implementations.
+pub fn registry() -> &'static FacetRegistry {
+    static REGISTRY: OnceLock<FacetRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(FacetRegistry::new)
+}
@@ -0,0 +1,154 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Timing reports and progress events for container builds.
+
+use std::cmp::Reverse;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::AsyncFactoryError;
+
+/// How long a facet took to build.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FacetBuildTime {
+    /// The name of the facet.
+    pub name: &'static str,
+
+    /// How long the factory method took to build the facet.  For async
+    /// factories, this does not include time spent waiting for the facet's
+    /// dependencies to be built.
+    pub duration: Duration,
+}
+
+/// A report of how long each facet took to build during a container build.
+///
+/// This is returned by the `build_instrumented` method that is generated
+/// for each factory.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BuildReport {
+    /// The facets that were built, in the order they finished building.
+    /// Facets that were already cached by the factory, or which are lazy,
+    /// are not included.
+    pub facets: Vec<FacetBuildTime>,
+
+    /// How long the whole build took.
+    pub total: Duration,
+}
+
+impl BuildReport {
+    /// Returns how long the named facet took to build, if it was built.
+    pub fn facet(&self, name: &str) -> Option<Duration> {
+        self.facets
+            .iter()
+            .find(|facet| facet.name == name)
+            .map(|facet| facet.duration)
+    }
+
+    /// Returns the facets that were built, slowest first.
+    pub fn slowest(&self) -> Vec<&FacetBuildTime> {
+        let mut facets = self.facets.iter().collect::<Vec<_>>();
+        facets.sort_by_key(|facet| Reverse(facet.duration));
+        facets
+    }
+}
+
+/// An event in the progress of an async container build.
+///
+/// These are passed to the callback given to the `build_with_progress`
+/// method that is generated for async factories.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BuildEvent {
+    /// The factory method for a facet has been called, now that its
+    /// dependencies have been built.
+    Started {
+        /// The name of the facet.
+        facet: &'static str,
+    },
+
+    /// A facet has been built.
+    Completed {
+        /// The name of the facet.
+        facet: &'static str,
+
+        /// How long the factory method took to build the facet.
+        duration: Duration,
+    },
+
+    /// A facet failed to build, so the build will fail.
+    Failed {
+        /// The name of the facet.
+        facet: &'static str,
+
+        /// How long the factory method ran before it failed.
+        duration: Duration,
+
+        /// A description of the error.
+        error: String,
+    },
+}
+
+// Records build times of facets as builders build them, and passes progress
+// events to the build's observer, if it has one.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct BuildRecorder {
+    facets: Mutex<Vec<FacetBuildTime>>,
+    observer: Option<Box<dyn Fn(BuildEvent) + Send + Sync>>,
+}
+
+impl BuildRecorder {
+    #[doc(hidden)]
+    pub fn with_observer(observer: impl Fn(BuildEvent) + Send + Sync + 'static) -> Self {
+        BuildRecorder {
+            facets: Mutex::default(),
+            observer: Some(Box::new(observer)),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn started(&self, facet: &'static str) {
+        self.notify(|| BuildEvent::Started { facet });
+    }
+
+    #[doc(hidden)]
+    pub fn record(&self, name: &'static str, duration: Duration) {
+        self.facets
+            .lock()
+            .unwrap()
+            .push(FacetBuildTime { name, duration });
+        self.notify(|| BuildEvent::Completed {
+            facet: name,
+            duration,
+        });
+    }
+
+    #[doc(hidden)]
+    pub fn failed(&self, facet: &'static str, duration: Duration, error: &AsyncFactoryError) {
+        self.notify(|| BuildEvent::Failed {
+            facet,
+            duration,
+            error: error.describe(),
+        });
+    }
+
+    fn notify(&self, event: impl FnOnce() -> BuildEvent) {
+        if let Some(observer) = &self.observer {
+            observer(event());
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn report(&self, total: Duration) -> BuildReport {
+        BuildReport {
+            facets: self.facets.lock().unwrap().clone(),
+            total,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Factory-scoped facets.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Cache of facets that are scoped to a factory, and so are shared between
+/// all containers built by that factory.
+#[derive(Default)]
+pub struct FacetCache {
+    facets: Mutex<HashMap<&'static str, Box<dyn Any + Send + Sync>>>,
+}
+
+impl FacetCache {
+    /// Create a new, empty cache.
+    pub fn new() -> Self {
+        FacetCache::default()
+    }
+
+    #[doc(hidden)]
+    pub fn get<A: Clone + 'static>(&self, name: &'static str) -> Option<A> {
+        let facets = self.facets.lock().expect("lock poisoned");
+        facets.get(name)?.downcast_ref::<A>().cloned()
+    }
+
+    #[doc(hidden)]
+    pub fn insert<A: Clone + Send + Sync + 'static>(&self, name: &'static str, facet: A) -> A {
+        let mut facets = self.facets.lock().expect("lock poisoned");
+        // If another build has cached this facet concurrently, then use the
+        // facet that was cached first so that all containers share it.
+        let cached = facets
+            .entry(name)
+            .or_insert_with(|| Box::new(facet.clone()));
+        cached.downcast_ref::<A>().cloned().unwrap_or(facet)
+    }
+
+    /// Returns true if the named facet has been built and cached.
+    pub fn contains(&self, name: &str) -> bool {
+        let facets = self.facets.lock().expect("lock poisoned");
+        facets.contains_key(name)
+    }
+
+    /// Remove all cached facets, so that they will be built again by the
+    /// next build that needs them.
+    pub fn clear(&self) {
+        let mut facets = self.facets.lock().expect("lock poisoned");
+        facets.clear();
+    }
+}
+
+/// Trait implemented by factories that have factory-scoped facets, to
+/// provide the cache that those facets are stored in.
+pub trait FactoryScope {
+    /// The cache of factory-scoped facets for this factory.
+    fn facet_cache(&self) -> &FacetCache;
+}
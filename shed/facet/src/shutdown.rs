@@ -0,0 +1,64 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Ordered shutdown of containers.
+
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+
+/// Trait for facets that need to be shut down when their container is shut
+/// down, for example to stop background tasks or close connections.
+///
+/// For dynamic facets, make this a supertrait of the facet trait.
+#[async_trait]
+pub trait FacetShutdown {
+    /// Shut down this facet.
+    ///
+    /// Facets may be shared between containers, so this may be called more
+    /// than once.
+    async fn shutdown(&self);
+}
+
+/// Trait implemented by containers to shut down their facets.
+#[async_trait]
+pub trait ContainerShutdown {
+    // Futures that shut down each of the facets of this container (and its
+    // nested containers) that are marked with `#[facet(shutdown)]`, along with
+    // the position of the facet in the build order and its name.
+    #[doc(hidden)]
+    fn shutdown_facets(&self) -> Vec<(usize, &'static str, BoxFuture<'_, ()>)>;
+
+    /// Shut down the facets of this container that are marked with
+    /// `#[facet(shutdown)]`.
+    ///
+    /// Facets are shut down one at a time, in the reverse of the order that
+    /// they were built, so that each facet is shut down before the facets
+    /// that it depends on.  Facets shared with nested containers are only
+    /// shut down once.
+    async fn shutdown(&self) {
+        let mut facets = self.shutdown_facets();
+        facets.sort_by_key(|(position, _, _)| Reverse(*position));
+        let mut shut_down = HashSet::new();
+        for (_, name, shutdown) in facets {
+            if shut_down.insert(name) {
+                shutdown.await;
+            }
+        }
+    }
+}
+
+impl<C: ContainerShutdown> ContainerShutdown for Arc<C> {
+    fn shutdown_facets(&self) -> Vec<(usize, &'static str, BoxFuture<'_, ()>)> {
+        C::shutdown_facets(self)
+    }
+}
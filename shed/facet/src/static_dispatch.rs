@@ -0,0 +1,23 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Static dispatch of facets.
+
+// Implemented by factories for each facet they build, giving the concrete
+// type of the facet built by the factory method whose name hashes to `NAME`.
+// Static-dispatch containers are generic over the factory, and store the
+// facets as these types.
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no factory method for a facet of a static-dispatch container",
+    note = "static-dispatch facets must be built by the factory method with the same name as the field"
+)]
+pub trait StaticFacet<const NAME: u128> {
+    type Impl;
+}
@@ -0,0 +1,52 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Facets that can be replaced after the container has been built.
+
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+
+/// A facet that can be replaced while the container holding it is in use.
+///
+/// Containers store `#[facet(swappable)]` fields as a `SwappableFacet`.
+/// Each access loads the current instance of the facet, so callers holding
+/// on to an earlier instance continue to use it until they access the facet
+/// again.
+pub struct SwappableFacet<T: ?Sized> {
+    // `ArcSwap` requires a sized type, so the facet's `Arc` is stored inside
+    // another `Arc`.
+    current: ArcSwap<Arc<T>>,
+}
+
+impl<T: ?Sized> SwappableFacet<T> {
+    #[doc(hidden)]
+    pub fn new(facet: Arc<T>) -> Self {
+        SwappableFacet {
+            current: ArcSwap::from_pointee(facet),
+        }
+    }
+
+    /// Returns the current instance of the facet.
+    pub fn load(&self) -> Arc<T> {
+        Arc::clone(&self.current.load())
+    }
+
+    /// Replaces the facet with a new instance, returning the previous
+    /// instance.
+    pub fn replace(&self, facet: Arc<T>) -> Arc<T> {
+        Arc::clone(&self.current.swap(Arc::new(facet)))
+    }
+}
+
+impl<T: ?Sized> From<Arc<T>> for SwappableFacet<T> {
+    fn from(facet: Arc<T>) -> Self {
+        SwappableFacet::new(facet)
+    }
+}
@@ -0,0 +1,88 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Tracking of which facets of a container are used.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Record of which facets of a container have been accessed.
+///
+/// Containers declared with `#[facet::container(track_usage)]` hold one of
+/// these, and flag each facet field the first time it is accessed through
+/// the container's `FacetRef`, `FacetArc` or keyed facet traits.  Fields
+/// that are accessed directly are not tracked.
+#[derive(Debug)]
+pub struct FacetUsage {
+    facets: Vec<(&'static str, AtomicBool)>,
+}
+
+impl FacetUsage {
+    #[doc(hidden)]
+    pub fn new(names: &[&'static str]) -> Self {
+        FacetUsage {
+            facets: names
+                .iter()
+                .map(|name| (*name, AtomicBool::new(false)))
+                .collect(),
+        }
+    }
+
+    // Record that the facet at `index` has been accessed.  The flag is only
+    // written the first time, so that accessing frequently used facets from
+    // many threads doesn't contend on it.
+    #[doc(hidden)]
+    #[inline]
+    pub fn mark(&self, index: usize) {
+        let used = &self.facets[index].1;
+        if !used.load(Ordering::Relaxed) {
+            used.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// The names of the facet fields that have been accessed.
+    pub fn used(&self) -> Vec<&'static str> {
+        self.filter(true)
+    }
+
+    /// The names of the facet fields that have never been accessed.
+    pub fn unused(&self) -> Vec<&'static str> {
+        self.filter(false)
+    }
+
+    fn filter(&self, used: bool) -> Vec<&'static str> {
+        self.facets
+            .iter()
+            .filter(|(_, flag)| flag.load(Ordering::Relaxed) == used)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+/// Trait implemented by containers that track which of their facets are
+/// used.
+pub trait ContainerUsage {
+    /// The record of which facets of this container have been accessed.
+    ///
+    /// Facets of nested containers are tracked by the nested container, if
+    /// it also tracks usage.
+    fn facet_usage(&self) -> &FacetUsage;
+
+    /// The names of the facet fields of this container that have never been
+    /// accessed, for example to report at shutdown.
+    fn unused_facets(&self) -> Vec<&'static str> {
+        self.facet_usage().unused()
+    }
+}
+
+impl<C: ContainerUsage> ContainerUsage for Arc<C> {
+    fn facet_usage(&self) -> &FacetUsage {
+        C::facet_usage(self)
+    }
+}
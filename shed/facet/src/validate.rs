@@ -0,0 +1,237 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Validation that a factory can build a container, without building it.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::{ContainerFacets, FacetGraph, FacetRegistry};
+
+/// A problem that would prevent a factory from building a container.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ValidationIssue {
+    /// The factory has no method that builds a facet the container needs.
+    MissingFacet {
+        /// The name of the facet.
+        facet: &'static str,
+
+        /// What needs the facet: `Container.field` paths for container
+        /// fields, or the names of the facets that depend on it.
+        needed_by: Vec<String>,
+    },
+
+    /// The implementation that the factory would resolve from the registry
+    /// for a facet is not registered.
+    MissingImplementation {
+        /// The name of the facet.
+        facet: &'static str,
+
+        /// The name of the implementation, or `None` if the factory uses
+        /// the only registered implementation.
+        implementation: Option<String>,
+
+        /// The names of the implementations of the facet that are
+        /// registered.
+        registered: Vec<String>,
+    },
+
+    /// The factory uses the only registered implementation of a facet, but
+    /// more than one is registered.
+    AmbiguousImplementation {
+        /// The name of the facet.
+        facet: &'static str,
+
+        /// The names of the implementations of the facet that are
+        /// registered.
+        registered: Vec<String>,
+    },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::MissingFacet { facet, needed_by } => write!(
+                f,
+                "no factory method builds '{}', needed by {}",
+                facet,
+                needed_by.join(", ")
+            ),
+            ValidationIssue::MissingImplementation {
+                facet,
+                implementation: Some(implementation),
+                registered,
+            } => write!(
+                f,
+                "implementation '{}' of '{}' is not registered (registered: [{}])",
+                implementation,
+                facet,
+                registered.join(", ")
+            ),
+            ValidationIssue::MissingImplementation {
+                facet,
+                implementation: None,
+                ..
+            } => write!(f, "no implementation of '{}' is registered", facet),
+            ValidationIssue::AmbiguousImplementation { facet, registered } => write!(
+                f,
+                "implementation of '{}' is ambiguous (registered: [{}])",
+                facet,
+                registered.join(", ")
+            ),
+        }
+    }
+}
+
+/// A report of whether a factory can build a container.
+///
+/// This is returned by the `validate` method that is generated for each
+/// factory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidationReport {
+    /// The name of the factory.
+    pub factory: &'static str,
+
+    /// The name of the container.
+    pub container: &'static str,
+
+    /// The problems that would prevent the factory building the container.
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Returns true if no problems were found.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "{} can build {}", self.factory, self.container);
+        }
+        write!(f, "{} can't build {}:", self.factory, self.container)?;
+        for issue in &self.issues {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+// Validates a factory against a container.  Used by the `validate` method
+// generated for each factory.
+#[doc(hidden)]
+pub struct Validator {
+    factory: &'static str,
+    container: &'static str,
+    needed: BTreeSet<&'static str>,
+    issues: Vec<ValidationIssue>,
+}
+
+impl Validator {
+    // Find the facets of the factory's graph that are needed to build
+    // container `C`, recording the facets the factory can't build.
+    pub fn new<C: ContainerFacets>(graph: FacetGraph) -> Self {
+        let mut needed = BTreeSet::new();
+        let mut missing = BTreeMap::<&'static str, Vec<String>>::new();
+        let mut pending: Vec<(&'static str, String)> = C::facet_fields()
+            .into_iter()
+            .map(|field| {
+                (
+                    field.facet,
+                    format!("{}.{}", C::container_name(), field.path),
+                )
+            })
+            .collect();
+        while let Some((facet, needed_by)) = pending.pop() {
+            let node = match graph.facet(facet) {
+                Some(node) => node,
+                None => {
+                    missing.entry(facet).or_default().push(needed_by);
+                    continue;
+                }
+            };
+            if !needed.insert(facet) {
+                continue;
+            }
+            let dependencies = node
+                .dependencies
+                .iter()
+                .chain(&node.weak_dependencies)
+                .chain(node.alternates.iter().flat_map(|alt| &alt.dependencies));
+            for dependency in dependencies {
+                pending.push((dependency, facet.to_string()));
+            }
+        }
+        let issues = missing
+            .into_iter()
+            .map(|(facet, mut needed_by)| {
+                needed_by.sort();
+                needed_by.dedup();
+                ValidationIssue::MissingFacet { facet, needed_by }
+            })
+            .collect();
+        Validator {
+            factory: graph.factory,
+            container: C::container_name(),
+            needed,
+            issues,
+        }
+    }
+
+    // Check that the registry has the implementation of facet `T` that its
+    // factory method would resolve, if the container needs the facet.
+    pub fn check_registered<T>(
+        &mut self,
+        registry: &FacetRegistry,
+        facet: &'static str,
+        _facet_type: PhantomData<Arc<T>>,
+        implementation: Option<&str>,
+    ) where
+        T: ?Sized + Send + Sync + 'static,
+    {
+        if !self.needed.contains(facet) {
+            return;
+        }
+        let registered = registry.names::<T>();
+        match implementation {
+            Some(implementation) if !registered.iter().any(|name| name == implementation) => {
+                self.issues.push(ValidationIssue::MissingImplementation {
+                    facet,
+                    implementation: Some(implementation.to_string()),
+                    registered,
+                });
+            }
+            Some(_) => {}
+            None if registered.is_empty() => {
+                self.issues.push(ValidationIssue::MissingImplementation {
+                    facet,
+                    implementation: None,
+                    registered,
+                });
+            }
+            None if registered.len() > 1 => {
+                self.issues
+                    .push(ValidationIssue::AmbiguousImplementation { facet, registered });
+            }
+            None => {}
+        }
+    }
+
+    pub fn finish(self) -> ValidationReport {
+        ValidationReport {
+            factory: self.factory,
+            container: self.container,
+            issues: self.issues,
+        }
+    }
+}
@@ -0,0 +1,68 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Weak references to facets.
+
+use std::sync::{Arc, OnceLock, Weak};
+
+/// A weak reference to a facet.
+///
+/// Factory methods can take a weak reference to another facet in order to
+/// break a dependency cycle.  The weak reference can be passed to the
+/// factory method before the facet it refers to has been built, and can be
+/// upgraded once the build has finished.
+pub struct WeakFacet<T: ?Sized> {
+    slot: Arc<OnceLock<Weak<T>>>,
+}
+
+impl<T: ?Sized> WeakFacet<T> {
+    #[doc(hidden)]
+    pub fn new() -> Self {
+        WeakFacet {
+            slot: Arc::new(OnceLock::new()),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn set(&self, facet: &Arc<T>) {
+        // Each facet is only built once per build, so the slot can only be
+        // set once.
+        let _ = self.slot.set(Arc::downgrade(facet));
+    }
+
+    /// Attempt to upgrade to a strong reference to the facet.
+    ///
+    /// Returns `None` if the facet has not been built yet, was not needed
+    /// by the build, or has since been dropped.
+    pub fn upgrade(&self) -> Option<Arc<T>> {
+        self.slot.get()?.upgrade()
+    }
+}
+
+impl<T: ?Sized> Clone for WeakFacet<T> {
+    fn clone(&self) -> Self {
+        WeakFacet {
+            slot: self.slot.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized> Default for WeakFacet<T> {
+    fn default() -> Self {
+        WeakFacet::new()
+    }
+}
+
+impl<T: ?Sized> From<&Arc<T>> for WeakFacet<T> {
+    fn from(facet: &Arc<T>) -> Self {
+        let weak = WeakFacet::new();
+        weak.set(facet);
+        weak
+    }
+}
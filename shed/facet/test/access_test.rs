@@ -0,0 +1,191 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod db {
+        #[facet::facet]
+        pub trait Db {
+            fn query(&self) -> String;
+        }
+    }
+
+    pub mod cache {
+        #[facet::facet]
+        pub struct Cache {
+            pub size: usize,
+        }
+    }
+
+    pub mod metrics {
+        #[facet::facet]
+        pub struct Metrics;
+    }
+
+    pub mod keys {
+        pub struct Primary;
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_db {
+        use crate::facets::db::Db;
+
+        pub struct SimpleDb;
+
+        impl Db for SimpleDb {
+            fn query(&self) -> String {
+                String::from("result")
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod simple_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::simple_db::SimpleDb;
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::db::ArcDb;
+        use crate::facets::metrics::{ArcMetrics, Metrics};
+
+        pub struct SimpleFactory;
+
+        #[facet::factory()]
+        impl SimpleFactory {
+            fn db(&self) -> ArcDb {
+                Arc::new(SimpleDb)
+            }
+
+            fn cache(&self) -> ArcCache {
+                Arc::new(Cache { size: 10 })
+            }
+
+            fn metrics(&self) -> ArcMetrics {
+                Arc::new(Metrics)
+            }
+
+            #[facet(key = "crate::facets::keys::Primary")]
+            fn primary(&self) -> ArcDb {
+                Arc::new(SimpleDb)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::cache::Cache;
+    use crate::facets::db::Db;
+    use crate::facets::metrics::Metrics;
+
+    #[facet::container]
+    pub struct Storage {
+        #[facet]
+        pub db: dyn Db,
+
+        #[facet]
+        pub cache: Cache,
+
+        #[facet(key = "crate::facets::keys::Primary")]
+        pub primary: dyn Db,
+    }
+
+    #[facet::container]
+    pub struct Inner {
+        #[facet]
+        pub db: dyn Db,
+    }
+
+    #[facet::container]
+    pub struct Service {
+        #[delegate(dyn Db)]
+        pub inner: Inner,
+
+        #[facet]
+        pub metrics: Metrics,
+    }
+
+    #[facet::container]
+    pub struct Unaccessed {
+        #[facet]
+        pub db: dyn Db,
+    }
+}
+
+use std::sync::Arc;
+
+use facet::KeyedFacetRef;
+
+use crate::containers::{Service, Storage, Unaccessed};
+use crate::facets::cache::CacheArc;
+use crate::facets::db::{Db, DbRef};
+use crate::facets::keys::Primary;
+use crate::facets::metrics::MetricsRef;
+use crate::factories::simple_factory::SimpleFactory;
+
+#[test]
+fn records_call_sites() {
+    let storage = Arc::new(SimpleFactory.build::<Storage>().unwrap());
+    let db_line = line!() + 2;
+    for _ in 0..3 {
+        assert_eq!(storage.db().query(), "result");
+    }
+    let cache_line = line!() + 1;
+    assert_eq!(storage.cache_arc().size, 10);
+    let primary_line = line!() + 1;
+    let primary: &(dyn Db + Send + Sync) = KeyedFacetRef::<Primary, _>::keyed_facet_ref(&storage);
+    assert_eq!(primary.query(), "result");
+
+    let report = facet::access_report();
+    assert_eq!(
+        report.accessed_facets("Storage"),
+        vec!["cache", "db", "primary"]
+    );
+    let accesses = report.container("Storage");
+    let sites: Vec<_> = accesses
+        .iter()
+        .map(|access| {
+            assert_eq!(access.location.file(), file!());
+            (access.facet, access.location.line(), access.count)
+        })
+        .collect();
+    assert_eq!(
+        sites,
+        vec![
+            ("cache", cache_line, 1),
+            ("db", db_line, 3),
+            ("primary", primary_line, 1),
+        ]
+    );
+}
+
+#[test]
+fn delegated_accesses_are_recorded_against_delegate() {
+    let service = SimpleFactory.build::<Service>().unwrap();
+    let line = line!() + 1;
+    assert_eq!(service.db().query(), "result");
+    service.metrics();
+
+    let report = facet::access_report();
+    assert_eq!(report.accessed_facets("Service"), vec!["metrics"]);
+    let inner = report.container("Inner");
+    assert_eq!(inner.len(), 1);
+    assert_eq!(inner[0].facet, "db");
+    assert_eq!(inner[0].location.line(), line);
+}
+
+#[test]
+fn direct_field_access_is_not_recorded() {
+    let unaccessed = SimpleFactory.build::<Unaccessed>().unwrap();
+    assert_eq!(unaccessed.db.query(), "result");
+
+    let report = facet::access_report();
+    assert!(report.container("Unaccessed").is_empty());
+    assert!(!report.to_string().contains("Unaccessed."));
+}
@@ -0,0 +1,144 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod db {
+        #[facet::facet]
+        pub trait Db {
+            fn connection_id(&self) -> usize;
+        }
+    }
+
+    pub mod cache {
+        #[facet::facet]
+        pub trait Cache {
+            fn cache_id(&self) -> usize;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod store {
+        use crate::facets::cache::Cache;
+        use crate::facets::db::Db;
+
+        pub struct Store {
+            pub id: usize,
+        }
+
+        impl Db for Store {
+            fn connection_id(&self) -> usize {
+                self.id
+            }
+        }
+
+        impl Cache for Store {
+            fn cache_id(&self) -> usize {
+                self.id
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use crate::facet_impls::store::Store;
+        use crate::facets::cache::Cache;
+        use crate::facets::db::Db;
+
+        #[derive(Default)]
+        pub struct SyncFactory {
+            pub stores_created: AtomicUsize,
+        }
+
+        #[facet::factory()]
+        impl SyncFactory {
+            #[facet(also = Db, also = Cache)]
+            fn store(&self) -> Arc<Store> {
+                let id = self.stores_created.fetch_add(1, Ordering::SeqCst);
+                Arc::new(Store { id })
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use anyhow::Error;
+
+        use crate::facet_impls::store::Store;
+        use crate::facets::cache::Cache;
+        use crate::facets::db::Db;
+
+        #[derive(Default)]
+        pub struct AsyncFactory {
+            pub stores_created: AtomicUsize,
+        }
+
+        #[facet::factory()]
+        impl AsyncFactory {
+            #[facet(also = Db, also = Cache)]
+            async fn store(&self) -> Result<Arc<Store>, Error> {
+                let id = self.stores_created.fetch_add(1, Ordering::SeqCst);
+                Ok(Arc::new(Store { id }))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::cache::Cache;
+    use crate::facets::db::Db;
+
+    #[facet::container]
+    pub struct Shared {
+        #[facet]
+        pub db: dyn Db,
+
+        #[facet]
+        pub cache: dyn Cache,
+    }
+}
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use containers::Shared;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+fn same_instance(container: &Shared) -> bool {
+    Arc::as_ptr(&container.db) as *const u8 == Arc::as_ptr(&container.cache) as *const u8
+}
+
+#[test]
+fn sync_also() {
+    let factory = SyncFactory::default();
+    let container = factory.build::<Shared>().unwrap();
+    assert_eq!(factory.stores_created.load(Ordering::SeqCst), 1);
+    assert_eq!(container.db.connection_id(), container.cache.cache_id());
+    assert!(same_instance(&container));
+
+    // Each build still gets its own instance.
+    let other = factory.build::<Shared>().unwrap();
+    assert_eq!(factory.stores_created.load(Ordering::SeqCst), 2);
+    assert_eq!(other.db.connection_id(), 1);
+}
+
+#[tokio::test]
+async fn async_also() {
+    let factory = AsyncFactory::default();
+    let container = factory.build::<Shared>().await.unwrap();
+    assert_eq!(factory.stores_created.load(Ordering::SeqCst), 1);
+    assert!(same_instance(&container));
+}
@@ -0,0 +1,119 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod store {
+        #[facet::facet]
+        #[async_trait::async_trait]
+        pub trait Store {
+            async fn count(&self) -> usize;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod vec_store {
+        use async_trait::async_trait;
+
+        use crate::facets::store::Store;
+
+        pub struct VecStore {
+            pub items: Vec<String>,
+        }
+
+        #[async_trait]
+        impl Store for VecStore {
+            async fn count(&self) -> usize {
+                tokio::task::yield_now().await;
+                self.items.len()
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod async_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::vec_store::VecStore;
+        use crate::facets::store::ArcStore;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(items: Vec<String>)]
+        impl AsyncFactory {
+            async fn store(&self, items: &[String]) -> ArcStore {
+                Arc::new(VecStore {
+                    items: items.to_vec(),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use std::sync::Arc;
+
+    use crate::facets::store::Store;
+
+    #[facet::container]
+    pub struct Inner {
+        #[facet]
+        pub store: dyn Store,
+
+        #[init(async { store.count().await })]
+        pub count: usize,
+
+        #[init(count * 2)]
+        pub double: usize,
+    }
+
+    #[facet::container]
+    pub struct Outer {
+        #[delegate(dyn Store)]
+        pub inner: Arc<Inner>,
+
+        #[init(async { inner.count + inner.store.count().await })]
+        pub total: usize,
+    }
+}
+
+use containers::{Inner, Outer};
+use factories::async_factory::AsyncFactory;
+
+fn items() -> Vec<String> {
+    vec![String::from("a"), String::from("b"), String::from("c")]
+}
+
+#[tokio::test]
+async fn async_init() {
+    let container = AsyncFactory.build::<Inner>(items()).await.unwrap();
+    assert_eq!(container.count, 3);
+    assert_eq!(container.double, 6);
+}
+
+#[tokio::test]
+async fn nested_async_init() {
+    let container = AsyncFactory.build::<Outer>(items()).await.unwrap();
+    assert_eq!(container.inner.count, 3);
+    assert_eq!(container.total, 6);
+}
+
+#[tokio::test]
+async fn rebuild_async_init() {
+    let container = AsyncFactory
+        .build_rebuildable::<Inner>(items())
+        .await
+        .unwrap();
+    let rebuilt = AsyncFactory
+        .rebuild(&container, vec![String::from("d")])
+        .await
+        .unwrap();
+    assert_eq!(rebuilt.count, 1);
+}
@@ -0,0 +1,178 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod config {
+        #[facet::facet]
+        pub struct Config {
+            pub prefix: String,
+        }
+    }
+
+    pub mod session {
+        #[facet::facet(boxed)]
+        pub trait Session {
+            fn describe(&self) -> String;
+        }
+    }
+
+    pub mod scratch {
+        #[facet::facet(boxed)]
+        pub struct Scratch {
+            pub buffer: Vec<u8>,
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_session {
+        use crate::facets::config::ArcConfig;
+        use crate::facets::session::Session;
+
+        pub struct SimpleSession {
+            pub config: ArcConfig,
+            pub name: String,
+        }
+
+        impl Session for SimpleSession {
+            fn describe(&self) -> String {
+                format!("{}{}", self.config.prefix, self.name)
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod session_factory {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use anyhow::{anyhow, Error};
+
+        use crate::facet_impls::simple_session::SimpleSession;
+        use crate::facets::config::{ArcConfig, Config};
+        use crate::facets::scratch::Scratch;
+        use crate::facets::session::BoxSession;
+
+        #[derive(Default)]
+        pub struct SessionFactory {
+            pub configs_built: AtomicUsize,
+            pub sessions_built: AtomicUsize,
+        }
+
+        #[facet::factory(name: String, size: usize)]
+        impl SessionFactory {
+            fn config(&self) -> ArcConfig {
+                self.configs_built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(Config {
+                    prefix: String::from("session:"),
+                })
+            }
+
+            #[facet(boxed)]
+            fn session(&self, name: &str, config: &ArcConfig) -> Result<BoxSession, Error> {
+                if name.is_empty() {
+                    return Err(anyhow!("sessions must have a name"));
+                }
+                self.sessions_built.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(SimpleSession {
+                    config: config.clone(),
+                    name: name.to_string(),
+                }))
+            }
+
+            fn scratch(&self, size: &usize) -> Box<Scratch> {
+                Box::new(Scratch {
+                    buffer: vec![0; *size],
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::config::Config;
+    use crate::facets::scratch::Scratch;
+    use crate::facets::session::Session;
+
+    #[facet::container]
+    pub struct SessionContainer {
+        #[facet]
+        pub config: Config,
+
+        #[facet(boxed)]
+        pub session: dyn Session,
+
+        #[facet(boxed)]
+        pub scratch: Scratch,
+    }
+}
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use facet::FactoryError;
+
+use crate::containers::SessionContainer;
+use crate::facets::scratch::ScratchRef;
+use crate::facets::session::SessionRef;
+use crate::factories::session_factory::SessionFactory;
+
+#[test]
+fn build_boxed_facets() {
+    let factory = SessionFactory::default();
+    let container = factory
+        .build::<SessionContainer>(String::from("main"), 16)
+        .unwrap();
+
+    assert_eq!(container.session().describe(), "session:main");
+    assert_eq!(container.scratch().buffer.len(), 16);
+    assert_eq!(container.session.describe(), "session:main");
+}
+
+#[test]
+fn boxed_facets_are_owned() {
+    let factory = SessionFactory::default();
+    let mut container = factory
+        .build::<SessionContainer>(String::from("main"), 16)
+        .unwrap();
+    container.scratch.buffer.push(1);
+
+    assert_eq!(container.scratch.buffer.len(), 17);
+    assert_eq!(factory.configs_built.load(Ordering::SeqCst), 1);
+    assert_eq!(factory.sessions_built.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn boxed_facets_depend_on_shared_facets() {
+    let factory = SessionFactory::default();
+    let first = factory
+        .build::<SessionContainer>(String::from("first"), 4)
+        .unwrap();
+    let second = factory
+        .build_with::<SessionContainer>(String::from("second"), 4)
+        .facet(first.config.clone())
+        .finish()
+        .unwrap();
+
+    assert!(Arc::ptr_eq(&first.config, &second.config));
+    assert_eq!(factory.configs_built.load(Ordering::SeqCst), 1);
+    assert_eq!(second.session.describe(), "session:second");
+}
+
+#[test]
+fn boxed_facet_fails() {
+    let factory = SessionFactory::default();
+    let result = factory.build::<SessionContainer>(String::new(), 4);
+
+    match result {
+        Err(FactoryError::FacetBuildFailed { name, .. }) => assert_eq!(name, "session"),
+        _ => panic!("expected the session to fail to build"),
+    }
+}
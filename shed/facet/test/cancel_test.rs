@@ -0,0 +1,184 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod drop_log {
+        use std::sync::{Arc, Mutex};
+
+        /// Records the names of facets as they are dropped.
+        pub type DropLog = Arc<Mutex<Vec<&'static str>>>;
+    }
+
+    pub mod db {
+        use crate::facets::drop_log::DropLog;
+
+        #[facet::facet]
+        pub struct Db {
+            pub log: DropLog,
+        }
+
+        impl Drop for Db {
+            fn drop(&mut self) {
+                self.log.lock().unwrap().push("db");
+            }
+        }
+    }
+
+    pub mod cache {
+        use crate::facets::db::ArcDb;
+        use crate::facets::drop_log::DropLog;
+
+        #[facet::facet]
+        pub struct Cache {
+            pub log: DropLog,
+            pub db: ArcDb,
+        }
+
+        impl Drop for Cache {
+            fn drop(&mut self) {
+                self.log.lock().unwrap().push("cache");
+            }
+        }
+    }
+
+    pub mod service {
+        use crate::facets::cache::ArcCache;
+        use crate::facets::drop_log::DropLog;
+
+        #[facet::facet]
+        pub struct Service {
+            pub log: DropLog,
+            pub cache: ArcCache,
+        }
+
+        impl Drop for Service {
+            fn drop(&mut self) {
+                self.log.lock().unwrap().push("service");
+            }
+        }
+    }
+
+    pub mod remote {
+        #[facet::facet]
+        pub struct Remote {
+            pub connected: bool,
+        }
+    }
+}
+
+pub mod factories {
+    pub mod slow_factory {
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::db::{ArcDb, Db};
+        use crate::facets::drop_log::DropLog;
+        use crate::facets::remote::{ArcRemote, Remote};
+        use crate::facets::service::{ArcService, Service};
+
+        /// Factory that builds its local facets quickly, but takes a minute
+        /// to connect to its remote.
+        #[derive(Default)]
+        pub struct SlowFactory {
+            pub log: DropLog,
+        }
+
+        #[facet::factory()]
+        impl SlowFactory {
+            async fn db(&self) -> ArcDb {
+                Arc::new(Db {
+                    log: self.log.clone(),
+                })
+            }
+
+            async fn cache(&self, db: &ArcDb) -> ArcCache {
+                Arc::new(Cache {
+                    log: self.log.clone(),
+                    db: db.clone(),
+                })
+            }
+
+            async fn service(&self, cache: &ArcCache) -> ArcService {
+                Arc::new(Service {
+                    log: self.log.clone(),
+                    cache: cache.clone(),
+                })
+            }
+
+            async fn remote(&self) -> ArcRemote {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Arc::new(Remote { connected: true })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::cache::Cache;
+    use crate::facets::db::Db;
+    use crate::facets::remote::Remote;
+    use crate::facets::service::Service;
+
+    #[facet::container]
+    pub struct Services {
+        #[facet]
+        pub db: Db,
+
+        #[facet]
+        pub cache: Cache,
+
+        #[facet]
+        pub service: Service,
+
+        #[facet]
+        pub remote: Remote,
+    }
+}
+
+use std::time::Duration;
+
+use facet::FactoryError;
+
+use crate::containers::Services;
+use crate::factories::slow_factory::SlowFactory;
+
+#[tokio::test(start_paused = true)]
+async fn cancel_build() {
+    let factory = SlowFactory::default();
+    let result = factory
+        .build_cancellable::<Services>(tokio::time::sleep(Duration::from_secs(1)))
+        .await;
+
+    assert!(matches!(result, Err(FactoryError::BuildCancelled)));
+    assert_eq!(*factory.log.lock().unwrap(), vec!["service", "cache", "db"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn build_not_cancelled() {
+    let factory = SlowFactory::default();
+    let services = factory
+        .build_cancellable::<Services>(futures::future::pending())
+        .await
+        .unwrap();
+
+    assert!(services.remote.connected);
+    assert!(factory.log.lock().unwrap().is_empty());
+    drop(services);
+    assert_eq!(factory.log.lock().unwrap().len(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn drop_build() {
+    let factory = SlowFactory::default();
+    let result = tokio::time::timeout(Duration::from_secs(1), factory.build::<Services>()).await;
+
+    assert!(result.is_err());
+    assert_eq!(*factory.log.lock().unwrap(), vec!["service", "cache", "db"]);
+}
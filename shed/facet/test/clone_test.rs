@@ -0,0 +1,138 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod counter {
+        #[facet::facet(clone)]
+        pub trait Counter {
+            fn value(&self) -> u32;
+
+            fn increment(&mut self);
+        }
+    }
+
+    pub mod shape {
+        #[facet::facet(clone, local)]
+        pub trait Shape {
+            fn sides(&self) -> u32;
+
+            fn add_side(&mut self);
+        }
+    }
+}
+
+pub mod facet_impls {
+    use crate::facets::counter::Counter;
+    use crate::facets::shape::Shape;
+
+    #[derive(Clone)]
+    pub struct SimpleCounter(pub u32);
+
+    impl Counter for SimpleCounter {
+        fn value(&self) -> u32 {
+            self.0
+        }
+
+        fn increment(&mut self) {
+            self.0 += 1;
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct Polygon(pub u32);
+
+    impl Shape for Polygon {
+        fn sides(&self) -> u32 {
+            self.0
+        }
+
+        fn add_side(&mut self) {
+            self.0 += 1;
+        }
+    }
+}
+
+pub mod factories {
+    use crate::facet_impls::{Polygon, SimpleCounter};
+    use crate::facets::counter::ArcCounter;
+    use crate::facets::shape::RcShape;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    pub struct Factory;
+
+    #[facet::factory(initial: u32)]
+    impl Factory {
+        fn counter(&self, initial: &u32) -> ArcCounter {
+            Arc::new(SimpleCounter(*initial))
+        }
+    }
+
+    pub struct LocalFactory;
+
+    #[facet::factory()]
+    impl LocalFactory {
+        fn shape(&self) -> RcShape {
+            Rc::new(Polygon(3))
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::counter::Counter;
+    use crate::facets::shape::Shape;
+
+    #[facet::container]
+    pub struct CounterContainer {
+        #[facet]
+        pub counter: dyn Counter,
+    }
+
+    #[facet::container(local)]
+    pub struct ShapeContainer {
+        #[facet]
+        pub shape: dyn Shape,
+    }
+}
+
+use containers::{CounterContainer, ShapeContainer};
+use facets::counter::CounterRef;
+use facets::shape::ShapeRef;
+use factories::{Factory, LocalFactory};
+
+#[test]
+fn clone_from_container() {
+    let container = Factory.build::<CounterContainer>(5).unwrap();
+
+    let mut counter = container.clone_counter();
+    counter.increment();
+    assert_eq!(counter.value(), 6);
+    assert_eq!(container.counter().value(), 5);
+}
+
+#[test]
+fn clone_box() {
+    let container = Factory.build::<CounterContainer>(5).unwrap();
+
+    let mut counter = container.clone_counter();
+    let copy = counter.clone();
+    counter.increment();
+    assert_eq!(counter.value(), 6);
+    assert_eq!(copy.value(), 5);
+}
+
+#[test]
+fn clone_local() {
+    let container = LocalFactory.build::<ShapeContainer>().unwrap();
+
+    let mut shape = container.clone_shape();
+    shape.add_side();
+    assert_eq!(shape.sides(), 4);
+    assert_eq!(container.shape().sides(), 3);
+}
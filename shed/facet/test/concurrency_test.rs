@@ -0,0 +1,135 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod db {
+        #[facet::facet]
+        pub struct Db;
+    }
+
+    pub mod cache {
+        #[facet::facet]
+        pub struct Cache;
+    }
+
+    pub mod queue {
+        #[facet::facet]
+        pub struct Queue;
+    }
+
+    pub mod blobs {
+        #[facet::facet]
+        pub struct Blobs;
+    }
+
+    pub mod service {
+        #[facet::facet]
+        pub struct Service;
+    }
+}
+
+pub mod factories {
+    pub mod conn_factory {
+        use crate::facets::blobs::{ArcBlobs, Blobs};
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::db::{ArcDb, Db};
+        use crate::facets::queue::{ArcQueue, Queue};
+        use crate::facets::service::{ArcService, Service};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        #[derive(Default)]
+        pub struct ConnFactory {
+            pub current: AtomicUsize,
+            pub max: AtomicUsize,
+        }
+
+        impl ConnFactory {
+            async fn connect(&self) {
+                let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                self.current.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        #[facet::factory()]
+        impl ConnFactory {
+            async fn db(&self) -> ArcDb {
+                self.connect().await;
+                Arc::new(Db)
+            }
+
+            async fn cache(&self) -> ArcCache {
+                self.connect().await;
+                Arc::new(Cache)
+            }
+
+            async fn queue(&self) -> ArcQueue {
+                self.connect().await;
+                Arc::new(Queue)
+            }
+
+            async fn blobs(&self) -> ArcBlobs {
+                self.connect().await;
+                Arc::new(Blobs)
+            }
+
+            async fn service(
+                &self,
+                _db: &ArcDb,
+                _cache: &ArcCache,
+                _queue: &ArcQueue,
+                _blobs: &ArcBlobs,
+            ) -> ArcService {
+                Arc::new(Service)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::service::Service;
+
+    #[facet::container]
+    pub struct Connected {
+        #[facet]
+        pub service: Service,
+    }
+}
+
+use std::sync::atomic::Ordering;
+
+use containers::Connected;
+use factories::conn_factory::ConnFactory;
+
+#[tokio::test(start_paused = true)]
+async fn unlimited() {
+    let factory = ConnFactory::default();
+    factory.build::<Connected>().await.unwrap();
+    assert_eq!(factory.max.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test(start_paused = true)]
+async fn limited() {
+    let factory = ConnFactory::default();
+    factory
+        .build_with_concurrency::<Connected>(2)
+        .await
+        .unwrap();
+    assert_eq!(factory.max.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+#[should_panic(expected = "facet build concurrency must be at least 1")]
+async fn zero_concurrency() {
+    let factory = ConnFactory::default();
+    let _ = factory.build_with_concurrency::<Connected>(0).await;
+}
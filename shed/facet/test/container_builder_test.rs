@@ -0,0 +1,138 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod settings {
+        #[facet::facet]
+        pub struct Settings {
+            pub name: String,
+            pub port: u16,
+            pub verbose: bool,
+            pub retries: u32,
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use std::sync::Arc;
+
+        use crate::facets::settings::{ArcSettings, Settings};
+
+        pub struct SyncFactory;
+
+        #[facet::factory(
+            name: String,
+            #[default(8080)] port: u16,
+            #[default] verbose: bool,
+            retries: u32,
+        )]
+        impl SyncFactory {
+            fn settings(
+                &self,
+                name: &str,
+                port: &u16,
+                verbose: &bool,
+                retries: &u32,
+            ) -> ArcSettings {
+                Arc::new(Settings {
+                    name: name.to_string(),
+                    port: *port,
+                    verbose: *verbose,
+                    retries: *retries,
+                })
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use std::sync::Arc;
+
+        use crate::facets::settings::{ArcSettings, Settings};
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(#[default(String::from("default"))] name: String, port: u16)]
+        impl AsyncFactory {
+            async fn settings(&self, name: &str, port: &u16) -> ArcSettings {
+                Arc::new(Settings {
+                    name: name.to_string(),
+                    port: *port,
+                    verbose: false,
+                    retries: 0,
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::settings::Settings;
+
+    #[facet::container]
+    pub struct SettingsContainer {
+        #[facet]
+        pub settings: Settings,
+    }
+}
+
+use containers::SettingsContainer;
+use facet::FactoryError;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+#[test]
+fn sync_container_builder() {
+    let container = SyncFactory
+        .builder::<SettingsContainer>()
+        .retries(3)
+        .name(String::from("sync"))
+        .build()
+        .unwrap();
+    assert_eq!(container.settings.name, "sync");
+    assert_eq!(container.settings.port, 8080);
+    assert!(!container.settings.verbose);
+    assert_eq!(container.settings.retries, 3);
+
+    let container = SyncFactory
+        .builder::<SettingsContainer>()
+        .name(String::from("sync"))
+        .port(1234)
+        .verbose(true)
+        .retries(0)
+        .build()
+        .unwrap();
+    assert_eq!(container.settings.port, 1234);
+    assert!(container.settings.verbose);
+}
+
+#[test]
+fn missing_parameter() {
+    match SyncFactory
+        .builder::<SettingsContainer>()
+        .name(String::from("sync"))
+        .build()
+    {
+        Err(FactoryError::MissingParameter { name }) => assert_eq!(name, "retries"),
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("container should not have built"),
+    }
+}
+
+#[tokio::test]
+async fn async_container_builder() {
+    let container = AsyncFactory
+        .builder::<SettingsContainer>()
+        .port(80)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(container.settings.name, "default");
+    assert_eq!(container.settings.port, 80);
+}
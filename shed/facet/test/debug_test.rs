@@ -0,0 +1,102 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod db {
+        #[facet::facet]
+        pub trait Db {}
+    }
+
+    pub mod cache {
+        #[facet::facet]
+        pub struct Cache;
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::db::{ArcDb, Db};
+        use std::sync::Arc;
+
+        struct NoDebugDb;
+
+        impl Db for NoDebugDb {}
+
+        pub struct SyncFactory;
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn db(&self) -> ArcDb {
+                Arc::new(NoDebugDb)
+            }
+
+            fn cache(&self) -> ArcCache {
+                Arc::new(Cache)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use std::sync::Arc;
+
+    use crate::facets::cache::Cache;
+    use crate::facets::db::Db;
+
+    #[facet::container(debug)]
+    pub struct Inner {
+        #[facet(name = "db")]
+        pub database: dyn Db,
+    }
+
+    #[facet::container(debug)]
+    pub struct Outer<T: Default + Send + Sync + 'static> {
+        #[init(String::from("outer"))]
+        pub name: String,
+
+        #[facet]
+        pub cache: Cache,
+
+        #[delegate(dyn Db)]
+        pub inner: Arc<Inner>,
+
+        #[init(T::default())]
+        pub extra: T,
+    }
+}
+
+use containers::Outer;
+use factories::sync_factory::SyncFactory;
+
+#[test]
+fn debug_container() {
+    let container = SyncFactory.build::<Outer<Vec<u32>>>().unwrap();
+    assert_eq!(
+        format!("{:?}", container),
+        concat!(
+            r#"Outer { name: "outer", cache: <cache>, "#,
+            "inner: Inner { database: <db> }, extra: [] }",
+        ),
+    );
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Holder {
+    container: Outer<u32>,
+}
+
+#[test]
+fn derive_debug_with_container() {
+    let holder = Holder {
+        container: SyncFactory.build().unwrap(),
+    };
+    assert!(format!("{:?}", holder).starts_with("Holder { container: Outer {"));
+}
@@ -0,0 +1,199 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod logger {
+        use crate::facet_impls::noop_logger::NoopLogger;
+
+        #[facet::facet(default = NoopLogger)]
+        pub trait Logger {
+            fn log(&self, message: &str) -> bool;
+        }
+    }
+
+    pub mod limits {
+        #[facet::facet(default = Self)]
+        #[derive(Default)]
+        pub struct Limits {
+            pub max_requests: u32,
+        }
+    }
+
+    pub mod service {
+        #[facet::facet]
+        pub struct Service {
+            pub logged: bool,
+            pub max_requests: u32,
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod noop_logger {
+        use crate::facets::logger::Logger;
+
+        #[derive(Default)]
+        pub struct NoopLogger;
+
+        impl Logger for NoopLogger {
+            fn log(&self, _message: &str) -> bool {
+                false
+            }
+        }
+    }
+
+    pub mod print_logger {
+        use crate::facets::logger::Logger;
+
+        pub struct PrintLogger;
+
+        impl Logger for PrintLogger {
+            fn log(&self, message: &str) -> bool {
+                println!("{}", message);
+                true
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod prod_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::print_logger::PrintLogger;
+        use crate::facets::limits::{ArcLimits, Limits};
+        use crate::facets::logger::ArcLogger;
+        use crate::facets::service::{ArcService, Service};
+
+        pub struct ProdFactory;
+
+        #[facet::factory()]
+        impl ProdFactory {
+            fn logger(&self) -> ArcLogger {
+                Arc::new(PrintLogger)
+            }
+
+            fn limits(&self) -> ArcLimits {
+                Arc::new(Limits { max_requests: 100 })
+            }
+
+            fn service(&self, logger: &ArcLogger, limits: &ArcLimits) -> ArcService {
+                Arc::new(Service {
+                    logged: logger.log("started"),
+                    max_requests: limits.max_requests,
+                })
+            }
+        }
+    }
+
+    pub mod test_factory {
+        use std::sync::Arc;
+
+        use crate::facets::limits::ArcLimits;
+        use crate::facets::logger::ArcLogger;
+        use crate::facets::service::{ArcService, Service};
+
+        pub struct TestFactory;
+
+        #[facet::factory(defaults(crate::facets::logger::Logger, crate::facets::limits::Limits))]
+        impl TestFactory {
+            fn service(&self, logger: &ArcLogger, limits: &ArcLimits) -> ArcService {
+                Arc::new(Service {
+                    logged: logger.log("started"),
+                    max_requests: limits.max_requests,
+                })
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use std::sync::Arc;
+
+        use crate::facets::logger::ArcLogger;
+        use crate::facets::service::{ArcService, Service};
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(defaults(crate::facets::logger::Logger))]
+        impl AsyncFactory {
+            async fn service(&self, logger: &ArcLogger) -> ArcService {
+                Arc::new(Service {
+                    logged: logger.log("started"),
+                    max_requests: 1,
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::limits::Limits;
+    use crate::facets::logger::Logger;
+    use crate::facets::service::Service;
+
+    #[facet::container]
+    pub struct ServiceContainer {
+        #[facet]
+        pub logger: dyn Logger,
+
+        #[facet]
+        pub limits: Limits,
+
+        #[facet]
+        pub service: Service,
+    }
+
+    #[facet::container]
+    pub struct LoggedContainer {
+        #[facet]
+        pub logger: dyn Logger,
+
+        #[facet]
+        pub service: Service,
+    }
+}
+
+use containers::{LoggedContainer, ServiceContainer};
+use facet::FacetDefault;
+use facets::limits::Limits;
+use facets::logger::Logger;
+use factories::async_factory::AsyncFactory;
+use factories::prod_factory::ProdFactory;
+use factories::test_factory::TestFactory;
+
+#[test]
+fn defined_facets() {
+    let container = ProdFactory.build::<ServiceContainer>().unwrap();
+    assert!(container.logger.log("message"));
+    assert!(container.service.logged);
+    assert_eq!(container.service.max_requests, 100);
+}
+
+#[test]
+fn default_facets() {
+    let container = TestFactory.build::<ServiceContainer>().unwrap();
+    assert!(!container.logger.log("message"));
+    assert!(!container.service.logged);
+    assert_eq!(container.limits.max_requests, 0);
+    assert_eq!(container.service.max_requests, 0);
+}
+
+#[tokio::test]
+async fn async_default_facets() {
+    let container = AsyncFactory.build::<LoggedContainer>().await.unwrap();
+    assert!(!container.logger.log("message"));
+    assert!(!container.service.logged);
+}
+
+#[test]
+fn default_facet() {
+    let logger = <dyn Logger + Send + Sync>::default_facet();
+    assert!(!logger.log("message"));
+    assert_eq!(Limits::default_facet().max_requests, 0);
+}
@@ -68,6 +68,16 @@ pub mod containers {
         #[delegate(dyn One)]
         basic: Arc<Basic>,
     }
+
+    #[facet::delegate]
+    pub struct Wrapper(pub Basic);
+
+    #[facet::delegate]
+    pub struct NamedWrapper<T> {
+        #[delegate]
+        pub basic: Arc<Basic>,
+        pub extra: T,
+    }
 }
 
 #[test]
@@ -85,3 +95,25 @@ fn main() {
     let wrapped_delegator = factory.build::<containers::WrappedDelegator>().unwrap();
     assert_eq!(wrapped_delegator.one().get(), 1);
 }
+
+fn get_one(container: impl facets::one::OneArc) -> u32 {
+    container.one_arc().get()
+}
+
+#[test]
+fn wrappers() {
+    let factory = factories::simple_factory::SimpleFactory;
+
+    use crate::facets::one::{OneArc, OneRef};
+    let wrapper = containers::Wrapper(factory.build::<containers::Basic>().unwrap());
+    assert_eq!(wrapper.one().get(), 1);
+    assert_eq!(get_one(&wrapper), 1);
+
+    let named_wrapper = containers::NamedWrapper {
+        basic: factory.build().unwrap(),
+        extra: "extra",
+    };
+    assert_eq!(named_wrapper.one().get(), 1);
+    assert_eq!(named_wrapper.one_arc().get(), 1);
+    assert_eq!(named_wrapper.extra, "extra");
+}
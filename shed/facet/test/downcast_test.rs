@@ -0,0 +1,143 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod db {
+        #[facet::facet(downcast)]
+        pub trait Db {
+            fn get(&self, key: &str) -> Option<String>;
+        }
+    }
+
+    pub mod cache {
+        #[facet::facet(downcast)]
+        pub trait Cache {}
+    }
+
+    pub mod config {
+        #[facet::facet]
+        pub struct Config {
+            pub name: String,
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod mem_db {
+        use crate::facets::db::Db;
+
+        pub struct MemDb {
+            pub entries: Vec<(String, String)>,
+        }
+
+        impl Db for MemDb {
+            fn get(&self, key: &str) -> Option<String> {
+                self.entries
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.clone())
+            }
+        }
+    }
+
+    pub mod null_cache {
+        use crate::facets::cache::Cache;
+
+        pub struct NullCache;
+
+        impl Cache for NullCache {}
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::mem_db::MemDb;
+        use crate::facet_impls::null_cache::NullCache;
+        use crate::facets::cache::ArcCache;
+        use crate::facets::config::{ArcConfig, Config};
+        use crate::facets::db::ArcDb;
+
+        pub struct SyncFactory;
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn db(&self) -> ArcDb {
+                Arc::new(MemDb {
+                    entries: vec![(String::from("key"), String::from("value"))],
+                })
+            }
+
+            fn cache(&self) -> ArcCache {
+                Arc::new(NullCache)
+            }
+
+            fn config(&self) -> ArcConfig {
+                Arc::new(Config {
+                    name: String::from("config"),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::cache::Cache;
+    use crate::facets::config::Config;
+    use crate::facets::db::Db;
+
+    #[facet::container]
+    pub struct Downcastable {
+        #[facet(downcast)]
+        pub db: dyn Db,
+
+        #[facet]
+        pub cache: dyn Cache,
+
+        #[facet(downcast)]
+        pub config: Config,
+    }
+}
+
+use containers::Downcastable;
+use facet_impls::mem_db::MemDb;
+use facet_impls::null_cache::NullCache;
+use facets::cache::CacheRef;
+use facets::config::Config;
+use facets::db::DbRef;
+use factories::sync_factory::SyncFactory;
+
+#[test]
+fn downcast_facet() {
+    let container = SyncFactory.build::<Downcastable>().unwrap();
+
+    let db = container.downcast_facet::<MemDb>().unwrap();
+    assert_eq!(db.entries.len(), 1);
+    assert_eq!(container.db().get("key").as_deref(), Some("value"));
+
+    let config = container.downcast_facet::<Config>().unwrap();
+    assert_eq!(config.name, "config");
+
+    // The cache field is not downcastable, even though its trait is.
+    assert!(container.downcast_facet::<NullCache>().is_none());
+    assert!(container.downcast_facet::<String>().is_none());
+}
+
+#[test]
+fn as_any() {
+    let container = SyncFactory.build::<Downcastable>().unwrap();
+
+    assert!(container.cache().as_any().is::<NullCache>());
+    assert!(container
+        .db()
+        .as_any()
+        .downcast_ref::<NullCache>()
+        .is_none());
+}
@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod database {
+        #[facet::facet]
+        pub trait Database {
+            fn name(&self) -> &str;
+        }
+    }
+
+    pub mod config {
+        #[facet::facet]
+        pub struct Config {
+            pub limit: u32,
+        }
+    }
+
+    pub mod replica {
+        pub struct Secondary;
+    }
+}
+
+pub mod facet_impls {
+    use crate::facets::database::Database;
+
+    pub struct NamedDatabase(pub &'static str);
+
+    impl Database for NamedDatabase {
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+}
+
+pub mod factories {
+    use crate::facet_impls::NamedDatabase;
+    use crate::facets::config::{ArcConfig, Config};
+    use crate::facets::database::ArcDatabase;
+    use std::sync::Arc;
+
+    pub struct Factory;
+
+    #[facet::factory(limit: u32)]
+    impl Factory {
+        fn config(&self, limit: &u32) -> ArcConfig {
+            Arc::new(Config { limit: *limit })
+        }
+
+        fn database(&self) -> ArcDatabase {
+            Arc::new(NamedDatabase("primary"))
+        }
+
+        #[facet(key = "crate::facets::replica::Secondary")]
+        fn secondary_database(&self) -> ArcDatabase {
+            Arc::new(NamedDatabase("secondary"))
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::config::Config;
+    use crate::facets::database::Database;
+
+    #[facet::container(downgrade)]
+    pub struct Service {
+        #[facet]
+        pub config: Config,
+
+        #[facet]
+        pub database: dyn Database,
+
+        #[facet(key = "crate::facets::replica::Secondary")]
+        pub secondary: dyn Database,
+    }
+}
+
+use std::sync::Arc;
+
+use containers::Service;
+use factories::Factory;
+
+#[test]
+fn upgrade_while_alive() {
+    let service = Factory.build::<Service>(10).unwrap();
+    let weak = service.downgrade();
+
+    let upgraded = weak.upgrade().unwrap();
+    assert!(Arc::ptr_eq(&upgraded.config, &service.config));
+    assert!(Arc::ptr_eq(&upgraded.database, &service.database));
+    assert!(Arc::ptr_eq(&upgraded.secondary, &service.secondary));
+    assert_eq!(upgraded.config.limit, 10);
+    assert_eq!(upgraded.secondary.name(), "secondary");
+}
+
+#[test]
+fn handle_does_not_keep_facets_alive() {
+    let service = Factory.build::<Service>(10).unwrap();
+    let weak = service.downgrade();
+    let cloned = weak.clone();
+    drop(service);
+
+    assert!(weak.upgrade().is_none());
+    assert!(cloned.upgrade().is_none());
+}
+
+#[test]
+fn upgrade_fails_if_any_facet_dropped() {
+    let service = Factory.build::<Service>(10).unwrap();
+    let weak = service.downgrade();
+    let config = service.config.clone();
+    drop(service);
+
+    assert!(weak.upgrade().is_none());
+    assert_eq!(config.limit, 10);
+}
@@ -0,0 +1,161 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod sql {
+        #[facet::facet]
+        pub struct Sql;
+    }
+
+    pub mod blobstore {
+        #[facet::facet]
+        pub struct Blobstore;
+    }
+
+    pub mod repo {
+        #[facet::facet]
+        pub struct Repo;
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facets::blobstore::{ArcBlobstore, Blobstore};
+        use crate::facets::repo::{ArcRepo, Repo};
+        use crate::facets::sql::{ArcSql, Sql};
+        use anyhow::{anyhow, Error};
+        use std::sync::Arc;
+
+        pub struct SyncFactory;
+
+        #[facet::factory(connect: bool)]
+        impl SyncFactory {
+            fn sql(&self, connect: &bool) -> Result<ArcSql, Error> {
+                if *connect {
+                    Ok(Arc::new(Sql))
+                } else {
+                    Err(anyhow!("connection refused"))
+                }
+            }
+
+            fn blobstore(&self, _sql: &ArcSql) -> ArcBlobstore {
+                Arc::new(Blobstore)
+            }
+
+            fn repo(&self, _blobstore: &ArcBlobstore) -> ArcRepo {
+                Arc::new(Repo)
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facets::blobstore::{ArcBlobstore, Blobstore};
+        use crate::facets::repo::{ArcRepo, Repo};
+        use crate::facets::sql::{ArcSql, Sql};
+        use anyhow::{anyhow, Error};
+        use std::sync::Arc;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(connect: bool)]
+        impl AsyncFactory {
+            async fn sql(&self, connect: &bool) -> Result<ArcSql, Error> {
+                if *connect {
+                    Ok(Arc::new(Sql))
+                } else {
+                    Err(anyhow!("connection refused"))
+                }
+            }
+
+            async fn blobstore(&self, _sql: &ArcSql) -> ArcBlobstore {
+                Arc::new(Blobstore)
+            }
+
+            async fn repo(&self, _blobstore: &ArcBlobstore) -> ArcRepo {
+                Arc::new(Repo)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::repo::Repo;
+    use crate::facets::sql::Sql;
+
+    #[facet::container]
+    pub struct RepoContainer {
+        #[facet]
+        pub repo: Repo,
+    }
+
+    #[facet::container]
+    pub struct SqlContainer {
+        #[facet]
+        pub sql: Sql,
+    }
+}
+
+use containers::{RepoContainer, SqlContainer};
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+fn assert_failed_path<T>(result: Result<T, facet::FactoryError>, expected_path: &[&str]) {
+    match result {
+        Err(e) => {
+            assert_eq!(e.path(), expected_path);
+            let expected_message = match expected_path {
+                [name] => format!("failed to build '{}'", name),
+                _ => format!(
+                    "failed to build '{}' ({})",
+                    expected_path[0],
+                    expected_path.join(" -> ")
+                ),
+            };
+            assert_eq!(e.to_string(), expected_message);
+            match e.failure() {
+                facet::FactoryError::FacetBuildFailed { name, source } => {
+                    assert_eq!(*name, "sql");
+                    assert_eq!(source.to_string(), "connection refused");
+                }
+                _ => panic!("build should fail with facet build error"),
+            }
+        }
+        Ok(_) => panic!("build should fail"),
+    }
+}
+
+#[test]
+fn sync_path() {
+    assert!(SyncFactory.build::<RepoContainer>(true).is_ok());
+    assert_failed_path(
+        SyncFactory.build::<RepoContainer>(false),
+        &["repo", "blobstore", "sql"],
+    );
+    assert_failed_path(SyncFactory.build::<SqlContainer>(false), &["sql"]);
+}
+
+#[test]
+fn parallel_path() {
+    assert!(SyncFactory.build_parallel::<RepoContainer>(true).is_ok());
+    assert_failed_path(
+        SyncFactory.build_parallel::<RepoContainer>(false),
+        &["repo", "blobstore", "sql"],
+    );
+    assert_failed_path(SyncFactory.build_parallel::<SqlContainer>(false), &["sql"]);
+}
+
+#[tokio::test]
+async fn async_path() {
+    assert!(AsyncFactory.build::<RepoContainer>(true).await.is_ok());
+    assert_failed_path(
+        AsyncFactory.build::<RepoContainer>(false).await,
+        &["repo", "blobstore", "sql"],
+    );
+    assert_failed_path(AsyncFactory.build::<SqlContainer>(false).await, &["sql"]);
+}
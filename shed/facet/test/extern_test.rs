@@ -0,0 +1,167 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+
+pub mod facets {
+    pub mod clock {
+        #[facet::facet]
+        pub trait Clock {
+            fn now(&self) -> u64;
+        }
+    }
+
+    pub mod db {
+        #[facet::facet]
+        pub struct Db {
+            pub name: String,
+            pub opened_at: u64,
+        }
+    }
+
+    pub mod service {
+        #[facet::facet]
+        pub struct Service(pub String);
+    }
+}
+
+pub mod facet_impls {
+    pub mod fixed_clock {
+        use crate::facets::clock::Clock;
+
+        pub struct FixedClock(pub u64);
+
+        impl Clock for FixedClock {
+            fn now(&self) -> u64 {
+                self.0
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod base_factory {
+        use crate::facet_impls::fixed_clock::FixedClock;
+        use crate::facets::clock::ArcClock;
+        use crate::facets::db::{ArcDb, Db};
+        use std::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        pub struct BaseFactory {
+            pub built: Mutex<Vec<&'static str>>,
+        }
+
+        #[facet::factory(name: String)]
+        impl BaseFactory {
+            fn clock(&self) -> ArcClock {
+                self.built.lock().unwrap().push("clock");
+                Arc::new(FixedClock(1000))
+            }
+
+            fn db(&self, name: &str, clock: &ArcClock) -> ArcDb {
+                self.built.lock().unwrap().push("db");
+                Arc::new(Db {
+                    name: name.to_string(),
+                    opened_at: clock.now(),
+                })
+            }
+        }
+    }
+
+    pub mod service_factory {
+        use crate::facets::clock::ArcClock;
+        use crate::facets::db::ArcDb;
+        use crate::facets::service::{ArcService, Service};
+        use crate::factories::base_factory::BaseFactory;
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        pub struct ServiceFactory {
+            pub base: BaseFactory,
+        }
+
+        impl AsRef<BaseFactory> for ServiceFactory {
+            fn as_ref(&self) -> &BaseFactory {
+                &self.base
+            }
+        }
+
+        #[facet::factory(
+            name: String,
+            extern = crate::factories::base_factory::BaseFactory(clock: ArcClock),
+        )]
+        impl ServiceFactory {
+            fn service(&self, name: &str, db: &ArcDb) -> ArcService {
+                Arc::new(Service(format!("{} using {}", name, db.name)))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::clock::Clock;
+    use crate::facets::db::Db;
+    use crate::facets::service::Service;
+
+    #[facet::container]
+    pub struct App {
+        #[facet]
+        pub clock: dyn Clock,
+
+        #[facet]
+        pub db: Db,
+
+        #[facet]
+        pub service: Service,
+    }
+}
+
+use std::sync::Arc;
+
+use containers::App;
+use facet_impls::fixed_clock::FixedClock;
+use facets::clock::ArcClock;
+use factories::service_factory::ServiceFactory;
+
+#[test]
+fn builds_extern_facets() {
+    let factory = ServiceFactory::default();
+    let app = factory.build::<App>("main".to_string()).unwrap();
+    assert_eq!(app.clock.now(), 1000);
+    assert_eq!(app.db.name, "main");
+    assert_eq!(app.db.opened_at, 1000);
+    assert_eq!(app.service.0, "main using main");
+    assert_eq!(*factory.base.built.lock().unwrap(), ["clock", "db"]);
+}
+
+#[test]
+fn injects_extern_facets() {
+    let factory = ServiceFactory::default();
+    let clock: ArcClock = Arc::new(FixedClock(42));
+    let app = factory
+        .build_with::<App>("main".to_string())
+        .facet(clock.clone())
+        .finish()
+        .unwrap();
+    assert!(Arc::ptr_eq(&app.clock, &clock));
+    assert_eq!(app.clock.now(), 42);
+    assert_eq!(app.db.opened_at, 42);
+    assert_eq!(*factory.base.built.lock().unwrap(), ["db"]);
+}
+
+#[cfg(feature = "graph")]
+#[test]
+fn composes_facet_graphs() {
+    let graph = ServiceFactory::facet_graph();
+    assert_eq!(graph.factory, "ServiceFactory");
+    let names: Vec<_> = graph.facets.iter().map(|node| node.name).collect();
+    assert_eq!(names, ["service", "clock", "db"]);
+    assert_eq!(graph.facet("service").unwrap().dependencies, ["db"]);
+    assert_eq!(graph.facet("db").unwrap().dependencies, ["clock"]);
+}
@@ -0,0 +1,238 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod clock {
+        #[facet::facet]
+        pub trait Clock {
+            fn now(&self) -> u64;
+        }
+    }
+
+    pub mod db {
+        #[facet::facet]
+        pub struct Db {
+            pub name: String,
+            pub opened_at: u64,
+        }
+    }
+
+    pub mod cache {
+        #[facet::facet]
+        pub struct Cache(pub String);
+    }
+
+    pub mod service {
+        #[facet::facet]
+        pub struct Service(pub String);
+    }
+}
+
+pub mod facet_impls {
+    pub mod fixed_clock {
+        use crate::facets::clock::Clock;
+
+        pub struct FixedClock(pub u64);
+
+        impl Clock for FixedClock {
+            fn now(&self) -> u64 {
+                self.0
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod prod_factory {
+        use crate::facet_impls::fixed_clock::FixedClock;
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::clock::ArcClock;
+        use crate::facets::db::{ArcDb, Db};
+        use crate::facets::service::{ArcService, Service};
+        use std::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        pub struct ProdFactory {
+            pub built: Mutex<Vec<&'static str>>,
+        }
+
+        #[facet::factory(name: String)]
+        impl ProdFactory {
+            fn clock(&self) -> ArcClock {
+                self.built.lock().unwrap().push("clock");
+                Arc::new(FixedClock(1000))
+            }
+
+            fn db(&self, name: &str, clock: &ArcClock) -> ArcDb {
+                self.built.lock().unwrap().push("db");
+                Arc::new(Db {
+                    name: name.to_string(),
+                    opened_at: clock.now(),
+                })
+            }
+
+            fn cache(&self, db: &ArcDb) -> ArcCache {
+                self.built.lock().unwrap().push("cache");
+                Arc::new(Cache(format!("memcache for {}", db.name)))
+            }
+
+            fn service(&self, db: &ArcDb, cache: &ArcCache) -> ArcService {
+                self.built.lock().unwrap().push("service");
+                Arc::new(Service(format!("{} with {}", db.name, cache.0)))
+            }
+        }
+    }
+
+    pub mod test_factory {
+        use crate::facet_impls::fixed_clock::FixedClock;
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::clock::ArcClock;
+        use crate::facets::db::ArcDb;
+        use crate::factories::prod_factory::ProdFactory;
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        pub struct TestFactory {
+            pub prod: ProdFactory,
+        }
+
+        #[facet::factory(name: String, delegate = prod: ProdFactory)]
+        impl TestFactory {
+            fn clock(&self) -> ArcClock {
+                Arc::new(FixedClock(42))
+            }
+
+            fn cache(&self, db: &ArcDb) -> ArcCache {
+                Arc::new(Cache(format!("test cache for {}", db.name)))
+            }
+        }
+    }
+
+    pub mod async_prod_factory {
+        use crate::facet_impls::fixed_clock::FixedClock;
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::clock::ArcClock;
+        use crate::facets::db::{ArcDb, Db};
+        use crate::facets::service::{ArcService, Service};
+        use std::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        pub struct AsyncProdFactory {
+            pub built: Mutex<Vec<&'static str>>,
+        }
+
+        #[facet::factory(name: String)]
+        impl AsyncProdFactory {
+            async fn clock(&self) -> ArcClock {
+                self.built.lock().unwrap().push("clock");
+                Arc::new(FixedClock(1000))
+            }
+
+            async fn db(&self, name: &str, clock: &ArcClock) -> ArcDb {
+                self.built.lock().unwrap().push("db");
+                Arc::new(Db {
+                    name: name.to_string(),
+                    opened_at: clock.now(),
+                })
+            }
+
+            async fn cache(&self, db: &ArcDb) -> ArcCache {
+                self.built.lock().unwrap().push("cache");
+                Arc::new(Cache(format!("memcache for {}", db.name)))
+            }
+
+            async fn service(&self, db: &ArcDb, cache: &ArcCache) -> ArcService {
+                self.built.lock().unwrap().push("service");
+                Arc::new(Service(format!("{} with {}", db.name, cache.0)))
+            }
+        }
+    }
+
+    pub mod async_test_factory {
+        use crate::facet_impls::fixed_clock::FixedClock;
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::clock::ArcClock;
+        use crate::facets::db::ArcDb;
+        use crate::factories::async_prod_factory::AsyncProdFactory;
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        pub struct AsyncTestFactory {
+            pub prod: AsyncProdFactory,
+        }
+
+        #[facet::factory(name: String, delegate = prod: AsyncProdFactory)]
+        impl AsyncTestFactory {
+            async fn clock(&self) -> ArcClock {
+                Arc::new(FixedClock(42))
+            }
+
+            fn cache(&self, db: &ArcDb) -> ArcCache {
+                Arc::new(Cache(format!("test cache for {}", db.name)))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::clock::Clock;
+    use crate::facets::db::Db;
+    use crate::facets::service::Service;
+
+    #[facet::container]
+    pub struct App {
+        #[facet]
+        pub clock: dyn Clock,
+
+        #[facet]
+        pub db: Db,
+
+        #[facet]
+        pub service: Service,
+    }
+}
+
+use std::sync::Arc;
+
+use containers::App;
+use factories::async_test_factory::AsyncTestFactory;
+use factories::test_factory::TestFactory;
+
+fn check_app(app: &App, built: &[&str]) {
+    assert_eq!(app.clock.now(), 42);
+    // The inner factory's facets are built using the overriding facets.
+    assert_eq!(app.db.opened_at, 42);
+    assert_eq!(app.service.0, "main with test cache for main");
+    assert_eq!(built, ["db", "service"]);
+}
+
+#[test]
+fn sync_delegate() {
+    let factory = TestFactory::default();
+    let app = factory.build::<App>("main".to_string()).unwrap();
+    check_app(&app, &factory.prod.built.lock().unwrap());
+}
+
+#[test]
+fn sync_inner_unchanged() {
+    let factory = TestFactory::default();
+    let app = factory.prod.build::<App>("main".to_string()).unwrap();
+    assert_eq!(app.clock.now(), 1000);
+    assert_eq!(app.service.0, "main with memcache for main");
+    assert!(Arc::strong_count(&app.db) >= 1);
+}
+
+#[tokio::test]
+async fn async_delegate() {
+    let factory = AsyncTestFactory::default();
+    let app = factory.build::<App>("main".to_string()).await.unwrap();
+    check_app(&app, &factory.prod.built.lock().unwrap());
+}
@@ -0,0 +1,200 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod store {
+        #[facet::facet]
+        pub trait Store {
+            fn kind(&self) -> String;
+        }
+    }
+
+    pub mod repo {
+        #[facet::facet]
+        pub struct Repo {
+            pub name: String,
+            pub store: String,
+        }
+    }
+}
+
+pub mod facet_impls {
+    use crate::facets::store::Store;
+
+    pub struct NamedStore(pub String);
+
+    impl Store for NamedStore {
+        fn kind(&self) -> String {
+            self.0.clone()
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factories {
+        use std::sync::Arc;
+
+        use anyhow::{bail, Result};
+
+        use crate::facet_impls::NamedStore;
+        use crate::facets::repo::{ArcRepo, Repo};
+        use crate::facets::store::ArcStore;
+
+        #[facet::factory_trait(name: String)]
+        pub trait RepoFactory {
+            fn store(&self) -> ArcStore;
+
+            fn repo(&self, name: &str, store: &ArcStore) -> Result<ArcRepo> {
+                if name.is_empty() {
+                    bail!("repo name must not be empty");
+                }
+                Ok(Arc::new(Repo {
+                    name: name.to_string(),
+                    store: store.kind(),
+                }))
+            }
+        }
+
+        pub struct ProdFactory {
+            pub region: String,
+        }
+
+        impl RepoFactory for ProdFactory {
+            fn store(&self) -> ArcStore {
+                Arc::new(NamedStore(format!("disk in {}", self.region)))
+            }
+        }
+
+        pub struct TestFactory;
+
+        impl RepoFactory for TestFactory {
+            fn store(&self) -> ArcStore {
+                Arc::new(NamedStore(String::from("memory")))
+            }
+
+            fn repo(&self, name: &str, store: &ArcStore) -> Result<ArcRepo> {
+                Ok(Arc::new(Repo {
+                    name: format!("test {}", name),
+                    store: store.kind(),
+                }))
+            }
+        }
+    }
+
+    pub mod async_factories {
+        use std::sync::Arc;
+
+        use async_trait::async_trait;
+
+        use crate::facet_impls::NamedStore;
+        use crate::facets::repo::{ArcRepo, Repo};
+        use crate::facets::store::ArcStore;
+
+        #[facet::factory_trait(name: String)]
+        #[async_trait]
+        pub trait AsyncRepoFactory {
+            async fn store(&self) -> ArcStore;
+
+            async fn repo(&self, name: &str, store: &ArcStore) -> ArcRepo {
+                Arc::new(Repo {
+                    name: name.to_string(),
+                    store: store.kind(),
+                })
+            }
+        }
+
+        pub struct AsyncProdFactory;
+
+        #[async_trait]
+        impl AsyncRepoFactory for AsyncProdFactory {
+            async fn store(&self) -> ArcStore {
+                Arc::new(NamedStore(String::from("remote")))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::repo::Repo;
+    use crate::facets::store::Store;
+
+    #[facet::container]
+    pub struct RepoContainer {
+        #[facet]
+        pub repo: Repo,
+
+        #[facet]
+        pub store: dyn Store,
+    }
+}
+
+use std::sync::Arc;
+
+use containers::RepoContainer;
+use factories::async_factories::{AsyncProdFactory, DynAsyncRepoFactory};
+use factories::sync_factories::{DynRepoFactory, ProdFactory, RepoFactory, TestFactory};
+
+fn factory(test: bool) -> Box<dyn RepoFactory> {
+    if test {
+        Box::new(TestFactory)
+    } else {
+        Box::new(ProdFactory {
+            region: String::from("us"),
+        })
+    }
+}
+
+#[test]
+fn build_from_trait_object() {
+    let container = factory(false)
+        .build::<RepoContainer>("main".to_string())
+        .unwrap();
+    assert_eq!(container.repo.name, "main");
+    assert_eq!(container.repo.store, "disk in us");
+    assert_eq!(container.store.kind(), "disk in us");
+
+    let container = factory(true)
+        .build::<RepoContainer>("main".to_string())
+        .unwrap();
+    assert_eq!(container.repo.name, "test main");
+    assert_eq!(container.repo.store, "memory");
+}
+
+#[test]
+fn build_fails() {
+    assert!(factory(false)
+        .build::<RepoContainer>(String::new())
+        .is_err());
+}
+
+#[test]
+fn shared_trait_object() {
+    let factory: Arc<DynRepoFactory> = Arc::new(TestFactory);
+    let container = factory.build::<RepoContainer>("main".to_string()).unwrap();
+    assert_eq!(container.repo.name, "test main");
+}
+
+#[cfg(feature = "graph")]
+#[test]
+fn trait_object_graph() {
+    let graph = DynRepoFactory::facet_graph();
+    assert_eq!(graph.factory, "DynRepoFactory");
+    assert_eq!(graph.facet("repo").unwrap().dependencies, ["store"]);
+}
+
+#[tokio::test]
+async fn build_from_async_trait_object() {
+    let factory: Box<DynAsyncRepoFactory> = Box::new(AsyncProdFactory);
+    let container = factory
+        .build::<RepoContainer>("main".to_string())
+        .await
+        .unwrap();
+    assert_eq!(container.repo.name, "main");
+    assert_eq!(container.store.kind(), "remote");
+}
@@ -0,0 +1,134 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod logger {
+        use fbinit::FacebookInit;
+
+        #[facet::facet]
+        pub struct Logger {
+            pub fb: FacebookInit,
+            pub category: String,
+        }
+    }
+
+    pub mod client {
+        use fbinit::FacebookInit;
+
+        #[facet::facet]
+        pub struct Client {
+            pub fb: FacebookInit,
+            pub retries: u32,
+        }
+    }
+}
+
+pub mod factories {
+    use crate::facets::client::{ArcClient, Client};
+    use crate::facets::logger::{ArcLogger, Logger};
+    use fbinit::FacebookInit;
+    use std::sync::Arc;
+
+    pub struct SyncFactory;
+
+    #[facet::factory(fb: FacebookInit, category: String, retries: u32)]
+    impl SyncFactory {
+        fn logger(&self, fb: FacebookInit, category: &str) -> ArcLogger {
+            Arc::new(Logger {
+                fb,
+                category: category.to_string(),
+            })
+        }
+
+        fn client(&self, fb: FacebookInit, _logger: &ArcLogger, retries: &u32) -> ArcClient {
+            Arc::new(Client {
+                fb,
+                retries: *retries,
+            })
+        }
+    }
+
+    pub struct AsyncFactory;
+
+    #[facet::factory(fb: FacebookInit, category: String)]
+    impl AsyncFactory {
+        async fn logger(&self, fb: FacebookInit, category: &str) -> ArcLogger {
+            Arc::new(Logger {
+                fb,
+                category: category.to_string(),
+            })
+        }
+
+        async fn client(&self, fb: FacebookInit) -> ArcClient {
+            Arc::new(Client { fb, retries: 0 })
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::client::Client;
+    use crate::facets::logger::Logger;
+
+    #[facet::container]
+    pub struct Service {
+        #[facet]
+        pub logger: Logger,
+
+        #[facet]
+        pub client: Client,
+    }
+}
+
+use std::sync::Arc;
+
+use fbinit::FacebookInit;
+
+use containers::Service;
+use factories::{AsyncFactory, SyncFactory};
+
+#[fbinit::test]
+fn copied_into_each_method(fb: FacebookInit) {
+    let service = SyncFactory
+        .build::<Service>(fb, String::from("service"), 3)
+        .unwrap();
+    assert_eq!(service.logger.category, "service");
+    assert_eq!(service.client.retries, 3);
+}
+
+#[cfg(feature = "graph")]
+#[test]
+fn not_modeled_as_a_dependency() {
+    let graph = SyncFactory::facet_graph();
+    let client = graph.facet("client").unwrap();
+    assert_eq!(client.dependencies, ["logger"]);
+    assert_eq!(client.params, ["fb", "retries"]);
+}
+
+#[fbinit::test]
+fn unchanged_by_rebuilds(fb: FacebookInit) {
+    let first = SyncFactory
+        .build_rebuildable::<Service>(fb, String::from("service"), 3)
+        .unwrap();
+    let second = SyncFactory
+        .rebuild(&first, fb, String::from("service"), 5)
+        .unwrap();
+    assert!(Arc::ptr_eq(&first.logger, &second.logger));
+    assert!(!Arc::ptr_eq(&first.client, &second.client));
+    assert_eq!(second.client.retries, 5);
+}
+
+#[fbinit::test]
+async fn async_copied_into_each_method(fb: FacebookInit) {
+    let service = AsyncFactory
+        .build::<Service>(fb, String::from("service"))
+        .await
+        .unwrap();
+    assert_eq!(service.logger.category, "service");
+    assert_eq!(service.client.retries, 0);
+}
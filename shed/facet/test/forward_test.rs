@@ -0,0 +1,184 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod store {
+        #[facet::facet(forward)]
+        pub trait Store {
+            fn get(&self, key: &str) -> Option<String>;
+
+            fn describe(&self) -> String {
+                String::from("store")
+            }
+        }
+    }
+
+    pub mod fetcher {
+        #[facet::facet(forward)]
+        #[async_trait::async_trait]
+        pub trait Fetcher {
+            async fn fetch(&self, url: &str) -> String;
+        }
+    }
+
+    pub mod loader {
+        #[facet::facet(forward)]
+        pub trait Loader {
+            async fn load(&self, id: u32) -> u32;
+        }
+    }
+
+    pub mod source {
+        #[facet::facet(forward)]
+        pub trait Source<T> {
+            fn next_value(&self) -> T;
+        }
+    }
+}
+
+pub mod facet_impls {
+    use async_trait::async_trait;
+
+    use crate::facets::fetcher::Fetcher;
+    use crate::facets::loader::Loader;
+    use crate::facets::source::Source;
+    use crate::facets::store::Store;
+
+    pub struct MemoryStore;
+
+    impl Store for MemoryStore {
+        fn get(&self, key: &str) -> Option<String> {
+            Some(format!("value of {}", key))
+        }
+
+        fn describe(&self) -> String {
+            String::from("memory store")
+        }
+    }
+
+    /// Decorator that uppercases the values of another store, which can be
+    /// a concrete store, or a facet held by reference, box or `Arc`.
+    pub struct Uppercase<S>(pub S);
+
+    impl<S: Store> Store for Uppercase<S> {
+        fn get(&self, key: &str) -> Option<String> {
+            self.0.get(key).map(|value| value.to_uppercase())
+        }
+    }
+
+    pub struct EchoFetcher;
+
+    #[async_trait]
+    impl Fetcher for EchoFetcher {
+        async fn fetch(&self, url: &str) -> String {
+            format!("fetched {}", url)
+        }
+    }
+
+    pub struct DoubleLoader;
+
+    impl Loader for DoubleLoader {
+        async fn load(&self, id: u32) -> u32 {
+            id * 2
+        }
+    }
+
+    pub struct Constant;
+
+    impl Source<u32> for Constant {
+        fn next_value(&self) -> u32 {
+            7
+        }
+    }
+}
+
+pub mod factories {
+    use std::sync::Arc;
+
+    use crate::facet_impls::{MemoryStore, Uppercase};
+    use crate::facets::store::ArcStore;
+
+    pub struct Factory;
+
+    #[facet::factory()]
+    impl Factory {
+        fn store(&self) -> ArcStore {
+            Arc::new(Uppercase(Arc::new(MemoryStore) as ArcStore))
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::store::Store;
+
+    #[facet::container]
+    pub struct StoreContainer {
+        #[facet]
+        pub store: dyn Store,
+    }
+}
+
+use std::sync::Arc;
+
+use containers::StoreContainer;
+use facet_impls::{Constant, DoubleLoader, EchoFetcher, MemoryStore, Uppercase};
+use facets::fetcher::{ArcFetcher, Fetcher};
+use facets::loader::{ArcLoader, Loader};
+use facets::source::{ArcSource, Source};
+use facets::store::{ArcStore, Store};
+use factories::Factory;
+
+#[test]
+fn forward_sync() {
+    let arc: ArcStore = Arc::new(MemoryStore);
+    assert_eq!(arc.get("a").unwrap(), "value of a");
+    assert_eq!(Store::describe(&arc), "memory store");
+
+    assert_eq!(Uppercase(&MemoryStore).get("b").unwrap(), "VALUE OF B");
+    assert_eq!(
+        Uppercase(Box::new(MemoryStore)).get("c").unwrap(),
+        "VALUE OF C"
+    );
+    assert_eq!(Uppercase(arc).get("d").unwrap(), "VALUE OF D");
+
+    let container = Factory.build::<StoreContainer>().unwrap();
+    assert_eq!(container.store.get("e").unwrap(), "VALUE OF E");
+    assert_eq!(container.store.describe(), "store");
+}
+
+#[test]
+fn forward_generic() {
+    fn next<S: Source<u32>>(source: S) -> u32 {
+        source.next_value()
+    }
+
+    let arc: ArcSource<u32> = Arc::new(Constant);
+    assert_eq!(next(&Constant), 7);
+    assert_eq!(next(Box::new(Constant)), 7);
+    assert_eq!(next(arc), 7);
+}
+
+#[tokio::test]
+async fn forward_async() {
+    async fn fetch<F: Fetcher>(fetcher: F) -> String {
+        fetcher.fetch("url").await
+    }
+
+    async fn load<L: Loader>(loader: L) -> u32 {
+        loader.load(2).await
+    }
+
+    let fetcher: ArcFetcher = Arc::new(EchoFetcher);
+    assert_eq!(fetch(fetcher).await, "fetched url");
+    assert_eq!(fetch(Box::new(EchoFetcher)).await, "fetched url");
+
+    let loader: ArcLoader = Arc::new(DoubleLoader);
+    assert_eq!(load(loader).await, 4);
+    assert_eq!(load(&DoubleLoader).await, 4);
+}
@@ -0,0 +1,143 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod database {
+        #[facet::facet]
+        pub trait Database {
+            fn name(&self) -> &str;
+        }
+    }
+
+    pub mod config {
+        #[facet::facet]
+        pub struct Config {
+            pub limit: u32,
+        }
+    }
+
+    pub mod replica {
+        pub struct Secondary;
+    }
+}
+
+pub mod facet_impls {
+    use crate::facets::database::Database;
+
+    pub struct NamedDatabase(pub &'static str);
+
+    impl Database for NamedDatabase {
+        fn name(&self) -> &str {
+            self.0
+        }
+    }
+}
+
+pub mod factories {
+    use crate::facet_impls::NamedDatabase;
+    use crate::facets::config::{ArcConfig, Config};
+    use crate::facets::database::ArcDatabase;
+    use std::sync::Arc;
+
+    pub struct Factory;
+
+    #[facet::factory(limit: u32)]
+    impl Factory {
+        fn config(&self, limit: &u32) -> ArcConfig {
+            Arc::new(Config { limit: *limit })
+        }
+
+        fn database(&self) -> ArcDatabase {
+            Arc::new(NamedDatabase("primary"))
+        }
+
+        #[facet(key = "crate::facets::replica::Secondary")]
+        fn secondary_database(&self) -> ArcDatabase {
+            Arc::new(NamedDatabase("secondary"))
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::config::Config;
+    use crate::facets::database::Database;
+
+    #[facet::container]
+    pub struct Big {
+        #[facet]
+        pub config: Config,
+
+        #[facet]
+        pub database: dyn Database,
+
+        #[facet(key = "crate::facets::replica::Secondary")]
+        pub secondary: dyn Database,
+
+        #[init(config.limit * 2)]
+        pub doubled: u32,
+    }
+
+    #[facet::container(from(Big, Outer))]
+    pub struct ConfigOnly {
+        #[facet]
+        pub config: Config,
+    }
+
+    #[facet::container(track_usage, from(Big))]
+    pub struct Databases {
+        #[facet]
+        pub db: dyn Database,
+
+        #[facet(key = "crate::facets::replica::Secondary")]
+        pub secondary: dyn Database,
+    }
+
+    #[facet::container]
+    pub struct Outer {
+        #[delegate(Config, dyn Database)]
+        pub big: Big,
+    }
+}
+
+use std::sync::Arc;
+
+use facet::ContainerUsage;
+
+use containers::{Big, ConfigOnly, Databases, Outer};
+use factories::Factory;
+
+#[test]
+fn from_container() {
+    let big = Factory.build::<Big>(10).unwrap();
+
+    let config_only = ConfigOnly::from(&big);
+    assert!(Arc::ptr_eq(&config_only.config, &big.config));
+    assert_eq!(config_only.config.limit, 10);
+
+    let databases = Databases::from(&big);
+    assert!(Arc::ptr_eq(&databases.db, &big.database));
+    assert!(Arc::ptr_eq(&databases.secondary, &big.secondary));
+    assert_eq!(databases.db.name(), "primary");
+    assert_eq!(databases.secondary.name(), "secondary");
+}
+
+#[test]
+fn from_delegating_container() {
+    let outer = Factory.build::<Outer>(10).unwrap();
+
+    let config_only = ConfigOnly::from(&outer);
+    assert!(Arc::ptr_eq(&config_only.config, &outer.big.config));
+}
+
+#[test]
+fn converted_usage_starts_unused() {
+    let big = Factory.build::<Big>(10).unwrap();
+    let databases = Databases::from(&big);
+    assert_eq!(databases.facet_usage().unused(), ["db", "secondary"]);
+}
@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod store {
+        use std::hash::Hash;
+
+        #[facet::facet]
+        pub trait Store<K: Eq + Hash, V> {
+            fn get(&self, key: &K) -> Option<V>;
+
+            fn put(&self, key: K, value: V);
+        }
+    }
+
+    pub mod settings {
+        #[facet::facet]
+        pub struct Settings<T> {
+            pub value: T,
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod memory_store {
+        use std::collections::HashMap;
+        use std::hash::Hash;
+        use std::sync::Mutex;
+
+        use crate::facets::store::Store;
+
+        pub struct MemoryStore<K, V> {
+            values: Mutex<HashMap<K, V>>,
+        }
+
+        impl<K, V> Default for MemoryStore<K, V> {
+            fn default() -> Self {
+                MemoryStore {
+                    values: Mutex::new(HashMap::new()),
+                }
+            }
+        }
+
+        impl<K: Eq + Hash, V: Clone> Store<K, V> for MemoryStore<K, V> {
+            fn get(&self, key: &K) -> Option<V> {
+                self.values.lock().unwrap().get(key).cloned()
+            }
+
+            fn put(&self, key: K, value: V) {
+                self.values.lock().unwrap().insert(key, value);
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod store_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::memory_store::MemoryStore;
+        use crate::facets::settings::{ArcSettings, Settings};
+        use crate::facets::store::ArcStore;
+
+        pub struct StoreFactory;
+
+        #[facet::factory()]
+        impl StoreFactory {
+            fn bytes_store(&self) -> ArcStore<String, Vec<u8>> {
+                Arc::new(MemoryStore::default())
+            }
+
+            fn string_store(&self, settings: &ArcSettings<u32>) -> ArcStore<String, String> {
+                let store = MemoryStore::default();
+                crate::facets::store::Store::put(
+                    &store,
+                    String::from("limit"),
+                    settings.value.to_string(),
+                );
+                Arc::new(store)
+            }
+
+            fn settings(&self) -> ArcSettings<u32> {
+                Arc::new(Settings { value: 10 })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::settings::Settings;
+    use crate::facets::store::Store;
+
+    #[facet::container]
+    pub struct StoreContainer {
+        #[facet]
+        pub bytes_store: dyn Store<String, Vec<u8>>,
+
+        #[facet]
+        pub string_store: dyn Store<String, String>,
+
+        #[facet]
+        pub settings: Settings<u32>,
+    }
+
+    #[facet::container]
+    pub struct BytesContainer {
+        #[facet]
+        pub bytes_store: dyn Store<String, Vec<u8>>,
+    }
+}
+
+use containers::{BytesContainer, StoreContainer};
+use facets::settings::SettingsRef;
+use facets::store::{ArcStore, StoreArc, StoreRef};
+use factories::store_factory::StoreFactory;
+
+fn stored_bytes(container: impl StoreRef<String, Vec<u8>>, key: &str) -> Option<Vec<u8>> {
+    container.store().get(&key.to_string())
+}
+
+#[test]
+fn generic_facets() {
+    let container = StoreFactory.build::<StoreContainer>().unwrap();
+    container
+        .bytes_store
+        .put(String::from("key"), vec![1, 2, 3]);
+    assert_eq!(
+        container.string_store.get(&String::from("limit")),
+        Some(String::from("10"))
+    );
+    assert_eq!(SettingsRef::<u32>::settings(&container).value, 10);
+}
+
+#[test]
+fn generic_ref_traits() {
+    let container = StoreFactory.build::<BytesContainer>().unwrap();
+    container.bytes_store.put(String::from("key"), vec![4]);
+    assert_eq!(stored_bytes(&container, "key"), Some(vec![4]));
+    let store: ArcStore<String, Vec<u8>> = container.store_arc();
+    assert_eq!(store.get(&String::from("missing")), None);
+}
+
+#[test]
+fn multiple_instantiations() {
+    let container = StoreFactory.build::<StoreContainer>().unwrap();
+    let strings = StoreRef::<String, String>::store(&container);
+    assert_eq!(strings.get(&String::from("limit")).as_deref(), Some("10"));
+    container.bytes_store.put(String::from("key"), vec![5]);
+    assert_eq!(stored_bytes(&container, "key"), Some(vec![5]));
+}
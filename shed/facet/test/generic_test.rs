@@ -0,0 +1,149 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod db {
+        #[facet::facet]
+        pub trait Db {
+            fn name(&self) -> &str;
+        }
+    }
+
+    pub mod counter {
+        #[facet::facet]
+        pub struct Counter(pub u32);
+    }
+}
+
+pub mod facet_impls {
+    pub mod named_db {
+        use crate::facets::db::Db;
+
+        pub struct NamedDb(pub String);
+
+        impl Db for NamedDb {
+            fn name(&self) -> &str {
+                &self.0
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facet_impls::named_db::NamedDb;
+        use crate::facets::counter::{ArcCounter, Counter};
+        use crate::facets::db::ArcDb;
+        use std::sync::Arc;
+
+        pub struct SyncFactory;
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn db(&self) -> ArcDb {
+                Arc::new(NamedDb(String::from("sync")))
+            }
+
+            fn counter(&self) -> ArcCounter {
+                Arc::new(Counter(3))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facet_impls::named_db::NamedDb;
+        use crate::facets::counter::{ArcCounter, Counter};
+        use crate::facets::db::ArcDb;
+        use std::sync::Arc;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory()]
+        impl AsyncFactory {
+            async fn db(&self) -> ArcDb {
+                Arc::new(NamedDb(String::from("async")))
+            }
+
+            async fn counter(&self) -> ArcCounter {
+                Arc::new(Counter(3))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::counter::Counter;
+    use crate::facets::db::Db;
+
+    #[facet::container]
+    pub struct Ctx<T: Clone + Default + Send + Sync + 'static> {
+        #[init(T::default())]
+        pub extra: T,
+
+        #[facet]
+        pub db: dyn Db,
+    }
+
+    #[facet::container]
+    pub struct Labelled<'a, L>
+    where
+        L: From<&'a str> + Send + Sync,
+    {
+        #[init(L::from("label"))]
+        pub label: L,
+
+        #[facet]
+        pub counter: Counter,
+
+        #[delegate(dyn Db)]
+        pub ctx: Ctx<Vec<u32>>,
+
+        #[init(::std::marker::PhantomData)]
+        pub marker: ::std::marker::PhantomData<&'a ()>,
+    }
+}
+
+use containers::{Ctx, Labelled};
+use facets::counter::CounterRef;
+use facets::db::DbRef;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+fn db_name(container: impl DbRef) -> String {
+    container.db().name().to_string()
+}
+
+fn counter(container: impl CounterRef) -> u32 {
+    container.counter().0
+}
+
+#[test]
+fn sync_generic() {
+    let ctx = SyncFactory.build::<Ctx<String>>().unwrap();
+    assert_eq!(ctx.extra, "");
+    assert_eq!(db_name(&ctx), "sync");
+
+    let labelled = SyncFactory.build::<Labelled<String>>().unwrap();
+    assert_eq!(labelled.label, "label");
+    assert_eq!(counter(&labelled), 3);
+    assert_eq!(db_name(&labelled), "sync");
+    assert!(labelled.ctx.extra.is_empty());
+}
+
+#[tokio::test]
+async fn async_generic() {
+    let ctx = AsyncFactory.build::<Ctx<u32>>().await.unwrap();
+    assert_eq!(ctx.extra, 0);
+    assert_eq!(db_name(&ctx), "async");
+
+    let labelled = AsyncFactory.build::<Labelled<String>>().await.unwrap();
+    assert_eq!(labelled.label, "label");
+    assert_eq!(counter(&labelled), 3);
+    assert_eq!(db_name(&labelled), "async");
+}
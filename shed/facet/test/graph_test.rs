@@ -0,0 +1,161 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod one {
+        #[facet::facet]
+        pub trait One {
+            fn get(&self) -> u32;
+        }
+    }
+
+    pub mod two {
+        #[facet::facet]
+        pub trait Two {
+            fn get(&self) -> u32;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_one {
+        use crate::facets::one::One;
+
+        pub struct SimpleOne(pub u32);
+
+        impl One for SimpleOne {
+            fn get(&self) -> u32 {
+                self.0
+            }
+        }
+    }
+
+    pub mod double_two {
+        use crate::facets::one::ArcOne;
+        use crate::facets::two::Two;
+
+        pub struct DoubleTwo(pub ArcOne);
+
+        impl Two for DoubleTwo {
+            fn get(&self) -> u32 {
+                self.0.get() * 2
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod graph_factory {
+        use crate::facet_impls::double_two::DoubleTwo;
+        use crate::facet_impls::simple_one::SimpleOne;
+        use crate::facets::one::ArcOne;
+        use crate::facets::two::ArcTwo;
+        use std::sync::Arc;
+
+        pub struct GraphFactory;
+
+        #[facet::factory(value: u32)]
+        impl GraphFactory {
+            fn one(&self, value: &u32) -> ArcOne {
+                Arc::new(SimpleOne(*value))
+            }
+
+            fn two(&self, one: &ArcOne) -> ArcTwo {
+                Arc::new(DoubleTwo(one.clone()))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::one::One;
+    use crate::facets::two::Two;
+    use std::sync::Arc;
+
+    #[facet::container]
+    pub struct TwoOnly {
+        #[facet]
+        two: dyn Two,
+    }
+
+    #[facet::container]
+    pub struct Outer {
+        #[facet]
+        one: dyn One,
+
+        #[delegate(dyn Two)]
+        inner: Arc<TwoOnly>,
+    }
+}
+
+use facet::{ContainerFacets, ContainerField};
+use factories::graph_factory::GraphFactory;
+
+#[test]
+fn factory_graph() {
+    let graph = GraphFactory::facet_graph();
+
+    assert_eq!(graph.factory, "GraphFactory");
+    assert_eq!(
+        graph
+            .facets
+            .iter()
+            .map(|node| node.name)
+            .collect::<Vec<_>>(),
+        ["one", "two"]
+    );
+    assert_eq!(graph.facet("one").unwrap().params, ["value"]);
+    assert!(graph.facet("one").unwrap().dependencies.is_empty());
+    assert!(graph.facet("two").unwrap().params.is_empty());
+    assert_eq!(graph.edges().collect::<Vec<_>>(), [("two", "one")]);
+    assert!(graph.facet("three").is_none());
+}
+
+#[test]
+fn container_fields() {
+    assert_eq!(containers::Outer::container_name(), "Outer");
+    assert_eq!(
+        containers::Outer::facet_fields(),
+        [
+            ContainerField {
+                path: String::from("one"),
+                facet: "one",
+            },
+            ContainerField {
+                path: String::from("inner.two"),
+                facet: "two",
+            },
+        ]
+    );
+
+    let graph = GraphFactory::facet_graph()
+        .with_container::<containers::Outer>()
+        .with_container::<containers::TwoOnly>();
+    assert_eq!(graph.facet("one").unwrap().consumers, ["Outer.one"]);
+    assert_eq!(
+        graph.facet("two").unwrap().consumers,
+        ["Outer.inner.two", "TwoOnly.two"]
+    );
+}
+
+#[test]
+fn dependency_dot() {
+    assert_eq!(
+        GraphFactory::dependency_dot(),
+        concat!(
+            "digraph GraphFactory {\n",
+            "    \"one\";\n",
+            "    \"two\";\n",
+            "    \"param:value\" [label=\"value\", shape=box];\n",
+            "    \"two\" -> \"one\";\n",
+            "    \"one\" -> \"param:value\" [style=dashed];\n",
+            "}\n",
+        )
+    );
+}
@@ -0,0 +1,169 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod db {
+        use facet::FacetHealth;
+
+        #[facet::facet]
+        pub trait Db: FacetHealth {}
+    }
+
+    pub mod cache {
+        use facet::FacetHealth;
+
+        #[facet::facet]
+        pub trait Cache: FacetHealth {}
+    }
+
+    pub mod queue {
+        #[facet::facet]
+        pub trait Queue {}
+    }
+}
+
+pub mod facet_impls {
+    pub mod checked {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use facet::{FacetHealth, HealthStatus};
+
+        use crate::facets::cache::Cache;
+        use crate::facets::db::Db;
+        use crate::facets::queue::Queue;
+
+        pub struct Checked {
+            pub status: HealthStatus,
+            pub checks: AtomicUsize,
+        }
+
+        #[async_trait::async_trait]
+        impl FacetHealth for Checked {
+            async fn check_health(&self) -> HealthStatus {
+                tokio::task::yield_now().await;
+                self.checks.fetch_add(1, Ordering::SeqCst);
+                self.status.clone()
+            }
+        }
+
+        impl Db for Checked {}
+        impl Cache for Checked {}
+        impl Queue for Checked {}
+    }
+}
+
+pub mod factories {
+    pub mod health_factory {
+        use std::sync::atomic::AtomicUsize;
+        use std::sync::Arc;
+
+        use facet::HealthStatus;
+
+        use crate::facet_impls::checked::Checked;
+        use crate::facets::cache::ArcCache;
+        use crate::facets::db::ArcDb;
+        use crate::facets::queue::ArcQueue;
+
+        pub struct HealthFactory;
+
+        #[facet::factory(cache_status: HealthStatus)]
+        impl HealthFactory {
+            fn db(&self) -> ArcDb {
+                Arc::new(Checked {
+                    status: HealthStatus::Healthy,
+                    checks: AtomicUsize::new(0),
+                })
+            }
+
+            fn cache(&self, cache_status: &HealthStatus) -> ArcCache {
+                Arc::new(Checked {
+                    status: cache_status.clone(),
+                    checks: AtomicUsize::new(0),
+                })
+            }
+
+            fn queue(&self) -> ArcQueue {
+                Arc::new(Checked {
+                    status: HealthStatus::Unhealthy(String::from("not checked")),
+                    checks: AtomicUsize::new(0),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::cache::Cache;
+    use crate::facets::db::Db;
+    use crate::facets::queue::Queue;
+
+    #[facet::container]
+    pub struct Storage {
+        #[facet(health)]
+        pub db: dyn Db,
+
+        #[facet(health)]
+        pub cache: dyn Cache,
+    }
+
+    #[facet::container]
+    pub struct Service {
+        #[delegate]
+        pub storage: Storage,
+
+        #[facet(health)]
+        pub db: dyn Db,
+
+        #[facet]
+        pub queue: dyn Queue,
+    }
+}
+
+use facet::{ContainerHealth, HealthStatus};
+
+use crate::containers::{Service, Storage};
+use crate::factories::health_factory::HealthFactory;
+
+#[tokio::test]
+async fn check_health() {
+    let storage = HealthFactory
+        .build::<Storage>(HealthStatus::Degraded(String::from("evicting")))
+        .unwrap();
+    let health = storage.check_health().await;
+
+    assert_eq!(health.len(), 2);
+    assert_eq!(health["db"], HealthStatus::Healthy);
+    assert_eq!(
+        health["cache"],
+        HealthStatus::Degraded(String::from("evicting"))
+    );
+    assert!(health["db"].is_healthy());
+    assert!(!health["cache"].is_healthy());
+}
+
+#[tokio::test]
+async fn check_nested_health() {
+    let service = HealthFactory
+        .build::<Service>(HealthStatus::Unhealthy(String::from("disconnected")))
+        .unwrap();
+    let health = service.check_health().await;
+
+    // The queue is not marked for health checks, and the db is shared with
+    // the nested container so it is only checked once.
+    assert_eq!(
+        health.into_iter().collect::<Vec<_>>(),
+        vec![
+            (
+                "cache",
+                HealthStatus::Unhealthy(String::from("disconnected"))
+            ),
+            ("db", HealthStatus::Healthy),
+        ]
+    );
+}
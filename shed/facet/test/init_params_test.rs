@@ -0,0 +1,160 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod value {
+        #[facet::facet]
+        pub trait Value {
+            fn get(&self) -> u32;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_value {
+        use crate::facets::value::Value;
+
+        pub struct SimpleValue(pub u32);
+
+        impl Value for SimpleValue {
+            fn get(&self) -> u32 {
+                self.0
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::simple_value::SimpleValue;
+        use crate::facets::value::ArcValue;
+
+        pub struct SyncFactory;
+
+        #[facet::factory(name: String, scale: u32, label: String)]
+        impl SyncFactory {
+            fn value(&self, scale: &u32) -> ArcValue {
+                Arc::new(SimpleValue(*scale * 10))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::simple_value::SimpleValue;
+        use crate::facets::value::ArcValue;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(name: String, scale: u32, label: String)]
+        impl AsyncFactory {
+            async fn value(&self, scale: &u32) -> ArcValue {
+                Arc::new(SimpleValue(*scale * 10))
+            }
+        }
+    }
+
+    pub mod unnamed_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::simple_value::SimpleValue;
+        use crate::facets::value::ArcValue;
+
+        pub struct UnnamedFactory;
+
+        #[facet::factory(scale: u32, label: String)]
+        impl UnnamedFactory {
+            async fn value(&self, scale: &u32) -> ArcValue {
+                Arc::new(SimpleValue(*scale))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::value::Value;
+
+    #[facet::container(params(name: String, scale: u32))]
+    pub struct NamedContainer {
+        #[init(params.name.clone())]
+        pub name: String,
+
+        #[init(value.get() + *params.scale)]
+        pub total: u32,
+
+        #[facet]
+        pub value: dyn Value,
+    }
+
+    #[facet::container(params(label: String))]
+    pub struct OuterContainer {
+        #[init(format!("{}: {}", params.label, inner.name))]
+        pub description: String,
+
+        #[delegate(dyn Value)]
+        pub inner: NamedContainer,
+    }
+}
+
+use containers::{NamedContainer, OuterContainer};
+use facet::FactoryError;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+use factories::unnamed_factory::UnnamedFactory;
+
+#[test]
+fn sync_init_params() {
+    let container = SyncFactory
+        .build::<NamedContainer>("example".to_string(), 3, "label".to_string())
+        .unwrap();
+    assert_eq!(container.name, "example");
+    assert_eq!(container.total, 33);
+}
+
+#[test]
+fn sync_delegate_init_params() {
+    let container = SyncFactory
+        .build::<OuterContainer>("example".to_string(), 2, "outer".to_string())
+        .unwrap();
+    assert_eq!(container.description, "outer: example");
+    assert_eq!(container.inner.total, 22);
+}
+
+#[tokio::test]
+async fn async_init_params() {
+    let container = AsyncFactory
+        .build::<NamedContainer>("example".to_string(), 3, "label".to_string())
+        .await
+        .unwrap();
+    assert_eq!(container.name, "example");
+    assert_eq!(container.total, 33);
+
+    let container = AsyncFactory
+        .build::<OuterContainer>("example".to_string(), 2, "outer".to_string())
+        .await
+        .unwrap();
+    assert_eq!(container.description, "outer: example");
+}
+
+#[tokio::test]
+async fn missing_param() {
+    // The factory has parameters of the right types, but none called
+    // `name`.
+    match UnnamedFactory
+        .build::<OuterContainer>(1, "outer".to_string())
+        .await
+    {
+        Err(FactoryError::MissingParameter { name }) => assert_eq!(name, "name"),
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("container should not have built"),
+    }
+}
@@ -0,0 +1,195 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod one {
+        #[facet::facet]
+        pub trait One {
+            fn get(&self) -> u32;
+        }
+    }
+
+    pub mod two {
+        #[facet::facet]
+        pub trait Two {
+            fn get(&self) -> u32;
+        }
+    }
+
+    pub mod three {
+        #[facet::facet]
+        pub struct Three(pub u32);
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_one {
+        use crate::facets::one::One;
+
+        pub struct SimpleOne;
+
+        impl One for SimpleOne {
+            fn get(&self) -> u32 {
+                1
+            }
+        }
+    }
+
+    pub mod simple_two {
+        use crate::facets::two::Two;
+
+        pub struct SimpleTwo(pub u32);
+
+        impl Two for SimpleTwo {
+            fn get(&self) -> u32 {
+                self.0
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facet_impls::simple_one::SimpleOne;
+        use crate::facet_impls::simple_two::SimpleTwo;
+        use crate::facets::one::ArcOne;
+        use crate::facets::three::{ArcThree, Three};
+        use crate::facets::two::ArcTwo;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        pub struct SyncFactory {
+            pub built: AtomicUsize,
+        }
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn one(&self) -> ArcOne {
+                self.built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleOne)
+            }
+
+            fn two(&self, one: &ArcOne) -> ArcTwo {
+                self.built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleTwo(one.get() * 2))
+            }
+
+            fn three(&self, two: &ArcTwo) -> ArcThree {
+                self.built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(Three(two.get() + 1))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facet_impls::simple_one::SimpleOne;
+        use crate::facet_impls::simple_two::SimpleTwo;
+        use crate::facets::one::ArcOne;
+        use crate::facets::three::{ArcThree, Three};
+        use crate::facets::two::ArcTwo;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        pub struct AsyncFactory {
+            pub built: AtomicUsize,
+        }
+
+        #[facet::factory()]
+        impl AsyncFactory {
+            async fn one(&self) -> ArcOne {
+                self.built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleOne)
+            }
+
+            async fn two(&self, one: &ArcOne) -> ArcTwo {
+                self.built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleTwo(one.get() * 2))
+            }
+
+            async fn three(&self, two: &ArcTwo) -> ArcThree {
+                self.built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(Three(two.get() + 1))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::three::Three;
+    use crate::facets::two::Two;
+
+    #[facet::container]
+    pub struct TwoThree {
+        #[facet]
+        pub two: dyn Two,
+
+        #[facet]
+        pub three: Three,
+    }
+}
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use containers::TwoThree;
+use facet_impls::simple_two::SimpleTwo;
+use facets::two::ArcTwo;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+#[test]
+fn sync_inject() {
+    let factory = SyncFactory::default();
+    let two: ArcTwo = Arc::new(SimpleTwo(10));
+    let container = factory
+        .build_with::<TwoThree>()
+        .facet(two.clone())
+        .finish()
+        .unwrap();
+    assert!(Arc::ptr_eq(&container.two, &two));
+    assert_eq!(container.three.0, 11);
+
+    // Only `three` was built, as `one` is only needed by `two`.
+    assert_eq!(factory.built.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn sync_no_inject() {
+    let factory = SyncFactory::default();
+    let container = factory.build_with::<TwoThree>().finish().unwrap();
+    assert_eq!(container.three.0, 3);
+    assert_eq!(factory.built.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn async_inject() {
+    let factory = AsyncFactory::default();
+    let two: ArcTwo = Arc::new(SimpleTwo(10));
+    let container = factory
+        .build_with::<TwoThree>()
+        .facet::<ArcTwo>(two.clone())
+        .finish()
+        .await
+        .unwrap();
+    assert!(Arc::ptr_eq(&container.two, &two));
+    assert_eq!(container.three.0, 11);
+
+    // Only `three` was built, as `one` is only needed by `two`.
+    assert_eq!(factory.built.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn async_no_inject() {
+    let factory = AsyncFactory::default();
+    let container = factory.build_with::<TwoThree>().finish().await.unwrap();
+    assert_eq!(container.three.0, 3);
+    assert_eq!(factory.built.load(Ordering::SeqCst), 3);
+}
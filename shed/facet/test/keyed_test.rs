@@ -0,0 +1,210 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod database {
+        #[facet::facet]
+        pub trait Database {
+            fn name(&self) -> String;
+        }
+    }
+
+    pub mod store {
+        #[facet::facet]
+        pub trait Store {
+            fn describe(&self) -> String;
+        }
+    }
+
+    pub mod keys {
+        pub struct Primary;
+        pub struct Replica;
+    }
+}
+
+pub mod facet_impls {
+    pub mod named_database {
+        use crate::facets::database::Database;
+
+        pub struct NamedDatabase(pub String);
+
+        impl Database for NamedDatabase {
+            fn name(&self) -> String {
+                self.0.clone()
+            }
+        }
+    }
+
+    pub mod replicated_store {
+        use crate::facets::database::ArcDatabase;
+        use crate::facets::store::Store;
+
+        pub struct ReplicatedStore {
+            pub primary: ArcDatabase,
+            pub replica: ArcDatabase,
+        }
+
+        impl Store for ReplicatedStore {
+            fn describe(&self) -> String {
+                format!("{} -> {}", self.primary.name(), self.replica.name())
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facet_impls::named_database::NamedDatabase;
+        use crate::facet_impls::replicated_store::ReplicatedStore;
+        use crate::facets::database::ArcDatabase;
+        use crate::facets::store::ArcStore;
+        use std::sync::Arc;
+
+        pub struct SyncFactory;
+
+        #[facet::factory(name: String)]
+        impl SyncFactory {
+            #[facet(key = "crate::facets::keys::Primary")]
+            fn db(&self, name: &str) -> ArcDatabase {
+                Arc::new(NamedDatabase(format!("{}-primary", name)))
+            }
+
+            #[facet(key = "crate::facets::keys::Replica")]
+            fn db_replica(&self, name: &str) -> ArcDatabase {
+                Arc::new(NamedDatabase(format!("{}-replica", name)))
+            }
+
+            fn store(&self, db: &ArcDatabase, db_replica: &ArcDatabase) -> ArcStore {
+                Arc::new(ReplicatedStore {
+                    primary: db.clone(),
+                    replica: db_replica.clone(),
+                })
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facet_impls::named_database::NamedDatabase;
+        use crate::facet_impls::replicated_store::ReplicatedStore;
+        use crate::facets::database::ArcDatabase;
+        use crate::facets::store::ArcStore;
+        use std::sync::Arc;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(name: String)]
+        impl AsyncFactory {
+            #[facet(key = "crate::facets::keys::Primary")]
+            async fn db(&self, name: &str) -> ArcDatabase {
+                Arc::new(NamedDatabase(format!("{}-primary", name)))
+            }
+
+            #[facet(key = "crate::facets::keys::Replica")]
+            async fn db_replica(&self, name: &str) -> ArcDatabase {
+                Arc::new(NamedDatabase(format!("{}-replica", name)))
+            }
+
+            async fn store(&self, db: &ArcDatabase, db_replica: &ArcDatabase) -> ArcStore {
+                Arc::new(ReplicatedStore {
+                    primary: db.clone(),
+                    replica: db_replica.clone(),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::database::Database;
+    use crate::facets::store::Store;
+
+    #[facet::container]
+    pub struct Databases {
+        #[facet(key = "crate::facets::keys::Primary")]
+        pub db: dyn Database,
+
+        #[facet(key = "crate::facets::keys::Replica")]
+        pub db_replica: dyn Database,
+    }
+
+    #[facet::container]
+    pub struct ReplicaOnly {
+        #[facet(key = "crate::facets::keys::Replica")]
+        pub db_replica: dyn Database,
+
+        #[facet]
+        pub store: dyn Store,
+    }
+}
+
+use facet::{KeyedFacetArc, KeyedFacetRef};
+
+use containers::{Databases, ReplicaOnly};
+use facets::database::Database;
+use facets::keys::{Primary, Replica};
+use facets::store::StoreRef;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+type DynDatabase = dyn Database + Send + Sync + 'static;
+
+fn names(
+    container: impl KeyedFacetRef<Primary, DynDatabase> + KeyedFacetRef<Replica, DynDatabase>,
+) -> (String, String) {
+    (
+        KeyedFacetRef::<Primary, DynDatabase>::keyed_facet_ref(&container).name(),
+        KeyedFacetRef::<Replica, DynDatabase>::keyed_facet_ref(&container).name(),
+    )
+}
+
+fn replica_name(container: impl KeyedFacetArc<Replica, DynDatabase>) -> String {
+    container.keyed_facet_arc().name()
+}
+
+fn describe(container: impl StoreRef) -> String {
+    container.store().describe()
+}
+
+#[test]
+fn sync_keyed() {
+    let databases = SyncFactory
+        .build::<Databases>(String::from("sync"))
+        .unwrap();
+    assert_eq!(databases.db.name(), "sync-primary");
+    assert_eq!(databases.db_replica.name(), "sync-replica");
+    assert_eq!(
+        names(&databases),
+        (String::from("sync-primary"), String::from("sync-replica"))
+    );
+
+    let replica_only = SyncFactory
+        .build::<ReplicaOnly>(String::from("sync"))
+        .unwrap();
+    assert_eq!(replica_name(&replica_only), "sync-replica");
+    assert_eq!(describe(&replica_only), "sync-primary -> sync-replica");
+}
+
+#[tokio::test]
+async fn async_keyed() {
+    let databases = AsyncFactory
+        .build::<Databases>(String::from("async"))
+        .await
+        .unwrap();
+    assert_eq!(
+        names(&databases),
+        (String::from("async-primary"), String::from("async-replica"))
+    );
+
+    let replica_only = AsyncFactory
+        .build::<ReplicaOnly>(String::from("async"))
+        .await
+        .unwrap();
+    assert_eq!(replica_name(&replica_only), "async-replica");
+    assert_eq!(describe(&replica_only), "async-primary -> async-replica");
+}
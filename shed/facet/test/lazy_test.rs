@@ -0,0 +1,162 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod one {
+        #[facet::facet]
+        pub trait One {
+            fn get(&self) -> u32;
+        }
+    }
+
+    pub mod two {
+        #[facet::facet]
+        pub trait Two {
+            fn get(&self) -> u32;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_one {
+        use crate::facets::one::One;
+
+        pub struct SimpleOne;
+
+        impl One for SimpleOne {
+            fn get(&self) -> u32 {
+                1
+            }
+        }
+    }
+
+    pub mod combined_two {
+        use crate::facets::one::ArcOne;
+        use crate::facets::two::Two;
+
+        pub struct CombinedTwo(pub ArcOne);
+
+        impl Two for CombinedTwo {
+            fn get(&self) -> u32 {
+                self.0.get() + self.0.get()
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod lazy_factory {
+        use crate::facet_impls::combined_two::CombinedTwo;
+        use crate::facet_impls::simple_one::SimpleOne;
+        use crate::facets::one::ArcOne;
+        use crate::facets::two::ArcTwo;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use thiserror::Error;
+
+        #[derive(Debug, Error)]
+        #[error("two is not available")]
+        pub struct TwoError;
+
+        #[derive(Clone, Default)]
+        pub struct LazyFactory {
+            pub ones_built: Arc<AtomicUsize>,
+            pub twos_built: Arc<AtomicUsize>,
+        }
+
+        #[facet::factory(fail: bool)]
+        impl LazyFactory {
+            async fn one(&self) -> ArcOne {
+                self.ones_built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleOne)
+            }
+
+            async fn two(&self, one: &ArcOne, fail: &bool) -> Result<ArcTwo, TwoError> {
+                if *fail {
+                    return Err(TwoError);
+                }
+                self.twos_built.fetch_add(1, Ordering::SeqCst);
+                Ok(Arc::new(CombinedTwo(one.clone())))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::one::One;
+    use crate::facets::two::Two;
+
+    #[facet::container]
+    pub struct LazyTwo {
+        #[facet(lazy)]
+        two: dyn Two,
+    }
+
+    #[facet::container]
+    pub struct LazyOne {
+        #[facet(lazy)]
+        one: dyn One,
+
+        #[facet]
+        two: dyn Two,
+    }
+}
+
+use std::sync::atomic::Ordering;
+
+use facets::two::TwoRef;
+use factories::lazy_factory::LazyFactory;
+
+#[tokio::test]
+async fn built_on_access() {
+    let factory = LazyFactory::default();
+    let ones_built = factory.ones_built.clone();
+    let twos_built = factory.twos_built.clone();
+
+    let container = factory.build::<containers::LazyTwo>(false).await.unwrap();
+    drop(factory);
+
+    // Neither the lazy facet nor its dependency, which nothing else
+    // needs, are built with the container.
+    assert_eq!(ones_built.load(Ordering::SeqCst), 0);
+    assert_eq!(twos_built.load(Ordering::SeqCst), 0);
+
+    // The container holds a clone of the factory, so the facets can be
+    // built after the factory it was built from is dropped.
+    assert_eq!(container.two().await.unwrap().get(), 2);
+    assert_eq!(container.two_arc().await.unwrap().get(), 2);
+    assert_eq!(ones_built.load(Ordering::SeqCst), 1);
+    assert_eq!(twos_built.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn lazy_build_failure() {
+    let factory = LazyFactory::default();
+
+    let container = factory.build::<containers::LazyTwo>(true).await.unwrap();
+
+    match container.two().await {
+        Err(facet::FactoryError::FacetBuildFailed { name, .. }) => assert_eq!(name, "two"),
+        _ => panic!("lazy access should fail with facet build error"),
+    }
+}
+
+#[tokio::test]
+async fn needed_by_other_facet() {
+    let factory = LazyFactory::default();
+
+    let container = factory.build::<containers::LazyOne>(false).await.unwrap();
+
+    // The lazy facet was already built as a dependency of another facet,
+    // and so that instance is shared.
+    assert_eq!(factory.ones_built.load(Ordering::SeqCst), 1);
+    assert_eq!(container.one().await.unwrap().get(), 1);
+    assert_eq!(container.two().get(), 2);
+    assert_eq!(factory.ones_built.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,196 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+// The facet traits deliberately name lifetimes that could be elided, as
+// that's what these tests cover.
+#![allow(clippy::needless_lifetimes)]
+
+pub mod facets {
+    pub mod names {
+        #[facet::facet]
+        pub trait Names {
+            fn first<'a>(&'a self) -> &'a str;
+            fn find<'a, 'b>(&'a self, key: &'b str) -> Option<&'a str>;
+            fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a str> + 'a>;
+            fn visit(&self, f: &mut dyn for<'a> FnMut(&'a str));
+            fn map_first(&self, f: fn(&str) -> &str) -> String;
+        }
+    }
+
+    pub mod async_names {
+        #[facet::facet]
+        pub trait AsyncNames {
+            async fn first<'a>(&'a self) -> &'a str;
+            async fn find<'a, 'b>(&'a self, key: &'b str) -> Option<&'a str>;
+            async fn visit(&self, f: &mut (dyn FnMut(&str) + Send));
+            async fn map_first(&self, f: fn(&str) -> &str) -> String;
+        }
+    }
+
+    pub mod lookup {
+        #[facet::facet]
+        #[facet::mock]
+        pub trait Lookup {
+            fn lookup<'a>(&'a self, key: &'a str) -> Option<u32>;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod list_names {
+        use crate::facets::async_names::AsyncNames;
+        use crate::facets::names::Names;
+
+        pub struct ListNames(pub Vec<String>);
+
+        impl Names for ListNames {
+            fn first<'a>(&'a self) -> &'a str {
+                &self.0[0]
+            }
+
+            fn find<'a, 'b>(&'a self, key: &'b str) -> Option<&'a str> {
+                self.0.iter().map(String::as_str).find(|name| *name == key)
+            }
+
+            fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+                Box::new(self.0.iter().map(String::as_str))
+            }
+
+            fn visit(&self, f: &mut dyn for<'a> FnMut(&'a str)) {
+                self.0.iter().for_each(|name| f(name))
+            }
+
+            fn map_first(&self, f: fn(&str) -> &str) -> String {
+                f(&self.0[0]).to_string()
+            }
+        }
+
+        impl AsyncNames for ListNames {
+            async fn first<'a>(&'a self) -> &'a str {
+                &self.0[0]
+            }
+
+            async fn find<'a, 'b>(&'a self, key: &'b str) -> Option<&'a str> {
+                self.0.iter().map(String::as_str).find(|name| *name == key)
+            }
+
+            async fn visit(&self, f: &mut (dyn FnMut(&str) + Send)) {
+                self.0.iter().for_each(|name| f(name))
+            }
+
+            async fn map_first(&self, f: fn(&str) -> &str) -> String {
+                f(&self.0[0]).to_string()
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod names_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::list_names::ListNames;
+        use crate::facets::async_names::ArcAsyncNames;
+        use crate::facets::lookup::{ArcLookup, MockLookup};
+        use crate::facets::names::ArcNames;
+
+        pub struct NamesFactory;
+
+        #[facet::factory()]
+        impl NamesFactory {
+            fn names(&self) -> ArcNames {
+                Arc::new(ListNames(vec!["alpha".to_string(), "beta".to_string()]))
+            }
+
+            fn async_names(&self) -> ArcAsyncNames {
+                Arc::new(ListNames(vec!["gamma".to_string(), "delta".to_string()]))
+            }
+
+            fn lookup(&self) -> ArcLookup {
+                let lookup = MockLookup::new();
+                lookup.lookup.returns(Some(1));
+                Arc::new(lookup)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::async_names::DynAsyncNames;
+    use crate::facets::lookup::Lookup;
+    use crate::facets::names::Names;
+
+    #[facet::container]
+    pub struct NamesContainer {
+        #[facet]
+        pub names: dyn Names,
+
+        #[facet]
+        pub async_names: dyn DynAsyncNames,
+
+        #[facet]
+        pub lookup: dyn Lookup,
+    }
+}
+
+use containers::NamesContainer;
+use facets::names::NamesRef;
+use factories::names_factory::NamesFactory;
+
+fn first_name(names: &impl NamesRef) -> &str {
+    names.names().first()
+}
+
+#[test]
+fn borrowed_results() {
+    let container = NamesFactory.build::<NamesContainer>().unwrap();
+    assert_eq!(first_name(&container), "alpha");
+    assert_eq!(container.names.find("beta"), Some("beta"));
+    assert_eq!(container.names.find("gamma"), None);
+    assert_eq!(
+        container.names.iter().collect::<Vec<_>>(),
+        ["alpha", "beta"]
+    );
+}
+
+#[test]
+fn higher_ranked_arguments() {
+    let container = NamesFactory.build::<NamesContainer>().unwrap();
+    let mut visited = Vec::new();
+    container
+        .names
+        .visit(&mut |name| visited.push(name.to_string()));
+    assert_eq!(visited, ["alpha", "beta"]);
+    assert_eq!(container.names.map_first(|name| &name[1..]), "lpha");
+}
+
+#[tokio::test]
+async fn async_borrowed_results() {
+    let container = NamesFactory.build::<NamesContainer>().unwrap();
+    let key = String::from("delta");
+    assert_eq!(container.async_names.first().await, "gamma");
+    assert_eq!(container.async_names.find(&key).await, Some("delta"));
+
+    let mut visited = Vec::new();
+    container
+        .async_names
+        .visit(&mut |name| visited.push(name.to_string()))
+        .await;
+    assert_eq!(visited, ["gamma", "delta"]);
+    assert_eq!(
+        container.async_names.map_first(|name| &name[1..]).await,
+        "amma"
+    );
+}
+
+#[test]
+fn mock_with_lifetimes() {
+    let container = NamesFactory.build::<NamesContainer>().unwrap();
+    assert_eq!(container.lookup.lookup("key"), Some(1));
+}
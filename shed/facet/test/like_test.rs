@@ -0,0 +1,155 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod db {
+        #[facet::facet]
+        pub trait Db {
+            fn query(&self) -> String;
+        }
+    }
+
+    pub mod config {
+        #[facet::facet]
+        pub struct Config {
+            pub name: String,
+        }
+    }
+
+    pub mod replica {
+        #[facet::facet]
+        pub trait Replica {
+            fn region(&self) -> &str;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_db {
+        use crate::facets::db::Db;
+        use crate::facets::replica::Replica;
+
+        pub struct SimpleDb(pub &'static str);
+
+        impl Db for SimpleDb {
+            fn query(&self) -> String {
+                format!("query {}", self.0)
+            }
+        }
+
+        impl Replica for SimpleDb {
+            fn region(&self) -> &str {
+                self.0
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod simple_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::simple_db::SimpleDb;
+        use crate::facets::config::{ArcConfig, Config};
+        use crate::facets::db::ArcDb;
+        use crate::facets::replica::ArcReplica;
+
+        pub struct SimpleFactory;
+
+        #[facet::factory(name: String)]
+        impl SimpleFactory {
+            fn db(&self) -> ArcDb {
+                Arc::new(SimpleDb("primary"))
+            }
+
+            fn config(&self, name: &str) -> ArcConfig {
+                Arc::new(Config {
+                    name: name.to_string(),
+                })
+            }
+
+            #[facet(key = "crate::containers::Europe")]
+            fn europe(&self) -> ArcReplica {
+                Arc::new(SimpleDb("eu"))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::config::Config;
+    use crate::facets::db::Db;
+    use crate::facets::replica::Replica;
+
+    pub struct Europe;
+
+    #[facet::container]
+    pub struct Storage {
+        #[facet]
+        pub db: dyn Db,
+
+        #[facet]
+        pub config: Config,
+
+        #[facet(key = "crate::containers::Europe")]
+        pub europe: dyn Replica,
+    }
+
+    #[facet::container]
+    pub struct Service {
+        #[delegate(dyn Db, Config)]
+        pub storage: Storage,
+
+        #[init(String::from("service"))]
+        pub label: String,
+    }
+}
+
+use facet::KeyedFacetRef;
+
+use crate::containers::{Europe, Service, ServiceLike, Storage, StorageLike};
+use crate::facets::config::ConfigRef;
+use crate::facets::db::DbRef;
+use crate::facets::replica::Replica;
+use crate::factories::simple_factory::SimpleFactory;
+
+type DynReplica = dyn Replica + Send + Sync + 'static;
+
+fn describe_storage(storage: &impl StorageLike) -> String {
+    let europe: &DynReplica = KeyedFacetRef::<Europe, _>::keyed_facet_ref(storage);
+    format!(
+        "{}: {} ({})",
+        storage.config().name,
+        storage.db().query(),
+        europe.region()
+    )
+}
+
+fn describe_service(service: &impl ServiceLike) -> String {
+    format!("{}: {}", service.config().name, service.db().query())
+}
+
+#[test]
+fn bound_on_like_trait() {
+    let storage = SimpleFactory
+        .build::<Storage>(String::from("storage"))
+        .unwrap();
+
+    assert_eq!(describe_storage(&storage), "storage: query primary (eu)");
+}
+
+#[test]
+fn delegated_facets_are_included() {
+    let service = SimpleFactory
+        .build::<Service>(String::from("service"))
+        .unwrap();
+
+    assert_eq!(describe_service(&service), "service: query primary");
+    assert_eq!(describe_service(&service.storage), "service: query primary");
+}
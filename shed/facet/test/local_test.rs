@@ -0,0 +1,133 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod counter {
+        #[facet::facet(local)]
+        pub trait Counter {
+            fn increment(&self) -> u32;
+        }
+    }
+
+    pub mod history {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        #[facet::facet(local)]
+        pub struct History {
+            pub entries: Rc<RefCell<Vec<u32>>>,
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod cell_counter {
+        use std::cell::Cell;
+
+        use crate::facets::counter::Counter;
+        use crate::facets::history::RcHistory;
+
+        pub struct CellCounter {
+            pub count: Cell<u32>,
+            pub history: RcHistory,
+        }
+
+        impl Counter for CellCounter {
+            fn increment(&self) -> u32 {
+                let count = self.count.get() + 1;
+                self.count.set(count);
+                self.history.entries.borrow_mut().push(count);
+                count
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod local_factory {
+        use std::cell::{Cell, RefCell};
+        use std::rc::Rc;
+
+        use crate::facet_impls::cell_counter::CellCounter;
+        use crate::facets::counter::RcCounter;
+        use crate::facets::history::{History, RcHistory};
+
+        pub struct LocalFactory;
+
+        #[facet::factory(initial: u32)]
+        impl LocalFactory {
+            fn history(&self) -> RcHistory {
+                Rc::new(History {
+                    entries: Rc::new(RefCell::new(Vec::new())),
+                })
+            }
+
+            fn counter(&self, initial: &u32, history: &RcHistory) -> RcCounter {
+                Rc::new(CellCounter {
+                    count: Cell::new(*initial),
+                    history: history.clone(),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use std::rc::Rc;
+
+    use crate::facets::counter::Counter;
+    use crate::facets::history::History;
+
+    #[facet::container(local)]
+    pub struct Inner {
+        #[facet]
+        pub history: History,
+    }
+
+    #[facet::container(local)]
+    pub struct Local {
+        #[facet]
+        pub counter: dyn Counter,
+
+        #[delegate(History)]
+        pub inner: Rc<Inner>,
+
+        #[init(counter.increment())]
+        pub first: u32,
+    }
+}
+
+use std::rc::Rc;
+
+use containers::Local;
+use facets::counter::{CounterRc, CounterRef};
+use facets::history::{HistoryRc, HistoryRef};
+use factories::local_factory::LocalFactory;
+
+fn increment(container: impl CounterRef) -> u32 {
+    container.counter().increment()
+}
+
+#[test]
+fn local_container() {
+    let container = LocalFactory.build::<Local>(10).unwrap();
+    assert_eq!(container.first, 11);
+    assert_eq!(increment(&container), 12);
+
+    let counter = container.counter_rc();
+    assert_eq!(counter.increment(), 13);
+    assert!(Rc::ptr_eq(&counter, &container.counter));
+
+    // The history is shared with the nested container.
+    assert!(Rc::ptr_eq(
+        &container.history_rc(),
+        &container.inner.history
+    ));
+    assert_eq!(*container.history().entries.borrow(), [11, 12, 13]);
+}
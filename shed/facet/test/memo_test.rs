@@ -0,0 +1,274 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod store {
+        #[facet::facet]
+        pub trait Store {
+            fn id(&self) -> usize;
+        }
+    }
+
+    pub mod repo {
+        #[facet::facet]
+        pub trait Repo {
+            fn name(&self) -> &str;
+            fn store_id(&self) -> usize;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_store {
+        use crate::facets::store::Store;
+
+        pub struct SimpleStore(pub usize);
+
+        impl Store for SimpleStore {
+            fn id(&self) -> usize {
+                self.0
+            }
+        }
+    }
+
+    pub mod simple_repo {
+        use crate::facets::repo::Repo;
+        use crate::facets::store::ArcStore;
+
+        pub struct SimpleRepo {
+            pub name: String,
+            pub store: ArcStore,
+        }
+
+        impl Repo for SimpleRepo {
+            fn name(&self) -> &str {
+                &self.name
+            }
+
+            fn store_id(&self) -> usize {
+                self.store.id()
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facet_impls::simple_repo::SimpleRepo;
+        use crate::facet_impls::simple_store::SimpleStore;
+        use crate::facets::repo::ArcRepo;
+        use crate::facets::store::ArcStore;
+        use facet::{BuildCache, FactoryMemo};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        pub struct SyncFactory {
+            pub stores_built: AtomicUsize,
+            pub repos_built: AtomicUsize,
+            pub builds: BuildCache,
+        }
+
+        impl FactoryMemo for SyncFactory {
+            fn build_cache(&self) -> &BuildCache {
+                &self.builds
+            }
+        }
+
+        #[facet::factory(memoize, name: String)]
+        impl SyncFactory {
+            fn store(&self) -> ArcStore {
+                let id = self.stores_built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleStore(id))
+            }
+
+            fn repo(&self, name: &str, store: &ArcStore) -> ArcRepo {
+                self.repos_built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleRepo {
+                    name: name.to_string(),
+                    store: store.clone(),
+                })
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facet_impls::simple_repo::SimpleRepo;
+        use crate::facet_impls::simple_store::SimpleStore;
+        use crate::facets::repo::ArcRepo;
+        use crate::facets::store::ArcStore;
+        use facet::{BuildCache, FactoryMemo};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        pub struct AsyncFactory {
+            pub stores_built: AtomicUsize,
+            pub repos_built: AtomicUsize,
+            pub builds: BuildCache,
+        }
+
+        impl FactoryMemo for AsyncFactory {
+            fn build_cache(&self) -> &BuildCache {
+                &self.builds
+            }
+        }
+
+        #[facet::factory(memoize, name: String, shard: u32)]
+        impl AsyncFactory {
+            async fn store(&self, shard: &u32) -> ArcStore {
+                self.stores_built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleStore(*shard as usize))
+            }
+
+            async fn repo(&self, name: &str, store: &ArcStore) -> ArcRepo {
+                self.repos_built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleRepo {
+                    name: name.to_string(),
+                    store: store.clone(),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::repo::Repo;
+    use crate::facets::store::Store;
+
+    #[facet::container]
+    pub struct StoreContainer {
+        #[facet]
+        pub store: dyn Store,
+    }
+
+    #[facet::container]
+    pub struct RepoContainer {
+        #[facet]
+        pub repo: dyn Repo,
+
+        #[facet]
+        pub store: dyn Store,
+    }
+}
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use containers::{RepoContainer, StoreContainer};
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+#[test]
+fn equal_params_reuse_facets() {
+    let factory = SyncFactory::default();
+    let first = factory
+        .build::<RepoContainer>(String::from("repo"))
+        .unwrap();
+    let second = factory
+        .build::<RepoContainer>(String::from("repo"))
+        .unwrap();
+
+    assert_eq!(factory.stores_built.load(Ordering::SeqCst), 1);
+    assert_eq!(factory.repos_built.load(Ordering::SeqCst), 1);
+    assert!(Arc::ptr_eq(&first.repo, &second.repo));
+    assert!(Arc::ptr_eq(&first.store, &second.store));
+}
+
+#[test]
+fn different_params_build_again() {
+    let factory = SyncFactory::default();
+    let first = factory
+        .build::<RepoContainer>(String::from("first"))
+        .unwrap();
+    let second = factory
+        .build::<RepoContainer>(String::from("second"))
+        .unwrap();
+
+    assert_eq!(factory.stores_built.load(Ordering::SeqCst), 2);
+    assert_eq!(first.repo.name(), "first");
+    assert_eq!(second.repo.name(), "second");
+    assert!(!Arc::ptr_eq(&first.store, &second.store));
+}
+
+#[test]
+fn newly_needed_facets_are_built() {
+    let factory = SyncFactory::default();
+    let store = factory
+        .build::<StoreContainer>(String::from("repo"))
+        .unwrap();
+    let repo = factory
+        .build::<RepoContainer>(String::from("repo"))
+        .unwrap();
+    let (_, report) = factory
+        .build_instrumented::<RepoContainer>(String::from("repo"))
+        .unwrap();
+
+    assert_eq!(factory.stores_built.load(Ordering::SeqCst), 2);
+    assert_eq!(factory.repos_built.load(Ordering::SeqCst), 2);
+    assert!(Arc::ptr_eq(&store.store, &repo.store));
+    assert_eq!(repo.repo.store_id(), store.store.id());
+    // Other build methods are not memoized.
+    assert!(report.facet("store").is_some());
+}
+
+#[test]
+fn cleared_cache() {
+    let factory = SyncFactory::default();
+    let first = factory
+        .build::<StoreContainer>(String::from("repo"))
+        .unwrap();
+    factory.builds.clear();
+    let second = factory
+        .build::<StoreContainer>(String::from("repo"))
+        .unwrap();
+
+    assert_eq!(factory.stores_built.load(Ordering::SeqCst), 2);
+    assert!(!Arc::ptr_eq(&first.store, &second.store));
+}
+
+#[tokio::test]
+async fn async_equal_params_reuse_facets() {
+    let factory = AsyncFactory::default();
+    let first = factory
+        .build::<RepoContainer>(String::from("repo"), 1)
+        .await
+        .unwrap();
+    let second = factory
+        .build::<RepoContainer>(String::from("repo"), 1)
+        .await
+        .unwrap();
+    let other = factory
+        .build::<RepoContainer>(String::from("repo"), 2)
+        .await
+        .unwrap();
+
+    assert_eq!(factory.stores_built.load(Ordering::SeqCst), 2);
+    assert_eq!(factory.repos_built.load(Ordering::SeqCst), 2);
+    assert!(Arc::ptr_eq(&first.repo, &second.repo));
+    assert!(!Arc::ptr_eq(&first.repo, &other.repo));
+    assert_eq!(other.repo.store_id(), 2);
+}
+
+#[tokio::test]
+async fn async_newly_needed_facets_are_built() {
+    let factory = AsyncFactory::default();
+    let store = factory
+        .build::<StoreContainer>(String::from("repo"), 1)
+        .await
+        .unwrap();
+    let repo = factory
+        .build::<RepoContainer>(String::from("repo"), 1)
+        .await
+        .unwrap();
+
+    assert_eq!(factory.stores_built.load(Ordering::SeqCst), 1);
+    assert_eq!(factory.repos_built.load(Ordering::SeqCst), 1);
+    assert!(Arc::ptr_eq(&store.store, &repo.store));
+}
@@ -0,0 +1,123 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod store {
+        #[facet::facet]
+        #[facet::mock]
+        pub trait Store {
+            fn get(&self, key: &str) -> Option<Vec<u8>>;
+            fn put(&self, key: &str, value: Vec<u8>);
+            fn len(&self) -> usize;
+
+            fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+        }
+    }
+
+    pub mod fetcher {
+        #[facet::facet]
+        #[facet::mock]
+        #[async_trait::async_trait]
+        pub trait Fetcher {
+            async fn fetch(&self, id: u64) -> Result<String, String>;
+        }
+    }
+}
+
+pub mod factories {
+    pub mod test_factory {
+        use crate::facets::fetcher::{ArcFetcher, MockFetcher};
+        use crate::facets::store::{ArcStore, MockStore};
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        pub struct TestFactory {
+            pub store: Arc<MockStore>,
+            pub fetcher: Arc<MockFetcher>,
+        }
+
+        #[facet::factory()]
+        impl TestFactory {
+            fn store(&self) -> ArcStore {
+                self.store.clone()
+            }
+
+            async fn fetcher(&self) -> ArcFetcher {
+                self.fetcher.clone()
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::fetcher::Fetcher;
+    use crate::facets::store::Store;
+
+    #[facet::container]
+    pub struct TestContainer {
+        #[facet]
+        pub store: dyn Store,
+
+        #[facet]
+        pub fetcher: dyn Fetcher,
+    }
+}
+
+use containers::TestContainer;
+use facets::store::MockStore;
+use factories::test_factory::TestFactory;
+
+#[test]
+fn canned_values() {
+    let store = MockStore::new();
+    store.get.returns(Some(b"value".to_vec()));
+    store.len.returns_with(|_| 3);
+
+    use facets::store::Store;
+    assert_eq!(store.get("key"), Some(b"value".to_vec()));
+    assert_eq!(store.get("other"), Some(b"value".to_vec()));
+    assert!(!store.is_empty());
+    store.put("key", b"new".to_vec());
+
+    assert_eq!(
+        store.get.calls(),
+        [(String::from("key"),), (String::from("other"),)]
+    );
+    assert_eq!(store.put.calls(), [(String::from("key"), b"new".to_vec())]);
+    assert_eq!(store.len.call_count(), 1);
+}
+
+#[test]
+#[should_panic(expected = "mock method 'Store::len' was called without a return value")]
+fn missing_return_value() {
+    use facets::store::Store;
+    MockStore::new().len();
+}
+
+#[tokio::test]
+async fn in_factory() {
+    let factory = TestFactory::default();
+    factory.fetcher.fetch.returns_with(|(id,)| {
+        if *id == 0 {
+            Err(String::from("not found"))
+        } else {
+            Ok(format!("item {}", id))
+        }
+    });
+    let container = factory.build::<TestContainer>().await.unwrap();
+
+    assert_eq!(container.fetcher.fetch(1).await.unwrap(), "item 1");
+    assert_eq!(container.fetcher.fetch(0).await.unwrap_err(), "not found");
+    assert_eq!(factory.fetcher.fetch.calls(), [(1,), (0,)]);
+
+    container.store.put("key", b"value".to_vec());
+    assert_eq!(factory.store.put.call_count(), 1);
+}
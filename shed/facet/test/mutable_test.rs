@@ -0,0 +1,138 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod store {
+        #[facet::facet(boxed)]
+        pub trait Store {
+            fn get(&self, key: &str) -> Option<String>;
+
+            fn set(&mut self, key: &str, value: &str);
+        }
+    }
+
+    pub mod counter {
+        #[facet::facet(boxed)]
+        pub struct Counter {
+            pub count: u32,
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod memory_store {
+        use std::collections::HashMap;
+
+        use crate::facets::store::Store;
+
+        #[derive(Default)]
+        pub struct MemoryStore {
+            pub values: HashMap<String, String>,
+        }
+
+        impl Store for MemoryStore {
+            fn get(&self, key: &str) -> Option<String> {
+                self.values.get(key).cloned()
+            }
+
+            fn set(&mut self, key: &str, value: &str) {
+                self.values.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod test_factory {
+        use crate::facet_impls::memory_store::MemoryStore;
+        use crate::facets::counter::Counter;
+        use crate::facets::store::BoxStore;
+
+        pub struct TestFactory;
+
+        #[facet::factory(initial: u32)]
+        impl TestFactory {
+            #[facet(boxed)]
+            fn store(&self) -> BoxStore {
+                Box::<MemoryStore>::default()
+            }
+
+            fn counter(&self, initial: &u32) -> Box<Counter> {
+                Box::new(Counter { count: *initial })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::counter::Counter;
+    use crate::facets::store::Store;
+
+    #[facet::container]
+    #[derive(Clone)]
+    pub struct TestContainer {
+        #[facet(mutable)]
+        pub store: dyn Store,
+
+        #[facet(mutable)]
+        pub counter: Counter,
+    }
+}
+
+use std::sync::Arc;
+
+use crate::containers::TestContainer;
+use crate::facet_impls::memory_store::MemoryStore;
+use crate::facets::store::Store;
+use crate::factories::test_factory::TestFactory;
+
+#[test]
+fn mutate_facets() {
+    let container = TestFactory.build::<TestContainer>(5).unwrap();
+    assert_eq!(container.store_read().get("key"), None);
+
+    container.store_write().set("key", "value");
+    container.counter_write().count += 1;
+
+    assert_eq!(container.store_read().get("key").as_deref(), Some("value"));
+    assert_eq!(container.counter_read().count, 6);
+}
+
+#[test]
+fn replace_facets() {
+    let container = TestFactory.build::<TestContainer>(5).unwrap();
+    let mut store = MemoryStore::default();
+    store.set("key", "replaced");
+
+    *container.store_write() = Box::new(store);
+
+    assert_eq!(
+        container.store_read().get("key").as_deref(),
+        Some("replaced")
+    );
+}
+
+#[test]
+fn clones_share_mutable_facets() {
+    let container = TestFactory.build::<TestContainer>(0).unwrap();
+    let clone = container.clone();
+    assert!(Arc::ptr_eq(&container.counter, &clone.counter));
+
+    clone.counter_write().count = 3;
+    assert_eq!(container.counter_read().count, 3);
+}
+
+#[test]
+fn containers_own_mutable_facets() {
+    let first = TestFactory.build::<TestContainer>(0).unwrap();
+    let second = TestFactory.build::<TestContainer>(0).unwrap();
+
+    first.store_write().set("key", "first");
+    assert_eq!(second.store_read().get("key"), None);
+}
@@ -0,0 +1,188 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod store {
+        #[facet::facet]
+        pub trait Store {
+            async fn get(&self, key: &str) -> Option<String>;
+
+            async fn put(&self, key: String, value: String);
+
+            fn name(&self) -> &str;
+        }
+    }
+
+    pub mod counter {
+        #[facet::facet(local)]
+        pub trait Counter {
+            async fn increment(&self) -> u32;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod memory_store {
+        use std::collections::HashMap;
+        use std::sync::Mutex;
+
+        use crate::facets::store::Store;
+
+        #[derive(Default)]
+        pub struct MemoryStore {
+            values: Mutex<HashMap<String, String>>,
+        }
+
+        impl Store for MemoryStore {
+            async fn get(&self, key: &str) -> Option<String> {
+                tokio::task::yield_now().await;
+                self.values.lock().unwrap().get(key).cloned()
+            }
+
+            async fn put(&self, key: String, value: String) {
+                tokio::task::yield_now().await;
+                self.values.lock().unwrap().insert(key, value);
+            }
+
+            fn name(&self) -> &str {
+                "memory"
+            }
+        }
+    }
+
+    pub mod cell_counter {
+        use std::cell::Cell;
+
+        use crate::facets::counter::Counter;
+
+        #[derive(Default)]
+        pub struct CellCounter {
+            count: Cell<u32>,
+        }
+
+        impl Counter for CellCounter {
+            async fn increment(&self) -> u32 {
+                self.count.set(self.count.get() + 1);
+                self.count.get()
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::memory_store::MemoryStore;
+        use crate::facets::store::ArcStore;
+
+        pub struct SyncFactory;
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn store(&self) -> ArcStore {
+                Arc::new(MemoryStore::default())
+            }
+        }
+    }
+
+    pub mod local_factory {
+        use std::rc::Rc;
+
+        use crate::facet_impls::cell_counter::CellCounter;
+        use crate::facets::counter::RcCounter;
+
+        pub struct LocalFactory;
+
+        #[facet::factory()]
+        impl LocalFactory {
+            fn counter(&self) -> RcCounter {
+                Rc::new(CellCounter::default())
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::memory_store::MemoryStore;
+        use crate::facets::store::ArcStore;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory()]
+        impl AsyncFactory {
+            async fn store(&self) -> ArcStore {
+                let store = MemoryStore::default();
+                crate::facets::store::Store::put(&store, "ready".into(), "yes".into()).await;
+                Arc::new(store)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::counter::DynCounter;
+    use crate::facets::store::DynStore;
+
+    #[facet::container]
+    pub struct StoreContainer {
+        #[facet]
+        pub store: dyn DynStore,
+    }
+
+    #[facet::container(local)]
+    pub struct CounterContainer {
+        #[facet]
+        pub counter: dyn DynCounter,
+    }
+}
+
+use containers::{CounterContainer, StoreContainer};
+use facets::counter::CounterRef;
+use facets::store::StoreRef;
+use factories::async_factory::AsyncFactory;
+use factories::local_factory::LocalFactory;
+use factories::sync_factory::SyncFactory;
+
+async fn round_trip(container: impl StoreRef) -> Option<String> {
+    let store = container.store();
+    store.put("key".into(), "value".into()).await;
+    store.get("key").await
+}
+
+#[tokio::test]
+async fn native_async_methods() {
+    let container = SyncFactory.build::<StoreContainer>().unwrap();
+    assert_eq!(container.store.name(), "memory");
+    assert_eq!(round_trip(&container).await.as_deref(), Some("value"));
+    assert_eq!(container.store.get("missing").await, None);
+}
+
+#[tokio::test]
+async fn futures_are_send() {
+    let container = SyncFactory.build::<StoreContainer>().unwrap();
+    let value = tokio::spawn(async move { round_trip(&container).await })
+        .await
+        .unwrap();
+    assert_eq!(value.as_deref(), Some("value"));
+}
+
+#[tokio::test]
+async fn async_factory() {
+    let container = AsyncFactory.build::<StoreContainer>().await.unwrap();
+    assert_eq!(container.store().get("ready").await.as_deref(), Some("yes"));
+}
+
+#[tokio::test]
+async fn local_native_async() {
+    let container = LocalFactory.build::<CounterContainer>().unwrap();
+    assert_eq!(container.counter().increment().await, 1);
+    assert_eq!(container.counter.increment().await, 2);
+}
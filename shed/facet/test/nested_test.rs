@@ -0,0 +1,170 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod one {
+        #[facet::facet]
+        pub trait One {
+            fn get(&self) -> u32;
+        }
+    }
+
+    pub mod two {
+        #[facet::facet]
+        pub trait Two {
+            fn get(&self) -> u32;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_one {
+        use crate::facets::one::One;
+
+        pub struct SimpleOne;
+
+        impl One for SimpleOne {
+            fn get(&self) -> u32 {
+                1
+            }
+        }
+    }
+
+    pub mod combined_two {
+        use crate::facets::one::ArcOne;
+        use crate::facets::two::Two;
+
+        pub struct CombinedTwo(pub ArcOne);
+
+        impl Two for CombinedTwo {
+            fn get(&self) -> u32 {
+                self.0.get() * 2
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facet_impls::combined_two::CombinedTwo;
+        use crate::facet_impls::simple_one::SimpleOne;
+        use crate::facets::one::ArcOne;
+        use crate::facets::two::ArcTwo;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        pub struct SyncFactory {
+            pub ones_built: AtomicUsize,
+        }
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn one(&self) -> ArcOne {
+                self.ones_built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleOne)
+            }
+
+            fn two(&self, one: &ArcOne) -> ArcTwo {
+                Arc::new(CombinedTwo(one.clone()))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facet_impls::combined_two::CombinedTwo;
+        use crate::facet_impls::simple_one::SimpleOne;
+        use crate::facets::one::ArcOne;
+        use crate::facets::two::ArcTwo;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        #[derive(Default)]
+        pub struct AsyncFactory {
+            pub ones_built: AtomicUsize,
+        }
+
+        #[facet::factory()]
+        impl AsyncFactory {
+            async fn one(&self) -> ArcOne {
+                self.ones_built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleOne)
+            }
+
+            async fn two(&self, one: &ArcOne) -> ArcTwo {
+                Arc::new(CombinedTwo(one.clone()))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::one::One;
+    use crate::facets::two::Two;
+    use std::sync::Arc;
+
+    #[facet::container]
+    pub struct OneSubsystem {
+        #[facet]
+        pub one: dyn One,
+    }
+
+    #[facet::container]
+    pub struct TwoSubsystem {
+        #[facet]
+        pub one: dyn One,
+
+        #[facet]
+        pub two: dyn Two,
+    }
+
+    #[facet::container]
+    pub struct Outer {
+        #[facet]
+        pub one: dyn One,
+
+        #[delegate]
+        pub one_subsystem: OneSubsystem,
+
+        #[delegate]
+        pub two_subsystem: Arc<TwoSubsystem>,
+
+        #[init(one_subsystem.one.get() + two_subsystem.two.get())]
+        pub total: u32,
+    }
+}
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use containers::Outer;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+fn check_shared(outer: &Outer) {
+    assert!(Arc::ptr_eq(&outer.one, &outer.one_subsystem.one));
+    assert!(Arc::ptr_eq(&outer.one, &outer.two_subsystem.one));
+    assert_eq!(outer.total, 3);
+}
+
+#[test]
+fn sync_nested() {
+    let factory = SyncFactory::default();
+    let outer = factory.build::<Outer>().unwrap();
+    check_shared(&outer);
+    assert_eq!(factory.ones_built.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn async_nested() {
+    let factory = AsyncFactory::default();
+    let outer = factory.build::<Outer>().await.unwrap();
+    check_shared(&outer);
+    assert_eq!(factory.ones_built.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,151 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod client {
+        #[facet::facet]
+        pub struct Client {
+            pub endpoint: String,
+            pub connection: crate::Connection,
+        }
+    }
+
+    pub mod greeter {
+        #[facet::facet]
+        pub struct Greeter {
+            pub greeting: String,
+        }
+    }
+}
+
+/// A parameter that can't be cloned, so it must be moved into the facet
+/// that uses it.
+#[derive(Debug)]
+pub struct Connection {
+    pub id: u32,
+}
+
+pub mod factories {
+    pub mod simple_factory {
+        use std::sync::Arc;
+
+        use crate::facets::client::{ArcClient, Client};
+        use crate::facets::greeter::{ArcGreeter, Greeter};
+        use crate::Connection;
+
+        pub struct SimpleFactory;
+
+        #[facet::factory(endpoint: String, connection: Connection, name: String)]
+        impl SimpleFactory {
+            fn client(&self, endpoint: String, connection: Connection) -> ArcClient {
+                Arc::new(Client {
+                    endpoint,
+                    connection,
+                })
+            }
+
+            fn greeter(&self, name: &str) -> ArcGreeter {
+                Arc::new(Greeter {
+                    greeting: format!("hello {}", name),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::client::Client;
+    use crate::facets::greeter::Greeter;
+
+    #[facet::container]
+    pub struct Service {
+        #[facet]
+        pub client: Client,
+
+        #[facet]
+        pub greeter: Greeter,
+    }
+
+    #[facet::container]
+    pub struct GreeterOnly {
+        #[facet]
+        pub greeter: Greeter,
+    }
+}
+
+use std::sync::Arc;
+
+use containers::{GreeterOnly, Service};
+use factories::simple_factory::SimpleFactory;
+
+#[test]
+fn params_moved_into_facet() {
+    let service = SimpleFactory
+        .build::<Service>(
+            String::from("https://example.com"),
+            Connection { id: 1 },
+            String::from("world"),
+        )
+        .unwrap();
+
+    assert_eq!(service.client.endpoint, "https://example.com");
+    assert_eq!(service.client.connection.id, 1);
+    assert_eq!(service.greeter.greeting, "hello world");
+}
+
+#[test]
+fn unused_params_are_dropped() {
+    let greeter_only = SimpleFactory
+        .build::<GreeterOnly>(
+            String::from("https://example.com"),
+            Connection { id: 1 },
+            String::from("world"),
+        )
+        .unwrap();
+
+    assert_eq!(greeter_only.greeter.greeting, "hello world");
+}
+
+#[test]
+fn parallel_build() {
+    let service = SimpleFactory
+        .build_parallel::<Service>(
+            String::from("https://example.com"),
+            Connection { id: 2 },
+            String::from("world"),
+        )
+        .unwrap();
+
+    assert_eq!(service.client.connection.id, 2);
+}
+
+#[test]
+fn rebuild_always_rebuilds_consumers() {
+    let existing = SimpleFactory
+        .build_rebuildable::<Service>(
+            String::from("https://example.com"),
+            Connection { id: 1 },
+            String::from("world"),
+        )
+        .unwrap();
+
+    let rebuilt = SimpleFactory
+        .rebuild(
+            &existing,
+            String::from("https://example.com"),
+            Connection { id: 2 },
+            String::from("world"),
+        )
+        .unwrap();
+
+    // The client took parameters by value, so it is always rebuilt, but the
+    // greeter's parameters are unchanged, so it is reused.
+    assert_eq!(rebuilt.client.connection.id, 2);
+    assert!(Arc::ptr_eq(&existing.greeter, &rebuilt.greeter));
+}
@@ -0,0 +1,159 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod one {
+        #[facet::facet]
+        pub struct One(pub u32);
+    }
+
+    pub mod two {
+        #[facet::facet]
+        pub struct Two(pub u32);
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facets::one::{ArcOne, One};
+        use crate::facets::two::{ArcTwo, Two};
+        use std::sync::Arc;
+
+        pub struct SyncFactory;
+
+        #[facet::factory(value: u32, catch_panics)]
+        impl SyncFactory {
+            fn one(&self, value: &u32) -> ArcOne {
+                if *value == 0 {
+                    panic!("value must not be zero");
+                }
+                Arc::new(One(*value))
+            }
+
+            #[facet(propagate_panic)]
+            fn two(&self, value: &u32) -> ArcTwo {
+                if *value == 0 {
+                    panic!("value must not be zero");
+                }
+                Arc::new(Two(*value))
+            }
+        }
+    }
+
+    pub mod default_factory {
+        use crate::facets::one::{ArcOne, One};
+        use std::sync::Arc;
+
+        pub struct DefaultFactory;
+
+        #[facet::factory(value: u32)]
+        impl DefaultFactory {
+            fn one(&self, value: &u32) -> ArcOne {
+                if *value == 0 {
+                    panic!("value must not be zero");
+                }
+                Arc::new(One(*value))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facets::one::{ArcOne, One};
+        use crate::facets::two::{ArcTwo, Two};
+        use std::sync::Arc;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(value: u32, catch_panics)]
+        impl AsyncFactory {
+            async fn one(&self, value: &u32) -> ArcOne {
+                if *value == 0 {
+                    panic!("value {} is invalid", value);
+                }
+                Arc::new(One(*value))
+            }
+
+            fn two(&self, one: &ArcOne) -> ArcTwo {
+                if one.0 == 1 {
+                    panic!("one must not be one");
+                }
+                Arc::new(Two(one.0 * 2))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::one::One;
+    use crate::facets::two::Two;
+
+    #[facet::container]
+    pub struct OneOnly {
+        #[facet]
+        pub one: One,
+    }
+
+    #[facet::container]
+    pub struct TwoOnly {
+        #[facet]
+        pub two: Two,
+    }
+}
+
+use containers::{OneOnly, TwoOnly};
+use factories::async_factory::AsyncFactory;
+use factories::default_factory::DefaultFactory;
+use factories::sync_factory::SyncFactory;
+
+fn assert_panicked<T>(result: Result<T, facet::FactoryError>, facet: &str, panic: &str) {
+    match result {
+        Err(facet::FactoryError::FacetBuildPanicked { name, message }) => {
+            assert_eq!(name, facet);
+            assert_eq!(message, panic);
+        }
+        _ => panic!("build should fail with a panic error"),
+    }
+}
+
+#[test]
+fn sync_panic() {
+    assert_eq!(SyncFactory.build::<OneOnly>(1).unwrap().one.0, 1);
+    assert_panicked(
+        SyncFactory.build::<OneOnly>(0),
+        "one",
+        "value must not be zero",
+    );
+}
+
+#[test]
+#[should_panic(expected = "value must not be zero")]
+fn sync_propagate_panic() {
+    let _ = SyncFactory.build::<TwoOnly>(0);
+}
+
+#[test]
+#[should_panic(expected = "value must not be zero")]
+fn default_propagates_panic() {
+    let _ = DefaultFactory.build::<OneOnly>(0);
+}
+
+#[tokio::test]
+async fn async_panic() {
+    assert_eq!(AsyncFactory.build::<TwoOnly>(2).await.unwrap().two.0, 4);
+    assert_panicked(
+        AsyncFactory.build::<OneOnly>(0).await,
+        "one",
+        "value 0 is invalid",
+    );
+    assert_panicked(
+        AsyncFactory.build::<TwoOnly>(1).await,
+        "two",
+        "one must not be one",
+    );
+}
@@ -0,0 +1,211 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod cache {
+        #[facet::facet]
+        pub struct Cache {
+            pub name: String,
+        }
+    }
+
+    pub mod db {
+        #[facet::facet]
+        pub struct Db {
+            pub name: String,
+        }
+    }
+
+    pub mod service {
+        #[facet::facet]
+        pub trait Service {
+            fn describe(&self) -> String;
+        }
+    }
+
+    pub mod unused {
+        #[facet::facet]
+        pub struct Unused;
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_service {
+        use crate::facets::cache::ArcCache;
+        use crate::facets::db::ArcDb;
+        use crate::facets::service::Service;
+
+        pub struct SimpleService {
+            pub db: ArcDb,
+            pub cache: ArcCache,
+        }
+
+        impl Service for SimpleService {
+            fn describe(&self) -> String {
+                format!("{} + {}", self.db.name, self.cache.name)
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod tracking_factory {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::{Arc, Barrier};
+
+        use crate::facet_impls::simple_service::SimpleService;
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::db::{ArcDb, Db};
+        use crate::facets::service::ArcService;
+        use crate::facets::unused::{ArcUnused, Unused};
+
+        /// Factory which records how many facets were being built at once.
+        #[derive(Default)]
+        pub struct TrackingFactory {
+            pub building: AtomicUsize,
+            pub max_building: AtomicUsize,
+            pub builds: AtomicUsize,
+            barrier: Option<Barrier>,
+        }
+
+        impl TrackingFactory {
+            /// Factory whose db and cache facets wait for each other, so
+            /// that they can only be built if they are built at the same
+            /// time.
+            pub fn parallel() -> Self {
+                Self {
+                    barrier: Some(Barrier::new(2)),
+                    ..Default::default()
+                }
+            }
+
+            fn tracked_build<T>(&self, build: impl FnOnce() -> T) -> T {
+                let building = self.building.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_building.fetch_max(building, Ordering::SeqCst);
+                self.builds.fetch_add(1, Ordering::SeqCst);
+                let facet = build();
+                self.building.fetch_sub(1, Ordering::SeqCst);
+                facet
+            }
+
+            fn wait_for_other_backend(&self) {
+                if let Some(barrier) = &self.barrier {
+                    barrier.wait();
+                }
+            }
+        }
+
+        #[facet::factory(name: String)]
+        impl TrackingFactory {
+            fn db(&self, name: &str) -> ArcDb {
+                self.tracked_build(|| {
+                    self.wait_for_other_backend();
+                    Arc::new(Db {
+                        name: format!("{}-db", name),
+                    })
+                })
+            }
+
+            fn cache(&self, name: &str) -> ArcCache {
+                self.tracked_build(|| {
+                    self.wait_for_other_backend();
+                    Arc::new(Cache {
+                        name: format!("{}-cache", name),
+                    })
+                })
+            }
+
+            fn service(&self, db: &ArcDb, cache: &ArcCache) -> ArcService {
+                self.tracked_build(|| {
+                    Arc::new(SimpleService {
+                        db: db.clone(),
+                        cache: cache.clone(),
+                    })
+                })
+            }
+
+            fn unused(&self) -> ArcUnused {
+                self.tracked_build(|| Arc::new(Unused))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::cache::Cache;
+    use crate::facets::db::Db;
+    use crate::facets::service::Service;
+
+    #[facet::container]
+    pub struct ServiceContainer {
+        #[facet]
+        pub service: dyn Service,
+    }
+
+    #[facet::container]
+    pub struct Backends {
+        #[facet]
+        pub db: Db,
+
+        #[facet]
+        pub cache: Cache,
+    }
+
+    #[facet::container]
+    pub struct Combined {
+        #[delegate(Db, Cache)]
+        pub backends: Backends,
+
+        #[facet]
+        pub service: dyn Service,
+    }
+}
+
+use std::sync::atomic::Ordering;
+
+use containers::{Combined, ServiceContainer};
+use factories::tracking_factory::TrackingFactory;
+
+#[test]
+fn independent_facets_built_in_parallel() {
+    let factory = TrackingFactory::parallel();
+    let container = factory
+        .build_parallel::<ServiceContainer>(String::from("parallel"))
+        .unwrap();
+
+    assert_eq!(container.service.describe(), "parallel-db + parallel-cache");
+    // The db and cache facets don't depend on each other, so they are built
+    // at the same time, which they wait for.  The unused facet is not built.
+    assert_eq!(factory.max_building.load(Ordering::SeqCst), 2);
+    assert_eq!(factory.builds.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn serial_build_is_unchanged() {
+    let factory = TrackingFactory::default();
+    let container = factory
+        .build::<ServiceContainer>(String::from("serial"))
+        .unwrap();
+
+    assert_eq!(container.service.describe(), "serial-db + serial-cache");
+    assert_eq!(factory.max_building.load(Ordering::SeqCst), 1);
+    assert_eq!(factory.builds.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn delegates_share_facets() {
+    let factory = TrackingFactory::parallel();
+    let container = factory
+        .build_parallel::<Combined>(String::from("combined"))
+        .unwrap();
+
+    assert_eq!(container.service.describe(), "combined-db + combined-cache");
+    assert_eq!(container.backends.db.name, "combined-db");
+    assert_eq!(factory.builds.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod server {
+        #[facet::facet]
+        pub struct Server {
+            pub host: String,
+            pub port: u16,
+        }
+    }
+
+    pub mod greeter {
+        #[facet::facet]
+        pub trait Greeter {
+            fn greet(&self) -> String;
+        }
+    }
+
+    pub mod summary {
+        #[facet::facet]
+        pub struct Summary(pub String);
+    }
+}
+
+pub mod facet_impls {
+    use crate::facets::greeter::Greeter;
+
+    pub struct NamedGreeter(pub String);
+
+    impl Greeter for NamedGreeter {
+        fn greet(&self) -> String {
+            format!("hello, {}", self.0)
+        }
+    }
+}
+
+pub mod factories {
+    use crate::facet_impls::NamedGreeter;
+    use crate::facets::greeter::ArcGreeter;
+    use crate::facets::server::{ArcServer, Server};
+    use crate::facets::summary::{ArcSummary, Summary};
+    use std::sync::Arc;
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ServiceParams {
+        pub host: String,
+        pub port: u16,
+        pub name: String,
+    }
+
+    pub struct SyncFactory;
+
+    #[facet::factory(params = ServiceParams)]
+    impl SyncFactory {
+        fn server(&self, host: &str, port: &u16) -> ArcServer {
+            Arc::new(Server {
+                host: host.to_string(),
+                port: *port,
+            })
+        }
+
+        fn greeter(&self, _port: &u16, name: &String) -> ArcGreeter {
+            Arc::new(NamedGreeter(name.clone()))
+        }
+
+        fn summary(&self, server: &ArcServer, params: &ServiceParams) -> ArcSummary {
+            Arc::new(Summary(format!(
+                "{} at {}:{}",
+                params.name, server.host, server.port
+            )))
+        }
+    }
+
+    pub struct AsyncFactory;
+
+    #[facet::factory(params = ServiceParams)]
+    impl AsyncFactory {
+        async fn server(&self, host: &str, port: &u16) -> ArcServer {
+            Arc::new(Server {
+                host: host.to_string(),
+                port: *port,
+            })
+        }
+
+        async fn greeter(&self, server: &ArcServer, name: &str) -> ArcGreeter {
+            Arc::new(NamedGreeter(format!("{} on {}", name, server.host)))
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::greeter::Greeter;
+    use crate::facets::server::Server;
+    use crate::facets::summary::Summary;
+
+    #[facet::container]
+    pub struct Service {
+        #[facet]
+        pub server: Server,
+
+        #[facet]
+        pub greeter: dyn Greeter,
+
+        #[facet]
+        pub summary: Summary,
+    }
+
+    #[facet::container]
+    pub struct Greeting {
+        #[facet]
+        pub greeter: dyn Greeter,
+    }
+}
+
+use containers::{Greeting, Service};
+use factories::{AsyncFactory, ServiceParams, SyncFactory};
+
+fn params() -> ServiceParams {
+    ServiceParams {
+        host: String::from("localhost"),
+        port: 8080,
+        name: String::from("world"),
+    }
+}
+
+#[test]
+fn fields_borrowed_from_params() {
+    let service = SyncFactory.build::<Service>(params()).unwrap();
+    assert_eq!(service.server.host, "localhost");
+    assert_eq!(service.server.port, 8080);
+    assert_eq!(service.greeter.greet(), "hello, world");
+    assert_eq!(service.summary.0, "world at localhost:8080");
+}
+
+#[test]
+fn named_params_struct() {
+    let service = SyncFactory
+        .builder::<Service>()
+        .params(params())
+        .build()
+        .unwrap();
+    assert_eq!(service.summary.0, "world at localhost:8080");
+}
+
+#[tokio::test]
+async fn async_fields_borrowed_from_params() {
+    let greeting = AsyncFactory.build::<Greeting>(params()).await.unwrap();
+    assert_eq!(greeting.greeter.greet(), "hello, world on localhost");
+}
@@ -0,0 +1,191 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod one {
+        #[facet::facet]
+        pub trait One {
+            fn get(&self) -> u32;
+        }
+    }
+
+    pub mod two {
+        #[facet::facet]
+        pub struct Two(pub u32);
+    }
+
+    pub mod three {
+        #[facet::facet]
+        pub struct Three(pub u32);
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_one {
+        use crate::facets::one::One;
+
+        pub struct SimpleOne;
+
+        impl One for SimpleOne {
+            fn get(&self) -> u32 {
+                1
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facet_impls::simple_one::SimpleOne;
+        use crate::facets::one::ArcOne;
+        use crate::facets::three::{ArcThree, Three};
+        use crate::facets::two::{ArcTwo, Two};
+        use std::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        pub struct SyncFactory {
+            pub built: Mutex<Vec<&'static str>>,
+        }
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn one(&self) -> ArcOne {
+                self.built.lock().unwrap().push("one");
+                Arc::new(SimpleOne)
+            }
+
+            fn two(&self, one: &ArcOne) -> ArcTwo {
+                self.built.lock().unwrap().push("two");
+                Arc::new(Two(one.get() * 2))
+            }
+
+            fn three(&self) -> ArcThree {
+                self.built.lock().unwrap().push("three");
+                Arc::new(Three(3))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facet_impls::simple_one::SimpleOne;
+        use crate::facets::one::ArcOne;
+        use crate::facets::three::{ArcThree, Three};
+        use crate::facets::two::{ArcTwo, Two};
+        use std::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        pub struct AsyncFactory {
+            pub built: Mutex<Vec<&'static str>>,
+        }
+
+        #[facet::factory()]
+        impl AsyncFactory {
+            async fn one(&self) -> ArcOne {
+                self.built.lock().unwrap().push("one");
+                Arc::new(SimpleOne)
+            }
+
+            async fn two(&self, one: &ArcOne) -> ArcTwo {
+                self.built.lock().unwrap().push("two");
+                Arc::new(Two(one.get() * 2))
+            }
+
+            async fn three(&self) -> ArcThree {
+                self.built.lock().unwrap().push("three");
+                Arc::new(Three(3))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::one::One;
+    use crate::facets::three::Three;
+    use crate::facets::two::Two;
+
+    #[facet::container]
+    pub struct Everything {
+        #[facet]
+        pub one: dyn One,
+
+        #[facet]
+        pub two: Two,
+
+        #[facet]
+        pub three: Three,
+
+        #[init(three.0 * 100)]
+        pub hundreds: u32,
+    }
+}
+
+use containers::{Everything, PartialEverything};
+use facet::{FacetSet, FactoryError};
+use facets::three::ThreeRef;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+fn get_three(container: impl ThreeRef) -> u32 {
+    container.three().0
+}
+
+fn check_partial(partial: &PartialEverything, built: &[&str]) {
+    assert_eq!(partial.two.as_ref().unwrap().0, 2);
+    assert!(partial.one.is_none());
+    assert!(partial.three.is_none());
+    assert_eq!(built, ["one", "two"]);
+}
+
+#[test]
+fn sync_partial() {
+    let factory = SyncFactory::default();
+    let partial = factory
+        .build_partial::<Everything>(&FacetSet::new().with("two"))
+        .unwrap();
+    check_partial(&partial, &factory.built.lock().unwrap());
+}
+
+#[test]
+#[should_panic(expected = "facet 'three' was not built by this partial build of 'Everything'")]
+fn sync_access_unbuilt() {
+    let partial = SyncFactory::default()
+        .build_partial::<Everything>(&FacetSet::new())
+        .unwrap();
+    get_three(&partial);
+}
+
+#[test]
+fn sync_unknown_field() {
+    let facets = ["two", "hundreds"].into_iter().collect::<FacetSet>();
+    match SyncFactory::default().build_partial::<Everything>(&facets) {
+        Err(FactoryError::UnknownFacetField { container, field }) => {
+            assert_eq!(container, "Everything");
+            assert_eq!(field, "hundreds");
+        }
+        _ => panic!("partial build of unknown field should fail"),
+    }
+}
+
+#[tokio::test]
+async fn async_partial() {
+    let factory = AsyncFactory::default();
+    let partial = factory
+        .build_partial::<Everything>(&FacetSet::new().with("two"))
+        .await
+        .unwrap();
+    check_partial(&partial, &factory.built.lock().unwrap());
+
+    let partial = factory
+        .build_partial::<Everything>(&FacetSet::new().with("three"))
+        .await
+        .unwrap();
+    assert_eq!(get_three(&partial), 3);
+}
@@ -0,0 +1,151 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod registry {
+        use std::sync::Mutex;
+
+        #[facet::facet]
+        #[derive(Default)]
+        pub struct Registry {
+            pub registered: Mutex<Vec<String>>,
+        }
+    }
+
+    pub mod cache {
+        use std::sync::atomic::AtomicBool;
+
+        #[facet::facet]
+        #[derive(Default)]
+        pub struct Cache {
+            pub warm: AtomicBool,
+        }
+    }
+}
+
+pub mod factories {
+    use crate::facets::cache::{ArcCache, Cache};
+    use crate::facets::registry::{ArcRegistry, Registry};
+    use anyhow::{bail, Result};
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    pub struct SyncFactory;
+
+    #[facet::factory(limit: u32)]
+    impl SyncFactory {
+        fn registry(&self) -> ArcRegistry {
+            Arc::new(Registry::default())
+        }
+
+        fn cache(&self) -> ArcCache {
+            Arc::new(Cache::default())
+        }
+
+        #[facet::post_build]
+        fn register_cache(&self, registry: &ArcRegistry, _cache: &ArcCache) {
+            registry
+                .registered
+                .lock()
+                .unwrap()
+                .push(String::from("cache"));
+        }
+
+        #[facet::post_build]
+        fn warm_cache(&self, cache: &ArcCache, limit: &u32) -> Result<()> {
+            if *limit == 0 {
+                bail!("limit must not be zero");
+            }
+            cache.warm.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    pub struct AsyncFactory;
+
+    #[facet::factory()]
+    impl AsyncFactory {
+        async fn registry(&self) -> ArcRegistry {
+            Arc::new(Registry::default())
+        }
+
+        async fn cache(&self) -> ArcCache {
+            Arc::new(Cache::default())
+        }
+
+        #[facet::post_build]
+        async fn warm_cache(&self, cache: &ArcCache, registry: &ArcRegistry) {
+            tokio::task::yield_now().await;
+            cache.warm.store(true, Ordering::SeqCst);
+            registry
+                .registered
+                .lock()
+                .unwrap()
+                .push(String::from("warmed"));
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::cache::Cache;
+    use crate::facets::registry::Registry;
+
+    #[facet::container]
+    pub struct Service {
+        #[facet]
+        pub registry: Registry,
+
+        #[facet]
+        pub cache: Cache,
+    }
+
+    #[facet::container]
+    pub struct RegistryOnly {
+        #[facet]
+        pub registry: Registry,
+    }
+}
+
+use std::sync::atomic::Ordering;
+
+use facet::FactoryError;
+
+use containers::{RegistryOnly, Service};
+use factories::{AsyncFactory, SyncFactory};
+
+#[test]
+fn hooks_run_after_build() {
+    let service = SyncFactory.build::<Service>(10).unwrap();
+    assert_eq!(*service.registry.registered.lock().unwrap(), ["cache"]);
+    assert!(service.cache.warm.load(Ordering::SeqCst));
+}
+
+#[test]
+fn hooks_need_their_facets_built() {
+    let registry_only = SyncFactory.build::<RegistryOnly>(10).unwrap();
+    assert!(registry_only.registry.registered.lock().unwrap().is_empty());
+}
+
+#[test]
+fn failed_hook_fails_build() {
+    match SyncFactory.build::<Service>(0) {
+        Err(FactoryError::PostBuildFailed { name, source }) => {
+            assert_eq!(name, "warm_cache");
+            assert_eq!(source.to_string(), "limit must not be zero");
+        }
+        _ => panic!("expected the post-build hook to fail"),
+    }
+}
+
+#[tokio::test]
+async fn async_hooks_run_after_build() {
+    let service = AsyncFactory.build::<Service>().await.unwrap();
+    assert_eq!(*service.registry.registered.lock().unwrap(), ["warmed"]);
+    assert!(service.cache.warm.load(Ordering::SeqCst));
+}
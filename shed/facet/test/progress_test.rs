@@ -0,0 +1,122 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod config {
+        #[facet::facet]
+        pub struct Config {
+            pub fail: bool,
+        }
+    }
+
+    pub mod database {
+        #[facet::facet]
+        pub struct Database;
+    }
+}
+
+pub mod factories {
+    use std::sync::Arc;
+
+    use anyhow::{bail, Result};
+
+    use crate::facets::config::{ArcConfig, Config};
+    use crate::facets::database::{ArcDatabase, Database};
+
+    pub struct Factory;
+
+    #[facet::factory(fail: bool)]
+    impl Factory {
+        async fn config(&self, fail: &bool) -> ArcConfig {
+            Arc::new(Config { fail: *fail })
+        }
+
+        async fn database(&self, config: &ArcConfig) -> Result<ArcDatabase> {
+            if config.fail {
+                bail!("connection refused");
+            }
+            Ok(Arc::new(Database))
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::database::Database;
+
+    #[facet::container]
+    pub struct DatabaseContainer {
+        #[facet]
+        pub database: Database,
+    }
+}
+
+use std::sync::{Arc, Mutex};
+
+use facet::BuildEvent;
+
+use containers::DatabaseContainer;
+use factories::Factory;
+
+/// Returns a callback that collects build events, and the events it has
+/// collected.
+fn collect() -> (
+    impl Fn(BuildEvent) + Send + Sync + 'static,
+    Arc<Mutex<Vec<BuildEvent>>>,
+) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let collected = events.clone();
+    (move |event| collected.lock().unwrap().push(event), events)
+}
+
+#[tokio::test]
+async fn progress() {
+    let (on_event, events) = collect();
+    Factory
+        .build_with_progress::<DatabaseContainer>(false, on_event)
+        .await
+        .unwrap();
+
+    let events = events.lock().unwrap();
+    assert!(matches!(
+        events.as_slice(),
+        [
+            BuildEvent::Started { facet: "config" },
+            BuildEvent::Completed {
+                facet: "config",
+                ..
+            },
+            BuildEvent::Started { facet: "database" },
+            BuildEvent::Completed {
+                facet: "database",
+                ..
+            },
+        ]
+    ));
+}
+
+#[tokio::test]
+async fn progress_failed() {
+    let (on_event, events) = collect();
+    assert!(Factory
+        .build_with_progress::<DatabaseContainer>(true, on_event)
+        .await
+        .is_err());
+
+    let events = events.lock().unwrap();
+    match events.as_slice() {
+        [BuildEvent::Started { facet: "config" }, BuildEvent::Completed {
+            facet: "config", ..
+        }, BuildEvent::Started { facet: "database" }, BuildEvent::Failed {
+            facet: "database",
+            error,
+            ..
+        }] => assert_eq!(error, "failed to build 'database': connection refused"),
+        events => panic!("unexpected events: {:?}", events),
+    }
+}
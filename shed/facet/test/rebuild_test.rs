@@ -0,0 +1,200 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod clock {
+        #[facet::facet]
+        pub struct Clock;
+    }
+
+    pub mod db {
+        #[facet::facet]
+        pub struct Db(pub String);
+    }
+
+    pub mod pool {
+        #[facet::facet]
+        pub struct Pool(pub u32);
+    }
+
+    pub mod service {
+        #[facet::facet]
+        pub struct Service(pub String);
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facets::clock::{ArcClock, Clock};
+        use crate::facets::db::{ArcDb, Db};
+        use crate::facets::pool::{ArcPool, Pool};
+        use crate::facets::service::{ArcService, Service};
+        use std::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        pub struct SyncFactory {
+            pub built: Mutex<Vec<&'static str>>,
+        }
+
+        #[facet::factory(db_name: String, pool_size: u32)]
+        impl SyncFactory {
+            fn clock(&self) -> ArcClock {
+                self.built.lock().unwrap().push("clock");
+                Arc::new(Clock)
+            }
+
+            fn db(&self, db_name: &str, _clock: &ArcClock) -> ArcDb {
+                self.built.lock().unwrap().push("db");
+                Arc::new(Db(db_name.to_string()))
+            }
+
+            fn pool(&self, pool_size: &u32) -> ArcPool {
+                self.built.lock().unwrap().push("pool");
+                Arc::new(Pool(*pool_size))
+            }
+
+            fn service(&self, db: &ArcDb, pool: &ArcPool) -> ArcService {
+                self.built.lock().unwrap().push("service");
+                Arc::new(Service(format!("{} x{}", db.0, pool.0)))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facets::clock::{ArcClock, Clock};
+        use crate::facets::db::{ArcDb, Db};
+        use crate::facets::pool::{ArcPool, Pool};
+        use crate::facets::service::{ArcService, Service};
+        use std::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        pub struct AsyncFactory {
+            pub built: Mutex<Vec<&'static str>>,
+        }
+
+        #[facet::factory(db_name: String, pool_size: u32)]
+        impl AsyncFactory {
+            async fn clock(&self) -> ArcClock {
+                self.built.lock().unwrap().push("clock");
+                Arc::new(Clock)
+            }
+
+            async fn db(&self, db_name: &str, _clock: &ArcClock) -> ArcDb {
+                self.built.lock().unwrap().push("db");
+                Arc::new(Db(db_name.to_string()))
+            }
+
+            async fn pool(&self, pool_size: &u32) -> ArcPool {
+                self.built.lock().unwrap().push("pool");
+                Arc::new(Pool(*pool_size))
+            }
+
+            async fn service(&self, db: &ArcDb, pool: &ArcPool) -> ArcService {
+                self.built.lock().unwrap().push("service");
+                Arc::new(Service(format!("{} x{}", db.0, pool.0)))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::clock::Clock;
+    use crate::facets::db::Db;
+    use crate::facets::pool::Pool;
+    use crate::facets::service::Service;
+
+    #[facet::container]
+    pub struct App {
+        #[facet]
+        pub clock: Clock,
+
+        #[facet]
+        pub db: Db,
+
+        #[facet]
+        pub pool: Pool,
+
+        #[facet]
+        pub service: Service,
+    }
+}
+
+use std::sync::{Arc, Mutex};
+
+use containers::App;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+fn take_built(built: &Mutex<Vec<&'static str>>) -> Vec<&'static str> {
+    let mut built = std::mem::take(&mut *built.lock().unwrap());
+    built.sort_unstable();
+    built
+}
+
+#[test]
+fn sync_rebuild() {
+    let factory = SyncFactory::default();
+    let first = factory
+        .build_rebuildable::<App>("main".to_string(), 4)
+        .unwrap();
+    assert_eq!(first.service.0, "main x4");
+    assert_eq!(
+        take_built(&factory.built),
+        ["clock", "db", "pool", "service"]
+    );
+
+    // Only the pool and the service that depends on it are rebuilt.
+    let second = factory.rebuild(&first, "main".to_string(), 8).unwrap();
+    assert_eq!(second.service.0, "main x8");
+    assert_eq!(take_built(&factory.built), ["pool", "service"]);
+    assert!(Arc::ptr_eq(&first.db, &second.db));
+    assert!(Arc::ptr_eq(&first.clock, &second.clock));
+    assert!(!Arc::ptr_eq(&first.pool, &second.pool));
+
+    // Nothing is rebuilt if the parameters are unchanged.
+    let third = factory.rebuild(&second, "main".to_string(), 8).unwrap();
+    assert!(take_built(&factory.built).is_empty());
+    assert!(Arc::ptr_eq(&second.service, &third.service));
+
+    let fourth = factory.rebuild(&third, "other".to_string(), 8).unwrap();
+    assert_eq!(fourth.into_inner().service.0, "other x8");
+    assert_eq!(take_built(&factory.built), ["db", "service"]);
+}
+
+#[tokio::test]
+async fn async_rebuild() {
+    let factory = AsyncFactory::default();
+    let first = factory
+        .build_rebuildable::<App>("main".to_string(), 4)
+        .await
+        .unwrap();
+    assert_eq!(first.service.0, "main x4");
+    assert_eq!(
+        take_built(&factory.built),
+        ["clock", "db", "pool", "service"]
+    );
+
+    let second = factory
+        .rebuild(&first, "main".to_string(), 8)
+        .await
+        .unwrap();
+    assert_eq!(second.service.0, "main x8");
+    assert_eq!(take_built(&factory.built), ["pool", "service"]);
+    assert!(Arc::ptr_eq(&first.db, &second.db));
+
+    let third = factory
+        .rebuild(&second, "other".to_string(), 8)
+        .await
+        .unwrap();
+    assert_eq!(third.container().service.0, "other x8");
+    assert_eq!(take_built(&factory.built), ["db", "service"]);
+    assert!(Arc::ptr_eq(&second.pool, &third.pool));
+}
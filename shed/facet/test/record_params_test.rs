@@ -0,0 +1,129 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod value {
+        #[facet::facet]
+        pub trait Value {
+            fn get(&self) -> u32;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_value {
+        use crate::facets::value::Value;
+
+        pub struct SimpleValue(pub u32);
+
+        impl Value for SimpleValue {
+            fn get(&self) -> u32 {
+                self.0
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::simple_value::SimpleValue;
+        use crate::facets::value::ArcValue;
+
+        pub struct SyncFactory;
+
+        #[facet::factory(repo: String, scale: u32)]
+        impl SyncFactory {
+            fn value(&self, scale: &u32) -> ArcValue {
+                Arc::new(SimpleValue(*scale * 10))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::simple_value::SimpleValue;
+        use crate::facets::value::ArcValue;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(repo: String, scale: u32)]
+        impl AsyncFactory {
+            async fn value(&self, scale: &u32) -> ArcValue {
+                Arc::new(SimpleValue(*scale * 10))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use std::marker::PhantomData;
+
+    use crate::facets::value::Value;
+
+    #[facet::container(params(repo: String, scale: u32), record_params)]
+    pub struct RecordingContainer {
+        #[facet]
+        pub value: dyn Value,
+    }
+
+    #[facet::container(params(repo: String), record_params)]
+    pub struct GenericContainer<T: Send + Sync + 'static> {
+        #[init(PhantomData)]
+        pub marker: PhantomData<T>,
+
+        #[facet]
+        pub value: dyn Value,
+    }
+}
+
+use containers::{GenericContainer, RecordingContainer};
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+#[test]
+fn sync_record_params() {
+    let container = SyncFactory
+        .build::<RecordingContainer>("repo".to_string(), 3)
+        .unwrap();
+    assert_eq!(container.build_params().repo, "repo");
+    assert_eq!(container.build_params().scale, 3);
+    assert_eq!(container.value.get(), 30);
+}
+
+#[tokio::test]
+async fn async_record_params() {
+    let container = AsyncFactory
+        .build::<RecordingContainer>("repo".to_string(), 4)
+        .await
+        .unwrap();
+    assert_eq!(container.build_params().repo, "repo");
+    assert_eq!(container.build_params().scale, 4);
+    assert_eq!(container.value.get(), 40);
+}
+
+#[test]
+fn record_params_debug() {
+    let container = SyncFactory
+        .build::<RecordingContainer>("repo".to_string(), 3)
+        .unwrap();
+    let params = format!("{:?}", container.build_params());
+    assert!(params.contains("repo: \"repo\""));
+    assert!(params.contains("scale: 3"));
+}
+
+#[test]
+fn generic_record_params() {
+    let container = SyncFactory
+        .build::<GenericContainer<u64>>("generic".to_string(), 1)
+        .unwrap();
+    assert_eq!(container.build_params().clone().repo, "generic");
+}
@@ -0,0 +1,206 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod greeter {
+        #[facet::facet]
+        pub trait Greeter {
+            fn greet(&self) -> String;
+        }
+    }
+
+    pub mod config {
+        #[facet::facet]
+        pub struct Config {
+            pub greeting: String,
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod greeters {
+        use crate::facets::greeter::Greeter;
+
+        pub struct English;
+
+        impl Greeter for English {
+            fn greet(&self) -> String {
+                String::from("hello")
+            }
+        }
+
+        pub struct French;
+
+        impl Greeter for French {
+            fn greet(&self) -> String {
+                String::from("bonjour")
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod plugin_factory {
+        use std::sync::Arc;
+
+        use anyhow::Error;
+        use facet::FacetRegistry;
+
+        use crate::facets::config::{ArcConfig, Config};
+        use crate::facets::greeter::{ArcGreeter, Greeter};
+
+        pub struct PluginFactory {
+            pub registry: FacetRegistry,
+        }
+
+        #[facet::factory(greeter_name: String)]
+        impl PluginFactory {
+            fn greeter(&self, greeter_name: &str) -> Result<ArcGreeter, Error> {
+                Ok(self
+                    .registry
+                    .resolve::<dyn Greeter + Send + Sync>(greeter_name)?)
+            }
+
+            fn config(&self, greeter: &ArcGreeter) -> ArcConfig {
+                Arc::new(Config {
+                    greeting: greeter.greet(),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::config::Config;
+    use crate::facets::greeter::Greeter;
+
+    #[facet::container]
+    pub struct GreeterContainer {
+        #[facet]
+        pub greeter: dyn Greeter,
+
+        #[facet]
+        pub config: Config,
+    }
+}
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use containers::GreeterContainer;
+use facet::FacetRegistry;
+use facet::FactoryError;
+use facet::RegistryError;
+use facet_impls::greeters::English;
+use facet_impls::greeters::French;
+use facets::greeter::Greeter;
+use factories::plugin_factory::PluginFactory;
+
+type DynGreeter = dyn Greeter + Send + Sync;
+
+fn factory() -> PluginFactory {
+    let registry = FacetRegistry::new();
+    registry
+        .register::<DynGreeter>("english", || Ok(Arc::new(English)))
+        .register::<DynGreeter>("french", || Ok(Arc::new(French)))
+        .register::<DynGreeter>("broken", || Err(anyhow!("no greeting")));
+    PluginFactory { registry }
+}
+
+#[test]
+fn resolve_by_name() {
+    let factory = factory();
+    let container = factory
+        .build::<GreeterContainer>("english".to_string())
+        .unwrap();
+    assert_eq!(container.greeter.greet(), "hello");
+    assert_eq!(container.config.greeting, "hello");
+
+    let container = factory
+        .build::<GreeterContainer>("french".to_string())
+        .unwrap();
+    assert_eq!(container.config.greeting, "bonjour");
+}
+
+#[test]
+fn names() {
+    let factory = factory();
+    assert_eq!(
+        factory.registry.names::<DynGreeter>(),
+        vec!["broken", "english", "french"]
+    );
+    assert!(factory.registry.contains::<DynGreeter>("french"));
+    assert!(!factory.registry.contains::<DynGreeter>("german"));
+    assert!(factory.registry.names::<String>().is_empty());
+}
+
+#[test]
+fn not_registered() {
+    match factory().build::<GreeterContainer>("german".to_string()) {
+        Err(FactoryError::FacetBuildFailed { name, source, .. }) => {
+            assert_eq!(name, "greeter");
+            assert!(matches!(
+                source.downcast_ref::<RegistryError>(),
+                Some(RegistryError::NotRegistered { name, .. }) if name == "german"
+            ));
+        }
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("container should not have built"),
+    }
+}
+
+#[test]
+fn construction_failed() {
+    match factory().build::<GreeterContainer>("broken".to_string()) {
+        Err(FactoryError::FacetBuildFailed { source, .. }) => {
+            assert!(matches!(
+                source.downcast_ref::<RegistryError>(),
+                Some(RegistryError::ConstructionFailed { name, .. }) if name == "broken"
+            ));
+        }
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("container should not have built"),
+    }
+}
+
+#[test]
+fn resolve_only() {
+    let registry = FacetRegistry::new();
+    assert!(matches!(
+        registry.resolve_only::<DynGreeter>(),
+        Err(RegistryError::NoneRegistered { .. })
+    ));
+
+    registry.register::<DynGreeter>("english", || Ok(Arc::new(English)));
+    let greeter = registry.resolve_only::<DynGreeter>().unwrap();
+    assert_eq!(greeter.greet(), "hello");
+
+    registry.register::<DynGreeter>("french", || Ok(Arc::new(French)));
+    assert!(matches!(
+        registry.resolve_only::<DynGreeter>(),
+        Err(RegistryError::Ambiguous { names, .. }) if names == ["english", "french"]
+    ));
+}
+
+#[test]
+#[should_panic(expected = "already registered")]
+fn duplicate_registration() {
+    factory()
+        .registry
+        .register::<DynGreeter>("english", || Ok(Arc::new(French)));
+}
+
+#[test]
+fn global_registry() {
+    facet::registry().register::<DynGreeter>("global_test", || Ok(Arc::new(English)));
+    let greeter = facet::registry()
+        .resolve::<DynGreeter>("global_test")
+        .unwrap();
+    assert_eq!(greeter.greet(), "hello");
+}
@@ -0,0 +1,86 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod client {
+        #[facet::facet(name = "http_client")]
+        pub trait HTTPXClientV2 {
+            fn fetch(&self) -> u32;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_client {
+        use crate::facets::client::HTTPXClientV2;
+
+        pub struct SimpleClient;
+
+        impl HTTPXClientV2 for SimpleClient {
+            fn fetch(&self) -> u32 {
+                200
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod simple_factory {
+        use crate::facet_impls::simple_client::SimpleClient;
+        use crate::facets::client::ArcHTTPXClientV2;
+        use std::sync::Arc;
+
+        pub struct SimpleFactory;
+
+        #[facet::factory()]
+        impl SimpleFactory {
+            fn http_client(&self) -> ArcHTTPXClientV2 {
+                Arc::new(SimpleClient)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::client::HTTPXClientV2;
+
+    #[facet::container]
+    pub struct ClientContainer {
+        #[facet(name = "http_client")]
+        pub client: dyn HTTPXClientV2,
+    }
+}
+
+use facets::client::{HTTPXClientV2Arc, HTTPXClientV2Ref};
+
+fn fetch(container: impl HTTPXClientV2Ref) -> u32 {
+    container.http_client().fetch()
+}
+
+#[test]
+fn renamed() {
+    let container = factories::simple_factory::SimpleFactory
+        .build::<containers::ClientContainer>()
+        .unwrap();
+
+    assert_eq!(container.client.fetch(), 200);
+    assert_eq!(container.http_client_arc().fetch(), 200);
+    assert_eq!(fetch(&container), 200);
+}
+
+#[cfg(feature = "graph")]
+#[test]
+fn renamed_fields() {
+    use facet::ContainerFacets;
+
+    let fields = containers::ClientContainer::facet_fields();
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].path, "client");
+    assert_eq!(fields[0].facet, "http_client");
+}
@@ -0,0 +1,111 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod fast {
+        #[facet::facet]
+        pub struct Fast;
+    }
+
+    pub mod slow {
+        #[facet::facet]
+        pub struct Slow;
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facets::fast::{ArcFast, Fast};
+        use crate::facets::slow::{ArcSlow, Slow};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        pub struct SyncFactory;
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn fast(&self) -> ArcFast {
+                Arc::new(Fast)
+            }
+
+            fn slow(&self, _fast: &ArcFast) -> ArcSlow {
+                std::thread::sleep(Duration::from_millis(50));
+                Arc::new(Slow)
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facets::fast::{ArcFast, Fast};
+        use crate::facets::slow::{ArcSlow, Slow};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory()]
+        impl AsyncFactory {
+            async fn fast(&self) -> ArcFast {
+                Arc::new(Fast)
+            }
+
+            async fn slow(&self, _fast: &ArcFast) -> ArcSlow {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Arc::new(Slow)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::slow::Slow;
+
+    #[facet::container]
+    pub struct SlowContainer {
+        #[facet]
+        pub slow: Slow,
+    }
+}
+
+use std::time::Duration;
+
+use containers::SlowContainer;
+use facet::BuildReport;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+fn check_report(report: &BuildReport) {
+    let names = report
+        .facets
+        .iter()
+        .map(|facet| facet.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["fast", "slow"]);
+
+    let slow = report.facet("slow").unwrap();
+    assert!(slow >= Duration::from_millis(50));
+    assert!(report.total >= slow);
+    assert_eq!(report.slowest()[0].name, "slow");
+    assert!(report.facet("missing").is_none());
+}
+
+#[test]
+fn sync_report() {
+    let (_container, report) = SyncFactory.build_instrumented::<SlowContainer>().unwrap();
+    check_report(&report);
+}
+
+#[tokio::test]
+async fn async_report() {
+    let (_container, report) = AsyncFactory
+        .build_instrumented::<SlowContainer>()
+        .await
+        .unwrap();
+    check_report(&report);
+}
@@ -0,0 +1,165 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod conn {
+        #[facet::facet]
+        pub trait Conn {
+            fn attempt(&self) -> u32;
+        }
+    }
+
+    pub mod client {
+        #[facet::facet]
+        pub struct Client {
+            pub attempt: u32,
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_conn {
+        use crate::facets::conn::Conn;
+
+        pub struct SimpleConn {
+            pub attempt: u32,
+        }
+
+        impl Conn for SimpleConn {
+            fn attempt(&self) -> u32 {
+                self.attempt
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod flaky_factory {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        use anyhow::{anyhow, Error};
+
+        use crate::facet_impls::simple_conn::SimpleConn;
+        use crate::facets::client::{ArcClient, Client};
+        use crate::facets::conn::ArcConn;
+
+        /// Factory whose facets fail until they have been attempted a given
+        /// number of times.
+        #[derive(Default)]
+        pub struct FlakyFactory {
+            pub conn_attempts: AtomicU32,
+            pub client_attempts: AtomicU32,
+        }
+
+        #[facet::factory(failures: u32)]
+        impl FlakyFactory {
+            #[facet(retries = 3, backoff = "1s")]
+            async fn conn(&self, failures: &u32) -> Result<ArcConn, Error> {
+                let attempt = self.conn_attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                if attempt <= *failures {
+                    return Err(anyhow!("connection refused on attempt {}", attempt));
+                }
+                Ok(Arc::new(SimpleConn { attempt }))
+            }
+
+            // Attempts that fail hang until they time out.
+            #[facet(retries = 2, timeout = "100ms")]
+            async fn client(&self, failures: &u32, conn: &ArcConn) -> Result<ArcClient, Error> {
+                let attempt = self.client_attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                if attempt <= *failures {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                }
+                Ok(Arc::new(Client {
+                    attempt: attempt + conn.attempt(),
+                }))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::client::Client;
+    use crate::facets::conn::Conn;
+
+    #[facet::container]
+    pub struct ConnContainer {
+        #[facet]
+        pub conn: dyn Conn,
+    }
+
+    #[facet::container]
+    pub struct ClientContainer {
+        #[facet]
+        pub client: Client,
+    }
+}
+
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use containers::{ClientContainer, ConnContainer};
+use facet::FactoryError;
+use factories::flaky_factory::FlakyFactory;
+
+#[tokio::test(start_paused = true)]
+async fn retries_until_success() {
+    let factory = FlakyFactory::default();
+    let start = tokio::time::Instant::now();
+    let container = factory.build::<ConnContainer>(2).await.unwrap();
+    assert_eq!(container.conn.attempt(), 3);
+    assert_eq!(factory.conn_attempts.load(Ordering::SeqCst), 3);
+
+    // The backoff doubles after each retry.
+    assert_eq!(start.elapsed(), Duration::from_secs(3));
+}
+
+#[tokio::test(start_paused = true)]
+async fn retries_exhausted() {
+    let factory = FlakyFactory::default();
+    let error = match factory.build::<ClientContainer>(10).await {
+        Err(error) => error,
+        Ok(_) => panic!("container should not have built"),
+    };
+    assert_eq!(error.path(), ["client", "conn"]);
+    match error.failure() {
+        FactoryError::FacetBuildRetriesExhausted {
+            name,
+            attempts,
+            source,
+        } => {
+            assert_eq!(*name, "conn");
+            assert_eq!(*attempts, 4);
+            assert_eq!(source.to_string(), "connection refused on attempt 4");
+        }
+        e => panic!("unexpected error: {}", e),
+    }
+    assert_eq!(factory.conn_attempts.load(Ordering::SeqCst), 4);
+    assert_eq!(factory.client_attempts.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn retries_timeouts() {
+    let factory = FlakyFactory::default();
+    let container = factory.build::<ClientContainer>(1).await.unwrap();
+    assert_eq!(container.client.attempt, 4);
+    assert_eq!(factory.client_attempts.load(Ordering::SeqCst), 2);
+
+    // A second build whose attempts all time out fails with the timeout
+    // as the final error.
+    let factory = FlakyFactory::default();
+    match factory.build::<ClientContainer>(3).await {
+        Err(e @ FactoryError::FacetBuildRetriesExhausted { .. }) => {
+            assert_eq!(e.to_string(), "failed to build 'client' after 3 attempts");
+        }
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("container should not have built"),
+    }
+}
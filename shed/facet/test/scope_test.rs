@@ -0,0 +1,230 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod pool {
+        #[facet::facet]
+        pub trait Pool {
+            fn id(&self) -> usize;
+        }
+    }
+
+    pub mod session {
+        #[facet::facet]
+        pub trait Session {
+            fn name(&self) -> &str;
+            fn pool_id(&self) -> usize;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_pool {
+        use crate::facets::pool::Pool;
+
+        pub struct SimplePool(pub usize);
+
+        impl Pool for SimplePool {
+            fn id(&self) -> usize {
+                self.0
+            }
+        }
+    }
+
+    pub mod simple_session {
+        use crate::facets::pool::ArcPool;
+        use crate::facets::session::Session;
+
+        pub struct SimpleSession {
+            pub name: String,
+            pub pool: ArcPool,
+        }
+
+        impl Session for SimpleSession {
+            fn name(&self) -> &str {
+                &self.name
+            }
+
+            fn pool_id(&self) -> usize {
+                self.pool.id()
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facet_impls::simple_pool::SimplePool;
+        use crate::facet_impls::simple_session::SimpleSession;
+        use crate::facets::pool::ArcPool;
+        use crate::facets::session::ArcSession;
+        use facet::{FacetCache, FactoryScope};
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        pub struct SyncFactory {
+            pub pools_built: AtomicUsize,
+            pub cache: FacetCache,
+        }
+
+        impl FactoryScope for SyncFactory {
+            fn facet_cache(&self) -> &FacetCache {
+                &self.cache
+            }
+        }
+
+        #[facet::factory(name: String)]
+        impl SyncFactory {
+            #[facet(scope = "factory")]
+            fn pool(&self) -> ArcPool {
+                let id = self.pools_built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimplePool(id))
+            }
+
+            fn session(&self, name: &str, pool: &ArcPool) -> ArcSession {
+                Arc::new(SimpleSession {
+                    name: name.to_string(),
+                    pool: pool.clone(),
+                })
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facet_impls::simple_pool::SimplePool;
+        use crate::facet_impls::simple_session::SimpleSession;
+        use crate::facets::pool::ArcPool;
+        use crate::facets::session::ArcSession;
+        use facet::{FacetCache, FactoryScope};
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        pub struct AsyncFactory {
+            pub pools_built: AtomicUsize,
+            pub sessions_built: AtomicUsize,
+            pub cache: FacetCache,
+        }
+
+        impl FactoryScope for AsyncFactory {
+            fn facet_cache(&self) -> &FacetCache {
+                &self.cache
+            }
+        }
+
+        #[facet::factory(name: String)]
+        impl AsyncFactory {
+            #[facet(scope = "factory")]
+            async fn pool(&self) -> ArcPool {
+                let id = self.pools_built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimplePool(id))
+            }
+
+            #[facet(scope = "factory")]
+            async fn session(&self, pool: &ArcPool) -> ArcSession {
+                self.sessions_built.fetch_add(1, Ordering::SeqCst);
+                Arc::new(SimpleSession {
+                    name: String::from("shared"),
+                    pool: pool.clone(),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::pool::Pool;
+    use crate::facets::session::Session;
+
+    #[facet::container]
+    pub struct PoolContainer {
+        #[facet]
+        pub pool: dyn Pool,
+    }
+
+    #[facet::container]
+    pub struct SessionContainer {
+        #[facet]
+        pub session: dyn Session,
+    }
+}
+
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+use containers::{PoolContainer, SessionContainer};
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+#[test]
+fn shared_across_builds() {
+    let factory = SyncFactory::default();
+    let first = factory
+        .build::<SessionContainer>(String::from("first"))
+        .unwrap();
+    let second = factory
+        .build::<SessionContainer>(String::from("second"))
+        .unwrap();
+    let pool = factory
+        .build::<PoolContainer>(String::from("pool"))
+        .unwrap();
+
+    assert_eq!(factory.pools_built.load(Ordering::SeqCst), 1);
+    assert!(factory.cache.contains("pool"));
+    assert!(!factory.cache.contains("session"));
+    assert_eq!(first.session.name(), "first");
+    assert_eq!(second.session.name(), "second");
+    assert!(!Arc::ptr_eq(&first.session, &second.session));
+    assert_eq!(first.session.pool_id(), pool.pool.id());
+    assert_eq!(second.session.pool_id(), pool.pool.id());
+}
+
+#[test]
+fn cleared_cache() {
+    let factory = SyncFactory::default();
+    let first = factory
+        .build::<PoolContainer>(String::from("first"))
+        .unwrap();
+    factory.cache.clear();
+    let second = factory
+        .build::<PoolContainer>(String::from("second"))
+        .unwrap();
+
+    assert_eq!(factory.pools_built.load(Ordering::SeqCst), 2);
+    assert_eq!(first.pool.id(), 0);
+    assert_eq!(second.pool.id(), 1);
+}
+
+#[tokio::test]
+async fn async_factory() {
+    let factory = AsyncFactory::default();
+    let first = factory
+        .build::<SessionContainer>(String::from("first"))
+        .await
+        .unwrap();
+    let second = factory
+        .build::<SessionContainer>(String::from("second"))
+        .await
+        .unwrap();
+
+    assert!(Arc::ptr_eq(&first.session, &second.session));
+    assert_eq!(second.session.name(), "shared");
+    assert_eq!(factory.sessions_built.load(Ordering::SeqCst), 1);
+    assert_eq!(factory.pools_built.load(Ordering::SeqCst), 1);
+
+    // The pool was cached when the session was first built.
+    let pool = factory
+        .build::<PoolContainer>(String::from("pool"))
+        .await
+        .unwrap();
+    assert_eq!(pool.pool.id(), 0);
+    assert_eq!(first.session.pool_id(), 0);
+    assert_eq!(factory.pools_built.load(Ordering::SeqCst), 1);
+}
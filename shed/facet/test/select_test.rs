@@ -0,0 +1,211 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod store {
+        #[facet::facet]
+        pub trait Store {
+            fn version(&self) -> u32;
+        }
+    }
+
+    pub mod cache {
+        #[facet::facet]
+        pub struct Cache {
+            pub size: u32,
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod versioned_store {
+        use crate::facets::store::Store;
+
+        pub struct VersionedStore(pub u32);
+
+        impl Store for VersionedStore {
+            fn version(&self) -> u32 {
+                self.0
+            }
+        }
+    }
+}
+
+pub mod factories {
+    #[derive(Clone, Debug, Default)]
+    pub struct Config {
+        pub use_v2: bool,
+        pub use_v3: bool,
+    }
+
+    pub mod sync_factory {
+        use std::sync::Arc;
+
+        use super::Config;
+        use crate::facet_impls::versioned_store::VersionedStore;
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::store::ArcStore;
+
+        pub struct SyncFactory;
+
+        #[facet::factory(config: Config)]
+        impl SyncFactory {
+            fn cache(&self) -> ArcCache {
+                Arc::new(Cache { size: 2 })
+            }
+
+            fn store(&self) -> ArcStore {
+                Arc::new(VersionedStore(1))
+            }
+
+            #[facet(alternate_of = store, select = "config.use_v3")]
+            fn store_v3(&self, _config: &Config) -> ArcStore {
+                Arc::new(VersionedStore(3))
+            }
+
+            #[facet(alternate_of = store, select = "config.use_v2")]
+            fn store_v2(&self, cache: &ArcCache) -> ArcStore {
+                Arc::new(VersionedStore(cache.size))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use std::sync::Arc;
+
+        use super::Config;
+        use crate::facet_impls::versioned_store::VersionedStore;
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::store::ArcStore;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(config: Config)]
+        impl AsyncFactory {
+            fn cache(&self) -> ArcCache {
+                Arc::new(Cache { size: 2 })
+            }
+
+            fn store(&self, config: &Config) -> Result<ArcStore, anyhow::Error> {
+                if config.use_v3 {
+                    anyhow::bail!("v3 is not supported");
+                }
+                Ok(Arc::new(VersionedStore(1)))
+            }
+
+            #[facet(alternate_of = store, select = "config.use_v2")]
+            async fn store_v2(&self, cache: &ArcCache) -> Result<ArcStore, anyhow::Error> {
+                Ok(Arc::new(VersionedStore(cache.size)))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::store::Store;
+
+    #[facet::container]
+    pub struct StoreContainer {
+        #[facet]
+        pub store: dyn Store,
+    }
+}
+
+use containers::StoreContainer;
+use facet::FactoryError;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+use factories::Config;
+
+#[test]
+fn default_method() {
+    let container = SyncFactory
+        .build::<StoreContainer>(Config::default())
+        .unwrap();
+    assert_eq!(container.store.version(), 1);
+}
+
+#[test]
+fn selected_alternate() {
+    let config = Config {
+        use_v2: true,
+        ..Default::default()
+    };
+    let container = SyncFactory.build::<StoreContainer>(config).unwrap();
+    assert_eq!(container.store.version(), 2);
+}
+
+#[test]
+fn first_selected_alternate() {
+    let config = Config {
+        use_v2: true,
+        use_v3: true,
+    };
+    let container = SyncFactory.build::<StoreContainer>(config).unwrap();
+    assert_eq!(container.store.version(), 3);
+}
+
+#[tokio::test]
+async fn async_alternate() {
+    let config = Config {
+        use_v2: true,
+        ..Default::default()
+    };
+    let container = AsyncFactory.build::<StoreContainer>(config).await.unwrap();
+    assert_eq!(container.store.version(), 2);
+
+    let container = AsyncFactory
+        .build::<StoreContainer>(Config::default())
+        .await
+        .unwrap();
+    assert_eq!(container.store.version(), 1);
+}
+
+#[tokio::test]
+async fn failing_default_method() {
+    let config = Config {
+        use_v3: true,
+        ..Default::default()
+    };
+    match AsyncFactory.build::<StoreContainer>(config).await {
+        Err(FactoryError::FacetBuildFailed { name, .. }) => assert_eq!(name, "store"),
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("container should not have built"),
+    }
+}
+
+#[cfg(feature = "graph")]
+#[test]
+fn graph_includes_alternates() {
+    use facet::FacetAlternate;
+
+    let graph = SyncFactory::facet_graph();
+    let store = graph.facet("store").unwrap();
+    assert_eq!(store.dependencies, ["cache"]);
+    assert_eq!(store.params, ["config"]);
+    assert_eq!(
+        store.alternates,
+        [
+            FacetAlternate {
+                method: "store_v3",
+                condition: "config.use_v3",
+                dependencies: vec![],
+                params: vec!["config"],
+            },
+            FacetAlternate {
+                method: "store_v2",
+                condition: "config.use_v2",
+                dependencies: vec!["cache"],
+                params: vec![],
+            },
+        ]
+    );
+    assert!(graph.facet("store_v2").is_none());
+    assert!(graph.facet("cache").unwrap().alternates.is_empty());
+}
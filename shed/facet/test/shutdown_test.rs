@@ -0,0 +1,217 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::{Arc, Mutex};
+
+pub type Log = Arc<Mutex<Vec<&'static str>>>;
+
+pub mod facets {
+    pub mod db {
+        use facet::FacetShutdown;
+
+        #[facet::facet]
+        pub trait Db: FacetShutdown {}
+    }
+
+    pub mod cache {
+        use facet::FacetShutdown;
+
+        #[facet::facet]
+        pub trait Cache: FacetShutdown {}
+    }
+
+    pub mod service {
+        use facet::FacetShutdown;
+
+        #[facet::facet]
+        pub trait Service: FacetShutdown {}
+    }
+}
+
+pub mod facet_impls {
+    pub mod logged {
+        use crate::facets::cache::Cache;
+        use crate::facets::db::Db;
+        use crate::facets::service::Service;
+        use crate::Log;
+        use facet::FacetShutdown;
+
+        pub struct Logged {
+            pub name: &'static str,
+            pub log: Log,
+        }
+
+        #[async_trait::async_trait]
+        impl FacetShutdown for Logged {
+            async fn shutdown(&self) {
+                tokio::task::yield_now().await;
+                self.log.lock().unwrap().push(self.name);
+            }
+        }
+
+        impl Db for Logged {}
+        impl Cache for Logged {}
+        impl Service for Logged {}
+    }
+}
+
+pub mod factories {
+    pub mod shutdown_factory {
+        use crate::facet_impls::logged::Logged;
+        use crate::facets::cache::ArcCache;
+        use crate::facets::db::ArcDb;
+        use crate::facets::service::ArcService;
+        use crate::Log;
+        use std::sync::Arc;
+
+        pub struct ShutdownFactory;
+
+        #[facet::factory(log: Log)]
+        impl ShutdownFactory {
+            fn service(&self, _cache: &ArcCache, _db: &ArcDb, log: &Log) -> ArcService {
+                Arc::new(Logged {
+                    name: "service",
+                    log: log.clone(),
+                })
+            }
+
+            fn cache(&self, _db: &ArcDb, log: &Log) -> ArcCache {
+                Arc::new(Logged {
+                    name: "cache",
+                    log: log.clone(),
+                })
+            }
+
+            fn db(&self, log: &Log) -> ArcDb {
+                Arc::new(Logged {
+                    name: "db",
+                    log: log.clone(),
+                })
+            }
+        }
+    }
+
+    pub mod async_shutdown_factory {
+        use crate::facet_impls::logged::Logged;
+        use crate::facets::cache::ArcCache;
+        use crate::facets::db::ArcDb;
+        use crate::facets::service::ArcService;
+        use crate::Log;
+        use std::sync::Arc;
+
+        pub struct AsyncShutdownFactory;
+
+        #[facet::factory(log: Log)]
+        impl AsyncShutdownFactory {
+            async fn service(&self, _cache: &ArcCache, log: &Log) -> ArcService {
+                Arc::new(Logged {
+                    name: "service",
+                    log: log.clone(),
+                })
+            }
+
+            async fn cache(&self, _db: &ArcDb, log: &Log) -> ArcCache {
+                Arc::new(Logged {
+                    name: "cache",
+                    log: log.clone(),
+                })
+            }
+
+            async fn db(&self, log: &Log) -> ArcDb {
+                Arc::new(Logged {
+                    name: "db",
+                    log: log.clone(),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::cache::Cache;
+    use crate::facets::db::Db;
+    use crate::facets::service::Service;
+    use std::sync::Arc;
+
+    #[facet::container]
+    pub struct All {
+        #[facet(shutdown)]
+        db: dyn Db,
+
+        #[facet(shutdown)]
+        service: dyn Service,
+
+        #[facet]
+        cache: dyn Cache,
+    }
+
+    #[facet::container]
+    pub struct Inner {
+        #[facet(shutdown)]
+        db: dyn Db,
+
+        #[facet(shutdown)]
+        cache: dyn Cache,
+    }
+
+    #[facet::container]
+    pub struct Outer {
+        #[facet(shutdown)]
+        db: dyn Db,
+
+        #[facet(shutdown)]
+        service: dyn Service,
+
+        #[delegate(dyn Cache)]
+        inner: Arc<Inner>,
+    }
+}
+
+use facet::ContainerShutdown;
+use factories::async_shutdown_factory::AsyncShutdownFactory;
+use factories::shutdown_factory::ShutdownFactory;
+
+#[tokio::test]
+async fn reverse_dependency_order() {
+    let log = Log::default();
+    let all = ShutdownFactory
+        .build::<containers::All>(log.clone())
+        .unwrap();
+
+    all.shutdown().await;
+
+    // Only facets marked for shutdown are shut down.
+    assert_eq!(*log.lock().unwrap(), ["service", "db"]);
+}
+
+#[tokio::test]
+async fn nested_containers() {
+    let log = Log::default();
+    let outer = ShutdownFactory
+        .build::<containers::Outer>(log.clone())
+        .unwrap();
+
+    outer.shutdown().await;
+
+    // The shared `db` facet is only shut down once.
+    assert_eq!(*log.lock().unwrap(), ["service", "cache", "db"]);
+}
+
+#[tokio::test]
+async fn async_factory() {
+    let log = Log::default();
+    let outer = AsyncShutdownFactory
+        .build::<containers::Outer>(log.clone())
+        .await
+        .unwrap();
+
+    outer.shutdown().await;
+
+    assert_eq!(*log.lock().unwrap(), ["service", "cache", "db"]);
+}
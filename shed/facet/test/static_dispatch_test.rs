@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod one {
+        #[facet::facet]
+        pub trait One {
+            fn get(&self) -> u32;
+        }
+    }
+
+    pub mod two {
+        #[facet::facet]
+        pub trait Two {
+            fn get(&self) -> u32;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_one {
+        use crate::facets::one::One;
+
+        pub struct SimpleOne;
+
+        impl One for SimpleOne {
+            fn get(&self) -> u32 {
+                1
+            }
+        }
+    }
+
+    pub mod combined_two {
+        use crate::facet_impls::simple_one::SimpleOne;
+        use crate::facets::two::Two;
+        use std::sync::Arc;
+
+        pub struct CombinedTwo(pub Arc<SimpleOne>);
+
+        impl Two for CombinedTwo {
+            fn get(&self) -> u32 {
+                use crate::facets::one::One;
+                self.0.get() * 2
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facet_impls::combined_two::CombinedTwo;
+        use crate::facet_impls::simple_one::SimpleOne;
+        use std::sync::Arc;
+
+        pub struct SyncFactory;
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn one(&self) -> Arc<SimpleOne> {
+                Arc::new(SimpleOne)
+            }
+
+            fn two(&self, one: &Arc<SimpleOne>) -> Result<Arc<CombinedTwo>, anyhow::Error> {
+                Ok(Arc::new(CombinedTwo(one.clone())))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facet_impls::combined_two::CombinedTwo;
+        use crate::facet_impls::simple_one::SimpleOne;
+        use std::sync::Arc;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory()]
+        impl AsyncFactory {
+            async fn one(&self) -> Arc<SimpleOne> {
+                Arc::new(SimpleOne)
+            }
+
+            async fn two(&self, one: &Arc<SimpleOne>) -> Arc<CombinedTwo> {
+                Arc::new(CombinedTwo(one.clone()))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::one::One;
+    use crate::facets::two::Two;
+
+    #[facet::container(static_dispatch)]
+    pub struct StaticContainer {
+        #[facet]
+        pub one: dyn One,
+
+        #[facet]
+        pub two: dyn Two,
+
+        #[init(one.get() + two.get())]
+        pub total: u32,
+    }
+}
+
+use std::sync::Arc;
+
+use containers::StaticContainer;
+use facet_impls::combined_two::CombinedTwo;
+use facet_impls::simple_one::SimpleOne;
+use facets::one::{One, OneArc, OneRef};
+use facets::two::{Two, TwoRef};
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+fn check_facets(one: &Arc<SimpleOne>, two: &Arc<CombinedTwo>) {
+    assert!(Arc::ptr_eq(one, &two.0));
+}
+
+fn check_dynamic(container: impl OneArc + TwoRef + Copy) {
+    // The facets can still be accessed dynamically.
+    assert_eq!(container.one().get(), 1);
+    assert_eq!(container.one_arc().get(), 1);
+    assert_eq!(container.two().get(), 2);
+    assert_eq!(get_one(container), 1);
+}
+
+fn get_one(container: impl OneRef) -> u32 {
+    container.one().get()
+}
+
+#[test]
+fn sync_static_dispatch() {
+    let container = SyncFactory.build::<StaticContainer<SyncFactory>>().unwrap();
+    check_facets(&container.one, &container.two);
+    check_dynamic(&container);
+    assert_eq!(container.total, 3);
+}
+
+#[tokio::test]
+async fn async_static_dispatch() {
+    let container = AsyncFactory
+        .build::<StaticContainer<AsyncFactory>>()
+        .await
+        .unwrap();
+    check_facets(&container.one, &container.two);
+    check_dynamic(&container);
+    assert_eq!(container.two.get(), 2);
+    assert_eq!(container.one.get(), 1);
+}
@@ -0,0 +1,282 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod one {
+        #[facet::facet]
+        pub struct One(pub u32);
+    }
+
+    pub mod two {
+        #[facet::facet]
+        pub struct Two;
+    }
+
+    pub mod three {
+        #[facet::facet]
+        pub struct Three(pub u32);
+    }
+
+    pub mod four {
+        #[facet::facet]
+        pub struct Four;
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facets::one::{ArcOne, One};
+        use crate::facets::two::{ArcTwo, Two};
+        use anyhow::{bail, Result};
+        use std::sync::Arc;
+
+        pub struct SyncFactory;
+
+        #[facet::factory(value: u32, catch_panics)]
+        impl SyncFactory {
+            fn one(&self, value: &u32) -> Result<ArcOne> {
+                if *value == 0 {
+                    bail!("value must not be zero");
+                }
+                Ok(Arc::new(One(*value)))
+            }
+
+            fn two(&self, one: &ArcOne) -> ArcTwo {
+                if one.0 == 1 {
+                    panic!("one must not be one");
+                }
+                Arc::new(Two)
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facets::four::{ArcFour, Four};
+        use crate::facets::three::{ArcThree, Three};
+        use anyhow::{bail, Result};
+        use std::sync::Arc;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(value: u32, catch_panics)]
+        impl AsyncFactory {
+            async fn three(&self, value: &u32) -> Result<ArcThree> {
+                if *value == 0 {
+                    bail!("value must not be zero");
+                }
+                Ok(Arc::new(Three(*value)))
+            }
+
+            async fn four(&self, three: &ArcThree) -> ArcFour {
+                if three.0 == 1 {
+                    panic!("three must not be one");
+                }
+                Arc::new(Four)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::four::Four;
+    use crate::facets::one::One;
+    use crate::facets::three::Three;
+    use crate::facets::two::Two;
+
+    #[facet::container]
+    pub struct OneOnly {
+        #[facet]
+        pub one: One,
+    }
+
+    #[facet::container]
+    pub struct OneAndTwo {
+        #[facet]
+        pub one: One,
+
+        #[facet]
+        pub two: Two,
+    }
+
+    #[facet::container]
+    pub struct ThreeOnly {
+        #[facet]
+        pub three: Three,
+    }
+
+    #[facet::container]
+    pub struct ThreeAndFour {
+        #[facet]
+        pub three: Three,
+
+        #[facet]
+        pub four: Four,
+    }
+}
+
+use std::collections::HashMap;
+use std::sync::{Mutex, Once};
+use std::time::Duration;
+
+use stats_traits::stat_types::{BoxCounter, BoxHistogram, BoxTimeseries};
+use stats_traits::stat_types::{Counter, Histogram, Timeseries};
+use stats_traits::stats_manager::{
+    AggregationType, BoxStatsManager, BucketConfig, StatsManager, StatsManagerFactory,
+};
+
+use containers::{OneAndTwo, OneOnly, ThreeAndFour, ThreeOnly};
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+/// Values recorded for each stat, by stat name.
+static RECORDED: Mutex<Option<HashMap<String, Vec<i64>>>> = Mutex::new(None);
+
+/// Stat that records each value added to it.
+struct RecordingStat(String);
+
+impl RecordingStat {
+    fn record(&self, value: i64) {
+        RECORDED
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .entry(self.0.clone())
+            .or_default()
+            .push(value);
+    }
+}
+
+impl Counter for RecordingStat {
+    fn increment_value(&self, value: i64) {
+        self.record(value);
+    }
+}
+
+impl Timeseries for RecordingStat {
+    fn add_value(&self, value: i64) {
+        self.record(value);
+    }
+
+    fn add_value_aggregated(&self, value: i64, _nsamples: u32) {
+        self.record(value);
+    }
+}
+
+impl Histogram for RecordingStat {
+    fn add_value(&self, value: i64) {
+        self.record(value);
+    }
+
+    fn add_repeated_value(&self, value: i64, nsamples: u32) {
+        for _ in 0..nsamples {
+            self.record(value);
+        }
+    }
+}
+
+struct RecordingStatsManager;
+
+impl StatsManager for RecordingStatsManager {
+    fn aggregate(&self) {}
+
+    fn create_counter(&self, name: &str) -> BoxCounter {
+        Box::new(RecordingStat(name.to_string()))
+    }
+
+    fn create_timeseries(
+        &self,
+        name: &str,
+        _aggregation_types: &[AggregationType],
+        _intervals: &[Duration],
+    ) -> BoxTimeseries {
+        Box::new(RecordingStat(name.to_string()))
+    }
+
+    fn create_histogram(
+        &self,
+        name: &str,
+        _aggregation_types: &[AggregationType],
+        _conf: BucketConfig,
+        _percentiles: &[u8],
+    ) -> BoxHistogram {
+        Box::new(RecordingStat(name.to_string()))
+    }
+}
+
+struct RecordingStatsManagerFactory;
+
+impl StatsManagerFactory for RecordingStatsManagerFactory {
+    fn create(&self) -> BoxStatsManager {
+        Box::new(RecordingStatsManager)
+    }
+}
+
+fn register() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| stats::register_stats_manager_factory(RecordingStatsManagerFactory));
+}
+
+/// Returns the sum of the values recorded for a stat.
+fn sum(name: &str) -> i64 {
+    values(name).iter().sum()
+}
+
+/// Returns the values recorded for a stat.
+fn values(name: &str) -> Vec<i64> {
+    RECORDED
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|recorded| recorded.get(name).cloned())
+        .unwrap_or_default()
+}
+
+#[test]
+fn sync_stats() {
+    register();
+
+    SyncFactory.build::<OneOnly>(2).unwrap();
+    assert_eq!(sum("facet.one.build_count"), 1);
+    assert_eq!(values("facet.one.build_time_ms").len(), 1);
+    assert_eq!(sum("facet.one.build_errors"), 0);
+
+    assert!(SyncFactory.build::<OneOnly>(0).is_err());
+    assert_eq!(sum("facet.one.build_count"), 2);
+    assert_eq!(values("facet.one.build_time_ms").len(), 1);
+    assert_eq!(sum("facet.one.build_errors"), 1);
+
+    assert!(SyncFactory.build::<OneAndTwo>(1).is_err());
+    assert_eq!(sum("facet.one.build_count"), 3);
+    assert_eq!(values("facet.one.build_time_ms").len(), 2);
+    assert_eq!(sum("facet.two.build_count"), 1);
+    assert_eq!(values("facet.two.build_time_ms").len(), 0);
+    assert_eq!(sum("facet.two.build_errors"), 1);
+}
+
+#[tokio::test]
+async fn async_stats() {
+    register();
+
+    AsyncFactory.build::<ThreeOnly>(2).await.unwrap();
+    assert_eq!(sum("facet.three.build_count"), 1);
+    assert_eq!(values("facet.three.build_time_ms").len(), 1);
+    assert_eq!(sum("facet.three.build_errors"), 0);
+
+    assert!(AsyncFactory.build::<ThreeOnly>(0).await.is_err());
+    assert_eq!(sum("facet.three.build_count"), 2);
+    assert_eq!(values("facet.three.build_time_ms").len(), 1);
+    assert_eq!(sum("facet.three.build_errors"), 1);
+
+    assert!(AsyncFactory.build::<ThreeAndFour>(1).await.is_err());
+    assert_eq!(sum("facet.three.build_count"), 3);
+    assert_eq!(values("facet.three.build_time_ms").len(), 2);
+    assert_eq!(sum("facet.four.build_count"), 1);
+    assert_eq!(values("facet.four.build_time_ms").len(), 0);
+    assert_eq!(sum("facet.four.build_errors"), 1);
+}
@@ -0,0 +1,116 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod base {
+        #[facet::facet]
+        pub trait Base {
+            fn base(&self) -> u32;
+        }
+    }
+
+    pub mod named {
+        #[facet::facet]
+        pub trait Named {
+            fn name(&self) -> &str;
+        }
+    }
+
+    pub mod special {
+        use crate::facets::base::Base;
+        use crate::facets::named::Named;
+
+        #[facet::facet]
+        pub trait Special: Base + Named {
+            fn special(&self) -> u32;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_special {
+        use crate::facets::base::Base;
+        use crate::facets::named::Named;
+        use crate::facets::special::Special;
+
+        pub struct SimpleSpecial;
+
+        impl Base for SimpleSpecial {
+            fn base(&self) -> u32 {
+                1
+            }
+        }
+
+        impl Named for SimpleSpecial {
+            fn name(&self) -> &str {
+                "simple"
+            }
+        }
+
+        impl Special for SimpleSpecial {
+            fn special(&self) -> u32 {
+                2
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod simple_factory {
+        use crate::facet_impls::simple_special::SimpleSpecial;
+        use crate::facets::special::ArcSpecial;
+        use std::sync::Arc;
+
+        pub struct SimpleFactory;
+
+        #[facet::factory()]
+        impl SimpleFactory {
+            fn special(&self) -> ArcSpecial {
+                Arc::new(SimpleSpecial)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::special::Special;
+
+    #[facet::container]
+    pub struct SpecialContainer {
+        #[facet(supertraits(crate::facets::base::Base, crate::facets::named::Named))]
+        pub special: dyn Special,
+    }
+}
+
+use std::sync::Arc;
+
+use facets::base::{BaseArc, BaseRef};
+use facets::named::NamedRef;
+use facets::special::SpecialRef;
+
+fn get_base(container: impl BaseRef) -> u32 {
+    container.base().base()
+}
+
+#[test]
+fn upcast() {
+    let container = factories::simple_factory::SimpleFactory
+        .build::<containers::SpecialContainer>()
+        .unwrap();
+
+    assert_eq!(container.special().special(), 2);
+    assert_eq!(container.base().base(), 1);
+    assert_eq!(container.named().name(), "simple");
+    assert_eq!(get_base(&container), 1);
+
+    // The upcast facet shares the facet's allocation.
+    let base = container.base_arc();
+    assert_eq!(Arc::strong_count(&container.special), 2);
+    assert_eq!(base.base(), 1);
+}
@@ -0,0 +1,146 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod config {
+        #[facet::facet]
+        pub trait Config {
+            fn limit(&self) -> u32;
+        }
+    }
+
+    pub mod name {
+        #[facet::facet]
+        pub struct Name(pub String);
+    }
+}
+
+pub mod facet_impls {
+    pub mod fixed_config {
+        use crate::facets::config::Config;
+
+        pub struct FixedConfig(pub u32);
+
+        impl Config for FixedConfig {
+            fn limit(&self) -> u32 {
+                self.0
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facet_impls::fixed_config::FixedConfig;
+        use crate::facets::config::ArcConfig;
+        use crate::facets::name::{ArcName, Name};
+        use std::sync::Arc;
+
+        pub struct SyncFactory;
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn config(&self) -> ArcConfig {
+                Arc::new(FixedConfig(10))
+            }
+
+            fn name(&self) -> ArcName {
+                Arc::new(Name(String::from("sync")))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facet_impls::fixed_config::FixedConfig;
+        use crate::facets::config::ArcConfig;
+        use crate::facets::name::{ArcName, Name};
+        use std::sync::Arc;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory()]
+        impl AsyncFactory {
+            async fn config(&self) -> ArcConfig {
+                Arc::new(FixedConfig(10))
+            }
+
+            async fn name(&self) -> ArcName {
+                Arc::new(Name(String::from("async")))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::config::Config;
+    use crate::facets::name::Name;
+
+    #[facet::container]
+    pub struct Service {
+        #[facet(swappable)]
+        pub config: dyn Config,
+
+        #[facet(swappable)]
+        pub name: Name,
+
+        #[init(config.load().limit() * 2)]
+        pub initial_double_limit: u32,
+    }
+}
+
+use std::sync::Arc;
+
+use containers::Service;
+use facet_impls::fixed_config::FixedConfig;
+use facets::name::Name;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+fn check_swap(service: Service) {
+    assert_eq!(service.initial_double_limit, 20);
+    let before = service.config();
+    assert_eq!(before.limit(), 10);
+
+    let previous = service.replace_config(Arc::new(FixedConfig(20)));
+    assert!(Arc::ptr_eq(&previous, &before));
+    assert_eq!(service.config().limit(), 20);
+
+    // Existing references continue to use the old instance.
+    assert_eq!(before.limit(), 10);
+
+    service.replace_name(Arc::new(Name(String::from("replaced"))));
+    assert_eq!(service.name().0, "replaced");
+}
+
+#[test]
+fn sync_swappable() {
+    let service = SyncFactory.build::<Service>().unwrap();
+    assert_eq!(service.name().0, "sync");
+    check_swap(service);
+}
+
+#[tokio::test]
+async fn async_swappable() {
+    let service = AsyncFactory.build::<Service>().await.unwrap();
+    assert_eq!(service.name().0, "async");
+    check_swap(service);
+}
+
+#[test]
+fn swap_across_threads() {
+    let service = Arc::new(SyncFactory.build::<Service>().unwrap());
+    let handle = std::thread::spawn({
+        let service = service.clone();
+        move || {
+            service.replace_config(Arc::new(FixedConfig(30)));
+        }
+    });
+    handle.join().unwrap();
+    assert_eq!(service.config().limit(), 30);
+}
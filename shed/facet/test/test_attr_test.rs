@@ -0,0 +1,111 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod greeter {
+        #[facet::facet]
+        pub trait Greeter {
+            fn greet(&self) -> String;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod fixed_greeter {
+        use crate::facets::greeter::Greeter;
+
+        pub struct FixedGreeter {
+            pub greeting: String,
+        }
+
+        impl Greeter for FixedGreeter {
+            fn greet(&self) -> String {
+                self.greeting.clone()
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::fixed_greeter::FixedGreeter;
+        use crate::facets::greeter::ArcGreeter;
+
+        pub struct SyncFactory {
+            pub punctuation: &'static str,
+        }
+
+        #[facet::factory(name: String)]
+        impl SyncFactory {
+            fn greeter(&self, name: &str) -> ArcGreeter {
+                Arc::new(FixedGreeter {
+                    greeting: format!("Hello, {}{}", name, self.punctuation),
+                })
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::fixed_greeter::FixedGreeter;
+        use crate::facets::greeter::ArcGreeter;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(name: String, times: usize)]
+        impl AsyncFactory {
+            async fn greeter(&self, name: &str, times: &usize) -> ArcGreeter {
+                tokio::task::yield_now().await;
+                Arc::new(FixedGreeter {
+                    greeting: format!("Hello, {}", name).repeat(*times),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::greeter::Greeter;
+
+    #[facet::container]
+    pub struct GreeterContainer {
+        #[facet]
+        pub greeter: dyn Greeter,
+    }
+}
+
+use containers::GreeterContainer;
+use facets::greeter::GreeterRef;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+#[facet::test(
+    factory = SyncFactory { punctuation: "!" },
+    params(String::from("sync")),
+)]
+fn sync_test(container: GreeterContainer) {
+    assert_eq!(container.greeter().greet(), "Hello, sync!");
+}
+
+#[facet::test(factory = AsyncFactory, params(String::from("async"), 2))]
+async fn async_test(container: GreeterContainer) {
+    tokio::task::yield_now().await;
+    assert_eq!(container.greeter().greet(), "Hello, asyncHello, async");
+}
+
+#[facet::test(factory = SyncFactory { punctuation: "?" }, params(String::from("result")))]
+fn result_test(container: GreeterContainer) -> Result<(), String> {
+    match container.greeter().greet().as_str() {
+        "Hello, result?" => Ok(()),
+        greeting => Err(format!("unexpected greeting: {}", greeting)),
+    }
+}
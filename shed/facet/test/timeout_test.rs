@@ -0,0 +1,135 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod one {
+        #[facet::facet]
+        pub trait One {
+            fn get(&self) -> u32;
+        }
+    }
+
+    pub mod two {
+        #[facet::facet]
+        pub trait Two {
+            fn get(&self) -> u32;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_one {
+        use crate::facets::one::One;
+
+        pub struct SimpleOne;
+
+        impl One for SimpleOne {
+            fn get(&self) -> u32 {
+                1
+            }
+        }
+    }
+
+    pub mod simple_two {
+        use crate::facets::two::Two;
+
+        pub struct SimpleTwo;
+
+        impl Two for SimpleTwo {
+            fn get(&self) -> u32 {
+                2
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod slow_factory {
+        use crate::facet_impls::simple_one::SimpleOne;
+        use crate::facet_impls::simple_two::SimpleTwo;
+        use crate::facets::one::ArcOne;
+        use crate::facets::two::ArcTwo;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        #[derive(Clone)]
+        pub struct SlowFactory;
+
+        #[facet::factory(delay_secs: u64)]
+        impl SlowFactory {
+            #[facet(timeout = "30s")]
+            async fn one(&self, delay_secs: &u64) -> ArcOne {
+                tokio::time::sleep(Duration::from_secs(*delay_secs)).await;
+                Arc::new(SimpleOne)
+            }
+
+            #[facet(timeout = "500ms")]
+            async fn two(&self, delay_secs: &u64) -> Result<ArcTwo, anyhow::Error> {
+                tokio::time::sleep(Duration::from_secs(*delay_secs)).await;
+                Ok(Arc::new(SimpleTwo))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::one::One;
+    use crate::facets::two::Two;
+
+    #[facet::container]
+    pub struct OneOnly {
+        #[facet]
+        pub one: dyn One,
+    }
+
+    #[facet::container]
+    pub struct LazyTwo {
+        #[facet]
+        pub one: dyn One,
+
+        #[facet(lazy)]
+        pub two: dyn Two,
+    }
+}
+
+use std::time::Duration;
+
+use containers::{LazyTwo, OneOnly};
+use factories::slow_factory::SlowFactory;
+
+#[tokio::test(start_paused = true)]
+async fn within_timeout() {
+    let container = SlowFactory.build::<OneOnly>(10).await.unwrap();
+    assert_eq!(container.one.get(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn timed_out() {
+    match SlowFactory.build::<OneOnly>(60).await {
+        Err(facet::FactoryError::FacetBuildTimedOut { name, duration }) => {
+            assert_eq!(name, "one");
+            assert_eq!(duration, Duration::from_secs(30));
+        }
+        _ => panic!("build should time out"),
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn lazy_timed_out() {
+    let container = SlowFactory.build::<LazyTwo>(1).await.unwrap();
+    assert_eq!(container.one.get(), 1);
+
+    match container.two().await {
+        Err(facet::FactoryError::FacetBuildTimedOut { name, duration }) => {
+            assert_eq!(name, "two");
+            assert_eq!(duration, Duration::from_millis(500));
+        }
+        _ => panic!("lazy access should time out"),
+    }
+}
@@ -0,0 +1,150 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod one {
+        #[facet::facet]
+        pub struct One;
+    }
+
+    pub mod two {
+        #[facet::facet]
+        pub struct Two;
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facets::one::{ArcOne, One};
+        use crate::facets::two::{ArcTwo, Two};
+        use std::sync::Arc;
+
+        pub struct SyncFactory;
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn one(&self) -> ArcOne {
+                tracing::info!("building one");
+                Arc::new(One)
+            }
+
+            fn two(&self, _one: &ArcOne) -> ArcTwo {
+                tracing::info!("building two");
+                Arc::new(Two)
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facets::one::{ArcOne, One};
+        use crate::facets::two::{ArcTwo, Two};
+        use std::sync::Arc;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory()]
+        impl AsyncFactory {
+            async fn one(&self) -> ArcOne {
+                tracing::info!("building one");
+                Arc::new(One)
+            }
+
+            async fn two(&self, _one: &ArcOne) -> ArcTwo {
+                tokio::task::yield_now().await;
+                tracing::info!("building two");
+                Arc::new(Two)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::two::Two;
+
+    #[facet::container]
+    pub struct TwoContainer {
+        #[facet]
+        pub two: Two,
+    }
+}
+
+use std::sync::{Arc, Mutex};
+
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+use containers::TwoContainer;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+/// Subscriber that records which span each event was emitted in.
+#[derive(Clone, Default)]
+struct SpanRecorder {
+    spans: Arc<Mutex<Vec<&'static str>>>,
+    current: Arc<Mutex<Vec<u64>>>,
+    events: Arc<Mutex<Vec<Option<&'static str>>>>,
+}
+
+impl Subscriber for SpanRecorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(span.metadata().name());
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {
+        let current = self.current.lock().unwrap().last().copied();
+        let spans = self.spans.lock().unwrap();
+        let span = current.map(|id| spans[id as usize - 1]);
+        self.events.lock().unwrap().push(span);
+    }
+
+    fn enter(&self, span: &Id) {
+        self.current.lock().unwrap().push(span.into_u64());
+    }
+
+    fn exit(&self, _span: &Id) {
+        self.current.lock().unwrap().pop();
+    }
+}
+
+#[test]
+fn sync_spans() {
+    let recorder = SpanRecorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        SyncFactory.build::<TwoContainer>().unwrap();
+    });
+    assert_eq!(*recorder.spans.lock().unwrap(), ["one", "two"]);
+    assert_eq!(*recorder.events.lock().unwrap(), [Some("one"), Some("two")]);
+}
+
+#[test]
+fn async_spans() {
+    let recorder = SpanRecorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime
+            .block_on(AsyncFactory.build::<TwoContainer>())
+            .unwrap();
+    });
+    let mut spans = recorder.spans.lock().unwrap().clone();
+    spans.sort_unstable();
+    assert_eq!(spans, ["one", "two"]);
+    assert_eq!(*recorder.events.lock().unwrap(), [Some("one"), Some("two")]);
+}
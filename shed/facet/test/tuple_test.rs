@@ -0,0 +1,182 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod client {
+        #[facet::facet]
+        pub trait Client {
+            fn request(&self) -> u32;
+        }
+    }
+
+    pub mod metrics {
+        use std::sync::atomic::AtomicU32;
+
+        #[facet::facet]
+        pub struct Metrics {
+            pub requests: AtomicU32,
+        }
+    }
+
+    pub mod service {
+        #[facet::facet]
+        pub struct Service {
+            pub name: String,
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod counting_client {
+        use std::sync::atomic::Ordering;
+
+        use crate::facets::client::Client;
+        use crate::facets::metrics::ArcMetrics;
+
+        pub struct CountingClient {
+            pub metrics: ArcMetrics,
+        }
+
+        impl Client for CountingClient {
+            fn request(&self) -> u32 {
+                self.metrics.requests.fetch_add(1, Ordering::SeqCst) + 1
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        use crate::facet_impls::counting_client::CountingClient;
+        use crate::facets::client::ArcClient;
+        use crate::facets::metrics::{ArcMetrics, Metrics};
+        use crate::facets::service::{ArcService, Service};
+
+        #[derive(Default)]
+        pub struct SyncFactory {
+            pub clients_created: AtomicU32,
+        }
+
+        #[facet::factory(name: String)]
+        impl SyncFactory {
+            fn client_with_metrics(&self) -> (ArcClient, ArcMetrics) {
+                self.clients_created.fetch_add(1, Ordering::SeqCst);
+                let metrics = Arc::new(Metrics {
+                    requests: AtomicU32::new(0),
+                });
+                let client = Arc::new(CountingClient {
+                    metrics: metrics.clone(),
+                });
+                (client, metrics)
+            }
+
+            fn service(&self, name: &str, client: &ArcClient) -> ArcService {
+                client.request();
+                Arc::new(Service {
+                    name: name.to_string(),
+                })
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use std::sync::atomic::AtomicU32;
+        use std::sync::Arc;
+
+        use anyhow::Error;
+
+        use crate::facet_impls::counting_client::CountingClient;
+        use crate::facets::client::ArcClient;
+        use crate::facets::metrics::Metrics;
+        use crate::facets::service::{ArcService, Service};
+
+        pub struct AsyncFactory;
+
+        #[facet::factory(name: String)]
+        impl AsyncFactory {
+            async fn client_with_metrics(&self) -> Result<(ArcClient, Arc<Metrics>), Error> {
+                tokio::task::yield_now().await;
+                let metrics = Arc::new(Metrics {
+                    requests: AtomicU32::new(0),
+                });
+                let client = Arc::new(CountingClient {
+                    metrics: metrics.clone(),
+                });
+                Ok((client, metrics))
+            }
+
+            async fn service(&self, name: &str, client: &ArcClient) -> ArcService {
+                client.request();
+                Arc::new(Service {
+                    name: name.to_string(),
+                })
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::client::Client;
+    use crate::facets::metrics::Metrics;
+    use crate::facets::service::Service;
+
+    #[facet::container]
+    pub struct Coupled {
+        #[facet]
+        pub client: dyn Client,
+
+        #[facet]
+        pub metrics: Metrics,
+
+        #[facet]
+        pub service: Service,
+    }
+}
+
+use std::sync::atomic::Ordering;
+
+use containers::Coupled;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+#[test]
+fn sync_tuple() {
+    let factory = SyncFactory::default();
+    let container = factory.build::<Coupled>(String::from("sync")).unwrap();
+    assert_eq!(factory.clients_created.load(Ordering::SeqCst), 1);
+    assert_eq!(container.service.name, "sync");
+
+    // The service's request was recorded by the client's metrics.
+    assert_eq!(container.client.request(), 2);
+    assert_eq!(container.metrics.requests.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn async_tuple() {
+    let container = AsyncFactory
+        .build::<Coupled>(String::from("async"))
+        .await
+        .unwrap();
+    assert_eq!(container.service.name, "async");
+    assert_eq!(container.client.request(), 2);
+    assert_eq!(container.metrics.requests.load(Ordering::SeqCst), 2);
+}
+
+#[cfg(feature = "graph")]
+#[test]
+fn tuple_graph() {
+    let graph = SyncFactory::facet_graph();
+    let client = graph.facet("client").unwrap();
+    assert_eq!(client.dependencies, ["client_with_metrics"]);
+    let service = graph.facet("service").unwrap();
+    assert_eq!(service.dependencies, ["client"]);
+}
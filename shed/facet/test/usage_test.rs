@@ -0,0 +1,149 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod db {
+        #[facet::facet]
+        pub trait Db {
+            fn query(&self) -> String;
+        }
+    }
+
+    pub mod cache {
+        #[facet::facet]
+        pub struct Cache {
+            pub size: usize,
+        }
+    }
+
+    pub mod metrics {
+        #[facet::facet]
+        pub struct Metrics;
+    }
+
+    pub mod keys {
+        pub struct Primary;
+    }
+}
+
+pub mod facet_impls {
+    pub mod simple_db {
+        use crate::facets::db::Db;
+
+        pub struct SimpleDb;
+
+        impl Db for SimpleDb {
+            fn query(&self) -> String {
+                String::from("result")
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod simple_factory {
+        use std::sync::Arc;
+
+        use crate::facet_impls::simple_db::SimpleDb;
+        use crate::facets::cache::{ArcCache, Cache};
+        use crate::facets::db::ArcDb;
+        use crate::facets::metrics::{ArcMetrics, Metrics};
+
+        pub struct SimpleFactory;
+
+        #[facet::factory()]
+        impl SimpleFactory {
+            fn db(&self) -> ArcDb {
+                Arc::new(SimpleDb)
+            }
+
+            fn cache(&self) -> ArcCache {
+                Arc::new(Cache { size: 10 })
+            }
+
+            fn metrics(&self) -> ArcMetrics {
+                Arc::new(Metrics)
+            }
+
+            #[facet(key = "crate::facets::keys::Primary")]
+            fn primary(&self) -> ArcDb {
+                Arc::new(SimpleDb)
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::cache::Cache;
+    use crate::facets::db::Db;
+    use crate::facets::metrics::Metrics;
+
+    #[facet::container(track_usage)]
+    pub struct Storage {
+        #[facet]
+        pub db: dyn Db,
+
+        #[facet]
+        pub cache: Cache,
+
+        #[facet(key = "crate::facets::keys::Primary")]
+        pub primary: dyn Db,
+    }
+
+    #[facet::container(track_usage)]
+    pub struct Service {
+        #[delegate(dyn Db)]
+        pub storage: Storage,
+
+        #[facet]
+        pub metrics: Metrics,
+    }
+}
+
+use std::sync::Arc;
+
+use facet::{ContainerUsage, KeyedFacetRef};
+
+use crate::containers::{Service, Storage};
+use crate::facets::cache::CacheArc;
+use crate::facets::db::{Db, DbRef};
+use crate::facets::keys::Primary;
+use crate::factories::simple_factory::SimpleFactory;
+
+#[test]
+fn tracks_accessed_facets() {
+    let storage = SimpleFactory.build::<Storage>().unwrap();
+    assert_eq!(storage.unused_facets(), vec!["db", "cache", "primary"]);
+
+    assert_eq!(storage.db().query(), "result");
+    assert_eq!(storage.unused_facets(), vec!["cache", "primary"]);
+
+    assert_eq!(storage.cache_arc().size, 10);
+    let primary: &(dyn Db + Send + Sync) = KeyedFacetRef::<Primary, _>::keyed_facet_ref(&storage);
+    assert_eq!(primary.query(), "result");
+    assert!(storage.unused_facets().is_empty());
+    assert_eq!(storage.facet_usage().used(), vec!["db", "cache", "primary"]);
+}
+
+#[test]
+fn direct_field_access_is_not_tracked() {
+    let storage = Arc::new(SimpleFactory.build::<Storage>().unwrap());
+    assert_eq!(storage.cache.size, 10);
+    assert_eq!(storage.unused_facets(), vec!["db", "cache", "primary"]);
+}
+
+#[test]
+fn delegated_facets_are_tracked_by_delegate() {
+    let service = SimpleFactory.build::<Service>().unwrap();
+    assert_eq!(service.db().query(), "result");
+
+    // The outer container only tracks its own facets.
+    assert_eq!(service.unused_facets(), vec!["metrics"]);
+    assert_eq!(service.storage.unused_facets(), vec!["cache", "primary"]);
+}
@@ -0,0 +1,221 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod storage {
+        #[facet::facet]
+        pub trait Storage {
+            fn kind(&self) -> &str;
+        }
+    }
+
+    pub mod transport {
+        #[facet::facet]
+        pub trait Transport {
+            fn kind(&self) -> &str;
+        }
+    }
+
+    pub mod service {
+        #[facet::facet]
+        pub struct Service {
+            pub description: String,
+        }
+    }
+
+    pub mod extra {
+        #[facet::facet]
+        pub struct Extra;
+    }
+}
+
+pub mod facet_impls {
+    use crate::facets::storage::Storage;
+    use crate::facets::transport::Transport;
+
+    pub struct Named(pub &'static str);
+
+    impl Storage for Named {
+        fn kind(&self) -> &str {
+            self.0
+        }
+    }
+
+    impl Transport for Named {
+        fn kind(&self) -> &str {
+            self.0
+        }
+    }
+}
+
+pub mod factories {
+    use std::sync::Arc;
+
+    use anyhow::Error;
+
+    use crate::facets::service::{ArcService, Service};
+    use crate::facets::storage::{ArcStorage, Storage};
+    use crate::facets::transport::{ArcTransport, Transport};
+
+    pub struct Factory;
+
+    #[facet::factory(storage_kind: String)]
+    impl Factory {
+        #[facet(registry = storage_kind)]
+        fn storage(&self, storage_kind: &str) -> Result<ArcStorage, Error> {
+            Ok(facet::registry().resolve::<dyn Storage + Send + Sync>(storage_kind)?)
+        }
+
+        #[facet(registry)]
+        fn transport(&self) -> Result<ArcTransport, Error> {
+            Ok(facet::registry().resolve_only::<dyn Transport + Send + Sync>()?)
+        }
+
+        fn service(&self, storage: &ArcStorage, transport: &ArcTransport) -> ArcService {
+            Arc::new(Service {
+                description: format!("{} over {}", storage.kind(), transport.kind()),
+            })
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::extra::Extra;
+    use crate::facets::service::Service;
+    use crate::facets::storage::Storage;
+
+    #[facet::container]
+    pub struct ServiceContainer {
+        #[facet]
+        pub service: Service,
+    }
+
+    #[facet::container]
+    pub struct StorageContainer {
+        #[facet]
+        pub storage: dyn Storage,
+    }
+
+    #[facet::container]
+    pub struct ExtraContainer {
+        #[facet]
+        pub extra: Extra,
+
+        #[delegate(dyn Storage)]
+        pub storage: StorageContainer,
+    }
+}
+
+use std::sync::{Arc, Mutex};
+
+use facet::ValidationIssue;
+
+use containers::{ExtraContainer, ServiceContainer, StorageContainer};
+use facet_impls::Named;
+use facets::storage::Storage;
+use facets::transport::Transport;
+use factories::Factory;
+
+type DynStorage = dyn Storage + Send + Sync;
+type DynTransport = dyn Transport + Send + Sync;
+
+/// The global registry is shared by the tests, so they take turns to
+/// register implementations.
+static REGISTRY_LOCK: Mutex<()> = Mutex::new(());
+
+fn register_once() {
+    static REGISTER: std::sync::Once = std::sync::Once::new();
+    REGISTER.call_once(|| {
+        facet::registry()
+            .register::<DynStorage>("memory", || Ok(Arc::new(Named("memory"))))
+            .register::<DynStorage>("disk", || Ok(Arc::new(Named("disk"))));
+    });
+}
+
+#[test]
+fn missing_implementation() {
+    let _lock = REGISTRY_LOCK.lock().unwrap();
+    register_once();
+
+    let report = Factory.validate::<StorageContainer>(&"memory".to_string());
+    assert!(report.is_valid(), "{}", report);
+
+    let report = Factory.validate::<StorageContainer>(&"cloud".to_string());
+    assert_eq!(report.factory, "Factory");
+    assert_eq!(report.container, "StorageContainer");
+    assert_eq!(
+        report.issues,
+        [ValidationIssue::MissingImplementation {
+            facet: "storage",
+            implementation: Some("cloud".to_string()),
+            registered: vec!["disk".to_string(), "memory".to_string()],
+        }]
+    );
+}
+
+#[test]
+fn only_implementation() {
+    let _lock = REGISTRY_LOCK.lock().unwrap();
+    register_once();
+
+    // The storage container doesn't need the transport, so it isn't
+    // checked.
+    let report = Factory.validate::<StorageContainer>(&"memory".to_string());
+    assert!(report.is_valid(), "{}", report);
+
+    let report = Factory.validate::<ServiceContainer>(&"memory".to_string());
+    assert_eq!(
+        report.issues,
+        [ValidationIssue::MissingImplementation {
+            facet: "transport",
+            implementation: None,
+            registered: Vec::new(),
+        }]
+    );
+
+    facet::registry().register::<DynTransport>("tcp", || Ok(Arc::new(Named("tcp"))));
+    let report = Factory.validate::<ServiceContainer>(&"memory".to_string());
+    assert!(report.is_valid(), "{}", report);
+    let container = Factory
+        .build::<ServiceContainer>("memory".to_string())
+        .unwrap();
+    assert_eq!(container.service.description, "memory over tcp");
+
+    facet::registry().register::<DynTransport>("udp", || Ok(Arc::new(Named("udp"))));
+    let report = Factory.validate::<ServiceContainer>(&"memory".to_string());
+    assert_eq!(
+        report.issues,
+        [ValidationIssue::AmbiguousImplementation {
+            facet: "transport",
+            registered: vec!["tcp".to_string(), "udp".to_string()],
+        }]
+    );
+    assert!(Factory
+        .build::<ServiceContainer>("memory".to_string())
+        .is_err());
+}
+
+#[test]
+fn missing_facet() {
+    let _lock = REGISTRY_LOCK.lock().unwrap();
+    register_once();
+
+    let report = Factory.validate::<ExtraContainer>(&"memory".to_string());
+    assert_eq!(
+        report.issues,
+        [ValidationIssue::MissingFacet {
+            facet: "extra",
+            needed_by: vec!["ExtraContainer.extra".to_string()],
+        }]
+    );
+    assert_eq!(
+        report.to_string(),
+        "Factory can't build ExtraContainer:\n  no factory method builds 'extra', needed by ExtraContainer.extra"
+    );
+}
@@ -0,0 +1,185 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+pub mod facets {
+    pub mod left {
+        #[facet::facet]
+        pub trait Left {
+            fn get(&self) -> u32;
+
+            fn right(&self) -> Option<u32>;
+        }
+    }
+
+    pub mod right {
+        #[facet::facet]
+        pub trait Right {
+            fn get(&self) -> u32;
+
+            fn left(&self) -> u32;
+        }
+    }
+}
+
+pub mod facet_impls {
+    pub mod weak_left {
+        use crate::facets::left::Left;
+        use crate::facets::right::WeakRight;
+
+        pub struct WeakLeft(pub WeakRight);
+
+        impl Left for WeakLeft {
+            fn get(&self) -> u32 {
+                1
+            }
+
+            fn right(&self) -> Option<u32> {
+                self.0.upgrade().map(|right| right.get())
+            }
+        }
+    }
+
+    pub mod strong_right {
+        use crate::facets::left::ArcLeft;
+        use crate::facets::right::Right;
+
+        pub struct StrongRight(pub ArcLeft);
+
+        impl Right for StrongRight {
+            fn get(&self) -> u32 {
+                2
+            }
+
+            fn left(&self) -> u32 {
+                self.0.get()
+            }
+        }
+    }
+}
+
+pub mod factories {
+    pub mod sync_factory {
+        use crate::facet_impls::strong_right::StrongRight;
+        use crate::facet_impls::weak_left::WeakLeft;
+        use crate::facets::left::ArcLeft;
+        use crate::facets::right::{ArcRight, WeakRight};
+        use std::sync::Arc;
+
+        pub struct SyncFactory;
+
+        #[facet::factory()]
+        impl SyncFactory {
+            fn left(&self, right: &WeakRight) -> ArcLeft {
+                Arc::new(WeakLeft(right.clone()))
+            }
+
+            fn right(&self, left: &ArcLeft) -> ArcRight {
+                Arc::new(StrongRight(left.clone()))
+            }
+        }
+    }
+
+    pub mod async_factory {
+        use crate::facet_impls::strong_right::StrongRight;
+        use crate::facet_impls::weak_left::WeakLeft;
+        use crate::facets::left::ArcLeft;
+        use crate::facets::right::{ArcRight, WeakRight};
+        use std::sync::Arc;
+
+        pub struct AsyncFactory;
+
+        #[facet::factory()]
+        impl AsyncFactory {
+            async fn left(&self, right: &WeakRight) -> ArcLeft {
+                Arc::new(WeakLeft(right.clone()))
+            }
+
+            async fn right(&self, left: &ArcLeft) -> ArcRight {
+                Arc::new(StrongRight(left.clone()))
+            }
+        }
+    }
+}
+
+pub mod containers {
+    use crate::facets::left::Left;
+    use crate::facets::right::Right;
+
+    #[facet::container]
+    pub struct Both {
+        #[facet]
+        left: dyn Left,
+
+        #[facet]
+        right: dyn Right,
+    }
+
+    #[facet::container]
+    pub struct LeftOnly {
+        #[facet]
+        left: dyn Left,
+    }
+
+    #[facet::container]
+    pub struct WeakLeft {
+        #[facet(weak)]
+        left: dyn Left,
+
+        #[facet]
+        right: dyn Right,
+    }
+}
+
+use facets::left::LeftRef;
+use facets::right::RightRef;
+use factories::async_factory::AsyncFactory;
+use factories::sync_factory::SyncFactory;
+
+#[test]
+fn sync_cycle() {
+    let factory = SyncFactory;
+
+    let both = factory.build::<containers::Both>().unwrap();
+    assert_eq!(both.left().right(), Some(2));
+    assert_eq!(both.right().left(), 1);
+
+    // The weak reference is never upgradeable if its facet wasn't needed.
+    let left_only = factory.build::<containers::LeftOnly>().unwrap();
+    assert_eq!(left_only.left().right(), None);
+
+    // The container can hold the facet weakly if another facet keeps it
+    // alive.
+    let weak_left = factory.build::<containers::WeakLeft>().unwrap();
+    assert_eq!(weak_left.left().unwrap().right(), Some(2));
+    assert_eq!(weak_left.right().left(), 1);
+}
+
+#[tokio::test]
+async fn async_cycle() {
+    let factory = AsyncFactory;
+
+    let both = factory.build::<containers::Both>().await.unwrap();
+    assert_eq!(both.left().right(), Some(2));
+    assert_eq!(both.right().left(), 1);
+
+    let left_only = factory.build::<containers::LeftOnly>().await.unwrap();
+    assert_eq!(left_only.left().right(), None);
+
+    let weak_left = factory.build::<containers::WeakLeft>().await.unwrap();
+    assert_eq!(weak_left.left().unwrap().right(), Some(2));
+}
+
+#[cfg(feature = "graph")]
+#[test]
+fn weak_graph() {
+    let graph = SyncFactory::facet_graph();
+    let left = graph.facet("left").unwrap();
+    assert!(left.dependencies.is_empty());
+    assert_eq!(left.weak_dependencies, ["right"]);
+}
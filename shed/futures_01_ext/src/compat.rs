@@ -0,0 +1,182 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Helpers for bridging between futures 0.1 and futures 0.3, so that legacy code can be used
+//! from async code running on Tokio 1.x, and the other way around, without assembling `compat()`
+//! chains at each call site.
+//!
+//! Futures 0.1 code that depends on a Tokio 0.1 reactor or timer will not work on Tokio 1.x even
+//! when converted; these helpers are for futures that only depend on their inputs, such as
+//! combinators and channels.
+
+use std::pin::Pin;
+
+use futures::future::{ExecuteError, Executor};
+use futures::{Future, Sink, Stream};
+use futures03::compat::{
+    Compat01As03Sink, CompatSink, Future01CompatExt, Sink01CompatExt, Stream01CompatExt,
+};
+use futures03::{
+    FutureExt as _, SinkExt as _, StreamExt as _, TryFutureExt as _, TryStreamExt as _,
+};
+
+use crate::{BoxFuture, BoxStream};
+
+/// Convert a futures 0.3 future that returns a `Result` into a boxed futures 0.1 future.
+pub fn box_future_01<F, T, E>(future: F) -> BoxFuture<T, E>
+where
+    F: futures03::Future<Output = Result<T, E>> + Send + 'static,
+{
+    Box::new(Box::pin(future).compat())
+}
+
+/// Convert a futures 0.1 future into a boxed futures 0.3 future that returns a `Result`.
+pub fn box_future_03<F>(
+    future: F,
+) -> futures03::future::BoxFuture<'static, Result<F::Item, F::Error>>
+where
+    F: Future + Send + 'static,
+{
+    future.compat().boxed()
+}
+
+/// Convert a futures 0.3 stream of `Result`s into a boxed futures 0.1 stream.
+pub fn box_stream_01<S, T, E>(stream: S) -> BoxStream<T, E>
+where
+    S: futures03::Stream<Item = Result<T, E>> + Send + 'static,
+{
+    Box::new(Box::pin(stream).compat())
+}
+
+/// Convert a futures 0.1 stream into a boxed futures 0.3 stream of `Result`s.
+pub fn box_stream_03<S>(
+    stream: S,
+) -> futures03::stream::BoxStream<'static, Result<S::Item, S::Error>>
+where
+    S: Stream + Send + 'static,
+{
+    stream.compat().boxed()
+}
+
+/// Convert a futures 0.3 sink into a futures 0.1 sink.
+pub fn sink_01<S, Item>(sink: S) -> CompatSink<Pin<Box<S>>, Item>
+where
+    S: futures03::Sink<Item>,
+{
+    Box::pin(sink).compat()
+}
+
+/// Convert a futures 0.1 sink into a futures 0.3 sink.
+pub fn sink_03<S>(sink: S) -> Compat01As03Sink<S, S::SinkItem>
+where
+    S: Sink,
+{
+    sink.sink_compat()
+}
+
+/// Spawn a futures 0.1 future on the current Tokio 1.x runtime.
+///
+/// Panics if called outside of a Tokio 1.x runtime.
+pub fn spawn_01<F>(future: F) -> tokio::task::JoinHandle<Result<F::Item, F::Error>>
+where
+    F: Future + Send + 'static,
+    F::Item: Send + 'static,
+    F::Error: Send + 'static,
+{
+    tokio::spawn(future.compat())
+}
+
+/// A futures 0.1 [Executor] that spawns futures on a Tokio 1.x runtime, for legacy code that
+/// takes an executor to spawn its background work on.
+#[derive(Clone, Debug)]
+pub struct TokioExecutor(tokio::runtime::Handle);
+
+impl TokioExecutor {
+    /// Create an executor that spawns futures on the runtime of `handle`.
+    pub fn new(handle: tokio::runtime::Handle) -> Self {
+        TokioExecutor(handle)
+    }
+
+    /// Create an executor that spawns futures on the current Tokio 1.x runtime.
+    ///
+    /// Panics if called outside of a Tokio 1.x runtime.
+    pub fn current() -> Self {
+        TokioExecutor(tokio::runtime::Handle::current())
+    }
+}
+
+impl<F> Executor<F> for TokioExecutor
+where
+    F: Future<Item = (), Error = ()> + Send + 'static,
+{
+    fn execute(&self, future: F) -> Result<(), ExecuteError<F>> {
+        self.0.spawn(future.compat());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::sync::{mpsc, oneshot};
+    use futures::{future, stream};
+    use futures03::TryStreamExt;
+
+    #[tokio::test]
+    async fn future_round_trip() {
+        let future_01 = box_future_01(async { Ok::<_, ()>(1) }).map(|v| v + 1);
+        assert_eq!(box_future_03(future_01).await, Ok(2));
+
+        let failed = box_future_03(box_future_01(async { Err::<(), _>("failed") }));
+        assert_eq!(failed.await, Err("failed"));
+    }
+
+    #[tokio::test]
+    async fn stream_round_trip() {
+        let stream_01 =
+            box_stream_01(futures03::stream::iter(vec![Ok::<_, ()>(1), Ok(2)])).map(|v| v * 10);
+        let items: Vec<_> = box_stream_03(stream_01).try_collect().await.unwrap();
+        assert_eq!(items, vec![10, 20]);
+
+        let items: Vec<_> = box_stream_03(stream::iter_ok::<_, ()>(vec![3, 4]))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items, vec![3, 4]);
+    }
+
+    #[tokio::test]
+    async fn sinks() {
+        let (tx, rx) = futures03::channel::mpsc::unbounded::<u32>();
+        let sink = sink_01(tx.sink_map_err(|_| ()));
+        let (_sink, _stream) = box_future_03(sink.send_all(stream::iter_ok(vec![1, 2, 3])))
+            .await
+            .unwrap();
+        assert_eq!(rx.collect::<Vec<_>>().await, vec![1, 2, 3]);
+
+        let (tx, rx) = mpsc::unbounded::<u32>();
+        let mut sink = sink_03(tx);
+        futures03::SinkExt::send(&mut sink, 4).await.unwrap();
+        drop(sink);
+        let items: Vec<_> = box_stream_03(rx).try_collect().await.unwrap();
+        assert_eq!(items, vec![4]);
+    }
+
+    #[tokio::test]
+    async fn spawn() {
+        assert_eq!(spawn_01(future::ok::<_, ()>(5)).await.unwrap(), Ok(5));
+
+        let (tx, rx) = oneshot::channel();
+        TokioExecutor::current()
+            .execute(future::lazy(move || tx.send(6)).map_err(|_| ()))
+            .unwrap();
+        assert_eq!(box_future_03(rx).await, Ok(6));
+    }
+}
@@ -22,11 +22,13 @@ use tokio_io::{
 };
 
 mod bytes_stream;
+pub mod compat;
 pub mod decode;
 pub mod encode;
 mod futures_ordered;
 pub mod io;
 mod select_all;
+mod select_biased_all;
 mod split_err;
 mod stream_wrappers;
 mod streamfork;
@@ -34,6 +36,7 @@ mod streamfork;
 pub use crate::bytes_stream::{BytesStream, BytesStreamFuture};
 pub use crate::futures_ordered::{futures_ordered, FuturesOrdered};
 pub use crate::select_all::{select_all, SelectAll};
+pub use crate::select_biased_all::{select_biased_all, SelectBiasedAll};
 pub use crate::split_err::split_err;
 pub use crate::stream_wrappers::{CollectNoConsume, CollectTo};
 
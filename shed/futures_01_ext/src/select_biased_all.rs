@@ -0,0 +1,150 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! A set of streams that gives priority to one of them
+
+use std::fmt::{self, Debug};
+
+use futures::{Async, Poll, Stream};
+
+use crate::select_all::SelectAll;
+
+/// A set of data streams together with a high-priority stream, which is polled before any of
+/// the data streams every time this stream is polled.
+///
+/// This is for streams of control messages, such as shutdown requests, that must not be
+/// starved by a set of busy data streams the way they can be with `select_all`, which polls its
+/// streams fairly. The stream ends once the priority stream and all of the data streams have
+/// ended, and an error from any of them is passed on.
+///
+/// This is created by the `select_biased_all` function.
+#[must_use = "streams do nothing unless polled"]
+pub struct SelectBiasedAll<P, S: Stream> {
+    priority: Option<P>,
+    data: SelectAll<S>,
+}
+
+impl<P, S: Stream> Debug for SelectBiasedAll<P, S> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "SelectBiasedAll {{ ... }}")
+    }
+}
+
+impl<P, S: Stream> SelectBiasedAll<P, S> {
+    /// Returns the number of data streams contained in the set.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if the set contains no data streams.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Push a data stream into the set.
+    ///
+    /// As with `SelectAll::push`, this will not call `poll` on the submitted stream. The caller
+    /// must ensure that `SelectBiasedAll::poll` is called in order to receive task
+    /// notifications.
+    pub fn push(&mut self, stream: S) {
+        self.data.push(stream);
+    }
+}
+
+impl<P, S> Stream for SelectBiasedAll<P, S>
+where
+    P: Stream<Item = S::Item, Error = S::Error>,
+    S: Stream,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        if let Some(priority) = self.priority.as_mut() {
+            match priority.poll()? {
+                Async::Ready(Some(item)) => return Ok(Async::Ready(Some(item))),
+                Async::Ready(None) => self.priority = None,
+                Async::NotReady => {}
+            }
+        }
+
+        match self.data.poll()? {
+            Async::Ready(Some(item)) => Ok(Async::Ready(Some(item))),
+            // The data streams are exhausted, but the priority stream may still yield items.
+            Async::Ready(None) if self.priority.is_some() => Ok(Async::NotReady),
+            Async::Ready(None) => Ok(Async::Ready(None)),
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
+
+/// Combine a high-priority stream with a list of data streams into a single `Stream`, which
+/// yields an item of the priority stream whenever one is available, and otherwise yields the
+/// items of the data streams as they become available.
+///
+/// More data streams can be added to the returned set with `SelectBiasedAll::push`.
+pub fn select_biased_all<P, I>(priority: P, streams: I) -> SelectBiasedAll<P, I::Item>
+where
+    P: Stream<Item = <I::Item as Stream>::Item, Error = <I::Item as Stream>::Error>,
+    I: IntoIterator,
+    I::Item: Stream,
+{
+    SelectBiasedAll {
+        priority: Some(priority),
+        data: crate::select_all::select_all(streams),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream::{iter_ok, repeat};
+    use futures::sync::mpsc;
+    use futures03::compat::Future01CompatExt;
+
+    #[test]
+    fn priority_stream_first() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let streams: Vec<_> = (0..3).map(|i| iter_ok::<_, ()>(vec![i; 3])).collect();
+        let set = select_biased_all(iter_ok(vec![10, 11]), streams);
+        let result = rt.block_on(set.collect().compat()).unwrap();
+        assert_eq!(&result[..2], &[10, 11]);
+        assert_eq!(result.len(), 11);
+    }
+
+    #[test]
+    fn control_message_not_starved() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (tx, rx) = mpsc::unbounded();
+        let data: Vec<_> = (0..4).map(|_| repeat::<_, ()>(0)).collect();
+        let set = select_biased_all(rx, data);
+
+        // Skip some data items, then send a control message, which must be the very next item.
+        let (_, set) = rt
+            .block_on(set.skip(10).into_future().compat())
+            .map_err(|(err, _)| err)
+            .unwrap();
+        tx.unbounded_send(1).unwrap();
+        let (item, _) = rt
+            .block_on(set.into_future().compat())
+            .map_err(|(err, _)| err)
+            .unwrap();
+        assert_eq!(item, Some(1));
+    }
+
+    #[test]
+    fn ends_when_all_streams_end() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mut set = select_biased_all(iter_ok::<_, ()>(vec![1]), Vec::new());
+        set.push(iter_ok(vec![2, 3]));
+        assert_eq!(set.len(), 1);
+        let result = rt.block_on(set.collect().compat()).unwrap();
+        assert_eq!(result, vec![1, 2, 3]);
+    }
+}
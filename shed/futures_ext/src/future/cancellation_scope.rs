@@ -0,0 +1,241 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use futures::channel::oneshot;
+use futures::future::{self, AbortHandle, Abortable, Aborted, FutureExt, Shared};
+use futures::task::{Context, Poll};
+use pin_project::pin_project;
+use tokio_shim::task::JoinHandle;
+
+/// A scope that owns a set of futures, aborting all of them when the scope is cancelled or
+/// dropped.
+///
+/// Futures can either be spawned as tasks with [CancellationScope::spawn], or attached to the
+/// scope with [CancellationScope::attach] to be polled by the caller. Futures that are added to
+/// a scope that has already been cancelled are aborted immediately.
+///
+/// This replaces keeping collections of [AbortHandle]s by hand, which leaks tasks on the error
+/// paths that forget to abort them.
+#[derive(Debug, Default)]
+pub struct CancellationScope {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    cancelled: bool,
+    tasks: Vec<Task>,
+}
+
+#[derive(Debug)]
+struct Task {
+    abort_handle: AbortHandle,
+    /// Resolves when the future is complete or dropped.
+    done: Shared<oneshot::Receiver<()>>,
+}
+
+impl Task {
+    fn is_done(&self) -> bool {
+        self.done.clone().now_or_never().is_some()
+    }
+}
+
+impl CancellationScope {
+    /// Create a new, empty scope.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawn a task running `fut` that will be aborted when the scope is cancelled. The task
+    /// resolves to `Err(Aborted)` if it was aborted before completing.
+    pub fn spawn<F>(&self, fut: F) -> JoinHandle<Result<F::Output, Aborted>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        tokio_shim::task::spawn(self.attach(fut))
+    }
+
+    /// Attach `fut` to the scope, so that it will be aborted when the scope is cancelled. The
+    /// returned future resolves to `Err(Aborted)` if it was aborted before completing.
+    pub fn attach<F: Future>(&self, fut: F) -> Scoped<F> {
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let (done_tx, done_rx) = oneshot::channel();
+        let mut state = self.state.lock().expect("lock poisoned");
+        if state.cancelled {
+            abort_handle.abort();
+        }
+        state.tasks.retain(|task| !task.is_done());
+        state.tasks.push(Task {
+            abort_handle,
+            done: done_rx.shared(),
+        });
+        Scoped {
+            inner: Abortable::new(fut, abort_registration),
+            done: Some(done_tx),
+        }
+    }
+
+    /// Abort all the futures in the scope, and any that are added to it later.
+    pub fn cancel(&self) {
+        let mut state = self.state.lock().expect("lock poisoned");
+        state.cancelled = true;
+        // The aborted futures are kept, so that joining the scope waits for them to be dropped.
+        for task in state.tasks.iter() {
+            task.abort_handle.abort();
+        }
+    }
+
+    /// Returns true if the scope has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.lock().expect("lock poisoned").cancelled
+    }
+
+    /// Wait until all the futures in the scope have completed or been dropped, which aborted
+    /// futures are once they have resolved to `Err(Aborted)`. This includes futures that are
+    /// added to the scope while waiting.
+    pub async fn join(&self) {
+        loop {
+            let pending: Vec<_> = {
+                let mut state = self.state.lock().expect("lock poisoned");
+                state.tasks.retain(|task| !task.is_done());
+                state.tasks.iter().map(|task| task.done.clone()).collect()
+            };
+            if pending.is_empty() {
+                return;
+            }
+            future::join_all(pending).await;
+        }
+    }
+}
+
+impl Drop for CancellationScope {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Future attached to a [CancellationScope], as returned by [CancellationScope::attach].
+#[pin_project]
+pub struct Scoped<F> {
+    #[pin]
+    inner: Abortable<F>,
+    /// Dropped when the future completes, to tell the scope it is done. If the future is
+    /// aborted, this is only dropped with it, which is after `inner` as it is declared after it.
+    done: Option<oneshot::Sender<()>>,
+}
+
+impl<F: Future> Future for Scoped<F> {
+    type Output = Result<F::Output, Aborted>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = futures::ready!(this.inner.poll(cx));
+        if res.is_ok() {
+            *this.done = None;
+        }
+        Poll::Ready(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn cancel_aborts_spawned_tasks() {
+        let scope = CancellationScope::new();
+        let tasks: Vec<_> = (0..3)
+            .map(|_| scope.spawn(future::pending::<()>()))
+            .collect();
+        scope.cancel();
+        scope.join().await;
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), Err(Aborted));
+        }
+        assert!(scope.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn join_after_cancel_waits_for_drop() {
+        struct SetOnDrop(Arc<AtomicUsize>);
+
+        impl Drop for SetOnDrop {
+            fn drop(&mut self) {
+                self.0.store(1, Ordering::SeqCst);
+            }
+        }
+
+        let scope = CancellationScope::new();
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let guard = SetOnDrop(dropped.clone());
+        let task = scope.spawn(async move {
+            let _guard = guard;
+            future::pending::<()>().await
+        });
+        scope.cancel();
+        scope.join().await;
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        assert_eq!(task.await.unwrap(), Err(Aborted));
+    }
+
+    #[tokio::test]
+    async fn drop_aborts_spawned_tasks() {
+        let scope = CancellationScope::new();
+        let task = scope.spawn(future::pending::<()>());
+        drop(scope);
+        assert_eq!(task.await.unwrap(), Err(Aborted));
+    }
+
+    #[tokio::test]
+    async fn join_waits_for_completion() {
+        let scope = CancellationScope::new();
+        let completed = Arc::new(AtomicUsize::new(0));
+        for i in 0..3 {
+            let completed = completed.clone();
+            scope.spawn(async move {
+                tokio::time::delay_for(Duration::from_millis(i * 10)).await;
+                completed.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        scope.join().await;
+        assert_eq!(completed.load(Ordering::SeqCst), 3);
+        assert!(!scope.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn attached_futures() {
+        let scope = CancellationScope::new();
+        assert_eq!(scope.attach(async { 5 }).await, Ok(5));
+
+        let pending = scope.attach(future::pending::<()>());
+        scope.cancel();
+        assert_eq!(pending.await, Err(Aborted));
+
+        // Futures attached after cancellation are aborted immediately.
+        assert_eq!(scope.attach(async { 5 }).await, Err(Aborted));
+        scope.join().await;
+    }
+
+    #[tokio::test]
+    async fn join_waits_for_dropped_futures() {
+        let scope = CancellationScope::new();
+        let attached = scope.attach(future::pending::<()>());
+        drop(attached);
+        scope.join().await;
+    }
+}
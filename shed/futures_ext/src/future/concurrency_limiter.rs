@@ -0,0 +1,355 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// A limit on the number of futures that run at once, which can be cloned to share the same
+/// limit between many futures or streams, for example all the requests a subsystem makes to a
+/// backend, wherever they are made from.
+///
+/// Unlike `buffered(n)`, which only bounds the futures of a single stream, the limit applies to
+/// every future run through [ConcurrencyLimiter::limited], or holding a permit from
+/// [ConcurrencyLimiter::acquire]. Waiting futures are granted permits in the order they started
+/// waiting. It doesn't depend on any runtime.
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimiter {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    limit: usize,
+    permits: Mutex<Permits>,
+    counters: Mutex<Counters>,
+}
+
+/// The permits that are available, and the futures waiting for one in the order they started
+/// waiting. A released permit is handed over to the first waiter that wasn't granted one yet, so
+/// there are permits available only when no waiter is left without one.
+#[derive(Debug)]
+struct Permits {
+    available: usize,
+    next_id: u64,
+    waiters: VecDeque<Waiter>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    id: u64,
+    waker: Waker,
+    granted: bool,
+}
+
+impl Permits {
+    fn release(&mut self) {
+        match self.waiters.iter_mut().find(|waiter| !waiter.granted) {
+            Some(waiter) => {
+                waiter.granted = true;
+                waiter.waker.wake_by_ref();
+            }
+            None => self.available += 1,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    waiters: usize,
+    in_flight: usize,
+    acquired: u64,
+    total_wait_time: Duration,
+    max_wait_time: Duration,
+}
+
+/// Statistics of a [ConcurrencyLimiter], as returned by [ConcurrencyLimiter::stats].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConcurrencyLimiterStats {
+    /// The maximum number of permits that can be held at once.
+    pub limit: usize,
+    /// The number of permits held at the moment.
+    pub in_flight: usize,
+    /// The number of futures waiting for a permit at the moment.
+    pub waiters: usize,
+    /// The number of permits that have been acquired since the limiter was created.
+    pub acquired: u64,
+    /// The total time spent waiting for the permits that have been acquired.
+    pub total_wait_time: Duration,
+    /// The longest time spent waiting for a single permit.
+    pub max_wait_time: Duration,
+}
+
+impl ConcurrencyLimiter {
+    /// Create a limiter that allows up to `limit` permits to be held at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is zero.
+    pub fn new(limit: usize) -> Self {
+        assert!(limit > 0, "limit must be greater than zero");
+        Self {
+            inner: Arc::new(Inner {
+                limit,
+                permits: Mutex::new(Permits {
+                    available: limit,
+                    next_id: 0,
+                    waiters: VecDeque::new(),
+                }),
+                counters: Mutex::new(Counters::default()),
+            }),
+        }
+    }
+
+    /// Run `fut` once a permit is available, holding the permit until it completes.
+    pub fn limited<F: Future>(&self, fut: F) -> impl Future<Output = F::Output> {
+        let limiter = self.clone();
+        async move {
+            let _permit = limiter.acquire().await;
+            fut.await
+        }
+    }
+
+    /// Wait until a permit is available, and take it. The permit is released when the returned
+    /// [ConcurrencyPermit] is dropped.
+    pub async fn acquire(&self) -> ConcurrencyPermit {
+        let start = Instant::now();
+        let waiting = Waiting::new(&self.inner);
+        Acquire {
+            inner: &self.inner,
+            id: None,
+        }
+        .await;
+        drop(waiting);
+        self.granted(start.elapsed())
+    }
+
+    /// Take a permit if one is available now, returning None if there wasn't one.
+    pub fn try_acquire(&self) -> Option<ConcurrencyPermit> {
+        let mut permits = self.inner.permits.lock().expect("lock poisoned");
+        if permits.available == 0 {
+            return None;
+        }
+        permits.available -= 1;
+        drop(permits);
+        Some(self.granted(Duration::from_secs(0)))
+    }
+
+    /// Return the current statistics of this limiter, which are shared between its clones.
+    pub fn stats(&self) -> ConcurrencyLimiterStats {
+        let counters = self.inner.counters.lock().expect("lock poisoned");
+        ConcurrencyLimiterStats {
+            limit: self.inner.limit,
+            in_flight: counters.in_flight,
+            waiters: counters.waiters,
+            acquired: counters.acquired,
+            total_wait_time: counters.total_wait_time,
+            max_wait_time: counters.max_wait_time,
+        }
+    }
+
+    fn granted(&self, wait_time: Duration) -> ConcurrencyPermit {
+        let mut counters = self.inner.counters.lock().expect("lock poisoned");
+        counters.in_flight += 1;
+        counters.acquired += 1;
+        counters.total_wait_time += wait_time;
+        counters.max_wait_time = counters.max_wait_time.max(wait_time);
+        ConcurrencyPermit {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// Waits for a permit, which it owns once it completes. If it is dropped before, it leaves the
+/// queue of waiters, and hands over the permit it was granted if there was one.
+struct Acquire<'a> {
+    inner: &'a Inner,
+    id: Option<u64>,
+}
+
+impl Future for Acquire<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut permits = self.inner.permits.lock().expect("lock poisoned");
+        match self.id {
+            None => {
+                if permits.available > 0 {
+                    permits.available -= 1;
+                    return Poll::Ready(());
+                }
+                let id = permits.next_id;
+                permits.next_id += 1;
+                permits.waiters.push_back(Waiter {
+                    id,
+                    waker: cx.waker().clone(),
+                    granted: false,
+                });
+                drop(permits);
+                self.id = Some(id);
+                Poll::Pending
+            }
+            Some(id) => {
+                let index = permits
+                    .waiters
+                    .iter()
+                    .position(|waiter| waiter.id == id)
+                    .expect("waiter is queued until it completes");
+                let waiter = &mut permits.waiters[index];
+                if !waiter.granted {
+                    if !waiter.waker.will_wake(cx.waker()) {
+                        waiter.waker = cx.waker().clone();
+                    }
+                    return Poll::Pending;
+                }
+                permits.waiters.remove(index);
+                drop(permits);
+                self.id = None;
+                Poll::Ready(())
+            }
+        }
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut permits = self.inner.permits.lock().expect("lock poisoned");
+            if let Some(index) = permits.waiters.iter().position(|waiter| waiter.id == id) {
+                let waiter = permits.waiters.remove(index);
+                if waiter.is_some_and(|waiter| waiter.granted) {
+                    permits.release();
+                }
+            }
+        }
+    }
+}
+
+/// Counts a future as waiting for a permit while it exists, including if the future is dropped
+/// while it waits.
+struct Waiting<'a>(&'a Inner);
+
+impl<'a> Waiting<'a> {
+    fn new(inner: &'a Inner) -> Self {
+        inner.counters.lock().expect("lock poisoned").waiters += 1;
+        Self(inner)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.counters.lock().expect("lock poisoned").waiters -= 1;
+    }
+}
+
+/// A permit from a [ConcurrencyLimiter], which is released when dropped.
+#[derive(Debug)]
+pub struct ConcurrencyPermit {
+    inner: Arc<Inner>,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.inner.counters.lock().expect("lock poisoned").in_flight -= 1;
+        self.inner.permits.lock().expect("lock poisoned").release();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::future::join_all;
+
+    #[tokio::test]
+    async fn test_limits_concurrency() {
+        let limiter = ConcurrencyLimiter::new(2);
+        let running = AtomicUsize::new(0);
+        let max_running = AtomicUsize::new(0);
+
+        let futs = (0..6).map(|_| {
+            limiter.limited(async {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now, Ordering::SeqCst);
+                tokio::time::delay_for(Duration::from_millis(10)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+            })
+        });
+        join_all(futs).await;
+
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+        let stats = limiter.stats();
+        assert_eq!(stats.limit, 2);
+        assert_eq!(stats.acquired, 6);
+        assert_eq!(stats.in_flight, 0);
+        assert_eq!(stats.waiters, 0);
+        assert!(stats.max_wait_time >= Duration::from_millis(10));
+        assert!(stats.total_wait_time >= stats.max_wait_time);
+    }
+
+    #[tokio::test]
+    async fn test_shared_between_clones() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let clone = limiter.clone();
+
+        let permit = limiter.acquire().await;
+        assert!(clone.try_acquire().is_none());
+        assert_eq!(clone.stats().in_flight, 1);
+
+        drop(permit);
+        assert!(clone.try_acquire().is_some());
+        assert_eq!(limiter.stats().in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let permit = limiter.acquire().await;
+
+        let mut waiter = Box::pin(limiter.acquire());
+        assert!(futures::poll!(waiter.as_mut()).is_pending());
+        assert_eq!(limiter.stats().waiters, 1);
+
+        drop(waiter);
+        assert_eq!(limiter.stats().waiters, 0);
+        drop(permit);
+        assert_eq!(limiter.stats().acquired, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_granted_waiter() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let permit = limiter.acquire().await;
+
+        let mut first = Box::pin(limiter.acquire());
+        let mut second = Box::pin(limiter.acquire());
+        assert!(futures::poll!(first.as_mut()).is_pending());
+        assert!(futures::poll!(second.as_mut()).is_pending());
+
+        // The permit is granted to the first waiter, which hands it over to the second one when
+        // it is dropped without having taken it.
+        drop(permit);
+        drop(first);
+        let second = futures::poll!(second.as_mut());
+        assert!(second.is_ready());
+        assert_eq!(limiter.stats().in_flight, 1);
+        assert!(limiter.try_acquire().is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_limit() {
+        let _ = ConcurrencyLimiter::new(0);
+    }
+}
@@ -0,0 +1,174 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::cell::Cell;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use futures::future::Future;
+use futures::task::{Context, Poll};
+use pin_project::pin_project;
+use thiserror::Error;
+use tokio_shim::time::Sleep;
+
+thread_local! {
+    /// The deadline of the future being polled on this thread, if any.
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Error returned when a future doesn't complete before its deadline.
+#[derive(Clone, Copy, Debug, Error, Eq, PartialEq)]
+#[error("deadline exceeded")]
+pub struct DeadlineExceeded;
+
+/// The deadline of the current task, as set by [with_deadline], or None if there is no deadline.
+///
+/// The deadline is only visible to code that is run while polling the future passed to
+/// [with_deadline], so it isn't inherited by spawned tasks.
+pub fn current_deadline() -> Option<Instant> {
+    DEADLINE.with(Cell::get)
+}
+
+/// The time remaining until the deadline of the current task, or None if there is no deadline.
+/// Returns zero if the deadline has passed.
+pub fn remaining_time() -> Option<Duration> {
+    current_deadline().map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+/// Run `fut` with a deadline of `deadline`, which is visible to the code it runs through
+/// [current_deadline] and [remaining_time]. If there is already a deadline, the earlier of the
+/// two is used. Resolves to [DeadlineExceeded] if `fut` doesn't complete before the deadline.
+pub fn with_deadline<F: Future>(deadline: Instant, fut: F) -> WithDeadline<F> {
+    WithDeadline::new(fut, Some(deadline))
+}
+
+/// Run `fut` with the deadline of the current task, resolving to [DeadlineExceeded] if it
+/// doesn't complete before the deadline. If there is no deadline, `fut` is run without one.
+///
+/// The deadline is read when this function is called.
+pub fn deadline_timeout<F: Future>(fut: F) -> WithDeadline<F> {
+    WithDeadline::new(fut, current_deadline())
+}
+
+/// Future returned by [with_deadline] and [deadline_timeout].
+#[pin_project]
+pub struct WithDeadline<F> {
+    #[pin]
+    inner: F,
+    deadline: Option<Instant>,
+    #[pin]
+    sleep: Option<Sleep>,
+}
+
+impl<F> WithDeadline<F> {
+    fn new(inner: F, deadline: Option<Instant>) -> Self {
+        Self {
+            inner,
+            deadline,
+            sleep: None,
+        }
+    }
+}
+
+/// Restores the previous deadline when dropped, including when polling panics.
+struct RestoreDeadline(Option<Instant>);
+
+impl Drop for RestoreDeadline {
+    fn drop(&mut self) {
+        DEADLINE.with(|deadline| deadline.set(self.0));
+    }
+}
+
+impl<F: Future> Future for WithDeadline<F> {
+    type Output = Result<F::Output, DeadlineExceeded>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        let deadline = match *this.deadline {
+            Some(deadline) => deadline,
+            None => return this.inner.poll(cx).map(Ok),
+        };
+
+        {
+            let previous = current_deadline();
+            let _restore = RestoreDeadline(previous);
+            let effective = previous.map_or(deadline, |previous| previous.min(deadline));
+            DEADLINE.with(|current| current.set(Some(effective)));
+            if let Poll::Ready(res) = this.inner.poll(cx) {
+                return Poll::Ready(Ok(res));
+            }
+        }
+
+        if this.sleep.is_none() {
+            this.sleep
+                .set(Some(tokio_shim::time::sleep_until(deadline)));
+        }
+        // NOTE: This unwrap() is safe as we just set the value.
+        match this.sleep.as_pin_mut().unwrap().poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Err(DeadlineExceeded)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::future;
+
+    #[tokio::test]
+    async fn test_deadline_visible_inside() {
+        assert_eq!(current_deadline(), None);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let res = with_deadline(deadline, async {
+            assert_eq!(current_deadline(), Some(deadline));
+            assert!(remaining_time().unwrap() <= Duration::from_secs(10));
+            5
+        })
+        .await;
+        assert_eq!(res, Ok(5));
+        assert_eq!(current_deadline(), None);
+    }
+
+    #[tokio::test]
+    async fn test_nested_deadlines_use_earliest() {
+        let outer = Instant::now() + Duration::from_secs(10);
+        let inner = outer + Duration::from_secs(10);
+        let res = with_deadline(outer, async move {
+            with_deadline(inner, async { current_deadline() }).await
+        })
+        .await;
+        assert_eq!(res, Ok(Ok(Some(outer))));
+    }
+
+    #[tokio::test]
+    async fn test_deadline_exceeded() {
+        let deadline = Instant::now() + Duration::from_millis(10);
+        let res = with_deadline(deadline, future::pending::<()>()).await;
+        assert_eq!(res, Err(DeadlineExceeded));
+    }
+
+    #[tokio::test]
+    async fn test_deadline_timeout() {
+        // Without a deadline, the future is run to completion.
+        assert_eq!(deadline_timeout(async { 5 }).await, Ok(5));
+
+        let deadline = Instant::now() + Duration::from_millis(10);
+        let res = with_deadline(deadline, async {
+            deadline_timeout(future::pending::<()>()).await
+        })
+        .await;
+        // Both futures have the same deadline, so either may report it.
+        assert!(matches!(
+            res,
+            Err(DeadlineExceeded) | Ok(Err(DeadlineExceeded))
+        ));
+    }
+}
@@ -0,0 +1,151 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::pin::Pin;
+
+use futures::future::{Future, TryFuture};
+use futures::ready;
+use futures::stream::{FuturesUnordered, Stream};
+use futures::task::{Context, Poll};
+use pin_project::pin_project;
+
+/// Error returned by [FirstOk] when all of its futures failed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FirstOkError<E> {
+    /// The errors of all the futures, in the order the futures were given in.
+    pub errors: Vec<E>,
+}
+
+impl<E: fmt::Display> fmt::Display for FirstOkError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "all {} futures failed", self.errors.len())?;
+        for (index, error) in self.errors.iter().enumerate() {
+            write!(f, "\n  {}: {}", index, error)?;
+        }
+        Ok(())
+    }
+}
+
+impl<E: StdError> StdError for FirstOkError<E> {}
+
+/// Future for the [first_ok] function.
+#[pin_project]
+pub struct FirstOk<Fut: TryFuture> {
+    #[pin]
+    futures: FuturesUnordered<Indexed<Fut>>,
+    errors: Vec<Option<Fut::Error>>,
+}
+
+/// Resolve to the result of the first of `futures` to succeed, dropping the others. If all of
+/// them fail, resolve to a [FirstOkError] holding all of their errors.
+///
+/// Unlike [futures::future::select_ok], which only returns the last error, this keeps every
+/// error, and it doesn't panic if `futures` is empty, resolving to a [FirstOkError] with no
+/// errors instead.
+pub fn first_ok<I>(futures: I) -> FirstOk<I::Item>
+where
+    I: IntoIterator,
+    I::Item: TryFuture,
+{
+    let futures: FuturesUnordered<_> = futures
+        .into_iter()
+        .enumerate()
+        .map(|(index, inner)| Indexed { index, inner })
+        .collect();
+    let errors = (0..futures.len()).map(|_| None).collect();
+    FirstOk { futures, errors }
+}
+
+impl<Fut: TryFuture> Future for FirstOk<Fut> {
+    type Output = Result<Fut::Ok, FirstOkError<Fut::Error>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        loop {
+            match ready!(this.futures.as_mut().poll_next(cx)) {
+                Some((_, Ok(value))) => return Poll::Ready(Ok(value)),
+                Some((index, Err(error))) => this.errors[index] = Some(error),
+                None => {
+                    let errors = this.errors.drain(..).flatten().collect();
+                    return Poll::Ready(Err(FirstOkError { errors }));
+                }
+            }
+        }
+    }
+}
+
+/// A future that resolves to its result along with its index.
+#[pin_project]
+struct Indexed<Fut> {
+    index: usize,
+    #[pin]
+    inner: Fut,
+}
+
+impl<Fut: TryFuture> Future for Indexed<Fut> {
+    type Output = (usize, Result<Fut::Ok, Fut::Error>);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.inner.try_poll(cx));
+        Poll::Ready((*this.index, res))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    use futures::future::{self, BoxFuture, FutureExt};
+
+    fn after(ms: u64, res: Result<u32, String>) -> BoxFuture<'static, Result<u32, String>> {
+        async move {
+            tokio::time::delay_for(Duration::from_millis(ms)).await;
+            res
+        }
+        .boxed()
+    }
+
+    #[tokio::test]
+    async fn resolves_to_first_ok() {
+        let res = first_ok(vec![
+            after(1, Err("a".to_string())),
+            after(20, Ok(2)),
+            after(5, Ok(3)),
+        ])
+        .await;
+        assert_eq!(res, Ok(3));
+    }
+
+    #[tokio::test]
+    async fn collects_all_errors_in_order() {
+        let res = first_ok(vec![
+            after(10, Err("a".to_string())),
+            after(1, Err("b".to_string())),
+            after(5, Err("c".to_string())),
+        ])
+        .await;
+        let err = res.unwrap_err();
+        assert_eq!(err.errors, vec!["a", "b", "c"]);
+        assert_eq!(
+            err.to_string(),
+            "all 3 futures failed\n  0: a\n  1: b\n  2: c"
+        );
+    }
+
+    #[tokio::test]
+    async fn empty() {
+        let res = first_ok(Vec::<future::Ready<Result<(), ()>>>::new()).await;
+        assert_eq!(res, Err(FirstOkError { errors: vec![] }));
+    }
+}
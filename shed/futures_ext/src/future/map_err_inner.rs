@@ -0,0 +1,88 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::pin::Pin;
+
+use futures::future::Future;
+use futures::ready;
+use futures::task::{Context, Poll};
+use pin_project::pin_project;
+
+/// Future combinator that flattens a future of `Result<Result<I, E1>, E2>` into a future of
+/// `Result<I, E2>`, converting the inner error with a closure. This structure's main usage is
+/// by calling [crate::FbTryFutureExt::map_err_inner].
+#[pin_project]
+pub struct MapErrInner<Fut, F> {
+    #[pin]
+    inner: Fut,
+    map_err: Option<F>,
+}
+
+impl<Fut, F> MapErrInner<Fut, F> {
+    pub(crate) fn new(inner: Fut, map_err: F) -> Self {
+        Self {
+            inner,
+            map_err: Some(map_err),
+        }
+    }
+}
+
+impl<Fut, F, I, E1, E2> Future for MapErrInner<Fut, F>
+where
+    Fut: Future<Output = Result<Result<I, E1>, E2>>,
+    F: FnOnce(E1) -> E2,
+{
+    type Output = Result<I, E2>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let output = ready!(this.inner.poll(cx));
+        let map_err = this
+            .map_err
+            .take()
+            .expect("MapErrInner polled after completion");
+        Poll::Ready(match output {
+            Ok(Ok(i)) => Ok(i),
+            Ok(Err(e1)) => Err(map_err(e1)),
+            Err(e2) => Err(e2),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::FbTryFutureExt;
+
+    #[tokio::test]
+    async fn test_map_err_inner() {
+        let ok = async { Ok::<Result<u32, u32>, String>(Ok(1)) };
+        assert_eq!(ok.map_err_inner(|e| e.to_string()).await, Ok(1));
+
+        let inner_err = async { Ok::<Result<u32, u32>, String>(Err(2)) };
+        assert_eq!(
+            inner_err.map_err_inner(|e| format!("inner {}", e)).await,
+            Err("inner 2".to_string())
+        );
+
+        let outer_err = async { Err::<Result<u32, u32>, String>("outer".to_string()) };
+        assert_eq!(
+            outer_err.map_err_inner(|e| e.to_string()).await,
+            Err("outer".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flatten_err() {
+        let inner_err = async { Ok::<Result<u32, String>, &str>(Err("inner".to_string())) };
+        assert_eq!(inner_err.flatten_err().await, Err("inner".to_string()));
+
+        let outer_err = async { Err::<Result<u32, String>, &str>("outer") };
+        assert_eq!(outer_err.flatten_err().await, Err("outer".to_string()));
+    }
+}
@@ -10,10 +10,19 @@
 //! Module extending functionality of [`futures::future`] module
 
 mod abort_handle_ref;
+mod cancellation_scope;
+mod concurrency_limiter;
 mod conservative_receiver;
+mod deadline;
+mod first_ok;
+mod map_err_inner;
 mod on_cancel;
 mod on_cancel_with_data;
+mod rate_limiter;
+mod retry;
+mod spawn_monitored;
 mod try_shared;
+mod watched;
 
 use anyhow::Error;
 use futures::future::{Future, FutureExt, TryFuture};
@@ -23,10 +32,24 @@ use tokio_shim::time::Timeout;
 pub use shared_error::anyhow::SharedError;
 
 pub use self::abort_handle_ref::{spawn_controlled, ControlledHandle};
+pub use self::cancellation_scope::{CancellationScope, Scoped};
+pub use self::concurrency_limiter::{
+    ConcurrencyLimiter, ConcurrencyLimiterStats, ConcurrencyPermit,
+};
 pub use self::conservative_receiver::ConservativeReceiver;
+pub use self::deadline::{
+    current_deadline, deadline_timeout, remaining_time, with_deadline, DeadlineExceeded,
+    WithDeadline,
+};
+pub use self::first_ok::{first_ok, FirstOk, FirstOkError};
+pub use self::map_err_inner::MapErrInner;
 pub use self::on_cancel::OnCancel;
 pub use self::on_cancel_with_data::{CancelData, OnCancelWithData};
-pub use self::try_shared::TryShared;
+pub use self::rate_limiter::RateLimiter;
+pub use self::retry::{retry_fn, Backoff, Retry, RetryAttempt, RetryPolicy};
+pub use self::spawn_monitored::{dump_tasks, spawn_monitored, MonitoredTask, TaskState};
+pub use self::try_shared::{TryShared, TrySharedTyped};
+pub use self::watched::{SlowPoll, Watched};
 
 /// A trait implemented by default for all Futures which extends the standard
 /// functionality.
@@ -58,6 +81,17 @@ pub trait FbFutureExt: Future {
     {
         OnCancelWithData::new(self, on_cancel)
     }
+
+    /// Measure each poll of this future, calling `on_slow_poll` with the location of the caller
+    /// whenever a single poll takes longer than `threshold`.
+    #[track_caller]
+    fn watched<F>(self, threshold: Duration, on_slow_poll: F) -> Watched<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(SlowPoll),
+    {
+        Watched::new(self, threshold, on_slow_poll)
+    }
 }
 
 impl<T> FbFutureExt for T where T: Future + ?Sized {}
@@ -69,16 +103,41 @@ pub trait FbTryFutureExt: Future {
     /// to the same result.
     ///
     /// Similar to [futures::future::Shared], but instead works on Futures
-    /// returning Result where Err is [anyhow::Error].
-    /// This is achieved by storing [anyhow::Error] in [std::sync::Arc].
+    /// returning Result where Err is [anyhow::Error], or any error that can
+    /// be converted into one.
+    /// This is achieved by storing the error as a [SharedError], which
+    /// holds [anyhow::Error] in [std::sync::Arc].
     fn try_shared(self) -> TryShared<Self>
     where
-        Self: TryFuture<Error = Error> + Sized,
+        Self: TryFuture + Sized,
         <Self as TryFuture>::Ok: Clone,
+        <Self as TryFuture>::Error: Into<Error>,
     {
         self::try_shared::try_shared(self)
     }
 
+    /// Like [FbTryFutureExt::try_shared], but keeps the type of the error by
+    /// storing it as a [shared_error::std::SharedError].
+    fn try_shared_typed(self) -> TrySharedTyped<Self>
+    where
+        Self: TryFuture + Sized,
+        <Self as TryFuture>::Ok: Clone,
+        <Self as TryFuture>::Error: std::error::Error + 'static,
+    {
+        self::try_shared::try_shared_typed(self)
+    }
+
+    /// Retry this future according to `policy` if it fails, calling `make_retry` to create the
+    /// future for each retry, e.g. `make_request().retry(policy, make_request)`. See [retry_fn]
+    /// to create the first attempt with the closure too.
+    fn retry<F>(self, policy: RetryPolicy<Self::Error>, make_retry: F) -> Retry<F>
+    where
+        Self: TryFuture + Sized,
+        F: FnMut() -> Self,
+    {
+        self::retry::retry(self, policy, make_retry)
+    }
+
     /// Convert a Future of Result<Result<I, E1>, E2> into a Future of Result<I, E1>, assuming E2
     /// can convert into E1.
     #[allow(clippy::type_complexity)]
@@ -103,6 +162,19 @@ pub trait FbTryFutureExt: Future {
 
         self.map(flatten_err)
     }
+
+    /// Convert a Future of Result<Result<I, E1>, E2> into a Future of Result<I, E2>, converting
+    /// E1 with `map_err`. This is the counterpart of [FbTryFutureExt::flatten_err] for when the
+    /// outer error is the more general one, such as an [anyhow::Error] around a fallible body
+    /// with a specific error type.
+    fn map_err_inner<I, E1, E2, F>(self, map_err: F) -> MapErrInner<Self, F>
+    where
+        Self: Sized,
+        Self: Future<Output = Result<Result<I, E1>, E2>>,
+        F: FnOnce(E1) -> E2,
+    {
+        MapErrInner::new(self, map_err)
+    }
 }
 
 impl<T> FbTryFutureExt for T where T: TryFuture + ?Sized {}
@@ -0,0 +1,142 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A token bucket rate limiter, which can be cloned to share the same limit between many
+/// futures or streams.
+///
+/// The bucket holds up to `burst` tokens, and is refilled at `rate` tokens per second. Each
+/// permit takes one token, so up to `burst` permits can be acquired at once, after which
+/// permits are granted at `rate` per second.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter that grants `rate` permits per second, with bursts of up to
+    /// `burst` permits. The bucket starts full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is not a positive number, or if `burst` is zero.
+    pub fn new(rate: f64, burst: u32) -> Self {
+        assert!(rate > 0.0, "rate must be positive");
+        assert!(burst > 0, "burst must be greater than zero");
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                rate,
+                burst: burst as f64,
+                tokens: burst as f64,
+                updated: tokio_shim::time::now(),
+            })),
+        }
+    }
+
+    /// Take a permit if one is available now, returning false if there wasn't one.
+    pub fn try_acquire(&self) -> bool {
+        self.try_acquire_or_wait().is_ok()
+    }
+
+    /// Take a permit if one is available now, or return how long the caller must wait before
+    /// one will be.
+    pub fn try_acquire_or_wait(&self) -> Result<(), Duration> {
+        let mut bucket = self.bucket.lock().expect("lock poisoned");
+        // The time is read from the same clock as the one the waits sleep with, so that the
+        // bucket is refilled when Tokio's time is paused and advanced.
+        let now = tokio_shim::time::now();
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * bucket.rate).min(bucket.burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            // The wait overflows a Duration if the rate is tiny.
+            let wait = (1.0 - bucket.tokens) / bucket.rate;
+            Err(Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX))
+        }
+    }
+
+    /// Wait until a permit is available, and take it.
+    pub async fn acquire(&self) {
+        while let Err(wait) = self.try_acquire_or_wait() {
+            tokio_shim::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_burst() {
+        let limiter = RateLimiter::new(1.0, 3);
+        assert!(limiter.try_acquire());
+        assert!(limiter.try_acquire());
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+        let wait = limiter.try_acquire_or_wait().unwrap_err();
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_shared_between_clones() {
+        let limiter = RateLimiter::new(1.0, 1);
+        let clone = limiter.clone();
+        assert!(limiter.try_acquire());
+        assert!(!clone.try_acquire());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits() {
+        let limiter = RateLimiter::new(100.0, 1);
+        let start = Instant::now();
+        for _ in 0..4 {
+            limiter.acquire().await;
+        }
+        // The first permit is immediate, and each of the others takes 10ms.
+        assert!(start.elapsed() >= Duration::from_millis(25));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_with_paused_time() {
+        tokio::time::pause();
+        let limiter = RateLimiter::new(1.0, 1);
+        let start = tokio::time::Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() >= Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_tiny_rate() {
+        let limiter = RateLimiter::new(1e-30, 1);
+        assert!(limiter.try_acquire());
+        assert_eq!(limiter.try_acquire_or_wait(), Err(Duration::MAX));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_burst() {
+        let _ = RateLimiter::new(1.0, 0);
+    }
+}
@@ -0,0 +1,369 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::{Future, TryFuture};
+use futures::ready;
+use futures::task::{Context, Poll};
+use pin_project::pin_project;
+use rand::Rng;
+use tokio_shim::time::Sleep;
+
+/// How long a [Retry] waits before each retry.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Backoff {
+    /// Wait the same duration before every retry.
+    Fixed(Duration),
+    /// Wait `initial` before the first retry, doubling the wait before each subsequent retry,
+    /// up to `max`.
+    Exponential {
+        /// The wait before the first retry.
+        initial: Duration,
+        /// The longest wait before any retry.
+        max: Duration,
+    },
+}
+
+/// Policy describing when and how often a [Retry] retries a failed future.
+///
+/// By default all errors are retried, and there is no limit on the number of attempts.
+pub struct RetryPolicy<E> {
+    backoff: Backoff,
+    jitter: bool,
+    max_attempts: Option<usize>,
+    retryable: Arc<dyn Fn(&E) -> bool + Send + Sync>,
+}
+
+impl<E> RetryPolicy<E> {
+    /// The longest wait before any retry of an exponential policy, unless it is changed with
+    /// [RetryPolicy::max_delay].
+    pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+    /// Create a policy that waits the same `delay` before every retry.
+    pub fn fixed(delay: Duration) -> Self {
+        Self::new(Backoff::Fixed(delay))
+    }
+
+    /// Create a policy that waits `initial` before the first retry, doubling the wait before
+    /// each subsequent retry, up to [RetryPolicy::DEFAULT_MAX_DELAY] unless [RetryPolicy::max_delay] is used
+    /// to change the limit.
+    pub fn exponential(initial: Duration) -> Self {
+        Self::new(Backoff::Exponential {
+            initial,
+            max: Self::DEFAULT_MAX_DELAY,
+        })
+    }
+
+    fn new(backoff: Backoff) -> Self {
+        Self {
+            backoff,
+            jitter: false,
+            max_attempts: None,
+            retryable: Arc::new(|_| true),
+        }
+    }
+
+    /// Limit the wait before any retry of an exponential policy to `max`.
+    pub fn max_delay(mut self, max: Duration) -> Self {
+        if let Backoff::Exponential { max: old_max, .. } = &mut self.backoff {
+            *old_max = max;
+        }
+        self
+    }
+
+    /// Randomize each wait to between half and all of the duration given by the backoff, so
+    /// that clients that failed at the same time don't all retry at the same time.
+    pub fn with_jitter(mut self) -> Self {
+        self.jitter = true;
+        self
+    }
+
+    /// Give up after `max_attempts` attempts, including the first. A limit of zero or one
+    /// means the future is never retried.
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Only retry errors for which `retryable` returns true. Other errors are returned
+    /// immediately.
+    pub fn retry_if<F>(mut self, retryable: F) -> Self
+    where
+        F: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        self.retryable = Arc::new(retryable);
+        self
+    }
+
+    /// The backoff of this policy.
+    pub fn backoff(&self) -> Backoff {
+        self.backoff
+    }
+
+    /// Returns true if a future that has failed `attempts` times with `error` should be
+    /// retried.
+    pub fn should_retry(&self, attempts: usize, error: &E) -> bool {
+        let within_limit = match self.max_attempts {
+            Some(max_attempts) => attempts < max_attempts,
+            None => true,
+        };
+        within_limit && (self.retryable)(error)
+    }
+
+    /// The duration to wait before retrying a future that has failed `attempts` times.
+    pub fn delay(&self, attempts: usize) -> Duration {
+        let delay = match self.backoff {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { initial, max } => {
+                let exponent = attempts.saturating_sub(1).min(u32::MAX as usize) as u32;
+                let factor = 2u32.checked_pow(exponent).unwrap_or(u32::MAX);
+                initial.saturating_mul(factor).min(max)
+            }
+        };
+        if self.jitter {
+            // Rounding may take the jittered wait just above the delay, which is the maximum.
+            let jitter = delay.mul_f64(rand::thread_rng().gen_range(0.0..=0.5));
+            (delay / 2).saturating_add(jitter).min(delay)
+        } else {
+            delay
+        }
+    }
+}
+
+impl<E> Clone for RetryPolicy<E> {
+    fn clone(&self) -> Self {
+        Self {
+            backoff: self.backoff,
+            jitter: self.jitter,
+            max_attempts: self.max_attempts,
+            retryable: self.retryable.clone(),
+        }
+    }
+}
+
+impl<E> fmt::Debug for RetryPolicy<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("backoff", &self.backoff)
+            .field("jitter", &self.jitter)
+            .field("max_attempts", &self.max_attempts)
+            .finish()
+    }
+}
+
+/// Creates the future for each attempt of a [Retry].
+///
+/// This is implemented for closures returning futures.
+pub trait RetryAttempt {
+    /// The future for each attempt.
+    type Future: TryFuture;
+
+    /// Create the future for the next attempt.
+    fn attempt(&mut self) -> Self::Future;
+}
+
+impl<F, Fut> RetryAttempt for F
+where
+    F: FnMut() -> Fut,
+    Fut: TryFuture,
+{
+    type Future = Fut;
+
+    fn attempt(&mut self) -> Fut {
+        self()
+    }
+}
+
+/// Future combinator that retries a failed future according to a [RetryPolicy], resolving to
+/// the result of the first successful attempt, or to the error of the last attempt.
+#[pin_project]
+pub struct Retry<A: RetryAttempt> {
+    make_attempt: A,
+    policy: RetryPolicy<<A::Future as TryFuture>::Error>,
+    attempts: usize,
+    #[pin]
+    future: A::Future,
+    #[pin]
+    sleep: Option<Sleep>,
+}
+
+impl<A: RetryAttempt> Retry<A> {
+    /// Construct a `Retry` combinator that will make attempts with `make_attempt`, retrying
+    /// them according to `policy`. The first attempt is created immediately.
+    pub fn new(mut make_attempt: A, policy: RetryPolicy<<A::Future as TryFuture>::Error>) -> Self {
+        Self::with_first_attempt(make_attempt.attempt(), make_attempt, policy)
+    }
+
+    fn with_first_attempt(
+        future: A::Future,
+        make_attempt: A,
+        policy: RetryPolicy<<A::Future as TryFuture>::Error>,
+    ) -> Self {
+        Self {
+            future,
+            make_attempt,
+            policy,
+            attempts: 0,
+            sleep: None,
+        }
+    }
+
+    /// The number of attempts made so far that have failed.
+    pub fn failed_attempts(&self) -> usize {
+        self.attempts
+    }
+}
+
+impl<A: RetryAttempt> Future for Retry<A> {
+    type Output = Result<<A::Future as TryFuture>::Ok, <A::Future as TryFuture>::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        loop {
+            if let Some(sleep) = this.sleep.as_mut().as_pin_mut() {
+                ready!(sleep.poll(cx));
+                this.sleep.set(None);
+                this.future.set(this.make_attempt.attempt());
+            }
+
+            match ready!(this.future.as_mut().try_poll(cx)) {
+                Ok(value) => return Poll::Ready(Ok(value)),
+                Err(error) => {
+                    *this.attempts += 1;
+                    if !this.policy.should_retry(*this.attempts, &error) {
+                        return Poll::Ready(Err(error));
+                    }
+                    let delay = this.policy.delay(*this.attempts);
+                    this.sleep.set(Some(tokio_shim::time::sleep(delay)));
+                }
+            }
+        }
+    }
+}
+
+/// Call `make_attempt` to create a future, retrying it by calling `make_attempt` again each
+/// time it fails, according to `policy`.
+pub fn retry_fn<F, Fut>(policy: RetryPolicy<Fut::Error>, make_attempt: F) -> Retry<F>
+where
+    F: FnMut() -> Fut,
+    Fut: TryFuture,
+{
+    Retry::new(make_attempt, policy)
+}
+
+pub(crate) fn retry<Fut, F>(fut: Fut, policy: RetryPolicy<Fut::Error>, make_retry: F) -> Retry<F>
+where
+    Fut: TryFuture,
+    F: FnMut() -> Fut,
+{
+    Retry::with_first_attempt(fut, make_retry, policy)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::future;
+
+    use crate::FbTryFutureExt;
+
+    fn counted(attempts: &AtomicUsize, succeed_at: usize) -> future::Ready<Result<usize, usize>> {
+        let attempt = attempts.fetch_add(1, Ordering::Relaxed) + 1;
+        if attempt >= succeed_at {
+            future::ok(attempt)
+        } else {
+            future::err(attempt)
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let attempts = AtomicUsize::new(0);
+        let policy = RetryPolicy::fixed(Duration::from_millis(1));
+        let res = retry_fn(policy, || counted(&attempts, 3)).await;
+        assert_eq!(res, Ok(3));
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn retries_future_with_new_attempts() {
+        let attempts = AtomicUsize::new(0);
+        let policy = RetryPolicy::fixed(Duration::from_millis(1));
+        let res = counted(&attempts, 3)
+            .retry(policy, || counted(&attempts, 3))
+            .await;
+        assert_eq!(res, Ok(3));
+        assert_eq!(attempts.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let attempts = AtomicUsize::new(0);
+        let policy = RetryPolicy::fixed(Duration::from_millis(1)).max_attempts(2);
+        let res = retry_fn(policy, || counted(&attempts, 3)).await;
+        assert_eq!(res, Err(2));
+        assert_eq!(attempts.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn only_retries_retryable_errors() {
+        let attempts = AtomicUsize::new(0);
+        let policy = RetryPolicy::fixed(Duration::from_millis(1)).retry_if(|e: &usize| *e < 2);
+        let res = retry_fn(policy, || counted(&attempts, 5)).await;
+        assert_eq!(res, Err(2));
+        assert_eq!(attempts.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn counts_failed_attempts() {
+        let attempts = AtomicUsize::new(0);
+        let policy = RetryPolicy::fixed(Duration::from_millis(1)).max_attempts(3);
+        let mut fut = Box::pin(retry_fn(policy, || counted(&attempts, 5)));
+        assert_eq!(fut.as_mut().await, Err(3));
+        assert_eq!(fut.failed_attempts(), 3);
+    }
+
+    #[test]
+    fn exponential_delays() {
+        let policy = RetryPolicy::<()>::exponential(Duration::from_millis(10))
+            .max_delay(Duration::from_millis(50));
+        let delays: Vec<_> = (1..=5).map(|attempts| policy.delay(attempts)).collect();
+        assert_eq!(
+            delays,
+            [10, 20, 40, 50, 50].map(Duration::from_millis).to_vec()
+        );
+        assert_eq!(policy.delay(1000), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn default_max_delay() {
+        let policy = RetryPolicy::<()>::exponential(Duration::from_secs(1)).with_jitter();
+        for attempts in [10, 64, 1000, usize::MAX] {
+            assert!(policy.delay(attempts) <= RetryPolicy::<()>::DEFAULT_MAX_DELAY);
+        }
+        let policy = RetryPolicy::<()>::exponential(Duration::MAX / 2).max_delay(Duration::MAX);
+        assert_eq!(policy.delay(64), Duration::MAX);
+        assert!(policy.with_jitter().delay(usize::MAX) >= Duration::MAX / 2);
+    }
+
+    #[test]
+    fn jittered_delays() {
+        let policy = RetryPolicy::<()>::fixed(Duration::from_millis(100)).with_jitter();
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+        }
+    }
+}
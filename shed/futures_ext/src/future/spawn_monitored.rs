@@ -0,0 +1,276 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::task::{Context, Poll};
+use pin_project::{pin_project, pinned_drop};
+use stats::prelude::*;
+use tokio_shim::task::JoinHandle;
+
+define_stats! {
+    prefix = "futures_ext.monitored_tasks";
+    spawned: dynamic_timeseries("{}.spawned", (name: &'static str); Rate, Sum),
+    completed: dynamic_timeseries("{}.completed", (name: &'static str); Rate, Sum),
+    dropped: dynamic_timeseries("{}.dropped", (name: &'static str); Rate, Sum),
+    running: dynamic_counter("{}.running", (name: &'static str)),
+}
+
+/// All the monitored tasks that have been spawned and have not finished yet, by id.
+static TASKS: Mutex<BTreeMap<u64, Arc<TaskShared>>> = Mutex::new(BTreeMap::new());
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Spawns a new detached task that is tracked under `name` until it finishes.
+///
+/// It is similar to [tokio_shim::task::spawn], but the task is listed by [dump_tasks] for as
+/// long as it is running, and the number of tasks spawned, completed and dropped before
+/// completion (because they were aborted, they panicked or the runtime was shut down), as well
+/// as the number still running, are exported via `stats` as
+/// `futures_ext.monitored_tasks.{name}.{spawned,completed,dropped,running}`.
+///
+/// The name is a `&'static str` so that the number of counters stays bounded; use it to
+/// identify the code that spawned the task rather than the individual task.
+pub fn spawn_monitored<F>(name: &'static str, fut: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio_shim::task::spawn(Monitored::new(name, fut))
+}
+
+/// Returns the monitored tasks that have not finished yet, in the order they were spawned, for
+/// finding out which tasks are stuck or leaking.
+pub fn dump_tasks() -> Vec<MonitoredTask> {
+    let tasks = TASKS.lock().expect("lock poisoned");
+    tasks
+        .iter()
+        .map(|(id, task)| {
+            let progress = task.progress.lock().expect("lock poisoned");
+            MonitoredTask {
+                id: *id,
+                name: task.name,
+                spawned_at: task.spawned_at,
+                state: progress.state,
+                poll_count: progress.poll_count,
+            }
+        })
+        .collect()
+}
+
+/// The state of a [MonitoredTask].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskState {
+    /// The task has been spawned, but has not been polled yet.
+    Scheduled,
+    /// The task is being polled.
+    Running,
+    /// The task has been polled and is waiting to be woken up.
+    Idle,
+}
+
+/// A snapshot of a task spawned with [spawn_monitored] that has not finished yet, as returned by
+/// [dump_tasks].
+#[derive(Clone, Debug)]
+pub struct MonitoredTask {
+    /// Unique id of the task, in the order the tasks were spawned.
+    pub id: u64,
+    /// The name the task was spawned with.
+    pub name: &'static str,
+    /// When the task was spawned.
+    pub spawned_at: Instant,
+    /// The state of the task.
+    pub state: TaskState,
+    /// How many times the task has been polled.
+    pub poll_count: u64,
+}
+
+impl MonitoredTask {
+    /// How long ago the task was spawned.
+    pub fn age(&self) -> Duration {
+        self.spawned_at.elapsed()
+    }
+}
+
+impl fmt::Display for MonitoredTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "task {} ({}): {:?}, spawned {:?} ago, polled {} times",
+            self.id,
+            self.name,
+            self.state,
+            self.age(),
+            self.poll_count
+        )
+    }
+}
+
+struct TaskShared {
+    name: &'static str,
+    spawned_at: Instant,
+    progress: Mutex<TaskProgress>,
+}
+
+struct TaskProgress {
+    state: TaskState,
+    poll_count: u64,
+}
+
+/// Future that keeps its task in [TASKS] until it completes or is dropped.
+#[pin_project(PinnedDrop)]
+struct Monitored<F> {
+    #[pin]
+    inner: F,
+    id: u64,
+    task: Arc<TaskShared>,
+    done: bool,
+}
+
+impl<F> Monitored<F> {
+    fn new(name: &'static str, inner: F) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let task = Arc::new(TaskShared {
+            name,
+            spawned_at: Instant::now(),
+            progress: Mutex::new(TaskProgress {
+                state: TaskState::Scheduled,
+                poll_count: 0,
+            }),
+        });
+        TASKS
+            .lock()
+            .expect("lock poisoned")
+            .insert(id, task.clone());
+        STATS::spawned.add_value(1, (name,));
+        STATS::running.increment_value(1, (name,));
+
+        Self {
+            inner,
+            id,
+            task,
+            done: false,
+        }
+    }
+
+    fn set_state(task: &TaskShared, state: TaskState) {
+        let mut progress = task.progress.lock().expect("lock poisoned");
+        if state == TaskState::Running {
+            progress.poll_count += 1;
+        }
+        progress.state = state;
+    }
+
+    fn finish(id: u64, task: &TaskShared) {
+        TASKS.lock().expect("lock poisoned").remove(&id);
+        STATS::running.increment_value(-1, (task.name,));
+    }
+}
+
+impl<F: Future> Future for Monitored<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        Self::set_state(this.task, TaskState::Running);
+
+        match this.inner.poll(cx) {
+            Poll::Ready(output) => {
+                *this.done = true;
+                Self::finish(*this.id, this.task);
+                STATS::completed.add_value(1, (this.task.name,));
+                Poll::Ready(output)
+            }
+            Poll::Pending => {
+                Self::set_state(this.task, TaskState::Idle);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[pinned_drop]
+impl<F> PinnedDrop for Monitored<F> {
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+        if !*this.done {
+            Self::finish(*this.id, this.task);
+            STATS::dropped.add_value(1, (this.task.name,));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::channel::oneshot;
+
+    fn dump_named(name: &str) -> Vec<MonitoredTask> {
+        dump_tasks()
+            .into_iter()
+            .filter(|task| task.name == name)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_tracks_running_tasks() {
+        let (started_tx, started_rx) = oneshot::channel();
+        let (finish_tx, finish_rx) = oneshot::channel::<()>();
+        let handle = spawn_monitored("test_tracks_running_tasks", async move {
+            started_tx.send(()).unwrap();
+            finish_rx.await.unwrap();
+            42
+        });
+
+        started_rx.await.unwrap();
+        let tasks = dump_named("test_tracks_running_tasks");
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].state, TaskState::Idle);
+        assert_eq!(tasks[0].poll_count, 1);
+        assert!(tasks[0].to_string().contains("test_tracks_running_tasks"));
+
+        finish_tx.send(()).unwrap();
+        assert_eq!(handle.await.unwrap(), 42);
+        assert!(dump_named("test_tracks_running_tasks").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_task_is_removed() {
+        let mut monitored = Box::pin(Monitored::new(
+            "test_dropped_task_is_removed",
+            futures::future::pending::<()>(),
+        ));
+        assert!(futures::poll!(monitored.as_mut()).is_pending());
+        let tasks = dump_named("test_dropped_task_is_removed");
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].state, TaskState::Idle);
+
+        drop(monitored);
+        assert!(dump_named("test_dropped_task_is_removed").is_empty());
+    }
+
+    #[test]
+    fn test_unpolled_task() {
+        let monitored = Monitored::new("test_unpolled_task", async {});
+        let tasks = dump_named("test_unpolled_task");
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].state, TaskState::Scheduled);
+        assert_eq!(tasks[0].poll_count, 0);
+
+        drop(monitored);
+        assert!(dump_named("test_unpolled_task").is_empty());
+    }
+}
@@ -10,18 +10,96 @@
 use anyhow::Error;
 use futures::future::{self, FutureExt, Shared, TryFuture, TryFutureExt};
 use shared_error::anyhow::{IntoSharedError, SharedError};
+use std::error::Error as StdError;
 
-/// Type returned by the `try_shared` method provided by the `FbFutureExt` trait.
-pub type TryShared<Fut> = Shared<future::MapErr<Fut, NewSharedError>>;
+/// Type returned by the `try_shared` method provided by the `FbTryFutureExt` trait.
+pub type TryShared<Fut> = Shared<future::MapErr<Fut, NewSharedError<<Fut as TryFuture>::Error>>>;
+
+/// Type returned by the `try_shared_typed` method provided by the `FbTryFutureExt` trait.
+pub type TrySharedTyped<Fut> =
+    Shared<future::MapErr<Fut, NewTypedSharedError<<Fut as TryFuture>::Error>>>;
 
 /// Type alias for easier definition of TryShared
-type NewSharedError = fn(Error) -> SharedError;
+type NewSharedError<E> = fn(E) -> SharedError;
+
+/// Type alias for easier definition of TrySharedTyped
+type NewTypedSharedError<E> = fn(E) -> shared_error::std::SharedError<E>;
 
 pub(crate) fn try_shared<Fut>(fut: Fut) -> TryShared<Fut>
 where
     <Fut as TryFuture>::Ok: Clone,
-    Fut: TryFuture<Error = Error> + Sized,
+    <Fut as TryFuture>::Error: Into<Error>,
+    Fut: TryFuture + Sized,
+{
+    fut.map_err(IntoSharedError::<SharedError>::shared_error as NewSharedError<_>)
+        .shared()
+}
+
+pub(crate) fn try_shared_typed<Fut>(fut: Fut) -> TrySharedTyped<Fut>
+where
+    <Fut as TryFuture>::Ok: Clone,
+    <Fut as TryFuture>::Error: StdError + 'static,
+    Fut: TryFuture + Sized,
 {
-    fut.map_err(IntoSharedError::<SharedError>::shared_error as NewSharedError)
+    fut.map_err(shared_error::std::SharedError::from as NewTypedSharedError<_>)
         .shared()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use anyhow::anyhow;
+    use futures::future::BoxFuture;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use thiserror::Error;
+
+    #[derive(Debug, Error)]
+    #[error("test error {0}")]
+    struct TestError(u32);
+
+    #[tokio::test]
+    async fn shares_anyhow_errors() {
+        let shared = try_shared(future::err::<(), _>(anyhow!("failed")));
+        let (a, b) = futures::join!(shared.clone(), shared);
+        assert_eq!(a.unwrap_err().to_string(), "failed");
+        assert_eq!(b.unwrap_err().to_string(), "failed");
+    }
+
+    #[tokio::test]
+    async fn shares_std_errors() {
+        let shared = try_shared(future::err::<(), _>(TestError(1)));
+        let clone = shared.clone();
+        assert_eq!(shared.await.unwrap_err().to_string(), "test error 1");
+        assert_eq!(clone.await.unwrap_err().to_string(), "test error 1");
+    }
+
+    #[tokio::test]
+    async fn shares_typed_errors() {
+        let shared = try_shared_typed(future::err::<(), _>(TestError(2)));
+        let clone = shared.clone();
+        let a = shared.await.unwrap_err();
+        let b = clone.await.unwrap_err();
+        assert_eq!(a.inner().0, 2);
+        assert!(std::ptr::eq(a.inner(), b.inner()));
+    }
+
+    #[tokio::test]
+    async fn runs_once() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let fut: BoxFuture<'static, Result<u32, Error>> = {
+            let polls = polls.clone();
+            async move {
+                polls.fetch_add(1, Ordering::SeqCst);
+                Ok(5)
+            }
+            .boxed()
+        };
+        let shared = try_shared(fut);
+        let (a, b) = futures::join!(shared.clone(), shared);
+        assert_eq!(a.unwrap(), 5);
+        assert_eq!(b.unwrap(), 5);
+        assert_eq!(polls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,163 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::fmt;
+use std::panic::Location;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use futures::future::Future;
+use futures::stream::Stream;
+use futures::task::{Context, Poll};
+use pin_project::pin_project;
+
+/// A single poll of a [Watched] future or stream that took longer than its threshold.
+#[derive(Clone, Copy, Debug)]
+pub struct SlowPoll {
+    /// Where the watched future or stream was created.
+    pub location: &'static Location<'static>,
+    /// How long the poll took.
+    pub duration: Duration,
+    /// The threshold that the poll exceeded.
+    pub threshold: Duration,
+}
+
+impl fmt::Display for SlowPoll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "poll of future watched at {} took {:?}, exceeding the threshold of {:?}",
+            self.location, self.duration, self.threshold
+        )
+    }
+}
+
+/// Future and stream combinator that measures each poll of the inner future or stream, calling
+/// `on_slow_poll` for each poll that takes longer than the threshold. This helps find code that
+/// blocks the executor.
+#[pin_project]
+pub struct Watched<Inner, OnSlowPollFn> {
+    #[pin]
+    inner: Inner,
+    threshold: Duration,
+    location: &'static Location<'static>,
+    on_slow_poll: OnSlowPollFn,
+}
+
+impl<Inner, OnSlowPollFn> Watched<Inner, OnSlowPollFn>
+where
+    OnSlowPollFn: FnMut(SlowPoll),
+{
+    /// Construct a `Watched` combinator that will call `on_slow_poll` whenever a poll of
+    /// `inner` takes longer than `threshold`. The location of the caller is reported with each
+    /// slow poll.
+    #[track_caller]
+    pub fn new(inner: Inner, threshold: Duration, on_slow_poll: OnSlowPollFn) -> Self {
+        Self {
+            inner,
+            threshold,
+            location: Location::caller(),
+            on_slow_poll,
+        }
+    }
+}
+
+impl<Inner, OnSlowPollFn> Watched<Inner, OnSlowPollFn> {
+    fn watch<T>(self: Pin<&mut Self>, poll: impl FnOnce(Pin<&mut Inner>) -> Poll<T>) -> Poll<T>
+    where
+        OnSlowPollFn: FnMut(SlowPoll),
+    {
+        let this = self.project();
+        let start = Instant::now();
+        let res = poll(this.inner);
+        let duration = start.elapsed();
+        if duration > *this.threshold {
+            (this.on_slow_poll)(SlowPoll {
+                location: this.location,
+                duration,
+                threshold: *this.threshold,
+            });
+        }
+        res
+    }
+}
+
+impl<Inner, OnSlowPollFn> Future for Watched<Inner, OnSlowPollFn>
+where
+    Inner: Future,
+    OnSlowPollFn: FnMut(SlowPoll),
+{
+    type Output = Inner::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.watch(|inner| inner.poll(cx))
+    }
+}
+
+impl<Inner, OnSlowPollFn> Stream for Watched<Inner, OnSlowPollFn>
+where
+    Inner: Stream,
+    OnSlowPollFn: FnMut(SlowPoll),
+{
+    type Item = Inner::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.watch(|inner| inner.poll_next(cx))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::stream::{self, StreamExt};
+
+    fn block_for(duration: Duration) {
+        std::thread::sleep(duration);
+    }
+
+    #[tokio::test]
+    async fn reports_slow_polls() {
+        let mut slow_polls = Vec::new();
+        let line = line!() + 1;
+        let fut = Watched::new(
+            async {
+                block_for(Duration::from_millis(20));
+                5
+            },
+            Duration::from_millis(10),
+            |slow_poll| slow_polls.push(slow_poll),
+        );
+        assert_eq!(fut.await, 5);
+        assert_eq!(slow_polls.len(), 1);
+        assert!(slow_polls[0].duration >= Duration::from_millis(20));
+        assert_eq!(slow_polls[0].location.file(), file!());
+        assert_eq!(slow_polls[0].location.line(), line);
+    }
+
+    #[tokio::test]
+    async fn ignores_fast_polls() {
+        let mut slow_polls = 0;
+        let fut = Watched::new(async { 5 }, Duration::from_secs(10), |_| slow_polls += 1);
+        assert_eq!(fut.await, 5);
+        assert_eq!(slow_polls, 0);
+    }
+
+    #[tokio::test]
+    async fn watches_streams() {
+        let mut slow_polls = 0;
+        let s = stream::iter(vec![1, 20, 1]).map(|ms| {
+            block_for(Duration::from_millis(ms));
+            ms
+        });
+        let s = Watched::new(s, Duration::from_millis(10), |_| slow_polls += 1);
+        assert_eq!(s.collect::<Vec<_>>().await, vec![1, 20, 1]);
+        assert_eq!(slow_polls, 1);
+    }
+}
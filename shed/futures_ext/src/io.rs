@@ -0,0 +1,303 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Module extending functionality of [`futures::io`] module with compression adapters, which
+//! are enabled by the `gzip` and `zstd` features.
+
+use futures::io::{AsyncRead, AsyncWrite, BufReader, IoSlice};
+use futures::task::{Context, Poll};
+use pin_project::pin_project;
+use std::io;
+use std::pin::Pin;
+
+use async_compression::futures::{bufread, write};
+
+/// A compression format supported by the compression adapters.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// The gzip format.
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// The zstd format.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+/// An [AsyncRead] that reads the compressed data of another reader, as returned by
+/// [FbAsyncReadExt::compressed].
+#[pin_project(project = CompressedReaderProj)]
+pub enum CompressedReader<R> {
+    /// Compressing with gzip.
+    #[cfg(feature = "gzip")]
+    Gzip(#[pin] bufread::GzipEncoder<BufReader<R>>),
+    /// Compressing with zstd.
+    #[cfg(feature = "zstd")]
+    Zstd(#[pin] bufread::ZstdEncoder<BufReader<R>>),
+}
+
+/// An [AsyncRead] that reads the decompressed data of another reader, as returned by
+/// [FbAsyncReadExt::decompressed].
+#[pin_project(project = DecompressedReaderProj)]
+pub enum DecompressedReader<R> {
+    /// Decompressing gzip.
+    #[cfg(feature = "gzip")]
+    Gzip(#[pin] bufread::GzipDecoder<BufReader<R>>),
+    /// Decompressing zstd.
+    #[cfg(feature = "zstd")]
+    Zstd(#[pin] bufread::ZstdDecoder<BufReader<R>>),
+}
+
+/// An [AsyncWrite] that compresses the data written to it before writing it to another writer,
+/// as returned by [FbAsyncWriteExt::compressed]. It must be closed to write the end of the
+/// compressed data.
+#[pin_project(project = CompressedWriterProj)]
+pub enum CompressedWriter<W> {
+    /// Compressing with gzip.
+    #[cfg(feature = "gzip")]
+    Gzip(#[pin] write::GzipEncoder<W>),
+    /// Compressing with zstd.
+    #[cfg(feature = "zstd")]
+    Zstd(#[pin] write::ZstdEncoder<W>),
+}
+
+/// An [AsyncWrite] that decompresses the data written to it before writing it to another
+/// writer, as returned by [FbAsyncWriteExt::decompressed].
+#[pin_project(project = DecompressedWriterProj)]
+pub enum DecompressedWriter<W> {
+    /// Decompressing gzip.
+    #[cfg(feature = "gzip")]
+    Gzip(#[pin] write::GzipDecoder<W>),
+    /// Decompressing zstd.
+    #[cfg(feature = "zstd")]
+    Zstd(#[pin] write::ZstdDecoder<W>),
+}
+
+impl<R: AsyncRead> AsyncRead for CompressedReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.project() {
+            #[cfg(feature = "gzip")]
+            CompressedReaderProj::Gzip(inner) => inner.poll_read(cx, buf),
+            #[cfg(feature = "zstd")]
+            CompressedReaderProj::Zstd(inner) => inner.poll_read(cx, buf),
+        }
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for DecompressedReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.project() {
+            #[cfg(feature = "gzip")]
+            DecompressedReaderProj::Gzip(inner) => inner.poll_read(cx, buf),
+            #[cfg(feature = "zstd")]
+            DecompressedReaderProj::Zstd(inner) => inner.poll_read(cx, buf),
+        }
+    }
+}
+
+macro_rules! impl_into_inner {
+    ($name:ident<$inner:ident: $bound:ident>, $($into_inner:tt)*) => {
+        impl<$inner: $bound> $name<$inner> {
+            /// Consume this adapter, returning the underlying reader or writer.
+            pub fn into_inner(self) -> $inner {
+                match self {
+                    #[cfg(feature = "gzip")]
+                    $name::Gzip(inner) => inner.into_inner()$($into_inner)*,
+                    #[cfg(feature = "zstd")]
+                    $name::Zstd(inner) => inner.into_inner()$($into_inner)*,
+                }
+            }
+        }
+    };
+}
+
+impl_into_inner!(CompressedReader<R: AsyncRead>, .into_inner());
+impl_into_inner!(DecompressedReader<R: AsyncRead>, .into_inner());
+impl_into_inner!(CompressedWriter<W: AsyncWrite>,);
+impl_into_inner!(DecompressedWriter<W: AsyncWrite>,);
+
+macro_rules! impl_async_write {
+    ($writer:ident, $proj:ident) => {
+        impl<W: AsyncWrite> AsyncWrite for $writer<W> {
+            fn poll_write(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<io::Result<usize>> {
+                match self.project() {
+                    #[cfg(feature = "gzip")]
+                    $proj::Gzip(inner) => inner.poll_write(cx, buf),
+                    #[cfg(feature = "zstd")]
+                    $proj::Zstd(inner) => inner.poll_write(cx, buf),
+                }
+            }
+
+            fn poll_write_vectored(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                bufs: &[IoSlice<'_>],
+            ) -> Poll<io::Result<usize>> {
+                match self.project() {
+                    #[cfg(feature = "gzip")]
+                    $proj::Gzip(inner) => inner.poll_write_vectored(cx, bufs),
+                    #[cfg(feature = "zstd")]
+                    $proj::Zstd(inner) => inner.poll_write_vectored(cx, bufs),
+                }
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                match self.project() {
+                    #[cfg(feature = "gzip")]
+                    $proj::Gzip(inner) => inner.poll_flush(cx),
+                    #[cfg(feature = "zstd")]
+                    $proj::Zstd(inner) => inner.poll_flush(cx),
+                }
+            }
+
+            fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                match self.project() {
+                    #[cfg(feature = "gzip")]
+                    $proj::Gzip(inner) => inner.poll_close(cx),
+                    #[cfg(feature = "zstd")]
+                    $proj::Zstd(inner) => inner.poll_close(cx),
+                }
+            }
+        }
+    };
+}
+
+impl_async_write!(CompressedWriter, CompressedWriterProj);
+impl_async_write!(DecompressedWriter, DecompressedWriterProj);
+
+/// A trait implemented by default for all AsyncReads which extends the standard
+/// functionality.
+pub trait FbAsyncReadExt: AsyncRead {
+    /// Read the data of this reader compressed with `compression`.
+    fn compressed(self, compression: Compression) -> CompressedReader<Self>
+    where
+        Self: Sized,
+    {
+        let reader = BufReader::new(self);
+        match compression {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => CompressedReader::Gzip(bufread::GzipEncoder::new(reader)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => CompressedReader::Zstd(bufread::ZstdEncoder::new(reader)),
+        }
+    }
+
+    /// Read the data of this reader decompressed from `compression`.
+    fn decompressed(self, compression: Compression) -> DecompressedReader<Self>
+    where
+        Self: Sized,
+    {
+        let reader = BufReader::new(self);
+        match compression {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => DecompressedReader::Gzip(bufread::GzipDecoder::new(reader)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => DecompressedReader::Zstd(bufread::ZstdDecoder::new(reader)),
+        }
+    }
+}
+
+impl<T> FbAsyncReadExt for T where T: AsyncRead + ?Sized {}
+
+/// A trait implemented by default for all AsyncWrites which extends the standard
+/// functionality.
+pub trait FbAsyncWriteExt: AsyncWrite {
+    /// Compress the data written with `compression` before writing it to this writer.
+    fn compressed(self, compression: Compression) -> CompressedWriter<Self>
+    where
+        Self: Sized,
+    {
+        match compression {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => CompressedWriter::Gzip(write::GzipEncoder::new(self)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => CompressedWriter::Zstd(write::ZstdEncoder::new(self)),
+        }
+    }
+
+    /// Decompress the data written from `compression` before writing it to this writer.
+    fn decompressed(self, compression: Compression) -> DecompressedWriter<Self>
+    where
+        Self: Sized,
+    {
+        match compression {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => DecompressedWriter::Gzip(write::GzipDecoder::new(self)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => DecompressedWriter::Zstd(write::ZstdDecoder::new(self)),
+        }
+    }
+}
+
+impl<T> FbAsyncWriteExt for T where T: AsyncWrite + ?Sized {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::io::{AsyncReadExt, AsyncWriteExt, Cursor};
+
+    const DATA: &[u8] = b"some data that is repeated, some data that is repeated";
+
+    fn compressions() -> Vec<Compression> {
+        vec![
+            #[cfg(feature = "gzip")]
+            Compression::Gzip,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd,
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_read_round_trip() {
+        for compression in compressions() {
+            let mut compressed = Vec::new();
+            FbAsyncReadExt::compressed(Cursor::new(DATA), compression)
+                .read_to_end(&mut compressed)
+                .await
+                .unwrap();
+            assert_ne!(compressed, DATA);
+
+            let mut decompressed = Vec::new();
+            FbAsyncReadExt::decompressed(Cursor::new(compressed), compression)
+                .read_to_end(&mut decompressed)
+                .await
+                .unwrap();
+            assert_eq!(decompressed, DATA);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_round_trip() {
+        for compression in compressions() {
+            let mut writer = FbAsyncWriteExt::compressed(Vec::new(), compression);
+            writer.write_all(DATA).await.unwrap();
+            writer.close().await.unwrap();
+            let compressed = writer.into_inner();
+
+            let mut writer = FbAsyncWriteExt::decompressed(Vec::new(), compression);
+            writer.write_all(&compressed).await.unwrap();
+            writer.close().await.unwrap();
+            let decompressed = writer.into_inner();
+            assert_eq!(decompressed, DATA);
+        }
+    }
+}
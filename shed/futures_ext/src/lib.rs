@@ -12,7 +12,11 @@
 //! Crate extending functionality of [`futures`] crate
 
 pub mod future;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub mod io;
 pub mod stream;
 
 pub use crate::future::{FbFutureExt, FbTryFutureExt};
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use crate::io::{FbAsyncReadExt, FbAsyncWriteExt};
 pub use crate::stream::{BufferedParams, FbStreamExt, FbTryStreamExt};
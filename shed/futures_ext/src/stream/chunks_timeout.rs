@@ -0,0 +1,156 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use futures::{
+    future::Future,
+    ready,
+    stream::{Fuse, Stream, StreamExt},
+    task::{Context, Poll},
+};
+use pin_project::pin_project;
+use std::mem;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio_shim::time::Sleep;
+
+/// A stream that batches the items of the inner stream into vectors, yielding a batch when it
+/// reaches the given size, or when the given duration has elapsed since its first item was
+/// buffered, whichever comes first. A final, possibly smaller, batch is yielded when the inner
+/// stream ends.
+#[pin_project]
+pub struct ChunksTimeout<S: Stream> {
+    #[pin]
+    inner: Fuse<S>,
+    items: Vec<S::Item>,
+    size: usize,
+    duration: Duration,
+    #[pin]
+    deadline: Option<Sleep>,
+}
+
+impl<S: Stream> ChunksTimeout<S> {
+    /// Create a new [ChunksTimeout].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(inner: S, size: usize, duration: Duration) -> Self {
+        assert!(size > 0, "chunk size must be greater than zero");
+        Self {
+            inner: inner.fuse(),
+            items: Vec::with_capacity(size),
+            size,
+            duration,
+            deadline: None,
+        }
+    }
+
+    fn take_items(items: &mut Vec<S::Item>, size: usize) -> Vec<S::Item> {
+        mem::replace(items, Vec::with_capacity(size))
+    }
+}
+
+impl<S: Stream> Stream for ChunksTimeout<S> {
+    type Item = Vec<S::Item>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(deadline) = this.deadline.as_mut().as_pin_mut() {
+                if deadline.poll(cx).is_ready() {
+                    this.deadline.set(None);
+                    return Poll::Ready(Some(Self::take_items(this.items, *this.size)));
+                }
+            }
+
+            match ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(item) => {
+                    if this.items.is_empty() {
+                        // The clock starts when the first item of a batch is buffered.
+                        this.deadline
+                            .set(Some(tokio_shim::time::sleep(*this.duration)));
+                    }
+                    this.items.push(item);
+                    if this.items.len() >= *this.size {
+                        this.deadline.set(None);
+                        return Poll::Ready(Some(Self::take_items(this.items, *this.size)));
+                    }
+                }
+                None => {
+                    this.deadline.set(None);
+                    if this.items.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    return Poll::Ready(Some(Self::take_items(this.items, *this.size)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::stream;
+
+    #[tokio::test]
+    async fn test_chunks_by_size() {
+        let s = ChunksTimeout::new(stream::iter(0..7), 3, Duration::from_secs(1));
+        let chunks = s.collect::<Vec<_>>().await;
+        assert_eq!(chunks, vec![vec![0, 1, 2], vec![3, 4, 5], vec![6]]);
+    }
+
+    #[tokio::test]
+    async fn test_chunks_by_timeout() {
+        tokio::time::pause();
+
+        let s = async_stream::stream! {
+            yield 1;
+            yield 2;
+            tokio::time::advance(Duration::from_secs(2)).await;
+            yield 3;
+        };
+
+        let mut s = ChunksTimeout::new(s.boxed(), 10, Duration::from_secs(1)).boxed();
+
+        assert_eq!(s.next().await, Some(vec![1, 2]));
+        assert_eq!(s.next().await, Some(vec![3]));
+        assert_eq!(s.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_clock_starts_at_first_item() {
+        tokio::time::pause();
+
+        let s = async_stream::stream! {
+            tokio::time::advance(Duration::from_secs(2)).await;
+            yield 1;
+            yield 2;
+        };
+
+        let mut s = ChunksTimeout::new(s.boxed(), 2, Duration::from_secs(1)).boxed();
+
+        assert_eq!(s.next().await, Some(vec![1, 2]));
+        assert_eq!(s.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_empty_stream() {
+        let s = ChunksTimeout::new(stream::empty::<()>(), 2, Duration::from_secs(1));
+        assert_eq!(s.collect::<Vec<_>>().await, Vec::<Vec<()>>::new());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_size() {
+        let _ = ChunksTimeout::new(stream::empty::<()>(), 0, Duration::from_secs(1));
+    }
+}
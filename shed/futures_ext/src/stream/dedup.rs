@@ -0,0 +1,176 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use futures::{
+    ready,
+    stream::Stream,
+    task::{Context, Poll},
+};
+use pin_project::pin_project;
+use std::pin::Pin;
+
+/// A stream that drops items that are equal to the item before them, so that runs of adjacent
+/// duplicates are yielded once. Each item that is yielded is cloned to compare with the items
+/// after it, but the dropped duplicates are not.
+#[pin_project]
+pub struct DedupConsecutive<S: Stream> {
+    #[pin]
+    inner: S,
+    /// The last item yielded.
+    last: Option<S::Item>,
+}
+
+impl<S> DedupConsecutive<S>
+where
+    S: Stream,
+    S::Item: PartialEq + Clone,
+{
+    /// Create a new [DedupConsecutive].
+    pub fn new(inner: S) -> Self {
+        Self { inner, last: None }
+    }
+}
+
+impl<S> Stream for DedupConsecutive<S>
+where
+    S: Stream,
+    S::Item: PartialEq + Clone,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            let item = match ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(item) => item,
+                None => return Poll::Ready(None),
+            };
+            if this.last.as_ref() != Some(&item) {
+                *this.last = Some(item.clone());
+                return Poll::Ready(Some(item));
+            }
+        }
+    }
+}
+
+/// A stream that drops items whose key is the same as the key of the item before them, so that
+/// runs of adjacent duplicates are yielded once.
+#[pin_project]
+pub struct DedupByKey<S, F, K> {
+    #[pin]
+    inner: S,
+    key_fn: F,
+    /// The key of the last item yielded.
+    last: Option<K>,
+}
+
+impl<S, F, K> DedupByKey<S, F, K>
+where
+    S: Stream,
+    F: FnMut(&S::Item) -> K,
+    K: PartialEq,
+{
+    /// Create a new [DedupByKey], comparing the keys returned by `key_fn`.
+    pub fn new(inner: S, key_fn: F) -> Self {
+        Self {
+            inner,
+            key_fn,
+            last: None,
+        }
+    }
+}
+
+impl<S, F, K> Stream for DedupByKey<S, F, K>
+where
+    S: Stream,
+    F: FnMut(&S::Item) -> K,
+    K: PartialEq,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            let item = match ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(item) => item,
+                None => return Poll::Ready(None),
+            };
+            let key = (this.key_fn)(&item);
+            if this.last.as_ref() != Some(&key) {
+                *this.last = Some(key);
+                return Poll::Ready(Some(item));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::stream::{self, StreamExt};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use crate::FbStreamExt;
+
+    #[tokio::test]
+    async fn test_dedup_consecutive() {
+        let s = stream::iter(vec![1, 1, 2, 2, 2, 1, 3, 3]).dedup_consecutive();
+        assert_eq!(s.collect::<Vec<_>>().await, vec![1, 2, 1, 3]);
+    }
+
+    /// An item that counts how many times it is cloned.
+    struct Counted<'a>(u32, &'a AtomicUsize);
+
+    impl PartialEq for Counted<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Clone for Counted<'_> {
+        fn clone(&self) -> Self {
+            self.1.fetch_add(1, Ordering::SeqCst);
+            Counted(self.0, self.1)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dedup_consecutive_clones_yielded_items() {
+        let clones = AtomicUsize::new(0);
+        let items = [1, 1, 1, 2, 2, 1].map(|i| Counted(i, &clones));
+        let s = stream::iter(items).dedup_consecutive();
+        let values = s.map(|item| item.0).collect::<Vec<_>>().await;
+        assert_eq!(values, vec![1, 2, 1]);
+        assert_eq!(clones.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_by_key() {
+        let s = stream::iter(vec![(1, "a"), (1, "b"), (2, "c"), (1, "d")]).dedup_by_key(|i| i.0);
+        assert_eq!(
+            s.collect::<Vec<_>>().await,
+            vec![(1, "a"), (2, "c"), (1, "d")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dedup_across_pending() {
+        let s = async_stream::stream! {
+            yield 1;
+            tokio::time::delay_for(Duration::from_millis(1)).await;
+            yield 1;
+            tokio::time::delay_for(Duration::from_millis(1)).await;
+            yield 2;
+        };
+        let s = s.boxed().dedup_consecutive();
+        assert_eq!(s.collect::<Vec<_>>().await, vec![1, 2]);
+    }
+}
@@ -9,21 +9,43 @@
 
 //! Module extending functionality of [`futures::stream`] module
 
+mod chunks_timeout;
+mod dedup;
+mod partition_buffered;
+mod prefetch;
+mod rate_limited;
 mod return_remainder;
+mod shutdown_aware_channel;
 mod stream_with_timeout;
+mod take_until_signal;
+mod tee;
+mod try_buffered_keep_going;
 mod weight_limited_buffered_stream;
+mod weighted_futures_unordered;
 mod yield_periodically;
 
 use futures::{Future, Stream, StreamExt, TryFuture, TryStream};
 use std::time::Duration;
 
-use crate::future::ConservativeReceiver;
+use crate::future::{ConservativeReceiver, RateLimiter, SlowPoll, Watched};
 
+pub use self::chunks_timeout::ChunksTimeout;
+pub use self::dedup::{DedupByKey, DedupConsecutive};
+pub use self::partition_buffered::PartitionBuffered;
+pub use self::prefetch::{Prefetch, PrefetchStats};
+pub use self::rate_limited::RateLimited;
 pub use self::return_remainder::ReturnRemainder;
+pub use self::shutdown_aware_channel::{
+    shutdown_aware_channel, ShutdownAwareReceiver, ShutdownAwareReceiverError, ShutdownAwareSender,
+};
 pub use self::stream_with_timeout::{StreamTimeoutError, StreamWithTimeout};
+pub use self::take_until_signal::TakeUntilSignal;
+pub use self::tee::Tee;
+pub use self::try_buffered_keep_going::TryBufferedKeepGoing;
 pub use self::weight_limited_buffered_stream::{
     BufferedParams, WeightLimitedBufferedStream, WeightLimitedBufferedTryStream,
 };
+pub use self::weighted_futures_unordered::WeightedFuturesUnordered;
 pub use self::yield_periodically::YieldPeriodically;
 
 /// A trait implemented by default for all Streams which extends the standard
@@ -41,6 +63,7 @@ pub trait FbStreamExt: Stream {
 
     /// Like [futures::stream::StreamExt::buffered] call,
     /// but can also limit number of futures in a buffer by "weight".
+    /// Outputs are yielded in the same order as the input futures.
     fn buffered_weight_limited<'a, I, Fut>(
         self,
         params: BufferedParams,
@@ -68,6 +91,125 @@ pub trait FbStreamExt: Stream {
     {
         YieldPeriodically::new(self, Duration::from_millis(10))
     }
+
+    /// Construct a new [self::chunks_timeout::ChunksTimeout], which batches items into vectors
+    /// of up to `size` items, yielding a batch early if `duration` has elapsed since its first
+    /// item was buffered.
+    fn chunks_timeout(self, size: usize, duration: Duration) -> ChunksTimeout<Self>
+    where
+        Self: Sized,
+    {
+        ChunksTimeout::new(self, size, duration)
+    }
+
+    /// Construct a new [self::rate_limited::RateLimited], which yields at most `rate` items
+    /// per second on average, with bursts of up to `burst` items.
+    fn rate_limit(self, rate: f64, burst: u32) -> RateLimited<Self>
+    where
+        Self: Sized,
+    {
+        RateLimited::new(self, RateLimiter::new(rate, burst))
+    }
+
+    /// Construct a new [self::rate_limited::RateLimited] that shares the limit of `limiter`
+    /// with the other streams and futures using it.
+    fn rate_limit_with(self, limiter: RateLimiter) -> RateLimited<Self>
+    where
+        Self: Sized,
+    {
+        RateLimited::new(self, limiter)
+    }
+
+    /// Construct a new [self::dedup::DedupConsecutive], which drops items that are equal to the
+    /// item before them.
+    fn dedup_consecutive(self) -> DedupConsecutive<Self>
+    where
+        Self: Sized,
+        Self::Item: PartialEq + Clone,
+    {
+        DedupConsecutive::new(self)
+    }
+
+    /// Construct a new [self::dedup::DedupByKey], which drops items whose key, as returned by
+    /// `key_fn`, is equal to the key of the item before them.
+    fn dedup_by_key<F, K>(self, key_fn: F) -> DedupByKey<Self, F, K>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> K,
+        K: PartialEq,
+    {
+        DedupByKey::new(self, key_fn)
+    }
+
+    /// Construct a new [self::take_until_signal::TakeUntilSignal], which ends cleanly when
+    /// `signal` resolves, after yielding any item that the stream has already produced.
+    fn take_until_signal<F>(self, signal: F) -> TakeUntilSignal<Self, F>
+    where
+        Self: Sized,
+        F: Future,
+    {
+        TakeUntilSignal::new(self, signal)
+    }
+
+    /// Split this stream into `n` streams that each yield a clone of every item, buffering up
+    /// to 16 items for the slower streams before applying backpressure to this stream.
+    fn tee(self, n: usize) -> Vec<Tee<Self>>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+    {
+        Tee::new(self, n, 16)
+    }
+
+    /// Like [FbStreamExt::tee], but buffering up to `buffer_size` items for the slower streams.
+    fn tee_with_buffer(self, n: usize, buffer_size: usize) -> Vec<Tee<Self>>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+    {
+        Tee::new(self, n, buffer_size)
+    }
+
+    /// Split this stream into a stream of the items for which `predicate` returns true, and a
+    /// stream of the rest, as a pair of [self::partition_buffered::PartitionBuffered]. Up to
+    /// `capacity` items are buffered for the slower stream before applying backpressure to this
+    /// stream.
+    ///
+    /// Panics if `capacity` is zero.
+    fn partition_buffered<P>(
+        self,
+        predicate: P,
+        capacity: usize,
+    ) -> (PartitionBuffered<Self, P>, PartitionBuffered<Self, P>)
+    where
+        Self: Sized,
+        P: FnMut(&Self::Item) -> bool,
+    {
+        PartitionBuffered::new(self, predicate, capacity)
+    }
+
+    /// Drive this stream eagerly on a spawned task, buffering up to `n` items ahead of the
+    /// consumer. The returned [self::prefetch::Prefetch] exposes the occupancy of the buffer.
+    ///
+    /// Panics if `n` is zero, or if called outside of a Tokio runtime.
+    fn prefetch(self, n: usize) -> Prefetch<Self::Item>
+    where
+        Self: Sized + Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        Prefetch::new(self, n)
+    }
+
+    /// Measure each poll of this stream, calling `on_slow_poll` with the location of the caller
+    /// whenever a single poll takes longer than `threshold`.
+    #[track_caller]
+    fn watched<F>(self, threshold: Duration, on_slow_poll: F) -> Watched<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(SlowPoll),
+    {
+        Watched::new(self, threshold, on_slow_poll)
+    }
 }
 
 impl<T> FbStreamExt for T where T: Stream + ?Sized {}
@@ -77,6 +219,7 @@ impl<T> FbStreamExt for T where T: Stream + ?Sized {}
 pub trait FbTryStreamExt: TryStream {
     /// Like [futures::stream::StreamExt::buffered] call, but for `TryStream` and
     /// can also limit number of futures in a buffer by "weight".
+    /// Outputs are yielded in the same order as the input futures.
     fn try_buffered_weight_limited<'a, I, Fut, E>(
         self,
         params: BufferedParams,
@@ -89,6 +232,18 @@ pub trait FbTryStreamExt: TryStream {
         WeightLimitedBufferedTryStream::new(params, self)
     }
 
+    /// Like [futures::stream::TryStreamExt::try_buffered], but instead of ending at the first
+    /// error, it yields every error and keeps driving the remaining futures. Use
+    /// [self::try_buffered_keep_going::TryBufferedKeepGoing::with_max_errors] to give up after
+    /// too many errors.
+    fn try_buffered_keep_going(self, n: usize) -> TryBufferedKeepGoing<Self>
+    where
+        Self: Sized,
+        Self::Ok: TryFuture<Error = Self::Error>,
+    {
+        TryBufferedKeepGoing::new(self, n)
+    }
+
     /// Convert a Stream of Result<Result<I, E1>, E2> into a Stream of Result<I, E1>, assuming E2
     /// can convert into E1.
     #[allow(clippy::type_complexity)]
@@ -0,0 +1,202 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use futures::{
+    stream::Stream,
+    task::{Context, Poll, Waker},
+};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// One of the two output streams of [crate::FbStreamExt::partition_buffered]: the first yields
+/// the items of the source stream that match the predicate, and the second yields the rest.
+///
+/// Items for an output stream are buffered until it yields them, up to a limit, after which the
+/// source stream isn't polled until that output stream catches up. Items for an output stream
+/// that has been dropped are discarded.
+pub struct PartitionBuffered<S: Stream, P> {
+    /// Whether this stream yields the items that match the predicate.
+    matching: bool,
+    shared: Arc<Mutex<Shared<S, P>>>,
+}
+
+struct Shared<S: Stream, P> {
+    source: Pin<Box<S>>,
+    predicate: P,
+    done: bool,
+    /// Items buffered for each output stream, indexed by whether they match the predicate.
+    buffers: [VecDeque<S::Item>; 2],
+    capacity: usize,
+    /// Whether each output stream has been dropped.
+    dropped: [bool; 2],
+    /// Output streams waiting for an item to be added to their buffer, or removed from the
+    /// buffer of the other stream.
+    wakers: [Option<Waker>; 2],
+}
+
+impl<S: Stream, P> Shared<S, P> {
+    fn wake(&mut self, index: usize) {
+        if let Some(waker) = self.wakers[index].take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<S, P> PartitionBuffered<S, P>
+where
+    S: Stream,
+    P: FnMut(&S::Item) -> bool,
+{
+    /// Split `source` into a stream of the items for which `predicate` returns true, and a
+    /// stream of the rest, buffering up to `capacity` items for each of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(source: S, predicate: P, capacity: usize) -> (Self, Self) {
+        assert!(capacity > 0, "capacity must be greater than zero");
+        let shared = Arc::new(Mutex::new(Shared {
+            source: Box::pin(source),
+            predicate,
+            done: false,
+            buffers: [VecDeque::new(), VecDeque::new()],
+            capacity,
+            dropped: [false; 2],
+            wakers: [None, None],
+        }));
+        (
+            PartitionBuffered {
+                matching: true,
+                shared: shared.clone(),
+            },
+            PartitionBuffered {
+                matching: false,
+                shared,
+            },
+        )
+    }
+}
+
+impl<S, P> Stream for PartitionBuffered<S, P>
+where
+    S: Stream,
+    P: FnMut(&S::Item) -> bool,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.shared.lock().expect("lock poisoned");
+        let shared = &mut *shared;
+        let index = self.matching as usize;
+        let other = 1 - index;
+
+        if let Some(item) = shared.buffers[index].pop_front() {
+            // The other stream may have been waiting for space in this buffer.
+            shared.wake(other);
+            return Poll::Ready(Some(item));
+        }
+
+        loop {
+            if shared.done {
+                return Poll::Ready(None);
+            }
+            if shared.buffers[other].len() >= shared.capacity {
+                shared.wakers[index] = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+            match shared.source.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    let target = (shared.predicate)(&item) as usize;
+                    if target == index {
+                        // The source only wakes the stream that polled it last, so let the
+                        // other stream poll it if it was waiting.
+                        shared.wake(other);
+                        return Poll::Ready(Some(item));
+                    }
+                    if !shared.dropped[target] {
+                        shared.buffers[target].push_back(item);
+                        shared.wake(target);
+                    }
+                }
+                Poll::Ready(None) => {
+                    shared.done = true;
+                    shared.wake(other);
+                    return Poll::Ready(None);
+                }
+                Poll::Pending => {
+                    shared.wakers[index] = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+impl<S: Stream, P> Drop for PartitionBuffered<S, P> {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            let index = self.matching as usize;
+            shared.dropped[index] = true;
+            shared.buffers[index].clear();
+            shared.wakers[index] = None;
+            // The other stream may have been waiting for space in this buffer.
+            shared.wake(1 - index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::stream::{self, StreamExt};
+
+    #[tokio::test]
+    async fn test_partition() {
+        let (even, odd) = PartitionBuffered::new(stream::iter(0..10), |i| i % 2 == 0, 2);
+        let (even, odd) =
+            futures::future::join(even.collect::<Vec<_>>(), odd.collect::<Vec<_>>()).await;
+        assert_eq!(even, vec![0, 2, 4, 6, 8]);
+        assert_eq!(odd, vec![1, 3, 5, 7, 9]);
+    }
+
+    #[tokio::test]
+    async fn test_backpressure() {
+        let polled = Arc::new(Mutex::new(0));
+        let source = stream::iter(0..10).inspect({
+            let polled = polled.clone();
+            move |_| *polled.lock().unwrap() += 1
+        });
+        let (mut small, mut large) = PartitionBuffered::new(source, |i| *i < 3, 2);
+
+        // Only two items for the other stream can be buffered.
+        for i in 0..3 {
+            assert_eq!(small.next().await, Some(i));
+        }
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert!(Pin::new(&mut small).poll_next(&mut cx).is_pending());
+        assert_eq!(*polled.lock().unwrap(), 5);
+
+        assert_eq!(large.next().await, Some(3));
+        assert!(Pin::new(&mut small).poll_next(&mut cx).is_pending());
+        assert_eq!(*polled.lock().unwrap(), 6);
+
+        assert_eq!(large.collect::<Vec<_>>().await, (4..10).collect::<Vec<_>>());
+        assert_eq!(small.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_output_discards_items() {
+        let (even, odd) = PartitionBuffered::new(stream::iter(0..10), |i| i % 2 == 0, 1);
+        drop(even);
+        assert_eq!(odd.collect::<Vec<_>>().await, vec![1, 3, 5, 7, 9]);
+    }
+}
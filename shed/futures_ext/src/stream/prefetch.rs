@@ -0,0 +1,264 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use futures::{
+    future::{AbortHandle, Abortable, Aborted},
+    ready,
+    stream::Stream,
+    task::{Context, Poll, Waker},
+    Future,
+};
+use std::collections::VecDeque;
+use std::panic;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio_shim::task::JoinHandle;
+
+/// A snapshot of the state of the buffer of a [Prefetch] stream, as returned by
+/// [Prefetch::stats].
+///
+/// A buffer that is usually full means that the consumer is the bottleneck; one that is usually
+/// empty means that the producer is.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PrefetchStats {
+    /// The maximum number of items that are buffered.
+    pub capacity: usize,
+    /// The number of items that are currently buffered.
+    pub buffered: usize,
+    /// The largest number of items that have been buffered at once.
+    pub max_buffered: usize,
+    /// The number of items that have been produced by the underlying stream.
+    pub produced: u64,
+    /// The number of times the producer waited because the buffer was full.
+    pub producer_waits: u64,
+    /// The number of times the consumer waited because the buffer was empty.
+    pub consumer_waits: u64,
+}
+
+/// A stream that eagerly drives another stream on a spawned task, buffering up to a limit of
+/// its items until they are consumed, as returned by [crate::FbStreamExt::prefetch].
+///
+/// Dropping the stream aborts the task, dropping the underlying stream. If the underlying
+/// stream panics, the panic is resumed when this stream reaches the point where it happened.
+pub struct Prefetch<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    task: Option<JoinHandle<Result<(), Aborted>>>,
+    abort_handle: AbortHandle,
+}
+
+struct Shared<T> {
+    buffer: VecDeque<T>,
+    done: bool,
+    stats: PrefetchStats,
+    /// The producer, if it is waiting for room in the buffer.
+    producer: Option<Waker>,
+    /// The consumer, if it is waiting for an item to be buffered.
+    consumer: Option<Waker>,
+}
+
+impl<T: Send + 'static> Prefetch<T> {
+    /// Spawn a task that drives `stream`, buffering up to `capacity` of its items.
+    ///
+    /// Panics if `capacity` is zero, or if called outside of a Tokio runtime.
+    pub fn new<S>(stream: S, capacity: usize) -> Self
+    where
+        S: Stream<Item = T> + Send + 'static,
+    {
+        assert!(capacity > 0, "capacity must be greater than zero");
+        let shared = Arc::new(Mutex::new(Shared {
+            buffer: VecDeque::with_capacity(capacity),
+            done: false,
+            stats: PrefetchStats {
+                capacity,
+                ..Default::default()
+            },
+            producer: None,
+            consumer: None,
+        }));
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let producer = Producer {
+            stream: Box::pin(stream),
+            shared: shared.clone(),
+        };
+        let task = tokio_shim::task::spawn(Abortable::new(producer, abort_registration));
+        Self {
+            shared,
+            task: Some(task),
+            abort_handle,
+        }
+    }
+}
+
+impl<T> Prefetch<T> {
+    /// Returns a snapshot of the state of the buffer.
+    pub fn stats(&self) -> PrefetchStats {
+        self.shared.lock().expect("lock poisoned").stats
+    }
+}
+
+impl<T> Drop for Prefetch<T> {
+    fn drop(&mut self) {
+        self.abort_handle.abort();
+    }
+}
+
+impl<T: Send + 'static> Stream for Prefetch<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        {
+            let mut shared = this.shared.lock().expect("lock poisoned");
+            if let Some(item) = shared.buffer.pop_front() {
+                shared.stats.buffered = shared.buffer.len();
+                if let Some(waker) = shared.producer.take() {
+                    waker.wake();
+                }
+                return Poll::Ready(Some(item));
+            }
+            if shared.done {
+                return Poll::Ready(None);
+            }
+        }
+
+        // The buffer is empty, and the producer hasn't finished, so wait for it, checking
+        // whether it has stopped by panicking.
+        if let Some(task) = this.task.as_mut() {
+            let mut shared = this.shared.lock().expect("lock poisoned");
+            shared.consumer = Some(cx.waker().clone());
+            shared.stats.consumer_waits += 1;
+            drop(shared);
+
+            let result = ready!(Pin::new(task).poll(cx));
+            this.task = None;
+            if let Err(e) = result {
+                if e.is_panic() {
+                    panic::resume_unwind(e.into_panic());
+                }
+            }
+            let mut shared = this.shared.lock().expect("lock poisoned");
+            shared.consumer = None;
+            if let Some(item) = shared.buffer.pop_front() {
+                shared.stats.buffered = shared.buffer.len();
+                return Poll::Ready(Some(item));
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+struct Producer<S: Stream> {
+    stream: Pin<Box<S>>,
+    shared: Arc<Mutex<Shared<S::Item>>>,
+}
+
+impl<S: Stream> Future for Producer<S> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            {
+                let mut shared = self.shared.lock().expect("lock poisoned");
+                if shared.buffer.len() >= shared.stats.capacity {
+                    shared.producer = Some(cx.waker().clone());
+                    shared.stats.producer_waits += 1;
+                    return Poll::Pending;
+                }
+            }
+
+            let item = ready!(self.stream.as_mut().poll_next(cx));
+            let mut shared = self.shared.lock().expect("lock poisoned");
+            match item {
+                Some(item) => {
+                    shared.buffer.push_back(item);
+                    let buffered = shared.buffer.len();
+                    shared.stats.buffered = buffered;
+                    shared.stats.max_buffered = shared.stats.max_buffered.max(buffered);
+                    shared.stats.produced += 1;
+                }
+                None => shared.done = true,
+            }
+            if let Some(waker) = shared.consumer.take() {
+                waker.wake();
+            }
+            if shared.done {
+                return Poll::Ready(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::stream::{self, StreamExt};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_prefetch_fills_buffer() {
+        let mut prefetch = Prefetch::new(stream::iter(0..10), 4);
+
+        // Give the producer task time to fill the buffer.
+        tokio::time::delay_for(Duration::from_millis(50)).await;
+        let stats = prefetch.stats();
+        assert_eq!(stats.capacity, 4);
+        assert_eq!(stats.buffered, 4);
+        assert_eq!(stats.max_buffered, 4);
+        assert_eq!(stats.produced, 4);
+        assert!(stats.producer_waits > 0);
+
+        assert_eq!(prefetch.next().await, Some(0));
+        let rest: Vec<_> = prefetch.collect().await;
+        assert_eq!(rest, (1..10).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_slow_producer() {
+        let slow = stream::iter(0..3).then(|i| async move {
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+            i
+        });
+        let mut prefetch = Prefetch::new(slow, 4);
+        assert_eq!((&mut prefetch).collect::<Vec<_>>().await, vec![0, 1, 2]);
+
+        let stats = prefetch.stats();
+        assert_eq!(stats.produced, 3);
+        assert_eq!(stats.buffered, 0);
+        assert!(stats.consumer_waits > 0);
+        assert_eq!(prefetch.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_propagates_panic() {
+        let panicking = stream::iter(0..3).map(|i| {
+            if i == 2 {
+                panic!("boom");
+            }
+            i
+        });
+        let prefetch = Prefetch::new(panicking, 4);
+        let result = tokio::spawn(prefetch.collect::<Vec<_>>()).await;
+        assert!(result.unwrap_err().is_panic());
+    }
+
+    #[tokio::test]
+    async fn test_drop_aborts_producer() {
+        let (tx, rx) = futures::channel::oneshot::channel::<()>();
+        let pending = stream::once(async move {
+            let _tx = tx;
+            futures::future::pending::<()>().await
+        });
+        let prefetch = Prefetch::new(pending, 1);
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+        drop(prefetch);
+        // The sender is dropped along with the underlying stream when the task is aborted.
+        assert!(rx.await.is_err());
+    }
+}
@@ -0,0 +1,97 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use futures::{
+    future::Future,
+    ready,
+    stream::Stream,
+    task::{Context, Poll},
+};
+use pin_project::pin_project;
+use std::pin::Pin;
+use tokio_shim::time::Sleep;
+
+use crate::future::RateLimiter;
+
+/// A stream that yields the items of the inner stream no faster than a [RateLimiter] allows,
+/// taking a permit for each item before yielding it.
+#[pin_project]
+pub struct RateLimited<S: Stream> {
+    #[pin]
+    inner: S,
+    limiter: RateLimiter,
+    /// The next item, waiting for a permit.
+    pending: Option<S::Item>,
+    #[pin]
+    sleep: Option<Sleep>,
+}
+
+impl<S: Stream> RateLimited<S> {
+    /// Create a new [RateLimited] stream, limited by `limiter`.
+    pub fn new(inner: S, limiter: RateLimiter) -> Self {
+        Self {
+            inner,
+            limiter,
+            pending: None,
+            sleep: None,
+        }
+    }
+}
+
+impl<S: Stream> Stream for RateLimited<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        if this.pending.is_none() {
+            match ready!(this.inner.poll_next(cx)) {
+                Some(item) => *this.pending = Some(item),
+                None => return Poll::Ready(None),
+            }
+        }
+
+        loop {
+            if let Some(sleep) = this.sleep.as_mut().as_pin_mut() {
+                ready!(sleep.poll(cx));
+                this.sleep.set(None);
+            }
+            match this.limiter.try_acquire_or_wait() {
+                Ok(()) => return Poll::Ready(this.pending.take()),
+                Err(wait) => this.sleep.set(Some(tokio_shim::time::sleep(wait))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::stream::{self, StreamExt};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_rate_limited() {
+        let start = Instant::now();
+        let s = RateLimited::new(stream::iter(0..5), RateLimiter::new(100.0, 2));
+        assert_eq!(s.collect::<Vec<_>>().await, vec![0, 1, 2, 3, 4]);
+        // Two items are in the burst, and each of the others takes 10ms.
+        assert!(start.elapsed() >= Duration::from_millis(25));
+    }
+
+    #[tokio::test]
+    async fn test_shared_limiter() {
+        let limiter = RateLimiter::new(1.0, 3);
+        let a = RateLimited::new(stream::iter(0..2), limiter.clone());
+        assert_eq!(a.collect::<Vec<_>>().await, vec![0, 1]);
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+    }
+}
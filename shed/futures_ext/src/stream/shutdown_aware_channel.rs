@@ -0,0 +1,225 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use futures::{
+    channel::mpsc::{self, Receiver, SendError, Sender, TrySendError},
+    ready,
+    sink::Sink,
+    stream::Stream,
+    task::{Context, Poll},
+};
+use thiserror::Error;
+
+/// Create a bounded [mpsc] channel whose receiver tells apart senders that shut down cleanly
+/// from senders that vanished unexpectedly.
+///
+/// A sender shuts down cleanly by calling [ShutdownAwareSender::finish]. If any sender is dropped
+/// without doing so, for example because the task that owned it panicked or was cancelled, the
+/// receiver yields [ShutdownAwareReceiverError::SenderVanished] after the last item, instead of
+/// just ending. As with [mpsc::channel], the capacity of the channel is `buffer` plus the number
+/// of senders.
+pub fn shutdown_aware_channel<T>(
+    buffer: usize,
+) -> (ShutdownAwareSender<T>, ShutdownAwareReceiver<T>) {
+    let (sender, receiver) = mpsc::channel(buffer);
+    let vanished = Arc::new(AtomicUsize::new(0));
+    (
+        ShutdownAwareSender {
+            inner: sender,
+            vanished: vanished.clone(),
+            finished: false,
+        },
+        ShutdownAwareReceiver {
+            inner: receiver,
+            vanished,
+            done: false,
+        },
+    )
+}
+
+/// The sending half of a [shutdown_aware_channel]. It must be shut down with
+/// [ShutdownAwareSender::finish] once the sending side has completed its part of the protocol,
+/// otherwise dropping it is reported to the receiver as an error.
+#[derive(Debug)]
+pub struct ShutdownAwareSender<T> {
+    inner: Sender<T>,
+    vanished: Arc<AtomicUsize>,
+    finished: bool,
+}
+
+impl<T> ShutdownAwareSender<T> {
+    /// Shut this sender down cleanly. Once all senders are gone, the receiver ends without an
+    /// error if they all finished.
+    pub fn finish(mut self) {
+        self.finished = true;
+    }
+
+    /// Attempt to send a message on this sender, returning it if the channel is full or the
+    /// receiver has been dropped. See [Sender::try_send].
+    pub fn try_send(&mut self, msg: T) -> Result<(), TrySendError<T>> {
+        self.inner.try_send(msg)
+    }
+
+    /// Returns whether the receiver has been dropped.
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+impl<T> Clone for ShutdownAwareSender<T> {
+    /// The new sender has to be finished separately.
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            vanished: self.vanished.clone(),
+            finished: false,
+        }
+    }
+}
+
+impl<T> Drop for ShutdownAwareSender<T> {
+    fn drop(&mut self) {
+        if !self.finished {
+            // This happens before the inner sender is dropped, so the receiver sees the
+            // increment by the time it sees the channel end.
+            self.vanished.fetch_add(1, Ordering::Release);
+        }
+    }
+}
+
+impl<T> Sink<T> for ShutdownAwareSender<T> {
+    type Error = SendError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, msg: T) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().inner).start_send(msg)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_close(cx)
+    }
+}
+
+/// The receiving half of a [shutdown_aware_channel]. It yields the items sent on the channel,
+/// and once all senders are gone, an error if any of them was dropped without being finished.
+#[derive(Debug)]
+pub struct ShutdownAwareReceiver<T> {
+    inner: Receiver<T>,
+    vanished: Arc<AtomicUsize>,
+    done: bool,
+}
+
+impl<T> ShutdownAwareReceiver<T> {
+    /// The number of senders that have been dropped so far without being finished.
+    pub fn vanished_senders(&self) -> usize {
+        self.vanished.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Stream for ShutdownAwareReceiver<T> {
+    type Item = Result<T, ShutdownAwareReceiverError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        match ready!(Pin::new(&mut this.inner).poll_next(cx)) {
+            Some(item) => Poll::Ready(Some(Ok(item))),
+            None => {
+                this.done = true;
+                match this.vanished.load(Ordering::Acquire) {
+                    0 => Poll::Ready(None),
+                    count => Poll::Ready(Some(Err(ShutdownAwareReceiverError::SenderVanished {
+                        count,
+                    }))),
+                }
+            }
+        }
+    }
+}
+
+/// Error that can be returned by [ShutdownAwareReceiver]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownAwareReceiverError {
+    /// Some senders were dropped without calling [ShutdownAwareSender::finish], which means that
+    /// the sending side went away without completing the protocol
+    #[error("{count} sender(s) of the channel dropped without finishing")]
+    SenderVanished {
+        /// The number of senders dropped without finishing
+        count: usize,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::{SinkExt, StreamExt};
+
+    #[tokio::test]
+    async fn finished_senders() {
+        let (mut sender, receiver) = shutdown_aware_channel(10);
+        let mut cloned = sender.clone();
+        sender.send(1).await.unwrap();
+        cloned.try_send(2).unwrap();
+        sender.finish();
+        cloned.finish();
+
+        let items: Vec<_> = receiver.collect().await;
+        assert_eq!(items, vec![Ok(1), Ok(2)]);
+    }
+
+    #[tokio::test]
+    async fn vanished_sender() {
+        let (mut sender, mut receiver) = shutdown_aware_channel(10);
+        let cloned = sender.clone();
+        sender.send(1).await.unwrap();
+        sender.finish();
+        drop(cloned);
+
+        assert_eq!(receiver.vanished_senders(), 1);
+        assert_eq!(receiver.next().await, Some(Ok(1)));
+        assert_eq!(
+            receiver.next().await,
+            Some(Err(ShutdownAwareReceiverError::SenderVanished { count: 1 }))
+        );
+        assert_eq!(receiver.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn sender_dropped_by_cancelled_task() {
+        let (sender, receiver) = shutdown_aware_channel::<u32>(1);
+        let (task, abort_handle) = futures::future::abortable(async move {
+            futures::future::pending::<()>().await;
+            sender.finish();
+        });
+        let handle = tokio::spawn(task);
+        abort_handle.abort();
+        assert!(handle.await.unwrap().is_err());
+
+        let items: Vec<_> = receiver.collect().await;
+        assert_eq!(
+            items,
+            vec![Err(ShutdownAwareReceiverError::SenderVanished { count: 1 })]
+        );
+    }
+}
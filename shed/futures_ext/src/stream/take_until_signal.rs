@@ -0,0 +1,109 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use futures::{
+    future::{Fuse, Future, FutureExt},
+    stream::Stream,
+    task::{Context, Poll},
+};
+use pin_project::pin_project;
+use std::pin::Pin;
+
+/// A stream that ends when a signal future resolves, such as a shutdown signal.
+///
+/// Once the signal has resolved, the inner stream is polled one last time, so that an item it
+/// has already produced is yielded rather than lost, and then the stream ends without dropping
+/// the inner stream.
+#[pin_project]
+pub struct TakeUntilSignal<S, F: Future> {
+    #[pin]
+    inner: S,
+    #[pin]
+    signal: Fuse<F>,
+    done: bool,
+}
+
+impl<S, F: Future> TakeUntilSignal<S, F> {
+    /// Create a new [TakeUntilSignal] that ends when `signal` resolves.
+    pub fn new(inner: S, signal: F) -> Self {
+        Self {
+            inner,
+            signal: signal.fuse(),
+            done: false,
+        }
+    }
+
+    /// Consume this stream, returning the inner stream so that its remaining items can be
+    /// drained.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Stream, F: Future> Stream for TakeUntilSignal<S, F> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        if this.signal.poll(cx).is_pending() {
+            return this.inner.poll_next(cx);
+        }
+
+        // The signal has resolved: yield an item that is already available, but don't wait for
+        // one.
+        *this.done = true;
+        match this.inner.poll_next(cx) {
+            Poll::Ready(Some(item)) => Poll::Ready(Some(item)),
+            Poll::Ready(None) | Poll::Pending => Poll::Ready(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::channel::oneshot;
+    use futures::future;
+    use futures::stream::{self, StreamExt};
+
+    #[tokio::test]
+    async fn test_ends_on_signal() {
+        let (tx, rx) = oneshot::channel::<()>();
+        let mut s = TakeUntilSignal::new(stream::iter(0..).boxed(), rx);
+        assert_eq!(s.next().await, Some(0));
+        assert_eq!(s.next().await, Some(1));
+        tx.send(()).unwrap();
+        // The item that was already produced is flushed.
+        assert_eq!(s.next().await, Some(2));
+        assert_eq!(s.next().await, None);
+        assert_eq!(s.next().await, None);
+        assert_eq!(s.into_inner().next().await, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_ends_on_signal_while_pending() {
+        let (tx, rx) = oneshot::channel::<()>();
+        let mut s = TakeUntilSignal::new(stream::pending::<()>(), rx);
+        let next = s.next();
+        tx.send(()).unwrap();
+        assert_eq!(next.await, None);
+    }
+
+    #[tokio::test]
+    async fn test_inner_ends_first() {
+        let s = TakeUntilSignal::new(stream::iter(0..3), future::pending::<()>());
+        assert_eq!(s.collect::<Vec<_>>().await, vec![0, 1, 2]);
+    }
+}
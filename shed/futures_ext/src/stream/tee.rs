@@ -0,0 +1,211 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use futures::{
+    stream::Stream,
+    task::{Context, Poll, Waker},
+};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// One of the output streams of [crate::FbStreamExt::tee], which yields a clone of every item
+/// of the source stream.
+///
+/// The items are buffered until every output stream has yielded them, up to a limit, after which
+/// the source stream isn't polled until the slowest of the output streams catches up. Dropping an
+/// output stream releases the items buffered for it.
+pub struct Tee<S: Stream> {
+    index: usize,
+    shared: Arc<Mutex<Shared<S>>>,
+}
+
+struct Shared<S: Stream> {
+    source: Pin<Box<S>>,
+    done: bool,
+    /// Items that have not yet been yielded by every output stream.
+    buffer: VecDeque<S::Item>,
+    buffer_size: usize,
+    /// The sequence number of the first item in the buffer.
+    base: u64,
+    /// The sequence number of the next item of each output stream, or None if it was dropped.
+    positions: Vec<Option<u64>>,
+    /// Output streams waiting for an item to be added to or removed from the buffer.
+    wakers: Vec<Option<Waker>>,
+}
+
+impl<S: Stream> Shared<S> {
+    fn wake_all(&mut self) {
+        for waker in self.wakers.iter_mut().filter_map(Option::take) {
+            waker.wake();
+        }
+    }
+
+    /// Remove the items that every output stream has yielded.
+    fn trim(&mut self) {
+        // If every output stream was dropped, no items are needed.
+        let needed_from = self
+            .positions
+            .iter()
+            .flatten()
+            .min()
+            .copied()
+            .unwrap_or(u64::MAX);
+        let mut trimmed = false;
+        while !self.buffer.is_empty() && needed_from > self.base {
+            self.buffer.pop_front();
+            self.base += 1;
+            trimmed = true;
+        }
+        if trimmed {
+            self.wake_all();
+        }
+    }
+}
+
+impl<S: Stream> Tee<S> {
+    /// Split `source` into `n` output streams, buffering up to `buffer_size` items for the
+    /// slower streams.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer_size` is zero.
+    pub fn new(source: S, n: usize, buffer_size: usize) -> Vec<Self> {
+        assert!(buffer_size > 0, "buffer size must be greater than zero");
+        let shared = Arc::new(Mutex::new(Shared {
+            source: Box::pin(source),
+            done: false,
+            buffer: VecDeque::with_capacity(buffer_size),
+            buffer_size,
+            base: 0,
+            positions: vec![Some(0); n],
+            wakers: vec![None; n],
+        }));
+        (0..n)
+            .map(|index| Tee {
+                index,
+                shared: shared.clone(),
+            })
+            .collect()
+    }
+}
+
+impl<S> Stream for Tee<S>
+where
+    S: Stream,
+    S::Item: Clone,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.shared.lock().expect("lock poisoned");
+        let shared = &mut *shared;
+        let position = shared.positions[self.index].expect("output stream was dropped");
+        let offset = (position - shared.base) as usize;
+
+        if offset == shared.buffer.len() {
+            // This stream has yielded all the buffered items, so it needs a new item from the
+            // source, unless the buffer is full because other streams are lagging.
+            if shared.done {
+                return Poll::Ready(None);
+            }
+            if shared.buffer.len() >= shared.buffer_size {
+                shared.wakers[self.index] = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+            match shared.source.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    shared.buffer.push_back(item);
+                    shared.wake_all();
+                }
+                Poll::Ready(None) => {
+                    shared.done = true;
+                    shared.wake_all();
+                    return Poll::Ready(None);
+                }
+                Poll::Pending => {
+                    shared.wakers[self.index] = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        let item = shared.buffer[offset].clone();
+        shared.positions[self.index] = Some(position + 1);
+        shared.trim();
+        Poll::Ready(Some(item))
+    }
+}
+
+impl<S: Stream> Drop for Tee<S> {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.positions[self.index] = None;
+            shared.wakers[self.index] = None;
+            shared.trim();
+            // The source may have been waiting to wake this stream.
+            shared.wake_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::stream::{self, StreamExt};
+
+    #[tokio::test]
+    async fn test_all_outputs_get_all_items() {
+        let outputs = Tee::new(stream::iter(0..10), 3, 4);
+        let results =
+            futures::future::join_all(outputs.into_iter().map(|output| output.collect::<Vec<_>>()))
+                .await;
+        for result in results {
+            assert_eq!(result, (0..10).collect::<Vec<_>>());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backpressure() {
+        let polled = Arc::new(Mutex::new(0));
+        let source = stream::iter(0..10).inspect({
+            let polled = polled.clone();
+            move |_| *polled.lock().unwrap() += 1
+        });
+        let mut outputs = Tee::new(source, 2, 3);
+        let mut slow = outputs.pop().unwrap();
+        let mut fast = outputs.pop().unwrap();
+
+        // The fast stream can only get ahead of the slow one by the buffer size.
+        for i in 0..3 {
+            assert_eq!(fast.next().await, Some(i));
+        }
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert!(Pin::new(&mut fast).poll_next(&mut cx).is_pending());
+        assert_eq!(*polled.lock().unwrap(), 3);
+
+        assert_eq!(slow.next().await, Some(0));
+        assert_eq!(fast.next().await, Some(3));
+        assert_eq!(*polled.lock().unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_output_releases_items() {
+        let mut outputs = Tee::new(stream::iter(0..10), 2, 1);
+        let mut kept = outputs.pop().unwrap();
+        drop(outputs);
+        assert_eq!(
+            (&mut kept).collect::<Vec<_>>().await,
+            (0..10).collect::<Vec<_>>()
+        );
+        assert_eq!(kept.next().await, None);
+    }
+}
@@ -0,0 +1,187 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use futures::{
+    future::{self, Either, IntoFuture, Ready, TryFuture, TryFutureExt},
+    ready,
+    stream::{Buffered, IntoStream, Map, Stream, StreamExt, TryStream, TryStreamExt},
+    task::{Context, Poll},
+};
+use pin_project::pin_project;
+use std::pin::Pin;
+
+type ItemFuture<S> = Either<
+    IntoFuture<<S as TryStream>::Ok>,
+    Ready<Result<<<S as TryStream>::Ok as TryFuture>::Ok, <S as TryStream>::Error>>,
+>;
+
+type ToFuture<S> = fn(Result<<S as TryStream>::Ok, <S as TryStream>::Error>) -> ItemFuture<S>;
+
+/// Like [futures::stream::TryBuffered], but instead of ending at the first error, it yields
+/// every error and keeps driving the remaining futures, as returned by
+/// [crate::FbTryStreamExt::try_buffered_keep_going].
+///
+/// As with [futures::stream::TryBuffered], the outputs are yielded in the order of the futures
+/// in the input stream. Errors from the input stream itself are yielded in the same position.
+/// A limit can be put on the number of errors with
+/// [TryBufferedKeepGoing::with_max_errors].
+#[pin_project]
+pub struct TryBufferedKeepGoing<S>
+where
+    S: TryStream,
+    S::Ok: TryFuture<Error = S::Error>,
+{
+    #[pin]
+    inner: Buffered<Map<IntoStream<S>, ToFuture<S>>>,
+    max_errors: Option<usize>,
+    errors: usize,
+    done: bool,
+}
+
+impl<S> TryBufferedKeepGoing<S>
+where
+    S: TryStream,
+    S::Ok: TryFuture<Error = S::Error>,
+{
+    /// Create a new [TryBufferedKeepGoing] that runs up to `n` futures at once.
+    pub fn new(stream: S, n: usize) -> Self {
+        fn to_future<S>(item: Result<S::Ok, S::Error>) -> ItemFuture<S>
+        where
+            S: TryStream,
+            S::Ok: TryFuture<Error = S::Error>,
+        {
+            match item {
+                Ok(fut) => Either::Left(fut.into_future()),
+                Err(e) => Either::Right(future::err(e)),
+            }
+        }
+
+        Self {
+            inner: stream
+                .into_stream()
+                .map(to_future::<S> as ToFuture<S>)
+                .buffered(n),
+            max_errors: None,
+            errors: 0,
+            done: false,
+        }
+    }
+
+    /// Tolerate at most `max_errors` errors: the stream ends straight after yielding the error
+    /// that goes over the limit, dropping the futures that are still running. With a limit of
+    /// zero, this behaves like [futures::stream::TryBuffered].
+    pub fn with_max_errors(self, max_errors: usize) -> Self {
+        Self {
+            max_errors: Some(max_errors),
+            ..self
+        }
+    }
+
+    /// The number of errors that have been yielded so far.
+    pub fn error_count(&self) -> usize {
+        self.errors
+    }
+}
+
+impl<S> Stream for TryBufferedKeepGoing<S>
+where
+    S: TryStream,
+    S::Ok: TryFuture<Error = S::Error>,
+{
+    type Item = Result<<S::Ok as TryFuture>::Ok, S::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        let item = ready!(this.inner.poll_next(cx));
+        match &item {
+            Some(Err(_)) => {
+                *this.errors += 1;
+                if let Some(max_errors) = this.max_errors {
+                    if *this.errors > *max_errors {
+                        *this.done = true;
+                    }
+                }
+            }
+            Some(Ok(_)) => {}
+            None => *this.done = true,
+        }
+        Poll::Ready(item)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::future::BoxFuture;
+    use futures::stream;
+    use futures::FutureExt;
+    use std::time::Duration;
+
+    fn delayed(
+        value: Result<u32, u32>,
+        delay: u64,
+    ) -> Result<BoxFuture<'static, Result<u32, u32>>, u32> {
+        Ok(async move {
+            tokio::time::delay_for(Duration::from_millis(delay)).await;
+            value
+        }
+        .boxed())
+    }
+
+    #[tokio::test]
+    async fn test_keeps_going_after_errors() {
+        let s = stream::iter(vec![
+            delayed(Ok(1), 30),
+            delayed(Err(2), 10),
+            Err(3),
+            delayed(Ok(4), 20),
+            delayed(Err(5), 0),
+            delayed(Ok(6), 0),
+        ]);
+        let mut keep_going = TryBufferedKeepGoing::new(s, 3);
+        let results: Vec<_> = (&mut keep_going).collect().await;
+        assert_eq!(results, vec![Ok(1), Err(2), Err(3), Ok(4), Err(5), Ok(6)]);
+        assert_eq!(keep_going.error_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_max_errors() {
+        let s = stream::iter(vec![
+            delayed(Err(1), 0),
+            delayed(Ok(2), 0),
+            delayed(Err(3), 0),
+            delayed(Ok(4), 0),
+        ]);
+        let results: Vec<_> = TryBufferedKeepGoing::new(s, 2)
+            .with_max_errors(1)
+            .collect()
+            .await;
+        assert_eq!(results, vec![Err(1), Ok(2), Err(3)]);
+    }
+
+    #[tokio::test]
+    async fn test_zero_max_errors() {
+        let s = stream::iter(vec![
+            delayed(Ok(1), 0),
+            delayed(Err(2), 0),
+            delayed(Ok(3), 0),
+        ]);
+        let results: Vec<_> = TryBufferedKeepGoing::new(s, 2)
+            .with_max_errors(0)
+            .collect()
+            .await;
+        assert_eq!(results, vec![Ok(1), Err(2)]);
+    }
+}
@@ -27,6 +27,9 @@ pub struct BufferedParams {
 }
 
 /// Like [stream::Buffered], but can also limit number of futures in a buffer by "weight".
+///
+/// As with [stream::Buffered], the outputs are yielded in the order of the futures in the
+/// input stream, regardless of the order in which they complete.
 #[pin_project]
 pub struct WeightLimitedBufferedStream<'a, S, I> {
     #[pin]
@@ -97,6 +100,9 @@ where
 
 /// Like [stream::Buffered], but is for TryStream and can also
 /// limit number of futures in a buffer by "weight"
+///
+/// The outputs are yielded in the order of the futures in the input stream,
+/// regardless of the order in which they complete.
 #[pin_project]
 pub struct WeightLimitedBufferedTryStream<'a, S, I, E> {
     #[pin]
@@ -189,6 +195,7 @@ mod test {
 
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
+    use std::time::Duration;
 
     type TestStream = BoxStream<'static, (BoxFuture<'static, ()>, u64)>;
 
@@ -267,6 +274,25 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn test_preserves_order() {
+        // Earlier futures take longer to complete, but their outputs must
+        // still come first.
+        let s = stream::iter((0..5u64).map(|i| {
+            let fut = async move {
+                tokio::time::delay_for(Duration::from_millis(10 * (5 - i))).await;
+                i
+            };
+            (fut, i + 1)
+        }));
+        let params = BufferedParams {
+            weight_limit: 6,
+            buffer_size: 3,
+        };
+        let s = WeightLimitedBufferedStream::new(params, s);
+        assert_eq!(s.collect::<Vec<_>>().await, vec![0, 1, 2, 3, 4]);
+    }
+
     type Error = String;
     type TestTryStream =
         BoxStream<'static, Result<(BoxFuture<'static, Result<(), Error>>, u64), Error>>;
@@ -379,11 +405,10 @@ mod test {
             // error, since we could not even calculate its
             // weithg and get its future
             assert!(v[0].is_err());
-            assert!(
-                v[0].clone()
-                    .unwrap_err()
-                    .contains("failed to calculate weight")
-            );
+            assert!(v[0]
+                .clone()
+                .unwrap_err()
+                .contains("failed to calculate weight"));
             // Third element of the resulting stream was
             // successfully produced
             assert_eq!(v[1], Ok(()));
@@ -427,11 +452,10 @@ mod test {
             // Second element of the resulting stream is an
             // error
             assert!(v[0].is_err());
-            assert!(
-                v[0].clone()
-                    .unwrap_err()
-                    .contains("failed to produce interesting value")
-            );
+            assert!(v[0]
+                .clone()
+                .unwrap_err()
+                .contains("failed to produce interesting value"));
             // Third element of the resulting stream was
             // successfully produced
             assert_eq!(v[1], Ok(()));
@@ -0,0 +1,239 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+use futures::{
+    ready,
+    stream::FuturesUnordered,
+    task::{Context, Poll},
+    Future, Stream, StreamExt,
+};
+use pin_project::pin_project;
+use std::collections::VecDeque;
+use std::pin::Pin;
+
+/// Like [FuturesUnordered], but each future carries a weight, and futures are only polled
+/// while the sum of the weights of the futures in flight stays within a limit.
+///
+/// Futures are started in the order they were pushed. A future that would take the weight in
+/// flight over the limit waits until enough of the futures in flight have completed, and the
+/// futures pushed after it wait behind it. A future that is heavier than the limit on its own
+/// is started once nothing else is in flight. As soon as a future completes its weight is
+/// released, and its output is yielded.
+///
+/// As with [FuturesUnordered], pushing a future doesn't poll it, so the caller must make sure
+/// that [Stream::poll_next] is called again after pushing to receive wake-up notifications.
+/// The stream yields `None` whenever it is empty, and can be reused by pushing more futures.
+pub struct WeightedFuturesUnordered<Fut> {
+    in_flight: FuturesUnordered<Weighted<Fut>>,
+    queued: VecDeque<(Fut, u64)>,
+    current_weight: u64,
+    weight_limit: u64,
+}
+
+// The queued futures are never pinned, they are only moved into `in_flight`, which pins them
+// separately.
+impl<Fut> Unpin for WeightedFuturesUnordered<Fut> {}
+
+impl<Fut: Future> WeightedFuturesUnordered<Fut> {
+    /// Create a new, empty, collection whose futures in flight can weigh at most
+    /// `weight_limit` in total.
+    pub fn new(weight_limit: u64) -> Self {
+        Self {
+            in_flight: FuturesUnordered::new(),
+            queued: VecDeque::new(),
+            current_weight: 0,
+            weight_limit,
+        }
+    }
+
+    /// Push a future with the given weight into the collection. It is started by a later
+    /// call to [Stream::poll_next] once there is room for its weight.
+    pub fn push(&mut self, future: Fut, weight: u64) {
+        self.queued.push_back((future, weight));
+    }
+
+    /// The number of futures in the collection, both in flight and waiting to be started.
+    pub fn len(&self) -> usize {
+        self.in_flight.len() + self.queued.len()
+    }
+
+    /// Returns true if the collection contains no futures.
+    pub fn is_empty(&self) -> bool {
+        self.in_flight.is_empty() && self.queued.is_empty()
+    }
+
+    /// The number of futures that are waiting for room to be started.
+    pub fn queued_len(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// The sum of the weights of the futures in flight.
+    pub fn current_weight(&self) -> u64 {
+        self.current_weight
+    }
+
+    /// The limit on the sum of the weights of the futures in flight.
+    pub fn weight_limit(&self) -> u64 {
+        self.weight_limit
+    }
+
+    fn start_queued(&mut self) {
+        while let Some((_, weight)) = self.queued.front() {
+            let fits = match self.current_weight.checked_add(*weight) {
+                Some(total) => total <= self.weight_limit,
+                None => false,
+            };
+            if !fits && !self.in_flight.is_empty() {
+                break;
+            }
+            let (future, weight) = self.queued.pop_front().expect("front was just checked");
+            self.current_weight = self.current_weight.saturating_add(weight);
+            self.in_flight.push(Weighted { future, weight });
+        }
+    }
+}
+
+impl<Fut: Future> Stream for WeightedFuturesUnordered<Fut> {
+    type Item = Fut::Output;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.start_queued();
+
+        // Starting the queued futures leaves something in flight unless the queue is empty,
+        // so `in_flight` only runs out once there is nothing left at all.
+        match ready!(this.in_flight.poll_next_unpin(cx)) {
+            Some((output, weight)) => {
+                this.current_weight -= weight;
+                Poll::Ready(Some(output))
+            }
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<Fut: Future> Extend<(Fut, u64)> for WeightedFuturesUnordered<Fut> {
+    fn extend<I: IntoIterator<Item = (Fut, u64)>>(&mut self, iter: I) {
+        self.queued.extend(iter);
+    }
+}
+
+#[pin_project]
+struct Weighted<Fut> {
+    #[pin]
+    future: Fut,
+    weight: u64,
+}
+
+impl<Fut: Future> Future for Weighted<Fut> {
+    type Output = (Fut::Output, u64);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let output = ready!(this.future.poll(cx));
+        Poll::Ready((output, *this.weight))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::future::{self, BoxFuture, FutureExt};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct Tracker {
+        current: AtomicU64,
+        max: AtomicU64,
+    }
+
+    fn tracked(
+        tracker: &Arc<Tracker>,
+        value: u32,
+        weight: u64,
+        delay: u64,
+    ) -> (BoxFuture<'static, u32>, u64) {
+        let tracker = tracker.clone();
+        let future = async move {
+            let current = tracker.current.fetch_add(weight, Ordering::SeqCst) + weight;
+            tracker.max.fetch_max(current, Ordering::SeqCst);
+            tokio::time::delay_for(Duration::from_millis(delay)).await;
+            tracker.current.fetch_sub(weight, Ordering::SeqCst);
+            value
+        };
+        (future.boxed(), weight)
+    }
+
+    #[tokio::test]
+    async fn test_weight_limit() {
+        tokio::time::pause();
+        let tracker = Arc::new(Tracker {
+            current: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+        });
+
+        let mut futures = WeightedFuturesUnordered::new(10);
+        futures.extend(vec![
+            tracked(&tracker, 1, 6, 30),
+            tracked(&tracker, 2, 4, 10),
+            tracked(&tracker, 3, 5, 10),
+            tracked(&tracker, 4, 5, 20),
+        ]);
+        assert_eq!(futures.len(), 4);
+
+        let outputs: Vec<_> = (&mut futures).collect().await;
+        assert_eq!(outputs, vec![2, 1, 3, 4]);
+        assert_eq!(tracker.max.load(Ordering::SeqCst), 10);
+        assert_eq!(futures.current_weight(), 0);
+        assert!(futures.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_overweight_future_runs_alone() {
+        tokio::time::pause();
+        let tracker = Arc::new(Tracker {
+            current: AtomicU64::new(0),
+            max: AtomicU64::new(0),
+        });
+
+        let mut futures = WeightedFuturesUnordered::new(10);
+        futures.push(tracked(&tracker, 1, 2, 10).0, 2);
+        futures.push(tracked(&tracker, 2, 20, 10).0, 20);
+        futures.push(tracked(&tracker, 3, 2, 10).0, 2);
+
+        assert_eq!(futures.next().await, Some(1));
+        assert_eq!(futures.queued_len(), 2);
+        assert_eq!(futures.current_weight(), 0);
+        assert_eq!(futures.next().await, Some(2));
+        assert_eq!(futures.next().await, Some(3));
+        assert_eq!(tracker.max.load(Ordering::SeqCst), 20);
+    }
+
+    #[tokio::test]
+    async fn test_push_after_empty() {
+        let mut futures = WeightedFuturesUnordered::new(1);
+        assert_eq!(futures.next().await, None);
+
+        futures.push(future::ready(1), 1);
+        futures.push(future::ready(2), 1);
+        assert_eq!(futures.next().await, Some(1));
+        assert_eq!(futures.next().await, Some(2));
+        assert_eq!(futures.next().await, None);
+
+        futures.push(future::ready(3), 1);
+        assert_eq!(futures.next().await, Some(3));
+    }
+}
@@ -17,7 +17,7 @@ use futures_ext::future::CancelData;
 use std::pin::Pin;
 use std::time::{Duration, Instant};
 
-use super::{FutureStats, StreamStats};
+use super::{FutureStats, PollHistogram, StreamStats};
 
 /// A Future that gathers some basic statistics for inner Future.
 /// This structure's main usage is by calling [TimedFutureExt::timed].
@@ -26,15 +26,31 @@ pub struct TimedFuture<F> {
     start: Option<Instant>,
     poll_count: u64,
     poll_time: Duration,
+    max_poll_time: Duration,
+    poll_histogram: Option<PollHistogram>,
 }
 
 impl<F> TimedFuture<F> {
-    fn new(future: F) -> Self {
+    fn new(future: F, poll_histogram: Option<PollHistogram>) -> Self {
         TimedFuture {
             inner: future,
             start: None,
             poll_count: 0,
             poll_time: Duration::from_secs(0),
+            max_poll_time: Duration::from_secs(0),
+            poll_histogram,
+        }
+    }
+
+    fn stats(&self) -> FutureStats {
+        FutureStats {
+            completion_time: self
+                .start
+                .map_or_else(|| Duration::from_secs(0), |start| start.elapsed()),
+            poll_time: self.poll_time,
+            poll_count: self.poll_count,
+            max_poll_time: self.max_poll_time,
+            poll_histogram: self.poll_histogram.clone(),
         }
     }
 }
@@ -50,20 +66,19 @@ impl<F: Future> Future for TimedFuture<F> {
         let poll_start = Instant::now();
 
         let poll = unsafe { Pin::new_unchecked(&mut this.inner).poll(cx) };
-        this.poll_time += poll_start.elapsed();
+        record_poll_time(
+            poll_start.elapsed(),
+            &mut this.poll_time,
+            &mut this.max_poll_time,
+            &mut this.poll_histogram,
+        );
 
         let out = match poll {
             Poll::Pending => return Poll::Pending,
             Poll::Ready(v) => v,
         };
 
-        let stats = FutureStats {
-            completion_time: this.start.expect("start time not set").elapsed(),
-            poll_time: this.poll_time,
-            poll_count: this.poll_count,
-        };
-
-        Poll::Ready((stats, out))
+        Poll::Ready((this.stats(), out))
     }
 }
 
@@ -71,13 +86,7 @@ impl<F> CancelData for TimedFuture<F> {
     type Data = FutureStats;
 
     fn cancel_data(&self) -> Self::Data {
-        FutureStats {
-            completion_time: self
-                .start
-                .map_or_else(|| Duration::from_secs(0), |start| start.elapsed()),
-            poll_time: self.poll_time,
-            poll_count: self.poll_count,
-        }
+        self.stats()
     }
 }
 
@@ -88,9 +97,9 @@ pub struct TimedTryFuture<F> {
 }
 
 impl<F> TimedTryFuture<F> {
-    fn new(future: F) -> Self {
+    fn new(future: F, poll_histogram: Option<PollHistogram>) -> Self {
         Self {
-            inner: TimedFuture::new(future),
+            inner: TimedFuture::new(future, poll_histogram),
         }
     }
 }
@@ -133,6 +142,8 @@ where
     count: usize,
     poll_count: u64,
     poll_time: Duration,
+    max_poll_time: Duration,
+    poll_histogram: Option<PollHistogram>,
     first_item_time: Option<Duration>,
 }
 
@@ -142,7 +153,7 @@ where
     C: FnOnce(StreamStats) -> F,
     F: Future<Output = ()>,
 {
-    fn new(stream: S, callback: C) -> Self {
+    fn new(stream: S, callback: C, poll_histogram: Option<PollHistogram>) -> Self {
         TimedStream {
             inner: stream,
             callback: Some(callback),
@@ -151,6 +162,8 @@ where
             count: 0,
             poll_count: 0,
             poll_time: Duration::from_secs(0),
+            max_poll_time: Duration::from_secs(0),
+            poll_histogram,
             first_item_time: None,
         }
     }
@@ -160,6 +173,8 @@ where
             completion_time: self.start.expect("start time not set").elapsed(),
             poll_time: self.poll_time,
             poll_count: self.poll_count,
+            max_poll_time: self.max_poll_time,
+            poll_histogram: self.poll_histogram.take(),
             count: self.count,
             first_item_time: self.first_item_time,
         };
@@ -201,7 +216,12 @@ where
 
         let poll_start = Instant::now();
         let poll = unsafe { Pin::new_unchecked(&mut this.inner).poll_next(cx) };
-        this.poll_time += poll_start.elapsed();
+        record_poll_time(
+            poll_start.elapsed(),
+            &mut this.poll_time,
+            &mut this.max_poll_time,
+            &mut this.poll_histogram,
+        );
         match poll {
             Poll::Pending => Poll::Pending,
             Poll::Ready(Some(item)) => {
@@ -219,6 +239,19 @@ where
     }
 }
 
+pub(crate) fn record_poll_time(
+    elapsed: Duration,
+    poll_time: &mut Duration,
+    max_poll_time: &mut Duration,
+    poll_histogram: &mut Option<PollHistogram>,
+) {
+    *poll_time += elapsed;
+    *max_poll_time = (*max_poll_time).max(elapsed);
+    if let Some(histogram) = poll_histogram {
+        histogram.record(elapsed);
+    }
+}
+
 /// A trait that provides the `timed` method to [futures::Future] for gathering stats
 pub trait TimedFutureExt: Future + Sized {
     /// Combinator that returns a future that will gather some statistics and
@@ -236,7 +269,13 @@ pub trait TimedFutureExt: Future + Sized {
     /// # });
     /// ```
     fn timed(self) -> TimedFuture<Self> {
-        TimedFuture::new(self)
+        TimedFuture::new(self, None)
+    }
+
+    /// Like [TimedFutureExt::timed], but also records a histogram of the time spent in each
+    /// poll in [FutureStats::poll_histogram].
+    fn timed_with_poll_histogram(self) -> TimedFuture<Self> {
+        TimedFuture::new(self, Some(PollHistogram::default()))
     }
 }
 
@@ -259,7 +298,13 @@ pub trait TimedTryFutureExt: TryFuture + Sized {
     /// # });
     /// ```
     fn try_timed(self) -> TimedTryFuture<Self> {
-        TimedTryFuture::new(self)
+        TimedTryFuture::new(self, None)
+    }
+
+    /// Like [TimedTryFutureExt::try_timed], but also records a histogram of the time spent in
+    /// each poll in [FutureStats::poll_histogram].
+    fn try_timed_with_poll_histogram(self) -> TimedTryFuture<Self> {
+        TimedTryFuture::new(self, Some(PollHistogram::default()))
     }
 }
 
@@ -294,7 +339,17 @@ pub trait TimedStreamExt: Stream + Sized {
         C: FnOnce(StreamStats) -> F,
         F: Future<Output = ()>,
     {
-        TimedStream::new(self, callback)
+        TimedStream::new(self, callback, None)
+    }
+
+    /// Like [TimedStreamExt::timed], but also records a histogram of the time spent in each
+    /// poll in [StreamStats::poll_histogram].
+    fn timed_with_poll_histogram<C, F>(self, callback: C) -> TimedStream<Self, C, F>
+    where
+        C: FnOnce(StreamStats) -> F,
+        F: Future<Output = ()>,
+    {
+        TimedStream::new(self, callback, Some(PollHistogram::default()))
     }
 }
 
@@ -328,6 +383,52 @@ mod tests {
         assert_eq!(stats.as_ref().unwrap().poll_count, 0)
     }
 
+    #[tokio::test]
+    async fn test_timed_future_poll_histogram() {
+        let (stats, ()) = async {}.timed().await;
+        assert!(stats.poll_histogram.is_none());
+
+        let (stats, ()) = async {
+            tokio::task::yield_now().await;
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        .timed_with_poll_histogram()
+        .await;
+        let histogram = stats.poll_histogram.unwrap();
+        assert_eq!(histogram.count(), stats.poll_count);
+        assert_eq!(histogram.max(), stats.max_poll_time);
+        assert!(stats.max_poll_time >= Duration::from_millis(5));
+        assert!(histogram.percentile(100.0) >= Duration::from_millis(5));
+        assert_eq!(
+            histogram.buckets().map(|(_, count)| count).sum::<u64>(),
+            stats.poll_count
+        );
+    }
+
+    #[test]
+    fn test_poll_histogram_percentiles() {
+        let mut histogram = PollHistogram::default();
+        assert_eq!(histogram.p95(), Duration::from_secs(0));
+
+        for _ in 0..95 {
+            histogram.record(Duration::from_micros(3));
+        }
+        for _ in 0..5 {
+            histogram.record(Duration::from_millis(10));
+        }
+        assert_eq!(histogram.count(), 100);
+        assert_eq!(histogram.max(), Duration::from_millis(10));
+        assert_eq!(histogram.p95(), Duration::from_micros(4));
+        assert_eq!(histogram.percentile(99.0), Duration::from_millis(10));
+        assert_eq!(
+            histogram.buckets().collect::<Vec<_>>(),
+            vec![
+                (Duration::from_micros(4), 95),
+                (Duration::from_micros(16384), 5)
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_timed_try_future() {
         let (stats, result) = async { Result::<_, ()>::Ok(123u32) }
@@ -338,6 +439,20 @@ mod tests {
         assert!(stats.poll_count > 0);
     }
 
+    #[tokio::test]
+    async fn test_timed_stream_poll_histogram() {
+        let (tx, rx) = futures::channel::oneshot::channel();
+        stream::iter([1u32, 2, 3].iter())
+            .timed_with_poll_histogram(move |stats| async move {
+                let histogram = stats.poll_histogram.unwrap();
+                assert_eq!(histogram.count(), stats.poll_count);
+                tx.send(()).unwrap();
+            })
+            .collect::<Vec<&u32>>()
+            .await;
+        rx.await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_timed_stream() {
         let callback_called = Arc::new(AtomicBool::new(false));
@@ -0,0 +1,351 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Wrappers for Tokio's `AsyncRead` and `AsyncWrite` that gather [StreamStats] about the bytes
+//! transferred through them.
+
+use std::io;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use futures::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::futures03::record_poll_time;
+use super::StreamStats;
+
+/// The stats gathered by [TimedRead] and [TimedWrite].
+struct IoStats {
+    start: Option<Instant>,
+    bytes: usize,
+    poll_count: u64,
+    poll_time: Duration,
+    max_poll_time: Duration,
+    first_byte_time: Option<Duration>,
+}
+
+impl IoStats {
+    fn new() -> Self {
+        IoStats {
+            start: None,
+            bytes: 0,
+            poll_count: 0,
+            poll_time: Duration::from_secs(0),
+            max_poll_time: Duration::from_secs(0),
+            first_byte_time: None,
+        }
+    }
+
+    fn record<T>(&mut self, poll: impl FnOnce() -> T) -> T {
+        let _ = self.start.get_or_insert_with(Instant::now);
+        self.poll_count += 1;
+
+        let poll_start = Instant::now();
+        let result = poll();
+        record_poll_time(
+            poll_start.elapsed(),
+            &mut self.poll_time,
+            &mut self.max_poll_time,
+            &mut None,
+        );
+        result
+    }
+
+    fn add_bytes(&mut self, bytes: usize) {
+        if bytes > 0 && self.first_byte_time.is_none() {
+            self.first_byte_time = Some(self.start.expect("start time not set").elapsed());
+        }
+        self.bytes += bytes;
+    }
+
+    fn stats(&self) -> StreamStats {
+        StreamStats {
+            completion_time: self
+                .start
+                .map_or_else(|| Duration::from_secs(0), |start| start.elapsed()),
+            first_item_time: self.first_byte_time,
+            poll_time: self.poll_time,
+            poll_count: self.poll_count,
+            max_poll_time: self.max_poll_time,
+            poll_histogram: None,
+            count: self.bytes,
+        }
+    }
+}
+
+/// An `AsyncRead` that gathers some basic statistics for the inner reader, in the form of
+/// [StreamStats] where `count` is the number of bytes read and `first_item_time` is the time to
+/// the first byte. This structure's main usage is by calling [TimedAsyncReadExt::timed_read].
+///
+/// The callback is called when the reader reaches end of file, or when it is dropped if that
+/// happens first.
+pub struct TimedRead<R, C: FnOnce(StreamStats)> {
+    inner: R,
+    callback: Option<C>,
+    stats: IoStats,
+}
+
+impl<R, C: FnOnce(StreamStats)> TimedRead<R, C> {
+    fn new(reader: R, callback: C) -> Self {
+        TimedRead {
+            inner: reader,
+            callback: Some(callback),
+            stats: IoStats::new(),
+        }
+    }
+
+    /// The stats gathered so far.
+    pub fn stats(&self) -> StreamStats {
+        self.stats.stats()
+    }
+
+    fn run_callback(&mut self) {
+        if let Some(callback) = self.callback.take() {
+            callback(self.stats.stats());
+        }
+    }
+}
+
+impl<R: AsyncRead, C: FnOnce(StreamStats)> AsyncRead for TimedRead<R, C> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let filled = buf.filled().len();
+        let inner = &mut this.inner;
+        let poll = this
+            .stats
+            .record(|| unsafe { Pin::new_unchecked(inner).poll_read(cx, buf) });
+
+        if let Poll::Ready(Ok(())) = poll {
+            let read = buf.filled().len() - filled;
+            this.stats.add_bytes(read);
+            if read == 0 && buf.remaining() > 0 {
+                this.run_callback();
+            }
+        }
+        poll
+    }
+}
+
+impl<R, C: FnOnce(StreamStats)> Drop for TimedRead<R, C> {
+    fn drop(&mut self) {
+        self.run_callback();
+    }
+}
+
+/// An `AsyncWrite` that gathers some basic statistics for the inner writer, in the form of
+/// [StreamStats] where `count` is the number of bytes written and `first_item_time` is the time
+/// to the first byte. This structure's main usage is by calling
+/// [TimedAsyncWriteExt::timed_write].
+///
+/// The callback is called when the writer is shut down, or when it is dropped if that happens
+/// first.
+pub struct TimedWrite<W, C: FnOnce(StreamStats)> {
+    inner: W,
+    callback: Option<C>,
+    stats: IoStats,
+}
+
+impl<W, C: FnOnce(StreamStats)> TimedWrite<W, C> {
+    fn new(writer: W, callback: C) -> Self {
+        TimedWrite {
+            inner: writer,
+            callback: Some(callback),
+            stats: IoStats::new(),
+        }
+    }
+
+    /// The stats gathered so far.
+    pub fn stats(&self) -> StreamStats {
+        self.stats.stats()
+    }
+
+    fn run_callback(&mut self) {
+        if let Some(callback) = self.callback.take() {
+            callback(self.stats.stats());
+        }
+    }
+}
+
+impl<W: AsyncWrite, C: FnOnce(StreamStats)> AsyncWrite for TimedWrite<W, C> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let inner = &mut this.inner;
+        let poll = this
+            .stats
+            .record(|| unsafe { Pin::new_unchecked(inner).poll_write(cx, buf) });
+        if let Poll::Ready(Ok(written)) = poll {
+            this.stats.add_bytes(written);
+        }
+        poll
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let inner = &mut this.inner;
+        let poll = this
+            .stats
+            .record(|| unsafe { Pin::new_unchecked(inner).poll_write_vectored(cx, bufs) });
+        if let Poll::Ready(Ok(written)) = poll {
+            this.stats.add_bytes(written);
+        }
+        poll
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let inner = &mut this.inner;
+        this.stats
+            .record(|| unsafe { Pin::new_unchecked(inner).poll_flush(cx) })
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let inner = &mut this.inner;
+        let poll = this
+            .stats
+            .record(|| unsafe { Pin::new_unchecked(inner).poll_shutdown(cx) });
+        if let Poll::Ready(Ok(())) = poll {
+            this.run_callback();
+        }
+        poll
+    }
+}
+
+impl<W, C: FnOnce(StreamStats)> Drop for TimedWrite<W, C> {
+    fn drop(&mut self) {
+        self.run_callback();
+    }
+}
+
+/// A trait that provides the `timed_read` method to Tokio's `AsyncRead` for gathering stats
+pub trait TimedAsyncReadExt: AsyncRead + Sized {
+    /// Combinator that returns a reader that will gather some statistics and pass them for
+    /// inspection to the provided callback when the reader reaches end of file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures_stats::TimedAsyncReadExt;
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let mut data = Vec::new();
+    /// (&b"hello"[..])
+    ///     .timed_read(|stats| assert_eq!(stats.count, 5))
+    ///     .read_to_end(&mut data)
+    ///     .await
+    ///     .unwrap();
+    /// # });
+    /// ```
+    fn timed_read<C>(self, callback: C) -> TimedRead<Self, C>
+    where
+        C: FnOnce(StreamStats),
+    {
+        TimedRead::new(self, callback)
+    }
+}
+
+impl<T: AsyncRead> TimedAsyncReadExt for T {}
+
+/// A trait that provides the `timed_write` method to Tokio's `AsyncWrite` for gathering stats
+pub trait TimedAsyncWriteExt: AsyncWrite + Sized {
+    /// Combinator that returns a writer that will gather some statistics and pass them for
+    /// inspection to the provided callback when the writer is shut down.
+    fn timed_write<C>(self, callback: C) -> TimedWrite<Self, C>
+    where
+        C: FnOnce(StreamStats),
+    {
+        TimedWrite::new(self, callback)
+    }
+}
+
+impl<T: AsyncWrite> TimedAsyncWriteExt for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_timed_read() {
+        let reported = Arc::new(Mutex::new(None));
+        let mut reader = (&b"hello world"[..]).timed_read({
+            let reported = reported.clone();
+            move |stats| *reported.lock().unwrap() = Some(stats)
+        });
+
+        let mut buf = [0u8; 5];
+        reader.read_exact(&mut buf).await.unwrap();
+        assert_eq!(reader.stats().count, 5);
+        assert!(reader.stats().first_item_time.is_some());
+        assert!(reported.lock().unwrap().is_none());
+
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).await.unwrap();
+        let stats = reported.lock().unwrap().take().unwrap();
+        assert_eq!(stats.count, 11);
+        assert_eq!(stats.poll_count, reader.stats().poll_count);
+
+        // The callback is only called once.
+        drop(reader);
+        assert!(reported.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_timed_write() {
+        let reported = Arc::new(Mutex::new(None));
+        let mut writer = Vec::new().timed_write({
+            let reported = reported.clone();
+            move |stats| *reported.lock().unwrap() = Some(stats)
+        });
+
+        writer.write_all(b"hello").await.unwrap();
+        writer.write_all(b" world").await.unwrap();
+        assert!(reported.lock().unwrap().is_none());
+        writer.shutdown().await.unwrap();
+
+        let stats = reported.lock().unwrap().take().unwrap();
+        assert_eq!(stats.count, 11);
+        assert!(stats.first_item_time.is_some());
+        assert!(stats.poll_count >= 3);
+    }
+
+    #[tokio::test]
+    async fn test_timed_write_dropped() {
+        let reported = Arc::new(Mutex::new(None));
+        let mut writer = Vec::new().timed_write({
+            let reported = reported.clone();
+            move |stats| *reported.lock().unwrap() = Some(stats)
+        });
+        writer.write_all(b"hello").await.unwrap();
+        drop(writer);
+
+        let stats = reported.lock().unwrap().take().unwrap();
+        assert_eq!(stats.count, 5);
+    }
+}
@@ -18,9 +18,15 @@
 use std::time::Duration;
 
 pub mod futures03;
+pub mod io;
+#[cfg(feature = "scuba")]
+pub mod scuba;
 
 // Export new Futures 0.3 API, which has different names.
 pub use futures03::{TimedFutureExt, TimedStreamExt, TimedTryFutureExt};
+pub use io::{TimedAsyncReadExt, TimedAsyncWriteExt};
+#[cfg(feature = "scuba")]
+pub use scuba::{TimedAndLoggedFutureExt, TimedAndLoggedTryFutureExt};
 
 /// A structure that holds some basic statistics for Future.
 #[derive(Clone, Debug)]
@@ -35,6 +41,13 @@ pub struct FutureStats {
 
     /// Number of times that the Future was polled.
     pub poll_count: u64,
+
+    /// The longest time the wrapped Future spent in a single call to its `poll()` function.
+    pub max_poll_time: Duration,
+
+    /// The distribution of the time spent in individual calls to `poll()`, if it was requested
+    /// with [TimedFutureExt::timed_with_poll_histogram].
+    pub poll_histogram: Option<PollHistogram>,
 }
 
 /// A structure that holds some basic statistics for Stream.
@@ -54,6 +67,89 @@ pub struct StreamStats {
     /// Number of times that the Stream was polled.
     pub poll_count: u64,
 
+    /// The longest time the wrapped Stream spent in a single call to its `poll()` function.
+    pub max_poll_time: Duration,
+
+    /// The distribution of the time spent in individual calls to `poll()`, if it was requested
+    /// with [TimedStreamExt::timed_with_poll_histogram].
+    pub poll_histogram: Option<PollHistogram>,
+
     /// Number of items in the stream
     pub count: usize,
 }
+
+/// The number of buckets in a [PollHistogram]. Bucket `i` counts the polls that took less than
+/// `2^i` microseconds, and more than the bucket before it; the last bucket counts every poll
+/// longer than that.
+const POLL_HISTOGRAM_BUCKETS: usize = 32;
+
+/// A histogram of the time spent in individual calls to `poll()`, with buckets that double in
+/// size from one microsecond.
+///
+/// Aggregate poll time hides the rare polls that hog the executor; this shows how the time was
+/// spread across polls.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PollHistogram {
+    buckets: [u64; POLL_HISTOGRAM_BUCKETS],
+    count: u64,
+    max: Duration,
+}
+
+impl PollHistogram {
+    pub(crate) fn record(&mut self, duration: Duration) {
+        let micros = duration.as_micros();
+        let bucket = (u128::BITS - micros.leading_zeros()) as usize;
+        self.buckets[bucket.min(POLL_HISTOGRAM_BUCKETS - 1)] += 1;
+        self.count += 1;
+        self.max = self.max.max(duration);
+    }
+
+    /// The number of polls recorded.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The longest poll recorded.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// An upper bound on the duration of the given percentage of the polls, which is the upper
+    /// bound of the bucket containing that percentile, or the longest poll if that is shorter.
+    /// Returns zero if no polls were recorded.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        let rank = (percentile * self.count as f64 / 100.0).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (upper_bound, count) in self.buckets() {
+            seen += count;
+            if seen >= rank {
+                return upper_bound.min(self.max);
+            }
+        }
+        self.max
+    }
+
+    /// The upper bound of the time spent in 95% of the polls, see
+    /// [PollHistogram::percentile].
+    pub fn p95(&self) -> Duration {
+        self.percentile(95.0)
+    }
+
+    /// The non-empty buckets of the histogram, as the upper bound of the duration of the polls
+    /// in each bucket and the number of polls in it. The upper bound of the last bucket is
+    /// [Duration::MAX].
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(bucket, count)| {
+                let upper_bound = if bucket == POLL_HISTOGRAM_BUCKETS - 1 {
+                    Duration::MAX
+                } else {
+                    Duration::from_micros(1 << bucket)
+                };
+                (upper_bound, *count)
+            })
+    }
+}
@@ -0,0 +1,218 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Logging of `futures_stats` to Scuba, enabled by the `scuba` feature.
+
+use std::fmt::Display;
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures::future::{Future, TryFuture};
+use futures::task::{Context, Poll};
+use scuba_sample::ScubaSampleBuilder;
+
+use super::futures03::{TimedFuture, TimedFutureExt};
+use super::{FutureStats, StreamStats};
+
+fn as_micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+impl FutureStats {
+    /// Add the standard fields for these stats to a Scuba sample: `completion_time_us`,
+    /// `poll_time_us`, `max_poll_time_us` and `poll_count`.
+    pub fn add_to_scuba(&self, scuba: &mut ScubaSampleBuilder) {
+        scuba
+            .add("completion_time_us", as_micros(self.completion_time))
+            .add("poll_time_us", as_micros(self.poll_time))
+            .add("max_poll_time_us", as_micros(self.max_poll_time))
+            .add("poll_count", self.poll_count);
+    }
+}
+
+impl StreamStats {
+    /// Add the standard fields for these stats to a Scuba sample: the fields added by
+    /// [FutureStats::add_to_scuba], `first_item_time_us` and `count`.
+    pub fn add_to_scuba(&self, scuba: &mut ScubaSampleBuilder) {
+        scuba
+            .add("completion_time_us", as_micros(self.completion_time))
+            .add_opt("first_item_time_us", self.first_item_time.map(as_micros))
+            .add("poll_time_us", as_micros(self.poll_time))
+            .add("max_poll_time_us", as_micros(self.max_poll_time))
+            .add("poll_count", self.poll_count)
+            .add("count", self.count);
+    }
+}
+
+/// A Future that logs the stats of the inner Future to Scuba when it completes.
+/// This structure's main usage is by calling [TimedAndLoggedFutureExt::timed_and_logged]
+/// or [TimedAndLoggedTryFutureExt::try_timed_and_logged].
+pub struct TimedAndLoggedFuture<F: Future> {
+    inner: TimedFuture<F>,
+    scuba: ScubaSampleBuilder,
+    add_output: fn(&mut ScubaSampleBuilder, &F::Output),
+}
+
+impl<F: Future> TimedAndLoggedFuture<F> {
+    fn new(
+        future: F,
+        mut scuba: ScubaSampleBuilder,
+        name: String,
+        add_output: fn(&mut ScubaSampleBuilder, &F::Output),
+    ) -> Self {
+        scuba.add("name", name);
+        Self {
+            inner: future.timed(),
+            scuba,
+            add_output,
+        }
+    }
+}
+
+impl<F: Future> Future for TimedAndLoggedFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let poll = unsafe { Pin::new_unchecked(&mut this.inner).poll(cx) };
+
+        match poll {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready((stats, out)) => {
+                stats.add_to_scuba(&mut this.scuba);
+                (this.add_output)(&mut this.scuba, &out);
+                this.scuba.log();
+                Poll::Ready(out)
+            }
+        }
+    }
+}
+
+/// A trait that provides the `timed_and_logged` method to [futures::Future] for logging stats
+/// to Scuba.
+pub trait TimedAndLoggedFutureExt: Future + Sized {
+    /// Combinator that returns a future that, when it completes, logs a sample with `name` in
+    /// the `name` field along with the fields added by [FutureStats::add_to_scuba], on top of
+    /// the fields already in `scuba`. Nothing is logged if the future is dropped before it
+    /// completes.
+    fn timed_and_logged(
+        self,
+        scuba: ScubaSampleBuilder,
+        name: impl Into<String>,
+    ) -> TimedAndLoggedFuture<Self> {
+        TimedAndLoggedFuture::new(self, scuba, name.into(), |_, _| {})
+    }
+}
+
+impl<T: Future> TimedAndLoggedFutureExt for T {}
+
+/// A trait that provides the `try_timed_and_logged` method to [futures::TryFuture] for logging
+/// stats to Scuba.
+pub trait TimedAndLoggedTryFutureExt: TryFuture + Sized {
+    /// Like [TimedAndLoggedFutureExt::timed_and_logged], but also logs whether the future
+    /// succeeded in the `success` field, and its error in the `error` field if it failed.
+    fn try_timed_and_logged<I, E>(
+        self,
+        scuba: ScubaSampleBuilder,
+        name: impl Into<String>,
+    ) -> TimedAndLoggedFuture<Self>
+    where
+        Self: Future<Output = Result<I, E>>,
+        E: Display,
+    {
+        fn add_result<I, E: Display>(scuba: &mut ScubaSampleBuilder, result: &Result<I, E>) {
+            match result {
+                Ok(_) => scuba.add("success", true),
+                Err(e) => scuba.add("success", false).add("error", e.to_string()),
+            };
+        }
+
+        TimedAndLoggedFuture::new(self, scuba, name.into(), add_result::<I, E>)
+    }
+}
+
+impl<T: TryFuture> TimedAndLoggedTryFutureExt for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    use serde_json::Value;
+
+    fn log_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "futures_stats_scuba_{}_{}.json",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn logged(path: &PathBuf) -> Vec<Value> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_timed_and_logged() {
+        let path = log_file("future");
+        let mut scuba = ScubaSampleBuilder::with_discard()
+            .with_log_file(&path)
+            .unwrap();
+        scuba.add("service", "test");
+
+        let value = async { 123u32 }.timed_and_logged(scuba, "answer").await;
+        assert_eq!(value, 123);
+
+        let samples = logged(&path);
+        assert_eq!(samples.len(), 1);
+        let normal = &samples[0]["normal"];
+        assert_eq!(normal["name"], "answer");
+        assert_eq!(normal["service"], "test");
+        assert_eq!(samples[0]["int"]["poll_count"], 1);
+        assert!(samples[0]["int"]["completion_time_us"].is_i64());
+        assert!(samples[0]["int"]["poll_time_us"].is_i64());
+        assert!(samples[0]["int"]["max_poll_time_us"].is_i64());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_try_timed_and_logged() {
+        let path = log_file("try_future");
+        let scuba = ScubaSampleBuilder::with_discard()
+            .with_log_file(&path)
+            .unwrap();
+
+        let ok = async { Result::<_, String>::Ok(1u32) }
+            .try_timed_and_logged(scuba.clone(), "ok")
+            .await;
+        assert_eq!(ok, Ok(1));
+        let err = async { Result::<u32, _>::Err("failed".to_string()) }
+            .try_timed_and_logged(scuba, "err")
+            .await;
+        assert_eq!(err, Err("failed".to_string()));
+
+        let samples = logged(&path);
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0]["normal"]["name"], "ok");
+        assert_eq!(samples[0]["normal"]["success"], "true");
+        assert!(samples[0]["normal"].get("error").is_none());
+        assert_eq!(samples[1]["normal"]["name"], "err");
+        assert_eq!(samples[1]["normal"]["success"], "false");
+        assert_eq!(samples[1]["normal"]["error"], "failed");
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,830 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! An implementation of the stats that aggregates them in the memory of the process, so that the
+//! binary can read them back, rather than sending them to a stats service.
+//!
+//! To use it, register [InMemoryStatsFactory] with
+//! [register_stats_manager_factory](crate::register_stats_manager_factory) before any of the
+//! stats are used.
+//!
+//! Stats with the same name share their values, whichever thread they are used from, and stop
+//! being exported once they are unregistered, e.g. when the key of a dynamic stat is evicted, by
+//! all the threads that use them. The values
+//! of gauges are the values returned by their callbacks when the stats are read. Timeseries
+//! and histograms are aggregated over each of their intervals, by splitting every interval into
+//! [BUCKETS_PER_INTERVAL] buckets, as well as over the lifetime of the process. Their values are
+//! exported with the same names as in fb303: `{name}.{aggregation}` for the lifetime of the
+//! process and `{name}.{aggregation}.{seconds}` for each interval, where the aggregation is one of
+//! `sum`, `count`, `avg`, `rate`, `pct` or `p{percentile}`. The names of labeled stats are
+//! followed by the values of their labels, e.g. `{name}.{value}.{aggregation}`.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use stats_traits::{
+    stat_types::{
+        BoxCounter, BoxGauge, BoxHistogram, BoxTimeseries, Counter, Gauge, GaugeCallback,
+        Histogram, Timeseries,
+    },
+    stats_manager::{
+        labeled_name, AggregationType, BoxStatsManager, BucketConfig, StatsManager,
+        StatsManagerFactory,
+    },
+};
+
+use crate::sketch::{BucketedCounts, QuantileSketch};
+
+/// The number of buckets each interval of a timeseries or histogram is split into.
+pub const BUCKETS_PER_INTERVAL: u32 = 60;
+
+/// The intervals over which timeseries and histograms are aggregated if they don't specify any,
+/// which are the same as Folly's.
+const DEFAULT_INTERVALS: [Duration; 3] = [
+    Duration::from_secs(60),
+    Duration::from_secs(600),
+    Duration::from_secs(3600),
+];
+
+/// The names and values of the labels of a stat.
+pub(crate) type Labels = Vec<(String, String)>;
+
+lazy_static! {
+    static ref REGISTRY: Mutex<BTreeMap<(String, Labels), Registered>> =
+        Mutex::new(BTreeMap::new());
+}
+
+/// Factory of the in-memory stats, see the [module level documentation](self).
+pub struct InMemoryStatsFactory;
+
+impl StatsManagerFactory for InMemoryStatsFactory {
+    fn create(&self) -> BoxStatsManager {
+        Box::new(InMemoryStatsManager)
+    }
+
+    fn create_gauge(&self, name: &str, callback: GaugeCallback) -> BoxGauge {
+        let key = (name.to_owned(), Labels::new());
+        let mut registry = REGISTRY.lock().expect("poisoned lock");
+        match registry.get(&key).map(|registered| &registered.stat) {
+            // The name is already used by another kind of stat, so this one is not exported.
+            Some(stat) if !matches!(stat, Stat::Gauge(_)) => {}
+            // The gauge replaces any gauge that was registered with the same name.
+            _ => {
+                registry.insert(
+                    key.clone(),
+                    Registered {
+                        stat: Stat::Gauge(callback.clone()),
+                        handles: Arc::default(),
+                    },
+                );
+            }
+        }
+        Box::new(InMemoryGauge { key, callback })
+    }
+}
+
+/// The stats are shared by all threads, and aggregated as the values are added, so there is
+/// nothing to do per thread.
+struct InMemoryStatsManager;
+
+impl StatsManager for InMemoryStatsManager {
+    fn aggregate(&self) {}
+
+    fn create_counter(&self, name: &str) -> BoxCounter {
+        self.create_labeled_counter(name, &[])
+    }
+
+    fn create_timeseries(
+        &self,
+        name: &str,
+        aggregation_types: &[AggregationType],
+        intervals: &[Duration],
+    ) -> BoxTimeseries {
+        self.create_labeled_timeseries(name, &[], aggregation_types, intervals)
+    }
+
+    fn create_labeled_counter(&self, name: &str, labels: &[(&str, &str)]) -> BoxCounter {
+        let key = key(name, labels);
+        let (counter, handles) = match register(&key, || Stat::Counter(Arc::new(AtomicI64::new(0))))
+        {
+            (Stat::Counter(counter), handles) => (counter, handles),
+            // The name is already used by another kind of stat, so this one is not exported.
+            _ => (Arc::new(AtomicI64::new(0)), Arc::default()),
+        };
+        Box::new(InMemoryCounter {
+            key,
+            handles,
+            counter,
+        })
+    }
+
+    fn create_labeled_timeseries(
+        &self,
+        name: &str,
+        labels: &[(&str, &str)],
+        aggregation_types: &[AggregationType],
+        intervals: &[Duration],
+    ) -> BoxTimeseries {
+        let create = || {
+            let aggregation_types = if aggregation_types.is_empty() {
+                vec![AggregationType::Average]
+            } else {
+                aggregation_types.to_vec()
+            };
+            let distribution = aggregation_types
+                .iter()
+                .any(|t| matches!(t, AggregationType::Percentile(_)))
+                .then(|| Distribution::Sketch(QuantileSketch::default()));
+            Arc::new(WindowedStat::new(
+                aggregation_types,
+                intervals,
+                distribution,
+            ))
+        };
+        let key = key(name, labels);
+        let (stat, handles) = match register(&key, || Stat::Timeseries(create())) {
+            (Stat::Timeseries(stat), handles) => (stat, handles),
+            _ => (create(), Arc::default()),
+        };
+        Box::new(InMemoryWindowed { key, handles, stat })
+    }
+
+    fn create_histogram(
+        &self,
+        name: &str,
+        aggregation_types: &[AggregationType],
+        conf: BucketConfig,
+        percentiles: &[u8],
+    ) -> BoxHistogram {
+        self.create_histogram_with_intervals(name, aggregation_types, conf, percentiles, &[])
+    }
+
+    fn create_histogram_with_intervals(
+        &self,
+        name: &str,
+        aggregation_types: &[AggregationType],
+        conf: BucketConfig,
+        percentiles: &[u8],
+        intervals: &[Duration],
+    ) -> BoxHistogram {
+        let create = || {
+            let aggregation_types = aggregation_types
+                .iter()
+                .copied()
+                .chain(percentiles.iter().map(|p| AggregationType::Percentile(*p)))
+                .collect();
+            let distribution = Distribution::Buckets(BucketedCounts::new(&conf));
+            Arc::new(WindowedStat::new(
+                aggregation_types,
+                intervals,
+                Some(distribution),
+            ))
+        };
+        let key = key(name, &[]);
+        let (stat, handles) = match register(&key, || Stat::Histogram(create())) {
+            (Stat::Histogram(stat), handles) => (stat, handles),
+            _ => (create(), Arc::default()),
+        };
+        Box::new(InMemoryWindowed { key, handles, stat })
+    }
+}
+
+/// The current values of all the in-memory stats, by the names they are exported with.
+pub fn exported_values() -> BTreeMap<String, i64> {
+    let now = Instant::now();
+    let mut values = BTreeMap::new();
+    for (name, labels, stat) in registered() {
+        let labels: Vec<_> = labels
+            .iter()
+            .map(|(label, value)| (label.as_str(), value.as_str()))
+            .collect();
+        let name = labeled_name(&name, &labels);
+        match stat {
+            Stat::Counter(counter) => {
+                values.insert(name, counter.load(Ordering::Relaxed));
+            }
+            Stat::Gauge(callback) => {
+                values.insert(name, callback());
+            }
+            Stat::Timeseries(stat) | Stat::Histogram(stat) => {
+                values.extend(stat.values(&name, now));
+            }
+        }
+    }
+    values
+}
+
+/// The current state of an in-memory stat, for exporters that keep the kind of the stats.
+pub(crate) enum StatState {
+    Counter(i64),
+    Gauge(i64),
+    /// The lifetime sum and count of a timeseries, with the percentiles it exports over its
+    /// shortest interval.
+    Timeseries {
+        sum: i64,
+        count: u64,
+        percentiles: Vec<(u8, i64)>,
+    },
+    /// The lifetime sum and count of a histogram, with the count of each of its buckets by
+    /// their exclusive upper bound, ending with the overflow bucket that has none.
+    Histogram {
+        sum: i64,
+        count: u64,
+        buckets: Vec<(Option<i64>, u64)>,
+    },
+}
+
+/// The current state of all the in-memory stats, by name and labels, sorted by name.
+pub(crate) fn stat_states() -> Vec<(String, Labels, StatState)> {
+    let now = Instant::now();
+    registered()
+        .into_iter()
+        .map(|(name, labels, stat)| {
+            let state = match stat {
+                Stat::Counter(counter) => StatState::Counter(counter.load(Ordering::Relaxed)),
+                Stat::Gauge(callback) => StatState::Gauge(callback()),
+                Stat::Timeseries(stat) => {
+                    let data = stat.data.lock().expect("poisoned lock");
+                    let percentiles = match data.windows.first() {
+                        Some(window) => {
+                            let merged = stat.merged(window, now);
+                            stat.aggregation_types
+                                .iter()
+                                .filter_map(|aggregation_type| match aggregation_type {
+                                    AggregationType::Percentile(percentile) => merged
+                                        .value(*aggregation_type, window.interval)
+                                        .map(|value| (*percentile, value)),
+                                    _ => None,
+                                })
+                                .collect()
+                        }
+                        None => Vec::new(),
+                    };
+                    StatState::Timeseries {
+                        sum: data.all_time.sum,
+                        count: data.all_time.count,
+                        percentiles,
+                    }
+                }
+                Stat::Histogram(stat) => {
+                    let data = stat.data.lock().expect("poisoned lock");
+                    let buckets = match &data.all_time.distribution {
+                        Some(Distribution::Buckets(buckets)) => buckets.buckets(),
+                        _ => Vec::new(),
+                    };
+                    StatState::Histogram {
+                        sum: data.all_time.sum,
+                        count: data.all_time.count,
+                        buckets,
+                    }
+                }
+            };
+            (name, labels, state)
+        })
+        .collect()
+}
+
+/// All the registered stats, copied so that they can be read without holding the lock of the
+/// registry.
+fn registered() -> Vec<(String, Labels, Stat)> {
+    REGISTRY
+        .lock()
+        .expect("poisoned lock")
+        .iter()
+        .map(|((name, labels), registered)| (name.clone(), labels.clone(), registered.stat.clone()))
+        .collect()
+}
+
+/// The key of the stat registered with `name` and `labels`.
+fn key(name: &str, labels: &[(&str, &str)]) -> (String, Labels) {
+    (
+        name.to_owned(),
+        labels
+            .iter()
+            .map(|(label, value)| ((*label).to_owned(), (*value).to_owned()))
+            .collect(),
+    )
+}
+
+/// Return the stat registered with `key` and the token of its handles, registering the one
+/// returned by `create` if there isn't one.
+fn register(key: &(String, Labels), create: impl FnOnce() -> Stat) -> (Stat, Arc<()>) {
+    let mut registry = REGISTRY.lock().expect("poisoned lock");
+    let registered = registry.entry(key.clone()).or_insert_with(|| Registered {
+        stat: create(),
+        handles: Arc::default(),
+    });
+    (registered.stat.clone(), registered.handles.clone())
+}
+
+/// Unregister the stat registered with `key` if it is the one whose handles hold `handles`, and
+/// the handle unregistering it is the only one left.
+fn unregister(key: &(String, Labels), handles: &Arc<()>) {
+    let mut registry = REGISTRY.lock().expect("poisoned lock");
+    if let Some(registered) = registry.get(key) {
+        if Arc::ptr_eq(&registered.handles, handles) && Arc::strong_count(handles) == 2 {
+            registry.remove(key);
+        }
+    }
+}
+
+/// A registered stat, with a token that each of its handles holds a clone of, so that it is
+/// only unregistered once none of the other handles use it.
+struct Registered {
+    stat: Stat,
+    handles: Arc<()>,
+}
+
+#[derive(Clone)]
+enum Stat {
+    Counter(Arc<AtomicI64>),
+    Gauge(GaugeCallback),
+    Timeseries(Arc<WindowedStat>),
+    Histogram(Arc<WindowedStat>),
+}
+
+/// Unregisters its gauge when dropped, unless it has been replaced by another gauge.
+struct InMemoryGauge {
+    key: (String, Labels),
+    callback: GaugeCallback,
+}
+
+impl Gauge for InMemoryGauge {}
+
+impl Drop for InMemoryGauge {
+    fn drop(&mut self) {
+        if let Ok(mut registry) = REGISTRY.lock() {
+            if let Some(Stat::Gauge(callback)) =
+                registry.get(&self.key).map(|registered| &registered.stat)
+            {
+                if Arc::ptr_eq(callback, &self.callback) {
+                    registry.remove(&self.key);
+                }
+            }
+        }
+    }
+}
+
+struct InMemoryCounter {
+    key: (String, Labels),
+    handles: Arc<()>,
+    counter: Arc<AtomicI64>,
+}
+
+impl Counter for InMemoryCounter {
+    fn increment_value(&self, value: i64) {
+        self.counter.fetch_add(value, Ordering::Relaxed);
+    }
+
+    fn unregister(&self) {
+        unregister(&self.key, &self.handles);
+    }
+}
+
+struct InMemoryWindowed {
+    key: (String, Labels),
+    handles: Arc<()>,
+    stat: Arc<WindowedStat>,
+}
+
+impl Timeseries for InMemoryWindowed {
+    fn add_value(&self, value: i64) {
+        self.stat.add(value, 1, value, Instant::now());
+    }
+
+    fn add_value_aggregated(&self, value: i64, nsamples: u32) {
+        if nsamples > 0 {
+            // The samples are assumed to be all equal to their average.
+            let average = value / i64::from(nsamples);
+            self.stat.add(value, nsamples, average, Instant::now());
+        }
+    }
+
+    fn unregister(&self) {
+        unregister(&self.key, &self.handles);
+    }
+}
+
+impl Histogram for InMemoryWindowed {
+    fn add_value(&self, value: i64) {
+        self.stat.add(value, 1, value, Instant::now());
+    }
+
+    fn add_repeated_value(&self, value: i64, nsamples: u32) {
+        let sum = value.saturating_mul(i64::from(nsamples));
+        self.stat.add(sum, nsamples, value, Instant::now());
+    }
+
+    fn unregister(&self) {
+        unregister(&self.key, &self.handles);
+    }
+}
+
+/// The distribution of the values in a [Bucket], for computing percentiles.
+#[derive(Clone, Debug)]
+enum Distribution {
+    Sketch(QuantileSketch),
+    Buckets(BucketedCounts),
+}
+
+impl Distribution {
+    fn add(&mut self, value: i64, nsamples: u64) {
+        match self {
+            Distribution::Sketch(sketch) => sketch.add(value, nsamples),
+            Distribution::Buckets(buckets) => buckets.add(value, nsamples),
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        match (self, other) {
+            (Distribution::Sketch(sketch), Distribution::Sketch(other)) => sketch.merge(other),
+            (Distribution::Buckets(buckets), Distribution::Buckets(other)) => buckets.merge(other),
+            _ => {}
+        }
+    }
+
+    fn percentile(&self, percentile: u8) -> Option<i64> {
+        match self {
+            Distribution::Sketch(sketch) => sketch.percentile(f64::from(percentile)),
+            Distribution::Buckets(buckets) => buckets.percentile(f64::from(percentile)),
+        }
+    }
+}
+
+/// The values added to a stat during some time.
+#[derive(Clone, Debug)]
+struct Bucket {
+    /// The index of the time slot this bucket holds, since the creation of the stat.
+    slot: u64,
+    sum: i64,
+    count: u64,
+    distribution: Option<Distribution>,
+}
+
+impl Bucket {
+    fn add(&mut self, sum: i64, nsamples: u32, value: i64) {
+        self.sum = self.sum.saturating_add(sum);
+        self.count += u64::from(nsamples);
+        if let Some(distribution) = &mut self.distribution {
+            distribution.add(value, u64::from(nsamples));
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.sum = self.sum.saturating_add(other.sum);
+        self.count += other.count;
+        if let (Some(distribution), Some(other)) = (&mut self.distribution, &other.distribution) {
+            distribution.merge(other);
+        }
+    }
+
+    fn value(&self, aggregation_type: AggregationType, duration: Duration) -> Option<i64> {
+        let average = || {
+            if self.count == 0 {
+                0
+            } else {
+                self.sum / self.count as i64
+            }
+        };
+        match aggregation_type {
+            AggregationType::Sum => Some(self.sum),
+            AggregationType::Count => Some(self.count as i64),
+            AggregationType::Average => Some(average()),
+            AggregationType::Percent => Some(average().saturating_mul(100)),
+            AggregationType::Rate => {
+                let seconds = duration.as_secs_f64().max(1.0);
+                Some((self.sum as f64 / seconds).round() as i64)
+            }
+            AggregationType::Percentile(percentile) => self
+                .distribution
+                .as_ref()
+                .and_then(|distribution| distribution.percentile(percentile)),
+        }
+    }
+}
+
+/// The buckets of one of the intervals of a [WindowedStat].
+struct Window {
+    interval: Duration,
+    bucket_width: Duration,
+    buckets: Vec<Option<Bucket>>,
+}
+
+/// A timeseries or histogram, aggregated over its intervals.
+struct WindowedStat {
+    aggregation_types: Vec<AggregationType>,
+    created: Instant,
+    /// An empty bucket, with an empty distribution if the stat exports percentiles.
+    empty: Bucket,
+    data: Mutex<WindowedData>,
+}
+
+struct WindowedData {
+    all_time: Bucket,
+    windows: Vec<Window>,
+}
+
+impl WindowedStat {
+    fn new(
+        aggregation_types: Vec<AggregationType>,
+        intervals: &[Duration],
+        distribution: Option<Distribution>,
+    ) -> Self {
+        let intervals = if intervals.is_empty() {
+            &DEFAULT_INTERVALS[..]
+        } else {
+            intervals
+        };
+        let empty = Bucket {
+            slot: 0,
+            sum: 0,
+            count: 0,
+            distribution,
+        };
+        let windows = intervals
+            .iter()
+            .map(|interval| Window {
+                interval: *interval,
+                bucket_width: (*interval / BUCKETS_PER_INTERVAL).max(Duration::from_millis(1)),
+                buckets: vec![None; BUCKETS_PER_INTERVAL as usize],
+            })
+            .collect();
+        Self {
+            aggregation_types,
+            created: Instant::now(),
+            data: Mutex::new(WindowedData {
+                all_time: empty.clone(),
+                windows,
+            }),
+            empty,
+        }
+    }
+
+    fn slot(&self, window: &Window, now: Instant) -> u64 {
+        let elapsed = now.saturating_duration_since(self.created);
+        (elapsed.as_nanos() / window.bucket_width.as_nanos()) as u64
+    }
+
+    /// Add `nsamples` samples summing to `sum`, whose distribution is approximated as all being
+    /// equal to `value`.
+    fn add(&self, sum: i64, nsamples: u32, value: i64, now: Instant) {
+        let mut data = self.data.lock().expect("poisoned lock");
+        data.all_time.add(sum, nsamples, value);
+        for index in 0..data.windows.len() {
+            let slot = self.slot(&data.windows[index], now);
+            let bucket =
+                &mut data.windows[index].buckets[(slot % BUCKETS_PER_INTERVAL as u64) as usize];
+            match bucket {
+                Some(bucket) if bucket.slot == slot => bucket.add(sum, nsamples, value),
+                _ => {
+                    let mut new_bucket = Bucket {
+                        slot,
+                        ..self.empty.clone()
+                    };
+                    new_bucket.add(sum, nsamples, value);
+                    *bucket = Some(new_bucket);
+                }
+            }
+        }
+    }
+
+    /// The values added during the interval of `window` that ends at `now`.
+    fn merged(&self, window: &Window, now: Instant) -> Bucket {
+        let current = self.slot(window, now);
+        let mut merged = self.empty.clone();
+        for bucket in window.buckets.iter().flatten() {
+            if bucket.slot <= current && bucket.slot + u64::from(BUCKETS_PER_INTERVAL) > current {
+                merged.merge(bucket);
+            }
+        }
+        merged
+    }
+
+    /// The values exported by this stat under `name` at `now`.
+    fn values(&self, name: &str, now: Instant) -> Vec<(String, i64)> {
+        let data = self.data.lock().expect("poisoned lock");
+        let lifetime = now.saturating_duration_since(self.created);
+        let mut values = Vec::new();
+
+        for aggregation_type in &self.aggregation_types {
+            if let Some(value) = data.all_time.value(*aggregation_type, lifetime) {
+                values.push((format!("{}.{}", name, suffix(*aggregation_type)), value));
+            }
+        }
+
+        for window in &data.windows {
+            let merged = self.merged(window, now);
+            let duration = window.interval.min(lifetime);
+            for aggregation_type in &self.aggregation_types {
+                if let Some(value) = merged.value(*aggregation_type, duration) {
+                    values.push((
+                        format!(
+                            "{}.{}.{}",
+                            name,
+                            suffix(*aggregation_type),
+                            window.interval.as_secs()
+                        ),
+                        value,
+                    ));
+                }
+            }
+        }
+        values
+    }
+}
+
+/// The suffix of the name of the values exported for `aggregation_type`.
+fn suffix(aggregation_type: AggregationType) -> String {
+    match aggregation_type {
+        AggregationType::Sum => "sum".to_owned(),
+        AggregationType::Count => "count".to_owned(),
+        AggregationType::Average => "avg".to_owned(),
+        AggregationType::Rate => "rate".to_owned(),
+        AggregationType::Percent => "pct".to_owned(),
+        AggregationType::Percentile(percentile) => format!("p{}", percentile),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use stats_traits::stats_manager::AggregationType::*;
+    use stats_traits::stats_manager::{P50, P99};
+
+    fn values_at(stat: &WindowedStat, now: Instant) -> BTreeMap<String, i64> {
+        stat.values("stat", now).into_iter().collect()
+    }
+
+    #[test]
+    fn test_timeseries_percentiles() {
+        let manager = InMemoryStatsManager;
+        let timeseries =
+            manager.create_timeseries("in_memory.test.timeseries", &[Sum, P50, P99], &[]);
+        for value in 1..=100 {
+            timeseries.add_value(value);
+        }
+
+        let values = exported_values();
+        assert_eq!(values["in_memory.test.timeseries.sum"], 5050);
+        assert_eq!(values["in_memory.test.timeseries.sum.60"], 5050);
+        assert_eq!(values["in_memory.test.timeseries.sum.3600"], 5050);
+        assert!((49..=51).contains(&values["in_memory.test.timeseries.p50.60"]));
+        assert!((98..=100).contains(&values["in_memory.test.timeseries.p99.600"]));
+        assert!(!values.contains_key("in_memory.test.timeseries.avg.60"));
+    }
+
+    #[test]
+    fn test_windows() {
+        let stat = WindowedStat::new(
+            vec![Sum, Count, Average, Rate, P50],
+            &[Duration::from_secs(60), Duration::from_secs(600)],
+            Some(Distribution::Sketch(QuantileSketch::default())),
+        );
+        let start = stat.created;
+        stat.add(10, 1, 10, start);
+        stat.add(60, 2, 30, start + Duration::from_secs(30));
+        stat.add(1000, 1, 1000, start + Duration::from_secs(90));
+
+        let values = values_at(&stat, start + Duration::from_secs(100));
+        assert_eq!(values["stat.sum"], 1070);
+        assert_eq!(values["stat.count"], 4);
+        // Only the last value is in the last minute.
+        assert_eq!(values["stat.sum.60"], 1000);
+        assert_eq!(values["stat.count.60"], 1);
+        assert_eq!(values["stat.avg.60"], 1000);
+        assert_eq!(values["stat.rate.60"], 17);
+        assert_eq!(values["stat.sum.600"], 1070);
+        assert_eq!(values["stat.avg.600"], 267);
+        assert!((29..=31).contains(&values["stat.p50.600"]));
+        // The rate is computed over the time since the creation of the stat, until the interval
+        // is over.
+        assert_eq!(values["stat.rate.600"], 11);
+
+        // The values expire when the interval is over.
+        let values = values_at(&stat, start + Duration::from_secs(1000));
+        assert_eq!(values["stat.sum"], 1070);
+        assert_eq!(values["stat.sum.60"], 0);
+        assert_eq!(values["stat.sum.600"], 0);
+        assert!(!values.contains_key("stat.p50.600"));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let manager = InMemoryStatsManager;
+        let histogram = manager.create_histogram(
+            "in_memory.test.histogram",
+            &[Count],
+            BucketConfig {
+                width: 10,
+                min: 0,
+                max: 1000,
+            },
+            &[50, 90],
+        );
+        histogram.add_repeated_value(5, 60);
+        histogram.add_repeated_value(505, 40);
+
+        let values = exported_values();
+        assert_eq!(values["in_memory.test.histogram.count.60"], 100);
+        assert!((0..10).contains(&values["in_memory.test.histogram.p50.60"]));
+        assert!((500..510).contains(&values["in_memory.test.histogram.p90.60"]));
+    }
+
+    #[test]
+    fn test_histogram_intervals() {
+        let histogram = InMemoryStatsManager.create_histogram_with_intervals(
+            "in_memory.test.histogram_intervals",
+            &[Sum],
+            BucketConfig {
+                width: 10,
+                min: 0,
+                max: 100,
+            },
+            &[],
+            &[Duration::from_secs(5)],
+        );
+        histogram.add_value(7);
+
+        let values = exported_values();
+        assert_eq!(values["in_memory.test.histogram_intervals.sum.5"], 7);
+        assert!(!values.contains_key("in_memory.test.histogram_intervals.sum.60"));
+    }
+
+    #[test]
+    fn test_shared_counter() {
+        let counter = InMemoryStatsManager.create_counter("in_memory.test.counter");
+        let other = std::thread::spawn(|| {
+            InMemoryStatsManager
+                .create_counter("in_memory.test.counter")
+                .increment_value(2)
+        });
+        counter.increment_value(1);
+        other.join().unwrap();
+
+        assert_eq!(exported_values()["in_memory.test.counter"], 3);
+    }
+
+    #[test]
+    fn test_labeled_stats() {
+        let get = InMemoryStatsManager
+            .create_labeled_counter("in_memory.test.labeled", &[("method", "GET")]);
+        let post = InMemoryStatsManager
+            .create_labeled_counter("in_memory.test.labeled", &[("method", "POST")]);
+        get.increment_value(1);
+        post.increment_value(2);
+        InMemoryStatsManager
+            .create_labeled_timeseries(
+                "in_memory.test.labeled_t",
+                &[("method", "GET")],
+                &[Sum],
+                &[],
+            )
+            .add_value(3);
+
+        let values = exported_values();
+        assert_eq!(values["in_memory.test.labeled.GET"], 1);
+        assert_eq!(values["in_memory.test.labeled.POST"], 2);
+        assert_eq!(values["in_memory.test.labeled_t.GET.sum"], 3);
+    }
+
+    #[test]
+    fn test_unregister() {
+        let counter = InMemoryStatsManager.create_counter("in_memory.test.unregister");
+        let other = InMemoryStatsManager.create_counter("in_memory.test.unregister");
+        counter.increment_value(1);
+
+        // The stat is still used by the other handle.
+        counter.unregister();
+        drop(counter);
+        other.increment_value(1);
+        assert_eq!(exported_values()["in_memory.test.unregister"], 2);
+
+        other.unregister();
+        assert!(!exported_values().contains_key("in_memory.test.unregister"));
+    }
+
+    #[test]
+    fn test_gauge() {
+        let value = Arc::new(AtomicI64::new(1));
+        let gauge = InMemoryStatsFactory.create_gauge("in_memory.test.gauge", {
+            let value = value.clone();
+            Arc::new(move || value.load(Ordering::Relaxed))
+        });
+        assert_eq!(exported_values()["in_memory.test.gauge"], 1);
+        value.store(5, Ordering::Relaxed);
+        assert_eq!(exported_values()["in_memory.test.gauge"], 5);
+
+        drop(gauge);
+        assert!(!exported_values().contains_key("in_memory.test.gauge"));
+    }
+}
@@ -13,8 +13,13 @@
 
 #![deny(warnings, missing_docs, clippy::all, rustdoc::broken_intra_doc_links)]
 
+pub mod in_memory;
 pub mod macros;
 mod noop_stats;
+#[cfg(feature = "opentelemetry")]
+pub mod opentelemetry;
+pub mod prometheus;
+mod sketch;
 pub mod thread_local_aggregator;
 
 pub mod prelude {
@@ -39,11 +44,13 @@ pub mod prelude {
     };
 }
 
-use std::sync::RwLock;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 
 use lazy_static::lazy_static;
 use stats_traits::{
-    stat_types::BoxSingletonCounter,
+    stat_types::{BoxGauge, BoxSingletonCounter},
     stats_manager::{BoxStatsManager, StatsManagerFactory},
 };
 
@@ -54,6 +61,9 @@ lazy_static! {
         RwLock::new(None);
 }
 
+/// Whether the in-memory stats have been registered by [enable_snapshot].
+static SNAPSHOT_ENABLED: AtomicBool = AtomicBool::new(false);
+
 /// This function must be called exactly once before accessing any of the stats,
 /// otherwise it will panic.
 /// If it won't be called a default stats manager factory will be assumed that
@@ -67,24 +77,68 @@ pub fn register_stats_manager_factory(factory: impl StatsManagerFactory + Send +
     global_factory.replace(Box::new(factory));
 }
 
+/// Register the [in_memory] stats, unless a stats manager factory has already been registered or
+/// the default one has already been used, so that the values of the stats can be read with
+/// [snapshot]. This is meant for tests that check which stats the code under test updates, and
+/// can be called any number of times, e.g. at the start of each of those tests.
+///
+/// Returns whether the stats can be read with [snapshot], which is only the case if the first of
+/// the stats was used after the first call to this function.
+pub fn enable_snapshot() -> bool {
+    let mut global_factory = STATS_MANAGER_FACTORY.write().expect("poisoned lock");
+    if global_factory.is_none() {
+        global_factory.replace(Box::new(in_memory::InMemoryStatsFactory));
+        SNAPSHOT_ENABLED.store(true, Ordering::Release);
+    }
+    SNAPSHOT_ENABLED.load(Ordering::Acquire)
+}
+
+/// The current values of the stats by the names they are exported with, as described in the
+/// documentation of the [in_memory] stats. It is empty unless the stats have been enabled with
+/// [enable_snapshot] or the in-memory stats have been registered.
+///
+/// The stats are shared by all the tests of a binary, so tests that run in parallel should
+/// compare the values before and after the code they test runs, rather than check their absolute
+/// values.
+pub fn snapshot() -> BTreeMap<String, i64> {
+    in_memory::exported_values()
+}
+
 #[doc(hidden)]
 /// You probably don't have to use this function, it is made public so that it
 /// might be used by the macros in this crate. It reads the globally registered
 /// StatsManagerFactory and creates a new instance of StatsManager.
 pub fn create_stats_manager() -> BoxStatsManager {
+    with_stats_manager_factory(|factory| factory.create())
+}
+
+/// Register a gauge named `name`, whose value is returned by `callback` whenever the stats are
+/// collected, e.g. `gauge_fn("queue_len", move || queue.len() as i64)`, so that values derived
+/// from live data structures don't need to be pushed as they change. The gauge is unregistered
+/// when the returned [BoxGauge] is dropped.
+///
+/// The callback may be called from any thread, and while the stats are being collected, so it
+/// should be quick. Gauges are ignored by the stats services that don't support them.
+pub fn gauge_fn(name: &str, callback: impl Fn() -> i64 + Send + Sync + 'static) -> BoxGauge {
+    with_stats_manager_factory(|factory| factory.create_gauge(name, Arc::new(callback)))
+}
+
+/// Call `f` with the globally registered StatsManagerFactory, registering the default one if
+/// there isn't one.
+fn with_stats_manager_factory<R>(f: impl FnOnce(&dyn StatsManagerFactory) -> R) -> R {
     if let Some(factory) = STATS_MANAGER_FACTORY
         .read()
         .expect("poisoned lock")
         .as_ref()
     {
-        return factory.create();
+        return f(factory.as_ref());
     }
     // We get here only if register_stats_manager_factory was not called yet
     // but we have to keep in mind this is a race so first get hold of write
     // lock and check if the factory is still unset.
     let mut write_lock = STATS_MANAGER_FACTORY.write().expect("poisoned lock");
     let factory = write_lock.get_or_insert_with(get_default_stats_manager_factory);
-    factory.create()
+    f(factory.as_ref())
 }
 
 fn get_default_stats_manager_factory() -> Box<dyn StatsManagerFactory + Send + Sync> {
@@ -113,3 +167,143 @@ pub fn create_singleton_counter(name: String) -> BoxSingletonCounter {
         Box::new(crate::noop_stats::Noop)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use crate::prelude::*;
+
+    define_stats! {
+        prefix = "stats.snapshot.test";
+        requests: counter(),
+        latency: timeseries(Sum, Count),
+        per_client: dynamic_counter("per_client.{}", (client: u32); limits(max_keys = 2)),
+        per_repo: dynamic_timeseries("per_repo.{}", (repo: u32); Sum;
+                                     limits(ttl = Duration::from_millis(10))),
+        by_method: labeled_counter("by_method", (method: &'static str, status: u16)),
+        latency_by_method: labeled_timeseries("latency_by_method", (method: &'static str); Sum),
+        short_windows: timeseries(Sum; intervals(Duration::from_secs(10))),
+        short_windows_h: histogram(10, 0, 100, Sum; P 50; intervals(Duration::from_secs(10))),
+    }
+
+    define_stats_struct! {
+        WindowedStats("stats.snapshot.test.struct.{}", id: u32),
+        struct_t: timeseries(Sum; intervals(Duration::from_secs(20))),
+        struct_h: histogram(10, 0, 100, Count; intervals(Duration::from_secs(30))),
+    }
+
+    #[test]
+    fn test_snapshot() {
+        assert!(enable_snapshot());
+        assert!(enable_snapshot());
+
+        STATS::requests.increment_value(2);
+        STATS::latency.add_value(10);
+        std::thread::spawn(|| {
+            STATS::requests.increment_value(1);
+            STATS::latency.add_value(20);
+        })
+        .join()
+        .expect("thread panicked");
+
+        let snapshot = snapshot();
+        assert_eq!(snapshot["stats.snapshot.test.requests"], 3);
+        assert_eq!(snapshot["stats.snapshot.test.latency.sum"], 30);
+        assert_eq!(snapshot["stats.snapshot.test.latency.count"], 2);
+    }
+
+    #[test]
+    fn test_gauge_fn() {
+        assert!(enable_snapshot());
+
+        let queue = Arc::new(RwLock::new(vec![1, 2]));
+        let gauge = gauge_fn("stats.snapshot.test.queue_len", {
+            let queue = queue.clone();
+            move || queue.read().expect("poisoned lock").len() as i64
+        });
+        assert_eq!(snapshot()["stats.snapshot.test.queue_len"], 2);
+        queue.write().expect("poisoned lock").push(3);
+        assert_eq!(snapshot()["stats.snapshot.test.queue_len"], 3);
+
+        drop(gauge);
+        assert!(!snapshot().contains_key("stats.snapshot.test.queue_len"));
+    }
+
+    #[test]
+    fn test_labeled_stats() {
+        assert!(enable_snapshot());
+
+        STATS::by_method.increment_value(1, ("GET", 200));
+        STATS::by_method.increment_value(1, ("GET", 404));
+        STATS::by_method.increment_value(1, ("GET", 200));
+        STATS::latency_by_method.add_value(7, ("POST",));
+
+        let snapshot = snapshot();
+        assert_eq!(snapshot["stats.snapshot.test.by_method.GET.200"], 2);
+        assert_eq!(snapshot["stats.snapshot.test.by_method.GET.404"], 1);
+        assert_eq!(
+            snapshot["stats.snapshot.test.latency_by_method.POST.sum"],
+            7
+        );
+    }
+
+    #[test]
+    fn test_intervals() {
+        assert!(enable_snapshot());
+
+        STATS::short_windows.add_value(1);
+        STATS::short_windows_h.add_value(2);
+        let stats = WindowedStats::new(1);
+        stats.struct_t.add_value(3);
+        stats.struct_h.add_value(4);
+
+        let snapshot = snapshot();
+        assert_eq!(snapshot["stats.snapshot.test.short_windows.sum.10"], 1);
+        assert!(!snapshot.contains_key("stats.snapshot.test.short_windows.sum.60"));
+        assert_eq!(snapshot["stats.snapshot.test.short_windows_h.sum.10"], 2);
+        assert!(snapshot.contains_key("stats.snapshot.test.short_windows_h.p50.10"));
+        assert_eq!(snapshot["stats.snapshot.test.struct.1.struct_t.sum.20"], 3);
+        assert_eq!(
+            snapshot["stats.snapshot.test.struct.1.struct_h.count.30"],
+            1
+        );
+    }
+
+    #[test]
+    fn test_max_keys() {
+        assert!(enable_snapshot());
+
+        STATS::per_client.increment_value(1, (1,));
+        STATS::per_client.increment_value(1, (2,));
+        STATS::per_client.increment_value(1, (1,));
+        // The key of client 2 is the least recently used one.
+        STATS::per_client.increment_value(1, (3,));
+        STATS::per_client.increment_value(1, (1,));
+        STATS::per_client.increment_value(1, (2,));
+
+        // The key of client 3 was evicted in turn, and client 2 was counted from scratch.
+        let snapshot = snapshot();
+        assert_eq!(snapshot["stats.snapshot.test.per_client.1"], 3);
+        assert_eq!(snapshot["stats.snapshot.test.per_client.2"], 1);
+        assert!(!snapshot.contains_key("stats.snapshot.test.per_client.3"));
+        assert_eq!(snapshot["stats.snapshot.test.per_client.evicted_keys"], 2);
+    }
+
+    #[test]
+    fn test_ttl() {
+        assert!(enable_snapshot());
+
+        STATS::per_repo.add_value(1, (1,));
+        STATS::per_repo.add_value(1, (2,));
+        std::thread::sleep(Duration::from_millis(20));
+        STATS::per_repo.add_value(1, (2,));
+
+        let snapshot = snapshot();
+        assert!(!snapshot.contains_key("stats.snapshot.test.per_repo.1.sum"));
+        assert_eq!(snapshot["stats.snapshot.test.per_repo.2.sum"], 1);
+        assert_eq!(snapshot["stats.snapshot.test.per_repo.evicted_keys"], 2);
+    }
+}
@@ -16,9 +16,11 @@ pub mod common_macro_prelude {
     pub use lazy_static::lazy_static;
     pub use perthread::{PerThread, ThreadMap};
     pub use stats_traits::{
-        dynamic_stat_types::DynamicStat,
+        dynamic_stat_types::{DynamicStat, DynamicStatLimits},
         stat_types::{BoxCounter, BoxHistogram, BoxSingletonCounter, BoxTimeseries},
-        stats_manager::{AggregationType::*, BoxStatsManager, BucketConfig, StatsManager},
+        stats_manager::{
+            AggregationType::*, BoxStatsManager, BucketConfig, StatsManager, P50, P90, P95, P99,
+        },
     };
     pub use std::sync::Arc;
     pub use std::time::Duration;
@@ -32,6 +34,30 @@ pub mod common_macro_prelude {
 /// export. This is the main and recomended way to interact with statistics provided by this crate.
 /// If non empty prefix is passed then the exported counter name will be "{prefix}.{name}"
 ///
+/// Histograms, for example of latencies, are defined with the width of their buckets followed by
+/// the range of values they cover, e.g. `histogram(10, 0, 1000, ...)` for buckets of 10 from 0 to
+/// 1000, with samples outside of the range going to an underflow or overflow bucket. The bucket
+/// configuration is followed by the exported aggregations and the exported percentiles, each
+/// preceded by `P`, e.g. `histogram(10, 0, 1000, Average, Count; P 50; P 99)`.
+///
+/// The intervals over which timeseries and histograms are aggregated can be given after their
+/// export types, or percentiles for histograms, e.g.
+/// `timeseries(Sum, Average; intervals(Duration::from_secs(60), Duration::from_secs(3600)))`,
+/// instead of the defaults of the stats service.
+///
+/// The number of keys of dynamic stats can be limited, by following their definition with e.g.
+/// `limits(max_keys = 1000, ttl = Duration::from_secs(600))`: when there are `max_keys` keys
+/// already, using a new key evicts the least recently used one, and keys that haven't been used
+/// for `ttl` are evicted. The keys are held per thread, and the number of keys evicted is counted
+/// by the counter `{name}.evicted_keys`.
+///
+/// Labeled stats are defined with their key and their labels instead of a key pattern, e.g.
+/// `labeled_counter("requests", (method: &'static str, status: u16))`, and are used with the
+/// values of their labels, e.g. `STATS::requests.increment_value(1, ("GET", 200))`. The labels
+/// are exported as such by the stats services that support them, and the others export the key
+/// followed by the values of the labels, e.g. `requests.GET.200`. Labeled stats can be limited
+/// like dynamic stats.
+///
 /// Examples:
 /// ```
 /// use stats::prelude::*;
@@ -44,11 +70,21 @@ pub mod common_macro_prelude {
 ///     test_c2: counter("test_c.two"),
 ///     test_t: timeseries(Sum, Average),
 ///     test_t2: timeseries("test_t.two"; Sum, Average),
+///     test_t3: timeseries(Average, P50, P99, Percentile(75)),
 ///     test_h: histogram(1, 0, 1000, Sum; P 99; P 50),
+///     test_t4: timeseries(Sum; intervals(Duration::from_secs(10), Duration::from_secs(3600))),
+///     test_h2: histogram(1, 0, 1000, Sum; P 99; intervals(Duration::from_secs(10))),
 ///     dtest_c: dynamic_counter("test_c.{}", (job: u64)),
 ///     dtest_t: dynamic_timeseries("test_t.{}", (region: &'static str); Rate, Sum),
 ///     dtest_t2: dynamic_timeseries("test_t.two.{}.{}", (job: u64, region: &'static str); Count),
 ///     dtest_h: dynamic_histogram("test_h.{}", (region: &'static str); 1, 0, 1000, Sum; P 99),
+///     dtest_c2: dynamic_counter("test_c.client.{}", (client: String);
+///                               limits(max_keys = 1000, ttl = Duration::from_secs(600))),
+///     dtest_t3: dynamic_timeseries("test_t.client.{}", (client: String); Rate, Sum;
+///                                  limits(max_keys = 1000)),
+///     ltest_c: labeled_counter("test_c.labeled", (method: &'static str, status: u16)),
+///     ltest_t: labeled_timeseries("test_t.labeled", (method: &'static str); Sum, Average;
+///                                  Duration::from_secs(60)),
 /// }
 ///
 /// #[allow(non_snake_case)]
@@ -69,12 +105,19 @@ pub mod common_macro_prelude {
 ///     STATS::test_c2.increment_value(100);
 ///     STATS::test_t.add_value(1);
 ///     STATS::test_t2.add_value_aggregated(79, 10);  // Add 79 and note it came from 10 samples
+///     STATS::test_t3.add_value(17);
 ///     STATS::test_h.add_value(1);
 ///     STATS::test_h.add_repeated_value(1, 44);  // 44 times repeat adding 1
+///     STATS::test_t4.add_value(1);
+///     STATS::test_h2.add_value(1);
 ///     STATS::dtest_c.increment_value(7, (1000,));
 ///     STATS::dtest_t.add_value(77, ("lla",));
 ///     STATS::dtest_t2.add_value_aggregated(81, 12, (7, "lla"));
 ///     STATS::dtest_h.add_value(2, ("frc",));
+///     STATS::dtest_c2.increment_value(1, ("client".to_string(),));
+///     STATS::dtest_t3.add_value(3, ("client".to_string(),));
+///     STATS::ltest_c.increment_value(1, ("GET", 200));
+///     STATS::ltest_t.add_value(12, ("GET",));
 ///
 ///     ALT_STATS::test_t.add_value(1);
 ///     ALT_STATS::test_t2.add_value(1);
@@ -151,6 +194,17 @@ macro_rules! __define_stat {
     // STATS::name), the key (used in ODS or to query the key), the export types (SUM, RATE, etc.),
     // and the intervals (e.g. 60, 600). The key defaults to the name, and the intervals default to
     // whatever default Folly uses (which happens to be 60, 600, 3600);
+    //
+    // The intervals can also be given after the export types in `intervals(...)`, which unlike
+    // the above doesn't require the key to be given.
+    ($prefix:expr; $name:ident: timeseries($( $aggregation_type:expr ),* ;
+                                           intervals($( $interval:expr ),*))) => (
+        $crate::__define_stat!($prefix; $name: timeseries(stringify!($name); $( $aggregation_type ),* ; $( $interval ),*));
+    );
+    ($prefix:expr; $name:ident: timeseries($key:expr; $( $aggregation_type:expr ),* ;
+                                           intervals($( $interval:expr ),*))) => (
+        $crate::__define_stat!($prefix; $name: timeseries($key; $( $aggregation_type ),* ; $( $interval ),*));
+    );
     ($prefix:expr; $name:ident: timeseries($( $aggregation_type:expr ),*)) => (
         $crate::__define_stat!($prefix; $name: timeseries(stringify!($name); $( $aggregation_type ),*));
     );
@@ -169,19 +223,27 @@ macro_rules! __define_stat {
         }
     );
 
+    // There are 7 inputs we use to produce a histogram: the prefix, the name (used in
+    // STATS::name), the key (used in ODS or to query the key), the bucket configuration (the
+    // width of each bucket, and the min and max of the values that aren't put in the underflow
+    // and overflow buckets), the export types (SUM, AVG, etc.), the exported percentiles
+    // (e.g. P 50; P 99), and the optional intervals (e.g. intervals(Duration::from_secs(60))).
+    // The key defaults to the name, and the intervals to the default of the stats service.
     ($prefix:expr;
      $name:ident: histogram($bucket_width:expr,
                             $min:expr,
                             $max:expr
                             $(, $aggregation_type:expr )*
-                            $(; P $percentile:expr )*)) => (
+                            $(; P $percentile:expr )*
+                            $(; intervals($( $interval:expr ),*) )?)) => (
         $crate::__define_stat!($prefix;
                       $name: histogram(stringify!($name);
                                        $bucket_width,
                                        $min,
                                        $max
                                        $(, $aggregation_type )*
-                                       $(; P $percentile )*));
+                                       $(; P $percentile )*
+                                       $(; intervals($( $interval ),*) )?));
     );
 
     ($prefix:expr;
@@ -190,10 +252,11 @@ macro_rules! __define_stat {
                             $min:expr,
                             $max:expr
                             $(, $aggregation_type:expr )*
-                            $(; P $percentile:expr )*)) => (
+                            $(; P $percentile:expr )*
+                            $(; intervals($( $interval:expr ),*) )?)) => (
         thread_local! {
             pub static $name: BoxHistogram = TL_STATS.with(|stats| {
-                stats.create_histogram(
+                stats.create_histogram_with_intervals(
                     &$crate::__create_stat_key!($prefix, $key),
                     &[$( $aggregation_type ),*],
                     BucketConfig {
@@ -201,13 +264,26 @@ macro_rules! __define_stat {
                         min: $min,
                         max: $max,
                     },
-                    &[$( $percentile ),*])
+                    &[$( $percentile ),*],
+                    &[$($( $interval ),*)?])
             });
         }
     );
 
+    // Every dynamic stat can be followed by the limits on its keys, e.g.
+    // `limits(max_keys = 1000, ttl = Duration::from_secs(600))`, and the number of keys evicted
+    // because of them is counted by a counter named after the stat, e.g. "{name}.evicted_keys".
     ($prefix:expr;
      $name:ident: dynamic_singleton_counter($key:expr, ($( $placeholder:ident: $type:ty ),+))) => (
+        $crate::__define_stat!(
+            $prefix;
+            $name: dynamic_singleton_counter($key, ($( $placeholder: $type ),+); limits())
+        );
+    );
+
+    ($prefix:expr;
+     $name:ident: dynamic_singleton_counter($key:expr, ($( $placeholder:ident: $type:ty ),+);
+                                            limits($( $limit:ident = $value:expr ),*))) => (
         thread_local! {
             pub static $name: DynamicStat<($( $type, )+), BoxSingletonCounter> = {
                 $crate::__define_key_generator!(
@@ -218,13 +294,25 @@ macro_rules! __define_stat {
                     create_singleton_counter(key.to_string())
                 }
 
-                DynamicStat::new(__key_generator, __stat_generator)
+                $crate::__dynamic_stat_with_limits!(
+                    $prefix; $name; DynamicStat::new(__key_generator, __stat_generator);
+                    $( $limit = $value ),*
+                )
             }
         }
     );
 
     ($prefix:expr;
      $name:ident: dynamic_counter($key:expr, ($( $placeholder:ident: $type:ty ),+))) => (
+        $crate::__define_stat!(
+            $prefix;
+            $name: dynamic_counter($key, ($( $placeholder: $type ),+); limits())
+        );
+    );
+
+    ($prefix:expr;
+     $name:ident: dynamic_counter($key:expr, ($( $placeholder:ident: $type:ty ),+);
+                                  limits($( $limit:ident = $value:expr ),*))) => (
         thread_local! {
             pub static $name: DynamicStat<($( $type, )+), BoxCounter> = {
                 $crate::__define_key_generator!(
@@ -237,27 +325,36 @@ macro_rules! __define_stat {
                     })
                 }
 
-                DynamicStat::new(__key_generator, __stat_generator)
+                $crate::__dynamic_stat_with_limits!(
+                    $prefix; $name; DynamicStat::new(__key_generator, __stat_generator);
+                    $( $limit = $value ),*
+                )
             }
         }
     );
 
+    // The limits of a dynamic timeseries have to be matched before its intervals, which they
+    // would otherwise be parsed as.
     ($prefix:expr;
      $name:ident: dynamic_timeseries($key:expr, ($( $placeholder:ident: $type:ty ),+);
-                                     $( $aggregation_type:expr ),*)) => (
+                                     $( $aggregation_type:expr ),* ;
+                                     limits($( $limit:ident = $value:expr ),*))) => (
         $crate::__define_stat!(
             $prefix;
             $name: dynamic_timeseries(
                 $key,
                 ($( $placeholder: $type ),+);
                 $( $aggregation_type ),* ;
+                ;
+                limits($( $limit = $value ),*)
             )
         );
     );
 
     ($prefix:expr;
      $name:ident: dynamic_timeseries($key:expr, ($( $placeholder:ident: $type:ty ),+);
-                                     $( $aggregation_type:expr ),* ; $( $interval:expr ),*)) => (
+                                     $( $aggregation_type:expr ),* ; $( $interval:expr ),* ;
+                                     limits($( $limit:ident = $value:expr ),*))) => (
         thread_local! {
             pub static $name: DynamicStat<($( $type, )+), BoxTimeseries> = {
                 $crate::__define_key_generator!(
@@ -270,18 +367,75 @@ macro_rules! __define_stat {
                     })
                 }
 
-                DynamicStat::new(__key_generator, __stat_generator)
+                $crate::__dynamic_stat_with_limits!(
+                    $prefix; $name; DynamicStat::new(__key_generator, __stat_generator);
+                    $( $limit = $value ),*
+                )
             };
         }
     );
 
+    ($prefix:expr;
+     $name:ident: dynamic_timeseries($key:expr, ($( $placeholder:ident: $type:ty ),+);
+                                     $( $aggregation_type:expr ),*)) => (
+        $crate::__define_stat!(
+            $prefix;
+            $name: dynamic_timeseries(
+                $key,
+                ($( $placeholder: $type ),+);
+                $( $aggregation_type ),* ;
+            )
+        );
+    );
+
+    ($prefix:expr;
+     $name:ident: dynamic_timeseries($key:expr, ($( $placeholder:ident: $type:ty ),+);
+                                     $( $aggregation_type:expr ),* ; $( $interval:expr ),*)) => (
+        $crate::__define_stat!(
+            $prefix;
+            $name: dynamic_timeseries(
+                $key,
+                ($( $placeholder: $type ),+);
+                $( $aggregation_type ),* ;
+                $( $interval ),* ;
+                limits()
+            )
+        );
+    );
+
+    ($prefix:expr;
+     $name:ident: dynamic_histogram($key:expr, ($( $placeholder:ident: $type:ty ),+);
+                                    $bucket_width:expr,
+                                    $min:expr,
+                                    $max:expr
+                                    $(, $aggregation_type:expr )*
+                                    $(; P $percentile:expr )*
+                                    $(; intervals($( $interval:expr ),*) )?)) => (
+        $crate::__define_stat!(
+            $prefix;
+            $name: dynamic_histogram(
+                $key,
+                ($( $placeholder: $type ),+);
+                $bucket_width,
+                $min,
+                $max
+                $(, $aggregation_type )*
+                $(; P $percentile )*
+                $(; intervals($( $interval ),*) )?;
+                limits()
+            )
+        );
+    );
+
     ($prefix:expr;
      $name:ident: dynamic_histogram($key:expr, ($( $placeholder:ident: $type:ty ),+);
                                     $bucket_width:expr,
                                     $min:expr,
                                     $max:expr
                                     $(, $aggregation_type:expr )*
-                                    $(; P $percentile:expr )*)) => (
+                                    $(; P $percentile:expr )*
+                                    $(; intervals($( $interval:expr ),*) )?;
+                                    limits($( $limit:ident = $value:expr ),*))) => (
         thread_local! {
             pub static $name: DynamicStat<($( $type, )+), BoxHistogram> = {
                 $crate::__define_key_generator!(
@@ -290,21 +444,178 @@ macro_rules! __define_stat {
 
                 fn __stat_generator(key: &str) -> BoxHistogram {
                     TL_STATS.with(|stats| {
-                        stats.create_histogram(key,
-                                               &[$( $aggregation_type ),*],
-                                               BucketConfig {
-                                                   width: $bucket_width,
-                                                   min: $min,
-                                                   max: $max,
-                                               },
-                                               &[$( $percentile ),*])
+                        stats.create_histogram_with_intervals(key,
+                                                              &[$( $aggregation_type ),*],
+                                                              BucketConfig {
+                                                                  width: $bucket_width,
+                                                                  min: $min,
+                                                                  max: $max,
+                                                              },
+                                                              &[$( $percentile ),*],
+                                                              &[$($( $interval ),*)?])
                     })
                 }
 
-                DynamicStat::new(__key_generator, __stat_generator)
+                $crate::__dynamic_stat_with_limits!(
+                    $prefix; $name; DynamicStat::new(__key_generator, __stat_generator);
+                    $( $limit = $value ),*
+                )
             };
         }
     );
+
+    // Labeled stats are defined with their key and the names and types of their labels, and
+    // are used with the values of their labels, which are formatted with Display. Stats services
+    // that don't support labels use the key followed by the values of the labels, e.g.
+    // "{key}.{value}". They can be limited like the dynamic stats.
+    ($prefix:expr;
+     $name:ident: labeled_counter($key:expr, ($( $label:ident: $type:ty ),+))) => (
+        $crate::__define_stat!(
+            $prefix;
+            $name: labeled_counter($key, ($( $label: $type ),+); limits())
+        );
+    );
+
+    ($prefix:expr;
+     $name:ident: labeled_counter($key:expr, ($( $label:ident: $type:ty ),+);
+                                  limits($( $limit:ident = $value:expr ),*))) => (
+        thread_local! {
+            pub static $name: DynamicStat<($( $type, )+), BoxCounter> = {
+                $crate::__define_label_key_generator!(__key_generator($( $label: $type ),+));
+
+                fn __stat_generator(&($( ref $label, )+): &($( $type, )+)) -> BoxCounter {
+                    let values = [$( $label.to_string() ),+];
+                    let names = [$( stringify!($label) ),+];
+                    let labels: Vec<_> = names
+                        .iter()
+                        .copied()
+                        .zip(values.iter().map(String::as_str))
+                        .collect();
+                    TL_STATS.with(|stats| {
+                        stats.create_labeled_counter(
+                            &$crate::__create_stat_key!($prefix, $key),
+                            &labels,
+                        )
+                    })
+                }
+
+                $crate::__dynamic_stat_with_limits!(
+                    $prefix; $name; DynamicStat::from_args(__key_generator, __stat_generator);
+                    $( $limit = $value ),*
+                )
+            };
+        }
+    );
+
+    ($prefix:expr;
+     $name:ident: labeled_timeseries($key:expr, ($( $label:ident: $type:ty ),+);
+                                     $( $aggregation_type:expr ),* ;
+                                     limits($( $limit:ident = $value:expr ),*))) => (
+        $crate::__define_stat!(
+            $prefix;
+            $name: labeled_timeseries(
+                $key,
+                ($( $label: $type ),+);
+                $( $aggregation_type ),* ;
+                ;
+                limits($( $limit = $value ),*)
+            )
+        );
+    );
+
+    ($prefix:expr;
+     $name:ident: labeled_timeseries($key:expr, ($( $label:ident: $type:ty ),+);
+                                     $( $aggregation_type:expr ),* ; $( $interval:expr ),* ;
+                                     limits($( $limit:ident = $value:expr ),*))) => (
+        thread_local! {
+            pub static $name: DynamicStat<($( $type, )+), BoxTimeseries> = {
+                $crate::__define_label_key_generator!(__key_generator($( $label: $type ),+));
+
+                fn __stat_generator(&($( ref $label, )+): &($( $type, )+)) -> BoxTimeseries {
+                    let values = [$( $label.to_string() ),+];
+                    let names = [$( stringify!($label) ),+];
+                    let labels: Vec<_> = names
+                        .iter()
+                        .copied()
+                        .zip(values.iter().map(String::as_str))
+                        .collect();
+                    TL_STATS.with(|stats| {
+                        stats.create_labeled_timeseries(
+                            &$crate::__create_stat_key!($prefix, $key),
+                            &labels,
+                            &[$( $aggregation_type ),*],
+                            &[$( $interval ),*],
+                        )
+                    })
+                }
+
+                $crate::__dynamic_stat_with_limits!(
+                    $prefix; $name; DynamicStat::from_args(__key_generator, __stat_generator);
+                    $( $limit = $value ),*
+                )
+            };
+        }
+    );
+
+    ($prefix:expr;
+     $name:ident: labeled_timeseries($key:expr, ($( $label:ident: $type:ty ),+);
+                                     $( $aggregation_type:expr ),*)) => (
+        $crate::__define_stat!(
+            $prefix;
+            $name: labeled_timeseries(
+                $key,
+                ($( $label: $type ),+);
+                $( $aggregation_type ),* ;
+            )
+        );
+    );
+
+    ($prefix:expr;
+     $name:ident: labeled_timeseries($key:expr, ($( $label:ident: $type:ty ),+);
+                                     $( $aggregation_type:expr ),* ; $( $interval:expr ),*)) => (
+        $crate::__define_stat!(
+            $prefix;
+            $name: labeled_timeseries(
+                $key,
+                ($( $label: $type ),+);
+                $( $aggregation_type ),* ;
+                $( $interval ),* ;
+                limits()
+            )
+        );
+    );
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __define_label_key_generator {
+    ($name:ident($( $label:ident: $type:ty ),+)) => (
+        fn $name(&($( ref $label, )+): &($( $type, )+)) -> String {
+            // The values are only joined to tell the sets of labels apart, with a separator
+            // that is unlikely to be in them.
+            [$( $label.to_string() ),+].join("\0")
+        }
+    )
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dynamic_stat_with_limits {
+    ($prefix:expr; $name:ident; $stat:expr; $( $limit:ident = $value:expr ),*) => {{
+        fn __eviction_counter_generator() -> BoxCounter {
+            TL_STATS.with(|stats| {
+                stats.create_counter(&$crate::__create_stat_key!(
+                    $prefix,
+                    concat!(stringify!($name), ".evicted_keys")
+                ))
+            })
+        }
+
+        $stat.with_limits(
+            DynamicStatLimits::default()$( .$limit($value) )*,
+            __eviction_counter_generator,
+        )
+    }};
 }
 
 #[doc(hidden)]
@@ -451,6 +762,12 @@ macro_rules! __struct_field_init {
         })
     }};
 
+    ($prefix:expr, $name:ident, timeseries, $( $aggregation_type:expr ),* ; intervals($( $interval:expr ),*)) => {
+        $crate::__struct_field_init! ($prefix, $name, timeseries, stringify!($name) ; $($aggregation_type),* ; $($interval),*)
+    };
+    ($prefix:expr, $name:ident, timeseries, $key:expr ; $( $aggregation_type:expr ),* ; intervals($( $interval:expr ),*)) => {
+        $crate::__struct_field_init! ($prefix, $name, timeseries, $key ; $($aggregation_type),* ; $($interval),*)
+    };
     ($prefix:expr, $name:ident, timeseries, $( $aggregation_type:expr ),+) => {
         $crate::__struct_field_init! ($prefix, $name, timeseries, stringify!($name) ; $($aggregation_type),*)
     };
@@ -466,17 +783,17 @@ macro_rules! __struct_field_init {
 
     ($prefix:expr, $name:ident, histogram,
         $bucket_width:expr, $min:expr, $max:expr $(, $aggregation_type:expr)*
-        $(; P $percentile:expr )*) => {
+        $(; P $percentile:expr )* $(; intervals($( $interval:expr ),*) )?) => {
         $crate::__struct_field_init! ($prefix, $name, histogram,
             stringify!($name) ; $bucket_width, $min, $max $(, $aggregation_type)*
-            $(; P $percentile)* )
+            $(; P $percentile)* $(; intervals($( $interval ),*) )? )
     };
     ($prefix:expr, $name:ident, histogram, $key:expr ;
         $bucket_width:expr, $min:expr, $max:expr $(, $aggregation_type:expr)*
-        $(; P $percentile:expr )*) => {{
+        $(; P $percentile:expr )* $(; intervals($( $interval:expr ),*) )?) => {{
         let key = format!("{}.{}", $prefix, $key);
         TL_STATS.with(|stats| {
-            stats.create_histogram(
+            stats.create_histogram_with_intervals(
                 &key,
                 &[$( $aggregation_type ),*],
                 BucketConfig {
@@ -484,7 +801,8 @@ macro_rules! __struct_field_init {
                     min: $min,
                     max: $max,
                 },
-                &[$( $percentile ),*])
+                &[$( $percentile ),*],
+                &[$($( $interval ),*)?])
         })
     }};
 }
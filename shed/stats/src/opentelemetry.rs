@@ -0,0 +1,463 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! A bridge that mirrors the stats into an OpenTelemetry [Meter], so that binaries that already
+//! export their metrics with OpenTelemetry get the stats of the libraries they use as well. It
+//! is enabled by the `opentelemetry` feature.
+//!
+//! To use it, register [OpenTelemetryStatsFactory] with
+//! [register_stats_manager_factory](crate::register_stats_manager_factory) before any of the
+//! stats are used. Every value is recorded both by the stats of the factory it mirrors and by
+//! an instrument of the meter with the same name as the stat:
+//!
+//! - counters are recorded by an `i64` up-down counter, since they can be decremented,
+//! - timeseries are recorded by an `f64` histogram, from which the sum, count, average, rate
+//!   and percentiles of the values can be computed, over whichever intervals the exporter of
+//!   the meter aggregates them,
+//! - histograms are recorded by an `f64` histogram with the same bucket boundaries,
+//! - gauges are recorded by an `i64` observable gauge, which calls the callback of the gauge
+//!   when the meter is collected, until the gauge is dropped.
+//!
+//! The labels of labeled stats are recorded as the attributes of their measurements.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use ::opentelemetry::{
+    metrics::{Histogram as OtelHistogram, Meter, ObservableGauge, UpDownCounter},
+    KeyValue,
+};
+use stats_traits::{
+    stat_types::{
+        BoxCounter, BoxGauge, BoxHistogram, BoxTimeseries, Counter, Gauge, GaugeCallback,
+        Histogram, Timeseries,
+    },
+    stats_manager::{
+        AggregationType, BoxStatsManager, BucketConfig, StatsManager, StatsManagerFactory,
+    },
+};
+
+/// Factory of the stats mirrored into a [Meter], see the [module level documentation](self).
+pub struct OpenTelemetryStatsFactory {
+    meter: Meter,
+    mirrored: Box<dyn StatsManagerFactory + Send + Sync>,
+}
+
+impl OpenTelemetryStatsFactory {
+    /// Mirror into `meter` the stats of the factory that is used when none is registered, which
+    /// does nothing outside of Facebook.
+    pub fn new(meter: Meter) -> Self {
+        Self {
+            meter,
+            mirrored: crate::get_default_stats_manager_factory(),
+        }
+    }
+
+    /// Mirror into `meter` the stats of `factory`, for example
+    /// [InMemoryStatsFactory](crate::in_memory::InMemoryStatsFactory) to also read them back
+    /// from the binary.
+    pub fn mirroring(
+        meter: Meter,
+        factory: impl StatsManagerFactory + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            meter,
+            mirrored: Box::new(factory),
+        }
+    }
+}
+
+impl StatsManagerFactory for OpenTelemetryStatsFactory {
+    fn create(&self) -> BoxStatsManager {
+        Box::new(OpenTelemetryStatsManager {
+            meter: self.meter.clone(),
+            mirrored: self.mirrored.create(),
+        })
+    }
+
+    fn create_gauge(&self, name: &str, callback: GaugeCallback) -> BoxGauge {
+        // The meter keeps the callbacks of its instruments for as long as it lives, so it is only
+        // given a weak reference to stop observing the gauge once it is dropped.
+        let weak = Arc::downgrade(&callback);
+        let instrument = self
+            .meter
+            .i64_observable_gauge(name.to_owned())
+            .with_callback(move |observer| {
+                if let Some(callback) = weak.upgrade() {
+                    observer.observe(callback(), &[]);
+                }
+            })
+            .build();
+        Box::new(OpenTelemetryGauge {
+            _instrument: instrument,
+            _mirrored: self.mirrored.create_gauge(name, callback.clone()),
+            _callback: callback,
+        })
+    }
+}
+
+struct OpenTelemetryStatsManager {
+    meter: Meter,
+    mirrored: BoxStatsManager,
+}
+
+impl StatsManager for OpenTelemetryStatsManager {
+    fn aggregate(&self) {
+        // The meter aggregates the values itself when they are collected.
+        self.mirrored.aggregate()
+    }
+
+    fn create_counter(&self, name: &str) -> BoxCounter {
+        Box::new(OpenTelemetryCounter {
+            instrument: self.meter.i64_up_down_counter(name.to_owned()).build(),
+            attributes: Vec::new(),
+            mirrored: self.mirrored.create_counter(name),
+        })
+    }
+
+    fn create_timeseries(
+        &self,
+        name: &str,
+        aggregation_types: &[AggregationType],
+        intervals: &[Duration],
+    ) -> BoxTimeseries {
+        Box::new(OpenTelemetryTimeseries {
+            instrument: self.meter.f64_histogram(name.to_owned()).build(),
+            attributes: Vec::new(),
+            mirrored: self
+                .mirrored
+                .create_timeseries(name, aggregation_types, intervals),
+        })
+    }
+
+    fn create_histogram(
+        &self,
+        name: &str,
+        aggregation_types: &[AggregationType],
+        conf: BucketConfig,
+        percentiles: &[u8],
+    ) -> BoxHistogram {
+        self.create_histogram_with_intervals(name, aggregation_types, conf, percentiles, &[])
+    }
+
+    fn create_histogram_with_intervals(
+        &self,
+        name: &str,
+        aggregation_types: &[AggregationType],
+        conf: BucketConfig,
+        percentiles: &[u8],
+        intervals: &[Duration],
+    ) -> BoxHistogram {
+        let width = conf.width.max(1) as usize;
+        let boundaries = (conf.min..conf.max)
+            .step_by(width)
+            .chain(std::iter::once(conf.max.max(conf.min)))
+            .map(f64::from)
+            .collect();
+        Box::new(OpenTelemetryHistogram {
+            instrument: self
+                .meter
+                .f64_histogram(name.to_owned())
+                .with_boundaries(boundaries)
+                .build(),
+            mirrored: self.mirrored.create_histogram_with_intervals(
+                name,
+                aggregation_types,
+                conf,
+                percentiles,
+                intervals,
+            ),
+        })
+    }
+
+    fn create_labeled_counter(&self, name: &str, labels: &[(&str, &str)]) -> BoxCounter {
+        Box::new(OpenTelemetryCounter {
+            instrument: self.meter.i64_up_down_counter(name.to_owned()).build(),
+            attributes: attributes(labels),
+            mirrored: self.mirrored.create_labeled_counter(name, labels),
+        })
+    }
+
+    fn create_labeled_timeseries(
+        &self,
+        name: &str,
+        labels: &[(&str, &str)],
+        aggregation_types: &[AggregationType],
+        intervals: &[Duration],
+    ) -> BoxTimeseries {
+        Box::new(OpenTelemetryTimeseries {
+            instrument: self.meter.f64_histogram(name.to_owned()).build(),
+            attributes: attributes(labels),
+            mirrored: self.mirrored.create_labeled_timeseries(
+                name,
+                labels,
+                aggregation_types,
+                intervals,
+            ),
+        })
+    }
+}
+
+fn attributes(labels: &[(&str, &str)]) -> Vec<KeyValue> {
+    labels
+        .iter()
+        .map(|(label, value)| KeyValue::new((*label).to_owned(), (*value).to_owned()))
+        .collect()
+}
+
+struct OpenTelemetryCounter {
+    instrument: UpDownCounter<i64>,
+    attributes: Vec<KeyValue>,
+    mirrored: BoxCounter,
+}
+
+impl Counter for OpenTelemetryCounter {
+    fn increment_value(&self, value: i64) {
+        self.instrument.add(value, &self.attributes);
+        self.mirrored.increment_value(value);
+    }
+}
+
+struct OpenTelemetryTimeseries {
+    instrument: OtelHistogram<f64>,
+    attributes: Vec<KeyValue>,
+    mirrored: BoxTimeseries,
+}
+
+impl Timeseries for OpenTelemetryTimeseries {
+    fn add_value(&self, value: i64) {
+        self.instrument.record(value as f64, &self.attributes);
+        self.mirrored.add_value(value);
+    }
+
+    fn add_value_aggregated(&self, value: i64, nsamples: u32) {
+        if nsamples > 0 {
+            // The samples are assumed to be all equal to their average.
+            let average = value as f64 / f64::from(nsamples);
+            for _ in 0..nsamples {
+                self.instrument.record(average, &self.attributes);
+            }
+        }
+        self.mirrored.add_value_aggregated(value, nsamples);
+    }
+}
+
+struct OpenTelemetryHistogram {
+    instrument: OtelHistogram<f64>,
+    mirrored: BoxHistogram,
+}
+
+impl Histogram for OpenTelemetryHistogram {
+    fn add_value(&self, value: i64) {
+        self.instrument.record(value as f64, &[]);
+        self.mirrored.add_value(value);
+    }
+
+    fn add_repeated_value(&self, value: i64, nsamples: u32) {
+        for _ in 0..nsamples {
+            self.instrument.record(value as f64, &[]);
+        }
+        self.mirrored.add_repeated_value(value, nsamples);
+    }
+}
+
+/// Keeps the gauge observed by the meter and registered with the mirrored factory until it is
+/// dropped.
+struct OpenTelemetryGauge {
+    _instrument: ObservableGauge<i64>,
+    _callback: GaugeCallback,
+    _mirrored: BoxGauge,
+}
+
+impl Gauge for OpenTelemetryGauge {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    use ::opentelemetry::metrics::{
+        AsyncInstrument, AsyncInstrumentBuilder, Callback, HistogramBuilder, InstrumentBuilder,
+        InstrumentProvider, SyncInstrument,
+    };
+
+    use crate::in_memory::{exported_values, InMemoryStatsFactory};
+
+    type Measurements = Arc<Mutex<BTreeMap<String, Vec<f64>>>>;
+    type Gauges = Arc<Mutex<Vec<(String, Callback<i64>)>>>;
+
+    /// Records the measurements of every instrument by the name of the instrument, with the
+    /// boundaries of the histograms as their first measurements, and keeps the callbacks of the
+    /// observable gauges to be called by [RecordingProvider::collect].
+    #[derive(Default)]
+    struct RecordingProvider(Measurements, Gauges);
+
+    struct RecordingInstrument(Measurements, String);
+
+    impl RecordingInstrument {
+        /// Record `measurement` by the name of the instrument, followed by the attributes of
+        /// the measurement if there are any.
+        fn push(&self, measurement: f64, attributes: &[KeyValue]) {
+            let mut name = self.1.clone();
+            for attribute in attributes {
+                name.push_str(&format!(" {}={}", attribute.key, attribute.value));
+            }
+            self.0
+                .lock()
+                .expect("poisoned lock")
+                .entry(name)
+                .or_default()
+                .push(measurement);
+        }
+    }
+
+    impl SyncInstrument<i64> for RecordingInstrument {
+        fn measure(&self, measurement: i64, attributes: &[KeyValue]) {
+            self.push(measurement as f64, attributes);
+        }
+    }
+
+    impl SyncInstrument<f64> for RecordingInstrument {
+        fn measure(&self, measurement: f64, attributes: &[KeyValue]) {
+            self.push(measurement, attributes);
+        }
+    }
+
+    impl AsyncInstrument<i64> for RecordingInstrument {
+        fn observe(&self, measurement: i64, attributes: &[KeyValue]) {
+            self.push(measurement as f64, attributes);
+        }
+    }
+
+    impl RecordingProvider {
+        fn collect(&self) {
+            for (name, callback) in self.1.lock().expect("poisoned lock").iter() {
+                callback(&RecordingInstrument(self.0.clone(), name.clone()));
+            }
+        }
+
+        fn instrument(&self, name: &str, boundaries: Vec<f64>) -> Arc<RecordingInstrument> {
+            self.0
+                .lock()
+                .expect("poisoned lock")
+                .entry(name.to_owned())
+                .or_insert(boundaries);
+            Arc::new(RecordingInstrument(self.0.clone(), name.to_owned()))
+        }
+    }
+
+    impl InstrumentProvider for RecordingProvider {
+        fn i64_up_down_counter(
+            &self,
+            builder: InstrumentBuilder<'_, UpDownCounter<i64>>,
+        ) -> UpDownCounter<i64> {
+            UpDownCounter::new(self.instrument(&builder.name, Vec::new()))
+        }
+
+        fn f64_histogram(
+            &self,
+            builder: HistogramBuilder<'_, OtelHistogram<f64>>,
+        ) -> OtelHistogram<f64> {
+            let boundaries = builder.boundaries.unwrap_or_default();
+            OtelHistogram::new(self.instrument(&builder.name, boundaries))
+        }
+
+        fn i64_observable_gauge(
+            &self,
+            builder: AsyncInstrumentBuilder<'_, ObservableGauge<i64>, i64>,
+        ) -> ObservableGauge<i64> {
+            let mut gauges = self.1.lock().expect("poisoned lock");
+            for callback in builder.callbacks {
+                gauges.push((builder.name.to_string(), callback));
+            }
+            ObservableGauge::new()
+        }
+    }
+
+    #[test]
+    fn test_mirrored_stats() {
+        let provider = RecordingProvider::default();
+        let measurements = provider.0.clone();
+        let manager = OpenTelemetryStatsFactory::mirroring(
+            Meter::new(Arc::new(provider)),
+            InMemoryStatsFactory,
+        )
+        .create();
+
+        let counter = manager.create_counter("opentelemetry.test.counter");
+        counter.increment_value(3);
+        counter.increment_value(-1);
+        let timeseries = manager.create_timeseries(
+            "opentelemetry.test.timeseries",
+            &[AggregationType::Sum],
+            &[],
+        );
+        timeseries.add_value(10);
+        timeseries.add_value_aggregated(20, 2);
+        let histogram = manager.create_histogram(
+            "opentelemetry.test.histogram",
+            &[AggregationType::Count],
+            BucketConfig {
+                width: 10,
+                min: 0,
+                max: 25,
+            },
+            &[],
+        );
+        histogram.add_repeated_value(7, 2);
+        manager
+            .create_labeled_counter("opentelemetry.test.labeled", &[("method", "GET")])
+            .increment_value(4);
+
+        let measurements = measurements.lock().expect("poisoned lock");
+        assert_eq!(measurements["opentelemetry.test.counter"], vec![3.0, -1.0]);
+        assert_eq!(
+            measurements["opentelemetry.test.timeseries"],
+            vec![10.0, 10.0, 10.0]
+        );
+        assert_eq!(
+            measurements["opentelemetry.test.histogram"],
+            vec![0.0, 10.0, 20.0, 25.0, 7.0, 7.0]
+        );
+
+        assert_eq!(
+            measurements["opentelemetry.test.labeled method=GET"],
+            vec![4.0]
+        );
+
+        let values = exported_values();
+        assert_eq!(values["opentelemetry.test.counter"], 2);
+        assert_eq!(values["opentelemetry.test.timeseries.sum"], 30);
+        assert_eq!(values["opentelemetry.test.histogram.count"], 2);
+        assert_eq!(values["opentelemetry.test.labeled.GET"], 4);
+    }
+
+    #[test]
+    fn test_gauge() {
+        let provider = Arc::new(RecordingProvider::default());
+        let factory = OpenTelemetryStatsFactory::mirroring(
+            Meter::new(provider.clone()),
+            InMemoryStatsFactory,
+        );
+
+        let gauge = factory.create_gauge("opentelemetry.test.gauge", Arc::new(|| 7));
+        provider.collect();
+        assert_eq!(exported_values()["opentelemetry.test.gauge"], 7);
+
+        drop(gauge);
+        provider.collect();
+        assert!(!exported_values().contains_key("opentelemetry.test.gauge"));
+        assert_eq!(
+            provider.0.lock().expect("poisoned lock")["opentelemetry.test.gauge"],
+            vec![7.0]
+        );
+    }
+}
@@ -0,0 +1,316 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Export of the stats in the Prometheus text exposition format, so that they can be scraped
+//! from whichever HTTP server the binary already runs, by serving the output of [render] with
+//! the [CONTENT_TYPE] content type.
+//!
+//! Only the stats of the [in_memory](crate::in_memory) implementation can be exported, so it
+//! has to be registered with
+//! [register_stats_manager_factory](crate::register_stats_manager_factory).
+//!
+//! The names of the stats have the characters that Prometheus doesn't allow, such as `.`,
+//! replaced with `_`, and the labels of labeled stats are exported as such. Counters are exported
+//! as gauges, since they can be decremented, and so are gauges. Timeseries are exported as
+//! summaries, with their sum and count over the lifetime of the process, and the percentiles they
+//! export, if any, over their shortest interval. Histograms are exported as histograms, with their
+//! buckets over the lifetime of the process, whose `le` bound is the largest integer in the
+//! bucket.
+
+use std::fmt::{self, Write};
+
+use crate::in_memory::{stat_states, Labels, StatState};
+
+/// The content type of the output of [render].
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Render all the stats in the Prometheus text exposition format.
+pub fn render() -> String {
+    let mut out = String::new();
+    let mut family = None;
+    for (name, labels, state) in stat_states() {
+        let name = metric_name(&name);
+        // The stats are sorted by name, so the labeled stats with the same name are consecutive,
+        // and the type of their metric is only written once.
+        let write_type = family.as_ref() != Some(&name);
+        write_stat(&mut out, &name, &labels, state, write_type)
+            .expect("writing to a String never fails");
+        family = Some(name);
+    }
+    out
+}
+
+fn write_stat(
+    out: &mut String,
+    name: &str,
+    labels: &Labels,
+    state: StatState,
+    write_type: bool,
+) -> fmt::Result {
+    let metric_type = match state {
+        StatState::Counter(_) | StatState::Gauge(_) => "gauge",
+        StatState::Timeseries { .. } => "summary",
+        StatState::Histogram { .. } => "histogram",
+    };
+    if write_type {
+        writeln!(out, "# TYPE {} {}", name, metric_type)?;
+    }
+    let labels = format_labels(labels);
+    match state {
+        StatState::Counter(value) | StatState::Gauge(value) => {
+            writeln!(out, "{}{} {}", name, braced(&labels), value)
+        }
+        StatState::Timeseries {
+            sum,
+            count,
+            percentiles,
+        } => {
+            for (percentile, value) in percentiles {
+                let quantile = format!("quantile=\"{}\"", f64::from(percentile) / 100.0);
+                writeln!(
+                    out,
+                    "{}{} {}",
+                    name,
+                    braced(&with_label(&labels, &quantile)),
+                    value
+                )?;
+            }
+            writeln!(out, "{}_sum{} {}", name, braced(&labels), sum)?;
+            writeln!(out, "{}_count{} {}", name, braced(&labels), count)
+        }
+        StatState::Histogram {
+            sum,
+            count,
+            buckets,
+        } => {
+            let mut cumulative = 0;
+            for (bound, bucket_count) in buckets {
+                cumulative += bucket_count;
+                // The overflow bucket has no bound, and is only counted in the +Inf bucket. The
+                // bounds of the buckets are exclusive, and the values are integers, so the
+                // largest value in a bucket is one less than its bound.
+                if let Some(bound) = bound {
+                    let le = format!("le=\"{}\"", bound - 1);
+                    writeln!(
+                        out,
+                        "{}_bucket{} {}",
+                        name,
+                        braced(&with_label(&labels, &le)),
+                        cumulative
+                    )?;
+                }
+            }
+            let le = with_label(&labels, "le=\"+Inf\"");
+            writeln!(out, "{}_bucket{} {}", name, braced(&le), count)?;
+            writeln!(out, "{}_sum{} {}", name, braced(&labels), sum)?;
+            writeln!(out, "{}_count{} {}", name, braced(&labels), count)
+        }
+    }
+}
+
+/// Format `labels` as a comma separated list of `name="value"`.
+fn format_labels(labels: &Labels) -> String {
+    labels
+        .iter()
+        .map(|(label, value)| format!("{}=\"{}\"", label_name(label), escape(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn with_label(labels: &str, label: &str) -> String {
+    if labels.is_empty() {
+        label.to_owned()
+    } else {
+        format!("{},{}", labels, label)
+    }
+}
+
+fn braced(labels: &str) -> String {
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", labels)
+    }
+}
+
+/// Escape the characters that can't be in the value of a label as is.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Turn the name of a stat into a valid Prometheus metric name.
+fn metric_name(name: &str) -> String {
+    sanitize(name, |c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+/// Turn the name of a label into a valid Prometheus label name.
+fn label_name(name: &str) -> String {
+    sanitize(name, |c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Replace the characters of `name` that aren't `valid` with `_`, and prefix it with `_` if it
+/// starts with a digit.
+fn sanitize(name: &str, valid: impl Fn(char) -> bool) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| if valid(c) { c } else { '_' })
+        .collect();
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) || sanitized.is_empty() {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use stats_traits::stats_manager::{
+        AggregationType::*, BucketConfig, StatsManager, StatsManagerFactory, P50,
+    };
+
+    use crate::in_memory::InMemoryStatsFactory;
+
+    #[test]
+    fn test_metric_name() {
+        assert_eq!(metric_name("my.stat-name"), "my_stat_name");
+        assert_eq!(metric_name("1.stat"), "_1_stat");
+        assert_eq!(metric_name("ok_name:sub"), "ok_name:sub");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[test]
+    fn test_render_labels() {
+        let manager = InMemoryStatsFactory.create();
+        manager
+            .create_labeled_counter("prometheus.test.labeled", &[("method", "GET")])
+            .increment_value(1);
+        manager
+            .create_labeled_counter("prometheus.test.labeled", &[("method", "POST")])
+            .increment_value(2);
+        manager
+            .create_labeled_timeseries(
+                "prometheus.test.labeled_t",
+                &[("method", "GET"), ("status", "200")],
+                &[P50],
+                &[],
+            )
+            .add_value(5);
+
+        let rendered = render();
+        let lines: Vec<_> = rendered.lines().collect();
+        for expected in [
+            "# TYPE prometheus_test_labeled gauge",
+            "prometheus_test_labeled{method=\"GET\"} 1",
+            "prometheus_test_labeled{method=\"POST\"} 2",
+            "prometheus_test_labeled_t{method=\"GET\",status=\"200\",quantile=\"0.5\"} 5",
+            "prometheus_test_labeled_t_count{method=\"GET\",status=\"200\"} 1",
+        ] {
+            assert!(
+                lines.contains(&expected),
+                "{:?} not found in:\n{}",
+                expected,
+                rendered
+            );
+        }
+        let types = lines
+            .iter()
+            .filter(|line| **line == "# TYPE prometheus_test_labeled gauge")
+            .count();
+        assert_eq!(types, 1);
+    }
+
+    #[test]
+    fn test_render() {
+        let manager = InMemoryStatsFactory.create();
+        manager
+            .create_counter("prometheus.test.counter")
+            .increment_value(3);
+        let timeseries = manager.create_timeseries("prometheus.test.timeseries", &[Sum, P50], &[]);
+        timeseries.add_value(10);
+        timeseries.add_value(20);
+        let histogram = manager.create_histogram(
+            "prometheus.test.histogram",
+            &[],
+            BucketConfig {
+                width: 10,
+                min: 0,
+                max: 20,
+            },
+            &[],
+        );
+        histogram.add_value(5);
+        histogram.add_value(15);
+        histogram.add_value(100);
+
+        let rendered = render();
+        let lines: Vec<_> = rendered.lines().collect();
+        for expected in [
+            "# TYPE prometheus_test_counter gauge",
+            "prometheus_test_counter 3",
+            "# TYPE prometheus_test_timeseries summary",
+            "prometheus_test_timeseries{quantile=\"0.5\"} 20",
+            "prometheus_test_timeseries_sum 30",
+            "prometheus_test_timeseries_count 2",
+            "# TYPE prometheus_test_histogram histogram",
+            "prometheus_test_histogram_bucket{le=\"-1\"} 0",
+            "prometheus_test_histogram_bucket{le=\"9\"} 1",
+            "prometheus_test_histogram_bucket{le=\"19\"} 2",
+            "prometheus_test_histogram_bucket{le=\"+Inf\"} 3",
+            "prometheus_test_histogram_sum 120",
+            "prometheus_test_histogram_count 3",
+        ] {
+            assert!(
+                lines.contains(&expected),
+                "{:?} not found in:\n{}",
+                expected,
+                rendered
+            );
+        }
+    }
+
+    #[test]
+    fn test_bucket_boundaries() {
+        let histogram = InMemoryStatsFactory.create().create_histogram(
+            "prometheus.test.boundaries",
+            &[],
+            BucketConfig {
+                width: 10,
+                min: 0,
+                max: 30,
+            },
+            &[],
+        );
+        histogram.add_value(9);
+        histogram.add_value(10);
+        histogram.add_value(20);
+
+        let rendered = render();
+        let lines: Vec<_> = rendered.lines().collect();
+        for expected in [
+            "prometheus_test_boundaries_bucket{le=\"9\"} 1",
+            "prometheus_test_boundaries_bucket{le=\"19\"} 2",
+            "prometheus_test_boundaries_bucket{le=\"29\"} 3",
+        ] {
+            assert!(
+                lines.contains(&expected),
+                "{:?} not found in:\n{}",
+                expected,
+                rendered
+            );
+        }
+    }
+}
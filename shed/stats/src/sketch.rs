@@ -0,0 +1,258 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under both the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree and the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree.
+ */
+
+//! Summaries of the distribution of the values added to a stat, from which percentiles are
+//! computed.
+
+use std::collections::BTreeMap;
+
+use stats_traits::stats_manager::BucketConfig;
+
+/// The relative error of the percentiles computed by a [QuantileSketch].
+const RELATIVE_ERROR: f64 = 0.01;
+
+/// A streaming sketch of a distribution, in the manner of DDSketch: values are counted in
+/// buckets whose bounds grow geometrically, so that the percentiles computed from it are within
+/// [RELATIVE_ERROR] of the actual values, and sketches can be merged by adding up their buckets.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct QuantileSketch {
+    positive: BTreeMap<i32, u64>,
+    negative: BTreeMap<i32, u64>,
+    zero: u64,
+}
+
+impl QuantileSketch {
+    fn gamma() -> f64 {
+        (1.0 + RELATIVE_ERROR) / (1.0 - RELATIVE_ERROR)
+    }
+
+    fn key(magnitude: f64) -> i32 {
+        (magnitude.ln() / Self::gamma().ln()).ceil() as i32
+    }
+
+    fn value(key: i32) -> f64 {
+        let gamma = Self::gamma();
+        2.0 * gamma.powi(key) / (gamma + 1.0)
+    }
+
+    pub(crate) fn add(&mut self, value: i64, nsamples: u64) {
+        match value {
+            0 => self.zero += nsamples,
+            v if v > 0 => *self.positive.entry(Self::key(v as f64)).or_default() += nsamples,
+            v => *self.negative.entry(Self::key(-(v as f64))).or_default() += nsamples,
+        }
+    }
+
+    pub(crate) fn merge(&mut self, other: &Self) {
+        for (key, count) in &other.positive {
+            *self.positive.entry(*key).or_default() += count;
+        }
+        for (key, count) in &other.negative {
+            *self.negative.entry(*key).or_default() += count;
+        }
+        self.zero += other.zero;
+    }
+
+    /// The value at `percentile` (from 0 to 100), or None if the sketch is empty.
+    pub(crate) fn percentile(&self, percentile: f64) -> Option<i64> {
+        let count = self
+            .positive
+            .values()
+            .chain(self.negative.values())
+            .sum::<u64>()
+            + self.zero;
+        let rank = target_rank(count, percentile)?;
+
+        // Go through the buckets from the smallest value to the largest.
+        let buckets = self
+            .negative
+            .iter()
+            .rev()
+            .map(|(key, count)| (-Self::value(*key), *count))
+            .chain(std::iter::once((0.0, self.zero)))
+            .chain(
+                self.positive
+                    .iter()
+                    .map(|(key, count)| (Self::value(*key), *count)),
+            );
+        let mut seen = 0;
+        for (value, count) in buckets {
+            seen += count;
+            if seen > rank {
+                return Some(value.round() as i64);
+            }
+        }
+        None
+    }
+}
+
+/// The counts of the values in each of the buckets configured for a histogram, with an
+/// underflow bucket first and an overflow bucket last.
+#[derive(Clone, Debug)]
+pub(crate) struct BucketedCounts {
+    width: i64,
+    min: i64,
+    max: i64,
+    counts: Vec<u64>,
+}
+
+impl BucketedCounts {
+    pub(crate) fn new(conf: &BucketConfig) -> Self {
+        let width = i64::from(conf.width.max(1));
+        let min = i64::from(conf.min);
+        let max = i64::from(conf.max.max(conf.min));
+        let buckets = ((max - min + width - 1) / width) as usize;
+        Self {
+            width,
+            min,
+            max,
+            counts: vec![0; buckets + 2],
+        }
+    }
+
+    pub(crate) fn add(&mut self, value: i64, nsamples: u64) {
+        let index = if value < self.min {
+            0
+        } else if value >= self.max {
+            self.counts.len() - 1
+        } else {
+            ((value - self.min) / self.width) as usize + 1
+        };
+        self.counts[index] += nsamples;
+    }
+
+    pub(crate) fn merge(&mut self, other: &Self) {
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+    }
+
+    /// The count of each bucket by its exclusive upper bound, ending with the overflow bucket
+    /// that has none.
+    pub(crate) fn buckets(&self) -> Vec<(Option<i64>, u64)> {
+        let last = self.counts.len() - 1;
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(index, count)| {
+                let bound = if index == last {
+                    None
+                } else {
+                    Some((self.min + index as i64 * self.width).min(self.max))
+                };
+                (bound, *count)
+            })
+            .collect()
+    }
+
+    /// The value at `percentile` (from 0 to 100), interpolated within its bucket, or None if
+    /// there are no values. Values in the underflow and overflow buckets are reported as the
+    /// minimum and the maximum of the histogram.
+    pub(crate) fn percentile(&self, percentile: f64) -> Option<i64> {
+        let rank = target_rank(self.counts.iter().sum(), percentile)?;
+        let last = self.counts.len() - 1;
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            if seen + count > rank {
+                if index == 0 {
+                    return Some(self.min);
+                }
+                if index == last {
+                    return Some(self.max);
+                }
+                let low = self.min + (index as i64 - 1) * self.width;
+                let high = (low + self.width).min(self.max);
+                let fraction = (rank - seen) as f64 / *count as f64;
+                return Some(low + ((high - low) as f64 * fraction).round() as i64);
+            }
+            seen += count;
+        }
+        None
+    }
+}
+
+/// The rank of the value at `percentile` among `count` values, or None if there are none.
+fn target_rank(count: u64, percentile: f64) -> Option<u64> {
+    if count == 0 {
+        return None;
+    }
+    let rank = (percentile.clamp(0.0, 100.0) / 100.0 * (count - 1) as f64).round() as u64;
+    Some(rank)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<i64>, expected: i64) {
+        let actual = actual.expect("sketch is empty");
+        let error = (actual - expected).abs() as f64;
+        assert!(
+            error <= expected.abs() as f64 * RELATIVE_ERROR + 1.0,
+            "got {}, expected {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_quantile_sketch() {
+        let mut sketch = QuantileSketch::default();
+        assert_eq!(sketch.percentile(50.0), None);
+        for value in 1..=1000 {
+            sketch.add(value, 1);
+        }
+        assert_close(sketch.percentile(0.0), 1);
+        assert_close(sketch.percentile(50.0), 500);
+        assert_close(sketch.percentile(99.0), 990);
+        assert_close(sketch.percentile(100.0), 1000);
+    }
+
+    #[test]
+    fn test_quantile_sketch_negative_and_merge() {
+        let mut sketch = QuantileSketch::default();
+        sketch.add(-100, 10);
+        sketch.add(0, 10);
+        let mut other = QuantileSketch::default();
+        other.add(100, 10);
+        sketch.merge(&other);
+
+        assert_close(sketch.percentile(0.0), -100);
+        assert_eq!(sketch.percentile(50.0), Some(0));
+        assert_close(sketch.percentile(100.0), 100);
+    }
+
+    #[test]
+    fn test_bucketed_counts() {
+        let mut counts = BucketedCounts::new(&BucketConfig {
+            width: 10,
+            min: 0,
+            max: 100,
+        });
+        assert_eq!(counts.percentile(50.0), None);
+        for value in 0..100 {
+            counts.add(value, 1);
+        }
+        assert_eq!(counts.percentile(0.0), Some(0));
+        assert_eq!(counts.percentile(50.0), Some(50));
+        let buckets = counts.buckets();
+        assert_eq!(buckets.len(), 12);
+        assert_eq!(buckets[0], (Some(0), 0));
+        assert_eq!(buckets[1], (Some(10), 10));
+        assert_eq!(buckets[10], (Some(100), 10));
+        assert_eq!(buckets[11], (None, 0));
+
+        let mut other = counts.clone();
+        other.add(-5, 100);
+        other.add(500, 300);
+        counts.merge(&other);
+        assert_eq!(counts.percentile(10.0), Some(0));
+        assert_eq!(counts.percentile(99.0), Some(100));
+    }
+}
@@ -13,42 +13,213 @@
 //! statically checked.
 
 use fbinit::FacebookInit;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::{Entry, HashMap};
 use std::thread::LocalKey;
+use std::time::{Duration, Instant};
 
 use crate::stat_types::{
     BoxCounter, BoxHistogram, BoxSingletonCounter, BoxTimeseries, Counter, Histogram,
     SingletonCounter, Timeseries,
 };
 
+/// Limits on the number of keys of a [DynamicStat], for stats whose keys are formatted from
+/// values with a high cardinality. The keys are held per thread, so the limits apply to each
+/// thread separately. The stats of the evicted keys are unregistered, see [Counter::unregister].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DynamicStatLimits {
+    max_keys: Option<usize>,
+    ttl: Option<Duration>,
+}
+
+impl DynamicStatLimits {
+    /// Limit the number of keys to `max_keys`: using a new key when there are that many already
+    /// evicts the key that was used the least recently.
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = Some(max_keys);
+        self
+    }
+
+    /// Evict the keys that haven't been used for `ttl`.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn is_limited(&self) -> bool {
+        self.max_keys.is_some() || self.ttl.is_some()
+    }
+}
+
+/// The stats that a [DynamicStat] creates for each of its keys.
+pub trait KeyStatType {
+    /// Called when the key of the stat is evicted, see [Counter::unregister].
+    fn unregister(&self);
+}
+
+impl KeyStatType for BoxCounter {
+    fn unregister(&self) {
+        Counter::unregister(self)
+    }
+}
+
+impl KeyStatType for BoxTimeseries {
+    fn unregister(&self) {
+        Timeseries::unregister(self)
+    }
+}
+
+impl KeyStatType for BoxHistogram {
+    fn unregister(&self) {
+        Histogram::unregister(self)
+    }
+}
+
+impl KeyStatType for BoxSingletonCounter {
+    /// Singleton counters are shared by all threads rather than held per thread, so they stay
+    /// registered.
+    fn unregister(&self) {}
+}
+
+struct KeyStat<TStatType> {
+    stat: TStatType,
+    /// When the key was last used, only tracked if the stat has limits.
+    last_used: Option<Instant>,
+}
+
 /// The struct to hold key and stat generators that are later being used in runtime to create new
 /// stats that are being held in a map to avoid reconstruction of the same counter.
 pub struct DynamicStat<T, TStatType> {
-    map: RefCell<HashMap<String, TStatType>>,
+    map: RefCell<HashMap<String, KeyStat<TStatType>>>,
     key_generator: fn(&T) -> String,
-    stat_generator: fn(&str) -> TStatType,
+    stat_generator: StatGenerator<T, TStatType>,
+    limits: DynamicStatLimits,
+    eviction_counter_generator: Option<fn() -> BoxCounter>,
+    eviction_counter: RefCell<Option<BoxCounter>>,
+    /// When the keys should next be checked for expiry, if the stat has a ttl.
+    next_expiry: Cell<Instant>,
 }
 
-impl<T, TStatType> DynamicStat<T, TStatType> {
+/// How the stats of a [DynamicStat] are created for a new key.
+enum StatGenerator<T, TStatType> {
+    /// From the key.
+    Key(fn(&str) -> TStatType),
+    /// From the args the key was generated from.
+    Args(fn(&T) -> TStatType),
+}
+
+impl<T, TStatType: KeyStatType> DynamicStat<T, TStatType> {
     pub fn new(key_generator: fn(&T) -> String, stat_generator: fn(&str) -> TStatType) -> Self {
+        Self::with_stat_generator(key_generator, StatGenerator::Key(stat_generator))
+    }
+
+    /// Like [DynamicStat::new], but the stats are created from the args rather than from the key
+    /// generated from them, e.g. for stats whose args are the values of their labels.
+    pub fn from_args(key_generator: fn(&T) -> String, stat_generator: fn(&T) -> TStatType) -> Self {
+        Self::with_stat_generator(key_generator, StatGenerator::Args(stat_generator))
+    }
+
+    fn with_stat_generator(
+        key_generator: fn(&T) -> String,
+        stat_generator: StatGenerator<T, TStatType>,
+    ) -> Self {
         DynamicStat {
             map: RefCell::new(HashMap::new()),
             key_generator,
             stat_generator,
+            limits: DynamicStatLimits::default(),
+            eviction_counter_generator: None,
+            eviction_counter: RefCell::new(None),
+            next_expiry: Cell::new(Instant::now()),
         }
     }
 
+    /// Apply `limits` to the keys of this stat. The number of keys that are evicted is counted by
+    /// the counter returned by `eviction_counter_generator`, which is only called once keys are
+    /// first evicted.
+    pub fn with_limits(
+        mut self,
+        limits: DynamicStatLimits,
+        eviction_counter_generator: fn() -> BoxCounter,
+    ) -> Self {
+        self.limits = limits;
+        self.eviction_counter_generator = Some(eviction_counter_generator);
+        self
+    }
+
     fn get_or_default<F, V>(&self, args: T, cb: F) -> V
     where
         F: FnOnce(&TStatType) -> V,
     {
-        // The HashMap::entry requires to pass the key by value, so we can't create the string
-        // once and pass it to entry and later to the closure. That is why we are creating the
-        // key twice if there is no entry for it already.
         let mut map = self.map.borrow_mut();
-        let entry = map.entry((self.key_generator)(&args));
-        cb(entry.or_insert_with(|| (self.stat_generator)(&(self.key_generator)(&args))))
+        let key = (self.key_generator)(&args);
+        let now = self.limits.is_limited().then(Instant::now);
+        if let Some(now) = now {
+            self.evict(&mut map, &key, now);
+        }
+        let key_stat = match map.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let stat = match self.stat_generator {
+                    StatGenerator::Key(stat_generator) => stat_generator(entry.key()),
+                    StatGenerator::Args(stat_generator) => stat_generator(&args),
+                };
+                entry.insert(KeyStat {
+                    stat,
+                    last_used: None,
+                })
+            }
+        };
+        key_stat.last_used = now;
+        cb(&key_stat.stat)
+    }
+
+    /// Evict the keys that have expired, and the least recently used key if using `key` would
+    /// exceed the maximum number of keys.
+    fn evict(&self, map: &mut HashMap<String, KeyStat<TStatType>>, key: &str, now: Instant) {
+        let len = map.len();
+        if let Some(ttl) = self.limits.ttl {
+            if self.next_expiry.get() <= now {
+                map.retain(|_, key_stat| {
+                    let keep = key_stat
+                        .last_used
+                        .is_some_and(|last_used| now.duration_since(last_used) < ttl);
+                    if !keep {
+                        key_stat.stat.unregister();
+                    }
+                    keep
+                });
+                let oldest = map
+                    .values()
+                    .filter_map(|key_stat| key_stat.last_used)
+                    .min()
+                    .unwrap_or(now);
+                // Expiring the keys goes through all of them, so it isn't done more often than
+                // every tenth of the ttl.
+                self.next_expiry.set((oldest + ttl).max(now + ttl / 10));
+            }
+        }
+        if let Some(max_keys) = self.limits.max_keys {
+            while map.len() >= max_keys.max(1) && !map.contains_key(key) {
+                let oldest = map
+                    .iter()
+                    .min_by_key(|(_, key_stat)| key_stat.last_used)
+                    .map(|(key, _)| key.clone());
+                match oldest.and_then(|oldest| map.remove(&oldest)) {
+                    Some(key_stat) => key_stat.stat.unregister(),
+                    None => break,
+                }
+            }
+        }
+        let evicted = len - map.len();
+        if evicted > 0 {
+            if let Some(generator) = self.eviction_counter_generator {
+                self.eviction_counter
+                    .borrow_mut()
+                    .get_or_insert_with(generator)
+                    .increment_value(evicted as i64);
+            }
+        }
     }
 }
 
@@ -7,6 +7,8 @@
  * of this source tree.
  */
 
+use std::sync::Arc;
+
 use auto_impl::auto_impl;
 use fbinit::FacebookInit;
 
@@ -14,6 +16,10 @@ pub type BoxSingletonCounter = Box<dyn SingletonCounter + Send + Sync>;
 pub type BoxCounter = Box<dyn Counter + Send + Sync>;
 pub type BoxTimeseries = Box<dyn Timeseries + Send + Sync>;
 pub type BoxHistogram = Box<dyn Histogram + Send + Sync>;
+pub type BoxGauge = Box<dyn Gauge + Send + Sync>;
+
+/// The function that returns the current value of a gauge.
+pub type GaugeCallback = Arc<dyn Fn() -> i64 + Send + Sync>;
 
 /// SingletonCounter is a non-aggregated, global counter. Use this if you don't want any aggregation,
 /// and just want to expose a value through stats.
@@ -29,12 +35,23 @@ pub trait SingletonCounter {
     fn get_value(&self, fb: FacebookInit) -> Option<i64>;
 }
 
+/// Gauge is a stat whose value is returned by a callback whenever the stats are collected, rather
+/// than being pushed as it changes, e.g. the length of a queue. It is registered for as long as
+/// this isn't dropped.
+pub trait Gauge {}
+
 /// Counter is the simplest type of aggregated stat, it behaves as a single number that can be
 /// incremented.
 #[auto_impl(Box)]
 pub trait Counter {
     /// Increments the counter by the given amount.
     fn increment_value(&self, value: i64);
+
+    /// Called when this stat stops being used, e.g. when the key of the dynamic stat it was
+    /// created for is evicted, so that stats services that keep every stat they create can stop
+    /// exporting it. The stat may still be used by other threads, which are using their own handle
+    /// to it. The default implementation does nothing.
+    fn unregister(&self) {}
 }
 
 /// Timeseries is a type of stat that can aggregate data send to it into
@@ -50,6 +67,9 @@ pub trait Timeseries {
     /// Please notice that difference in the value semantic compared to
     /// `Histogram::add_repeated_value`.
     fn add_value_aggregated(&self, value: i64, nsamples: u32);
+
+    /// See [Counter::unregister].
+    fn unregister(&self) {}
 }
 
 /// Histogram is a type of stat that can aggregate data send to it into
@@ -67,6 +87,9 @@ pub trait Histogram {
     /// Please notice that difference in the value semantic compared to
     /// `Timeseries::add_value_aggregated`.
     fn add_repeated_value(&self, value: i64, nsamples: u32);
+
+    /// See [Counter::unregister].
+    fn unregister(&self) {}
 }
 
 mod localkey_impls {
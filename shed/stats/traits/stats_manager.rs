@@ -11,26 +11,58 @@ use std::time::Duration;
 
 use auto_impl::auto_impl;
 
-use crate::stat_types::{BoxCounter, BoxHistogram, BoxTimeseries};
+use crate::stat_types::{BoxCounter, BoxGauge, BoxHistogram, BoxTimeseries, Gauge, GaugeCallback};
 
 pub trait StatsManagerFactory {
     fn create(&self) -> BoxStatsManager;
+
+    /// Register a gauge named `name`, whose value is returned by `callback` whenever the stats
+    /// are collected, until the returned [BoxGauge] is dropped. Unlike the other stats, gauges
+    /// aren't aggregated per thread, so they are created by the factory.
+    /// The default implementation is for stats services that don't support gauges, and ignores
+    /// them.
+    fn create_gauge(&self, name: &str, callback: GaugeCallback) -> BoxGauge {
+        let _ = (name, callback);
+        Box::new(IgnoredGauge)
+    }
 }
 
+struct IgnoredGauge;
+
+impl Gauge for IgnoredGauge {}
+
 pub type BoxStatsManager = Box<dyn StatsManager + Send + Sync>;
 
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub enum AggregationType {
     Sum,
     Count,
     Average,
     Rate,
     Percent,
+    /// The given percentile (from 0 to 100) of the values. Timeseries compute it from a
+    /// streaming sketch of the values of each interval, rather than from fixed buckets like
+    /// histograms do.
+    Percentile(u8),
 }
 
+/// The median, as a timeseries aggregation.
+pub const P50: AggregationType = AggregationType::Percentile(50);
+/// The 90th percentile, as a timeseries aggregation.
+pub const P90: AggregationType = AggregationType::Percentile(90);
+/// The 95th percentile, as a timeseries aggregation.
+pub const P95: AggregationType = AggregationType::Percentile(95);
+/// The 99th percentile, as a timeseries aggregation.
+pub const P99: AggregationType = AggregationType::Percentile(99);
+
+/// The buckets of a histogram: values from `min` to `max` are put in buckets that are `width`
+/// wide, and values outside that range are put in an underflow or overflow bucket.
 pub struct BucketConfig {
+    /// The width of each bucket.
     pub width: u32,
+    /// The smallest value of the first bucket.
     pub min: u32,
+    /// The largest value of the last bucket.
     pub max: u32,
 }
 
@@ -78,4 +110,54 @@ pub trait StatsManager {
         conf: BucketConfig,
         percentiles: &[u8],
     ) -> BoxHistogram;
+
+    /// Like [StatsManager::create_histogram], but with the `intervals` at which the data should
+    /// be aggregated, like for timeseries.
+    /// The default implementation is for stats services that don't support configuring the
+    /// intervals of histograms, and ignores them.
+    fn create_histogram_with_intervals(
+        &self,
+        name: &str,
+        aggregation_types: &[AggregationType],
+        conf: BucketConfig,
+        percentiles: &[u8],
+        intervals: &[Duration],
+    ) -> BoxHistogram {
+        let _ = intervals;
+        self.create_histogram(name, aggregation_types, conf, percentiles)
+    }
+
+    /// Create a new instance of [BoxCounter] with a set of `labels`, each being the name of a
+    /// label and its value, and bind it to self for aggregation purposes.
+    /// The default implementation is for stats services that don't support labels, and creates
+    /// a counter whose name is followed by the values of the labels, e.g. "{name}.{value}".
+    fn create_labeled_counter(&self, name: &str, labels: &[(&str, &str)]) -> BoxCounter {
+        self.create_counter(&labeled_name(name, labels))
+    }
+
+    /// Create a new instance of [BoxTimeseries] with a set of `labels`, each being the name of
+    /// a label and its value, and bind it to self for aggregation purposes.
+    /// The default implementation is for stats services that don't support labels, and creates
+    /// a timeseries whose name is followed by the values of the labels, e.g. "{name}.{value}".
+    fn create_labeled_timeseries(
+        &self,
+        name: &str,
+        labels: &[(&str, &str)],
+        aggregation_types: &[AggregationType],
+        intervals: &[Duration],
+    ) -> BoxTimeseries {
+        self.create_timeseries(&labeled_name(name, labels), aggregation_types, intervals)
+    }
+}
+
+/// The name of a stat followed by the values of its labels, as used by the stats services that
+/// don't support labels. This is the same name as the stat would have if it was defined with a
+/// dynamic key, e.g. "{name}.{}.{}".
+pub fn labeled_name(name: &str, labels: &[(&str, &str)]) -> String {
+    let mut labeled_name = name.to_owned();
+    for (_, value) in labels {
+        labeled_name.push('.');
+        labeled_name.push_str(value);
+    }
+    labeled_name
 }
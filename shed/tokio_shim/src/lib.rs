@@ -152,8 +152,15 @@ pub mod time {
         }
     }
 
+    /// The longest sleep with Tokio 0.2, whose timers fail if they are more than about two years
+    /// ahead of the time of its timer wheel, which may lag behind.
+    const TOKIO_02_MAX_SLEEP: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
     pub fn sleep(duration: Duration) -> Sleep {
         if tokio_02::runtime::Handle::try_current().is_ok() {
+            // Unlike Tokio 1.x, Tokio 0.2 panics if the deadline is too far away, so longer
+            // sleeps are cut short.
+            let duration = duration.min(TOKIO_02_MAX_SLEEP);
             return Sleep::Tokio02(tokio_02::time::delay_for(duration));
         }
 
@@ -182,6 +189,20 @@ pub mod time {
         panic!("A Tokio 0.2 or 1.x runtime is required, but neither was running");
     }
 
+    /// The current time according to the clock of the running Tokio runtime, which is the one
+    /// [sleep] and [timeout] use and may be paused in tests, or of the system if there isn't one.
+    pub fn now() -> Instant {
+        if tokio_02::runtime::Handle::try_current().is_ok() {
+            return tokio_02::time::Instant::now().into_std();
+        }
+
+        if tokio_1x::runtime::Handle::try_current().is_ok() {
+            return tokio_1x::time::Instant::now().into_std();
+        }
+
+        Instant::now()
+    }
+
     #[derive(Debug, Error)]
     #[error("deadline has elapsed")]
     pub struct Elapsed;
@@ -308,7 +329,12 @@ mod test {
         task::spawn_blocking(|| ()).await.unwrap();
 
         time::sleep(Duration::from_millis(1)).await;
-        time::sleep_until(Instant::now() + Duration::from_millis(1)).await;
+        time::sleep_until(time::now() + Duration::from_millis(1)).await;
+        assert!(
+            time::timeout(Duration::from_millis(1), time::sleep(Duration::MAX))
+                .await
+                .is_err()
+        );
 
         time::interval_stream(Duration::from_millis(1)).next().await;
 